@@ -9,11 +9,10 @@ fn normal_distribution_benchmark(c: &mut Criterion) {
     let gpu_compute = GpuCompute::new();
     let mut pipeline = gpu_compute.gen_pipeline::<[f32; 1000], u32, [f32; 1000], 1>(
         None,
-        [StageDesc {
-            name: Some("norm"),
-            shader: include_str!("../examples/normal_distribution.wgsl"),
-            entrypoint: "main",
-        }],
+        [
+            StageDesc::new(include_str!("../examples/normal_distribution.wgsl"), "main")
+                .with_name("norm"),
+        ],
     );
     const N: u32 = 1000;
     const WORKGROUP_SIZE: u32 = 10;
@@ -0,0 +1,42 @@
+use sgpu_compute::kernels::gray_scott::{self, GrayScottOutput, GrayScottParams};
+use sgpu_compute::prelude::*;
+use sgpu_compute::trace::Trace;
+use std::{io::Write, sync::Arc};
+
+const WIDTH: u32 = 128;
+const HEIGHT: u32 = 128;
+const N: usize = (WIDTH * HEIGHT) as usize;
+/// Eight steps per run and the output stage.
+const STAGES: usize = 9;
+
+fn main() {
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[[f32; 2]; N], GrayScottParams, [[u8; 4]; N], STAGES>(
+        gray_scott::scratchpad_size(WIDTH, HEIGHT),
+        gray_scott::stages(GrayScottOutput::Rgba),
+    );
+    println!("{}", pipeline.describe());
+
+    let trace = Arc::new(Trace::new());
+    pipeline.set_trace(Arc::clone(&trace));
+
+    let initial: [[f32; 2]; N] = gray_scott::seed_square(WIDTH, HEIGHT, 10)
+        .try_into()
+        .unwrap();
+    let params = GrayScottParams::new(WIDTH, HEIGHT);
+    pipeline.write_uniform(&params.with_reset(true));
+    let workgroups = gray_scott::workgroups(WIDTH, HEIGHT);
+    let mut image = pipeline.run(&initial, workgroups, |image| *image);
+    pipeline.write_uniform(&params);
+    for _ in 0..500 {
+        image = pipeline.run(&initial, workgroups, |image| *image);
+    }
+
+    let mut file = std::io::BufWriter::new(std::fs::File::create("gray_scott.ppm").unwrap());
+    write!(file, "P6\n{} {}\n255\n", WIDTH, HEIGHT).unwrap();
+    for [r, g, b, _] in image {
+        file.write_all(&[r, g, b]).unwrap();
+    }
+    trace.write_chrome_trace("gray_scott_trace.json").unwrap();
+    println!("Wrote gray_scott.ppm and gray_scott_trace.json");
+}
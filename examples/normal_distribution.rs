@@ -4,11 +4,7 @@ fn main() {
     let gpu = GpuCompute::new();
     let mut pipeline = gpu.gen_pipeline(
         None,
-        [StageDesc {
-            name: Some("norm"),
-            shader: include_str!("normal_distribution.wgsl"),
-            entrypoint: "main",
-        }],
+        [StageDesc::new(include_str!("normal_distribution.wgsl"), "main").with_name("norm")],
     );
     let input: [f32; 100] = std::array::from_fn(|i| i as f32 / 100.0);
     pipeline.write_uniform(&32768);
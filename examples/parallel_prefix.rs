@@ -1,5 +1,4 @@
-use rand::Rng;
-use sgpu_compute::prelude::*;
+use sgpu_compute::{prelude::*, testgen};
 
 #[derive(Debug, Copy, Clone, bytemuck::Zeroable, bytemuck::Pod)]
 #[repr(C)]
@@ -7,12 +6,6 @@ struct Uniform {
     width: u32,
 }
 
-fn gen<const N: usize>() -> [f32; N] {
-    let mut out = [0.0; N];
-    rand::thread_rng().fill(&mut out[..]);
-    out
-}
-
 fn parallel_prefix<const N: usize>(input: &[f32; N]) -> [f32; N] {
     let mut out = [0.0; N];
     let mut tot = 0.0;
@@ -20,7 +13,7 @@ fn parallel_prefix<const N: usize>(input: &[f32; N]) -> [f32; N] {
         tot += input[i];
         out[i] = tot;
     }
-    return out;
+    out
 }
 
 fn main() {
@@ -30,27 +23,15 @@ fn main() {
     const N_WG: u32 = (N_WORKER - 1) / 16 + 1;
     const N_PADDED: usize = (N_WG * 16 * PER_WORKER) as _;
 
-    let input: [f32; N] = gen();
+    let input: [f32; N] = testgen::random_f32(0, 0.0..1.0);
 
     let gpu = GpuCompute::new();
     let mut pipeline = gpu.gen_pipeline(
-        NonZeroUsize::new(std::mem::size_of::<f32>() * N_PADDED as usize / PER_WORKER as usize),
+        NonZeroUsize::new(std::mem::size_of::<f32>() * N_PADDED / PER_WORKER as usize),
         [
-            StageDesc {
-                name: Some("first_pass"),
-                shader: include_str!("parallel_prefix.wgsl"),
-                entrypoint: "pass1",
-            },
-            StageDesc {
-                name: Some("second_pass"),
-                shader: include_str!("parallel_prefix.wgsl"),
-                entrypoint: "pass2",
-            },
-            StageDesc {
-                name: Some("last_pass"),
-                shader: include_str!("parallel_prefix.wgsl"),
-                entrypoint: "pass3",
-            },
+            StageDesc::new(include_str!("parallel_prefix.wgsl"), "pass1").with_name("first_pass"),
+            StageDesc::new(include_str!("parallel_prefix.wgsl"), "pass2").with_name("second_pass"),
+            StageDesc::new(include_str!("parallel_prefix.wgsl"), "pass3").with_name("last_pass"),
         ],
     );
     pipeline.write_uniform(&Uniform { width: PER_WORKER });
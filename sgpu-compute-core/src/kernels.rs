@@ -0,0 +1,180 @@
+//! WGSL sources of the kernels of `sgpu_compute::kernels`.
+//!
+//! The sources are the same strings as the `SHADER` constants of the kernel modules, so a CPU reference implementation or a code generator can use the entry points without depending on wgpu.
+
+/// WGSL sources of `sgpu_compute::kernels::bfs`.
+pub mod bfs {
+    /// WGSL source containing the `init`, `expand` and `advance` entry points.
+    pub const SHADER: &str = include_str!("kernels/bfs.wgsl");
+}
+
+/// WGSL sources of `sgpu_compute::kernels::bvh`.
+pub mod bvh {
+    /// WGSL source of the `morton` entry point.
+    pub const MORTON_SHADER: &str = include_str!("kernels/morton.wgsl");
+
+    /// WGSL source of the `lbvh` entry point.
+    pub const LBVH_SHADER: &str = include_str!("kernels/lbvh.wgsl");
+}
+
+/// WGSL sources of `sgpu_compute::kernels::ccl`.
+pub mod ccl {
+    /// WGSL source containing the `init` and `propagate` entry points.
+    pub const SHADER: &str = include_str!("kernels/ccl.wgsl");
+}
+
+/// WGSL sources of `sgpu_compute::kernels::dedup`.
+pub mod dedup {
+    /// WGSL source containing the radix sort entry points and the `dedup_flag`, `dedup_scan`, `dedup_compact` and `dedup_count` entry points.
+    pub const SHADER: &str = concat!(
+        include_str!("kernels/radix.wgsl"),
+        include_str!("kernels/dedup.wgsl")
+    );
+}
+
+/// WGSL sources of `sgpu_compute::kernels::dsp`.
+pub mod dsp {
+    /// WGSL source containing the `frame`, `forward_fft`, `inverse_fft`, `write_spectrum` and `overlap_add` entry points.
+    pub const SHADER: &str = include_str!("kernels/dsp.wgsl");
+}
+
+/// WGSL sources of `sgpu_compute::kernels::error_map`.
+pub mod error_map {
+    /// WGSL source containing the `error_map` and `argmax` entry points.
+    pub const SHADER: &str = include_str!("kernels/error_map.wgsl");
+}
+
+/// WGSL sources of `sgpu_compute::kernels::gather`.
+pub mod gather {
+    /// WGSL source containing the `clear`, `gather` and `scatter` entry points.
+    pub const SHADER: &str = include_str!("kernels/gather.wgsl");
+}
+
+/// WGSL sources of `sgpu_compute::kernels::gray_scott`.
+pub mod gray_scott {
+    /// WGSL source containing the `first_step`, `step_ab`, `step_ba`, `write_concentration` and `write_rgba` entry points.
+    pub const SHADER: &str = include_str!("kernels/gray_scott.wgsl");
+}
+
+/// WGSL sources of `sgpu_compute::kernels::hash`.
+pub mod hash {
+    /// WGSL source containing the `murmur3_32`, `xxh32` and `xxh64` entry points.
+    pub const SHADER: &str = include_str!("kernels/hash.wgsl");
+}
+
+/// WGSL sources of `sgpu_compute::kernels::hash_map`.
+pub mod hash_map {
+    /// WGSL source containing the `clear`, `insert`, `insert_retry`, `settle`, `finish_insert` and `lookup` entry points.
+    pub const SHADER: &str = include_str!("kernels/hash_map.wgsl");
+}
+
+/// WGSL sources of `sgpu_compute::kernels::integral`.
+pub mod integral {
+    /// WGSL source containing the `scan_rows` and `scan_columns` entry points.
+    pub const SHADER: &str = include_str!("kernels/integral.wgsl");
+}
+
+/// WGSL sources of `sgpu_compute::kernels::intersect`.
+pub mod intersect {
+    /// WGSL source containing the `ray_triangle` and `ray_aabb` entry points.
+    pub const SHADER: &str = include_str!("kernels/intersect.wgsl");
+}
+
+/// WGSL sources of `sgpu_compute::kernels::map_reduce`.
+pub mod map_reduce {
+    /// WGSL source containing the `map_reduce` and `finish` entry points, without the declarations generated by `shader`.
+    pub const SHADER: &str = include_str!("kernels/map_reduce.wgsl");
+}
+
+/// WGSL sources of `sgpu_compute::kernels::mask`.
+pub mod mask {
+    /// WGSL source of the `bitpack` entry point.
+    pub const BITPACK_SHADER: &str = include_str!("kernels/bitpack.wgsl");
+
+    /// WGSL source containing the `count_runs`, `scan_blocks` and `emit` entry points.
+    pub const RLE_SHADER: &str = include_str!("kernels/rle.wgsl");
+}
+
+/// WGSL sources of `sgpu_compute::kernels::monte_carlo`.
+pub mod monte_carlo {
+    /// WGSL source containing the `sample` and `accumulate` entry points, without the `seed` module and the integrand, see `shader`.
+    pub const SHADER: &str = include_str!("kernels/monte_carlo.wgsl");
+}
+
+/// WGSL sources of `sgpu_compute::kernels::noise`.
+pub mod noise {
+    /// WGSL source containing the `noise_2d`, `noise_3d`, `simplex_2d` and `simplex_3d` entry points.
+    pub const SHADER: &str = include_str!("kernels/noise.wgsl");
+}
+
+/// WGSL sources of `sgpu_compute::kernels::optimize`.
+pub mod optimize {
+    /// WGSL source of the `evaluate` entry point, without the number of dimensions and the objective, see `sgpu_compute::kernels::optimize::shader`.
+    pub const SHADER: &str = include_str!("kernels/optimize.wgsl");
+}
+
+/// WGSL sources of `sgpu_compute::kernels::pagerank`.
+pub mod pagerank {
+    /// WGSL source containing the `init`, `dangling`, `spmv` and `finish` entry points.
+    pub const SHADER: &str = include_str!("kernels/pagerank.wgsl");
+}
+
+/// WGSL sources of `sgpu_compute::kernels::particles`.
+pub mod particles {
+    /// WGSL source containing the `integrate`, `clear_grid`, `count`, `scan`, `scatter`, `collide` and `apply` entry points.
+    pub const SHADER: &str = include_str!("kernels/particles.wgsl");
+}
+
+/// WGSL sources of `sgpu_compute::kernels::population`.
+pub mod population {
+    /// WGSL source of the `evaluate` entry point, without the bindings and the fitness, see `sgpu_compute::kernels::population::shader`.
+    pub const SHADER: &str = include_str!("kernels/population.wgsl");
+}
+
+/// WGSL sources of `sgpu_compute::kernels::rolling`.
+pub mod rolling {
+    /// WGSL source containing the `blocks` and `windows` entry points.
+    pub const SHADER: &str = include_str!("kernels/rolling.wgsl");
+}
+
+/// WGSL sources of `sgpu_compute::kernels::search`.
+pub mod search {
+    /// WGSL source containing the `clear` and `search` entry points.
+    pub const SHADER: &str = include_str!("kernels/search.wgsl");
+}
+
+/// WGSL sources of `sgpu_compute::kernels::sort`.
+pub mod sort {
+    /// WGSL source containing the `sort_load`, `sort_histogram`, `sort_scan`, `sort_scatter` and `write_sorted` entry points.
+    pub const SHADER: &str = concat!(
+        include_str!("kernels/radix.wgsl"),
+        include_str!("kernels/sort.wgsl")
+    );
+}
+
+/// WGSL sources of `sgpu_compute::kernels::sparse`.
+pub mod sparse {
+    /// WGSL source containing the `clear` and `compact` entry points.
+    pub const TO_SPARSE_SHADER: &str = include_str!("kernels/dense_to_sparse.wgsl");
+
+    /// WGSL source containing the `fill` and `scatter` entry points.
+    pub const TO_DENSE_SHADER: &str = include_str!("kernels/sparse_to_dense.wgsl");
+}
+
+/// WGSL sources of `sgpu_compute::kernels::stats`.
+pub mod stats {
+    /// WGSL source containing the `partial_moments` and `accumulate` entry points.
+    pub const SHADER: &str = include_str!("kernels/stats.wgsl");
+}
+
+/// WGSL sources of `sgpu_compute::kernels::tonemap`.
+pub mod tonemap {
+    /// WGSL source containing the `clear`, `histogram`, `cdf` and `map` entry points.
+    pub const SHADER: &str = include_str!("kernels/tonemap.wgsl");
+}
+
+/// WGSL sources of `sgpu_compute::kernels::zip_map`.
+pub mod zip_map {
+    /// WGSL source of the `zip_map` entry point, without the declaration generated by `shader`.
+    pub const SHADER: &str = include_str!("kernels/zip_map.wgsl");
+}
@@ -0,0 +1,80 @@
+struct Params {
+    vertices: u32,
+    edges: u32,
+    source: u32,
+    reset: u32,
+}
+
+struct Distances {
+    frontier: atomic<u32>,
+    level: atomic<u32>,
+    distances: array<atomic<u32>>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// Size of the two frontier queues and the current level, then the two queues.
+@group(0) @binding(1) var<storage, read_write> scratchpad: array<atomic<u32>>;
+// CSR graph: `vertices + 1` row offsets then `edges` column indices.
+@group(0) @binding(2) var<storage, read> in: array<u32>;
+@group(0) @binding(3) var<storage, read_write> out: Distances;
+
+const UNREACHED: u32 = 0xffffffffu;
+const META_WORDS: u32 = 4u;
+
+fn queue_offset(queue: u32) -> u32 {
+    return META_WORDS + queue * params.vertices;
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn init(@builtin(global_invocation_id) id: vec3<u32>) {
+    if params.reset == 0u || id.x >= params.vertices {
+        return;
+    }
+    atomicStore(&out.distances[id.x], select(UNREACHED, 0u, id.x == params.source));
+    if id.x == 0u {
+        atomicStore(&scratchpad[0], 1u);
+        atomicStore(&scratchpad[1], 0u);
+        atomicStore(&scratchpad[2], 0u);
+        atomicStore(&scratchpad[queue_offset(0u)], params.source);
+        atomicStore(&out.frontier, 1u);
+        atomicStore(&out.level, 0u);
+    }
+}
+
+// Visits the neighbors of the current frontier and appends the unvisited ones to the next frontier.
+@compute
+@workgroup_size(64, 1, 1)
+fn expand(@builtin(global_invocation_id) id: vec3<u32>) {
+    let level = atomicLoad(&scratchpad[2]);
+    let current = level % 2u;
+    let next = 1u - current;
+    if id.x >= atomicLoad(&scratchpad[current]) {
+        return;
+    }
+    let vertex = atomicLoad(&scratchpad[queue_offset(current) + id.x]);
+    let columns = params.vertices + 1u;
+    for (var e = in[vertex]; e < in[vertex + 1u]; e++) {
+        let neighbor = in[columns + e];
+        if atomicMin(&out.distances[neighbor], level + 1u) == UNREACHED {
+            let slot = atomicAdd(&scratchpad[next], 1u);
+            atomicStore(&scratchpad[queue_offset(next) + slot], neighbor);
+        }
+    }
+}
+
+// Makes the next frontier the current one.
+@compute
+@workgroup_size(1, 1, 1)
+fn advance() {
+    let level = atomicLoad(&scratchpad[2]);
+    let current = level % 2u;
+    let next = 1u - current;
+    if atomicLoad(&scratchpad[current]) == 0u {
+        return;
+    }
+    atomicStore(&scratchpad[current], 0u);
+    atomicStore(&scratchpad[2], level + 1u);
+    atomicStore(&out.frontier, atomicLoad(&scratchpad[next]));
+    atomicStore(&out.level, level + 1u);
+}
@@ -0,0 +1,19 @@
+@group(0) @binding(0) var<storage, read> in: array<u32>;
+@group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+@compute
+@workgroup_size(64, 1, 1)
+fn bitpack(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= arrayLength(&out) {
+        return;
+    }
+    let n = arrayLength(&in);
+    var word = 0u;
+    for (var bit = 0u; bit < 32u; bit++) {
+        let i = 32u * id.x + bit;
+        if i < n && in[i] != 0u {
+            word |= 1u << bit;
+        }
+    }
+    out[id.x] = word;
+}
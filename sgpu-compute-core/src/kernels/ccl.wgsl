@@ -0,0 +1,60 @@
+struct Params {
+    width: u32,
+    height: u32,
+    reset: u32,
+}
+
+struct Labels {
+    changed: atomic<u32>,
+    labels: array<u32>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> in: array<u32>;
+@group(0) @binding(2) var<storage, read_write> out: Labels;
+
+const BACKGROUND: u32 = 0xffffffffu;
+
+@compute
+@workgroup_size(8, 8, 1)
+fn init(@builtin(global_invocation_id) id: vec3<u32>) {
+    if all(id.xy == vec2<u32>(0u)) {
+        atomicStore(&out.changed, 0u);
+    }
+    if params.reset == 0u || id.x >= params.width || id.y >= params.height {
+        return;
+    }
+    let i = id.x + params.width * id.y;
+    out.labels[i] = select(BACKGROUND, i, in[i] != 0u);
+}
+
+/// Replaces each label by the smallest label of its 4-neighbourhood. Labels are updated in place, which only speeds up the convergence since they can only decrease.
+@compute
+@workgroup_size(8, 8, 1)
+fn propagate(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.width || id.y >= params.height {
+        return;
+    }
+    let i = id.x + params.width * id.y;
+    let current = out.labels[i];
+    if current == BACKGROUND {
+        return;
+    }
+    var label = current;
+    if id.x > 0u {
+        label = min(label, out.labels[i - 1u]);
+    }
+    if id.x + 1u < params.width {
+        label = min(label, out.labels[i + 1u]);
+    }
+    if id.y > 0u {
+        label = min(label, out.labels[i - params.width]);
+    }
+    if id.y + 1u < params.height {
+        label = min(label, out.labels[i + params.width]);
+    }
+    if label < current {
+        out.labels[i] = label;
+        atomicStore(&out.changed, 1u);
+    }
+}
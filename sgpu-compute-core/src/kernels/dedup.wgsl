@@ -0,0 +1,135 @@
+
+struct Params {
+    count: u32,
+    key_words: u32,
+    blocks: u32,
+    _pad0: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// The words of the radix sort, then the number of unique keys before each block and the index of the first occurrence of each unique key.
+@group(0) @binding(1) var<storage, read_write> scratchpad: array<u32>;
+@group(0) @binding(2) var<storage, read> in: array<u32>;
+// Number of unique keys and padding, then the entries: the words of the key and its count, padded to `2 * key_words` words.
+@group(0) @binding(3) var<storage, read_write> out: array<u32>;
+
+const HEADER_WORDS: u32 = 2u;
+
+var<workgroup> head_flags: array<u32, RADIX_BLOCK>;
+var<workgroup> partial_sums: array<u32, RADIX_BLOCK>;
+
+fn block_sums_offset() -> u32 {
+    return histogram_offset() + RADIX_DIGITS * params.blocks;
+}
+
+fn first_index_offset() -> u32 {
+    return block_sums_offset() + params.blocks;
+}
+
+fn entry_words() -> u32 {
+    return 2u * params.key_words;
+}
+
+fn max_entries() -> u32 {
+    return (arrayLength(&out) - HEADER_WORDS) / entry_words();
+}
+
+// Whether the sorted key `i` is the first occurrence of its value.
+fn is_head(i: u32) -> bool {
+    if i >= params.count {
+        return false;
+    }
+    if i == 0u {
+        return true;
+    }
+    for (var w = 0u; w < params.key_words; w++) {
+        if sorted_key_word(i, w) != sorted_key_word(i - 1u, w) {
+            return true;
+        }
+    }
+    return false;
+}
+
+@compute
+@workgroup_size(RADIX_BLOCK, 1, 1)
+fn dedup_flag(@builtin(local_invocation_index) local: u32, @builtin(workgroup_id) block: vec3<u32>) {
+    head_flags[local] = u32(is_head(block.x * RADIX_BLOCK + local));
+    workgroupBarrier();
+    if local == 0u {
+        var sum = 0u;
+        for (var l = 0u; l < RADIX_BLOCK; l++) {
+            sum += head_flags[l];
+        }
+        scratchpad[block_sums_offset() + block.x] = sum;
+    }
+}
+
+// Exclusive scan of the block sums by a single workgroup, each invocation scanning a contiguous range of blocks.
+@compute
+@workgroup_size(RADIX_BLOCK, 1, 1)
+fn dedup_scan(@builtin(local_invocation_index) local: u32) {
+    let per_invocation = (params.blocks + RADIX_BLOCK - 1u) / RADIX_BLOCK;
+    let begin = min(local * per_invocation, params.blocks);
+    let end = min(begin + per_invocation, params.blocks);
+    var sum = 0u;
+    for (var b = begin; b < end; b++) {
+        sum += scratchpad[block_sums_offset() + b];
+    }
+    partial_sums[local] = sum;
+    workgroupBarrier();
+    var offset = 0u;
+    for (var l = 0u; l < local; l++) {
+        offset += partial_sums[l];
+    }
+    for (var b = begin; b < end; b++) {
+        let count = scratchpad[block_sums_offset() + b];
+        scratchpad[block_sums_offset() + b] = offset;
+        offset += count;
+    }
+    if local == RADIX_BLOCK - 1u {
+        out[0] = offset;
+        out[1] = 0u;
+    }
+}
+
+@compute
+@workgroup_size(RADIX_BLOCK, 1, 1)
+fn dedup_compact(@builtin(local_invocation_index) local: u32, @builtin(workgroup_id) block: vec3<u32>) {
+    let i = block.x * RADIX_BLOCK + local;
+    let head = is_head(i);
+    head_flags[local] = u32(head);
+    workgroupBarrier();
+    if !head {
+        return;
+    }
+    var index = scratchpad[block_sums_offset() + block.x];
+    for (var l = 0u; l < local; l++) {
+        index += head_flags[l];
+    }
+    scratchpad[first_index_offset() + index] = i;
+    if index < max_entries() {
+        let entry = HEADER_WORDS + index * entry_words();
+        for (var w = 0u; w < params.key_words; w++) {
+            out[entry + w] = sorted_key_word(i, w);
+        }
+    }
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn dedup_count(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    let unique = out[0];
+    if index >= unique || index >= max_entries() {
+        return;
+    }
+    var end = params.count;
+    if index + 1u < unique {
+        end = scratchpad[first_index_offset() + index + 1u];
+    }
+    let entry = HEADER_WORDS + index * entry_words();
+    out[entry + params.key_words] = end - scratchpad[first_index_offset() + index];
+    if params.key_words == 2u {
+        out[entry + 3u] = 0u;
+    }
+}
@@ -0,0 +1,39 @@
+struct Params {
+    len: u32,
+    fill: f32,
+    _pad0: u32,
+    _pad1: u32,
+}
+
+struct Entry {
+    index: u32,
+    value: f32,
+}
+
+struct Sparse {
+    count: atomic<u32>,
+    entries: array<Entry>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> in: array<f32>;
+@group(0) @binding(2) var<storage, read_write> out: Sparse;
+
+@compute
+@workgroup_size(1, 1, 1)
+fn clear() {
+    atomicStore(&out.count, 0u);
+}
+
+// Appends the elements which are not the fill value, counting them even if they don't fit.
+@compute
+@workgroup_size(64, 1, 1)
+fn compact(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.len || in[id.x] == params.fill {
+        return;
+    }
+    let slot = atomicAdd(&out.count, 1u);
+    if slot < arrayLength(&out.entries) {
+        out.entries[slot] = Entry(id.x, in[id.x]);
+    }
+}
@@ -0,0 +1,123 @@
+struct Params {
+    frame_size: u32,
+    hop_size: u32,
+    frames: u32,
+    window: u32,
+}
+
+const MAX_FRAME_SIZE: u32 = 1024u;
+const FFT_WORKGROUP_SIZE: u32 = 256u;
+const TAU: f32 = 6.283185307179586;
+
+@group(0) @binding(0) var<uniform> params: Params;
+// Complex frames, the frame `f` starting at `f * frame_size`.
+@group(0) @binding(1) var<storage, read_write> scratchpad: array<vec2<f32>>;
+// Signal of `(frames - 1) * hop_size + frame_size` samples.
+@group(0) @binding(2) var<storage, read> in: array<f32>;
+// Either the interleaved spectra or the overlap-added signal.
+@group(0) @binding(3) var<storage, read_write> out: array<f32>;
+
+var<workgroup> fft_buffer: array<vec2<f32>, MAX_FRAME_SIZE>;
+
+// Periodic windows, so that overlapping frames sum to a constant.
+fn window(i: u32) -> f32 {
+    let x = TAU * f32(i) / f32(params.frame_size);
+    switch params.window {
+        case 1u: {
+            return 0.5 - 0.5 * cos(x);
+        }
+        case 2u: {
+            return 0.54 - 0.46 * cos(x);
+        }
+        case 3u: {
+            return 0.42 - 0.5 * cos(x) + 0.08 * cos(2.0 * x);
+        }
+        default: {
+            return 1.0;
+        }
+    }
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn frame(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.frames * params.frame_size {
+        return;
+    }
+    let f = id.x / params.frame_size;
+    let i = id.x % params.frame_size;
+    scratchpad[id.x] = vec2(in[f * params.hop_size + i] * window(i), 0.0);
+}
+
+// In-place radix-2 FFT of the frame of the workgroup, `sign` is -1 for the forward transform and 1 for the inverse one.
+fn fft(f: u32, local: u32, sign: f32) {
+    let n = params.frame_size;
+    let bits = countTrailingZeros(n);
+    let base = f * n;
+    for (var i = local; i < n; i += FFT_WORKGROUP_SIZE) {
+        fft_buffer[reverseBits(i) >> (32u - bits)] = scratchpad[base + i];
+    }
+    workgroupBarrier();
+    for (var half = 1u; half < n; half <<= 1u) {
+        for (var b = local; b < n / 2u; b += FFT_WORKGROUP_SIZE) {
+            let k = b % half;
+            let i = (b / half) * 2u * half + k;
+            let j = i + half;
+            let angle = sign * TAU * f32(k) / f32(2u * half);
+            let twiddle = vec2(cos(angle), sin(angle));
+            let odd = fft_buffer[j];
+            let t = vec2(odd.x * twiddle.x - odd.y * twiddle.y, odd.x * twiddle.y + odd.y * twiddle.x);
+            let even = fft_buffer[i];
+            fft_buffer[i] = even + t;
+            fft_buffer[j] = even - t;
+        }
+        workgroupBarrier();
+    }
+    let scale = select(1.0, 1.0 / f32(n), sign > 0.0);
+    for (var i = local; i < n; i += FFT_WORKGROUP_SIZE) {
+        scratchpad[base + i] = fft_buffer[i] * scale;
+    }
+}
+
+@compute
+@workgroup_size(FFT_WORKGROUP_SIZE, 1, 1)
+fn forward_fft(@builtin(workgroup_id) workgroup: vec3<u32>, @builtin(local_invocation_index) local: u32) {
+    fft(workgroup.x, local, -1.0);
+}
+
+@compute
+@workgroup_size(FFT_WORKGROUP_SIZE, 1, 1)
+fn inverse_fft(@builtin(workgroup_id) workgroup: vec3<u32>, @builtin(local_invocation_index) local: u32) {
+    fft(workgroup.x, local, 1.0);
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn write_spectrum(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.frames * params.frame_size {
+        return;
+    }
+    let value = scratchpad[id.x];
+    out[2u * id.x] = value.x;
+    out[2u * id.x + 1u] = value.y;
+}
+
+// Sums the real part of the frames covering each sample.
+@compute
+@workgroup_size(64, 1, 1)
+fn overlap_add(@builtin(global_invocation_id) id: vec3<u32>) {
+    let t = id.x;
+    if t >= (params.frames - 1u) * params.hop_size + params.frame_size {
+        return;
+    }
+    let last = min(t / params.hop_size, params.frames - 1u);
+    var first = 0u;
+    if t >= params.frame_size {
+        first = (t - params.frame_size) / params.hop_size + 1u;
+    }
+    var sum = 0.0;
+    for (var f = first; f <= last; f++) {
+        sum += scratchpad[f * params.frame_size + t - f * params.hop_size].x;
+    }
+    out[t] = sum;
+}
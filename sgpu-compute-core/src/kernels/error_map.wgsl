@@ -0,0 +1,88 @@
+struct Params {
+    len: u32,
+    absolute: f32,
+    relative: f32,
+    _pad: u32,
+}
+
+struct ElementError {
+    absolute: f32,
+    relative: f32,
+}
+
+// The maxima are the bits of non-negative floats, which are ordered like the floats, and the indices are complemented so that `atomicMax` keeps the first one.
+struct Summary {
+    max_absolute: atomic<u32>,
+    max_absolute_index: atomic<u32>,
+    max_relative: atomic<u32>,
+    max_relative_index: atomic<u32>,
+    over_absolute: atomic<u32>,
+    over_relative: atomic<u32>,
+    mismatches: atomic<u32>,
+    nan_mismatches: atomic<u32>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read_write> reference: array<f32>;
+@group(0) @binding(2) var<storage, read_write> errors: array<ElementError>;
+@group(0) @binding(3) var<storage, read> actual: array<f32>;
+@group(0) @binding(4) var<storage, read_write> summary: Summary;
+
+const WORKGROUP_SIZE: u32 = 256u;
+const INFINITY_BITS: u32 = 0x7f800000u;
+
+fn is_nan(x: f32) -> bool {
+    return (bitcast<u32>(x) & 0x7fffffffu) > INFINITY_BITS;
+}
+
+fn is_infinite(x: f32) -> bool {
+    return (bitcast<u32>(x) & 0x7fffffffu) == INFINITY_BITS;
+}
+
+@compute @workgroup_size(256)
+fn error_map(@builtin(global_invocation_id) id: vec3<u32>, @builtin(num_workgroups) groups: vec3<u32>) {
+    let infinity = bitcast<f32>(INFINITY_BITS);
+    for (var i = id.x; i < params.len; i += groups.x * WORKGROUP_SIZE) {
+        let a = actual[i];
+        let r = reference[i];
+        var absolute = 0.0;
+        var relative = 0.0;
+        if is_nan(a) != is_nan(r) {
+            absolute = infinity;
+            relative = infinity;
+            atomicAdd(&summary.nan_mismatches, 1u);
+        } else if !is_nan(a) && a != r {
+            absolute = abs(a - r);
+            relative = select(absolute / abs(r), infinity, r == 0.0 || is_infinite(r));
+        }
+        errors[i] = ElementError(absolute, relative);
+        atomicMax(&summary.max_absolute, bitcast<u32>(absolute));
+        atomicMax(&summary.max_relative, bitcast<u32>(relative));
+        let over_absolute = absolute > params.absolute;
+        let over_relative = relative > params.relative;
+        if over_absolute {
+            atomicAdd(&summary.over_absolute, 1u);
+        }
+        if over_relative {
+            atomicAdd(&summary.over_relative, 1u);
+        }
+        if over_absolute && over_relative {
+            atomicAdd(&summary.mismatches, 1u);
+        }
+    }
+}
+
+@compute @workgroup_size(256)
+fn argmax(@builtin(global_invocation_id) id: vec3<u32>, @builtin(num_workgroups) groups: vec3<u32>) {
+    let max_absolute = atomicLoad(&summary.max_absolute);
+    let max_relative = atomicLoad(&summary.max_relative);
+    for (var i = id.x; i < params.len; i += groups.x * WORKGROUP_SIZE) {
+        let error = errors[i];
+        if bitcast<u32>(error.absolute) == max_absolute {
+            atomicMax(&summary.max_absolute_index, ~i);
+        }
+        if bitcast<u32>(error.relative) == max_relative {
+            atomicMax(&summary.max_relative_index, ~i);
+        }
+    }
+}
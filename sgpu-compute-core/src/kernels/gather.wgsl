@@ -0,0 +1,77 @@
+struct Params {
+    count: u32,
+    len: u32,
+    element_words: u32,
+    policy: u32,
+}
+
+struct Values {
+    out_of_range: atomic<u32>,
+    first_out_of_range: atomic<u32>,
+    _pad0: u32,
+    _pad1: u32,
+    words: array<u32>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// `count` indices then the words of the elements.
+@group(0) @binding(1) var<storage, read> in: array<u32>;
+@group(0) @binding(2) var<storage, read_write> out: Values;
+
+const POLICY_CLAMP: u32 = 0u;
+const POLICY_FLAG: u32 = 2u;
+
+// Returns the index to use for the position `i`, or `len` if the element is skipped.
+fn checked_index(i: u32) -> u32 {
+    let index = in[i];
+    if index < params.len {
+        return index;
+    }
+    if params.policy == POLICY_CLAMP {
+        return params.len - 1u;
+    }
+    if params.policy == POLICY_FLAG {
+        atomicAdd(&out.out_of_range, 1u);
+        atomicMin(&out.first_out_of_range, i);
+    }
+    return params.len;
+}
+
+@compute
+@workgroup_size(1, 1, 1)
+fn clear() {
+    atomicStore(&out.out_of_range, 0u);
+    atomicStore(&out.first_out_of_range, 0xffffffffu);
+}
+
+// `out[i] = data[indices[i]]`.
+@compute
+@workgroup_size(64, 1, 1)
+fn gather(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.count {
+        return;
+    }
+    let index = checked_index(id.x);
+    if index == params.len {
+        return;
+    }
+    for (var w = 0u; w < params.element_words; w++) {
+        out.words[id.x * params.element_words + w] = in[params.count + index * params.element_words + w];
+    }
+}
+
+// `out[indices[i]] = data[i]`.
+@compute
+@workgroup_size(64, 1, 1)
+fn scatter(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.count {
+        return;
+    }
+    let index = checked_index(id.x);
+    if index == params.len {
+        return;
+    }
+    for (var w = 0u; w < params.element_words; w++) {
+        out.words[index * params.element_words + w] = in[params.count + id.x * params.element_words + w];
+    }
+}
@@ -0,0 +1,99 @@
+struct Params {
+    width: u32,
+    height: u32,
+    reset: u32,
+    feed: f32,
+    kill: f32,
+    diffusion_u: f32,
+    diffusion_v: f32,
+    dt: f32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// Two fields of (u, v) concentrations, the steps ping-pong between them and a run ends in the first one.
+@group(0) @binding(1) var<storage, read_write> scratchpad: array<vec2<f32>>;
+@group(0) @binding(2) var<storage, read> in: array<vec2<f32>>;
+// Either the `v` concentrations as `f32` bits or RGBA8 colors.
+@group(0) @binding(3) var<storage, read_write> out: array<u32>;
+
+fn cell_count() -> u32 {
+    return params.width * params.height;
+}
+
+// Index of the cell at (x + dx, y + dy), wrapping around the edges.
+fn wrap(x: u32, y: u32, dx: i32, dy: i32) -> u32 {
+    let w = i32(params.width);
+    let h = i32(params.height);
+    let nx = (i32(x) + dx + w) % w;
+    let ny = (i32(y) + dy + h) % h;
+    return u32(nx) + params.width * u32(ny);
+}
+
+fn load(from_input: bool, base: u32, i: u32) -> vec2<f32> {
+    if from_input {
+        return in[i];
+    }
+    return scratchpad[base + i];
+}
+
+// One explicit Euler step with the 9-point Laplacian, reading the field at `src` and writing the one at `dst`.
+fn step(id: vec2<u32>, src: u32, dst: u32, from_input: bool) {
+    if id.x >= params.width || id.y >= params.height {
+        return;
+    }
+    var laplacian = -load(from_input, src, wrap(id.x, id.y, 0, 0));
+    for (var dy = -1; dy <= 1; dy++) {
+        for (var dx = -1; dx <= 1; dx++) {
+            if dx == 0 && dy == 0 {
+                continue;
+            }
+            let weight = select(0.2, 0.05, dx != 0 && dy != 0);
+            laplacian += weight * load(from_input, src, wrap(id.x, id.y, dx, dy));
+        }
+    }
+    let c = load(from_input, src, wrap(id.x, id.y, 0, 0));
+    let reaction = c.x * c.y * c.y;
+    let du = params.diffusion_u * laplacian.x - reaction + params.feed * (1.0 - c.x);
+    let dv = params.diffusion_v * laplacian.y + reaction - (params.kill + params.feed) * c.y;
+    scratchpad[dst + id.x + params.width * id.y] = c + params.dt * vec2(du, dv);
+}
+
+@compute
+@workgroup_size(8, 8, 1)
+fn first_step(@builtin(global_invocation_id) id: vec3<u32>) {
+    step(id.xy, 0u, cell_count(), params.reset != 0u);
+}
+
+@compute
+@workgroup_size(8, 8, 1)
+fn step_ab(@builtin(global_invocation_id) id: vec3<u32>) {
+    step(id.xy, 0u, cell_count(), false);
+}
+
+@compute
+@workgroup_size(8, 8, 1)
+fn step_ba(@builtin(global_invocation_id) id: vec3<u32>) {
+    step(id.xy, cell_count(), 0u, false);
+}
+
+@compute
+@workgroup_size(8, 8, 1)
+fn write_concentration(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.width || id.y >= params.height {
+        return;
+    }
+    let i = id.x + params.width * id.y;
+    out[i] = bitcast<u32>(scratchpad[i].y);
+}
+
+@compute
+@workgroup_size(8, 8, 1)
+fn write_rgba(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.width || id.y >= params.height {
+        return;
+    }
+    let i = id.x + params.width * id.y;
+    let c = scratchpad[i];
+    let color = clamp(vec3(c.x - c.y, 1.0 - c.x, 4.0 * c.y), vec3(0.0), vec3(1.0));
+    out[i] = pack4x8unorm(vec4(color, 1.0));
+}
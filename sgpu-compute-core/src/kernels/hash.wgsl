@@ -0,0 +1,199 @@
+struct Params {
+    count: u32,
+    key_words: u32,
+    // Low and high words of the seed, the 32-bit hashes only use the low word.
+    seed_lo: u32,
+    seed_hi: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// Keys of `key_words` words each.
+@group(0) @binding(1) var<storage, read> in: array<u32>;
+// One word per key for the 32-bit hashes, two for the 64-bit ones (low word first).
+@group(0) @binding(2) var<storage, read_write> out: array<u32>;
+
+fn rotl32(x: u32, r: u32) -> u32 {
+    return (x << r) | (x >> (32u - r));
+}
+
+fn key_word(key: u32, i: u32) -> u32 {
+    return in[key * params.key_words + i];
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn murmur3_32(@builtin(global_invocation_id) id: vec3<u32>) {
+    let key = id.x;
+    if key >= params.count {
+        return;
+    }
+    var h = params.seed_lo;
+    for (var i = 0u; i < params.key_words; i++) {
+        var k = key_word(key, i) * 0xcc9e2d51u;
+        k = rotl32(k, 15u) * 0x1b873593u;
+        h = rotl32(h ^ k, 13u) * 5u + 0xe6546b64u;
+    }
+    h ^= 4u * params.key_words;
+    h ^= h >> 16u;
+    h *= 0x85ebca6bu;
+    h ^= h >> 13u;
+    h *= 0xc2b2ae35u;
+    h ^= h >> 16u;
+    out[key] = h;
+}
+
+const XXH32_P1: u32 = 0x9e3779b1u;
+const XXH32_P2: u32 = 0x85ebca77u;
+const XXH32_P3: u32 = 0xc2b2ae3du;
+const XXH32_P4: u32 = 0x27d4eb2fu;
+const XXH32_P5: u32 = 0x165667b1u;
+
+fn xxh32_round(acc: u32, lane: u32) -> u32 {
+    return rotl32(acc + lane * XXH32_P2, 13u) * XXH32_P1;
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn xxh32(@builtin(global_invocation_id) id: vec3<u32>) {
+    let key = id.x;
+    if key >= params.count {
+        return;
+    }
+    let seed = params.seed_lo;
+    var h: u32;
+    var i = 0u;
+    if params.key_words >= 4u {
+        var v = vec4(seed + XXH32_P1 + XXH32_P2, seed + XXH32_P2, seed, seed - XXH32_P1);
+        for (; i + 4u <= params.key_words; i += 4u) {
+            v = vec4(
+                xxh32_round(v.x, key_word(key, i)),
+                xxh32_round(v.y, key_word(key, i + 1u)),
+                xxh32_round(v.z, key_word(key, i + 2u)),
+                xxh32_round(v.w, key_word(key, i + 3u)),
+            );
+        }
+        h = rotl32(v.x, 1u) + rotl32(v.y, 7u) + rotl32(v.z, 12u) + rotl32(v.w, 18u);
+    } else {
+        h = seed + XXH32_P5;
+    }
+    h += 4u * params.key_words;
+    for (; i < params.key_words; i++) {
+        h = rotl32(h + key_word(key, i) * XXH32_P3, 17u) * XXH32_P4;
+    }
+    h ^= h >> 15u;
+    h *= XXH32_P2;
+    h ^= h >> 13u;
+    h *= XXH32_P3;
+    h ^= h >> 16u;
+    out[key] = h;
+}
+
+// 64-bit integers as (low, high) words.
+
+fn add64(a: vec2<u32>, b: vec2<u32>) -> vec2<u32> {
+    let lo = a.x + b.x;
+    return vec2(lo, a.y + b.y + select(0u, 1u, lo < a.x));
+}
+
+fn mul_wide(a: u32, b: u32) -> vec2<u32> {
+    let a0 = a & 0xffffu;
+    let a1 = a >> 16u;
+    let b0 = b & 0xffffu;
+    let b1 = b >> 16u;
+    let p00 = a0 * b0;
+    let p01 = a0 * b1;
+    let p10 = a1 * b0;
+    let p11 = a1 * b1;
+    let mid = (p00 >> 16u) + (p01 & 0xffffu) + (p10 & 0xffffu);
+    return vec2((p00 & 0xffffu) | (mid << 16u), p11 + (p01 >> 16u) + (p10 >> 16u) + (mid >> 16u));
+}
+
+fn mul64(a: vec2<u32>, b: vec2<u32>) -> vec2<u32> {
+    let low = mul_wide(a.x, b.x);
+    return vec2(low.x, low.y + a.x * b.y + a.y * b.x);
+}
+
+fn rotl64(a: vec2<u32>, r: u32) -> vec2<u32> {
+    var x = a;
+    var s = r;
+    if s >= 32u {
+        x = x.yx;
+        s -= 32u;
+    }
+    if s == 0u {
+        return x;
+    }
+    return vec2((x.x << s) | (x.y >> (32u - s)), (x.y << s) | (x.x >> (32u - s)));
+}
+
+// Xors `a` with itself shifted right by `r`, with `32 <= r < 64` or `0 < r < 32`.
+fn xorshift64(a: vec2<u32>, r: u32) -> vec2<u32> {
+    if r >= 32u {
+        return vec2(a.x ^ (a.y >> (r - 32u)), a.y);
+    }
+    return vec2(a.x ^ ((a.x >> r) | (a.y << (32u - r))), a.y ^ (a.y >> r));
+}
+
+const XXH64_P1: vec2<u32> = vec2(0x85ebca87u, 0x9e3779b1u);
+const XXH64_P2: vec2<u32> = vec2(0x27d4eb4fu, 0xc2b2ae3du);
+const XXH64_P3: vec2<u32> = vec2(0x9e3779f9u, 0x165667b1u);
+const XXH64_P4: vec2<u32> = vec2(0xc2b2ae63u, 0x85ebca77u);
+const XXH64_P5: vec2<u32> = vec2(0x165667c5u, 0x27d4eb2fu);
+
+fn xxh64_round(acc: vec2<u32>, lane: vec2<u32>) -> vec2<u32> {
+    return mul64(rotl64(add64(acc, mul64(lane, XXH64_P2)), 31u), XXH64_P1);
+}
+
+fn xxh64_merge_round(acc: vec2<u32>, v: vec2<u32>) -> vec2<u32> {
+    return add64(mul64(acc ^ xxh64_round(vec2(0u), v), XXH64_P1), XXH64_P4);
+}
+
+fn key_lane(key: u32, i: u32) -> vec2<u32> {
+    return vec2(key_word(key, i), key_word(key, i + 1u));
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn xxh64(@builtin(global_invocation_id) id: vec3<u32>) {
+    let key = id.x;
+    if key >= params.count {
+        return;
+    }
+    let seed = vec2(params.seed_lo, params.seed_hi);
+    var h: vec2<u32>;
+    var i = 0u;
+    if params.key_words >= 8u {
+        var v1 = add64(add64(seed, XXH64_P1), XXH64_P2);
+        var v2 = add64(seed, XXH64_P2);
+        var v3 = seed;
+        // seed - P1, as seed + (2^64 - P1).
+        var v4 = add64(seed, add64(~XXH64_P1, vec2(1u, 0u)));
+        for (; i + 8u <= params.key_words; i += 8u) {
+            v1 = xxh64_round(v1, key_lane(key, i));
+            v2 = xxh64_round(v2, key_lane(key, i + 2u));
+            v3 = xxh64_round(v3, key_lane(key, i + 4u));
+            v4 = xxh64_round(v4, key_lane(key, i + 6u));
+        }
+        h = add64(add64(rotl64(v1, 1u), rotl64(v2, 7u)), add64(rotl64(v3, 12u), rotl64(v4, 18u)));
+        h = xxh64_merge_round(h, v1);
+        h = xxh64_merge_round(h, v2);
+        h = xxh64_merge_round(h, v3);
+        h = xxh64_merge_round(h, v4);
+    } else {
+        h = add64(seed, XXH64_P5);
+    }
+    h = add64(h, vec2(4u * params.key_words, 0u));
+    for (; i + 2u <= params.key_words; i += 2u) {
+        h ^= xxh64_round(vec2(0u), key_lane(key, i));
+        h = add64(mul64(rotl64(h, 27u), XXH64_P1), XXH64_P4);
+    }
+    if i < params.key_words {
+        h ^= mul64(vec2(key_word(key, i), 0u), XXH64_P1);
+        h = add64(mul64(rotl64(h, 23u), XXH64_P2), XXH64_P3);
+    }
+    h = mul64(xorshift64(h, 33u), XXH64_P2);
+    h = mul64(xorshift64(h, 29u), XXH64_P3);
+    h = xorshift64(h, 32u);
+    out[2u * key] = h.x;
+    out[2u * key + 1u] = h.y;
+}
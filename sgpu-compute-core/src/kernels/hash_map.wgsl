@@ -0,0 +1,148 @@
+struct Params {
+    capacity: u32,
+    count: u32,
+    _pad0: u32,
+    _pad1: u32,
+}
+
+struct Entry {
+    key: vec2<u32>,
+    value: u32,
+    _pad0: u32,
+}
+
+struct Lookup {
+    found: u32,
+    value: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// The slots of the table, 4 words each: state, low and high words of the key, value. They are followed by the probe position of each entry of the batch being inserted.
+@group(0) @binding(1) var<storage, read_write> scratchpad: array<atomic<u32>>;
+@group(0) @binding(2) var<storage, read> in: array<Entry>;
+@group(0) @binding(3) var<storage, read_write> out: array<Lookup>;
+
+// Slot states, such that setting the `BUSY` bit only changes the state of empty slots, which claims them.
+const EMPTY: u32 = 0u;
+// Claimed during the current insertion pass, its key may not be visible yet.
+const BUSY: u32 = 1u;
+const FULL: u32 = 3u;
+
+// Probe positions of the entries which are inserted or can't be inserted.
+const DONE: u32 = 0xffffffffu;
+const TABLE_FULL: u32 = 0xfffffffeu;
+
+fn position_index(i: u32) -> u32 {
+    return 4u * params.capacity + i;
+}
+
+fn fmix(h: u32) -> u32 {
+    var x = h;
+    x ^= x >> 16u;
+    x *= 0x85ebca6bu;
+    x ^= x >> 13u;
+    x *= 0xc2b2ae35u;
+    x ^= x >> 16u;
+    return x;
+}
+
+fn home_slot(key: vec2<u32>) -> u32 {
+    return fmix(key.x ^ fmix(key.y + 0x9e3779b9u)) & (params.capacity - 1u);
+}
+
+// Walks the probe sequence of the entry `i` from `start`, returns its new probe position.
+fn probe(i: u32, start: u32) -> u32 {
+    let entry = in[i];
+    var slot = start;
+    for (var n = 0u; n < params.capacity; n++) {
+        let base = 4u * slot;
+        let state = atomicOr(&scratchpad[base], BUSY);
+        if state == EMPTY {
+            atomicStore(&scratchpad[base + 1u], entry.key.x);
+            atomicStore(&scratchpad[base + 2u], entry.key.y);
+            atomicStore(&scratchpad[base + 3u], entry.value);
+            return DONE;
+        }
+        if state == BUSY {
+            return slot;
+        }
+        if atomicLoad(&scratchpad[base + 1u]) == entry.key.x && atomicLoad(&scratchpad[base + 2u]) == entry.key.y {
+            atomicStore(&scratchpad[base + 3u], entry.value);
+            return DONE;
+        }
+        slot = (slot + 1u) & (params.capacity - 1u);
+    }
+    return TABLE_FULL;
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn clear(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x < params.capacity {
+        atomicStore(&scratchpad[4u * id.x], EMPTY);
+    }
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn insert(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if i < params.count {
+        atomicStore(&scratchpad[position_index(i)], probe(i, home_slot(in[i].key)));
+    }
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn insert_retry(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if i >= params.count {
+        return;
+    }
+    let position = atomicLoad(&scratchpad[position_index(i)]);
+    if position < params.capacity {
+        atomicStore(&scratchpad[position_index(i)], probe(i, position));
+    }
+}
+
+// Makes the slots claimed by the previous pass visible to the next one.
+@compute
+@workgroup_size(64, 1, 1)
+fn settle(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x < params.capacity && atomicLoad(&scratchpad[4u * id.x]) == BUSY {
+        atomicStore(&scratchpad[4u * id.x], FULL);
+    }
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn finish_insert(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if i < params.count {
+        out[i] = Lookup(u32(atomicLoad(&scratchpad[position_index(i)]) == DONE), 0u);
+    }
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn lookup(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if i >= params.count {
+        return;
+    }
+    let key = in[i].key;
+    var slot = home_slot(key);
+    var result = Lookup(0u, 0u);
+    for (var n = 0u; n < params.capacity; n++) {
+        let base = 4u * slot;
+        if atomicLoad(&scratchpad[base]) == EMPTY {
+            break;
+        }
+        if atomicLoad(&scratchpad[base + 1u]) == key.x && atomicLoad(&scratchpad[base + 2u]) == key.y {
+            result = Lookup(1u, atomicLoad(&scratchpad[base + 3u]));
+            break;
+        }
+        slot = (slot + 1u) & (params.capacity - 1u);
+    }
+    out[i] = result;
+}
@@ -0,0 +1,38 @@
+struct Params {
+    width: u32,
+    height: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// Row-scanned image, transposed (height columns, width rows).
+@group(0) @binding(1) var<storage, read_write> scratchpad: array<f32>;
+@group(0) @binding(2) var<storage, read> in: array<f32>;
+@group(0) @binding(3) var<storage, read_write> out: array<f32>;
+
+@compute
+@workgroup_size(64, 1, 1)
+fn scan_rows(@builtin(global_invocation_id) id: vec3<u32>) {
+    let y = id.x;
+    if y >= params.height {
+        return;
+    }
+    var total = 0.0;
+    for (var x = 0u; x < params.width; x++) {
+        total += in[x + params.width * y];
+        scratchpad[y + params.height * x] = total;
+    }
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn scan_columns(@builtin(global_invocation_id) id: vec3<u32>) {
+    let x = id.x;
+    if x >= params.width {
+        return;
+    }
+    var total = 0.0;
+    for (var y = 0u; y < params.height; y++) {
+        total += scratchpad[y + params.height * x];
+        out[x + params.width * y] = total;
+    }
+}
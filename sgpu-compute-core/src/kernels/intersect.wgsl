@@ -0,0 +1,98 @@
+struct Hit {
+    distance: f32,
+    primitive: u32,
+}
+
+// Rays are stored first (2 vec4 per ray), followed by the primitives (3 vec4 per triangle, 2 vec4 per box).
+@group(0) @binding(0) var<storage, read> in: array<vec4<f32>>;
+@group(0) @binding(1) var<storage, read_write> out: array<Hit>;
+
+const MISS: u32 = 0xffffffffu;
+const EPSILON: f32 = 1e-8;
+const HUGE: f32 = 1e30;
+
+/// Möller–Trumbore intersection, returns -1.0 when the triangle is missed.
+fn intersect_triangle(origin: vec3<f32>, direction: vec3<f32>, t_min: f32, t_max: f32, a: vec3<f32>, b: vec3<f32>, c: vec3<f32>) -> f32 {
+    let e1 = b - a;
+    let e2 = c - a;
+    let p = cross(direction, e2);
+    let det = dot(e1, p);
+    if abs(det) < EPSILON {
+        return -1.0;
+    }
+    let inv_det = 1.0 / det;
+    let s = origin - a;
+    let u = dot(s, p) * inv_det;
+    if u < 0.0 || u > 1.0 {
+        return -1.0;
+    }
+    let q = cross(s, e1);
+    let v = dot(direction, q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return -1.0;
+    }
+    let t = dot(e2, q) * inv_det;
+    if t < t_min || t > t_max {
+        return -1.0;
+    }
+    return t;
+}
+
+/// Slab test, returns -1.0 when the box is missed and the entry distance otherwise.
+fn intersect_aabb(origin: vec3<f32>, direction: vec3<f32>, t_min: f32, t_max: f32, lo: vec3<f32>, hi: vec3<f32>) -> f32 {
+    let inv_dir = select(1.0 / direction, vec3<f32>(HUGE), direction == vec3<f32>(0.0));
+    let t0 = (lo - origin) * inv_dir;
+    let t1 = (hi - origin) * inv_dir;
+    let near = min(t0, t1);
+    let far = max(t0, t1);
+    let enter = max(max(max(near.x, near.y), near.z), t_min);
+    let exit = min(min(min(far.x, far.y), far.z), t_max);
+    if enter > exit {
+        return -1.0;
+    }
+    return enter;
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn ray_triangle(@builtin(global_invocation_id) id: vec3<u32>) {
+    let n_rays = arrayLength(&out);
+    if id.x >= n_rays {
+        return;
+    }
+    let origin = in[2u * id.x];
+    let direction = in[2u * id.x + 1u];
+    let n_triangles = (arrayLength(&in) - 2u * n_rays) / 3u;
+
+    var best = Hit(-1.0, MISS);
+    for (var i = 0u; i < n_triangles; i++) {
+        let base = 2u * n_rays + 3u * i;
+        let t = intersect_triangle(origin.xyz, direction.xyz, origin.w, direction.w, in[base].xyz, in[base + 1u].xyz, in[base + 2u].xyz);
+        if t >= 0.0 && (best.primitive == MISS || t < best.distance) {
+            best = Hit(t, i);
+        }
+    }
+    out[id.x] = best;
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn ray_aabb(@builtin(global_invocation_id) id: vec3<u32>) {
+    let n_rays = arrayLength(&out);
+    if id.x >= n_rays {
+        return;
+    }
+    let origin = in[2u * id.x];
+    let direction = in[2u * id.x + 1u];
+    let n_boxes = (arrayLength(&in) - 2u * n_rays) / 2u;
+
+    var best = Hit(-1.0, MISS);
+    for (var i = 0u; i < n_boxes; i++) {
+        let base = 2u * n_rays + 2u * i;
+        let t = intersect_aabb(origin.xyz, direction.xyz, origin.w, direction.w, in[base].xyz, in[base + 1u].xyz);
+        if t >= 0.0 && (best.primitive == MISS || t < best.distance) {
+            best = Hit(t, i);
+        }
+    }
+    out[id.x] = best;
+}
@@ -0,0 +1,72 @@
+struct Node {
+    left: u32,
+    right: u32,
+}
+
+// Sorted Morton codes, one per leaf.
+@group(0) @binding(0) var<storage, read> in: array<u32>;
+@group(0) @binding(1) var<storage, read_write> out: array<Node>;
+
+const LEAF: u32 = 0x80000000u;
+
+/// Length of the common prefix of the keys at i and j, -1 when j is out of range. Equal codes are disambiguated with their index.
+fn delta(i: i32, j: i32) -> i32 {
+    let n = i32(arrayLength(&in));
+    if j < 0 || j >= n {
+        return -1;
+    }
+    let a = in[i];
+    let b = in[j];
+    if a == b {
+        return 32 + i32(countLeadingZeros(u32(i) ^ u32(j)));
+    }
+    return i32(countLeadingZeros(a ^ b));
+}
+
+/// Emits internal node i following "Maximizing Parallelism in the Construction of BVHs, Octrees, and k-d Trees" (Karras 2012).
+@compute
+@workgroup_size(64, 1, 1)
+fn lbvh(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= arrayLength(&out) {
+        return;
+    }
+    let i = i32(id.x);
+
+    // Direction of the range covered by the node.
+    let d = select(-1, 1, delta(i, i + 1) - delta(i, i - 1) >= 0);
+    let delta_min = delta(i, i - d);
+
+    // Upper bound then binary search of the other end of the range.
+    var l_max = 2;
+    while delta(i, i + l_max * d) > delta_min {
+        l_max *= 2;
+    }
+    var l = 0;
+    for (var t = l_max / 2; t >= 1; t /= 2) {
+        if delta(i, i + (l + t) * d) > delta_min {
+            l += t;
+        }
+    }
+    let j = i + l * d;
+
+    // Binary search of the split position.
+    let delta_node = delta(i, j);
+    var s = 0;
+    var divisor = 2;
+    loop {
+        let t = (l + divisor - 1) / divisor;
+        if delta(i, i + (s + t) * d) > delta_node {
+            s += t;
+        }
+        if t <= 1 {
+            break;
+        }
+        divisor *= 2;
+    }
+    let gamma = i + s * d + min(d, 0);
+
+    var node: Node;
+    node.left = select(u32(gamma), u32(gamma) | LEAF, min(i, j) == gamma);
+    node.right = select(u32(gamma + 1), u32(gamma + 1) | LEAF, max(i, j) == gamma + 1);
+    out[id.x] = node;
+}
@@ -0,0 +1,57 @@
+// Preceded by the declarations of `map_element`, `combine` and `identity` generated by `shader`.
+
+const WORKGROUP_SIZE: u32 = 256u;
+
+@group(0) @binding(0) var<uniform> len: u32;
+// Partial result of each workgroup of the first stage.
+@group(0) @binding(1) var<storage, read_write> scratchpad: array<f32>;
+@group(0) @binding(2) var<storage, read> in: array<f32>;
+@group(0) @binding(3) var<storage, read_write> out: f32;
+
+var<workgroup> partial: array<f32, WORKGROUP_SIZE>;
+
+// Reduces `partial` into `partial[0]`.
+fn reduce_workgroup(local: u32) {
+    for (var stride = WORKGROUP_SIZE / 2u; stride > 0u; stride /= 2u) {
+        workgroupBarrier();
+        if local < stride {
+            partial[local] = combine(partial[local], partial[local + stride]);
+        }
+    }
+    workgroupBarrier();
+}
+
+// Maps and reduces a strided part of the input per workgroup.
+@compute
+@workgroup_size(256, 1, 1)
+fn map_reduce(
+    @builtin(local_invocation_index) local: u32,
+    @builtin(workgroup_id) workgroup: vec3<u32>,
+    @builtin(num_workgroups) workgroups: vec3<u32>,
+) {
+    var acc = identity();
+    let stride = workgroups.x * WORKGROUP_SIZE;
+    for (var i = workgroup.x * WORKGROUP_SIZE + local; i < len; i += stride) {
+        acc = combine(acc, map_element(in[i]));
+    }
+    partial[local] = acc;
+    reduce_workgroup(local);
+    if local == 0u {
+        scratchpad[workgroup.x] = partial[0];
+    }
+}
+
+// Reduces the partial results of the workgroups, which are at most `WORKGROUP_SIZE`.
+@compute
+@workgroup_size(256, 1, 1)
+fn finish(@builtin(local_invocation_index) local: u32) {
+    let partials = min(WORKGROUP_SIZE, (len + WORKGROUP_SIZE - 1u) / WORKGROUP_SIZE);
+    partial[local] = identity();
+    if local < partials {
+        partial[local] = scratchpad[local];
+    }
+    reduce_workgroup(local);
+    if local == 0u {
+        out = partial[0];
+    }
+}
@@ -0,0 +1,97 @@
+// Prepended with the WGSL of the `seed` module and followed by the integrand, which declares `fn integrand(x: vec4<f32>) -> f32`.
+
+const WORKGROUP_SIZE: u32 = 64u;
+const SAMPLES_PER_INVOCATION: u32 = 16u;
+const BATCH_WORKGROUPS: u32 = 256u;
+
+struct Params {
+    seed: Seed,
+    samples: u32,
+    reset: u32,
+    _pad0: u32,
+    _pad1: u32,
+}
+
+// Count, mean and sum of the squared differences to the mean of a set of samples.
+struct Moments {
+    count: f32,
+    mean: f32,
+    m2: f32,
+    _pad0: f32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// Moments of each workgroup of the batch, followed by the moments of all the batches since the last reset.
+@group(0) @binding(1) var<storage, read_write> scratchpad: array<Moments>;
+@group(0) @binding(2) var<storage, read_write> out: Moments;
+
+var<workgroup> shared_moments: array<Moments, WORKGROUP_SIZE>;
+
+// Parallel combination of Chan et al.
+fn merge(a: Moments, b: Moments) -> Moments {
+    let count = a.count + b.count;
+    if count == 0.0 {
+        return a;
+    }
+    let delta = b.mean - a.mean;
+    let mean = a.mean + delta * (b.count / count);
+    let m2 = a.m2 + b.m2 + delta * delta * (a.count * b.count / count);
+    return Moments(count, mean, m2, 0.0);
+}
+
+fn reduce_workgroup(local: u32, moments: Moments) -> Moments {
+    shared_moments[local] = moments;
+    workgroupBarrier();
+    for (var stride = WORKGROUP_SIZE / 2u; stride > 0u; stride >>= 1u) {
+        if local < stride {
+            shared_moments[local] = merge(shared_moments[local], shared_moments[local + stride]);
+        }
+        workgroupBarrier();
+    }
+    return shared_moments[0];
+}
+
+@compute
+@workgroup_size(WORKGROUP_SIZE, 1, 1)
+fn sample(@builtin(local_invocation_index) local: u32, @builtin(workgroup_id) workgroup: vec3<u32>) {
+    let first = (workgroup.x * WORKGROUP_SIZE + local) * SAMPLES_PER_INVOCATION;
+    var moments = Moments(0.0, 0.0, 0.0, 0.0);
+    for (var i = 0u; i < SAMPLES_PER_INVOCATION; i++) {
+        let index = first + i;
+        if index < params.samples {
+            let x = vec4(
+                seed_random_f32(params.seed, index, 0u),
+                seed_random_f32(params.seed, index, 1u),
+                seed_random_f32(params.seed, index, 2u),
+                seed_random_f32(params.seed, index, 3u),
+            );
+            let y = integrand(x);
+            // Welford's update.
+            moments.count += 1.0;
+            let delta = y - moments.mean;
+            moments.mean += delta / moments.count;
+            moments.m2 += delta * (y - moments.mean);
+        }
+    }
+    let total = reduce_workgroup(local, moments);
+    if local == 0u {
+        scratchpad[workgroup.x] = total;
+    }
+}
+
+@compute
+@workgroup_size(WORKGROUP_SIZE, 1, 1)
+fn accumulate(@builtin(local_invocation_index) local: u32) {
+    var moments = Moments(0.0, 0.0, 0.0, 0.0);
+    for (var i = local; i < BATCH_WORKGROUPS; i += WORKGROUP_SIZE) {
+        moments = merge(moments, scratchpad[i]);
+    }
+    var total = reduce_workgroup(local, moments);
+    if local == 0u {
+        if params.reset == 0u {
+            total = merge(scratchpad[BATCH_WORKGROUPS], total);
+        }
+        scratchpad[BATCH_WORKGROUPS] = total;
+        out = total;
+    }
+}
@@ -0,0 +1,30 @@
+struct Bounds {
+    min: vec4<f32>,
+    max: vec4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> bounds: Bounds;
+@group(0) @binding(1) var<storage, read> in: array<vec4<f32>>;
+@group(0) @binding(2) var<storage, read_write> out: array<u32>;
+
+/// Inserts two zero bits between each of the 10 lowest bits of v.
+fn expand_bits(value: u32) -> u32 {
+    var v = value & 0x3ffu;
+    v = (v | (v << 16u)) & 0x030000ffu;
+    v = (v | (v << 8u)) & 0x0300f00fu;
+    v = (v | (v << 4u)) & 0x030c30c3u;
+    v = (v | (v << 2u)) & 0x09249249u;
+    return v;
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn morton(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= arrayLength(&out) {
+        return;
+    }
+    let extent = max(bounds.max.xyz - bounds.min.xyz, vec3<f32>(1e-30));
+    let normalized = clamp((in[id.x].xyz - bounds.min.xyz) / extent, vec3<f32>(0.0), vec3<f32>(1.0));
+    let cell = min(vec3<u32>(normalized * 1024.0), vec3<u32>(1023u));
+    out[id.x] = (expand_bits(cell.x) << 2u) | (expand_bits(cell.y) << 1u) | expand_bits(cell.z);
+}
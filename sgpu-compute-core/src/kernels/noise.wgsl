@@ -0,0 +1,141 @@
+struct Params {
+    width: u32,
+    height: u32,
+    depth: u32,
+    seed: u32,
+    frequency: f32,
+    octaves: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read_write> out: array<f32>;
+
+/// PCG hash from "Hash Functions for GPU Rendering" (Jarzynski & Olano 2020).
+fn pcg(v: u32) -> u32 {
+    let state = v * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+fn hash(cell: vec3<u32>, seed: u32) -> u32 {
+    return pcg(cell.x ^ pcg(cell.y ^ pcg(cell.z ^ pcg(seed))));
+}
+
+/// Dot product between the offset and one of the 12 gradients of improved Perlin noise.
+fn grad(h: u32, d: vec3<f32>) -> f32 {
+    let k = h & 15u;
+    let u = select(d.y, d.x, k < 8u);
+    let v = select(select(d.z, d.x, k == 12u || k == 14u), d.y, k < 4u);
+    return select(-u, u, (k & 1u) == 0u) + select(-v, v, (k & 2u) == 0u);
+}
+
+fn fade(t: vec3<f32>) -> vec3<f32> {
+    return t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+}
+
+fn perlin(p: vec3<f32>, seed: u32) -> f32 {
+    let cell = floor(p);
+    let f = p - cell;
+    let c = bitcast<vec3<u32>>(vec3<i32>(cell));
+    let u = fade(f);
+
+    let n000 = grad(hash(c, seed), f);
+    let n100 = grad(hash(c + vec3<u32>(1u, 0u, 0u), seed), f - vec3<f32>(1.0, 0.0, 0.0));
+    let n010 = grad(hash(c + vec3<u32>(0u, 1u, 0u), seed), f - vec3<f32>(0.0, 1.0, 0.0));
+    let n110 = grad(hash(c + vec3<u32>(1u, 1u, 0u), seed), f - vec3<f32>(1.0, 1.0, 0.0));
+    let n001 = grad(hash(c + vec3<u32>(0u, 0u, 1u), seed), f - vec3<f32>(0.0, 0.0, 1.0));
+    let n101 = grad(hash(c + vec3<u32>(1u, 0u, 1u), seed), f - vec3<f32>(1.0, 0.0, 1.0));
+    let n011 = grad(hash(c + vec3<u32>(0u, 1u, 1u), seed), f - vec3<f32>(0.0, 1.0, 1.0));
+    let n111 = grad(hash(c + vec3<u32>(1u, 1u, 1u), seed), f - vec3<f32>(1.0, 1.0, 1.0));
+
+    let x00 = mix(n000, n100, u.x);
+    let x10 = mix(n010, n110, u.x);
+    let x01 = mix(n001, n101, u.x);
+    let x11 = mix(n011, n111, u.x);
+    return mix(mix(x00, x10, u.y), mix(x01, x11, u.y), u.z);
+}
+
+/// Simplex noise (Gustavson 2005), with the gradients of `perlin`. It samples 4 corners instead of 8 and has no axis-aligned artifacts.
+fn simplex(p: vec3<f32>, seed: u32) -> f32 {
+    // Skews the space so the simplices are the halves of cubes, and unskews the corner of the cell.
+    let cell = floor(p + (p.x + p.y + p.z) / 3.0);
+    let d0 = p - cell + (cell.x + cell.y + cell.z) / 6.0;
+    // The other corners are chosen by the order of the coordinates of the offset.
+    let g = step(d0.yzx, d0.xyz);
+    let l = 1.0 - g;
+    let i1 = min(g, l.zxy);
+    let i2 = max(g, l.zxy);
+    let d1 = d0 - i1 + 1.0 / 6.0;
+    let d2 = d0 - i2 + 2.0 / 6.0;
+    let d3 = d0 - 1.0 + 3.0 / 6.0;
+    let c = bitcast<vec3<u32>>(vec3<i32>(cell));
+    let total = corner(hash(c, seed), d0)
+        + corner(hash(c + vec3<u32>(i1), seed), d1)
+        + corner(hash(c + vec3<u32>(i2), seed), d2)
+        + corner(hash(c + vec3<u32>(1u, 1u, 1u), seed), d3);
+    // Scales the extrema of the sum to about 1, clamping the rare values slightly above.
+    return clamp(76.0 * total, -1.0, 1.0);
+}
+
+/// Contribution of a corner of a simplex at the offset `d`.
+fn corner(h: u32, d: vec3<f32>) -> f32 {
+    let t = max(0.5 - dot(d, d), 0.0);
+    return t * t * t * t * grad(h, d);
+}
+
+/// Sum of `octaves` layers of Perlin noise, or simplex noise if `use_simplex` is set, each one with twice the frequency and half the amplitude of the previous one.
+fn fbm(p: vec3<f32>, use_simplex: bool) -> f32 {
+    var total = 0.0;
+    var amplitude = 1.0;
+    var norm = 0.0;
+    var frequency = params.frequency;
+    for (var octave = 0u; octave < max(params.octaves, 1u); octave++) {
+        let q = p * frequency;
+        let seed = params.seed + octave;
+        if use_simplex {
+            total += amplitude * simplex(q, seed);
+        } else {
+            total += amplitude * perlin(q, seed);
+        }
+        norm += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    return total / norm;
+}
+
+@compute
+@workgroup_size(8, 8, 1)
+fn noise_2d(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.width || id.y >= params.height {
+        return;
+    }
+    out[id.x + params.width * id.y] = fbm(vec3<f32>(vec2<f32>(id.xy), 0.0), false);
+}
+
+@compute
+@workgroup_size(4, 4, 4)
+fn noise_3d(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.width || id.y >= params.height || id.z >= params.depth {
+        return;
+    }
+    out[id.x + params.width * (id.y + params.height * id.z)] = fbm(vec3<f32>(id), false);
+}
+
+@compute
+@workgroup_size(8, 8, 1)
+fn simplex_2d(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.width || id.y >= params.height {
+        return;
+    }
+    out[id.x + params.width * id.y] = fbm(vec3<f32>(vec2<f32>(id.xy), 0.0), true);
+}
+
+@compute
+@workgroup_size(4, 4, 4)
+fn simplex_3d(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.width || id.y >= params.height || id.z >= params.depth {
+        return;
+    }
+    out[id.x + params.width * (id.y + params.height * id.z)] = fbm(vec3<f32>(id), true);
+}
@@ -0,0 +1,22 @@
+// Prepended with the number of parameters of a candidate, `const DIMENSIONS: u32`, and followed by the objective, which declares `fn objective(x: ptr<function, array<f32, DIMENSIONS>>) -> f32`.
+
+const WORKGROUP_SIZE: u32 = 64u;
+
+// Parameters of the candidates, `DIMENSIONS` values per candidate.
+@group(0) @binding(0) var<storage, read> in: array<f32>;
+// Value of the objective for each candidate.
+@group(0) @binding(1) var<storage, read_write> out: array<f32>;
+
+@compute
+@workgroup_size(WORKGROUP_SIZE, 1, 1)
+fn evaluate(@builtin(global_invocation_id) id: vec3<u32>) {
+    let candidate = id.x;
+    if candidate >= arrayLength(&out) {
+        return;
+    }
+    var x: array<f32, DIMENSIONS>;
+    for (var i = 0u; i < DIMENSIONS; i++) {
+        x[i] = in[candidate * DIMENSIONS + i];
+    }
+    out[candidate] = objective(&x);
+}
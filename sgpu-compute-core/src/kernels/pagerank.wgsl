@@ -0,0 +1,119 @@
+struct Params {
+    vertices: u32,
+    damping: f32,
+    reset: u32,
+    _pad0: u32,
+}
+
+struct Ranks {
+    residual: f32,
+    iterations: u32,
+    ranks: array<f32>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// Parity of the current ranks and dangling mass, then the two rank buffers.
+@group(0) @binding(1) var<storage, read_write> scratchpad: array<f32>;
+// Transposed CSR graph: `vertices + 1` offsets into the sources of the incoming edges, the sources, then the out-degree of each vertex.
+@group(0) @binding(2) var<storage, read> in: array<u32>;
+@group(0) @binding(3) var<storage, read_write> out: Ranks;
+
+const META_WORDS: u32 = 4u;
+const WORKGROUP: u32 = 256u;
+
+var<workgroup> partial: array<f32, WORKGROUP>;
+
+fn ranks_offset(buffer: u32) -> u32 {
+    return META_WORDS + buffer * params.vertices;
+}
+
+fn current() -> u32 {
+    return bitcast<u32>(scratchpad[0]);
+}
+
+fn out_degree(vertex: u32) -> u32 {
+    let edges = in[params.vertices];
+    return in[params.vertices + 1u + edges + vertex];
+}
+
+// Sums `partial` into `partial[0]`.
+fn reduce(local: u32) {
+    for (var stride = WORKGROUP / 2u; stride > 0u; stride /= 2u) {
+        workgroupBarrier();
+        if local < stride {
+            partial[local] += partial[local + stride];
+        }
+    }
+    workgroupBarrier();
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn init(@builtin(global_invocation_id) id: vec3<u32>) {
+    if params.reset == 0u || id.x >= params.vertices {
+        return;
+    }
+    scratchpad[ranks_offset(0u) + id.x] = 1.0 / f32(params.vertices);
+    if id.x == 0u {
+        scratchpad[0] = bitcast<f32>(0u);
+        out.residual = 0.0;
+        out.iterations = 0u;
+    }
+}
+
+// Sums the ranks of the vertices without outgoing edges, which are spread over all the vertices.
+@compute
+@workgroup_size(256, 1, 1)
+fn dangling(@builtin(local_invocation_index) local: u32) {
+    let ranks = ranks_offset(current());
+    var sum = 0.0;
+    for (var v = local; v < params.vertices; v += WORKGROUP) {
+        if out_degree(v) == 0u {
+            sum += scratchpad[ranks + v];
+        }
+    }
+    partial[local] = sum;
+    reduce(local);
+    if local == 0u {
+        scratchpad[1] = partial[0];
+    }
+}
+
+// Multiplies the ranks by the transition matrix, pulling the contributions of the incoming edges.
+@compute
+@workgroup_size(64, 1, 1)
+fn spmv(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.vertices {
+        return;
+    }
+    let ranks = ranks_offset(current());
+    let sources = params.vertices + 1u;
+    var sum = 0.0;
+    for (var e = in[id.x]; e < in[id.x + 1u]; e++) {
+        let source = in[sources + e];
+        sum += scratchpad[ranks + source] / f32(out_degree(source));
+    }
+    let n = f32(params.vertices);
+    let rank = (1.0 - params.damping) / n + params.damping * (sum + scratchpad[1] / n);
+    scratchpad[ranks_offset(1u - current()) + id.x] = rank;
+    out.ranks[id.x] = rank;
+}
+
+// Computes the L1 distance between the previous and the new ranks, then makes the new ranks the current ones.
+@compute
+@workgroup_size(256, 1, 1)
+fn finish(@builtin(local_invocation_index) local: u32) {
+    let previous = ranks_offset(current());
+    let next = ranks_offset(1u - current());
+    var sum = 0.0;
+    for (var v = local; v < params.vertices; v += WORKGROUP) {
+        sum += abs(scratchpad[next + v] - scratchpad[previous + v]);
+    }
+    partial[local] = sum;
+    reduce(local);
+    if local == 0u {
+        out.residual = partial[0];
+        out.iterations += 1u;
+        scratchpad[0] = bitcast<f32>(1u - current());
+    }
+}
@@ -0,0 +1,188 @@
+struct Params {
+    gravity: vec2<f32>,
+    bounds: vec2<f32>,
+    count: u32,
+    grid_width: u32,
+    grid_height: u32,
+    reset: u32,
+    cell_size: f32,
+    radius: f32,
+    dt: f32,
+    restitution: f32,
+}
+
+struct Particle {
+    position: vec2<f32>,
+    velocity: vec2<f32>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// Layout in words: the particle count of each cell, the first sorted slot of each cell followed by the particle count,
+// the particle indices sorted by cell, then the velocity of each particle after the collisions as `f32` bits.
+@group(0) @binding(1) var<storage, read_write> scratchpad: array<atomic<u32>>;
+@group(0) @binding(2) var<storage, read> in: array<Particle>;
+// The particles persist in the output between runs, the input is only read on reset.
+@group(0) @binding(3) var<storage, read_write> out: array<Particle>;
+
+const SCAN_SIZE: u32 = 64u;
+
+var<workgroup> partial_sums: array<u32, SCAN_SIZE>;
+
+fn cell_count() -> u32 {
+    return params.grid_width * params.grid_height;
+}
+
+fn starts_offset() -> u32 {
+    return cell_count();
+}
+
+fn sorted_offset() -> u32 {
+    return 2u * cell_count() + 1u;
+}
+
+fn velocity_offset() -> u32 {
+    return sorted_offset() + params.count;
+}
+
+fn cell_coords(position: vec2<f32>) -> vec2<i32> {
+    let grid = vec2(i32(params.grid_width), i32(params.grid_height));
+    return clamp(vec2<i32>(floor(position / params.cell_size)), vec2(0), grid - 1);
+}
+
+fn cell_index(coords: vec2<i32>) -> u32 {
+    return u32(coords.x) + params.grid_width * u32(coords.y);
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn integrate(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if i >= params.count {
+        return;
+    }
+    var particle = out[i];
+    if params.reset != 0u {
+        particle = in[i];
+    }
+    particle.velocity += params.gravity * params.dt;
+    particle.position += particle.velocity * params.dt;
+    let low = vec2(params.radius);
+    let high = params.bounds - params.radius;
+    for (var axis = 0; axis < 2; axis++) {
+        if particle.position[axis] < low[axis] {
+            particle.position[axis] = low[axis];
+            particle.velocity[axis] = abs(particle.velocity[axis]) * params.restitution;
+        } else if particle.position[axis] > high[axis] {
+            particle.position[axis] = high[axis];
+            particle.velocity[axis] = -abs(particle.velocity[axis]) * params.restitution;
+        }
+    }
+    out[i] = particle;
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn clear_grid(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x < cell_count() {
+        atomicStore(&scratchpad[id.x], 0u);
+    }
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn count(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x < params.count {
+        atomicAdd(&scratchpad[cell_index(cell_coords(out[id.x].position))], 1u);
+    }
+}
+
+// Exclusive scan of the counts by a single workgroup, each invocation scanning a contiguous range of cells.
+@compute
+@workgroup_size(SCAN_SIZE, 1, 1)
+fn scan(@builtin(local_invocation_index) local: u32) {
+    let cells = cell_count();
+    let per_invocation = (cells + SCAN_SIZE - 1u) / SCAN_SIZE;
+    let begin = min(local * per_invocation, cells);
+    let end = min(begin + per_invocation, cells);
+    var sum = 0u;
+    for (var c = begin; c < end; c++) {
+        sum += atomicLoad(&scratchpad[c]);
+    }
+    partial_sums[local] = sum;
+    workgroupBarrier();
+    var offset = 0u;
+    for (var j = 0u; j < local; j++) {
+        offset += partial_sums[j];
+    }
+    for (var c = begin; c < end; c++) {
+        atomicStore(&scratchpad[starts_offset() + c], offset);
+        offset += atomicLoad(&scratchpad[c]);
+    }
+    if local == 0u {
+        atomicStore(&scratchpad[starts_offset() + cells], params.count);
+    }
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn scatter(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.count {
+        return;
+    }
+    let cell = cell_index(cell_coords(out[id.x].position));
+    let slot = atomicLoad(&scratchpad[starts_offset() + cell]) + atomicSub(&scratchpad[cell], 1u) - 1u;
+    atomicStore(&scratchpad[sorted_offset() + slot], id.x);
+}
+
+// Impulse of equal-mass spheres for each approaching neighbor closer than twice the radius.
+@compute
+@workgroup_size(64, 1, 1)
+fn collide(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if i >= params.count {
+        return;
+    }
+    let particle = out[i];
+    let center = cell_coords(particle.position);
+    let grid = vec2(i32(params.grid_width), i32(params.grid_height));
+    var velocity = particle.velocity;
+    for (var y = max(center.y - 1, 0); y <= min(center.y + 1, grid.y - 1); y++) {
+        for (var x = max(center.x - 1, 0); x <= min(center.x + 1, grid.x - 1); x++) {
+            let cell = cell_index(vec2(x, y));
+            let begin = atomicLoad(&scratchpad[starts_offset() + cell]);
+            let end = atomicLoad(&scratchpad[starts_offset() + cell + 1u]);
+            for (var slot = begin; slot < end; slot++) {
+                let j = atomicLoad(&scratchpad[sorted_offset() + slot]);
+                if j == i {
+                    continue;
+                }
+                let other = out[j];
+                let offset = particle.position - other.position;
+                let distance = length(offset);
+                if distance >= 2.0 * params.radius || distance == 0.0 {
+                    continue;
+                }
+                let normal = offset / distance;
+                let approach = dot(particle.velocity - other.velocity, normal);
+                if approach < 0.0 {
+                    velocity -= 0.5 * (1.0 + params.restitution) * approach * normal;
+                }
+            }
+        }
+    }
+    atomicStore(&scratchpad[velocity_offset() + 2u * i], bitcast<u32>(velocity.x));
+    atomicStore(&scratchpad[velocity_offset() + 2u * i + 1u], bitcast<u32>(velocity.y));
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn apply(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if i >= params.count {
+        return;
+    }
+    out[i].velocity = vec2(
+        bitcast<f32>(atomicLoad(&scratchpad[velocity_offset() + 2u * i])),
+        bitcast<f32>(atomicLoad(&scratchpad[velocity_offset() + 2u * i + 1u])),
+    );
+}
@@ -0,0 +1,11 @@
+// Prepended with the bindings of the population, `population: array<Genome>`, and of the scores, `scores: array<f32>`, and followed by the fitness, which declares the `Genome` type and `fn fitness(index: u32) -> f32` scoring `population[index]`.
+
+const WORKGROUP_SIZE: u32 = 64u;
+
+@compute
+@workgroup_size(WORKGROUP_SIZE, 1, 1)
+fn evaluate(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x < arrayLength(&scores) {
+        scores[id.x] = fitness(id.x);
+    }
+}
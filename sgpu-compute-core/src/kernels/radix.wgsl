@@ -0,0 +1,120 @@
+// LSD radix sort of `u32` or `u64` keys, 8 bits per pass, shared by the `sort` and `dedup` shaders. They declare `params`
+// (with `count`, `key_words` and `blocks`), `scratchpad: array<u32>` and `in: array<u32>` holding the keys.
+
+const RADIX_BLOCK: u32 = 256u;
+const RADIX_DIGITS: u32 = 256u;
+// The pass read by the histograms, then the pass read by the scatter.
+const META_WORDS: u32 = 4u;
+
+var<workgroup> digit_counts: array<atomic<u32>, RADIX_DIGITS>;
+var<workgroup> digit_totals: array<u32, RADIX_DIGITS>;
+var<workgroup> block_digits: array<u32, RADIX_BLOCK>;
+
+fn keys_a() -> u32 {
+    return META_WORDS;
+}
+
+fn keys_b() -> u32 {
+    return META_WORDS + params.count * params.key_words;
+}
+
+// Digit counts of each block, digit-major, then their exclusive scan.
+fn histogram_offset() -> u32 {
+    return META_WORDS + 2u * params.count * params.key_words;
+}
+
+// Word `w` of the key `i` once sorted, the passes ping-pong between the two key buffers and end in the first one.
+fn sorted_key_word(i: u32, w: u32) -> u32 {
+    return scratchpad[keys_a() + i * params.key_words + w];
+}
+
+// Source and destination key buffers of the pass `p`.
+fn pass_buffers(p: u32) -> vec2<u32> {
+    if p % 2u == 0u {
+        return vec2(keys_a(), keys_b());
+    }
+    return vec2(keys_b(), keys_a());
+}
+
+fn digit(src: u32, i: u32, p: u32) -> u32 {
+    let word = scratchpad[src + i * params.key_words + p / 4u];
+    return (word >> (8u * (p % 4u))) & 0xffu;
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn sort_load(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x == 0u {
+        scratchpad[0] = 0u;
+    }
+    if id.x < params.count * params.key_words {
+        scratchpad[keys_a() + id.x] = in[id.x];
+    }
+}
+
+@compute
+@workgroup_size(RADIX_BLOCK, 1, 1)
+fn sort_histogram(@builtin(local_invocation_index) local: u32, @builtin(workgroup_id) block: vec3<u32>) {
+    atomicStore(&digit_counts[local], 0u);
+    workgroupBarrier();
+    let p = scratchpad[0];
+    let i = block.x * RADIX_BLOCK + local;
+    if i < params.count {
+        atomicAdd(&digit_counts[digit(pass_buffers(p).x, i, p)], 1u);
+    }
+    workgroupBarrier();
+    scratchpad[histogram_offset() + local * params.blocks + block.x] = atomicLoad(&digit_counts[local]);
+}
+
+// Exclusive scan of the histogram by a single workgroup, one invocation per digit.
+@compute
+@workgroup_size(RADIX_DIGITS, 1, 1)
+fn sort_scan(@builtin(local_invocation_index) local: u32) {
+    let row = histogram_offset() + local * params.blocks;
+    var total = 0u;
+    for (var b = 0u; b < params.blocks; b++) {
+        let count = scratchpad[row + b];
+        scratchpad[row + b] = total;
+        total += count;
+    }
+    digit_totals[local] = total;
+    workgroupBarrier();
+    var offset = 0u;
+    for (var d = 0u; d < local; d++) {
+        offset += digit_totals[d];
+    }
+    for (var b = 0u; b < params.blocks; b++) {
+        scratchpad[row + b] += offset;
+    }
+    if local == 0u {
+        let p = scratchpad[0];
+        scratchpad[1] = p;
+        scratchpad[0] = p + 1u;
+    }
+}
+
+// Stable scatter, each key is ranked among the keys of its block with the same digit.
+@compute
+@workgroup_size(RADIX_BLOCK, 1, 1)
+fn sort_scatter(@builtin(local_invocation_index) local: u32, @builtin(workgroup_id) block: vec3<u32>) {
+    let p = scratchpad[1];
+    let buffers = pass_buffers(p);
+    let i = block.x * RADIX_BLOCK + local;
+    var d = RADIX_DIGITS;
+    if i < params.count {
+        d = digit(buffers.x, i, p);
+    }
+    block_digits[local] = d;
+    workgroupBarrier();
+    if i >= params.count {
+        return;
+    }
+    var rank = 0u;
+    for (var l = 0u; l < local; l++) {
+        rank += u32(block_digits[l] == d);
+    }
+    let dst = scratchpad[histogram_offset() + d * params.blocks + block.x] + rank;
+    for (var w = 0u; w < params.key_words; w++) {
+        scratchpad[buffers.y + dst * params.key_words + w] = scratchpad[buffers.x + i * params.key_words + w];
+    }
+}
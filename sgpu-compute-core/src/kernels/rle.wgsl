@@ -0,0 +1,94 @@
+struct Run {
+    value: u32,
+    start: u32,
+    end: u32,
+}
+
+struct Runs {
+    count: u32,
+    runs: array<Run>,
+}
+
+// Number of runs starting in each block, then the number of runs starting before each block.
+@group(0) @binding(0) var<storage, read_write> scratchpad: array<u32>;
+@group(0) @binding(1) var<storage, read> in: array<u32>;
+@group(0) @binding(2) var<storage, read_write> out: Runs;
+
+const BLOCK: u32 = 256u;
+
+var<workgroup> block_count: atomic<u32>;
+var<workgroup> local_scan: array<u32, BLOCK>;
+
+fn is_set(i: u32) -> bool {
+    return in[i] != 0u;
+}
+
+fn is_start(i: u32) -> bool {
+    return i < arrayLength(&in) && (i == 0u || is_set(i) != is_set(i - 1u));
+}
+
+fn is_end(i: u32) -> bool {
+    let n = arrayLength(&in);
+    return i < n && (i + 1u == n || is_set(i) != is_set(i + 1u));
+}
+
+@compute
+@workgroup_size(256, 1, 1)
+fn count_runs(@builtin(global_invocation_id) id: vec3<u32>, @builtin(local_invocation_index) local: u32, @builtin(workgroup_id) block: vec3<u32>) {
+    if local == 0u {
+        atomicStore(&block_count, 0u);
+    }
+    workgroupBarrier();
+    if is_start(id.x) {
+        atomicAdd(&block_count, 1u);
+    }
+    workgroupBarrier();
+    if local == 0u {
+        scratchpad[block.x] = atomicLoad(&block_count);
+    }
+}
+
+@compute
+@workgroup_size(1, 1, 1)
+fn scan_blocks() {
+    let n_blocks = (arrayLength(&in) + BLOCK - 1u) / BLOCK;
+    var total = 0u;
+    for (var i = 0u; i < n_blocks; i++) {
+        let count = scratchpad[i];
+        scratchpad[i] = total;
+        total += count;
+    }
+    out.count = total;
+}
+
+@compute
+@workgroup_size(256, 1, 1)
+fn emit(@builtin(global_invocation_id) id: vec3<u32>, @builtin(local_invocation_index) local: u32, @builtin(workgroup_id) block: vec3<u32>) {
+    // Hillis-Steele inclusive scan of the run starts of the block.
+    local_scan[local] = u32(is_start(id.x));
+    workgroupBarrier();
+    for (var offset = 1u; offset < BLOCK; offset *= 2u) {
+        var value = local_scan[local];
+        if local >= offset {
+            value += local_scan[local - offset];
+        }
+        workgroupBarrier();
+        local_scan[local] = value;
+        workgroupBarrier();
+    }
+
+    if id.x >= arrayLength(&in) {
+        return;
+    }
+    let run = scratchpad[block.x] + local_scan[local] - 1u;
+    if run >= arrayLength(&out.runs) {
+        return;
+    }
+    if is_start(id.x) {
+        out.runs[run].value = u32(is_set(id.x));
+        out.runs[run].start = id.x;
+    }
+    if is_end(id.x) {
+        out.runs[run].end = id.x + 1u;
+    }
+}
@@ -0,0 +1,73 @@
+struct Params {
+    len: u32,
+    window: u32,
+    op: u32,
+    _pad0: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// Prefix then suffix of each block of `window` elements.
+@group(0) @binding(1) var<storage, read_write> scratchpad: array<f32>;
+@group(0) @binding(2) var<storage, read> in: array<f32>;
+@group(0) @binding(3) var<storage, read_write> out: array<f32>;
+
+const OP_SUM: u32 = 0u;
+const OP_MEAN: u32 = 1u;
+const OP_MIN: u32 = 2u;
+const OP_MAX: u32 = 3u;
+
+fn combine(a: f32, b: f32) -> f32 {
+    switch params.op {
+        case OP_MIN: {
+            return min(a, b);
+        }
+        case OP_MAX: {
+            return max(a, b);
+        }
+        default: {
+            return a + b;
+        }
+    }
+}
+
+// Computes the prefix and the suffix of a block of `window` elements.
+@compute
+@workgroup_size(64, 1, 1)
+fn blocks(@builtin(global_invocation_id) id: vec3<u32>) {
+    let start = id.x * params.window;
+    if start >= params.len {
+        return;
+    }
+    let end = min(start + params.window, params.len);
+    var acc = in[start];
+    scratchpad[start] = acc;
+    for (var i = start + 1u; i < end; i++) {
+        acc = combine(acc, in[i]);
+        scratchpad[i] = acc;
+    }
+    let suffix = params.len;
+    acc = in[end - 1u];
+    scratchpad[suffix + end - 1u] = acc;
+    for (var i = end - 1u; i > start; i--) {
+        acc = combine(in[i - 1u], acc);
+        scratchpad[suffix + i - 1u] = acc;
+    }
+}
+
+// Combines the suffix of the block of the first element of each window with the prefix of the block of its last element.
+@compute
+@workgroup_size(64, 1, 1)
+fn windows(@builtin(global_invocation_id) id: vec3<u32>) {
+    let start = id.x;
+    if start + params.window > params.len {
+        return;
+    }
+    var value = scratchpad[params.len + start];
+    if start % params.window != 0u {
+        value = combine(value, scratchpad[start + params.window - 1u]);
+    }
+    if params.op == OP_MEAN {
+        value /= f32(params.window);
+    }
+    out[start] = value;
+}
@@ -0,0 +1,76 @@
+const MAX_PATTERNS: u32 = 16u;
+const MAX_PATTERN_LEN: u32 = 32u;
+
+struct Params {
+    len: u32,
+    pattern_count: u32,
+    _pad0: u32,
+    _pad1: u32,
+    // Length of each pattern, 4 per vector.
+    lengths: array<vec4<u32>, 4>,
+    // Bytes of the pattern `p` starting at the byte `p * MAX_PATTERN_LEN`, 16 per vector.
+    bytes: array<vec4<u32>, 32>,
+}
+
+struct Match {
+    offset: u32,
+    pattern: u32,
+}
+
+struct Matches {
+    count: atomic<u32>,
+    matches: array<Match>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// Bytes of the haystack, 4 per word in little-endian order.
+@group(0) @binding(1) var<storage, read> in: array<u32>;
+@group(0) @binding(2) var<storage, read_write> out: Matches;
+
+fn haystack_byte(i: u32) -> u32 {
+    return (in[i / 4u] >> (8u * (i % 4u))) & 0xffu;
+}
+
+fn pattern_byte(p: u32, i: u32) -> u32 {
+    let byte = p * MAX_PATTERN_LEN + i;
+    return (params.bytes[byte / 16u][(byte / 4u) % 4u] >> (8u * (byte % 4u))) & 0xffu;
+}
+
+fn pattern_len(p: u32) -> u32 {
+    return params.lengths[p / 4u][p % 4u];
+}
+
+@compute
+@workgroup_size(1, 1, 1)
+fn clear() {
+    atomicStore(&out.count, 0u);
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn search(@builtin(global_invocation_id) id: vec3<u32>) {
+    let offset = id.x;
+    if offset >= params.len {
+        return;
+    }
+    let first = haystack_byte(offset);
+    for (var p = 0u; p < params.pattern_count; p++) {
+        let len = pattern_len(p);
+        if len == 0u || offset + len > params.len || pattern_byte(p, 0u) != first {
+            continue;
+        }
+        var found = true;
+        for (var i = 1u; i < len; i++) {
+            if haystack_byte(offset + i) != pattern_byte(p, i) {
+                found = false;
+                break;
+            }
+        }
+        if found {
+            let slot = atomicAdd(&out.count, 1u);
+            if slot < arrayLength(&out.matches) {
+                out.matches[slot] = Match(offset, p);
+            }
+        }
+    }
+}
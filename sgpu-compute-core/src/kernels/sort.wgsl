@@ -0,0 +1,21 @@
+
+struct Params {
+    count: u32,
+    key_words: u32,
+    blocks: u32,
+    _pad0: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// Metadata, the two key buffers and the histogram, see the radix sort core.
+@group(0) @binding(1) var<storage, read_write> scratchpad: array<u32>;
+@group(0) @binding(2) var<storage, read> in: array<u32>;
+@group(0) @binding(3) var<storage, read_write> out: array<u32>;
+
+@compute
+@workgroup_size(64, 1, 1)
+fn write_sorted(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x < params.count * params.key_words {
+        out[id.x] = scratchpad[keys_a() + id.x];
+    }
+}
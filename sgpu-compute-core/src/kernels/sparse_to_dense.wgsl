@@ -0,0 +1,41 @@
+struct Params {
+    len: u32,
+    fill: f32,
+    _pad0: u32,
+    _pad1: u32,
+}
+
+struct Entry {
+    index: u32,
+    value: f32,
+}
+
+struct Sparse {
+    count: u32,
+    entries: array<Entry>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> in: Sparse;
+@group(0) @binding(2) var<storage, read_write> out: array<f32>;
+
+@compute
+@workgroup_size(64, 1, 1)
+fn fill(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x < params.len {
+        out[id.x] = params.fill;
+    }
+}
+
+// Writes each entry at its index, the entries out of the dense array are ignored.
+@compute
+@workgroup_size(64, 1, 1)
+fn scatter(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= min(in.count, arrayLength(&in.entries)) {
+        return;
+    }
+    let entry = in.entries[id.x];
+    if entry.index < params.len {
+        out[entry.index] = entry.value;
+    }
+}
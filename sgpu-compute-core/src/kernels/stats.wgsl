@@ -0,0 +1,108 @@
+const WORKGROUP_SIZE: u32 = 256u;
+
+struct Moments {
+    count: u32,
+    sum: f32,
+    mean: f32,
+    // Sum of the squared differences to the mean.
+    m2: f32,
+    min: f32,
+    max: f32,
+}
+
+struct Params {
+    len: u32,
+    reset: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+// Moments of each workgroup of the first stage.
+@group(0) @binding(1) var<storage, read_write> scratchpad: array<Moments>;
+@group(0) @binding(2) var<storage, read> in: array<f32>;
+// Accumulator, kept between runs.
+@group(0) @binding(3) var<storage, read_write> out: Moments;
+
+var<workgroup> partial: array<Moments, WORKGROUP_SIZE>;
+
+// Moments of no value, infinities have no WGSL literal.
+fn empty() -> Moments {
+    return Moments(0u, 0.0, 0.0, 0.0, bitcast<f32>(0x7f800000u), bitcast<f32>(0xff800000u));
+}
+
+// Merges the moments of two disjoint sets of values.
+fn merge(a: Moments, b: Moments) -> Moments {
+    if a.count == 0u {
+        return b;
+    }
+    if b.count == 0u {
+        return a;
+    }
+    let count = a.count + b.count;
+    let n = f32(count);
+    let delta = b.mean - a.mean;
+    return Moments(
+        count,
+        a.sum + b.sum,
+        a.mean + delta * f32(b.count) / n,
+        a.m2 + b.m2 + delta * delta * f32(a.count) * f32(b.count) / n,
+        min(a.min, b.min),
+        max(a.max, b.max),
+    );
+}
+
+// Merges `partial` into `partial[0]`.
+fn reduce_workgroup(local: u32) {
+    for (var stride = WORKGROUP_SIZE / 2u; stride > 0u; stride /= 2u) {
+        workgroupBarrier();
+        if local < stride {
+            partial[local] = merge(partial[local], partial[local + stride]);
+        }
+    }
+    workgroupBarrier();
+}
+
+// Accumulates a strided part of the batch per invocation with Welford's algorithm, then merges the invocations of the workgroup.
+@compute
+@workgroup_size(256, 1, 1)
+fn partial_moments(
+    @builtin(local_invocation_index) local: u32,
+    @builtin(workgroup_id) workgroup: vec3<u32>,
+    @builtin(num_workgroups) workgroups: vec3<u32>,
+) {
+    var acc = empty();
+    let stride = workgroups.x * WORKGROUP_SIZE;
+    for (var i = workgroup.x * WORKGROUP_SIZE + local; i < params.len; i += stride) {
+        let x = in[i];
+        acc.count += 1u;
+        acc.sum += x;
+        let delta = x - acc.mean;
+        acc.mean += delta / f32(acc.count);
+        acc.m2 += delta * (x - acc.mean);
+        acc.min = min(acc.min, x);
+        acc.max = max(acc.max, x);
+    }
+    partial[local] = acc;
+    reduce_workgroup(local);
+    if local == 0u {
+        scratchpad[workgroup.x] = partial[0];
+    }
+}
+
+// Merges the moments of the workgroups, which are at most `WORKGROUP_SIZE`, into the accumulator.
+@compute
+@workgroup_size(256, 1, 1)
+fn accumulate(@builtin(local_invocation_index) local: u32) {
+    let partials = min(WORKGROUP_SIZE, (params.len + WORKGROUP_SIZE - 1u) / WORKGROUP_SIZE);
+    partial[local] = empty();
+    if local < partials {
+        partial[local] = scratchpad[local];
+    }
+    reduce_workgroup(local);
+    if local == 0u {
+        var acc = out;
+        if params.reset != 0u {
+            acc = empty();
+        }
+        out = merge(acc, partial[0]);
+    }
+}
@@ -0,0 +1,75 @@
+struct Params {
+    exposure: f32,
+}
+
+const BINS: u32 = 256u;
+const CDF: u32 = 256u;
+const CDF_MIN: u32 = 512u;
+
+@group(0) @binding(0) var<uniform> params: Params;
+// Histogram in [0, 256), cumulative histogram in [256, 512) and its first non-zero value at 512.
+@group(0) @binding(1) var<storage, read_write> scratchpad: array<atomic<u32>>;
+@group(0) @binding(2) var<storage, read> in: array<f32>;
+// Four u8 pixels are packed in each u32.
+@group(0) @binding(3) var<storage, read_write> out: array<u32>;
+
+/// Reinhard tone mapping of the exposed value to [0, 1).
+fn tonemap(v: f32) -> f32 {
+    let exposed = max(v, 0.0) * params.exposure;
+    return exposed / (1.0 + exposed);
+}
+
+fn bin(v: f32) -> u32 {
+    return min(u32(tonemap(v) * f32(BINS)), BINS - 1u);
+}
+
+@compute
+@workgroup_size(256, 1, 1)
+fn clear(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= arrayLength(&scratchpad) {
+        return;
+    }
+    atomicStore(&scratchpad[id.x], 0u);
+}
+
+@compute
+@workgroup_size(256, 1, 1)
+fn histogram(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= arrayLength(&in) {
+        return;
+    }
+    atomicAdd(&scratchpad[bin(in[id.x])], 1u);
+}
+
+@compute
+@workgroup_size(1, 1, 1)
+fn cdf() {
+    var total = 0u;
+    var cdf_min = 0u;
+    for (var i = 0u; i < BINS; i++) {
+        total += atomicLoad(&scratchpad[i]);
+        atomicStore(&scratchpad[CDF + i], total);
+        if cdf_min == 0u {
+            cdf_min = total;
+        }
+    }
+    atomicStore(&scratchpad[CDF_MIN], cdf_min);
+}
+
+fn equalize(v: f32) -> u32 {
+    let cdf_min = atomicLoad(&scratchpad[CDF_MIN]);
+    let n = arrayLength(&in);
+    let range = f32(max(n - cdf_min, 1u));
+    let value = f32(atomicLoad(&scratchpad[CDF + bin(v)]) - cdf_min) / range;
+    return u32(round(value * 255.0));
+}
+
+@compute
+@workgroup_size(64, 1, 1)
+fn map(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= arrayLength(&out) {
+        return;
+    }
+    let base = 4u * id.x;
+    out[id.x] = equalize(in[base]) | (equalize(in[base + 1u]) << 8u) | (equalize(in[base + 2u]) << 16u) | (equalize(in[base + 3u]) << 24u);
+}
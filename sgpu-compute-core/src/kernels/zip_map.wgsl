@@ -0,0 +1,21 @@
+// Preceded by the declaration of `zip_element` generated by `shader`.
+
+const WORKGROUP_SIZE: u32 = 256u;
+
+@group(0) @binding(0) var<uniform> len: u32;
+// The `len` elements of `a` followed by the ones of `b`.
+@group(0) @binding(1) var<storage, read> in: array<f32>;
+@group(0) @binding(2) var<storage, read_write> out: array<f32>;
+
+// Combines the elements of `a` and `b` at the same index, looping over the elements.
+@compute
+@workgroup_size(256, 1, 1)
+fn zip_map(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) workgroups: vec3<u32>,
+) {
+    let stride = workgroups.x * WORKGROUP_SIZE;
+    for (var i = id.x; i < len; i += stride) {
+        out[i] = zip_element(in[i], in[len + i]);
+    }
+}
@@ -0,0 +1,118 @@
+//! Buffers bound by a pipeline and bindings declared by its shaders.
+//!
+//! The buffers are bound to `@group(0)` in this order: the uniform, the scratchpad, the named scratchpads, the input, the output and the extra outputs. The uniform and the input are skipped when they are empty, and the scratchpad when there is none, so the following bindings are shifted down.
+use crate::ScratchpadDesc;
+use alloc::{string::String, vec::Vec};
+use core::{fmt, num::NonZeroUsize};
+
+/// Role of a buffer bound by the pipeline.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BufferRole {
+    Uniform,
+    Scratchpad,
+    Input,
+    Output,
+}
+
+/// A buffer bound by the pipeline, in binding order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BufferDescription {
+    pub binding: u32,
+    pub role: BufferRole,
+    /// Size of the buffer in bytes.
+    pub size: usize,
+}
+
+/// Kind of a binding declared by a shader.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BindingKind {
+    Uniform,
+    Storage {
+        read_only: bool,
+    },
+    /// Textures and samplers, which are not bound by the pipeline.
+    Handle,
+}
+
+/// A binding declared by the shader of a stage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindingDescription {
+    pub group: u32,
+    pub binding: u32,
+    pub name: Option<String>,
+    pub kind: BindingKind,
+    /// Minimum size in bytes of the buffer bound to it: the size of its type or, if it ends with a runtime-sized array, the size of its fixed part and of one element. 0 for handles.
+    pub min_size: usize,
+    /// Whether its type ends with a runtime-sized array, in which case it can be bound to any buffer of at least `min_size` bytes.
+    pub runtime_sized: bool,
+}
+
+/// Sizes in bytes of the buffers of a pipeline, a size of zero skips the binding of the uniform or of the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BufferSizes {
+    pub uniform: usize,
+    pub scratchpad: Option<NonZeroUsize>,
+    /// Scratchpads bound after the scratchpad, in order.
+    pub scratchpads: Vec<ScratchpadDesc>,
+    pub input: usize,
+    pub output: usize,
+    /// Outputs bound after the output, each one is read back with it.
+    pub extra_outputs: Vec<usize>,
+    /// Size of the push constants of the stages, 0 if they have none. They are not bound to the group 0.
+    pub push_constants: usize,
+}
+
+impl BufferSizes {
+    /// Sizes of the buffers of a pipeline with these types.
+    pub fn of<Input, Uniform, Output>(scratchpad: Option<NonZeroUsize>) -> Self {
+        Self {
+            uniform: core::mem::size_of::<Uniform>(),
+            scratchpad,
+            scratchpads: Vec::new(),
+            input: core::mem::size_of::<Input>(),
+            output: core::mem::size_of::<Output>(),
+            extra_outputs: Vec::new(),
+            push_constants: 0,
+        }
+    }
+}
+
+/// Buffers bound to the group 0 for the given sizes, in the order of their bindings.
+pub fn buffer_layout(sizes: &BufferSizes) -> Vec<BufferDescription> {
+    let scratchpads = sizes
+        .scratchpad
+        .into_iter()
+        .chain(sizes.scratchpads.iter().map(|scratchpad| scratchpad.size))
+        .map(|size| (BufferRole::Scratchpad, size.get()));
+    let extra_outputs = sizes
+        .extra_outputs
+        .iter()
+        .map(|&size| (BufferRole::Output, size));
+    [(BufferRole::Uniform, sizes.uniform)]
+        .into_iter()
+        .chain(scratchpads)
+        .chain([
+            (BufferRole::Input, sizes.input),
+            (BufferRole::Output, sizes.output),
+        ])
+        .filter(|(role, size)| *role == BufferRole::Output || *size > 0)
+        .chain(extra_outputs)
+        .enumerate()
+        .map(|(binding, (role, size))| BufferDescription {
+            binding: binding as u32,
+            role,
+            size,
+        })
+        .collect()
+}
+
+impl fmt::Display for BindingKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BindingKind::Uniform => write!(f, "var<uniform>"),
+            BindingKind::Storage { read_only: true } => write!(f, "var<storage, read>"),
+            BindingKind::Storage { read_only: false } => write!(f, "var<storage, read_write>"),
+            BindingKind::Handle => write!(f, "a texture or a sampler"),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! # SGPU-Compute core
+//! The types describing the pipelines of `sgpu-compute` and the WGSL sources of its kernels, without wgpu. They can be shared by alternative executors, e.g. a CPU reference implementation, and by code generation tools.
+//!
+//! The crate is `no_std`, it only needs `alloc`.
+//!
+//! ```
+//! use sgpu_compute_core::layout::{buffer_layout, BufferRole, BufferSizes};
+//!
+//! let buffers = buffer_layout(&BufferSizes::of::<[u32; 64], (), [u32; 64]>(None));
+//! assert_eq!(buffers.len(), 2);
+//! assert_eq!(buffers[0].role, BufferRole::Input);
+//! assert_eq!(buffers[1].binding, 1);
+//! ```
+#![no_std]
+
+extern crate alloc;
+
+pub mod kernels;
+pub mod layout;
+
+/// A stage of a pipeline: the WGSL source, or the key given to a shader source provider, and the entry point to run. The name is used in the labels, in the traces and in the error messages.
+///
+/// `constants` sets the `override` declarations of the shader, e.g. `&[("WORKGROUP_SIZE", 128.0)]`, so the same source can be specialized per pipeline. They are passed to the compute pipeline like the constants of WebGPU: an override with an `@id` is set by its id, the others by their name. The overrides which aren't set keep their default value.
+///
+/// `source` is the format of the shader, `ShaderSource::Wgsl` for the WGSL source or the key in `shader`, a SPIR-V binary, e.g. compiled offline from HLSL, or a GLSL compute shader. For the GLSL shaders, `shader` is only a label, used like the name of the stage.
+///
+/// The struct is non-exhaustive, so that new options of the stages don't break the code building them: a stage is built with `StageDesc::new` and the `with_*` methods, e.g. `StageDesc::new(shader, "main").with_name("norm")`.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct StageDesc {
+    pub name: Option<&'static str>,
+    pub shader: &'static str,
+    pub entrypoint: &'static str,
+    pub constants: &'static [(&'static str, f64)],
+    pub source: ShaderSource,
+}
+
+impl StageDesc {
+    /// Stage running `entrypoint` of the WGSL `shader`, without name nor constants.
+    pub const fn new(shader: &'static str, entrypoint: &'static str) -> Self {
+        StageDesc {
+            name: None,
+            shader,
+            entrypoint,
+            constants: &[],
+            source: ShaderSource::Wgsl,
+        }
+    }
+
+    /// Sets the name of the stage.
+    pub const fn with_name(mut self, name: &'static str) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    /// Sets the values of the `override` declarations of the shader.
+    pub const fn with_constants(mut self, constants: &'static [(&'static str, f64)]) -> Self {
+        self.constants = constants;
+        self
+    }
+
+    /// Sets the format of the shader.
+    pub const fn with_source(mut self, source: ShaderSource) -> Self {
+        self.source = source;
+        self
+    }
+}
+
+/// Format of the shader of a stage, see `StageDesc`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ShaderSource {
+    /// The `shader` of the stage is the WGSL source, or the key given to a shader source provider.
+    Wgsl,
+    /// SPIR-V binary, as words. It is only accepted by `sgpu-compute` with its `spirv` feature.
+    SpirV(&'static [u32]),
+    /// GLSL compute shader, e.g. a `.comp` shader of an OpenGL codebase, whose entry point is `main`. The `shader` of the stage is then only a label, e.g. the name of the file. It is only accepted by `sgpu-compute` with its `glsl` feature.
+    Glsl { src: &'static str },
+}
+
+/// A named scratchpad of a pipeline with several scratchpads, e.g. the keys and the histogram of a radix sort. The name is used in the labels of the buffer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ScratchpadDesc {
+    pub name: Option<&'static str>,
+    pub size: core::num::NonZeroUsize,
+}
@@ -0,0 +1,109 @@
+//! # SGPU-Compute derive
+//! The `BindingSet` derive macro of `sgpu-compute`, see the `sgpu_compute::binding` module. It is re-exported by `sgpu-compute`, this crate shouldn't be used directly.
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{parse_macro_input, spanned::Spanned, Data, DeriveInput, Fields, Ident};
+
+/// Implements `sgpu_compute::binding::BindingSet` for a struct with named fields, each field is bound in the order of declaration with the role given by its `#[binding(uniform | input | storage | output)]` attribute.
+#[proc_macro_derive(BindingSet, attributes(binding))]
+pub fn derive_binding_set(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new(
+                    input.ident.span(),
+                    "`BindingSet` can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "`BindingSet` can only be derived for structs",
+            ))
+        }
+    };
+    let mut bindings = Vec::new();
+    let mut idents = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().expect("The fields are named");
+        let role = role(field)?;
+        let ty = &field.ty;
+        let name = ident.to_string();
+        bindings.push(quote! {
+            ::sgpu_compute::binding::Binding {
+                name: #name,
+                role: ::sgpu_compute::binding::BindingRole::#role,
+                size: ::core::mem::size_of::<#ty>(),
+            }
+        });
+        idents.push(ident);
+    }
+    let indices = (0..idents.len()).collect::<Vec<_>>();
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::sgpu_compute::binding::BindingSet for #name #ty_generics #where_clause {
+            fn bindings() -> ::std::vec::Vec<::sgpu_compute::binding::Binding> {
+                ::std::vec![#(#bindings),*]
+            }
+
+            fn bytes(&self, index: usize) -> &[u8] {
+                match index {
+                    #(#indices => ::sgpu_compute::binding::bytes_of(&self.#idents),)*
+                    _ => panic!("No binding at index {}", index),
+                }
+            }
+
+            fn bytes_mut(&mut self, index: usize) -> &mut [u8] {
+                match index {
+                    #(#indices => ::sgpu_compute::binding::bytes_of_mut(&mut self.#idents),)*
+                    _ => panic!("No binding at index {}", index),
+                }
+            }
+        }
+    })
+}
+
+/// Variant of `BindingRole` given by the `#[binding(...)]` attribute of `field`.
+fn role(field: &syn::Field) -> syn::Result<Ident> {
+    let mut roles = field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("binding"));
+    let Some(attr) = roles.next() else {
+        return Err(syn::Error::new(
+            field.span(),
+            "missing `#[binding(uniform | input | storage | output)]` attribute",
+        ));
+    };
+    if let Some(attr) = roles.next() {
+        return Err(syn::Error::new(
+            attr.span(),
+            "a field can only have one `#[binding(...)]` attribute",
+        ));
+    }
+    let role: Ident = attr.parse_args()?;
+    let variant = match role.to_string().as_str() {
+        "uniform" => "Uniform",
+        "input" => "Input",
+        "storage" => "Storage",
+        "output" => "Output",
+        _ => {
+            return Err(syn::Error::new(
+                role.span(),
+                "expected `uniform`, `input`, `storage` or `output`",
+            ))
+        }
+    };
+    Ok(Ident::new(variant, Span::call_site()))
+}
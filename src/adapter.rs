@@ -0,0 +1,65 @@
+//! Explicit selection of the adapter.
+//!
+//! `GpuComputeAsync::new` lets wgpu pick a high-performance adapter, which is not always the expected one on a machine with an integrated and a discrete GPU. `GpuComputeAsync::enumerate_adapters` lists the adapters of all the backends, in a stable order for a given machine, and `GpuComputeAsync::with_adapter` creates the device on the adapter selected by its index in this list or by a predicate on its `wgpu::AdapterInfo`.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//!
+//! for (index, info) in GpuCompute::enumerate_adapters().iter().enumerate() {
+//!     println!("{}: {} ({:?}, {:?})", index, info.name, info.backend, info.device_type);
+//! }
+//! // Prefer a discrete GPU, and fall back to the first adapter.
+//! let gpu = GpuCompute::with_adapter(
+//!     |info: &wgpu::AdapterInfo| info.device_type == wgpu::DeviceType::DiscreteGpu,
+//!     GpuComputeOptions::default(),
+//! )
+//! .or_else(|_| GpuCompute::with_adapter(0, GpuComputeOptions::default()))
+//! .unwrap();
+//! ```
+use crate::{error::SgpuError, options::GpuComputeOptions, GpuComputeAsync};
+
+/// Selection of an adapter among the ones returned by `GpuComputeAsync::enumerate_adapters`, by index or by a predicate on its info.
+pub trait AdapterSelector {
+    /// Whether the adapter at `index` with the given info is selected. The first selected adapter is used.
+    fn select(&self, index: usize, info: &wgpu::AdapterInfo) -> bool;
+}
+
+impl AdapterSelector for usize {
+    #[inline]
+    fn select(&self, index: usize, _: &wgpu::AdapterInfo) -> bool {
+        *self == index
+    }
+}
+
+impl<F: Fn(&wgpu::AdapterInfo) -> bool> AdapterSelector for F {
+    #[inline]
+    fn select(&self, _: usize, info: &wgpu::AdapterInfo) -> bool {
+        self(info)
+    }
+}
+
+impl GpuComputeAsync {
+    /// This method is used to list the adapters of all the backends, the index of an adapter in this list can be given to `GpuComputeAsync::with_adapter`.
+    pub fn enumerate_adapters() -> Vec<wgpu::AdapterInfo> {
+        let instance = wgpu::Instance::default();
+        let adapters = instance.enumerate_adapters(wgpu::Backends::all());
+        adapters.iter().map(wgpu::Adapter::get_info).collect()
+    }
+
+    /// Same as `GpuComputeAsync::try_with_options`, but the device is created on the first adapter selected by `selector`. Returns `SgpuError::NoAdapter` if no adapter is selected.
+    pub async fn with_adapter(
+        selector: impl AdapterSelector,
+        options: GpuComputeOptions,
+    ) -> Result<Self, SgpuError> {
+        // The adapters must be dropped before their instance.
+        let instance = wgpu::Instance::default();
+        let adapter = instance
+            .enumerate_adapters(wgpu::Backends::all())
+            .into_iter()
+            .enumerate()
+            .find(|(index, adapter)| selector.select(*index, &adapter.get_info()))
+            .map(|(_, adapter)| adapter)
+            .ok_or(SgpuError::NoAdapter)?;
+        Self::from_adapter(&adapter, &Self::builder().options(options)).await
+    }
+}
@@ -0,0 +1,192 @@
+//! Scratchpads of several pipelines sharing the same allocations.
+//!
+//! In a compute graph running pipelines one after the other, the scratchpad of a pipeline often only has to keep its content while the pipeline is used, e.g. for the intermediate results of its stages. `GpuComputeAsync::alias_scratchpads` takes the steps of the graph during which each scratchpad is live and binds the scratchpads which are never live at the same time to the same buffer, which cuts the peak memory of the graph. The returned `AliasReport` lists the aliasing decisions.
+//!
+//! The scratchpads are placed from the largest to the smallest, each one in the first allocation in which no other scratchpad is live during its steps, so an allocation has the size of its first scratchpad. An aliased scratchpad starts each of its steps with the content left by the previous scratchpad of its allocation.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//!
+//! let shader = "
+//!     @group(0) @binding(0) var<storage, read_write> tmp: array<u32, 256>;
+//!     @group(0) @binding(1) var<storage, read> in: array<u32, 4>;
+//!     @group(0) @binding(2) var<storage, read_write> out: array<u32, 4>;
+//!
+//!     @compute @workgroup_size(4)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         tmp[id.x] = in[id.x] * 2u;
+//!         out[id.x] = tmp[id.x] + 1u;
+//!     }
+//! ";
+//! let stages = [StageDesc::new(shader, "main")];
+//! let gpu = GpuCompute::new();
+//! let size = NonZeroUsize::new(1024);
+//! let mut first = gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(size, stages.clone());
+//! let mut second = gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(size, stages);
+//! // The first pipeline runs at step 0 and the second one at step 1.
+//! let report = gpu.alias_scratchpads(&mut [(&mut *first, 0..=0), (&mut *second, 1..=1)]);
+//! assert_eq!(report.allocations().len(), 1);
+//! assert_eq!(report.saved(), 1024);
+//! let out = first.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out);
+//! assert_eq!(second.run(&out, [(1, 1, 1)], |out| *out), [7, 11, 15, 19]);
+//! ```
+use crate::{GpuComputeAsync, PipelineAsync};
+use std::{fmt, ops::RangeInclusive, sync::Arc};
+
+/// A pipeline whose scratchpad can be aliased, see the module documentation.
+pub trait AliasScratchpad {
+    /// Size of the scratchpad in bytes, `None` if there is none.
+    fn scratchpad_size(&self) -> Option<u64>;
+
+    /// Binds `buffer`, of at least `AliasScratchpad::scratchpad_size` bytes, as the scratchpad.
+    fn alias_scratchpad(&mut self, buffer: Arc<wgpu::Buffer>);
+}
+
+impl<Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod, const N: usize>
+    AliasScratchpad for PipelineAsync<'_, Input, Uniform, Output, N>
+{
+    #[inline]
+    fn scratchpad_size(&self) -> Option<u64> {
+        self.buffers.scratchpad.as_ref().map(|buffer| buffer.size())
+    }
+
+    fn alias_scratchpad(&mut self, buffer: Arc<wgpu::Buffer>) {
+        self.buffers.scratchpad = Some(buffer);
+        self.buffers
+            .rebind(&self.device, &self.stages.bindgroup_layout);
+    }
+}
+
+/// A buffer shared by scratchpads which are never live at the same time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Allocation {
+    /// Size of the buffer in bytes, the size of its largest scratchpad.
+    pub size: u64,
+    /// Indices of the scratchpads bound to the buffer in the slice given to `GpuComputeAsync::alias_scratchpads`, from the largest.
+    pub scratchpads: Vec<usize>,
+}
+
+/// Aliasing decisions of `GpuComputeAsync::alias_scratchpads`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AliasReport {
+    allocations: Vec<Allocation>,
+    /// Indices of the pipelines without scratchpad.
+    skipped: Vec<usize>,
+    /// Total size of the scratchpads before aliasing.
+    before: u64,
+}
+
+impl AliasReport {
+    /// The buffers shared by the scratchpads, from the largest.
+    #[inline]
+    pub fn allocations(&self) -> &[Allocation] {
+        &self.allocations
+    }
+
+    /// Indices of the pipelines left alone since they have no scratchpad.
+    #[inline]
+    pub fn skipped(&self) -> &[usize] {
+        &self.skipped
+    }
+
+    /// Total size in bytes of the scratchpads before aliasing.
+    #[inline]
+    pub fn size_before(&self) -> u64 {
+        self.before
+    }
+
+    /// Total size in bytes of the allocations.
+    pub fn size_after(&self) -> u64 {
+        self.allocations
+            .iter()
+            .map(|allocation| allocation.size)
+            .sum()
+    }
+
+    /// Number of bytes saved by the aliasing.
+    #[inline]
+    pub fn saved(&self) -> u64 {
+        self.before - self.size_after()
+    }
+}
+
+impl fmt::Display for AliasReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, allocation) in self.allocations.iter().enumerate() {
+            writeln!(
+                f,
+                "allocation {} ({} bytes): scratchpads {:?}",
+                i, allocation.size, allocation.scratchpads
+            )?;
+        }
+        if !self.skipped.is_empty() {
+            writeln!(f, "without scratchpad: {:?}", self.skipped)?;
+        }
+        write!(
+            f,
+            "{} bytes instead of {} bytes, {} bytes saved",
+            self.size_after(),
+            self.before,
+            self.saved()
+        )
+    }
+}
+
+impl GpuComputeAsync {
+    /// This method is used to bind the scratchpads which are never live at the same time to the same buffers, see the `alias` module. Each pipeline is given with the steps of the compute graph during which its scratchpad is live, and the pipelines have to be created on this device.
+    pub fn alias_scratchpads(
+        &self,
+        scratchpads: &mut [(&mut dyn AliasScratchpad, RangeInclusive<usize>)],
+    ) -> AliasReport {
+        let mut skipped = Vec::new();
+        let mut sized = Vec::new();
+        for (i, (pipeline, _)) in scratchpads.iter().enumerate() {
+            match pipeline.scratchpad_size() {
+                Some(size) => sized.push((i, size)),
+                None => skipped.push(i),
+            }
+        }
+        // Largest first, the stable sort keeps the order of the slice for equal sizes.
+        sized.sort_by_key(|&(_, size)| std::cmp::Reverse(size));
+        let overlap = |a: &RangeInclusive<usize>, b: &RangeInclusive<usize>| {
+            a.start() <= b.end() && b.start() <= a.end()
+        };
+        let mut allocations: Vec<Allocation> = Vec::new();
+        for &(i, size) in &sized {
+            let steps = &scratchpads[i].1;
+            let free = allocations.iter_mut().find(|allocation| {
+                allocation
+                    .scratchpads
+                    .iter()
+                    .all(|&other| !overlap(steps, &scratchpads[other].1))
+            });
+            match free {
+                Some(allocation) => allocation.scratchpads.push(i),
+                None => allocations.push(Allocation {
+                    size,
+                    scratchpads: vec![i],
+                }),
+            }
+        }
+        for allocation in &allocations {
+            let buffer = Arc::new(self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Aliased scratchpad buffer"),
+                size: allocation.size,
+                usage: wgpu::BufferUsages::COPY_SRC
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            }));
+            for &i in &allocation.scratchpads {
+                scratchpads[i].0.alias_scratchpad(Arc::clone(&buffer));
+            }
+        }
+        let report = AliasReport {
+            allocations,
+            skipped,
+            before: sized.iter().map(|&(_, size)| size).sum(),
+        };
+        crate::diagnostics::log_debug!("Aliased scratchpads:\n{}", report);
+        report
+    }
+}
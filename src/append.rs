@@ -0,0 +1,284 @@
+//! Pipelines whose output length is only known once the stages have run.
+//!
+//! A filter, a stream compaction or a collision detection appends a number of values which depends on the data. `GpuComputeAsync::gen_pipeline_append` binds a counter after the output, a `var<storage, read_write> len: atomic<u32>` which the stages increment with `atomicAdd` to reserve a slot in the output. `AppendPipelineAsync::run` clears the counter, runs the stages, reads the counter back and then only reads back the values which were appended.
+//!
+//! The stages must not write past `arrayLength` of the output, but they keep counting the values which didn't fit. When the count exceeds the capacity of the output, the output is reallocated to fit it and the stages run again on the same input, so the result is complete. `AppendPipelineAsync::set_growable` disables the growth, the values which didn't fit are then dropped and counted by `AppendPipelineAsync::overflow`.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//!
+//! let shader = "
+//!     @group(0) @binding(0) var<storage, read> in: array<u32>;
+//!     @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+//!     @group(0) @binding(2) var<storage, read_write> len: atomic<u32>;
+//!
+//!     @compute @workgroup_size(64)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         if id.x < arrayLength(&in) && in[id.x] % 3u == 0u {
+//!             let i = atomicAdd(&len, 1u);
+//!             if i < arrayLength(&out) {
+//!                 out[i] = in[id.x];
+//!             }
+//!         }
+//!     }
+//! ";
+//! let gpu = GpuCompute::new();
+//! // Room for 16 values, the output grows on the first run.
+//! let mut pipeline = gpu.gen_pipeline_append::<u32, (), u32, 1>(1000, 16, None, [StageDesc::new(shader, "main")]);
+//! let input = (0..1000).collect::<Vec<u32>>();
+//! let mut multiples = pipeline.run(&input, [(16, 1, 1)]);
+//! multiples.sort();
+//! assert_eq!(multiples, (0..1000).step_by(3).collect::<Vec<_>>());
+//! assert_eq!(pipeline.capacity(), 334);
+//! ```
+use crate::{
+    error::SgpuError, provider::ShaderSourceProvider, BufferSizes, Buffers, CompiledStages,
+    GpuComputeAsync, GpuRef, StageDesc,
+};
+use std::{marker::PhantomData, num::NonZeroUsize, sync::Arc};
+
+/// Size in bytes of the counter bound after the output.
+const COUNTER_SIZE: usize = std::mem::size_of::<u32>();
+
+/// Pipeline with an input of `input_len` values of `T`, a uniform `U` and an output of a number of values of `V` counted by the stages, see the module documentation.
+pub struct AppendPipelineAsync<
+    'a,
+    T: bytemuck::Pod,
+    U: bytemuck::Pod,
+    V: bytemuck::Pod,
+    const N: usize,
+> {
+    buffers: Buffers,
+    stages: CompiledStages<N>,
+    input_len: usize,
+    capacity: usize,
+    growable: bool,
+    overflow: usize,
+    device: GpuRef<'a>,
+    _phantom: PhantomData<(T, U, V)>,
+}
+
+impl GpuComputeAsync {
+    /// Same as `GpuComputeAsync::gen_pipeline_dyn`, but the output has room for `capacity` values of `V` and is followed by the counter of the values appended by the stages, see the `append` module.
+    ///
+    /// # Panics
+    /// If `capacity` is zero, if the size in bytes of the input or of `V` is not a multiple of 4, or in the same cases as `GpuComputeAsync::gen_pipeline`.
+    pub async fn gen_pipeline_append<
+        T: bytemuck::Pod,
+        U: bytemuck::Pod,
+        V: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        input_len: usize,
+        capacity: usize,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> AppendPipelineAsync<'_, T, U, V, N> {
+        self.try_gen_pipeline_append(input_len, capacity, scratchpad_size, stages)
+            .await
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Same as `GpuComputeAsync::gen_pipeline_append`, but the errors of the shaders and of the lengths are returned instead of panicking.
+    pub async fn try_gen_pipeline_append<
+        T: bytemuck::Pod,
+        U: bytemuck::Pod,
+        V: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        input_len: usize,
+        capacity: usize,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> Result<AppendPipelineAsync<'_, T, U, V, N>, SgpuError> {
+        if !std::mem::size_of::<V>().is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT as usize) {
+            return Err(SgpuError::LayoutMismatch(format!(
+                "the values of the output are {} bytes, it must be a multiple of {} bytes",
+                std::mem::size_of::<V>(),
+                wgpu::COPY_BUFFER_ALIGNMENT
+            )));
+        }
+        let sizes = append_sizes::<T, U, V>(input_len, capacity, scratchpad_size);
+        crate::dynamic::check_sizes(&sizes)?;
+        let provider: Option<Arc<dyn ShaderSourceProvider>> = None;
+        let stages = self.compile_stages(&sizes, stages, provider).await?;
+        let buffers = self.create_buffers(&sizes, &stages.bindgroup_layout, self.zero_init);
+        Ok(AppendPipelineAsync {
+            buffers,
+            stages,
+            input_len,
+            capacity,
+            growable: true,
+            overflow: 0,
+            device: GpuRef::Borrowed(self),
+            _phantom: PhantomData,
+        })
+    }
+}
+
+/// Sizes of the buffers of an append pipeline, the counter is the only extra output.
+fn append_sizes<T, U, V>(
+    input_len: usize,
+    capacity: usize,
+    scratchpad: Option<NonZeroUsize>,
+) -> BufferSizes {
+    BufferSizes {
+        uniform: std::mem::size_of::<U>(),
+        scratchpad,
+        scratchpads: Vec::new(),
+        input: input_len * std::mem::size_of::<T>(),
+        output: capacity * std::mem::size_of::<V>(),
+        extra_outputs: vec![COUNTER_SIZE],
+        push_constants: 0,
+    }
+}
+
+impl<T: bytemuck::Pod, U: bytemuck::Pod, V: bytemuck::Pod, const N: usize>
+    AppendPipelineAsync<'_, T, U, V, N>
+{
+    /// Number of values of `T` expected by `AppendPipelineAsync::run`.
+    #[inline]
+    pub fn input_len(&self) -> usize {
+        self.input_len
+    }
+
+    /// Number of values of `V` the output has room for, it grows when the stages append more values.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// This method is used to choose whether the output grows when the stages append more values than its capacity, which is the default. Otherwise, the values which didn't fit are dropped.
+    #[inline]
+    pub fn set_growable(&mut self, growable: bool) {
+        self.growable = growable;
+    }
+
+    /// Number of values dropped by the last run because they didn't fit in the output, always 0 when the output is growable.
+    #[inline]
+    pub fn overflow(&self) -> usize {
+        self.overflow
+    }
+
+    /// This method is used to write the uniform buffer. It is useful to change the uniform between runs.
+    #[inline]
+    pub fn write_uniform(&mut self, uniform: &U) {
+        self.device.queue.write_buffer(
+            self.buffers.uniform.as_ref().expect("No uniforms"),
+            0,
+            bytemuck::bytes_of(uniform),
+        )
+    }
+
+    /// This method is used to run the stages on `input` and read back the values they appended, in the order of their slots. If they appended more values than the capacity of the output, the output grows and the stages run again, unless the growth is disabled by `AppendPipelineAsync::set_growable`.
+    ///
+    /// # Panics
+    /// If `input` doesn't have `AppendPipelineAsync::input_len` values.
+    pub async fn run(&mut self, input: &[T], workgroups: [(u32, u32, u32); N]) -> Vec<V> {
+        assert_eq!(
+            input.len(),
+            self.input_len,
+            "Expected {} input values, got {}",
+            self.input_len,
+            input.len()
+        );
+        if let Some(buffer) = &self.buffers.input {
+            self.device
+                .queue
+                .write_buffer(buffer, 0, bytemuck::cast_slice(input));
+        }
+        let mut len = self.count(workgroups).await;
+        if len > self.capacity && self.growable {
+            crate::diagnostics::log_debug!(
+                "The stages appended {} values, growing the output from {} values",
+                len,
+                self.capacity
+            );
+            self.grow(len);
+            len = self.count(workgroups).await;
+        }
+        self.overflow = len.saturating_sub(self.capacity);
+        self.read_values(len.min(self.capacity)).await
+    }
+
+    /// Clears the counter, runs the stages and returns the number of values they appended.
+    async fn count(&self, workgroups: [(u32, u32, u32); N]) -> usize {
+        let (counter, readback) = &self.buffers.extra_outputs[0];
+        let mut encoder =
+            self.device
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Append pipeline"),
+                });
+        encoder.clear_buffer(counter, 0, None);
+        self.stages.record_passes(
+            &mut encoder,
+            &self.buffers.bindgroup,
+            &self.device,
+            &workgroups,
+        );
+        encoder.copy_buffer_to_buffer(counter, 0, readback, 0, COUNTER_SIZE as u64);
+        self.device.queue.submit(Some(encoder.finish()));
+        let bytes = self.map_read(readback, COUNTER_SIZE as u64).await;
+        bytemuck::pod_read_unaligned::<u32>(&bytes) as usize
+    }
+
+    /// Reads back the first `len` values of the output.
+    async fn read_values(&self, len: usize) -> Vec<V> {
+        let size = (len * std::mem::size_of::<V>()) as u64;
+        if size == 0 {
+            return Vec::new();
+        }
+        let mut encoder = self
+            .device
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_buffer_to_buffer(&self.buffers.staging, 0, &self.buffers.output, 0, size);
+        self.device.queue.submit(Some(encoder.finish()));
+        let bytes = self.map_read(&self.buffers.output, size).await;
+        bytes
+            .chunks_exact(std::mem::size_of::<V>())
+            .map(bytemuck::pod_read_unaligned)
+            .collect()
+    }
+
+    /// Maps the first `size` bytes of `buffer` once the submitted work is done and copies them.
+    async fn map_read(&self, buffer: &wgpu::Buffer, size: u64) -> Vec<u8> {
+        let slice = buffer.slice(..size);
+        let (sender, receiver) = flume::bounded(1);
+        slice.map_async(wgpu::MapMode::Read, move |e| {
+            e.expect("Could not map buffer");
+            sender.send(()).unwrap()
+        });
+        self.device.wait_submitted();
+        receiver.recv_async().await.expect("Error with channel");
+        let bytes = slice.get_mapped_range().to_vec();
+        buffer.unmap();
+        bytes
+    }
+
+    /// Reallocates the output with room for `capacity` values, the uniform, the input and the scratchpad are kept.
+    fn grow(&mut self, capacity: usize) {
+        let sizes = append_sizes::<T, U, V>(
+            self.input_len,
+            capacity,
+            self.buffers
+                .scratchpad
+                .as_ref()
+                .and_then(|buffer| NonZeroUsize::new(buffer.size() as usize)),
+        );
+        let mut buffers = self.device.create_buffers(
+            &sizes,
+            &self.stages.bindgroup_layout,
+            crate::ZeroInit::Never,
+        );
+        std::mem::swap(&mut buffers.uniform, &mut self.buffers.uniform);
+        std::mem::swap(&mut buffers.input, &mut self.buffers.input);
+        std::mem::swap(&mut buffers.scratchpad, &mut self.buffers.scratchpad);
+        buffers.rebind(&self.device, &self.stages.bindgroup_layout);
+        self.buffers = buffers;
+        self.capacity = capacity;
+    }
+}
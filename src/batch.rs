@@ -0,0 +1,319 @@
+//! Batches of uniforms, each run of a batch binding its own uniform.
+//!
+//! A parameter sweep over hundreds of configurations would otherwise write the uniform and submit the stages once per configuration. `PipelineAsync::write_uniforms` uploads all the uniforms at once in a single buffer, each one aligned to `min_uniform_buffer_offset_alignment`, and `PipelineAsync::dispatch_batch` records one run of the stages per uniform in a single submission, the runs binding the uniform at their index with a dynamic offset. The stages are compiled again with a dynamic offset on the uniform binding on the first batch, the variant being shared by the clones of the pipeline.
+//!
+//! The runs of a batch are in order and share the buffers, so each run sees the scratchpad and the output left by the previous one, like consecutive `run`s. With `ZeroInit::BeforeEachRun`, the buffers are cleared before each run of the batch. `PipelineAsync::sweep` runs an input against each configuration of a batch independently and returns all their outputs.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//!
+//! let shader = "
+//!     @group(0) @binding(0) var<uniform> step: u32;
+//!     @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+//!
+//!     @compute @workgroup_size(4)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[id.x] += step;
+//!     }
+//! ";
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline::<(), u32, [u32; 4], 1>(None, [StageDesc::new(shader, "main")]);
+//! let steps: Vec<u32> = (1..=100).collect();
+//! pipeline.write_uniforms(&steps);
+//! assert_eq!(pipeline.run_batch(&(), [(1, 1, 1)], |out| *out), [5050; 4]);
+//! ```
+use crate::{shared, GpuComputeAsync, PipelineAsync, ZeroInit};
+use std::num::NonZeroU64;
+
+/// Variant of the stages of a pipeline binding the uniform with a dynamic offset.
+pub(crate) struct BatchedStages {
+    bindgroup_layout: wgpu::BindGroupLayout,
+    pipelines: Vec<wgpu::ComputePipeline>,
+}
+
+/// Buffer of the uniforms of a batch.
+pub(crate) struct UniformBatch {
+    buffer: wgpu::Buffer,
+    /// Distance between two uniforms in the buffer.
+    stride: u64,
+    len: usize,
+    /// Uniforms of the batch without padding, kept to write them again when the pipeline is rebuilt.
+    values: Vec<u8>,
+    /// Outputs of the runs of the last sweep, kept for the next ones.
+    readback: Option<wgpu::Buffer>,
+}
+
+impl<Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod, const N: usize>
+    PipelineAsync<'_, Input, Uniform, Output, N>
+{
+    /// This method is used to upload a batch of uniforms at once, the runs of `PipelineAsync::dispatch_batch` binding them in order. The buffer of the batch grows with the number of uniforms and is reused by smaller batches. The uniform of `write_uniform` is kept for the other runs.
+    ///
+    /// # Panics
+    /// If the uniform is zero-sized or if `uniforms` is empty.
+    pub fn write_uniforms(&mut self, uniforms: &[Uniform]) {
+        assert!(self.buffers.uniform.is_some(), "No uniforms");
+        assert!(!uniforms.is_empty(), "A batch needs at least one uniform");
+        let size = std::mem::size_of::<Uniform>() as u64;
+        let alignment = self
+            .device
+            .device
+            .limits()
+            .min_uniform_buffer_offset_alignment as u64;
+        let stride = size.next_multiple_of(alignment);
+        let capacity = self
+            .uniforms
+            .as_ref()
+            .map_or(0, |batch| batch.buffer.size() / batch.stride);
+        if capacity < uniforms.len() as u64 {
+            self.uniforms = Some(UniformBatch {
+                buffer: self.device.device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Uniform batch buffer"),
+                    size: stride * uniforms.len() as u64,
+                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+                    mapped_at_creation: false,
+                }),
+                stride,
+                len: 0,
+                values: Vec::new(),
+                readback: None,
+            });
+        }
+        let mut bytes = vec![0; stride as usize * uniforms.len()];
+        for (chunk, uniform) in bytes.chunks_exact_mut(stride as usize).zip(uniforms) {
+            chunk[..size as usize].copy_from_slice(bytemuck::bytes_of(uniform));
+        }
+        let batch = self.uniforms.as_mut().expect("Allocated above");
+        batch.len = uniforms.len();
+        batch.values = bytemuck::cast_slice(uniforms).to_vec();
+        self.device.queue.write_buffer(&batch.buffer, 0, &bytes);
+    }
+
+    /// Uniforms of the batch written with `PipelineAsync::write_uniforms`, `None` before the first one.
+    pub(crate) fn batch_uniforms(&self) -> Option<Vec<Uniform>> {
+        let batch = self.uniforms.as_ref()?;
+        Some(
+            batch
+                .values
+                .chunks_exact(std::mem::size_of::<Uniform>())
+                .map(bytemuck::pod_read_unaligned)
+                .collect(),
+        )
+    }
+
+    /// Number of uniforms of the batch written with `PipelineAsync::write_uniforms`, 0 before the first one.
+    #[inline]
+    pub fn batch_size(&self) -> usize {
+        self.uniforms.as_ref().map_or(0, |batch| batch.len)
+    }
+
+    /// This method is used to run the stages once per uniform of the batch, in a single submission and without reading the output back. The input is written once and read by all the runs.
+    ///
+    /// # Panics
+    /// If no batch was written with `PipelineAsync::write_uniforms`.
+    pub fn dispatch_batch(&mut self, input: &Input, workgroups: [(u32, u32, u32); N]) {
+        let clear = self.zero_init == ZeroInit::BeforeEachRun;
+        self.submit_batch(Some(input), workgroups, clear, |_, _| {});
+    }
+
+    /// Same as `PipelineAsync::dispatch_batch`, but `callback` gets the output left by the last run of the batch.
+    ///
+    /// # Panics
+    /// If no batch was written with `PipelineAsync::write_uniforms`.
+    pub async fn run_batch_async<T>(
+        &mut self,
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+        callback: impl FnOnce(&Output) -> T,
+    ) -> T {
+        self.dispatch_batch(input, workgroups);
+        self.read_output(callback).await
+    }
+
+    /// Blocking version of `PipelineAsync::run_batch_async`. It is enabled by the `blocking` feature.
+    #[cfg(feature = "blocking")]
+    #[inline]
+    pub fn run_batch<T>(
+        &mut self,
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+        callback: impl FnOnce(&Output) -> T,
+    ) -> T {
+        pollster::block_on(self.run_batch_async(input, workgroups, callback))
+    }
+
+    /// This method is used to run `input` against each configuration of `uniforms` and returns the output of each one, e.g. for a calibration or a hyperparameter search. The uniforms are written with `PipelineAsync::write_uniforms`, and the runs are submitted at once, the output of each one being copied into a readback buffer kept for the next sweeps. The buffers are cleared before each run, so the outputs don't depend on the order of the configurations.
+    ///
+    /// # Panics
+    /// If the uniform is zero-sized or if `uniforms` is empty.
+    pub async fn sweep_async(
+        &mut self,
+        uniforms: &[Uniform],
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+    ) -> Vec<Output> {
+        self.write_uniforms(uniforms);
+        let size = std::mem::size_of::<Output>() as u64;
+        let batch = self.uniforms.as_mut().expect("Written above");
+        if batch
+            .readback
+            .as_ref()
+            .is_none_or(|readback| readback.size() < size * uniforms.len() as u64)
+        {
+            batch.readback = Some(self.device.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Sweep readback buffer"),
+                size: size * uniforms.len() as u64,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }));
+        }
+        let readback = self
+            .uniforms
+            .as_ref()
+            .and_then(|batch| batch.readback.as_ref())
+            .expect("Allocated above");
+        let staging = &self.buffers.staging;
+        self.submit_batch(Some(input), workgroups, true, |encoder, run| {
+            encoder.copy_buffer_to_buffer(staging, 0, readback, run as u64 * size, size);
+        });
+        let slice = readback.slice(..size * uniforms.len() as u64);
+        let (sender, receiver) = flume::bounded(1);
+        slice.map_async(wgpu::MapMode::Read, move |e| {
+            e.expect("Could not map buffer");
+            sender.send(()).unwrap()
+        });
+        self.device.wait_submitted();
+        receiver.recv_async().await.expect("Error with channel");
+        let outputs = slice
+            .get_mapped_range()
+            .chunks_exact(size as usize)
+            .map(bytemuck::pod_read_unaligned)
+            .collect();
+        readback.unmap();
+        outputs
+    }
+
+    /// Blocking version of `PipelineAsync::sweep_async`. It is enabled by the `blocking` feature.
+    #[cfg(feature = "blocking")]
+    #[inline]
+    pub fn sweep(
+        &mut self,
+        uniforms: &[Uniform],
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+    ) -> Vec<Output> {
+        pollster::block_on(self.sweep_async(uniforms, input, workgroups))
+    }
+
+    /// Writes the input if there is one and submits one run of the stages per uniform of the batch, clearing the buffers before each run if `clear` is set. `after_run` records commands after the run at the given index, e.g. a copy of its output.
+    pub(crate) fn submit_batch(
+        &self,
+        input: Option<&Input>,
+        workgroups: [(u32, u32, u32); N],
+        clear: bool,
+        mut after_run: impl FnMut(&mut wgpu::CommandEncoder, usize),
+    ) -> wgpu::SubmissionIndex {
+        let batch = self
+            .uniforms
+            .as_ref()
+            .expect("No batch of uniforms, write one with `write_uniforms` first");
+        self.write_input(input);
+        let stages = &self.stages;
+        let batched = stages
+            .batched
+            .get_or_init(|| compile_batched(&self.device, &self.sizes, stages));
+        let buffers = &self.buffers;
+        let bindgroup_items = std::iter::once(wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+            buffer: &batch.buffer,
+            offset: 0,
+            size: NonZeroU64::new(std::mem::size_of::<Uniform>() as u64),
+        }))
+        .chain(
+            buffers
+                .scratchpad
+                .as_deref()
+                .into_iter()
+                .chain(&buffers.scratchpads)
+                .chain(&buffers.input)
+                .chain(std::iter::once(&buffers.staging))
+                .chain(buffers.extra_outputs.iter().map(|(staging, _)| staging))
+                .map(|buffer| wgpu::BindingResource::Buffer(buffer.as_entire_buffer_binding())),
+        )
+        .enumerate()
+        .map(|(i, resource)| wgpu::BindGroupEntry {
+            binding: i as _,
+            resource,
+        })
+        .collect::<Vec<_>>();
+        let bindgroup = self
+            .device
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Uniform batch bind group"),
+                layout: &batched.bindgroup_layout,
+                entries: &bindgroup_items,
+            });
+        let mut encoder =
+            self.device
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Uniform batch"),
+                });
+        for run in 0..batch.len {
+            if clear {
+                buffers.clear(&mut encoder, false);
+            }
+            let offset = (run as u64 * batch.stride) as u32;
+            for (i, workgroup) in workgroups.iter().enumerate() {
+                self.device
+                    .split_submission(&mut encoder, run * N + i, Some("Uniform batch"));
+                let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: stages.desc[i].name,
+                    timestamp_writes: None,
+                });
+                cpass.set_pipeline(&batched.pipelines[i]);
+                cpass.set_bind_group(0, &bindgroup, &[offset]);
+                if !self.push_constants.is_empty() {
+                    cpass.set_push_constants(0, &self.push_constants);
+                }
+                if stages.shared_uniform {
+                    cpass.set_bind_group(
+                        shared::GROUP,
+                        &self.device.shared_uniform().bindgroup,
+                        &[],
+                    );
+                }
+                cpass.dispatch_workgroups(workgroup.0, workgroup.1, workgroup.2);
+            }
+            after_run(&mut encoder, run);
+        }
+        self.device.queue.submit(Some(encoder.finish()))
+    }
+}
+
+/// Compiles the stages again with a dynamic offset on the uniform binding. Their sources were already checked when the pipeline was generated.
+fn compile_batched<const N: usize>(
+    gpu: &GpuComputeAsync,
+    sizes: &crate::BufferSizes,
+    stages: &crate::CompiledStages<N>,
+) -> BatchedStages {
+    let bindgroup_layout = gpu.bindgroup_layout(&crate::buffer_layout(sizes), true);
+    let pipelines = stages
+        .desc
+        .iter()
+        .map(|desc| {
+            let source = crate::describe::stage_source(desc, stages.provider.as_deref())
+                .unwrap_or_else(|error| panic!("{}", error));
+            gpu.compile_stage(
+                desc,
+                &source,
+                &bindgroup_layout,
+                stages.shared_uniform,
+                stages.push_constants,
+            )
+        })
+        .collect();
+    BatchedStages {
+        bindgroup_layout,
+        pipelines,
+    }
+}
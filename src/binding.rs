@@ -0,0 +1,260 @@
+//! Pipelines whose buffers are described by a Rust type.
+//!
+//! `PipelineAsync` binds a fixed set of buffers: a uniform, scratchpads, an input and outputs, in this order. A `BindingSet` describes the buffers of `@group(0)` with a struct instead, one binding per field in the order of declaration, and the role of each field is given by its `#[binding(...)]` attribute:
+//! - `uniform`: a `var<uniform>`, written before each run,
+//! - `input`: a `var<storage, read>`, written before each run,
+//! - `storage`: a `var<storage, read_write>` only used by the GPU, its content is kept between runs,
+//! - `output`: a `var<storage, read_write>`, read back into the field after each run.
+//!
+//! The fields must be `bytemuck::Pod` and their sizes a non-zero multiple of 4 bytes. `GpuComputeAsync::gen_bound_pipeline` checks the bindings declared by the stages against the set, like `GpuComputeAsync::gen_pipeline` does, and `BoundPipelineAsync::run` takes the set itself, so the buffers can't be mixed up.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//!
+//! #[derive(BindingSet)]
+//! struct Saxpy {
+//!     #[binding(uniform)]
+//!     a: f32,
+//!     #[binding(input)]
+//!     x: [f32; 64],
+//!     #[binding(input)]
+//!     y: [f32; 64],
+//!     #[binding(output)]
+//!     out: [f32; 64],
+//! }
+//!
+//! let shader = "
+//!     @group(0) @binding(0) var<uniform> a: f32;
+//!     @group(0) @binding(1) var<storage, read> x: array<f32, 64>;
+//!     @group(0) @binding(2) var<storage, read> y: array<f32, 64>;
+//!     @group(0) @binding(3) var<storage, read_write> out: array<f32, 64>;
+//!
+//!     @compute @workgroup_size(64)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[id.x] = a * x[id.x] + y[id.x];
+//!     }
+//! ";
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_bound_pipeline::<Saxpy, 1>([StageDesc::new(shader, "main")]);
+//! let mut set = Saxpy { a: 2.0, x: [1.0; 64], y: [3.0; 64], out: [0.0; 64] };
+//! pipeline.run(&mut set, [(1, 1, 1)]);
+//! assert_eq!(set.out, [5.0; 64]);
+//! ```
+use crate::{error::SgpuError, CompiledStages, GpuComputeAsync, GpuRef, StageDesc};
+use sgpu_compute_core::layout::{BufferDescription, BufferRole};
+use std::{marker::PhantomData, sync::Arc, sync::OnceLock};
+
+pub use bytemuck::{bytes_of, bytes_of_mut};
+pub use sgpu_compute_derive::BindingSet;
+
+/// Role of a binding of a `BindingSet`, see the module documentation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BindingRole {
+    Uniform,
+    Input,
+    Storage,
+    Output,
+}
+
+impl BindingRole {
+    /// Role of the buffer in the layouts of `sgpu_compute_core`.
+    fn buffer_role(self) -> BufferRole {
+        match self {
+            BindingRole::Uniform => BufferRole::Uniform,
+            BindingRole::Input => BufferRole::Input,
+            BindingRole::Storage => BufferRole::Scratchpad,
+            BindingRole::Output => BufferRole::Output,
+        }
+    }
+
+    /// Whether the field is written to its buffer before each run.
+    fn written_on_run(self) -> bool {
+        matches!(self, BindingRole::Uniform | BindingRole::Input)
+    }
+}
+
+/// A binding of a `BindingSet`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binding {
+    /// Name of the field.
+    pub name: &'static str,
+    pub role: BindingRole,
+    /// Size of the field in bytes.
+    pub size: usize,
+}
+
+/// Buffers of `@group(0)` described by a Rust type, see the module documentation. It is implemented with `#[derive(BindingSet)]`.
+pub trait BindingSet {
+    /// Bindings of the set, the binding at index `i` is bound to `@binding(i)`.
+    fn bindings() -> Vec<Binding>;
+
+    /// Bytes of the field of the binding at `index`.
+    fn bytes(&self, index: usize) -> &[u8];
+
+    /// Mutable bytes of the field of the binding at `index`.
+    fn bytes_mut(&mut self, index: usize) -> &mut [u8];
+}
+
+/// Pipeline binding the buffers of the set `B`, see the module documentation.
+pub struct BoundPipelineAsync<'a, B: BindingSet, const N: usize> {
+    bindings: Vec<Binding>,
+    /// Buffer of each binding, with the buffer its field is read back from for the outputs.
+    buffers: Vec<(wgpu::Buffer, Option<wgpu::Buffer>)>,
+    bindgroup: wgpu::BindGroup,
+    stages: CompiledStages<N>,
+    device: GpuRef<'a>,
+    _phantom: PhantomData<B>,
+}
+
+impl GpuComputeAsync {
+    /// Same as `GpuComputeAsync::gen_pipeline`, but the buffers are the fields of the binding set `B`, see the `binding` module.
+    ///
+    /// # Panics
+    /// If a field is empty or its size is not a multiple of 4 bytes, or in the same cases as `GpuComputeAsync::gen_pipeline`.
+    pub async fn gen_bound_pipeline<B: BindingSet, const N: usize>(
+        &self,
+        stages: [StageDesc; N],
+    ) -> BoundPipelineAsync<'_, B, N> {
+        self.try_gen_bound_pipeline(stages)
+            .await
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Same as `GpuComputeAsync::gen_bound_pipeline`, but the errors of the shaders and of the binding set are returned instead of panicking.
+    pub async fn try_gen_bound_pipeline<B: BindingSet, const N: usize>(
+        &self,
+        stages: [StageDesc; N],
+    ) -> Result<BoundPipelineAsync<'_, B, N>, SgpuError> {
+        let bindings = B::bindings();
+        for binding in &bindings {
+            if binding.size == 0
+                || !binding
+                    .size
+                    .is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT as usize)
+            {
+                return Err(SgpuError::LayoutMismatch(format!(
+                    "the binding `{}` is {} bytes, it must be a non-zero multiple of {} bytes",
+                    binding.name,
+                    binding.size,
+                    wgpu::COPY_BUFFER_ALIGNMENT
+                )));
+            }
+        }
+        let layout = bindings
+            .iter()
+            .enumerate()
+            .map(|(i, binding)| BufferDescription {
+                binding: i as u32,
+                role: binding.role.buffer_role(),
+                size: binding.size,
+            })
+            .collect::<Vec<_>>();
+        let (bindgroup_layout, pipelines, shared_uniform) = self
+            .compile_layout_stages(&layout, 0, &stages, None)
+            .await?;
+        let buffers = bindings
+            .iter()
+            .map(|binding| {
+                let usage = match binding.role {
+                    BindingRole::Uniform => wgpu::BufferUsages::UNIFORM,
+                    _ => wgpu::BufferUsages::STORAGE,
+                };
+                let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some(binding.name),
+                    size: binding.size as _,
+                    usage: usage | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+                let readback = (binding.role == BindingRole::Output).then(|| {
+                    self.device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some(binding.name),
+                        size: binding.size as _,
+                        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                        mapped_at_creation: false,
+                    })
+                });
+                (buffer, readback)
+            })
+            .collect::<Vec<_>>();
+        let bindgroup =
+            self.create_bindgroup(&bindgroup_layout, buffers.iter().map(|(buffer, _)| buffer));
+        Ok(BoundPipelineAsync {
+            bindings,
+            buffers,
+            bindgroup,
+            stages: CompiledStages {
+                bindgroup_layout: Arc::new(bindgroup_layout),
+                pipelines: pipelines.try_into().expect("Wrong length?"),
+                desc: stages,
+                provider: None,
+                shared_uniform,
+                push_constants: 0,
+                batched: OnceLock::new(),
+            },
+            device: GpuRef::Borrowed(self),
+            _phantom: PhantomData,
+        })
+    }
+}
+
+impl<B: BindingSet, const N: usize> BoundPipelineAsync<'_, B, N> {
+    /// Bindings of the set, in binding order.
+    #[inline]
+    pub fn bindings(&self) -> &[Binding] {
+        &self.bindings
+    }
+
+    /// This method is used to write every field of `set` to its buffer, including the storage and the outputs, e.g. to set the initial content of the storage.
+    pub fn write(&mut self, set: &B) {
+        for (i, (buffer, _)) in self.buffers.iter().enumerate() {
+            self.device.queue.write_buffer(buffer, 0, set.bytes(i));
+        }
+    }
+
+    /// This method is used to write the uniforms and the inputs of `set`, run the stages with the given workgroups and read the outputs back into `set`. The storage keeps its content between runs.
+    pub async fn run(&mut self, set: &mut B, workgroups: [(u32, u32, u32); N]) {
+        let queue = &self.device.queue;
+        for (i, binding) in self.bindings.iter().enumerate() {
+            if binding.role.written_on_run() {
+                queue.write_buffer(&self.buffers[i].0, 0, set.bytes(i));
+            }
+        }
+        let mut encoder =
+            self.device
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Bound pipeline"),
+                });
+        self.stages
+            .record_passes(&mut encoder, &self.bindgroup, &self.device, &workgroups);
+        for (buffer, readback) in &self.buffers {
+            if let Some(readback) = readback {
+                encoder.copy_buffer_to_buffer(buffer, 0, readback, 0, buffer.size());
+            }
+        }
+        queue.submit(Some(encoder.finish()));
+        let readbacks = self
+            .buffers
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (_, readback))| Some((i, readback.as_ref()?)))
+            .collect::<Vec<_>>();
+        let (sender, receiver) = flume::bounded(readbacks.len());
+        for (_, readback) in &readbacks {
+            let sender = sender.clone();
+            readback.slice(..).map_async(wgpu::MapMode::Read, move |e| {
+                e.expect("Could not map buffer");
+                sender.send(()).unwrap()
+            });
+        }
+        self.device.wait_submitted();
+        for _ in &readbacks {
+            receiver.recv_async().await.expect("Error with channel");
+        }
+        for (i, readback) in readbacks {
+            set.bytes_mut(i)
+                .copy_from_slice(&readback.slice(..).get_mapped_range());
+            readback.unmap();
+        }
+    }
+}
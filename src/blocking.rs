@@ -1,8 +1,16 @@
-use crate::*;
+use crate::{
+    append::AppendPipelineAsync,
+    binding::{BindingSet, BoundPipelineAsync},
+    dynamic::DynPipelineAsync,
+    graph::{ComputeGraph, GraphPipelineAsync},
+    multi::{MultiPipelineAsync, Outputs},
+    pool::PipelinePool,
+    *,
+};
 use std::ops::{Deref, DerefMut};
 
 /// This is a blocking version of `GpuComputeAsync`. It is enabled by the `blocking` feature. This feature is enabled by default.
-pub struct GpuCompute(GpuComputeAsync);
+pub struct GpuCompute(pub(crate) GpuComputeAsync);
 
 impl GpuCompute {
     /// Blocking version of `GpuComputeAsync::new`.
@@ -11,6 +19,66 @@ impl GpuCompute {
         Self(pollster::block_on(GpuComputeAsync::new()))
     }
 
+    /// Blocking version of `GpuComputeAsync::with_options`.
+    #[inline]
+    pub fn with_options(options: options::GpuComputeOptions) -> Self {
+        Self(pollster::block_on(GpuComputeAsync::with_options(options)))
+    }
+
+    /// Blocking version of `GpuComputeAsync::try_new`.
+    #[inline]
+    pub fn try_new() -> Result<Self, SgpuError> {
+        pollster::block_on(GpuComputeAsync::try_new()).map(Self)
+    }
+
+    /// Blocking version of `GpuComputeAsync::try_with_options`.
+    #[inline]
+    pub fn try_with_options(options: options::GpuComputeOptions) -> Result<Self, SgpuError> {
+        pollster::block_on(GpuComputeAsync::try_with_options(options)).map(Self)
+    }
+
+    /// Same as `GpuComputeAsync::from_device`.
+    #[inline]
+    pub fn from_device(
+        device: impl Into<Arc<wgpu::Device>>,
+        queue: impl Into<Arc<wgpu::Queue>>,
+    ) -> Self {
+        Self(GpuComputeAsync::from_device(device, queue))
+    }
+
+    /// Same as `GpuComputeAsync::from_device_with_options`.
+    #[inline]
+    pub fn from_device_with_options(
+        device: impl Into<Arc<wgpu::Device>>,
+        queue: impl Into<Arc<wgpu::Queue>>,
+        options: options::GpuComputeOptions,
+    ) -> Self {
+        Self(GpuComputeAsync::from_device_with_options(
+            device, queue, options,
+        ))
+    }
+
+    /// Same as `GpuComputeAsync::builder`, the device is created with `GpuComputeBuilder::build`.
+    #[inline]
+    pub fn builder() -> options::GpuComputeBuilder {
+        GpuComputeAsync::builder()
+    }
+
+    /// Same as `GpuComputeAsync::enumerate_adapters`.
+    #[inline]
+    pub fn enumerate_adapters() -> Vec<wgpu::AdapterInfo> {
+        GpuComputeAsync::enumerate_adapters()
+    }
+
+    /// Blocking version of `GpuComputeAsync::with_adapter`.
+    #[inline]
+    pub fn with_adapter(
+        selector: impl adapter::AdapterSelector,
+        options: options::GpuComputeOptions,
+    ) -> Result<Self, SgpuError> {
+        pollster::block_on(GpuComputeAsync::with_adapter(selector, options)).map(Self)
+    }
+
     /// Blocking version of `GpuComputeAsync::gen_pipeline`.
     #[inline]
     pub fn gen_pipeline<
@@ -22,11 +90,326 @@ impl GpuCompute {
         &self,
         scratchpad_size: Option<NonZeroUsize>,
         stages: [StageDesc; N],
-    ) -> Pipeline<Input, Uniform, Output, N> {
+    ) -> Pipeline<'_, Input, Uniform, Output, N> {
         Pipeline(pollster::block_on(
             self.0.gen_pipeline(scratchpad_size, stages),
         ))
     }
+
+    /// Blocking version of `GpuComputeAsync::gen_pipeline_owned`.
+    #[inline]
+    pub fn gen_pipeline_owned<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        Output: bytemuck::Pod,
+        const N: usize,
+    >(
+        self: &Arc<Self>,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> OwnedPipeline<Input, Uniform, Output, N> {
+        let pipeline = pollster::block_on(self.0.gen_pipeline(scratchpad_size, stages));
+        Pipeline(pipeline.with_device(GpuRef::Shared(Arc::clone(self) as _)))
+    }
+
+    /// Blocking version of `GpuComputeAsync::gen_pipeline_with_scratchpads`.
+    #[inline]
+    pub fn gen_pipeline_with_scratchpads<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        Output: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        scratchpads: &[ScratchpadDesc],
+        stages: [StageDesc; N],
+    ) -> Pipeline<'_, Input, Uniform, Output, N> {
+        Pipeline(pollster::block_on(
+            self.0.gen_pipeline_with_scratchpads(scratchpads, stages),
+        ))
+    }
+
+    /// Blocking version of `GpuComputeAsync::try_gen_pipeline_with_scratchpads`.
+    #[inline]
+    pub fn try_gen_pipeline_with_scratchpads<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        Output: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        scratchpads: &[ScratchpadDesc],
+        stages: [StageDesc; N],
+    ) -> Result<Pipeline<'_, Input, Uniform, Output, N>, SgpuError> {
+        pollster::block_on(
+            self.0
+                .try_gen_pipeline_with_scratchpads(scratchpads, stages),
+        )
+        .map(Pipeline)
+    }
+
+    /// Blocking version of `GpuComputeAsync::gen_pipeline_with_push_constants`.
+    #[inline]
+    pub fn gen_pipeline_with_push_constants<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        Output: bytemuck::Pod,
+        PushConstants: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> Pipeline<'_, Input, Uniform, Output, N> {
+        Pipeline(pollster::block_on(
+            self.0
+                .gen_pipeline_with_push_constants::<Input, Uniform, Output, PushConstants, N>(
+                    scratchpad_size,
+                    stages,
+                ),
+        ))
+    }
+
+    /// Blocking version of `GpuComputeAsync::try_gen_pipeline_with_push_constants`.
+    #[inline]
+    pub fn try_gen_pipeline_with_push_constants<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        Output: bytemuck::Pod,
+        PushConstants: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> Result<Pipeline<'_, Input, Uniform, Output, N>, SgpuError> {
+        pollster::block_on(
+            self.0
+                .try_gen_pipeline_with_push_constants::<Input, Uniform, Output, PushConstants, N>(
+                    scratchpad_size,
+                    stages,
+                ),
+        )
+        .map(Pipeline)
+    }
+
+    /// Blocking version of `GpuComputeAsync::gen_pipeline_dyn`.
+    #[inline]
+    pub fn gen_pipeline_dyn<
+        T: bytemuck::Pod,
+        U: bytemuck::Pod,
+        V: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        input_len: usize,
+        output_len: usize,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> DynPipeline<'_, T, U, V, N> {
+        DynPipeline(pollster::block_on(self.0.gen_pipeline_dyn(
+            input_len,
+            output_len,
+            scratchpad_size,
+            stages,
+        )))
+    }
+
+    /// Blocking version of `GpuComputeAsync::try_gen_pipeline_dyn`.
+    #[inline]
+    pub fn try_gen_pipeline_dyn<
+        T: bytemuck::Pod,
+        U: bytemuck::Pod,
+        V: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        input_len: usize,
+        output_len: usize,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> Result<DynPipeline<'_, T, U, V, N>, SgpuError> {
+        pollster::block_on(self.0.try_gen_pipeline_dyn(
+            input_len,
+            output_len,
+            scratchpad_size,
+            stages,
+        ))
+        .map(DynPipeline)
+    }
+
+    /// Blocking version of `GpuComputeAsync::gen_pipeline_append`.
+    #[inline]
+    pub fn gen_pipeline_append<
+        T: bytemuck::Pod,
+        U: bytemuck::Pod,
+        V: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        input_len: usize,
+        capacity: usize,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> AppendPipeline<'_, T, U, V, N> {
+        AppendPipeline(pollster::block_on(self.0.gen_pipeline_append(
+            input_len,
+            capacity,
+            scratchpad_size,
+            stages,
+        )))
+    }
+
+    /// Blocking version of `GpuComputeAsync::try_gen_pipeline_append`.
+    #[inline]
+    pub fn try_gen_pipeline_append<
+        T: bytemuck::Pod,
+        U: bytemuck::Pod,
+        V: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        input_len: usize,
+        capacity: usize,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> Result<AppendPipeline<'_, T, U, V, N>, SgpuError> {
+        pollster::block_on(self.0.try_gen_pipeline_append(
+            input_len,
+            capacity,
+            scratchpad_size,
+            stages,
+        ))
+        .map(AppendPipeline)
+    }
+
+    /// Blocking version of `GpuComputeAsync::gen_graph_pipeline`.
+    #[inline]
+    pub fn gen_graph_pipeline(&self, graph: ComputeGraph) -> GraphPipeline<'_> {
+        GraphPipeline(pollster::block_on(self.0.gen_graph_pipeline(graph)))
+    }
+
+    /// Blocking version of `GpuComputeAsync::try_gen_graph_pipeline`.
+    #[inline]
+    pub fn try_gen_graph_pipeline(
+        &self,
+        graph: ComputeGraph,
+    ) -> Result<GraphPipeline<'_>, SgpuError> {
+        pollster::block_on(self.0.try_gen_graph_pipeline(graph)).map(GraphPipeline)
+    }
+
+    /// Blocking version of `GpuComputeAsync::gen_bound_pipeline`.
+    #[inline]
+    pub fn gen_bound_pipeline<B: BindingSet, const N: usize>(
+        &self,
+        stages: [StageDesc; N],
+    ) -> BoundPipeline<'_, B, N> {
+        BoundPipeline(pollster::block_on(self.0.gen_bound_pipeline(stages)))
+    }
+
+    /// Blocking version of `GpuComputeAsync::try_gen_bound_pipeline`.
+    #[inline]
+    pub fn try_gen_bound_pipeline<B: BindingSet, const N: usize>(
+        &self,
+        stages: [StageDesc; N],
+    ) -> Result<BoundPipeline<'_, B, N>, SgpuError> {
+        pollster::block_on(self.0.try_gen_bound_pipeline(stages)).map(BoundPipeline)
+    }
+
+    /// Blocking version of `GpuComputeAsync::gen_pipeline_multi`.
+    #[inline]
+    pub fn gen_pipeline_multi<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        O: Outputs,
+        const N: usize,
+    >(
+        &self,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> MultiPipeline<'_, Input, Uniform, O, N> {
+        MultiPipeline(pollster::block_on(
+            self.0.gen_pipeline_multi(scratchpad_size, stages),
+        ))
+    }
+
+    /// Blocking version of `GpuComputeAsync::try_gen_pipeline_multi`.
+    #[inline]
+    pub fn try_gen_pipeline_multi<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        O: Outputs,
+        const N: usize,
+    >(
+        &self,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> Result<MultiPipeline<'_, Input, Uniform, O, N>, SgpuError> {
+        pollster::block_on(self.0.try_gen_pipeline_multi(scratchpad_size, stages))
+            .map(MultiPipeline)
+    }
+
+    /// Blocking version of `GpuComputeAsync::gen_pipeline_with_provider`.
+    #[inline]
+    pub fn gen_pipeline_with_provider<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        Output: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        provider: impl provider::ShaderSourceProvider + 'static,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> Pipeline<'_, Input, Uniform, Output, N> {
+        Pipeline(pollster::block_on(self.0.gen_pipeline_with_provider(
+            provider,
+            scratchpad_size,
+            stages,
+        )))
+    }
+
+    /// Blocking version of `GpuComputeAsync::try_gen_pipeline`.
+    #[inline]
+    pub fn try_gen_pipeline<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        Output: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> Result<Pipeline<'_, Input, Uniform, Output, N>, SgpuError> {
+        pollster::block_on(self.0.try_gen_pipeline(scratchpad_size, stages)).map(Pipeline)
+    }
+
+    /// Blocking version of `GpuComputeAsync::try_gen_pipeline_with_provider`.
+    #[inline]
+    pub fn try_gen_pipeline_with_provider<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        Output: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        provider: impl provider::ShaderSourceProvider + 'static,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> Result<Pipeline<'_, Input, Uniform, Output, N>, SgpuError> {
+        pollster::block_on(
+            self.0
+                .try_gen_pipeline_with_provider(provider, scratchpad_size, stages),
+        )
+        .map(Pipeline)
+    }
+}
+
+impl Default for GpuCompute {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Deref for GpuCompute {
@@ -45,17 +428,73 @@ impl DerefMut for GpuCompute {
     }
 }
 
+impl SharedGpu for GpuCompute {
+    #[inline]
+    fn gpu(&self) -> &GpuComputeAsync {
+        &self.0
+    }
+}
+
 pub struct Pipeline<
     'a,
     Input: bytemuck::Pod,
     Uniform: bytemuck::Pod,
     Output: bytemuck::Pod,
     const N: usize,
->(PipelineAsync<'a, Input, Uniform, Output, N>);
+>(pub(crate) PipelineAsync<'a, Input, Uniform, Output, N>);
+
+/// Blocking version of `OwnedPipelineAsync`.
+pub type OwnedPipeline<Input, Uniform, Output, const N: usize> =
+    Pipeline<'static, Input, Uniform, Output, N>;
 
 impl<'a, Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod, const N: usize>
     Pipeline<'a, Input, Uniform, Output, N>
 {
+    /// Same as `PipelineAsync::clone_for_thread`, but returns a blocking pipeline.
+    #[inline]
+    pub fn clone_for_thread(&self) -> Self {
+        Self(self.0.clone_for_thread())
+    }
+
+    /// Same as `PipelineAsync::into_pool`, but the pool contains blocking pipelines.
+    pub fn into_pool(self, size: usize) -> PipelinePool<Self> {
+        let clones = (1..size)
+            .map(|_| self.clone_for_thread())
+            .collect::<Vec<_>>();
+        PipelinePool::new(std::iter::once(self).chain(clones))
+    }
+
+    /// Same as `PipelineAsync::into_budgeted`, the job is the same for both kinds of pipelines.
+    #[inline]
+    pub fn into_budgeted(
+        self,
+        budget: std::time::Duration,
+        chunk_count: usize,
+    ) -> budget::BudgetedJob<'a, Input, Uniform, Output, N> {
+        self.0.into_budgeted(budget, chunk_count)
+    }
+
+    /// Blocking version of `PipelineAsync::rebuild_on`.
+    #[inline]
+    pub fn rebuild_on<'b>(
+        &self,
+        gpu: &'b GpuComputeAsync,
+    ) -> Result<Pipeline<'b, Input, Uniform, Output, N>, SgpuError> {
+        pollster::block_on(self.0.rebuild_on(gpu)).map(Pipeline)
+    }
+
+    /// Blocking version of `PipelineAsync::replace_stage`.
+    #[inline]
+    pub fn replace_stage(&mut self, index: usize, stage: StageDesc) {
+        pollster::block_on(self.0.replace_stage(index, stage))
+    }
+
+    /// Blocking version of `PipelineAsync::try_replace_stage`.
+    #[inline]
+    pub fn try_replace_stage(&mut self, index: usize, stage: StageDesc) -> Result<(), SgpuError> {
+        pollster::block_on(self.0.try_replace_stage(index, stage))
+    }
+
     /// Blocking version of `PipelineAsync::run`.
     #[inline]
     pub fn run<T: Send + 'static>(
@@ -66,6 +505,110 @@ impl<'a, Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod, co
     ) -> T {
         pollster::block_on(self.0.run(input, workgroups, callback))
     }
+
+    /// Blocking version of `PipelineAsync::try_run`.
+    #[inline]
+    pub fn try_run<T: Send + 'static>(
+        &mut self,
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+        callback: impl FnOnce(&Output) -> T + Send,
+    ) -> Result<T, SgpuError> {
+        pollster::block_on(self.0.try_run(input, workgroups, callback))
+    }
+
+    /// Blocking version of `PipelineAsync::run_with_timeout`.
+    #[inline]
+    pub fn run_with_timeout<T: Send + 'static>(
+        &mut self,
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+        timeout: std::time::Duration,
+        callback: impl FnOnce(&Output) -> T + Send,
+    ) -> Result<T, SgpuError> {
+        pollster::block_on(
+            self.0
+                .run_with_timeout(input, workgroups, timeout, callback),
+        )
+    }
+
+    /// Blocking version of `PipelineAsync::run_profiled`.
+    #[inline]
+    pub fn run_profiled<T>(
+        &mut self,
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+        callback: impl FnOnce(&Output) -> T,
+    ) -> (T, crate::profile::StageTimings) {
+        pollster::block_on(self.0.run_profiled(input, workgroups, callback))
+    }
+
+    /// Blocking version of `PipelineAsync::run_with_statistics`.
+    #[inline]
+    pub fn run_with_statistics<T>(
+        &mut self,
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+        callback: impl FnOnce(&Output) -> T,
+    ) -> (T, Vec<crate::profile::PassStatistics>) {
+        pollster::block_on(self.0.run_with_statistics(input, workgroups, callback))
+    }
+
+    /// Blocking version of `PipelineAsync::run_readback_into_vec`.
+    #[inline]
+    pub fn run_readback_into_vec<T: bytemuck::Pod + Send>(
+        &mut self,
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+        out: &mut Vec<T>,
+    ) {
+        pollster::block_on(self.0.run_readback_into_vec(input, workgroups, out))
+    }
+
+    /// Blocking version of `PipelineAsync::run_if_changed`.
+    #[inline]
+    pub fn run_if_changed<T: Send + 'static>(
+        &mut self,
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+        callback: impl FnOnce(&Output) -> T + Send,
+    ) -> Option<T> {
+        pollster::block_on(self.0.run_if_changed(input, workgroups, callback))
+    }
+
+    /// Blocking version of `PipelineAsync::run_labeled`.
+    #[inline]
+    pub fn run_labeled<T: Send + 'static>(
+        &mut self,
+        label: &str,
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+        callback: impl FnOnce(&Output) -> T + Send,
+    ) -> T {
+        pollster::block_on(self.0.run_labeled(label, input, workgroups, callback))
+    }
+
+    /// Blocking version of `PipelineAsync::read_scratchpad`.
+    #[inline]
+    pub fn read_scratchpad<T: bytemuck::Pod + Send>(&self) -> T {
+        pollster::block_on(self.0.read_scratchpad())
+    }
+
+    /// Blocking version of `PipelineAsync::run_without_upload`.
+    #[inline]
+    pub fn run_without_upload<T: Send + 'static>(
+        &mut self,
+        workgroups: [(u32, u32, u32); N],
+        callback: impl FnOnce(&Output) -> T + Send,
+    ) -> T {
+        pollster::block_on(self.0.run_without_upload(workgroups, callback))
+    }
+
+    /// Blocking version of `PipelineAsync::read_output`.
+    #[inline]
+    pub fn read_output<T>(&mut self, callback: impl FnOnce(&Output) -> T) -> T {
+        pollster::block_on(self.0.read_output(callback))
+    }
 }
 
 impl<'a, Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod, const N: usize> Deref
@@ -87,3 +630,200 @@ impl<'a, Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod, co
         &mut self.0
     }
 }
+
+/// Blocking version of `DynPipelineAsync`.
+pub struct DynPipeline<'a, T: bytemuck::Pod, U: bytemuck::Pod, V: bytemuck::Pod, const N: usize>(
+    pub(crate) DynPipelineAsync<'a, T, U, V, N>,
+);
+
+impl<T: bytemuck::Pod, U: bytemuck::Pod, V: bytemuck::Pod, const N: usize>
+    DynPipeline<'_, T, U, V, N>
+{
+    /// Blocking version of `DynPipelineAsync::run`.
+    #[inline]
+    pub fn run<R: Send + 'static>(
+        &mut self,
+        input: &[T],
+        workgroups: [(u32, u32, u32); N],
+        callback: impl FnOnce(&[V]) -> R + Send,
+    ) -> R {
+        pollster::block_on(self.0.run(input, workgroups, callback))
+    }
+
+    /// Blocking version of `DynPipelineAsync::run_to_vec`.
+    #[inline]
+    pub fn run_to_vec(&mut self, input: &[T], workgroups: [(u32, u32, u32); N]) -> Vec<V>
+    where
+        V: Send,
+    {
+        pollster::block_on(self.0.run_to_vec(input, workgroups))
+    }
+
+    /// Blocking version of `DynPipelineAsync::run_readback_into_vec`.
+    #[inline]
+    pub fn run_readback_into_vec(
+        &mut self,
+        input: &[T],
+        workgroups: [(u32, u32, u32); N],
+        out: &mut Vec<V>,
+    ) where
+        V: Send,
+    {
+        pollster::block_on(self.0.run_readback_into_vec(input, workgroups, out))
+    }
+}
+
+impl<'a, T: bytemuck::Pod, U: bytemuck::Pod, V: bytemuck::Pod, const N: usize> Deref
+    for DynPipeline<'a, T, U, V, N>
+{
+    type Target = DynPipelineAsync<'a, T, U, V, N>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: bytemuck::Pod, U: bytemuck::Pod, V: bytemuck::Pod, const N: usize> DerefMut
+    for DynPipeline<'_, T, U, V, N>
+{
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Blocking version of `AppendPipelineAsync`.
+pub struct AppendPipeline<'a, T: bytemuck::Pod, U: bytemuck::Pod, V: bytemuck::Pod, const N: usize>(
+    pub(crate) AppendPipelineAsync<'a, T, U, V, N>,
+);
+
+impl<T: bytemuck::Pod, U: bytemuck::Pod, V: bytemuck::Pod, const N: usize>
+    AppendPipeline<'_, T, U, V, N>
+{
+    /// Blocking version of `AppendPipelineAsync::run`.
+    #[inline]
+    pub fn run(&mut self, input: &[T], workgroups: [(u32, u32, u32); N]) -> Vec<V> {
+        pollster::block_on(self.0.run(input, workgroups))
+    }
+}
+
+impl<'a, T: bytemuck::Pod, U: bytemuck::Pod, V: bytemuck::Pod, const N: usize> Deref
+    for AppendPipeline<'a, T, U, V, N>
+{
+    type Target = AppendPipelineAsync<'a, T, U, V, N>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: bytemuck::Pod, U: bytemuck::Pod, V: bytemuck::Pod, const N: usize> DerefMut
+    for AppendPipeline<'_, T, U, V, N>
+{
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Blocking version of `GraphPipelineAsync`.
+pub struct GraphPipeline<'a>(pub(crate) GraphPipelineAsync<'a>);
+
+impl GraphPipeline<'_> {
+    /// Blocking version of `GraphPipelineAsync::run`.
+    #[inline]
+    pub fn run(&mut self) {
+        pollster::block_on(self.0.run())
+    }
+}
+
+impl<'a> Deref for GraphPipeline<'a> {
+    type Target = GraphPipelineAsync<'a>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for GraphPipeline<'_> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Blocking version of `BoundPipelineAsync`.
+pub struct BoundPipeline<'a, B: BindingSet, const N: usize>(
+    pub(crate) BoundPipelineAsync<'a, B, N>,
+);
+
+impl<B: BindingSet, const N: usize> BoundPipeline<'_, B, N> {
+    /// Blocking version of `BoundPipelineAsync::run`.
+    #[inline]
+    pub fn run(&mut self, set: &mut B, workgroups: [(u32, u32, u32); N]) {
+        pollster::block_on(self.0.run(set, workgroups))
+    }
+}
+
+impl<'a, B: BindingSet, const N: usize> Deref for BoundPipeline<'a, B, N> {
+    type Target = BoundPipelineAsync<'a, B, N>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<B: BindingSet, const N: usize> DerefMut for BoundPipeline<'_, B, N> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Blocking version of `MultiPipelineAsync`.
+pub struct MultiPipeline<
+    'a,
+    Input: bytemuck::Pod,
+    Uniform: bytemuck::Pod,
+    O: Outputs,
+    const N: usize,
+>(pub(crate) MultiPipelineAsync<'a, Input, Uniform, O, N>);
+
+impl<Input: bytemuck::Pod, Uniform: bytemuck::Pod, O: Outputs, const N: usize>
+    MultiPipeline<'_, Input, Uniform, O, N>
+{
+    /// Blocking version of `MultiPipelineAsync::run`.
+    #[inline]
+    pub fn run<T: Send + 'static>(
+        &mut self,
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+        callback: impl for<'r> FnOnce(O::Refs<'r>) -> T + Send,
+    ) -> T {
+        pollster::block_on(self.0.run(input, workgroups, callback))
+    }
+}
+
+impl<'a, Input: bytemuck::Pod, Uniform: bytemuck::Pod, O: Outputs, const N: usize> Deref
+    for MultiPipeline<'a, Input, Uniform, O, N>
+{
+    type Target = MultiPipelineAsync<'a, Input, Uniform, O, N>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<Input: bytemuck::Pod, Uniform: bytemuck::Pod, O: Outputs, const N: usize> DerefMut
+    for MultiPipeline<'_, Input, Uniform, O, N>
+{
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
@@ -0,0 +1,296 @@
+//! Long jobs spread across frames under a GPU time budget.
+//!
+//! An interactive application can't wait for a job taking seconds of GPU time, and dispatching all of it in one frame would stall its rendering. A `BudgetedJob` splits the job into chunks, each one being a run of the pipeline without readback, and `BudgetedJob::frame` only dispatches as many chunks as fit the GPU time budget of a frame. The cost of a chunk is measured with timestamp queries around the chunks of a frame when the device supports `TIMESTAMP_QUERY`, and otherwise with the time between their submission and the completion of the work on the CPU, which also includes the queue latency. The measurements are never waited for: each frame uses the mean cost of the last `WINDOW` measured frames, and the number of chunks per frame slides with it.
+//!
+//! The first frame dispatches a single chunk to calibrate the cost, and the number of chunks at most doubles from a frame to the next one, so an underestimated cost doesn't blow the budget. At least one chunk is dispatched per frame, so a job always progresses even if one chunk exceeds the budget.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use std::time::Duration;
+//!
+//! let shader = "
+//!     @group(0) @binding(0) var<uniform> chunk: u32;
+//!     @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+//!
+//!     @compute @workgroup_size(64)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[64u * chunk + id.x] = chunk;
+//!     }
+//! ";
+//! let gpu = GpuCompute::new();
+//! let pipeline = gpu.gen_pipeline::<(), u32, [u32; 6400], 1>(None, [StageDesc::new(shader, "main")]);
+//! let mut job = pipeline.into_budgeted(Duration::from_millis(2), 100);
+//! while !job.is_done() {
+//!     job.frame(|chunk, pipeline| {
+//!         pipeline.write_uniform(&(chunk as u32));
+//!         ((), [(1, 1, 1)])
+//!     });
+//!     // Rendering of the frame.
+//! }
+//! assert_eq!(job.read_output(|out| out[6399]), 99);
+//! ```
+use crate::PipelineAsync;
+use std::{collections::VecDeque, ops::Range, time::Duration, time::Instant};
+
+/// Number of measured frames whose mean chunk cost sets the chunks of a frame.
+pub const WINDOW: usize = 8;
+
+/// Job of `chunk_count` runs of a pipeline spread across frames, see the module documentation.
+pub struct BudgetedJob<
+    'a,
+    Input: bytemuck::Pod,
+    Uniform: bytemuck::Pod,
+    Output: bytemuck::Pod,
+    const N: usize,
+> {
+    pipeline: PipelineAsync<'a, Input, Uniform, Output, N>,
+    budget: Duration,
+    chunk_count: usize,
+    next: usize,
+    /// Chunks dispatched by the last frame.
+    last_chunks: usize,
+    /// Cost of a chunk in the last measured frames.
+    costs: VecDeque<Duration>,
+    timestamps: Option<FrameTimestamps>,
+    /// The frame being measured, only one frame is measured at a time.
+    measurement: Option<Measurement>,
+}
+
+/// Queries written before and after the chunks of a frame.
+struct FrameTimestamps {
+    query_set: wgpu::QuerySet,
+    resolve: wgpu::Buffer,
+    readback: wgpu::Buffer,
+}
+
+struct Measurement {
+    chunks: usize,
+    submitted: Instant,
+    /// Receives the completion time of the chunks, or the mapping of the timestamps.
+    done: flume::Receiver<Instant>,
+}
+
+impl<'a, Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod, const N: usize>
+    PipelineAsync<'a, Input, Uniform, Output, N>
+{
+    /// This method is used to spread a job of `chunk_count` runs of the pipeline across frames, with a GPU time budget of `budget` per frame, see the `budget` module.
+    pub fn into_budgeted(
+        self,
+        budget: Duration,
+        chunk_count: usize,
+    ) -> BudgetedJob<'a, Input, Uniform, Output, N> {
+        let device = &self.device.device;
+        let timestamps = device
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY)
+            .then(|| {
+                let size = 2 * std::mem::size_of::<u64>() as u64;
+                FrameTimestamps {
+                    query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                        label: Some("Frame budget timestamps"),
+                        ty: wgpu::QueryType::Timestamp,
+                        count: 2,
+                    }),
+                    resolve: device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some("Frame budget timestamps resolve"),
+                        size,
+                        usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                        mapped_at_creation: false,
+                    }),
+                    readback: device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some("Frame budget timestamps readback"),
+                        size,
+                        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                        mapped_at_creation: false,
+                    }),
+                }
+            });
+        BudgetedJob {
+            pipeline: self,
+            budget,
+            chunk_count,
+            next: 0,
+            last_chunks: 0,
+            costs: VecDeque::with_capacity(WINDOW),
+            timestamps,
+            measurement: None,
+        }
+    }
+}
+
+impl<'a, Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod, const N: usize>
+    BudgetedJob<'a, Input, Uniform, Output, N>
+{
+    /// The GPU time budget of a frame.
+    #[inline]
+    pub fn budget(&self) -> Duration {
+        self.budget
+    }
+
+    /// This method is used to change the budget of the next frames, e.g. when the frame rate drops.
+    #[inline]
+    pub fn set_budget(&mut self, budget: Duration) {
+        self.budget = budget;
+    }
+
+    /// Number of chunks of the job.
+    #[inline]
+    pub fn chunk_count(&self) -> usize {
+        self.chunk_count
+    }
+
+    /// Number of chunks dispatched so far.
+    #[inline]
+    pub fn dispatched(&self) -> usize {
+        self.next
+    }
+
+    /// Whether all the chunks are dispatched, they may still be running.
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        self.next == self.chunk_count
+    }
+
+    /// Estimated GPU time of a chunk, the mean over the last `WINDOW` measured frames. `None` until a frame is measured.
+    pub fn chunk_cost(&self) -> Option<Duration> {
+        let total = self.costs.iter().sum::<Duration>();
+        (!self.costs.is_empty()).then(|| total / self.costs.len() as u32)
+    }
+
+    /// The pipeline running the chunks.
+    #[inline]
+    pub fn pipeline(&mut self) -> &mut PipelineAsync<'a, Input, Uniform, Output, N> {
+        &mut self.pipeline
+    }
+
+    /// Returns the pipeline, the chunks dispatched so far keep running.
+    #[inline]
+    pub fn into_pipeline(self) -> PipelineAsync<'a, Input, Uniform, Output, N> {
+        self.pipeline
+    }
+
+    /// Dispatches the chunks of this frame and returns their indices, empty once the job is done. For each chunk, `chunk` gets its index and the pipeline, e.g. to write the uniform of the chunk, and returns the input and the workgroups of its run. It doesn't wait for the GPU.
+    pub fn frame(
+        &mut self,
+        mut chunk: impl FnMut(
+            usize,
+            &mut PipelineAsync<'a, Input, Uniform, Output, N>,
+        ) -> (Input, [(u32, u32, u32); N]),
+    ) -> Range<usize> {
+        self.update();
+        let count = match self.chunk_cost() {
+            // Still calibrating.
+            None => 1,
+            Some(cost) => {
+                let fitting = self.budget.as_secs_f64() / cost.as_secs_f64().max(1e-9);
+                (fitting as usize).clamp(1, 2 * self.last_chunks.max(1))
+            }
+        };
+        let chunks = self.next..(self.next + count).min(self.chunk_count);
+        if chunks.is_empty() {
+            return chunks;
+        }
+        let measured = self.measurement.is_none();
+        if measured {
+            self.write_timestamp(true);
+        }
+        let submitted = Instant::now();
+        for index in chunks.clone() {
+            let (input, workgroups) = chunk(index, &mut self.pipeline);
+            self.pipeline.dispatch(&input, workgroups);
+        }
+        if measured {
+            let (sender, receiver) = flume::bounded(1);
+            match self.write_timestamp(false) {
+                Some(readback) => readback.slice(..).map_async(wgpu::MapMode::Read, move |e| {
+                    e.expect("Could not map timestamps");
+                    sender.send(Instant::now()).unwrap()
+                }),
+                None => self
+                    .pipeline
+                    .device
+                    .queue
+                    .on_submitted_work_done(move || sender.send(Instant::now()).unwrap()),
+            }
+            self.measurement = Some(Measurement {
+                chunks: chunks.len(),
+                submitted,
+                done: receiver,
+            });
+        }
+        self.last_chunks = chunks.len();
+        self.next = chunks.end;
+        chunks
+    }
+
+    /// Submits an empty pass writing the timestamp before the chunks if `before` is set, or after them, followed by the copy of the timestamps to the readback buffer, which is returned.
+    fn write_timestamp(&self, before: bool) -> Option<&wgpu::Buffer> {
+        let timestamps = self.timestamps.as_ref()?;
+        let device = &self.pipeline.device;
+        let mut encoder = device
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Frame budget timestamp"),
+            });
+        encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Frame budget timestamp"),
+            timestamp_writes: Some(wgpu::ComputePassTimestampWrites {
+                query_set: &timestamps.query_set,
+                beginning_of_pass_write_index: before.then_some(0),
+                end_of_pass_write_index: (!before).then_some(1),
+            }),
+        });
+        if !before {
+            encoder.resolve_query_set(&timestamps.query_set, 0..2, &timestamps.resolve, 0);
+            encoder.copy_buffer_to_buffer(
+                &timestamps.resolve,
+                0,
+                &timestamps.readback,
+                0,
+                timestamps.readback.size(),
+            );
+        }
+        device.queue.submit(Some(encoder.finish()));
+        Some(&timestamps.readback)
+    }
+
+    /// Polls the device without blocking and records the cost of the measured frame if it is completed.
+    fn update(&mut self) {
+        self.pipeline.device.device.poll(wgpu::Maintain::Poll);
+        let Some(measurement) = &self.measurement else {
+            return;
+        };
+        let Ok(done) = measurement.done.try_recv() else {
+            return;
+        };
+        let elapsed = match &self.timestamps {
+            Some(timestamps) => {
+                let period = self.pipeline.device.queue.get_timestamp_period() as f64;
+                let ticks: [u64; 2] =
+                    bytemuck::pod_read_unaligned(&timestamps.readback.slice(..).get_mapped_range());
+                timestamps.readback.unmap();
+                Duration::from_nanos((ticks[1].saturating_sub(ticks[0]) as f64 * period) as u64)
+            }
+            None => done.saturating_duration_since(measurement.submitted),
+        };
+        if self.costs.len() == WINDOW {
+            self.costs.pop_front();
+        }
+        self.costs.push_back(elapsed / measurement.chunks as u32);
+        self.measurement = None;
+    }
+
+    /// Waits for the dispatched chunks and calls `callback` on the output they leave.
+    pub async fn read_output_async<T>(&mut self, callback: impl FnOnce(&Output) -> T) -> T {
+        let output = self.pipeline.read_output(callback).await;
+        self.update();
+        output
+    }
+
+    /// Blocking version of `BudgetedJob::read_output_async`. It is enabled by the `blocking` feature.
+    #[cfg(feature = "blocking")]
+    #[inline]
+    pub fn read_output<T>(&mut self, callback: impl FnOnce(&Output) -> T) -> T {
+        pollster::block_on(self.read_output_async(callback))
+    }
+}
@@ -0,0 +1,178 @@
+//! Detection of unchanged outputs.
+//!
+//! With `PipelineAsync::set_change_detection`, `PipelineAsync::run_if_changed` compares the output of the stages with the output of the previous run on the GPU and only copies and maps it when a word differs. Otherwise, only a flag of 4 bytes is read back and the callback is not called, which saves the bandwidth of the readback in convergence loops or in UI-driven recomputations where most runs give the same result. The first run always reports a change.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//!
+//! let shader = "
+//!     @group(0) @binding(0) var<storage, read> in: array<u32>;
+//!     @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+//!
+//!     @compute @workgroup_size(4)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[id.x] = in[id.x] / 10u;
+//!     }
+//! ";
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(None, [StageDesc::new(shader, "main")]);
+//! pipeline.set_change_detection(true);
+//! assert_eq!(pipeline.run_if_changed(&[10, 20, 30, 40], [(1, 1, 1)], |out| *out), Some([1, 2, 3, 4]));
+//! assert_eq!(pipeline.run_if_changed(&[11, 22, 33, 44], [(1, 1, 1)], |out| *out), None);
+//! assert_eq!(pipeline.run_if_changed(&[11, 22, 33, 54], [(1, 1, 1)], |out| *out), Some([1, 2, 3, 5]));
+//! ```
+use crate::PipelineAsync;
+
+/// WGSL source of the comparison of the outputs.
+const SHADER: &str = include_str!("change.wgsl");
+
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Buffers and pipeline comparing the output with the previous one.
+pub(crate) struct ChangeDetection {
+    pipeline: wgpu::ComputePipeline,
+    bindgroup: wgpu::BindGroup,
+    flag: wgpu::Buffer,
+    readback: wgpu::Buffer,
+    words: u32,
+    /// Whether a run already wrote the previous output.
+    has_previous: bool,
+}
+
+impl ChangeDetection {
+    fn new(device: &wgpu::Device, staging: &wgpu::Buffer) -> Self {
+        let size = staging.size();
+        assert!(
+            size.is_multiple_of(4),
+            "Change detection requires an output whose size is a multiple of 4 bytes"
+        );
+        let previous = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Previous output buffer"),
+            size,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let flag = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Output changed flag"),
+            size: 4,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Output changed flag readback"),
+            size: 4,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Output comparison"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Output comparison"),
+            layout: None,
+            module: &module,
+            entry_point: Some("compare"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        let bindgroup = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Output comparison bind group"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: staging.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: previous.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: flag.as_entire_binding(),
+                },
+            ],
+        });
+        Self {
+            pipeline,
+            bindgroup,
+            flag,
+            readback,
+            words: (size / 4) as u32,
+            has_previous: false,
+        }
+    }
+}
+
+impl<Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod, const N: usize>
+    PipelineAsync<'_, Input, Uniform, Output, N>
+{
+    /// This method is used to enable or disable the comparison of the outputs used by `PipelineAsync::run_if_changed`, see the `change` module. It allocates a copy of the output on the GPU.
+    ///
+    /// # Panics
+    /// If the size of the output is not a multiple of 4 bytes.
+    pub fn set_change_detection(&mut self, enabled: bool) {
+        self.change_detection =
+            enabled.then(|| ChangeDetection::new(&self.device.device, &self.buffers.staging));
+    }
+
+    /// Same as `PipelineAsync::run`, but returns `None` without copying nor mapping the output if it is the same as after the previous run, see the `change` module. It is a regular run if change detection is disabled.
+    pub async fn run_if_changed<T: Send + 'static>(
+        &mut self,
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+        callback: impl FnOnce(&Output) -> T + Send,
+    ) -> Option<T> {
+        if self.change_detection.is_none() {
+            return Some(self.run(input, workgroups, callback).await);
+        }
+        self.dispatch(input, workgroups);
+        let detection = self
+            .change_detection
+            .as_ref()
+            .expect("Change detection is enabled");
+        let device = &self.device.device;
+        let mut encoder =
+            device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.clear_buffer(&detection.flag, 0, None);
+        {
+            let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Output comparison"),
+                timestamp_writes: None,
+            });
+            cpass.set_pipeline(&detection.pipeline);
+            cpass.set_bind_group(0, &detection.bindgroup, &[]);
+            let groups = detection.words.div_ceil(WORKGROUP_SIZE);
+            let max = device.limits().max_compute_workgroups_per_dimension;
+            cpass.dispatch_workgroups(groups.min(max), groups.div_ceil(max), 1);
+        }
+        encoder.copy_buffer_to_buffer(&detection.flag, 0, &detection.readback, 0, 4);
+        self.device.queue.submit(Some(encoder.finish()));
+
+        let (sender, receiver) = flume::bounded(1);
+        detection
+            .readback
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |e| {
+                e.expect("Could not map buffer");
+                sender.send(()).unwrap()
+            });
+        self.device.wait_submitted();
+        receiver.recv_async().await.expect("Error with channel");
+        let changed = detection.readback.slice(..).get_mapped_range()[..] != [0; 4];
+        detection.readback.unmap();
+
+        let detection = self
+            .change_detection
+            .as_mut()
+            .expect("Change detection is enabled");
+        let first = !std::mem::replace(&mut detection.has_previous, true);
+        if !(changed || first) {
+            return None;
+        }
+        Some(self.read_output(callback).await)
+    }
+}
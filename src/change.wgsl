@@ -0,0 +1,23 @@
+const WORKGROUP_SIZE: u32 = 64u;
+
+@group(0) @binding(0) var<storage, read> current: array<u32>;
+@group(0) @binding(1) var<storage, read_write> previous: array<u32>;
+@group(0) @binding(2) var<storage, read_write> changed: u32;
+
+// Flags the words of the output which differ from the previous run and updates them.
+@compute
+@workgroup_size(64, 1, 1)
+fn compare(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) workgroups: vec3<u32>,
+) {
+    let i = id.y * workgroups.x * WORKGROUP_SIZE + id.x;
+    if i >= arrayLength(&current) {
+        return;
+    }
+    if current[i] != previous[i] {
+        // All the invocations write the same value, so the race is benign.
+        changed = 1u;
+        previous[i] = current[i];
+    }
+}
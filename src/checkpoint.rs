@@ -0,0 +1,435 @@
+//! Checkpointing of long batch jobs split into chunks.
+//!
+//! A `Checkpoint` records the output of each completed chunk in a file, so a job running for hours can resume after a crash or a device loss instead of restarting from zero: `Checkpoint::run` skips the chunks already in the file. Each output is appended and synced before the next chunk starts, and a record cut by a crash is detected by its checksum and dropped when the file is opened again. Only the position of the records is kept in memory, the outputs are read from the file when they are needed, see `Checkpoint::output`.
+//!
+//! A chunk recorded again supersedes its previous record. The file is rewritten without the superseded records once they take more space than the others.
+//!
+//! The file starts with the magic `SGCK`, the format version (1 byte) and the number of chunks as a `u64`, followed by one record per completed chunk: its index and the size of its output as `u64`, the output, and the FNV-1a hash of the output as `u64`, everything being little-endian.
+//!
+//! ```rust
+//! use sgpu_compute::checkpoint::Checkpoint;
+//! use sgpu_compute::prelude::*;
+//!
+//! let shader = "
+//!     @group(0) @binding(0) var<storage, read> in: array<u32>;
+//!     @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+//!
+//!     @compute @workgroup_size(64)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[id.x] = in[id.x] + 1u;
+//!     }
+//! ";
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline::<[u32; 64], (), [u32; 64], 1>(None, [StageDesc::new(shader, "main")]);
+//!
+//! let path = std::env::temp_dir().join("sgpu-checkpoint-doc");
+//! # let _ = std::fs::remove_file(&path);
+//! let mut checkpoint = Checkpoint::open(&path, 16).unwrap();
+//! let outputs = checkpoint
+//!     .run(|chunk| {
+//!         let input: [u32; 64] = std::array::from_fn(|i| (64 * chunk + i) as u32);
+//!         Ok::<_, SgpuError>(pipeline.run(&input, [(1, 1, 1)], |out| out.to_vec()))
+//!     })
+//!     .unwrap();
+//! assert_eq!(outputs[1000], 1001);
+//! # std::fs::remove_file(path).unwrap();
+//! ```
+use crate::error::invalid_data;
+use std::{
+    fmt,
+    fs::{File, OpenOptions},
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+const MAGIC: &[u8; 4] = b"SGCK";
+const VERSION: u8 = 1;
+const HEADER_SIZE: u64 = 13;
+/// Size of a record without its output: the index, the size and the hash.
+const RECORD_OVERHEAD: u64 = 3 * 8;
+
+/// Outputs of the completed chunks of a job, persisted in a file, see the module documentation.
+pub struct Checkpoint {
+    file: File,
+    path: PathBuf,
+    records: Vec<Option<Record>>,
+    /// Size of the superseded records in the file.
+    superseded: u64,
+}
+
+/// Position of the output of a chunk in the file.
+#[derive(Debug, Clone, Copy)]
+struct Record {
+    offset: u64,
+    len: u64,
+    hash: u64,
+}
+
+/// Error of `Checkpoint::run`.
+#[derive(Debug)]
+pub enum CheckpointError<E> {
+    /// The checkpoint file could not be read or written, or an output in the file doesn't fit the type of the values.
+    Io(io::Error),
+    /// The chunk at `index` failed, the previous chunks are in the checkpoint.
+    Chunk { index: usize, error: E },
+}
+
+impl Checkpoint {
+    /// Opens the checkpoint at `path` of a job of `chunk_count` chunks, or creates it if it doesn't exist. The records of the chunks completed by a previous run are checked, but their outputs aren't loaded.
+    ///
+    /// # Errors
+    /// If the file can't be read or written, if it isn't a checkpoint or if it is the checkpoint of a job with another number of chunks.
+    pub fn open(path: impl AsRef<Path>, chunk_count: usize) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+        let mut records = vec![None; chunk_count];
+        if file.metadata()?.len() == 0 {
+            file.write_all(&header(chunk_count))?;
+            file.sync_data()?;
+            return Ok(Self {
+                file,
+                path,
+                records,
+                superseded: 0,
+            });
+        }
+        let mut reader = BufReader::new(&mut file);
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("the file is not a checkpoint".into()));
+        }
+        let mut version = [0u8; 1];
+        reader.read_exact(&mut version)?;
+        if version[0] != VERSION {
+            return Err(invalid_data(format!(
+                "unsupported checkpoint version {}",
+                version[0]
+            )));
+        }
+        let count = read_u64(&mut reader)?;
+        if count != chunk_count as u64 {
+            return Err(invalid_data(format!(
+                "the checkpoint is for {} chunks, not {}",
+                count, chunk_count
+            )));
+        }
+        let mut valid = HEADER_SIZE;
+        let mut superseded = 0;
+        while let Some((index, len, hash)) = read_record(&mut reader, chunk_count) {
+            let record = Record {
+                offset: valid + 2 * 8,
+                len,
+                hash,
+            };
+            if let Some(previous) = records[index].replace(record) {
+                superseded += RECORD_OVERHEAD + previous.len;
+            }
+            valid += RECORD_OVERHEAD + len;
+        }
+        drop(reader);
+        // Drops the record cut by a crash, if any, so the next ones are appended after the valid ones.
+        file.set_len(valid)?;
+        let mut checkpoint = Self {
+            file,
+            path,
+            records,
+            superseded,
+        };
+        checkpoint.compact_if_needed()?;
+        Ok(checkpoint)
+    }
+
+    /// Number of chunks of the job.
+    #[inline]
+    pub fn chunk_count(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Number of chunks whose output is in the checkpoint.
+    pub fn completed(&self) -> usize {
+        self.records
+            .iter()
+            .filter(|record| record.is_some())
+            .count()
+    }
+
+    /// Whether the output of the chunk at `index` is in the checkpoint.
+    #[inline]
+    pub fn is_completed(&self, index: usize) -> bool {
+        self.records[index].is_some()
+    }
+
+    /// Indices of the chunks which are not completed, in order.
+    pub fn pending(&self) -> impl Iterator<Item = usize> + '_ {
+        self.records
+            .iter()
+            .enumerate()
+            .filter(|(_, record)| record.is_none())
+            .map(|(index, _)| index)
+    }
+
+    /// Reads the output of the chunk at `index` from the file, `None` if it isn't completed.
+    ///
+    /// # Errors
+    /// If the file can't be read.
+    pub fn output(&self, index: usize) -> io::Result<Option<Vec<u8>>> {
+        let Some(mut reader) = self.reader(index)? else {
+            return Ok(None);
+        };
+        let mut output = Vec::with_capacity(reader.remaining as usize);
+        reader.read_to_end(&mut output)?;
+        Ok(Some(output))
+    }
+
+    /// Reader of the output of the chunk at `index` in the file, `None` if it isn't completed, to stream an output too large to be read at once. Several readers can be used at once, since they read at the position of their record.
+    ///
+    /// # Errors
+    /// If the file can't be read.
+    pub fn reader(&self, index: usize) -> io::Result<Option<RecordReader<'_>>> {
+        Ok(self.records[index].map(|record| RecordReader {
+            file: &self.file,
+            offset: record.offset,
+            remaining: record.len,
+        }))
+    }
+
+    /// This method is used to record the output of the chunk at `index`. It is synced to the disk before returning, and supersedes the previous output of the chunk if there is one.
+    ///
+    /// # Panics
+    /// If `index` is out of bounds.
+    pub fn record(&mut self, index: usize, output: &[u8]) -> io::Result<()> {
+        assert!(
+            index < self.chunk_count(),
+            "Chunk {} out of bounds ({} chunks)",
+            index,
+            self.chunk_count()
+        );
+        let hash = fnv1a(FNV_OFFSET, output);
+        let offset = self.file.seek(SeekFrom::End(0))?;
+        let mut record = Vec::with_capacity(RECORD_OVERHEAD as usize + output.len());
+        record.extend_from_slice(&(index as u64).to_le_bytes());
+        record.extend_from_slice(&(output.len() as u64).to_le_bytes());
+        record.extend_from_slice(output);
+        record.extend_from_slice(&hash.to_le_bytes());
+        self.file.write_all(&record)?;
+        self.file.sync_data()?;
+        let record = Record {
+            offset: offset + 2 * 8,
+            len: output.len() as u64,
+            hash,
+        };
+        if let Some(previous) = self.records[index].replace(record) {
+            self.superseded += RECORD_OVERHEAD + previous.len;
+            self.compact_if_needed()?;
+        }
+        Ok(())
+    }
+
+    /// Runs `run_chunk` on each pending chunk in order, recording its output, then returns the outputs of all the chunks concatenated in the order of the chunks, read back from the file. If a chunk fails, e.g. because the device was lost, the error is returned and the next call, possibly after reopening the checkpoint in a new process, resumes from this chunk.
+    ///
+    /// # Errors
+    /// If a chunk fails, if the file can't be read or written, or if the size of an output in the file isn't a multiple of the size of `T`, e.g. because it was recorded with another type.
+    pub fn run<T: bytemuck::Pod, E>(
+        &mut self,
+        mut run_chunk: impl FnMut(usize) -> Result<Vec<T>, E>,
+    ) -> Result<Vec<T>, CheckpointError<E>> {
+        for index in 0..self.chunk_count() {
+            if self.is_completed(index) {
+                continue;
+            }
+            let output =
+                run_chunk(index).map_err(|error| CheckpointError::Chunk { index, error })?;
+            self.record(index, bytemuck::cast_slice(&output))
+                .map_err(CheckpointError::Io)?;
+        }
+        let size = std::mem::size_of::<T>().max(1);
+        let mut outputs = Vec::new();
+        for index in 0..self.chunk_count() {
+            let output = self
+                .output(index)
+                .map_err(CheckpointError::Io)?
+                .expect("Every chunk is completed");
+            if output.len() % size != 0 {
+                return Err(CheckpointError::Io(invalid_data(format!(
+                    "the output of chunk {} is {} bytes, which isn't a multiple of the size of the values ({} bytes)",
+                    index,
+                    output.len(),
+                    size
+                ))));
+            }
+            outputs.extend(
+                output
+                    .chunks_exact(size)
+                    .map(bytemuck::pod_read_unaligned::<T>),
+            );
+        }
+        Ok(outputs)
+    }
+
+    /// Rewrites the file without the superseded records once they take more space than the others.
+    fn compact_if_needed(&mut self) -> io::Result<()> {
+        let live = self
+            .records
+            .iter()
+            .flatten()
+            .map(|record| RECORD_OVERHEAD + record.len)
+            .sum::<u64>();
+        if self.superseded == 0 || self.superseded <= live {
+            return Ok(());
+        }
+        // The records are written to a new file which replaces the checkpoint once it is synced, so a crash leaves one of the two complete files.
+        let mut path = self.path.clone().into_os_string();
+        path.push(".compact");
+        let path = PathBuf::from(path);
+        let mut compacted = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)?;
+        let mut writer = BufWriter::new(&mut compacted);
+        writer.write_all(&header(self.chunk_count()))?;
+        let mut offset = HEADER_SIZE;
+        let mut records = self.records.clone();
+        for (index, record) in records.iter_mut().enumerate() {
+            let Some(record) = record else {
+                continue;
+            };
+            writer.write_all(&(index as u64).to_le_bytes())?;
+            writer.write_all(&record.len.to_le_bytes())?;
+            let mut reader = RecordReader {
+                file: &self.file,
+                offset: record.offset,
+                remaining: record.len,
+            };
+            io::copy(&mut reader, &mut writer)?;
+            writer.write_all(&record.hash.to_le_bytes())?;
+            record.offset = offset + 2 * 8;
+            offset += RECORD_OVERHEAD + record.len;
+        }
+        writer.flush()?;
+        drop(writer);
+        compacted.sync_all()?;
+        std::fs::rename(&path, &self.path)?;
+        sync_parent(&self.path)?;
+        self.file = compacted;
+        self.records = records;
+        self.superseded = 0;
+        Ok(())
+    }
+}
+
+/// Reader of the output of a chunk, returned by `Checkpoint::reader`.
+pub struct RecordReader<'c> {
+    file: &'c File,
+    offset: u64,
+    remaining: u64,
+}
+
+impl Read for RecordReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf
+            .len()
+            .min(self.remaining.try_into().unwrap_or(usize::MAX));
+        if len == 0 {
+            return Ok(0);
+        }
+        let read = read_at(self.file, &mut buf[..len], self.offset)?;
+        self.offset += read as u64;
+        self.remaining -= read as u64;
+        Ok(read)
+    }
+}
+
+/// Reads at `offset` in `file` without moving its cursor, so the readers of different records don't interfere.
+#[cfg(unix)]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    std::os::unix::fs::FileExt::read_at(file, buf, offset)
+}
+
+/// Reads at `offset` in `file`. The cursor is moved, but the records are appended after seeking the end of the file anyway.
+#[cfg(windows)]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    std::os::windows::fs::FileExt::seek_read(file, buf, offset)
+}
+
+/// Syncs the directory containing `path`, so a file renamed to `path` survives a crash.
+#[cfg(unix)]
+fn sync_parent(path: &Path) -> io::Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    File::open(parent)?.sync_all()
+}
+
+/// Directories can't be opened as a `File` to be synced outside of Unix.
+#[cfg(not(unix))]
+fn sync_parent(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+impl<E: fmt::Display> fmt::Display for CheckpointError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::Io(error) => write!(f, "could not use the checkpoint: {}", error),
+            CheckpointError::Chunk { index, error } => {
+                write!(f, "chunk {} failed: {}", index, error)
+            }
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for CheckpointError<E> {}
+
+/// Header of the checkpoint of a job of `chunk_count` chunks.
+fn header(chunk_count: usize) -> Vec<u8> {
+    let mut header = Vec::with_capacity(HEADER_SIZE as usize);
+    header.extend_from_slice(MAGIC);
+    header.push(VERSION);
+    header.extend_from_slice(&(chunk_count as u64).to_le_bytes());
+    header
+}
+
+/// Reads the next complete record, hashing its output without keeping it, and returns its index, the size of its output and its hash. `None` at the end of the file or if the record is cut or corrupted.
+fn read_record(reader: &mut impl Read, chunk_count: usize) -> Option<(usize, u64, u64)> {
+    let index = read_u64(reader).ok()? as usize;
+    let len = read_u64(reader).ok()?;
+    if index >= chunk_count {
+        return None;
+    }
+    let mut output = reader.take(len);
+    let mut buffer = [0u8; 8192];
+    let (mut hash, mut read) = (FNV_OFFSET, 0);
+    loop {
+        let n = output.read(&mut buffer).ok()?;
+        if n == 0 {
+            break;
+        }
+        hash = fnv1a(hash, &buffer[..n]);
+        read += n as u64;
+    }
+    (read == len && read_u64(reader).ok()? == hash).then_some((index, len, hash))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Initial value of the FNV-1a hash.
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+
+/// 64-bit FNV-1a hash of `bytes`, continuing from `hash`, `FNV_OFFSET` for the first bytes.
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
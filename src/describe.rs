@@ -0,0 +1,501 @@
+//! Introspection of pipelines.
+//!
+//! `PipelineAsync::describe` returns a `PipelineDescription` listing the buffers bound by the pipeline and, for each stage, its entry point, its workgroup size and the bindings declared by its shader. It implements `Display`, so it can be logged when the bindings of a shader don't match the types of the pipeline.
+//!
+//! The same reflection is used when a pipeline is generated: the bindings used by each entry point are checked against the buffers of the pipeline, e.g. an input declared as `var<storage, read_write>`, a uniform declared as storage or an input whose type doesn't have the size of `Input`, and the generation panics with a `SgpuError::LayoutMismatch` listing all the mismatches instead of leaving them to the validation of the backend.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::describe::{BindingKind, BufferRole};
+//!
+//! let shader = "
+//!     @group(0) @binding(0) var<uniform> coefficient: u32;
+//!     @group(0) @binding(1) var<storage, read> in: array<u32>;
+//!     @group(0) @binding(2) var<storage, read_write> out: array<u32>;
+//!
+//!     @compute @workgroup_size(64)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[id.x] = coefficient * in[id.x];
+//!     }
+//! ";
+//! let gpu = GpuCompute::new();
+//! let pipeline = gpu.gen_pipeline::<[u32; 64], u32, [u32; 64], 1>(None, [StageDesc::new(shader, "main").with_name("scale")]);
+//! let description = pipeline.describe();
+//! assert_eq!(description.buffers[0].role, BufferRole::Uniform);
+//! assert_eq!(description.buffers[2].size, 256);
+//! assert_eq!(description.stages[0].workgroup_size, Some([64, 1, 1]));
+//! assert_eq!(description.stages[0].bindings[1].kind, BindingKind::Storage { read_only: true });
+//! println!("{}", description);
+//! ```
+use crate::{
+    error::{SgpuError, ShaderDiagnostic, SourceSpan},
+    provider::ShaderSourceProvider,
+    PipelineAsync, ShaderSource, StageDesc,
+};
+use sgpu_compute_core::layout::buffer_layout;
+use std::{borrow::Cow, fmt};
+
+pub use sgpu_compute_core::layout::{
+    BindingDescription, BindingKind, BufferDescription, BufferRole,
+};
+
+/// A stage of the pipeline and what its shader declares. The reflected fields are empty if the shader could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageDescription {
+    pub name: Option<&'static str>,
+    pub entrypoint: &'static str,
+    pub workgroup_size: Option<[u32; 3]>,
+    pub bindings: Vec<BindingDescription>,
+}
+
+/// Description of a pipeline returned by `PipelineAsync::describe`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipelineDescription {
+    pub buffers: Vec<BufferDescription>,
+    pub stages: Vec<StageDescription>,
+}
+
+impl<Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod, const N: usize>
+    PipelineAsync<'_, Input, Uniform, Output, N>
+{
+    /// This method is used to describe the buffers and the stages of the pipeline, see the `describe` module.
+    pub fn describe(&self) -> PipelineDescription {
+        let stages = self
+            .stages
+            .desc
+            .iter()
+            .map(|desc| {
+                let source = stage_source(desc, self.stages.provider.as_deref());
+                let module = source
+                    .ok()
+                    .and_then(|source| parse_stage(desc, &source))
+                    .and_then(|module| crate::specialize::specialize(desc, module));
+                let workgroup_size = module.as_ref().and_then(|module| {
+                    module
+                        .entry_points
+                        .iter()
+                        .find(|entry_point| entry_point.name == desc.entrypoint)
+                        .map(|entry_point| entry_point.workgroup_size)
+                });
+                let bindings = module.map_or_else(Vec::new, |module| {
+                    module
+                        .global_variables
+                        .iter()
+                        .filter_map(|(_, global)| binding_description(&module, global))
+                        .collect()
+                });
+                StageDescription {
+                    name: desc.name,
+                    entrypoint: desc.entrypoint,
+                    workgroup_size,
+                    bindings,
+                }
+            })
+            .collect();
+        PipelineDescription {
+            buffers: buffer_layout(&self.sizes),
+            stages,
+        }
+    }
+}
+
+/// Returns the WGSL source of a stage. The source of a GLSL stage is returned as is, and the source of a SPIR-V stage is empty, its binary is in its `StageDesc`.
+pub(crate) fn stage_source<'a>(
+    desc: &'a StageDesc,
+    provider: Option<&'a dyn ShaderSourceProvider>,
+) -> Result<Cow<'a, str>, SgpuError> {
+    if let ShaderSource::SpirV(_) = desc.source {
+        if !cfg!(feature = "spirv") {
+            return Err(SgpuError::Unsupported(
+                "SPIR-V shaders require the `spirv` feature".into(),
+            ));
+        }
+        if !desc.constants.is_empty() {
+            return Err(SgpuError::Unsupported(format!(
+                "stage {} is a SPIR-V binary, its constants can't be set",
+                desc.name.unwrap_or(desc.entrypoint)
+            )));
+        }
+        return Ok(Cow::Borrowed(""));
+    }
+    if let ShaderSource::Glsl { src } = desc.source {
+        let name = desc.name.unwrap_or(desc.entrypoint);
+        if !cfg!(feature = "glsl") {
+            return Err(SgpuError::Unsupported(
+                "GLSL shaders require the `glsl` feature".into(),
+            ));
+        }
+        if desc.entrypoint != "main" {
+            return Err(SgpuError::Validation(format!(
+                "stage {} is a GLSL shader, its entry point must be `main` instead of `{}`",
+                name, desc.entrypoint
+            )));
+        }
+        if !desc.constants.is_empty() {
+            return Err(SgpuError::Unsupported(format!(
+                "stage {} is a GLSL shader, its constants can't be set",
+                name
+            )));
+        }
+        return Ok(Cow::Borrowed(src));
+    }
+    match provider {
+        Some(provider) => provider.try_source(desc.shader),
+        None => Ok(desc.shader.into()),
+    }
+}
+
+fn binding_description(
+    module: &wgpu::naga::Module,
+    global: &wgpu::naga::GlobalVariable,
+) -> Option<BindingDescription> {
+    let binding = global.binding.as_ref()?;
+    let kind = match global.space {
+        wgpu::naga::AddressSpace::Uniform => BindingKind::Uniform,
+        wgpu::naga::AddressSpace::Storage { access } => BindingKind::Storage {
+            read_only: !access.contains(wgpu::naga::StorageAccess::STORE),
+        },
+        _ => BindingKind::Handle,
+    };
+    let (min_size, runtime_sized) = match kind {
+        BindingKind::Handle => (0, false),
+        _ => (
+            module.types[global.ty].inner.size(module.to_ctx()) as usize,
+            is_runtime_sized(module, global.ty),
+        ),
+    };
+    Some(BindingDescription {
+        group: binding.group,
+        binding: binding.binding,
+        name: global.name.clone(),
+        kind,
+        min_size,
+        runtime_sized,
+    })
+}
+
+/// Whether the type is a runtime-sized array or a struct ending with one.
+fn is_runtime_sized(module: &wgpu::naga::Module, ty: wgpu::naga::Handle<wgpu::naga::Type>) -> bool {
+    match &module.types[ty].inner {
+        wgpu::naga::TypeInner::Array {
+            size: wgpu::naga::ArraySize::Dynamic,
+            ..
+        } => true,
+        wgpu::naga::TypeInner::Struct { members, .. } => members
+            .last()
+            .is_some_and(|member| is_runtime_sized(module, member.ty)),
+        _ => false,
+    }
+}
+
+/// Parses the shader of a stage, WGSL, SPIR-V or GLSL, `None` if it can't be parsed.
+fn parse_stage(desc: &StageDesc, source: &str) -> Option<wgpu::naga::Module> {
+    match desc.source {
+        ShaderSource::Wgsl => wgpu::naga::front::wgsl::parse_str(source).ok(),
+        #[cfg(feature = "spirv")]
+        ShaderSource::SpirV(words) => wgpu::naga::front::spv::parse_u8_slice(
+            bytemuck::cast_slice(words),
+            &wgpu::naga::front::spv::Options::default(),
+        )
+        .ok(),
+        #[cfg(not(feature = "spirv"))]
+        ShaderSource::SpirV(_) => None,
+        #[cfg(feature = "glsl")]
+        ShaderSource::Glsl { .. } => wgpu::naga::front::glsl::Frontend::default()
+            .parse(
+                &wgpu::naga::front::glsl::Options::from(wgpu::naga::ShaderStage::Compute),
+                source,
+            )
+            .ok(),
+        #[cfg(not(feature = "glsl"))]
+        ShaderSource::Glsl { .. } => None,
+    }
+}
+
+/// Parses and validates the shader of a WGSL or GLSL stage, returning an `SgpuError::InvalidShader` locating the first error in `source`, then checks that it has the compute entry point of the stage. SPIR-V binaries are only checked for the entry point.
+pub(crate) fn diagnose_stage(desc: &StageDesc, source: &str) -> Result<(), SgpuError> {
+    let stage = desc.name.unwrap_or(desc.entrypoint).to_owned();
+    let span = |span: wgpu::naga::Span, label: &str| {
+        let location = span.location(source);
+        SourceSpan {
+            line: location.line_number,
+            column: location.line_position,
+            length: location.length,
+            label: label.to_owned(),
+            excerpt: source
+                .lines()
+                .nth(location.line_number as usize - 1)
+                .unwrap_or_default()
+                .to_owned(),
+        }
+    };
+    let module = match desc.source {
+        ShaderSource::Wgsl => {
+            wgpu::naga::front::wgsl::parse_str(source).map_err(|error| ShaderDiagnostic {
+                stage: stage.clone(),
+                message: error.message().to_owned(),
+                spans: error
+                    .labels()
+                    .map(|(location, label)| span(location, label))
+                    .collect(),
+            })
+        }
+        #[cfg(feature = "glsl")]
+        ShaderSource::Glsl { .. } => wgpu::naga::front::glsl::Frontend::default()
+            .parse(
+                &wgpu::naga::front::glsl::Options::from(wgpu::naga::ShaderStage::Compute),
+                source,
+            )
+            .map_err(|errors| ShaderDiagnostic {
+                stage: stage.clone(),
+                message: errors
+                    .errors
+                    .iter()
+                    .map(|error| error.kind.to_string())
+                    .collect::<Vec<_>>()
+                    .join("; "),
+                spans: errors
+                    .errors
+                    .iter()
+                    .filter(|error| error.meta.is_defined())
+                    .map(|error| span(error.meta, &error.kind.to_string()))
+                    .collect(),
+            }),
+        _ => match parse_stage(desc, source) {
+            Some(module) => return check_entry_point(desc, &module),
+            None => return Ok(()),
+        },
+    }
+    .map_err(|diagnostic| SgpuError::InvalidShader(Box::new(diagnostic)))?;
+    wgpu::naga::valid::Validator::new(
+        wgpu::naga::valid::ValidationFlags::all(),
+        wgpu::naga::valid::Capabilities::all(),
+    )
+    .validate(&module)
+    .map_err(|error| {
+        let mut message = error.as_inner().to_string();
+        let mut cause = std::error::Error::source(error.as_inner());
+        while let Some(error) = cause {
+            message = format!("{}: {}", message, error);
+            cause = error.source();
+        }
+        SgpuError::InvalidShader(Box::new(ShaderDiagnostic {
+            stage,
+            message,
+            spans: error
+                .spans()
+                .map(|(location, label)| span(*location, label))
+                .collect(),
+        }))
+    })?;
+    check_entry_point(desc, &module)?;
+    crate::specialize::check_constants(desc, &module)
+}
+
+/// Returns an `SgpuError::Validation` listing the compute entry points of the module if the entry point of the stage isn't one of them.
+fn check_entry_point(desc: &StageDesc, module: &wgpu::naga::Module) -> Result<(), SgpuError> {
+    let available = module
+        .entry_points
+        .iter()
+        .filter(|entry_point| entry_point.stage == wgpu::naga::ShaderStage::Compute)
+        .map(|entry_point| entry_point.name.as_str())
+        .collect::<Vec<_>>();
+    if available.contains(&desc.entrypoint) {
+        return Ok(());
+    }
+    let stage = desc
+        .name
+        .map(|name| format!(" in stage `{}`", name))
+        .unwrap_or_default();
+    Err(SgpuError::Validation(format!(
+        "entry point `{}` not found{}; available: [{}]",
+        desc.entrypoint,
+        stage,
+        available.join(", ")
+    )))
+}
+
+/// Returns the bindings used by the entry point of a stage, or `None` if its shader can't be parsed or validated.
+fn used_bindings(desc: &StageDesc, source: &str) -> Option<Vec<BindingDescription>> {
+    let module = parse_stage(desc, source)?;
+    let info = wgpu::naga::valid::Validator::new(
+        wgpu::naga::valid::ValidationFlags::all(),
+        wgpu::naga::valid::Capabilities::all(),
+    )
+    .validate(&module)
+    .ok()?;
+    let index = module
+        .entry_points
+        .iter()
+        .position(|entry_point| entry_point.name == desc.entrypoint)?;
+    let entry_point = info.get_entry_point(index);
+    Some(
+        module
+            .global_variables
+            .iter()
+            .filter(|(handle, _)| !entry_point[*handle].is_empty())
+            .filter_map(|(_, global)| binding_description(&module, global))
+            .collect(),
+    )
+}
+
+/// Whether a stage uses the shared uniform of the device, see the `shared` module.
+pub(crate) fn uses_shared_uniform(stages: &[StageDesc], sources: &[Cow<'_, str>]) -> bool {
+    stages.iter().zip(sources).any(|(desc, source)| {
+        used_bindings(desc, source).is_some_and(|bindings| {
+            bindings
+                .iter()
+                .any(|binding| binding.group == crate::shared::GROUP)
+        })
+    })
+}
+
+/// Checks that the bindings used by the entry point of each stage match the buffers of the pipeline: the uniform must be declared as `var<uniform>`, the input as `var<storage, read>` and the scratchpad and the output as storage, and no other binding of the group 0 may be used. The type of a binding must fit in its buffer and, if it isn't runtime-sized, have the size of the input or of the output it is bound to. The only binding of the other groups is the shared uniform, `@group(1) @binding(0) var<uniform>`. Shaders which can't be parsed or validated are skipped, the device reports their errors when they are compiled.
+pub(crate) fn check_bindings(
+    buffers: &[BufferDescription],
+    stages: &[StageDesc],
+    sources: &[Cow<'_, str>],
+) -> Result<(), SgpuError> {
+    let mut mismatches = Vec::new();
+    // The first output is the `Output` of the pipeline, the next ones are its extra outputs.
+    let output_binding = buffers
+        .iter()
+        .find(|buffer| buffer.role == BufferRole::Output)
+        .map_or(u32::MAX, |buffer| buffer.binding);
+    for (desc, source) in stages.iter().zip(sources) {
+        let Some(bindings) = used_bindings(desc, source) else {
+            continue;
+        };
+        let stage = desc.name.unwrap_or(desc.entrypoint);
+        for binding in bindings {
+            let name = binding.name.as_deref().unwrap_or("_");
+            if binding.group != 0 {
+                if binding.group != crate::shared::GROUP
+                    || binding.binding != 0
+                    || binding.kind != BindingKind::Uniform
+                {
+                    mismatches.push(format!(
+                        "stage `{}` uses @group({}) @binding({}) `{}` but the only binding outside of the group 0 is the shared uniform, @group({}) @binding(0) var<uniform>",
+                        stage,
+                        binding.group,
+                        binding.binding,
+                        name,
+                        crate::shared::GROUP
+                    ));
+                }
+                continue;
+            }
+            let Some(buffer) = buffers
+                .iter()
+                .find(|buffer| buffer.binding == binding.binding)
+            else {
+                mismatches.push(format!(
+                    "stage `{}` uses @binding({}) `{}` but the pipeline only binds {} buffer(s)",
+                    stage,
+                    binding.binding,
+                    name,
+                    buffers.len()
+                ));
+                continue;
+            };
+            let expected = match buffer.role {
+                BufferRole::Uniform => "var<uniform>",
+                BufferRole::Input => "var<storage, read>",
+                BufferRole::Scratchpad | BufferRole::Output => "var<storage>",
+            };
+            let matches = match (buffer.role, binding.kind) {
+                (BufferRole::Uniform, BindingKind::Uniform) => true,
+                (BufferRole::Input, BindingKind::Storage { read_only }) => read_only,
+                (BufferRole::Scratchpad | BufferRole::Output, BindingKind::Storage { .. }) => true,
+                _ => false,
+            };
+            if !matches {
+                mismatches.push(format!(
+                    "stage `{}` declares @binding({}) `{}` as {} but it is the {:?} buffer, which must be declared as {}",
+                    stage,
+                    binding.binding,
+                    name,
+                    binding.kind,
+                    buffer.role,
+                    expected
+                ));
+                continue;
+            }
+            // The scratchpads are often larger than the part a stage uses and the uniforms are often padded to 16 bytes, the input and the outputs hold whole Rust types.
+            let exact = !binding.runtime_sized
+                && matches!(buffer.role, BufferRole::Input | BufferRole::Output);
+            if binding.min_size > buffer.size || (exact && binding.min_size != buffer.size) {
+                mismatches.push(format!(
+                    "stage `{}` declares @binding({}) `{}` with {}{} bytes but the {:?} buffer has {} bytes{}",
+                    stage,
+                    binding.binding,
+                    name,
+                    if binding.runtime_sized { "at least " } else { "" },
+                    binding.min_size,
+                    buffer.role,
+                    buffer.size,
+                    match buffer.role {
+                        BufferRole::Uniform => ", the size of `Uniform`",
+                        BufferRole::Input => ", the size of `Input`",
+                        BufferRole::Output if buffer.binding == output_binding => ", the size of `Output`",
+                        _ => "",
+                    }
+                ));
+            }
+        }
+    }
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(SgpuError::LayoutMismatch(mismatches.join("; ")))
+    }
+}
+
+impl fmt::Display for PipelineDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Pipeline with {} stage(s)", self.stages.len())?;
+        writeln!(f, "  buffers:")?;
+        for buffer in &self.buffers {
+            writeln!(
+                f,
+                "    @binding({}) {:?}: {} bytes",
+                buffer.binding, buffer.role, buffer.size
+            )?;
+        }
+        for (i, stage) in self.stages.iter().enumerate() {
+            write!(
+                f,
+                "  stage {} `{}` (entry point `{}`",
+                i,
+                stage.name.unwrap_or("unnamed"),
+                stage.entrypoint
+            )?;
+            match stage.workgroup_size {
+                Some([x, y, z]) => writeln!(f, ", workgroup size {}x{}x{})", x, y, z)?,
+                None => writeln!(f, ", not reflected)")?,
+            }
+            for binding in &stage.bindings {
+                let kind = match binding.kind {
+                    BindingKind::Uniform => "uniform",
+                    BindingKind::Storage { read_only: true } => "storage, read",
+                    BindingKind::Storage { read_only: false } => "storage, read_write",
+                    BindingKind::Handle => "handle",
+                };
+                write!(
+                    f,
+                    "    @group({}) @binding({}) {} ({}",
+                    binding.group,
+                    binding.binding,
+                    binding.name.as_deref().unwrap_or("_"),
+                    kind
+                )?;
+                match (binding.kind, binding.runtime_sized) {
+                    (BindingKind::Handle, _) => writeln!(f, ")")?,
+                    (_, true) => writeln!(f, ", at least {} bytes)", binding.min_size)?,
+                    (_, false) => writeln!(f, ", {} bytes)", binding.min_size)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
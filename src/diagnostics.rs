@@ -0,0 +1,27 @@
+//! Diagnostics through the `log` crate, emitted when the `log` feature is enabled.
+//!
+//! The macros expand to nothing without the feature, but their arguments are still type-checked, so the call sites don't need to be gated.
+
+macro_rules! log_debug {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        log::debug!(target: "sgpu_compute", $($arg)+);
+        #[cfg(not(feature = "log"))]
+        if false {
+            let _ = format_args!($($arg)+);
+        }
+    };
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        log::warn!(target: "sgpu_compute", $($arg)+);
+        #[cfg(not(feature = "log"))]
+        if false {
+            let _ = format_args!($($arg)+);
+        }
+    };
+}
+
+pub(crate) use {log_debug, log_warn};
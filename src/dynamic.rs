@@ -0,0 +1,352 @@
+//! Pipelines whose input and output lengths are chosen at runtime.
+//!
+//! `PipelineAsync` bakes the sizes of its buffers into its `Pod` types, so every problem size is a new type and the input and the output are arrays on the stack. `GpuComputeAsync::gen_pipeline_dyn` takes the numbers of elements instead: `DynPipelineAsync::run` accepts a slice of `T` of exactly this length and gives the output to the callback as a slice of `V`. The uniform stays a `Pod` type, and the stages are checked and compiled like the ones of `GpuComputeAsync::gen_pipeline`.
+//!
+//! The buffers are copied as whole 4-byte words, so the size in bytes of the input and of the output must be a multiple of 4.
+//!
+//! When the size of the problem changes, `DynPipelineAsync::resize_input`, `DynPipelineAsync::resize_output` and `DynPipelineAsync::resize_scratchpad` reallocate the buffers and rebuild the bind group, without recompiling the stages.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//!
+//! let shader = "
+//!     @group(0) @binding(0) var<uniform> scale: f32;
+//!     @group(0) @binding(1) var<storage, read> in: array<f32>;
+//!     @group(0) @binding(2) var<storage, read_write> out: array<f32>;
+//!
+//!     @compute @workgroup_size(64)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         if id.x < arrayLength(&out) {
+//!             out[id.x] = in[id.x] * scale;
+//!         }
+//!     }
+//! ";
+//! let len = 1000;
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline_dyn::<f32, f32, f32, 1>(len, len, None, [StageDesc::new(shader, "main")]);
+//! pipeline.write_uniform(&0.5);
+//! let input = (0..len).map(|i| i as f32).collect::<Vec<_>>();
+//! let output = pipeline.run_to_vec(&input, [(len.div_ceil(64) as u32, 1, 1)]);
+//! assert_eq!(output[999], 499.5);
+//! ```
+use crate::{
+    error::SgpuError, options::ZeroInit, provider::ShaderSourceProvider, BufferSizes, Buffers,
+    CompiledStages, GpuComputeAsync, GpuRef, StageDesc,
+};
+use sgpu_compute_core::layout::buffer_layout;
+use std::{marker::PhantomData, num::NonZeroUsize, sync::Arc};
+
+/// Pipeline with an input of `input_len` values of `T`, a uniform `U` and an output of `output_len` values of `V`, see the module documentation.
+pub struct DynPipelineAsync<
+    'a,
+    T: bytemuck::Pod,
+    U: bytemuck::Pod,
+    V: bytemuck::Pod,
+    const N: usize,
+> {
+    buffers: Buffers,
+    stages: CompiledStages<N>,
+    input_len: usize,
+    output_len: usize,
+    zero_init: ZeroInit,
+    device: GpuRef<'a>,
+    _phantom: PhantomData<(T, U, V)>,
+}
+
+impl GpuComputeAsync {
+    /// Same as `GpuComputeAsync::gen_pipeline`, but the input is `input_len` values of `T` and the output is `output_len` values of `V`, see the `dynamic` module. An input length of zero skips the binding of the input, like a zero-sized input.
+    ///
+    /// # Panics
+    /// If `output_len` is zero, if the size in bytes of the input or of the output is not a multiple of 4, or in the same cases as `GpuComputeAsync::gen_pipeline`.
+    pub async fn gen_pipeline_dyn<
+        T: bytemuck::Pod,
+        U: bytemuck::Pod,
+        V: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        input_len: usize,
+        output_len: usize,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> DynPipelineAsync<'_, T, U, V, N> {
+        self.try_gen_pipeline_dyn(input_len, output_len, scratchpad_size, stages)
+            .await
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Same as `GpuComputeAsync::gen_pipeline_dyn`, but the errors of the shaders and of the lengths are returned instead of panicking.
+    pub async fn try_gen_pipeline_dyn<
+        T: bytemuck::Pod,
+        U: bytemuck::Pod,
+        V: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        input_len: usize,
+        output_len: usize,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> Result<DynPipelineAsync<'_, T, U, V, N>, SgpuError> {
+        self.gen_pipeline_dyn_from(input_len, output_len, scratchpad_size, stages, None)
+            .await
+    }
+
+    /// Same as `GpuComputeAsync::try_gen_pipeline_dyn`, with the shader source provider of the stages, if any.
+    pub(crate) async fn gen_pipeline_dyn_from<
+        T: bytemuck::Pod,
+        U: bytemuck::Pod,
+        V: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        input_len: usize,
+        output_len: usize,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+        provider: Option<Arc<dyn ShaderSourceProvider>>,
+    ) -> Result<DynPipelineAsync<'_, T, U, V, N>, SgpuError> {
+        let sizes = BufferSizes {
+            uniform: std::mem::size_of::<U>(),
+            scratchpad: scratchpad_size,
+            scratchpads: Vec::new(),
+            input: input_len * std::mem::size_of::<T>(),
+            output: output_len * std::mem::size_of::<V>(),
+            extra_outputs: Vec::new(),
+            push_constants: 0,
+        };
+        check_sizes(&sizes)?;
+        let stages = self.compile_stages(&sizes, stages, provider).await?;
+        let buffers = self.create_buffers(&sizes, &stages.bindgroup_layout, self.zero_init);
+        Ok(DynPipelineAsync {
+            buffers,
+            stages,
+            input_len,
+            output_len,
+            zero_init: self.zero_init,
+            device: GpuRef::Borrowed(self),
+            _phantom: PhantomData,
+        })
+    }
+}
+
+/// Checks that the output isn't empty and that the input and the output can be copied as whole 4-byte words.
+pub(crate) fn check_sizes(sizes: &BufferSizes) -> Result<(), SgpuError> {
+    if sizes.output == 0 {
+        return Err(SgpuError::LayoutMismatch(
+            "the output of a pipeline can't be empty".into(),
+        ));
+    }
+    for (role, size) in [("input", sizes.input), ("output", sizes.output)] {
+        if !size.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT as usize) {
+            return Err(SgpuError::LayoutMismatch(format!(
+                "the {} is {} bytes, it must be a multiple of {} bytes",
+                role,
+                size,
+                wgpu::COPY_BUFFER_ALIGNMENT
+            )));
+        }
+    }
+    Ok(())
+}
+
+impl<T: bytemuck::Pod, U: bytemuck::Pod, V: bytemuck::Pod, const N: usize>
+    DynPipelineAsync<'_, T, U, V, N>
+{
+    /// Number of values of `T` expected by `DynPipelineAsync::run`.
+    #[inline]
+    pub fn input_len(&self) -> usize {
+        self.input_len
+    }
+
+    /// Number of values of `V` given to the callback of `DynPipelineAsync::run`.
+    #[inline]
+    pub fn output_len(&self) -> usize {
+        self.output_len
+    }
+
+    /// This method is used to write the uniform buffer. It is useful to change the uniform between runs.
+    #[inline]
+    pub fn write_uniform(&mut self, uniform: &U) {
+        self.device.queue.write_buffer(
+            self.buffers.uniform.as_ref().expect("No uniforms"),
+            0,
+            bytemuck::bytes_of(uniform),
+        )
+    }
+
+    /// Size in bytes of the scratchpad, `None` if the pipeline has none.
+    #[inline]
+    pub fn scratchpad_size(&self) -> Option<NonZeroUsize> {
+        self.buffers
+            .scratchpad
+            .as_ref()
+            .and_then(|buffer| NonZeroUsize::new(buffer.size() as usize))
+    }
+
+    /// This method is used to change the number of input values, reallocating the input buffer but keeping the compiled stages, see `DynPipelineAsync::try_resize`.
+    ///
+    /// # Panics
+    /// In the cases where `DynPipelineAsync::try_resize` returns an error.
+    pub fn resize_input(&mut self, input_len: usize) {
+        self.try_resize(input_len, self.output_len, self.scratchpad_size())
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Same as `DynPipelineAsync::resize_input`, for the number of output values.
+    pub fn resize_output(&mut self, output_len: usize) {
+        self.try_resize(self.input_len, output_len, self.scratchpad_size())
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Same as `DynPipelineAsync::resize_input`, for the size in bytes of the scratchpad.
+    pub fn resize_scratchpad(&mut self, scratchpad_size: NonZeroUsize) {
+        self.try_resize(self.input_len, self.output_len, Some(scratchpad_size))
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// This method is used to change the lengths of the input and of the output and the size of the scratchpad without recompiling the stages, e.g. when the size of the problem changes occasionally. The buffers whose size changes are reallocated and zeroed, the other ones, and the uniform, keep their content. The bindings of the stages are checked again against the new sizes.
+    ///
+    /// # Errors
+    /// `SgpuError::LayoutMismatch` if the new sizes don't match the bindings of the stages, in the cases of `GpuComputeAsync::try_gen_pipeline_dyn`, or if the input or the scratchpad would be added or removed, which changes the layout of the stages.
+    pub fn try_resize(
+        &mut self,
+        input_len: usize,
+        output_len: usize,
+        scratchpad_size: Option<NonZeroUsize>,
+    ) -> Result<(), SgpuError> {
+        let sizes = BufferSizes {
+            uniform: std::mem::size_of::<U>(),
+            scratchpad: scratchpad_size,
+            scratchpads: Vec::new(),
+            input: input_len * std::mem::size_of::<T>(),
+            output: output_len * std::mem::size_of::<V>(),
+            extra_outputs: Vec::new(),
+            push_constants: 0,
+        };
+        check_sizes(&sizes)?;
+        if (sizes.input > 0) != self.buffers.input.is_some()
+            || scratchpad_size.is_some() != self.buffers.scratchpad.is_some()
+        {
+            return Err(SgpuError::LayoutMismatch(
+                "a resize can't add or remove the input or the scratchpad, generate a new pipeline instead".into(),
+            ));
+        }
+        let sources = self
+            .stages
+            .desc
+            .iter()
+            .map(|desc| crate::describe::stage_source(desc, self.stages.provider.as_deref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        crate::describe::check_bindings(&buffer_layout(&sizes), &self.stages.desc, &sources)?;
+
+        let mut buffers =
+            self.device
+                .create_buffers(&sizes, &self.stages.bindgroup_layout, self.zero_init);
+        let old = &mut self.buffers;
+        std::mem::swap(&mut buffers.uniform, &mut old.uniform);
+        if buffers.input.as_ref().map(wgpu::Buffer::size)
+            == old.input.as_ref().map(wgpu::Buffer::size)
+        {
+            std::mem::swap(&mut buffers.input, &mut old.input);
+        }
+        if buffers.scratchpad.as_ref().map(|buffer| buffer.size())
+            == old.scratchpad.as_ref().map(|buffer| buffer.size())
+        {
+            std::mem::swap(&mut buffers.scratchpad, &mut old.scratchpad);
+        }
+        if buffers.staging.size() == old.staging.size() {
+            std::mem::swap(&mut buffers.staging, &mut old.staging);
+            std::mem::swap(&mut buffers.output, &mut old.output);
+        }
+        buffers.rebind(&self.device, &self.stages.bindgroup_layout);
+        self.buffers = buffers;
+        self.input_len = input_len;
+        self.output_len = output_len;
+        Ok(())
+    }
+
+    /// Same as `PipelineAsync::run`, but the input is a slice of `T` and the callback gets the output as a slice of `V`.
+    ///
+    /// # Panics
+    /// If `input` doesn't have `DynPipelineAsync::input_len` values.
+    pub async fn run<R: Send + 'static>(
+        &mut self,
+        input: &[T],
+        workgroups: [(u32, u32, u32); N],
+        callback: impl FnOnce(&[V]) -> R + Send,
+    ) -> R {
+        assert_eq!(
+            input.len(),
+            self.input_len,
+            "Expected {} input values, got {}",
+            self.input_len,
+            input.len()
+        );
+        if let Some(buffer) = &self.buffers.input {
+            self.device
+                .queue
+                .write_buffer(buffer, 0, bytemuck::cast_slice(input));
+        }
+        let mut encoder = self
+            .device
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        if self.zero_init == ZeroInit::BeforeEachRun {
+            self.buffers.clear(&mut encoder, false);
+        }
+        self.stages.record_passes(
+            &mut encoder,
+            &self.buffers.bindgroup,
+            &self.device,
+            &workgroups,
+        );
+        encoder.copy_buffer_to_buffer(
+            &self.buffers.staging,
+            0,
+            &self.buffers.output,
+            0,
+            self.buffers.output.size(),
+        );
+        self.device.queue.submit(Some(encoder.finish()));
+        let (sender, receiver) = flume::bounded(1);
+        self.buffers
+            .output
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |e| {
+                e.expect("Could not map buffer");
+                sender.send(()).unwrap()
+            });
+        self.device.wait_submitted();
+        receiver.recv_async().await.expect("Error with channel");
+        let res = callback(bytemuck::cast_slice(
+            self.buffers.output.slice(..).get_mapped_range().as_ref(),
+        ));
+        self.buffers.output.unmap();
+        res
+    }
+
+    /// Same as `DynPipelineAsync::run`, but the output is returned as a `Vec`.
+    pub async fn run_to_vec(&mut self, input: &[T], workgroups: [(u32, u32, u32); N]) -> Vec<V>
+    where
+        V: Send,
+    {
+        self.run(input, workgroups, <[V]>::to_vec).await
+    }
+
+    /// Same as `DynPipelineAsync::run`, but the output is copied into `out`, reusing its capacity, see `PipelineAsync::run_readback_into_vec`.
+    pub async fn run_readback_into_vec(
+        &mut self,
+        input: &[T],
+        workgroups: [(u32, u32, u32); N],
+        out: &mut Vec<V>,
+    ) where
+        V: Send,
+    {
+        self.run(input, workgroups, |output| {
+            out.clear();
+            out.extend_from_slice(output);
+        })
+        .await
+    }
+}
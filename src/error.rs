@@ -0,0 +1,174 @@
+//! Errors of the crate.
+//!
+//! `SgpuError` separates the failures that may succeed when retried (out of memory, device lost) from the fatal ones (validation, layout mismatch), which are bugs in the shaders or in the types given to the pipeline. `SgpuError::is_retryable` lets callers implement their retry policy without matching the error messages.
+//!
+//! ```rust
+//! use sgpu_compute::error::SgpuError;
+//!
+//! fn run_with_retries<T>(mut run: impl FnMut() -> Result<T, SgpuError>) -> Result<T, SgpuError> {
+//!     let mut attempts = 0;
+//!     loop {
+//!         match run() {
+//!             Err(e) if e.is_retryable() && attempts < 3 => attempts += 1,
+//!             res => return res,
+//!         }
+//!     }
+//! }
+//!
+//! let mut failures = 2;
+//! let res = run_with_retries(|| {
+//!     if failures > 0 {
+//!         failures -= 1;
+//!         Err(SgpuError::OutOfMemory)
+//!     } else {
+//!         Ok(42)
+//!     }
+//! });
+//! assert_eq!(res, Ok(42));
+//! ```
+use std::fmt;
+
+/// Error of a GPU operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SgpuError {
+    /// The device could not allocate a resource. Retryable, e.g. once other resources are dropped.
+    OutOfMemory,
+    /// The device was lost, e.g. after a driver reset. Retryable on a new device.
+    DeviceLost(String),
+    /// The device rejected a shader, a pipeline or a command. Fatal.
+    Validation(String),
+    /// The bindings declared by the shaders don't match the types given to the pipeline. Fatal.
+    LayoutMismatch(String),
+    /// No adapter is available, e.g. on a machine without GPU or drivers. Fatal.
+    NoAdapter,
+    /// The adapter could not create the device. Retryable, e.g. with another adapter.
+    DeviceRequestFailed(String),
+    /// A shader could not be compiled. Fatal.
+    ShaderCompilation(String),
+    /// The shader of a stage could not be parsed or validated, with the location of the error in its source. Fatal.
+    InvalidShader(Box<ShaderDiagnostic>),
+    /// The adapter doesn't satisfy the required limits or features. Fatal on this adapter.
+    Unsupported(String),
+    /// A run didn't complete within its timeout, e.g. because of a shader looping forever. Fatal.
+    TimedOut(std::time::Duration),
+    /// A compute graph is malformed, e.g. it has a cycle or a buffer written twice. Fatal.
+    InvalidGraph(String),
+}
+
+impl SgpuError {
+    /// Whether the operation may succeed if it is retried, possibly on a new device.
+    #[inline]
+    pub const fn is_retryable(&self) -> bool {
+        match self {
+            SgpuError::OutOfMemory
+            | SgpuError::DeviceLost(_)
+            | SgpuError::DeviceRequestFailed(_) => true,
+            SgpuError::Validation(_)
+            | SgpuError::LayoutMismatch(_)
+            | SgpuError::NoAdapter
+            | SgpuError::ShaderCompilation(_)
+            | SgpuError::InvalidShader(_)
+            | SgpuError::Unsupported(_)
+            | SgpuError::TimedOut(_)
+            | SgpuError::InvalidGraph(_) => false,
+        }
+    }
+
+    /// Whether retrying the operation will fail again, this is the opposite of `SgpuError::is_retryable`.
+    #[inline]
+    pub const fn is_fatal(&self) -> bool {
+        !self.is_retryable()
+    }
+}
+
+impl fmt::Display for SgpuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SgpuError::OutOfMemory => write!(f, "out of GPU memory"),
+            SgpuError::DeviceLost(reason) => write!(f, "device lost: {}", reason),
+            SgpuError::Validation(description) => write!(f, "validation error: {}", description),
+            SgpuError::LayoutMismatch(description) => {
+                write!(f, "layout mismatch: {}", description)
+            }
+            SgpuError::NoAdapter => write!(f, "GPU not available"),
+            SgpuError::DeviceRequestFailed(reason) => {
+                write!(f, "device request failed: {}", reason)
+            }
+            SgpuError::ShaderCompilation(description) => {
+                write!(f, "shader compilation failed: {}", description)
+            }
+            SgpuError::InvalidShader(diagnostic) => {
+                write!(f, "shader compilation failed in {}", diagnostic)
+            }
+            SgpuError::Unsupported(description) => write!(f, "unsupported: {}", description),
+            SgpuError::TimedOut(timeout) => write!(f, "run timed out after {:?}", timeout),
+            SgpuError::InvalidGraph(description) => write!(f, "invalid graph: {}", description),
+        }
+    }
+}
+
+/// Error in the shader of a stage, see `SgpuError::InvalidShader`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShaderDiagnostic {
+    /// Name of the stage, or its index if it has none.
+    pub stage: String,
+    /// Description of the error, followed by its causes.
+    pub message: String,
+    /// Parts of the source the error refers to, the first one being the main one. Empty if the error isn't located.
+    pub spans: Vec<SourceSpan>,
+}
+
+/// Part of the source of a shader, with what it has to do with the error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceSpan {
+    /// Line of the start of the span, starting at 1.
+    pub line: u32,
+    /// Column of the start of the span in characters, starting at 1.
+    pub column: u32,
+    /// Length of the span in bytes.
+    pub length: u32,
+    pub label: String,
+    /// Line of the source where the span starts.
+    pub excerpt: String,
+}
+
+impl fmt::Display for ShaderDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "stage `{}`: {}", self.stage, self.message)?;
+        for span in &self.spans {
+            let excerpt = span.excerpt.trim_end();
+            let column = (span.column as usize).saturating_sub(1);
+            let rest = excerpt.chars().count().saturating_sub(column);
+            let width = (span.length as usize).clamp(1, rest.max(1));
+            write!(f, "\n  --> {}:{}", span.line, span.column)?;
+            if !span.label.is_empty() {
+                write!(f, ": {}", span.label)?;
+            }
+            write!(
+                f,
+                "\n   | {}\n   | {}{}",
+                excerpt,
+                " ".repeat(column),
+                "^".repeat(width)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SgpuError {}
+
+impl From<wgpu::Error> for SgpuError {
+    fn from(error: wgpu::Error) -> Self {
+        match error {
+            wgpu::Error::OutOfMemory { .. } => SgpuError::OutOfMemory,
+            wgpu::Error::Validation { description, .. }
+            | wgpu::Error::Internal { description, .. } => SgpuError::Validation(description),
+        }
+    }
+}
+
+/// I/O error of a file whose content is invalid, e.g. a checkpoint or a serialized array which is truncated or has another format.
+pub(crate) fn invalid_data(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
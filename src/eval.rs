@@ -0,0 +1,194 @@
+//! Evaluation of a single WGSL function over test vectors.
+//!
+//! `GpuComputeAsync::eval_function` wraps a WGSL function into a generated elementwise kernel and returns its results over the given arguments, so that the functions of a shader can be unit-tested against a Rust reference implementation in isolation. The arguments and the result are `WgslType`s, the scalars and the 2 and 4 components vectors of `u32`, `i32` and `f32`, and several arguments are given as a tuple. The source must only contain the function and what it uses, e.g. other functions, constants and structs, but no bindings: the generated kernel declares its own, prefixed by `sgpu_`.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//!
+//! let source = "
+//!     fn lerp(a: f32, b: f32, t: f32) -> f32 {
+//!         return a + (b - a) * t;
+//!     }
+//! ";
+//! let args = [(0.0f32, 10.0f32, 0.5f32), (1.0, 3.0, 0.25), (-1.0, 1.0, 1.0)];
+//! let gpu = GpuCompute::new();
+//! let results: Vec<f32> = gpu.eval_function(source, "lerp", &args);
+//! let expected: Vec<f32> = args.iter().map(|(a, b, t)| a + (b - a) * t).collect();
+//! assert_eq!(results, expected);
+//! ```
+use crate::{GpuComputeAsync, StageDesc};
+
+/// Number of `u32` words of the input and of the output of a run, larger arguments are evaluated over several runs.
+const CHUNK_WORDS: usize = 1 << 14;
+
+const WORKGROUP_SIZE: u32 = 64;
+
+const STAGES: [StageDesc; 1] = [StageDesc::new("eval", "sgpu_eval").with_name("eval")];
+
+/// A value which can be given to or returned by a WGSL function with `GpuComputeAsync::eval_function`.
+pub trait WgslType: bytemuck::Pod {
+    /// Name of the WGSL type.
+    const WGSL: &'static str;
+}
+
+macro_rules! impl_wgsl_type {
+    ($($ty:ty => $wgsl:literal),*) => {
+        $(impl WgslType for $ty {
+            const WGSL: &'static str = $wgsl;
+        })*
+    };
+}
+
+impl_wgsl_type!(
+    u32 => "u32", i32 => "i32", f32 => "f32",
+    [u32; 2] => "vec2<u32>", [i32; 2] => "vec2<i32>", [f32; 2] => "vec2<f32>",
+    [u32; 4] => "vec4<u32>", [i32; 4] => "vec4<i32>", [f32; 4] => "vec4<f32>"
+);
+
+/// Number of `u32` words of a `WgslType`.
+const fn words<T: WgslType>() -> usize {
+    std::mem::size_of::<T>() / std::mem::size_of::<u32>()
+}
+
+/// Arguments of a WGSL function, a single `WgslType` or a tuple of up to 4 of them.
+pub trait WgslArgs: Copy {
+    /// WGSL types of the arguments, with their number of `u32` words.
+    fn types() -> Vec<(&'static str, usize)>;
+
+    /// Appends the arguments as `u32` words.
+    fn write(&self, words: &mut Vec<u32>);
+}
+
+impl<A: WgslType> WgslArgs for A {
+    fn types() -> Vec<(&'static str, usize)> {
+        vec![(A::WGSL, words::<A>())]
+    }
+
+    fn write(&self, words: &mut Vec<u32>) {
+        words.extend_from_slice(bytemuck::cast_slice(bytemuck::bytes_of(self)));
+    }
+}
+
+macro_rules! impl_wgsl_args {
+    ($($arg:ident $index:tt),*) => {
+        impl<$($arg: WgslType),*> WgslArgs for ($($arg,)*) {
+            fn types() -> Vec<(&'static str, usize)> {
+                vec![$(($arg::WGSL, words::<$arg>())),*]
+            }
+
+            fn write(&self, words: &mut Vec<u32>) {
+                $(words.extend_from_slice(bytemuck::cast_slice(bytemuck::bytes_of(&self.$index)));)*
+            }
+        }
+    };
+}
+
+impl_wgsl_args!(A 0);
+impl_wgsl_args!(A 0, B 1);
+impl_wgsl_args!(A 0, B 1, C 2);
+impl_wgsl_args!(A 0, B 1, C 2, D 3);
+
+/// WGSL expression converting the `u32` words at `offset` of `sgpu_in` to `ty`.
+fn load(ty: &str, words: usize, offset: usize) -> String {
+    let words_wgsl = (offset..offset + words)
+        .map(|word| format!("sgpu_in[sgpu_base + {}u]", word))
+        .collect::<Vec<_>>()
+        .join(", ");
+    match words {
+        1 => format!("bitcast<{}>({})", ty, words_wgsl),
+        _ => format!("bitcast<{}>(vec{}<u32>({}))", ty, words, words_wgsl),
+    }
+}
+
+/// Returns the complete shader evaluating `function` of `source` with the arguments `A`, returning `R`.
+pub fn shader<A: WgslArgs, R: WgslType>(source: &str, function: &str) -> String {
+    let types = A::types();
+    let mut offset = 0;
+    let args = types
+        .iter()
+        .map(|(ty, words)| {
+            let arg = load(ty, *words, offset);
+            offset += words;
+            arg
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let store = match words::<R>() {
+        1 => "    sgpu_out[i] = bitcast<u32>(result);".to_string(),
+        words => {
+            let mut store = format!("    let sgpu_words = bitcast<vec{}<u32>>(result);", words);
+            for word in 0..words {
+                store += &format!(
+                    "\n    sgpu_out[i * {}u + {}u] = sgpu_words[{}];",
+                    words, word, word
+                );
+            }
+            store
+        }
+    };
+    format!(
+        "@group(0) @binding(0) var<uniform> sgpu_count: u32;\n@group(0) @binding(1) var<storage, read> sgpu_in: array<u32>;\n@group(0) @binding(2) var<storage, read_write> sgpu_out: array<u32>;\n\n{}\n\n@compute @workgroup_size({})\nfn sgpu_eval(@builtin(global_invocation_id) id: vec3<u32>) {{\n    let i = id.x;\n    if i >= sgpu_count {{\n        return;\n    }}\n    let sgpu_base = i * {}u;\n    let result: {} = {}({});\n{}\n}}\n",
+        source, WORKGROUP_SIZE, offset, R::WGSL, function, args, store
+    )
+}
+
+impl GpuComputeAsync {
+    /// This method is used to evaluate the WGSL function `function` of `source` over each of `args`, see the `eval` module. The shader is compiled on each call.
+    ///
+    /// # Panics
+    /// If the shader doesn't compile, e.g. if the types of the function are not `A` and `R`.
+    pub async fn eval_function<A: WgslArgs, R: WgslType>(
+        &self,
+        source: &str,
+        function: &str,
+        args: &[A],
+    ) -> Vec<R> {
+        let arg_words = A::types().iter().map(|(_, words)| words).sum::<usize>();
+        let chunk = CHUNK_WORDS / arg_words.max(words::<R>()).max(1);
+        let source = shader::<A, R>(source, function);
+        let mut pipeline = self
+            .gen_pipeline_with_provider::<[u32; CHUNK_WORDS], u32, [u32; CHUNK_WORDS], 1>(
+                move |_: &str| source.clone(),
+                None,
+                STAGES,
+            )
+            .await;
+        let mut results = Vec::with_capacity(args.len());
+        let result_words = words::<R>();
+        let mut input = Box::new([0u32; CHUNK_WORDS]);
+        let mut packed = Vec::with_capacity(CHUNK_WORDS);
+        for args in args.chunks(chunk) {
+            packed.clear();
+            args.iter().for_each(|arg| arg.write(&mut packed));
+            input[..packed.len()].copy_from_slice(&packed);
+            pipeline.write_uniform(&(args.len() as u32));
+            let len = args.len() * result_words;
+            let out = pipeline
+                .run(
+                    &input,
+                    [((args.len() as u32).div_ceil(WORKGROUP_SIZE), 1, 1)],
+                    move |out| out[..len].to_vec(),
+                )
+                .await;
+            results.extend(
+                out.chunks_exact(result_words)
+                    .map(|words| bytemuck::pod_read_unaligned::<R>(bytemuck::cast_slice(words))),
+            );
+        }
+        results
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl crate::blocking::GpuCompute {
+    /// Blocking version of `GpuComputeAsync::eval_function`.
+    #[inline]
+    pub fn eval_function<A: WgslArgs, R: WgslType>(
+        &self,
+        source: &str,
+        function: &str,
+        args: &[A],
+    ) -> Vec<R> {
+        pollster::block_on((**self).eval_function(source, function, args))
+    }
+}
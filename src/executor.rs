@@ -0,0 +1,423 @@
+//! Pluggable backends running the same pipelines. It is enabled by the `blocking` feature.
+//!
+//! `Executor` is the minimal interface of a backend: create a pipeline from the sizes of its buffers and its stages, write its uniform, upload its input, dispatch its stages and read back its output. The values are passed as bytes, so the trait is object safe and an application can choose its backend at runtime with a `Box<dyn Executor>`, while `dyn Executor` has typed helpers, `gen_pipeline` and `run`.
+//!
+//! `WgpuExecutor` runs the WGSL stages on a device. `CpuExecutor` runs a Rust closure registered for the entry point of each stage instead, which makes it a reference implementation: a test can run the same code against both backends and compare their outputs.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::executor::{CpuExecutor, Executor, WgpuExecutor};
+//!
+//! let shader = "
+//!     @group(0) @binding(0) var<storage, read> in: array<u32>;
+//!     @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+//!
+//!     @compute @workgroup_size(4)
+//!     fn square(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[id.x] = in[id.x] * in[id.x];
+//!     }
+//! ";
+//! fn squares(executor: &mut dyn Executor, shader: &'static str) -> [u32; 8] {
+//!     let pipeline = executor
+//!         .gen_pipeline::<[u32; 8], (), [u32; 8]>(None, &[StageDesc::new(shader, "square")])
+//!         .unwrap();
+//!     executor.run(pipeline, &[1, 2, 3, 4, 5, 6, 7, 8], &[(2, 1, 1)])
+//! }
+//!
+//! let mut cpu = CpuExecutor::new().with_kernel("square", |buffers, _| {
+//!     let input: &[u32] = bytemuck::cast_slice(buffers.input);
+//!     let output: &mut [u32] = bytemuck::cast_slice_mut(buffers.output);
+//!     for (out, v) in output.iter_mut().zip(input) {
+//!         *out = v * v;
+//!     }
+//! });
+//! let mut gpu = WgpuExecutor::from(GpuCompute::new());
+//! assert_eq!(squares(&mut gpu, shader), squares(&mut cpu, shader));
+//! ```
+use crate::{
+    blocking::GpuCompute, error::SgpuError, options::ZeroInit, BufferSizes, Buffers,
+    GpuComputeAsync, StageDesc, StagePass,
+};
+use std::{collections::HashMap, sync::Arc};
+
+/// Identifier of a pipeline created by an `Executor`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PipelineId(usize);
+
+/// Backend running pipelines, see the module documentation.
+pub trait Executor {
+    /// Creates a pipeline with buffers of the given sizes running `stages` in order.
+    fn create_pipeline(
+        &mut self,
+        sizes: &BufferSizes,
+        stages: &[StageDesc],
+    ) -> Result<PipelineId, SgpuError>;
+
+    /// Writes the uniform of the pipeline. It must have the size given to `Executor::create_pipeline`.
+    fn write_uniform(&mut self, pipeline: PipelineId, uniform: &[u8]);
+
+    /// Writes the push constants of the pipeline, which are set in each pass. They must have the size given to `Executor::create_pipeline`.
+    fn write_push_constants(&mut self, pipeline: PipelineId, push_constants: &[u8]);
+
+    /// Writes the input of the pipeline. It must have the size given to `Executor::create_pipeline`.
+    fn upload(&mut self, pipeline: PipelineId, input: &[u8]);
+
+    /// Runs the stages of the pipeline, one workgroup count per stage. The scratchpad and the output are kept for the next dispatch, unless the backend clears them before each run, like a `WgpuExecutor` on a device with `ZeroInit::BeforeEachRun`.
+    fn dispatch(&mut self, pipeline: PipelineId, workgroups: &[(u32, u32, u32)]);
+
+    /// Copies the output left by the last dispatch into `output`, which must have the size given to `Executor::create_pipeline`.
+    fn readback(&mut self, pipeline: PipelineId, output: &mut [u8]);
+
+    /// Frees the buffers of the pipeline, its id must not be used anymore.
+    fn destroy_pipeline(&mut self, pipeline: PipelineId);
+}
+
+impl dyn Executor + '_ {
+    /// Same as `GpuComputeAsync::gen_pipeline`, but the pipeline is created by the executor. The stages are given as a slice, since the executor is not typed.
+    pub fn gen_pipeline<Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod>(
+        &mut self,
+        scratchpad_size: Option<std::num::NonZeroUsize>,
+        stages: &[StageDesc],
+    ) -> Result<PipelineId, SgpuError> {
+        self.create_pipeline(
+            &BufferSizes::of::<Input, Uniform, Output>(scratchpad_size),
+            stages,
+        )
+    }
+
+    /// Uploads `input`, dispatches the stages and reads back the output.
+    pub fn run<Input: bytemuck::Pod, Output: bytemuck::Pod>(
+        &mut self,
+        pipeline: PipelineId,
+        input: &Input,
+        workgroups: &[(u32, u32, u32)],
+    ) -> Output {
+        self.upload(pipeline, bytemuck::bytes_of(input));
+        self.dispatch(pipeline, workgroups);
+        let mut output = Output::zeroed();
+        self.readback(pipeline, bytemuck::bytes_of_mut(&mut output));
+        output
+    }
+}
+
+/// Pipeline of a `WgpuExecutor`, with a number of stages known at runtime.
+struct WgpuPipeline {
+    buffers: Buffers,
+    stages: Vec<Arc<wgpu::ComputePipeline>>,
+    names: Vec<Option<&'static str>>,
+    push_constants: Vec<u8>,
+    shared_uniform: bool,
+    sizes: BufferSizes,
+}
+
+/// `Executor` running the WGSL stages on the device of a `GpuComputeAsync`.
+pub struct WgpuExecutor {
+    gpu: GpuComputeAsync,
+    pipelines: Vec<Option<WgpuPipeline>>,
+}
+
+impl WgpuExecutor {
+    pub fn new(gpu: GpuComputeAsync) -> Self {
+        Self {
+            gpu,
+            pipelines: Vec::new(),
+        }
+    }
+
+    /// The device running the pipelines.
+    #[inline]
+    pub fn gpu(&self) -> &GpuComputeAsync {
+        &self.gpu
+    }
+
+    fn pipeline(&self, pipeline: PipelineId) -> &WgpuPipeline {
+        self.pipelines[pipeline.0]
+            .as_ref()
+            .expect("Pipeline was destroyed")
+    }
+}
+
+impl From<GpuCompute> for WgpuExecutor {
+    #[inline]
+    fn from(gpu: GpuCompute) -> Self {
+        Self::new(gpu.0)
+    }
+}
+
+impl Executor for WgpuExecutor {
+    fn create_pipeline(
+        &mut self,
+        sizes: &BufferSizes,
+        stages: &[StageDesc],
+    ) -> Result<PipelineId, SgpuError> {
+        let (layout, pipelines, shared_uniform) =
+            pollster::block_on(self.gpu.compile_stage_list(sizes, stages, None))?;
+        let buffers = self.gpu.create_buffers(sizes, &layout, self.gpu.zero_init);
+        self.pipelines.push(Some(WgpuPipeline {
+            buffers,
+            stages: pipelines,
+            names: stages.iter().map(|desc| desc.name).collect(),
+            push_constants: vec![0; sizes.push_constants],
+            shared_uniform,
+            sizes: sizes.clone(),
+        }));
+        Ok(PipelineId(self.pipelines.len() - 1))
+    }
+
+    fn write_uniform(&mut self, pipeline: PipelineId, uniform: &[u8]) {
+        let pipeline = self.pipeline(pipeline);
+        assert_eq!(uniform.len(), pipeline.sizes.uniform, "Wrong uniform size");
+        self.gpu.queue.write_buffer(
+            pipeline.buffers.uniform.as_ref().expect("No uniforms"),
+            0,
+            uniform,
+        );
+    }
+
+    fn write_push_constants(&mut self, pipeline: PipelineId, push_constants: &[u8]) {
+        let pipeline = self.pipelines[pipeline.0]
+            .as_mut()
+            .expect("Pipeline was destroyed");
+        assert_eq!(
+            push_constants.len(),
+            pipeline.push_constants.len(),
+            "Wrong push constants size"
+        );
+        pipeline.push_constants.copy_from_slice(push_constants);
+    }
+
+    fn upload(&mut self, pipeline: PipelineId, input: &[u8]) {
+        let pipeline = self.pipeline(pipeline);
+        assert_eq!(input.len(), pipeline.sizes.input, "Wrong input size");
+        if let Some(buffer) = &pipeline.buffers.input {
+            self.gpu.queue.write_buffer(buffer, 0, input);
+        }
+    }
+
+    fn dispatch(&mut self, pipeline: PipelineId, workgroups: &[(u32, u32, u32)]) {
+        let pipeline = self.pipeline(pipeline);
+        assert_eq!(
+            workgroups.len(),
+            pipeline.stages.len(),
+            "Expected one workgroup count per stage"
+        );
+        let mut encoder = self
+            .gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        if self.gpu.zero_init == ZeroInit::BeforeEachRun {
+            pipeline.buffers.clear(&mut encoder, false);
+        }
+        for (i, ((stage, name), workgroup)) in pipeline
+            .stages
+            .iter()
+            .zip(&pipeline.names)
+            .zip(workgroups)
+            .enumerate()
+        {
+            self.gpu.split_submission(&mut encoder, i, None);
+            StagePass {
+                pipeline: stage,
+                bindgroup: pipeline.buffers.stage_bindgroup(i),
+                push_constants: &pipeline.push_constants,
+                shared_uniform: pipeline.shared_uniform,
+                name: *name,
+                index: i,
+            }
+            .record(&mut encoder, &self.gpu, *workgroup, None, None, None);
+        }
+        self.gpu.queue.submit(Some(encoder.finish()));
+    }
+
+    fn readback(&mut self, pipeline: PipelineId, output: &mut [u8]) {
+        let pipeline = self.pipeline(pipeline);
+        assert_eq!(output.len(), pipeline.sizes.output, "Wrong output size");
+        let buffers = &pipeline.buffers;
+        let mut encoder = self
+            .gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_buffer_to_buffer(
+            &buffers.staging,
+            0,
+            &buffers.output,
+            0,
+            buffers.output.size(),
+        );
+        self.gpu.queue.submit(Some(encoder.finish()));
+        let (sender, receiver) = flume::bounded(1);
+        buffers
+            .output
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |e| {
+                e.expect("Could not map buffer");
+                sender.send(()).unwrap()
+            });
+        self.gpu.wait_polled(&receiver);
+        output.copy_from_slice(&buffers.output.slice(..).get_mapped_range());
+        buffers.output.unmap();
+    }
+
+    fn destroy_pipeline(&mut self, pipeline: PipelineId) {
+        self.pipelines[pipeline.0] = None;
+    }
+}
+
+/// Buffers of a pipeline of a `CpuExecutor`, given to the kernels as bytes. They are aligned to 8 bytes, so they can be cast with `bytemuck::cast_slice` to slices of the element types of WGSL.
+pub struct CpuBuffers<'b> {
+    pub uniform: &'b [u8],
+    pub push_constants: &'b [u8],
+    pub scratchpad: &'b mut [u8],
+    pub input: &'b [u8],
+    pub output: &'b mut [u8],
+}
+
+/// Rust implementation of an entry point, called once per dispatch with the buffers and the number of workgroups.
+type CpuKernel = Box<dyn Fn(CpuBuffers<'_>, (u32, u32, u32)) + Send + Sync>;
+
+/// Zeroed buffer of `size` bytes aligned to 8 bytes.
+#[derive(Clone)]
+struct CpuBuffer {
+    words: Vec<u64>,
+    size: usize,
+}
+
+impl CpuBuffer {
+    fn new(size: usize) -> Self {
+        Self {
+            words: vec![0; size.div_ceil(8)],
+            size,
+        }
+    }
+
+    fn bytes(&self) -> &[u8] {
+        &bytemuck::cast_slice(&self.words)[..self.size]
+    }
+
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        &mut bytemuck::cast_slice_mut(&mut self.words)[..self.size]
+    }
+}
+
+struct CpuPipeline {
+    entrypoints: Vec<&'static str>,
+    uniform: CpuBuffer,
+    push_constants: CpuBuffer,
+    scratchpad: CpuBuffer,
+    input: CpuBuffer,
+    output: CpuBuffer,
+}
+
+/// `Executor` running a Rust closure for each stage instead of its shader, see the module documentation. The closures are registered by entry point with `CpuExecutor::with_kernel`.
+#[derive(Default)]
+pub struct CpuExecutor {
+    kernels: HashMap<&'static str, CpuKernel>,
+    pipelines: Vec<Option<CpuPipeline>>,
+}
+
+impl CpuExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the implementation of the entry point `entrypoint`, which runs the stages using it. The closure gets the buffers and the number of workgroups of the dispatch, and runs all the invocations.
+    pub fn with_kernel(
+        mut self,
+        entrypoint: &'static str,
+        kernel: impl Fn(CpuBuffers<'_>, (u32, u32, u32)) + Send + Sync + 'static,
+    ) -> Self {
+        self.kernels.insert(entrypoint, Box::new(kernel));
+        self
+    }
+
+    fn pipeline(&mut self, pipeline: PipelineId) -> &mut CpuPipeline {
+        self.pipelines[pipeline.0]
+            .as_mut()
+            .expect("Pipeline was destroyed")
+    }
+}
+
+impl Executor for CpuExecutor {
+    fn create_pipeline(
+        &mut self,
+        sizes: &BufferSizes,
+        stages: &[StageDesc],
+    ) -> Result<PipelineId, SgpuError> {
+        if let Some(desc) = stages
+            .iter()
+            .find(|desc| !self.kernels.contains_key(desc.entrypoint))
+        {
+            return Err(SgpuError::Unsupported(format!(
+                "no CPU kernel is registered for the entry point `{}`, see `CpuExecutor::with_kernel`",
+                desc.entrypoint
+            )));
+        }
+        if !sizes.scratchpads.is_empty() || !sizes.extra_outputs.is_empty() {
+            return Err(SgpuError::Unsupported(
+                "the CPU executor only has a single scratchpad and a single output".into(),
+            ));
+        }
+        self.pipelines.push(Some(CpuPipeline {
+            entrypoints: stages.iter().map(|desc| desc.entrypoint).collect(),
+            uniform: CpuBuffer::new(sizes.uniform),
+            push_constants: CpuBuffer::new(sizes.push_constants),
+            scratchpad: CpuBuffer::new(sizes.scratchpad.map_or(0, |size| size.get())),
+            input: CpuBuffer::new(sizes.input),
+            output: CpuBuffer::new(sizes.output),
+        }));
+        Ok(PipelineId(self.pipelines.len() - 1))
+    }
+
+    fn write_uniform(&mut self, pipeline: PipelineId, uniform: &[u8]) {
+        self.pipeline(pipeline)
+            .uniform
+            .bytes_mut()
+            .copy_from_slice(uniform);
+    }
+
+    fn write_push_constants(&mut self, pipeline: PipelineId, push_constants: &[u8]) {
+        self.pipeline(pipeline)
+            .push_constants
+            .bytes_mut()
+            .copy_from_slice(push_constants);
+    }
+
+    fn upload(&mut self, pipeline: PipelineId, input: &[u8]) {
+        self.pipeline(pipeline)
+            .input
+            .bytes_mut()
+            .copy_from_slice(input);
+    }
+
+    fn dispatch(&mut self, pipeline: PipelineId, workgroups: &[(u32, u32, u32)]) {
+        let pipeline = self.pipelines[pipeline.0]
+            .as_mut()
+            .expect("Pipeline was destroyed");
+        assert_eq!(
+            workgroups.len(),
+            pipeline.entrypoints.len(),
+            "Expected one workgroup count per stage"
+        );
+        for (entrypoint, workgroup) in pipeline.entrypoints.iter().zip(workgroups) {
+            let kernel = &self.kernels[entrypoint];
+            kernel(
+                CpuBuffers {
+                    uniform: pipeline.uniform.bytes(),
+                    push_constants: pipeline.push_constants.bytes(),
+                    scratchpad: pipeline.scratchpad.bytes_mut(),
+                    input: pipeline.input.bytes(),
+                    output: pipeline.output.bytes_mut(),
+                },
+                *workgroup,
+            );
+        }
+    }
+
+    fn readback(&mut self, pipeline: PipelineId, output: &mut [u8]) {
+        output.copy_from_slice(self.pipeline(pipeline).output.bytes());
+    }
+
+    fn destroy_pipeline(&mut self, pipeline: PipelineId) {
+        self.pipelines[pipeline.0] = None;
+    }
+}
@@ -0,0 +1,115 @@
+//! CSV export of result buffers. It is enabled by the `csv` feature. Only CSV is supported, columnar formats like Parquet are left to dedicated crates, which can be fed from `ToCsv::columns`.
+//!
+//! Outputs implementing `ToCsv` are written with one named column per field. Arrays are written as a single `value` column, struct-of-arrays outputs list their fields in `ToCsv::columns`. When columns have different lengths, the missing cells are left empty.
+//!
+//! ```rust
+//! use sgpu_compute::export::{Column, ToCsv};
+//!
+//! #[derive(Copy, Clone, bytemuck::Zeroable, bytemuck::Pod)]
+//! #[repr(C)]
+//! struct Output {
+//!     position: [f32; 3],
+//!     id: [u32; 3],
+//! }
+//!
+//! impl ToCsv for Output {
+//!     fn columns(&self) -> Vec<Column<'_>> {
+//!         vec![Column::new("position", &self.position), Column::new("id", &self.id)]
+//!     }
+//! }
+//!
+//! let output = Output { position: [0.5, 1.0, 1.5], id: [4, 5, 6] };
+//! let mut csv = Vec::new();
+//! output.write_csv(&mut csv).unwrap();
+//! assert_eq!(String::from_utf8(csv).unwrap(), "position,id\n0.5,4\n1,5\n1.5,6\n");
+//! ```
+use std::{fmt::Display, io::Write, path::Path};
+
+/// Values of a column, type-erased so that columns of different types can be written together.
+pub trait ColumnValues {
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Formats the value at `index`.
+    fn format(&self, index: usize) -> String;
+}
+
+impl<T: Display> ColumnValues for [T] {
+    #[inline]
+    fn len(&self) -> usize {
+        <[T]>::len(self)
+    }
+
+    #[inline]
+    fn format(&self, index: usize) -> String {
+        self[index].to_string()
+    }
+}
+
+impl<T: Display, const N: usize> ColumnValues for [T; N] {
+    #[inline]
+    fn len(&self) -> usize {
+        N
+    }
+
+    #[inline]
+    fn format(&self, index: usize) -> String {
+        self[index].to_string()
+    }
+}
+
+/// A named column of an output.
+pub struct Column<'a> {
+    pub name: &'a str,
+    pub values: &'a dyn ColumnValues,
+}
+
+impl<'a> Column<'a> {
+    #[inline]
+    pub fn new(name: &'a str, values: &'a dyn ColumnValues) -> Self {
+        Self { name, values }
+    }
+}
+
+/// Outputs that can be exported as CSV.
+pub trait ToCsv {
+    /// The columns of the output, in order.
+    fn columns(&self) -> Vec<Column<'_>>;
+
+    /// Writes the header and the rows to `writer`.
+    fn write_csv(&self, writer: impl Write) -> csv::Result<()> {
+        let columns = self.columns();
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(columns.iter().map(|column| column.name))?;
+        let rows = columns
+            .iter()
+            .map(|column| column.values.len())
+            .max()
+            .unwrap_or(0);
+        for row in 0..rows {
+            writer.write_record(columns.iter().map(|column| {
+                if row < column.values.len() {
+                    column.values.format(row)
+                } else {
+                    String::new()
+                }
+            }))?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes the output to the file at `path`.
+    fn to_csv(&self, path: impl AsRef<Path>) -> csv::Result<()> {
+        self.write_csv(std::fs::File::create(path)?)
+    }
+}
+
+impl<T: Display, const N: usize> ToCsv for [T; N] {
+    fn columns(&self) -> Vec<Column<'_>> {
+        vec![Column::new("value", self)]
+    }
+}
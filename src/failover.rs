@@ -0,0 +1,264 @@
+//! Warm standby device taking over when the primary device is lost. It is enabled by the `blocking` feature.
+//!
+//! `FailoverGpu` creates the primary device like `GpuCompute::new` and initializes a standby device in the background, preferably on another physical GPU (e.g. the integrated GPU). The same GPU through another backend, e.g. GL instead of Vulkan, doesn't count as another GPU since a driver reset takes both devices down, it is only used when there is no other GPU. When the primary device is lost (driver reset, GPU switch...), the `FailoverPipeline`s rebuild themselves on the standby device on their next run and write back their last uniform, so callers don't have to handle the failover themselves. `FailoverPipeline::try_run` returns the errors of the migration, e.g. when no standby device could be created, instead of panicking.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::failover::FailoverGpu;
+//!
+//! let shader = "
+//!     @group(0) @binding(0) var<storage, read> in: array<u32>;
+//!     @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+//!
+//!     @compute @workgroup_size(4)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[id.x] = in[id.x] * 3u;
+//!     }
+//! ";
+//! let gpu = FailoverGpu::new();
+//! let mut pipeline = gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(None, [StageDesc::new(shader, "main")]);
+//! assert_eq!(pipeline.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out), [3, 6, 9, 12]);
+//! gpu.force_failover();
+//! assert_eq!(pipeline.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out), [3, 6, 9, 12]);
+//! assert!(pipeline.is_on_standby());
+//! ```
+use crate::{blocking::Pipeline, error::SgpuError, GpuComputeAsync, StageDesc};
+use std::{
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    thread::JoinHandle,
+};
+
+/// The standby device and its backend.
+type Standby = (GpuComputeAsync, wgpu::Backend);
+
+/// A primary device with a standby device initialized in the background.
+pub struct FailoverGpu {
+    primary: GpuComputeAsync,
+    primary_backend: wgpu::Backend,
+    primary_lost: Arc<AtomicBool>,
+    standby: OnceLock<Option<Standby>>,
+    standby_init: Mutex<Option<JoinHandle<Option<Standby>>>>,
+}
+
+impl FailoverGpu {
+    /// Creates the primary device and starts initializing the standby device on another thread.
+    ///
+    /// # Panics
+    /// If the primary device can't be created, see `FailoverGpu::try_new`.
+    pub fn new() -> Self {
+        Self::try_new().unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Same as `FailoverGpu::new`, but returns the error of the creation of the primary device, like `GpuCompute::try_new`, instead of panicking. The standby device is optional, its failures are only logged.
+    pub fn try_new() -> Result<Self, SgpuError> {
+        let primary = pollster::block_on(GpuComputeAsync::try_new())?;
+        let primary_info = primary
+            .adapter_info()
+            .cloned()
+            .expect("A device built from an adapter has its info");
+        let primary_backend = primary_info.backend;
+
+        let primary_lost = Arc::new(AtomicBool::new(false));
+        let lost = Arc::clone(&primary_lost);
+        primary.set_device_lost_handler(move |_| lost.store(true, Ordering::Release));
+
+        let standby_init = std::thread::spawn(move || {
+            let instance = wgpu::Instance::default();
+            let adapters = instance.enumerate_adapters(wgpu::Backends::all());
+            // The same physical GPU on another backend is lost along with the primary device.
+            let is_primary = |info: &wgpu::AdapterInfo| {
+                info.vendor == primary_info.vendor
+                    && info.device == primary_info.device
+                    && info.name == primary_info.name
+            };
+            let adapter = adapters
+                .iter()
+                .find(|adapter| !is_primary(&adapter.get_info()))
+                .or_else(|| {
+                    crate::diagnostics::log_debug!(
+                        "No other GPU than the primary one, the standby device uses the same GPU"
+                    );
+                    adapters.first()
+                })?;
+            match pollster::block_on(GpuComputeAsync::from_adapter(
+                adapter,
+                &GpuComputeAsync::builder(),
+            )) {
+                Ok(gpu) => Some((gpu, adapter.get_info().backend)),
+                Err(error) => {
+                    crate::diagnostics::log_warn!("Could not create the standby device: {}", error);
+                    None
+                }
+            }
+        });
+
+        Ok(Self {
+            primary,
+            primary_backend,
+            primary_lost,
+            standby: OnceLock::new(),
+            standby_init: Mutex::new(Some(standby_init)),
+        })
+    }
+
+    /// Whether the primary device was lost.
+    #[inline]
+    pub fn is_primary_lost(&self) -> bool {
+        self.primary_lost.load(Ordering::Acquire)
+    }
+
+    /// Switches to the standby device as if the primary device was lost, e.g. to exercise the failover path.
+    #[inline]
+    pub fn force_failover(&self) {
+        self.primary_lost.store(true, Ordering::Release);
+    }
+
+    #[inline]
+    pub fn primary(&self) -> &GpuComputeAsync {
+        &self.primary
+    }
+
+    /// Returns the standby device, waiting for its initialization if needed. Returns `None` if no standby device could be created.
+    pub fn standby(&self) -> Option<&GpuComputeAsync> {
+        self.standby
+            .get_or_init(|| {
+                self.standby_init
+                    .lock()
+                    .expect("Standby initialization panicked")
+                    .take()
+                    .and_then(|handle| handle.join().ok().flatten())
+            })
+            .as_ref()
+            .map(|(gpu, _)| gpu)
+    }
+
+    /// Returns the device currently in use: the primary device until it is lost, then the standby device.
+    pub fn active(&self) -> &GpuComputeAsync {
+        if self.is_primary_lost() {
+            self.standby()
+                .expect("Primary device lost and no standby device available.")
+        } else {
+            &self.primary
+        }
+    }
+
+    /// Same as `GpuCompute::gen_pipeline`, but the pipeline is generated on the active device and follows the failover.
+    pub fn gen_pipeline<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        Output: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> FailoverPipeline<'_, Input, Uniform, Output, N> {
+        let on_standby = self.is_primary_lost();
+        let pipeline = Pipeline(pollster::block_on(
+            self.active().gen_pipeline(scratchpad_size, stages),
+        ));
+        FailoverPipeline {
+            gpu: self,
+            pipeline,
+            on_standby,
+            uniform: None,
+        }
+    }
+}
+
+impl Drop for FailoverGpu {
+    fn drop(&mut self) {
+        // The GL backend terminates the EGL display shared by all its devices when one of them is dropped, so dropping both devices fails. The standby device is leaked instead.
+        if self.primary_backend == wgpu::Backend::Gl {
+            if let Some(Some((standby, wgpu::Backend::Gl))) = self.standby.take() {
+                std::mem::forget(standby);
+            }
+        }
+    }
+}
+
+impl Default for FailoverGpu {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A pipeline migrating to the standby device when the primary device is lost.
+pub struct FailoverPipeline<
+    'a,
+    Input: bytemuck::Pod,
+    Uniform: bytemuck::Pod,
+    Output: bytemuck::Pod,
+    const N: usize,
+> {
+    gpu: &'a FailoverGpu,
+    pipeline: Pipeline<'a, Input, Uniform, Output, N>,
+    on_standby: bool,
+    uniform: Option<Uniform>,
+}
+
+impl<'a, Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod, const N: usize>
+    FailoverPipeline<'a, Input, Uniform, Output, N>
+{
+    /// Whether the pipeline already migrated to the standby device.
+    #[inline]
+    pub fn is_on_standby(&self) -> bool {
+        self.on_standby
+    }
+
+    /// Same as `PipelineAsync::write_uniform`, the uniform is kept to be written again after a migration.
+    #[inline]
+    pub fn write_uniform(&mut self, uniform: &Uniform) {
+        self.pipeline.write_uniform(uniform);
+        self.uniform = Some(*uniform);
+    }
+
+    /// Same as `Pipeline::run`, migrating the pipeline first if the primary device was lost.
+    ///
+    /// # Panics
+    /// If the migration or the run fails, see `FailoverPipeline::try_run`.
+    #[inline]
+    pub fn run<T: Send + 'static>(
+        &mut self,
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+        callback: impl FnOnce(&Output) -> T + Send,
+    ) -> T {
+        self.try_run(input, workgroups, callback)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Same as `FailoverPipeline::run`, but returns an error instead of panicking: `SgpuError::DeviceLost` if the primary device was lost without a standby device, the errors of `PipelineAsync::rebuild_on` if the pipeline can't be rebuilt on the standby device, or the errors of the run.
+    pub fn try_run<T: Send + 'static>(
+        &mut self,
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+        callback: impl FnOnce(&Output) -> T + Send,
+    ) -> Result<T, SgpuError> {
+        self.migrate_if_lost()?;
+        self.pipeline.try_run(input, workgroups, callback)
+    }
+
+    fn migrate_if_lost(&mut self) -> Result<(), SgpuError> {
+        if self.on_standby || !self.gpu.is_primary_lost() {
+            return Ok(());
+        }
+        let standby = self.gpu.standby().ok_or_else(|| {
+            SgpuError::DeviceLost(
+                "the primary device was lost and no standby device is available".into(),
+            )
+        })?;
+        crate::diagnostics::log_warn!("Rebuilding the pipeline on the standby device");
+        self.pipeline = self.pipeline.rebuild_on(standby)?;
+        if let Some(uniform) = &self.uniform {
+            self.pipeline.write_uniform(uniform);
+        }
+        self.on_standby = true;
+        Ok(())
+    }
+}
@@ -0,0 +1,187 @@
+//! Comparison of floating-point results.
+//!
+//! A fixed absolute epsilon like `(a - b).abs() < 1e-5` is too strict for large values and too loose near zero, and a relative one fails on values which should be zero. A `Tolerance` accepts two values if any of its bounds holds: the absolute difference, the difference relative to the largest magnitude, or the distance in ULPs, the number of representable floats between them. `Tolerance::is_close` compares two values and `assert_all_close` compares the results of a kernel with a CPU reference, reporting the first mismatch.
+//!
+//! ```rust
+//! use sgpu_compute::float_cmp::{assert_all_close, ulps, Tolerance};
+//!
+//! assert_eq!(ulps(1.0f32, 1.0 + f32::EPSILON), 1);
+//! let tolerance = Tolerance::ulps(4).with_abs(1e-6);
+//! assert!(tolerance.is_close(0.1f32 + 0.2, 0.3));
+//! assert!(tolerance.is_close(1e-9f32, -1e-9));
+//! assert!(!tolerance.is_close(1.0f32, 1.001));
+//! assert!(Tolerance::ulps(2).is_close(1000.0f32, 1000.0001));
+//! assert!(!Tolerance::ulps(2).is_close(1.0f64, 1.0 + 4.0 * f64::EPSILON));
+//! assert_all_close(&[1.0f32, 2.0, 3.0], &[1.0, 2.0, 3.0000002], tolerance);
+//! ```
+
+/// Floating-point types which can be compared, `f32` and `f64`.
+pub trait Float: Copy + PartialEq + std::fmt::Debug {
+    fn to_f64(self) -> f64;
+
+    /// Position of the value on a line where consecutive floats are consecutive integers, and `-0.0` and `0.0` are equal.
+    fn ordinal(self) -> i64;
+
+    fn is_nan(self) -> bool;
+}
+
+impl Float for f32 {
+    #[inline]
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    #[inline]
+    fn ordinal(self) -> i64 {
+        let bits = self.to_bits() as i32;
+        (if bits < 0 { i32::MIN - bits } else { bits }) as i64
+    }
+
+    #[inline]
+    fn is_nan(self) -> bool {
+        f32::is_nan(self)
+    }
+}
+
+impl Float for f64 {
+    #[inline]
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    #[inline]
+    fn ordinal(self) -> i64 {
+        let bits = self.to_bits() as i64;
+        if bits < 0 {
+            i64::MIN - bits
+        } else {
+            bits
+        }
+    }
+
+    #[inline]
+    fn is_nan(self) -> bool {
+        f64::is_nan(self)
+    }
+}
+
+/// Number of representable values between `a` and `b`, 0 if they are equal and `u64::MAX` if one is NaN.
+pub fn ulps<T: Float>(a: T, b: T) -> u64 {
+    if a.is_nan() || b.is_nan() {
+        return u64::MAX;
+    }
+    (a.ordinal() as i128 - b.ordinal() as i128).unsigned_abs() as u64
+}
+
+/// Bounds under which two values are considered equal, see the module documentation. The default tolerance only accepts equal values.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Tolerance {
+    /// Maximum absolute difference.
+    pub abs: f64,
+    /// Maximum difference relative to the largest magnitude of the two values.
+    pub rel: f64,
+    /// Maximum distance in ULPs.
+    pub ulps: u64,
+}
+
+impl Tolerance {
+    /// Tolerance accepting only equal values.
+    pub const EXACT: Self = Self {
+        abs: 0.0,
+        rel: 0.0,
+        ulps: 0,
+    };
+
+    /// Tolerance accepting values within `abs` of each other.
+    #[inline]
+    pub const fn abs(abs: f64) -> Self {
+        Self { abs, ..Self::EXACT }
+    }
+
+    /// Tolerance accepting values within `rel` times the largest magnitude of each other.
+    #[inline]
+    pub const fn rel(rel: f64) -> Self {
+        Self { rel, ..Self::EXACT }
+    }
+
+    /// Tolerance accepting values within `ulps` representable values of each other.
+    #[inline]
+    pub const fn ulps(ulps: u64) -> Self {
+        Self {
+            ulps,
+            ..Self::EXACT
+        }
+    }
+
+    /// Same tolerance, also accepting values within `abs` of each other, e.g. for results which should be zero.
+    #[inline]
+    pub const fn with_abs(self, abs: f64) -> Self {
+        Self { abs, ..self }
+    }
+
+    /// Same tolerance, also accepting values within `rel` times the largest magnitude of each other.
+    #[inline]
+    pub const fn with_rel(self, rel: f64) -> Self {
+        Self { rel, ..self }
+    }
+
+    /// Same tolerance, also accepting values within `ulps` representable values of each other.
+    #[inline]
+    pub const fn with_ulps(self, ulps: u64) -> Self {
+        Self { ulps, ..self }
+    }
+
+    /// Whether `a` and `b` are equal within the tolerance. NaN is never close to anything, infinities are only close to themselves.
+    pub fn is_close<T: Float>(&self, a: T, b: T) -> bool {
+        if a == b {
+            return true;
+        }
+        if a.is_nan() || b.is_nan() {
+            return false;
+        }
+        let distance = ulps(a, b);
+        let (a, b) = (a.to_f64(), b.to_f64());
+        if a.is_infinite() || b.is_infinite() {
+            return false;
+        }
+        let diff = (a - b).abs();
+        diff <= self.abs || diff <= self.rel * a.abs().max(b.abs()) || distance <= self.ulps
+    }
+
+    /// Index and values of the first pair of values of `actual` and `expected` which aren't close, `None` if they all are.
+    ///
+    /// # Panics
+    /// If the slices don't have the same length.
+    pub fn first_mismatch<T: Float>(&self, actual: &[T], expected: &[T]) -> Option<(usize, T, T)> {
+        assert_eq!(
+            actual.len(),
+            expected.len(),
+            "Expected {} values, got {}",
+            expected.len(),
+            actual.len()
+        );
+        actual
+            .iter()
+            .zip(expected)
+            .position(|(&a, &b)| !self.is_close(a, b))
+            .map(|i| (i, actual[i], expected[i]))
+    }
+}
+
+/// Asserts that each value of `actual` is close to the value of `expected` at the same index, e.g. the output of a kernel and the one of a CPU reference.
+///
+/// # Panics
+/// With the index, the values and their distance in ULPs of the first mismatch, or if the slices don't have the same length.
+#[track_caller]
+pub fn assert_all_close<T: Float>(actual: &[T], expected: &[T], tolerance: Tolerance) {
+    if let Some((i, a, b)) = tolerance.first_mismatch(actual, expected) {
+        panic!(
+            "Values at index {} differ: {:?} != {:?} ({} ULPs, {:?})",
+            i,
+            a,
+            b,
+            ulps(a, b),
+            tolerance
+        );
+    }
+}
@@ -0,0 +1,307 @@
+//! Finite-difference gradients of a scalar output with respect to the parameters of the uniform.
+//!
+//! A `FiniteDifferencesAsync` wraps a pipeline whose uniform is made of `f32` parameters, e.g. the coefficients of a model, and whose output is reduced to a scalar by a callback, e.g. a loss or a measurement. `FiniteDifferencesAsync::gradient` perturbs each parameter by a small step and runs the pipeline again to estimate the partial derivatives of the scalar, which is useful to optimize the parameters or to analyze the sensitivity of a simulation. The perturbations are independent, so each one runs on its own clone of the pipeline from `PipelineAsync::clone_for_thread`, with the push constants and the uniforms of the stages of the pipeline, and they are all submitted before the first one is read back. The clones have their own scratchpads, so the stages shouldn't depend on the scratchpad left by previous runs.
+//!
+//! The scheme sets the number of runs and the accuracy of the estimate:
+//!     - `Scheme::Forward` runs the pipeline once per parameter, plus the unperturbed run, and its error is proportional to the step
+//!     - `Scheme::Central` runs it twice per parameter, plus the unperturbed run, and its error is proportional to the square of the step
+//!
+//! The step of a parameter is relative to its magnitude, `step * max(|x|, 1)`, and it is rounded to the precision of `f32` before the difference is divided by it.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::gradient::{FiniteDifferences, Scheme};
+//!
+//! let shader = "
+//!     @group(0) @binding(0) var<uniform> coefficients: vec2<f32>;
+//!     @group(0) @binding(1) var<storage, read> in: array<f32>;
+//!     @group(0) @binding(2) var<storage, read_write> out: array<f32>;
+//!
+//!     @compute @workgroup_size(4)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[id.x] = coefficients.x * in[id.x] + coefficients.y * coefficients.y;
+//!     }
+//! ";
+//! let gpu = GpuCompute::new();
+//! let pipeline = gpu.gen_pipeline::<[f32; 4], [f32; 2], [f32; 4], 1>(
+//!     None,
+//!     [StageDesc::new(shader, "main")],
+//! );
+//! let mut differences = FiniteDifferences::new(pipeline, Scheme::Central);
+//! // Sum of the outputs: a * (1 + 2 + 3 + 4) + 4 * b², so its gradient is (10, 8b).
+//! let gradient = differences.gradient(&[0.5, 2.0], &[1.0, 2.0, 3.0, 4.0], [(1, 1, 1)], |out| {
+//!     out.iter().map(|&v| v as f64).sum()
+//! });
+//! assert!((gradient.value - 21.0).abs() < 1e-4);
+//! assert!((gradient.gradient[0] - 10.0).abs() < 1e-2);
+//! assert!((gradient.gradient[1] - 16.0).abs() < 1e-2);
+//! ```
+use crate::PipelineAsync;
+
+/// Finite-difference scheme of a `FiniteDifferencesAsync`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Scheme {
+    /// `(f(x + h) - f(x)) / h`, one run per parameter.
+    Forward,
+    /// `(f(x + h) - f(x - h)) / 2h`, two runs per parameter.
+    #[default]
+    Central,
+}
+
+impl Scheme {
+    /// Default relative step, which balances the truncation error of the scheme with the rounding error of `f32`.
+    pub fn default_step(self) -> f64 {
+        match self {
+            Scheme::Forward => (f32::EPSILON as f64).sqrt(),
+            Scheme::Central => (f32::EPSILON as f64).cbrt(),
+        }
+    }
+}
+
+/// Estimate of `FiniteDifferencesAsync::gradient`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    /// Scalar of the unperturbed parameters.
+    pub value: f64,
+    /// Partial derivative of the scalar for each perturbed parameter, in the order of the parameters.
+    pub gradient: Vec<f64>,
+    /// Number of runs of the pipeline.
+    pub evaluations: usize,
+}
+
+/// Pipeline whose output is differentiated with respect to its uniform, see the module documentation.
+pub struct FiniteDifferencesAsync<
+    'a,
+    Input: bytemuck::Pod,
+    Uniform: bytemuck::Pod,
+    Output: bytemuck::Pod,
+    const N: usize,
+> {
+    /// The pipeline, followed by its clones for the perturbations.
+    pipelines: Vec<PipelineAsync<'a, Input, Uniform, Output, N>>,
+    scheme: Scheme,
+    step: f64,
+    parameters: Option<Vec<usize>>,
+}
+
+impl<'a, Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod, const N: usize>
+    FiniteDifferencesAsync<'a, Input, Uniform, Output, N>
+{
+    /// Differentiates the output of `pipeline` with `scheme` and its default step, for all the parameters of the uniform.
+    ///
+    /// # Panics
+    /// If the size of `Uniform` isn't a non-zero multiple of 4 bytes.
+    pub fn new(pipeline: PipelineAsync<'a, Input, Uniform, Output, N>, scheme: Scheme) -> Self {
+        assert!(
+            std::mem::size_of::<Uniform>() > 0 && std::mem::size_of::<Uniform>().is_multiple_of(4),
+            "The uniform must be made of f32 parameters"
+        );
+        Self {
+            pipelines: vec![pipeline],
+            scheme,
+            step: scheme.default_step(),
+            parameters: None,
+        }
+    }
+
+    /// Same differences, with the relative step `step`.
+    ///
+    /// # Panics
+    /// If `step` isn't positive and finite.
+    pub fn with_step(self, step: f64) -> Self {
+        assert!(
+            step > 0.0 && step.is_finite(),
+            "The step must be positive and finite"
+        );
+        Self { step, ..self }
+    }
+
+    /// Same differences, only for the parameters at `indices` in the uniform seen as `[f32]`, e.g. when it also contains counts or flags.
+    ///
+    /// # Panics
+    /// If an index is out of the uniform.
+    pub fn with_parameters(self, indices: impl IntoIterator<Item = usize>) -> Self {
+        let indices = indices.into_iter().collect::<Vec<_>>();
+        let count = std::mem::size_of::<Uniform>() / 4;
+        if let Some(index) = indices.iter().find(|&&index| index >= count) {
+            panic!(
+                "The parameter {} is out of the uniform, which has {} parameters",
+                index, count
+            );
+        }
+        Self {
+            parameters: Some(indices),
+            ..self
+        }
+    }
+
+    /// The differentiated pipeline.
+    #[inline]
+    pub fn pipeline(&mut self) -> &mut PipelineAsync<'a, Input, Uniform, Output, N> {
+        &mut self.pipelines[0]
+    }
+
+    /// Returns the differentiated pipeline, the clones are dropped.
+    pub fn into_pipeline(mut self) -> PipelineAsync<'a, Input, Uniform, Output, N> {
+        self.pipelines.swap_remove(0)
+    }
+
+    /// Estimates the gradient of `objective(output)` at the parameters `uniform`, for the given input and workgroups. The uniform of the pipeline is left to `uniform`.
+    pub async fn gradient(
+        &mut self,
+        uniform: &Uniform,
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+        objective: impl Fn(&Output) -> f64,
+    ) -> Gradient {
+        let parameters = match &self.parameters {
+            Some(parameters) => parameters.clone(),
+            None => (0..std::mem::size_of::<Uniform>() / 4).collect(),
+        };
+        let signs: &[f32] = match self.scheme {
+            Scheme::Forward => &[1.0],
+            Scheme::Central => &[1.0, -1.0],
+        };
+        // The unperturbed uniform, followed by the perturbations of each parameter.
+        let mut uniforms = vec![*uniform];
+        let mut steps = Vec::with_capacity(parameters.len());
+        for &parameter in &parameters {
+            let x = read_parameter(uniform, parameter);
+            let h = self.step * (x.abs() as f64).max(1.0);
+            for &sign in signs {
+                let mut perturbed = *uniform;
+                write_parameter(&mut perturbed, parameter, x + sign * h as f32);
+                uniforms.push(perturbed);
+            }
+            // The step actually taken, once rounded to f32.
+            steps.push(match self.scheme {
+                Scheme::Forward => (x + h as f32) as f64 - x as f64,
+                Scheme::Central => (x + h as f32) as f64 - (x - h as f32) as f64,
+            });
+        }
+        while self.pipelines.len() < uniforms.len() {
+            let clone = self.pipelines[0].clone_for_thread();
+            self.pipelines.push(clone);
+        }
+        // The push constants and the uniforms of the stages may have changed since the clones were made.
+        let (original, clones) = self.pipelines.split_at_mut(1);
+        for clone in clones {
+            original[0].copy_stage_parameters(clone);
+        }
+        let mut runs = Vec::with_capacity(uniforms.len());
+        for (pipeline, uniform) in self.pipelines.iter_mut().zip(&uniforms) {
+            pipeline.write_uniform(uniform);
+            runs.push(pipeline.submit(None, Some(input), workgroups).1);
+        }
+        self.pipelines[0].device.wait_submitted();
+        let mut values = Vec::with_capacity(runs.len());
+        for (pipeline, receiver) in self.pipelines.iter().zip(runs) {
+            receiver.recv_async().await.expect("Error with channel");
+            let output = &pipeline.buffers.output;
+            values.push(objective(bytemuck::from_bytes(
+                output.slice(..).get_mapped_range().as_ref(),
+            )));
+            output.unmap();
+        }
+        let value = values[0];
+        let gradient = steps
+            .iter()
+            .enumerate()
+            .map(|(i, step)| match self.scheme {
+                Scheme::Forward => (values[1 + i] - value) / step,
+                Scheme::Central => (values[1 + 2 * i] - values[2 + 2 * i]) / step,
+            })
+            .collect();
+        self.pipelines[0].write_uniform(uniform);
+        Gradient {
+            value,
+            gradient,
+            evaluations: values.len(),
+        }
+    }
+}
+
+/// Parameter `index` of the uniform seen as `[f32]`.
+fn read_parameter<Uniform: bytemuck::Pod>(uniform: &Uniform, index: usize) -> f32 {
+    bytemuck::pod_read_unaligned(&bytemuck::bytes_of(uniform)[4 * index..4 * index + 4])
+}
+
+fn write_parameter<Uniform: bytemuck::Pod>(uniform: &mut Uniform, index: usize, value: f32) {
+    bytemuck::bytes_of_mut(uniform)[4 * index..4 * index + 4].copy_from_slice(&value.to_ne_bytes());
+}
+
+/// Blocking version of `FiniteDifferencesAsync`, it is enabled by the `blocking` feature.
+#[cfg(feature = "blocking")]
+pub struct FiniteDifferences<
+    'a,
+    Input: bytemuck::Pod,
+    Uniform: bytemuck::Pod,
+    Output: bytemuck::Pod,
+    const N: usize,
+>(FiniteDifferencesAsync<'a, Input, Uniform, Output, N>);
+
+#[cfg(feature = "blocking")]
+impl<'a, Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod, const N: usize>
+    FiniteDifferences<'a, Input, Uniform, Output, N>
+{
+    /// Same as `FiniteDifferencesAsync::new`, but takes a blocking pipeline.
+    #[inline]
+    pub fn new(
+        pipeline: crate::blocking::Pipeline<'a, Input, Uniform, Output, N>,
+        scheme: Scheme,
+    ) -> Self {
+        Self(FiniteDifferencesAsync::new(pipeline.0, scheme))
+    }
+
+    /// Same as `FiniteDifferencesAsync::with_step`.
+    #[inline]
+    pub fn with_step(self, step: f64) -> Self {
+        Self(self.0.with_step(step))
+    }
+
+    /// Same as `FiniteDifferencesAsync::with_parameters`.
+    #[inline]
+    pub fn with_parameters(self, indices: impl IntoIterator<Item = usize>) -> Self {
+        Self(self.0.with_parameters(indices))
+    }
+
+    /// Same as `FiniteDifferencesAsync::into_pipeline`, but returns a blocking pipeline.
+    #[inline]
+    pub fn into_pipeline(self) -> crate::blocking::Pipeline<'a, Input, Uniform, Output, N> {
+        crate::blocking::Pipeline(self.0.into_pipeline())
+    }
+
+    /// Blocking version of `FiniteDifferencesAsync::gradient`.
+    #[inline]
+    pub fn gradient(
+        &mut self,
+        uniform: &Uniform,
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+        objective: impl Fn(&Output) -> f64,
+    ) -> Gradient {
+        pollster::block_on(self.0.gradient(uniform, input, workgroups, objective))
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<'a, Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod, const N: usize>
+    std::ops::Deref for FiniteDifferences<'a, Input, Uniform, Output, N>
+{
+    type Target = FiniteDifferencesAsync<'a, Input, Uniform, Output, N>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod, const N: usize>
+    std::ops::DerefMut for FiniteDifferences<'_, Input, Uniform, Output, N>
+{
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
@@ -0,0 +1,587 @@
+//! Compute graphs of kernels wired through named buffers.
+//!
+//! A `PipelineAsync` runs its stages in the order of an array, on a fixed set of buffers. A `ComputeGraph` declares named buffers and kernels instead, each kernel binding some of the buffers with an `Access`: binding `i` of its `@group(0)` is the `i`-th access, a `var<uniform>` for `Access::Uniform`, a `var<storage, read>` for `Access::Read` and a `var<storage, read_write>` for `Access::Write`. A kernel writing a buffer runs before the kernels reading it, and `ComputeGraph::connect` copies a buffer into another one, e.g. to feed the output of a kernel to the input of a kernel which declared its own buffers.
+//!
+//! `GpuComputeAsync::gen_graph_pipeline` checks the bindings of each kernel like `GpuComputeAsync::gen_pipeline` does, orders the kernels and the copies topologically, kernels and copies declared first going first when they don't depend on each other, and creates the buffers. `GraphPipelineAsync::run` then records every pass and copy in one command encoder, followed by the copies of the buffers marked with `ComputeGraph::read_back`, and reads them back. Each buffer is written by at most one kernel or copy, so the results of a run never depend on the order of the declarations, and the graph must be acyclic. Iterating, e.g. the time steps of a simulation, is done by running the graph again: the buffers keep their content between runs.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::graph::{Access, ComputeGraph};
+//!
+//! let square = "
+//!     @group(0) @binding(0) var<storage, read> x: array<u32, 64>;
+//!     @group(0) @binding(1) var<storage, read_write> squares: array<u32, 64>;
+//!
+//!     @compute @workgroup_size(64)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         squares[id.x] = x[id.x] * x[id.x];
+//!     }
+//! ";
+//! let add = "
+//!     @group(0) @binding(0) var<storage, read> squares: array<u32, 64>;
+//!     @group(0) @binding(1) var<storage, read> x: array<u32, 64>;
+//!     @group(0) @binding(2) var<storage, read_write> out: array<u32, 64>;
+//!
+//!     @compute @workgroup_size(64)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[id.x] = squares[id.x] + x[id.x];
+//!     }
+//! ";
+//! let stage = |shader| StageDesc::new(shader, "main");
+//! let mut graph = ComputeGraph::new();
+//! let x = graph.buffer("x", 256);
+//! let squares = graph.buffer("squares", 256);
+//! let out = graph.buffer("out", 256);
+//! // `add` is declared first but reads the squares, so it runs second.
+//! graph.kernel("add", stage(add), &[Access::Read(squares), Access::Read(x), Access::Write(out)], (1, 1, 1));
+//! graph.kernel("square", stage(square), &[Access::Read(x), Access::Write(squares)], (1, 1, 1));
+//! graph.read_back(out);
+//!
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_graph_pipeline(graph);
+//! assert_eq!(pipeline.kernel_order(), ["square", "add"]);
+//! pipeline.write(x, &(0..64).collect::<Vec<u32>>());
+//! pipeline.run();
+//! assert_eq!(pipeline.read::<u32>(out), (0..64).map(|x| x * x + x).collect::<Vec<_>>());
+//! ```
+use crate::{error::SgpuError, shared, GpuComputeAsync, GpuRef, StageDesc};
+use sgpu_compute_core::layout::{BufferDescription, BufferRole};
+use std::{collections::BTreeSet, sync::Arc};
+
+/// Identifier of a buffer of a `ComputeGraph`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct BufferId(usize);
+
+/// Identifier of a kernel of a `ComputeGraph`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct KernelId(usize);
+
+/// How a kernel binds a buffer, see the module documentation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Access {
+    Uniform(BufferId),
+    Read(BufferId),
+    Write(BufferId),
+}
+
+impl Access {
+    #[inline]
+    fn buffer(self) -> BufferId {
+        match self {
+            Access::Uniform(buffer) | Access::Read(buffer) | Access::Write(buffer) => buffer,
+        }
+    }
+
+    #[inline]
+    fn role(self) -> BufferRole {
+        match self {
+            Access::Uniform(_) => BufferRole::Uniform,
+            Access::Read(_) => BufferRole::Input,
+            Access::Write(_) => BufferRole::Output,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct GraphBuffer {
+    name: String,
+    /// Size in bytes.
+    size: usize,
+    read_back: bool,
+}
+
+#[derive(Debug, Clone)]
+struct GraphKernel {
+    name: String,
+    stage: StageDesc,
+    accesses: Vec<Access>,
+    workgroups: (u32, u32, u32),
+}
+
+/// A kernel or a copy of a graph, in the order of execution.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Step {
+    Kernel(usize),
+    /// Index in `ComputeGraph::copies`.
+    Copy(usize),
+}
+
+/// Description of a compute graph, see the module documentation.
+#[derive(Debug, Clone, Default)]
+pub struct ComputeGraph {
+    buffers: Vec<GraphBuffer>,
+    kernels: Vec<GraphKernel>,
+    /// Source and destination of each copy.
+    copies: Vec<(BufferId, BufferId)>,
+}
+
+impl ComputeGraph {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// This method is used to declare a buffer of `size` bytes. Its content is zeroed when the graph pipeline is created and kept between runs.
+    pub fn buffer(&mut self, name: impl Into<String>, size: usize) -> BufferId {
+        self.buffers.push(GraphBuffer {
+            name: name.into(),
+            size,
+            read_back: false,
+        });
+        BufferId(self.buffers.len() - 1)
+    }
+
+    /// This method is used to declare a kernel running the stage with the given workgroups, binding the buffers of `accesses` in order.
+    pub fn kernel(
+        &mut self,
+        name: impl Into<String>,
+        stage: StageDesc,
+        accesses: &[Access],
+        workgroups: (u32, u32, u32),
+    ) -> KernelId {
+        self.kernels.push(GraphKernel {
+            name: name.into(),
+            stage,
+            accesses: accesses.to_vec(),
+            workgroups,
+        });
+        KernelId(self.kernels.len() - 1)
+    }
+
+    /// This method is used to copy `from` into `to` on each run, once `from` is written and before `to` is read. The buffers must have the same size.
+    pub fn connect(&mut self, from: BufferId, to: BufferId) {
+        self.copies.push((from, to));
+    }
+
+    /// This method is used to read `buffer` back at the end of each run, see `GraphPipelineAsync::read`.
+    #[inline]
+    pub fn read_back(&mut self, buffer: BufferId) {
+        self.buffers[buffer.0].read_back = true;
+    }
+
+    /// Checks the graph and returns its steps in a topological order.
+    fn order(&self) -> Result<Vec<Step>, SgpuError> {
+        let invalid = |message: String| Err(SgpuError::InvalidGraph(message));
+        for buffer in &self.buffers {
+            if buffer.size == 0
+                || !buffer
+                    .size
+                    .is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT as usize)
+            {
+                return invalid(format!(
+                    "the buffer `{}` is {} bytes, it must be a non-zero multiple of {} bytes",
+                    buffer.name,
+                    buffer.size,
+                    wgpu::COPY_BUFFER_ALIGNMENT
+                ));
+            }
+        }
+        let used = self
+            .kernels
+            .iter()
+            .flat_map(|kernel| kernel.accesses.iter().map(|access| access.buffer()))
+            .chain(self.copies.iter().flat_map(|&(from, to)| [from, to]));
+        for buffer in used {
+            if buffer.0 >= self.buffers.len() {
+                return invalid(format!(
+                    "the buffer {} was declared by another graph",
+                    buffer.0
+                ));
+            }
+        }
+
+        let steps = (0..self.kernels.len())
+            .map(Step::Kernel)
+            .chain((0..self.copies.len()).map(Step::Copy))
+            .collect::<Vec<_>>();
+        let step_name = |step: Step| match step {
+            Step::Kernel(k) => format!("kernel `{}`", self.kernels[k].name),
+            Step::Copy(c) => format!(
+                "copy of `{}` into `{}`",
+                self.buffers[self.copies[c].0 .0].name, self.buffers[self.copies[c].1 .0].name
+            ),
+        };
+        // The step writing each buffer, and the steps reading it.
+        let mut writers = vec![None; self.buffers.len()];
+        let mut readers = vec![Vec::new(); self.buffers.len()];
+        for (i, &step) in steps.iter().enumerate() {
+            let (written, read) = match step {
+                Step::Kernel(k) => {
+                    let kernel = &self.kernels[k];
+                    for (j, access) in kernel.accesses.iter().enumerate() {
+                        let bound_twice = kernel.accesses[..j]
+                            .iter()
+                            .any(|other| other.buffer() == access.buffer());
+                        if bound_twice {
+                            return invalid(format!(
+                                "the kernel `{}` binds the buffer `{}` twice",
+                                kernel.name,
+                                self.buffers[access.buffer().0].name
+                            ));
+                        }
+                    }
+                    let written = kernel
+                        .accesses
+                        .iter()
+                        .filter(|access| matches!(access, Access::Write(_)))
+                        .map(|access| access.buffer())
+                        .collect::<Vec<_>>();
+                    let read = kernel
+                        .accesses
+                        .iter()
+                        .filter(|access| !matches!(access, Access::Write(_)))
+                        .map(|access| access.buffer())
+                        .collect::<Vec<_>>();
+                    (written, read)
+                }
+                Step::Copy(c) => {
+                    let (from, to) = self.copies[c];
+                    let (from_buffer, to_buffer) = (&self.buffers[from.0], &self.buffers[to.0]);
+                    if from == to || from_buffer.size != to_buffer.size {
+                        return invalid(format!(
+                            "the buffer `{}` ({} bytes) can't be copied into `{}` ({} bytes)",
+                            from_buffer.name, from_buffer.size, to_buffer.name, to_buffer.size
+                        ));
+                    }
+                    (vec![to], vec![from])
+                }
+            };
+            for buffer in written {
+                if let Some(writer) = writers[buffer.0].replace(i) {
+                    return invalid(format!(
+                        "the buffer `{}` is written by the {} and by the {}",
+                        self.buffers[buffer.0].name,
+                        step_name(steps[writer]),
+                        step_name(step)
+                    ));
+                }
+            }
+            for buffer in read {
+                readers[buffer.0].push(i);
+            }
+        }
+
+        // Kahn's algorithm, taking the first declared step among the ready ones.
+        let mut successors = vec![Vec::new(); steps.len()];
+        let mut predecessors = vec![0; steps.len()];
+        for (writer, readers) in writers.iter().zip(&readers) {
+            if let Some(writer) = *writer {
+                for &reader in readers {
+                    successors[writer].push(reader);
+                    predecessors[reader] += 1;
+                }
+            }
+        }
+        let mut ready = (0..steps.len())
+            .filter(|&i| predecessors[i] == 0)
+            .collect::<BTreeSet<_>>();
+        let mut order = Vec::with_capacity(steps.len());
+        while let Some(i) = ready.pop_first() {
+            order.push(steps[i]);
+            for &next in &successors[i] {
+                predecessors[next] -= 1;
+                if predecessors[next] == 0 {
+                    ready.insert(next);
+                }
+            }
+        }
+        if order.len() < steps.len() {
+            let cycle = (0..steps.len())
+                .filter(|&i| predecessors[i] > 0)
+                .map(|i| step_name(steps[i]))
+                .collect::<Vec<_>>();
+            return invalid(format!(
+                "the graph has a cycle through {}",
+                cycle.join(", ")
+            ));
+        }
+        Ok(order)
+    }
+}
+
+struct CompiledKernel {
+    name: String,
+    pipeline: Arc<wgpu::ComputePipeline>,
+    bindgroup: wgpu::BindGroup,
+    shared_uniform: bool,
+    workgroups: (u32, u32, u32),
+}
+
+/// Compute graph compiled on a device, see the module documentation.
+pub struct GraphPipelineAsync<'a> {
+    buffers: Vec<wgpu::Buffer>,
+    names: Vec<String>,
+    /// Readback buffer of the buffers read back, with their content after the last run.
+    readbacks: Vec<Option<(wgpu::Buffer, Vec<u8>)>>,
+    kernels: Vec<CompiledKernel>,
+    copies: Vec<(BufferId, BufferId)>,
+    order: Vec<Step>,
+    device: GpuRef<'a>,
+}
+
+impl GpuComputeAsync {
+    /// This method is used to compile the kernels of `graph` and create its buffers, see the `graph` module.
+    ///
+    /// # Panics
+    /// If the graph is malformed, or in the same cases as `GpuComputeAsync::gen_pipeline` for each kernel.
+    pub async fn gen_graph_pipeline(&self, graph: ComputeGraph) -> GraphPipelineAsync<'_> {
+        self.try_gen_graph_pipeline(graph)
+            .await
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Same as `GpuComputeAsync::gen_graph_pipeline`, but the errors of the graph and of the shaders are returned instead of panicking.
+    pub async fn try_gen_graph_pipeline(
+        &self,
+        graph: ComputeGraph,
+    ) -> Result<GraphPipelineAsync<'_>, SgpuError> {
+        let order = graph.order()?;
+        crate::diagnostics::log_debug!(
+            "Compiling a graph of {} kernel(s) and {} copies",
+            graph.kernels.len(),
+            graph.copies.len()
+        );
+        let buffers = graph
+            .buffers
+            .iter()
+            .enumerate()
+            .map(|(i, buffer)| {
+                // A buffer written by a kernel and read as a uniform by another one needs both usages.
+                let usage = graph
+                    .kernels
+                    .iter()
+                    .flat_map(|kernel| &kernel.accesses)
+                    .filter(|access| access.buffer() == BufferId(i))
+                    .fold(wgpu::BufferUsages::empty(), |usage, access| {
+                        usage
+                            | match access {
+                                Access::Uniform(_) => wgpu::BufferUsages::UNIFORM,
+                                Access::Read(_) | Access::Write(_) => wgpu::BufferUsages::STORAGE,
+                            }
+                    });
+                // The buffers only used by the copies are storage buffers too.
+                let usage = if usage.is_empty() {
+                    wgpu::BufferUsages::STORAGE
+                } else {
+                    usage
+                };
+                self.device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some(&buffer.name),
+                    size: buffer.size as _,
+                    usage: usage | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut kernels = Vec::with_capacity(graph.kernels.len());
+        for kernel in &graph.kernels {
+            let layout = kernel
+                .accesses
+                .iter()
+                .enumerate()
+                .map(|(i, access)| BufferDescription {
+                    binding: i as u32,
+                    role: access.role(),
+                    size: graph.buffers[access.buffer().0].size,
+                })
+                .collect::<Vec<_>>();
+            let stage = kernel
+                .stage
+                .clone()
+                .with_name(kernel.stage.name.unwrap_or(kernel.stage.entrypoint));
+            let (bindgroup_layout, mut pipelines, shared_uniform) = self
+                .compile_layout_stages(&layout, 0, std::slice::from_ref(&stage), None)
+                .await
+                .map_err(|error| match error {
+                    SgpuError::LayoutMismatch(message) => {
+                        SgpuError::LayoutMismatch(format!("kernel `{}`: {}", kernel.name, message))
+                    }
+                    error => error,
+                })?;
+            let bindgroup = self.create_bindgroup(
+                &bindgroup_layout,
+                kernel
+                    .accesses
+                    .iter()
+                    .map(|access| &buffers[access.buffer().0]),
+            );
+            kernels.push(CompiledKernel {
+                name: kernel.name.clone(),
+                pipeline: pipelines.pop().expect("One pipeline per stage"),
+                bindgroup,
+                shared_uniform,
+                workgroups: kernel.workgroups,
+            });
+        }
+        let readbacks = graph
+            .buffers
+            .iter()
+            .map(|buffer| {
+                buffer.read_back.then(|| {
+                    let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+                        label: Some(&buffer.name),
+                        size: buffer.size as _,
+                        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                        mapped_at_creation: false,
+                    });
+                    (readback, vec![0; buffer.size])
+                })
+            })
+            .collect();
+        Ok(GraphPipelineAsync {
+            buffers,
+            names: graph
+                .buffers
+                .into_iter()
+                .map(|buffer| buffer.name)
+                .collect(),
+            readbacks,
+            kernels,
+            copies: graph.copies,
+            order,
+            device: GpuRef::Borrowed(self),
+        })
+    }
+}
+
+impl GraphPipelineAsync<'_> {
+    /// Names of the kernels in the order they run.
+    pub fn kernel_order(&self) -> Vec<&str> {
+        self.order
+            .iter()
+            .filter_map(|step| match step {
+                Step::Kernel(k) => Some(self.kernels[*k].name.as_str()),
+                Step::Copy(_) => None,
+            })
+            .collect()
+    }
+
+    /// Identifier of the buffer declared with `name`, `None` if there is none.
+    pub fn buffer_id(&self, name: &str) -> Option<BufferId> {
+        self.names
+            .iter()
+            .position(|other| other == name)
+            .map(BufferId)
+    }
+
+    /// The buffer `buffer`, e.g. to bind it in a render pass.
+    #[inline]
+    pub fn buffer(&self, buffer: BufferId) -> &wgpu::Buffer {
+        &self.buffers[buffer.0]
+    }
+
+    /// This method is used to write `data` to `buffer`, e.g. the inputs of the graph or a uniform before a run.
+    ///
+    /// # Panics
+    /// If `data` doesn't have the size of the buffer.
+    pub fn write<T: bytemuck::Pod>(&mut self, buffer: BufferId, data: &[T]) {
+        let bytes = bytemuck::cast_slice(data);
+        let target = &self.buffers[buffer.0];
+        assert_eq!(
+            bytes.len() as u64,
+            target.size(),
+            "The buffer `{}` is {} bytes, got {} bytes",
+            self.names[buffer.0],
+            target.size(),
+            bytes.len()
+        );
+        self.device.queue.write_buffer(target, 0, bytes);
+    }
+
+    /// This method is used to change the workgroups the kernel is dispatched with.
+    #[inline]
+    pub fn set_workgroups(&mut self, kernel: KernelId, workgroups: (u32, u32, u32)) {
+        self.kernels[kernel.0].workgroups = workgroups;
+    }
+
+    /// This method is used to run the kernels and the copies of the graph in order, then read back the buffers marked with `ComputeGraph::read_back`.
+    pub async fn run(&mut self) {
+        let mut encoder =
+            self.device
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Compute graph"),
+                });
+        let mut passes = 0;
+        for step in &self.order {
+            match *step {
+                Step::Kernel(k) => {
+                    self.device
+                        .split_submission(&mut encoder, passes, Some("Compute graph"));
+                    passes += 1;
+                    let kernel = &self.kernels[k];
+                    let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                        label: Some(&kernel.name),
+                        timestamp_writes: None,
+                    });
+                    cpass.set_pipeline(&kernel.pipeline);
+                    cpass.set_bind_group(0, &kernel.bindgroup, &[]);
+                    if kernel.shared_uniform {
+                        cpass.set_bind_group(
+                            shared::GROUP,
+                            &self.device.shared_uniform().bindgroup,
+                            &[],
+                        );
+                    }
+                    cpass.insert_debug_marker(&format!("sgpu-{}", kernel.name));
+                    let (x, y, z) = kernel.workgroups;
+                    cpass.dispatch_workgroups(x, y, z);
+                }
+                Step::Copy(c) => {
+                    let (from, to) = self.copies[c];
+                    let from = &self.buffers[from.0];
+                    encoder.copy_buffer_to_buffer(from, 0, &self.buffers[to.0], 0, from.size());
+                }
+            }
+        }
+        for (buffer, readback) in self.buffers.iter().zip(&self.readbacks) {
+            if let Some((readback, _)) = readback {
+                encoder.copy_buffer_to_buffer(buffer, 0, readback, 0, buffer.size());
+            }
+        }
+        self.device.queue.submit(Some(encoder.finish()));
+        let count = self.readbacks.iter().flatten().count();
+        if count == 0 {
+            return;
+        }
+        let (sender, receiver) = flume::bounded(count);
+        for (readback, _) in self.readbacks.iter().flatten() {
+            let sender = sender.clone();
+            readback.slice(..).map_async(wgpu::MapMode::Read, move |e| {
+                e.expect("Could not map buffer");
+                sender.send(()).unwrap()
+            });
+        }
+        self.device.wait_submitted();
+        for _ in 0..count {
+            receiver.recv_async().await.expect("Error with channel");
+        }
+        for (readback, content) in self.readbacks.iter_mut().flatten() {
+            content.copy_from_slice(&readback.slice(..).get_mapped_range());
+            readback.unmap();
+        }
+    }
+
+    /// Content of `buffer` at the end of the last run, as values of `T`. It is zeroed before the first run.
+    ///
+    /// # Panics
+    /// If `buffer` isn't read back, see `ComputeGraph::read_back`, or if its size is not a multiple of the size of `T`.
+    pub fn read<T: bytemuck::Pod>(&self, buffer: BufferId) -> Vec<T> {
+        let (_, content) = self.readbacks[buffer.0].as_ref().unwrap_or_else(|| {
+            panic!(
+                "The buffer `{}` isn't read back, use `ComputeGraph::read_back`",
+                self.names[buffer.0]
+            )
+        });
+        assert!(
+            std::mem::size_of::<T>() > 0 && content.len().is_multiple_of(std::mem::size_of::<T>()),
+            "The buffer `{}` can't be read as a slice of {}",
+            self.names[buffer.0],
+            std::any::type_name::<T>()
+        );
+        content
+            .chunks_exact(std::mem::size_of::<T>())
+            .map(bytemuck::pod_read_unaligned)
+            .collect()
+    }
+}
@@ -0,0 +1,170 @@
+//! Level-synchronous breadth-first search over CSR graphs.
+//!
+//! The input is a `Csr` graph and the output a `Distances` holding the number of edges from the source to each vertex. The frontier of each level is kept in a queue of the scratchpad: the `expand` stage visits the neighbors of the frontier and appends the ones which were not visited yet to the next queue, then `advance` swaps the queues. A run is made of the `init` stage followed by `K - 1` stages alternating `expand` and `advance`, so it expands `(K - 1) / 2` levels. When `BfsParams::reset` is set, `init` restarts the search from the source, otherwise the search continues where the previous run stopped, so the pipeline is run again until `Distances::is_done`.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::bfs::{self, BfsParams, Csr, Distances};
+//!
+//! // 0 -> 1 -> 2 -> 3 and 0 -> 2, the vertex 4 is not reachable.
+//! let graph = Csr::<5, 4>::from_edges(&[(0, 1), (1, 2), (2, 3), (0, 2)]);
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline::<Csr<5, 4>, BfsParams, Distances<5>, 5>(bfs::scratchpad_size(5), bfs::stages());
+//! pipeline.write_uniform(&BfsParams::new(5, 4, 0, true));
+//! let distances = loop {
+//!     let (done, distances) = pipeline.run(&graph, bfs::workgroups(5), |out| (out.is_done(), out.distances));
+//!     if done {
+//!         break distances;
+//!     }
+//!     pipeline.write_uniform(&BfsParams::new(5, 4, 0, false));
+//! };
+//! assert_eq!(distances, [0, 1, 1, 2, bfs::UNREACHED]);
+//! ```
+use crate::StageDesc;
+use std::num::NonZeroUsize;
+
+/// WGSL source containing the `init`, `expand` and `advance` entry points.
+pub const SHADER: &str = sgpu_compute_core::kernels::bfs::SHADER;
+
+/// Distance of the vertices which are not reachable from the source.
+pub const UNREACHED: u32 = u32::MAX;
+
+/// Stage restarting the search from the source on reset.
+pub const INIT: StageDesc = StageDesc::new(SHADER, "init").with_name("bfs_init");
+
+/// Stage visiting the neighbors of the frontier.
+pub const EXPAND: StageDesc = StageDesc::new(SHADER, "expand").with_name("bfs_expand");
+
+/// Stage making the next frontier the current one.
+pub const ADVANCE: StageDesc = StageDesc::new(SHADER, "advance").with_name("bfs_advance");
+
+/// Returns `INIT` followed by `K - 1` stages alternating `EXPAND` and `ADVANCE`.
+pub fn stages<const K: usize>() -> [StageDesc; K] {
+    std::array::from_fn(|i| match i {
+        0 => INIT,
+        i if i % 2 == 1 => EXPAND,
+        _ => ADVANCE,
+    })
+}
+
+/// Returns the scratchpad size needed by the frontier queues of a graph of `vertices` vertices.
+#[inline]
+pub const fn scratchpad_size(vertices: u32) -> Option<NonZeroUsize> {
+    NonZeroUsize::new((4 + 2 * vertices as usize) * std::mem::size_of::<u32>())
+}
+
+/// Returns the workgroups of each stage for a graph of `vertices` vertices.
+pub fn workgroups<const K: usize>(vertices: u32) -> [(u32, u32, u32); K] {
+    std::array::from_fn(|i| match i {
+        i if i > 0 && i % 2 == 0 => (1, 1, 1),
+        _ => (vertices.div_ceil(64), 1, 1),
+    })
+}
+
+/// Uniform of the stages.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct BfsParams {
+    pub vertices: u32,
+    pub edges: u32,
+    pub source: u32,
+    /// Non-zero to restart the search from the source.
+    pub reset: u32,
+}
+
+impl BfsParams {
+    #[inline]
+    pub const fn new(vertices: u32, edges: u32, source: u32, reset: bool) -> Self {
+        Self {
+            vertices,
+            edges,
+            source,
+            reset: reset as u32,
+        }
+    }
+}
+
+/// Directed graph of `V` vertices and `E` edges in compressed sparse row format: the neighbors of the vertex `v` are `columns[offsets[v]..offsets[v + 1]]`, with `offsets[V]` being `end`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(C)]
+pub struct Csr<const V: usize, const E: usize> {
+    pub offsets: [u32; V],
+    pub end: u32,
+    pub columns: [u32; E],
+}
+
+impl<const V: usize, const E: usize> Csr<V, E> {
+    /// Builds the graph from its `E` directed edges `(from, to)`.
+    ///
+    /// # Panics
+    /// If there are not `E` edges or a vertex is not in `0..V`.
+    pub fn from_edges(edges: &[(u32, u32)]) -> Self {
+        assert_eq!(edges.len(), E, "Wrong number of edges");
+        let mut graph = Self {
+            offsets: [0; V],
+            end: E as u32,
+            columns: [0; E],
+        };
+        let mut degrees = [0u32; V];
+        for &(from, to) in edges {
+            assert!((to as usize) < V, "Vertex {} out of bounds", to);
+            degrees[from as usize] += 1;
+        }
+        let mut offset = 0;
+        for (v, degree) in degrees.iter().enumerate() {
+            graph.offsets[v] = offset;
+            offset += degree;
+        }
+        let mut cursors = graph.offsets;
+        for &(from, to) in edges {
+            graph.columns[cursors[from as usize] as usize] = to;
+            cursors[from as usize] += 1;
+        }
+        graph
+    }
+
+    /// Neighbors of the vertex `v`.
+    #[inline]
+    pub fn neighbors(&self, v: usize) -> &[u32] {
+        let end = self.offsets.get(v + 1).copied().unwrap_or(self.end);
+        &self.columns[self.offsets[v] as usize..end as usize]
+    }
+}
+
+// SAFETY: the struct is only made of `u32`, so it has no padding.
+unsafe impl<const V: usize, const E: usize> bytemuck::Zeroable for Csr<V, E> {}
+unsafe impl<const V: usize, const E: usize> bytemuck::Pod for Csr<V, E> {}
+
+/// Output of the search for a graph of `V` vertices.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct Distances<const V: usize> {
+    frontier: u32,
+    level: u32,
+    /// Distance of each vertex from the source, `UNREACHED` if it wasn't reached yet.
+    pub distances: [u32; V],
+}
+
+impl<const V: usize> Distances<V> {
+    /// Whether all the reachable vertices were visited.
+    #[inline]
+    pub const fn is_done(&self) -> bool {
+        self.frontier == 0
+    }
+
+    /// Number of vertices of the current frontier.
+    #[inline]
+    pub const fn frontier(&self) -> u32 {
+        self.frontier
+    }
+
+    /// Number of levels expanded since the reset.
+    #[inline]
+    pub const fn level(&self) -> u32 {
+        self.level
+    }
+}
+
+// SAFETY: the struct is only made of `u32`, so it has no padding.
+unsafe impl<const V: usize> bytemuck::Zeroable for Distances<V> {}
+unsafe impl<const V: usize> bytemuck::Pod for Distances<V> {}
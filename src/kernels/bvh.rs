@@ -0,0 +1,124 @@
+//! Building blocks of a linear bounding volume hierarchy (LBVH).
+//!
+//! Building the hierarchy is done in three steps:
+//!     - `MORTON` computes a 30 bits Morton code for each point (e.g. the centroid of each primitive) relative to the scene `Bounds`
+//!     - the codes are sorted, keeping track of the permutation to find back the primitives
+//!     - `LBVH` emits the `n - 1` internal nodes of the binary radix tree over the `n` sorted codes, the root being node 0
+//!
+//! The codes are sorted with `kernels::sort`, which sorts keys without a permutation: packing each code with the index of its point in a `u64` key, `code << 32 | index`, and sorting them with `sort::STAGES_U64` keeps the permutation in the low words of the sorted keys, the codes being in the high words.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::bvh::{self, Bounds, Child, Node};
+//! use sgpu_compute::kernels::sort::{self, SortParams};
+//!
+//! let points: [[f32; 4]; 4] = [
+//!     [0.9, 0.9, 0.9, 0.0],
+//!     [0.1, 0.1, 0.1, 0.0],
+//!     [0.8, 0.1, 0.1, 0.0],
+//!     [0.1, 0.9, 0.1, 0.0],
+//! ];
+//! let gpu = GpuCompute::new();
+//! let mut morton = gpu.gen_pipeline::<[[f32; 4]; 4], Bounds, [u32; 4], 1>(None, [bvh::MORTON]);
+//! morton.write_uniform(&Bounds::new([0.0; 3], [1.0; 3]));
+//! let keys = morton.run(&points, [bvh::workgroups(4)], |codes| {
+//!     std::array::from_fn::<u64, 4, _>(|i| u64::from(codes[i]) << 32 | i as u64)
+//! });
+//!
+//! let params = SortParams::new(4, 2);
+//! let mut sorter = gpu.gen_pipeline::<[u64; 4], SortParams, [u64; 4], 26>(params.scratchpad_size(), sort::STAGES_U64);
+//! sorter.write_uniform(&params);
+//! let sorted = sorter.run(&keys, params.workgroups(), |sorted| *sorted);
+//! let codes = sorted.map(|key| (key >> 32) as u32);
+//! let permutation = sorted.map(|key| key as u32);
+//! assert_eq!(permutation[0], 1);
+//!
+//! let mut lbvh = gpu.gen_pipeline::<[u32; 4], (), [Node; 3], 1>(None, [bvh::LBVH]);
+//! let nodes = lbvh.run(&codes, [bvh::workgroups(3)], |nodes| *nodes);
+//! assert_eq!(nodes[0].left(), Child::Internal(1));
+//! assert_eq!(nodes[1].left(), Child::Leaf(0));
+//! ```
+use crate::StageDesc;
+
+/// WGSL source of the `morton` entry point.
+pub const MORTON_SHADER: &str = sgpu_compute_core::kernels::bvh::MORTON_SHADER;
+
+/// WGSL source of the `lbvh` entry point.
+pub const LBVH_SHADER: &str = sgpu_compute_core::kernels::bvh::LBVH_SHADER;
+
+/// Number of elements processed by a single workgroup.
+pub const WORKGROUP_SIZE: u32 = 64;
+
+/// Stage computing the Morton code of each `[x, y, z, _]` point of the input. Expects a `Bounds` uniform.
+pub const MORTON: StageDesc = StageDesc::new(MORTON_SHADER, "morton").with_name("morton");
+
+/// Stage emitting the internal nodes of the hierarchy from sorted Morton codes. The output must contain one node less than the input.
+pub const LBVH: StageDesc = StageDesc::new(LBVH_SHADER, "lbvh").with_name("lbvh");
+
+/// Returns the workgroups needed to process `n` elements.
+#[inline]
+pub const fn workgroups(n: usize) -> (u32, u32, u32) {
+    ((n as u32).div_ceil(WORKGROUP_SIZE), 1, 1)
+}
+
+/// Bounds of the scene, points outside of them are clamped to the closest cell.
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct Bounds {
+    pub min: [f32; 3],
+    _pad0: f32,
+    pub max: [f32; 3],
+    _pad1: f32,
+}
+
+impl Bounds {
+    #[inline]
+    pub const fn new(min: [f32; 3], max: [f32; 3]) -> Self {
+        Self {
+            min,
+            _pad0: 0.0,
+            max,
+            _pad1: 0.0,
+        }
+    }
+}
+
+/// Child of an internal `Node`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Child {
+    /// Index of a leaf, i.e. a position in the sorted Morton codes.
+    Leaf(u32),
+    /// Index of another internal node.
+    Internal(u32),
+}
+
+/// Internal node of the hierarchy as written by the `LBVH` stage.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct Node {
+    left: u32,
+    right: u32,
+}
+
+impl Node {
+    const LEAF: u32 = 0x8000_0000;
+
+    #[inline]
+    pub const fn left(&self) -> Child {
+        Self::child(self.left)
+    }
+
+    #[inline]
+    pub const fn right(&self) -> Child {
+        Self::child(self.right)
+    }
+
+    #[inline]
+    const fn child(raw: u32) -> Child {
+        if raw & Self::LEAF != 0 {
+            Child::Leaf(raw & !Self::LEAF)
+        } else {
+            Child::Internal(raw)
+        }
+    }
+}
@@ -0,0 +1,94 @@
+//! Connected-component labeling of binary images by iterative label propagation.
+//!
+//! The input is a row-major mask where non-zero values are foreground. Each foreground pixel ends up labeled with the smallest row-major index of its 4-connected component, background pixels are labeled `BACKGROUND`.
+//!
+//! A run is made of the `init` stage followed by `K - 1` propagation stages. When `CclParams::reset` is set, `init` labels each pixel with its own index, otherwise the labels of the previous run are kept. The output reports whether any label changed during the run, so the pipeline is run again until the labels are stable.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::ccl::{self, CclParams, Labels};
+//!
+//! #[rustfmt::skip]
+//! let mask = [
+//!     1, 1, 0, 1,
+//!     0, 1, 0, 1,
+//!     1, 0, 0, 1,
+//! ];
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline::<[u32; 12], CclParams, Labels<12>, 4>(None, ccl::stages());
+//! pipeline.write_uniform(&CclParams::new(4, 3, true));
+//! let labels = loop {
+//!     let (changed, labels) = pipeline.run(&mask, ccl::workgroups(4, 3), |out| (out.changed(), out.labels));
+//!     if !changed {
+//!         break labels;
+//!     }
+//!     pipeline.write_uniform(&CclParams::new(4, 3, false));
+//! };
+//! let b = ccl::BACKGROUND;
+//! assert_eq!(labels, [0, 0, b, 3, b, 0, b, 3, 8, b, b, 3]);
+//! ```
+use crate::StageDesc;
+
+/// WGSL source containing the `init` and `propagate` entry points.
+pub const SHADER: &str = sgpu_compute_core::kernels::ccl::SHADER;
+
+/// Label of the background pixels.
+pub const BACKGROUND: u32 = u32::MAX;
+
+/// Stage initializing the labels and clearing the changed flag.
+pub const INIT: StageDesc = StageDesc::new(SHADER, "init").with_name("ccl_init");
+
+/// Stage doing one propagation iteration.
+pub const PROPAGATE: StageDesc = StageDesc::new(SHADER, "propagate").with_name("ccl_propagate");
+
+/// Returns `INIT` followed by `K - 1` times `PROPAGATE`.
+pub fn stages<const K: usize>() -> [StageDesc; K] {
+    std::array::from_fn(|i| if i == 0 { INIT } else { PROPAGATE })
+}
+
+/// Returns the workgroups of each stage for a `width * height` image.
+#[inline]
+pub const fn workgroups<const K: usize>(width: u32, height: u32) -> [(u32, u32, u32); K] {
+    [(width.div_ceil(8), height.div_ceil(8), 1); K]
+}
+
+/// Uniform of the stages.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct CclParams {
+    pub width: u32,
+    pub height: u32,
+    /// Non-zero to restart the labeling from the input mask.
+    pub reset: u32,
+}
+
+impl CclParams {
+    #[inline]
+    pub const fn new(width: u32, height: u32, reset: bool) -> Self {
+        Self {
+            width,
+            height,
+            reset: reset as u32,
+        }
+    }
+}
+
+/// Output of the pipeline for an image of `N` pixels.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct Labels<const N: usize> {
+    changed: u32,
+    pub labels: [u32; N],
+}
+
+impl<const N: usize> Labels<N> {
+    /// Whether a label changed during the run.
+    #[inline]
+    pub const fn changed(&self) -> bool {
+        self.changed != 0
+    }
+}
+
+// SAFETY: the struct is only made of `u32`, so it has no padding.
+unsafe impl<const N: usize> bytemuck::Zeroable for Labels<N> {}
+unsafe impl<const N: usize> bytemuck::Pod for Labels<N> {}
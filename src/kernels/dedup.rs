@@ -0,0 +1,134 @@
+//! Duplicate detection in `u32` and `u64` arrays.
+//!
+//! The keys are sorted with the radix sort of the `sort` module, then each key different from the previous one is flagged, the flags are scanned and the first occurrence of each key is compacted into the output along with its number of occurrences. The output holds the unique keys in increasing order, it is a `Uniques<Unique32, MAX>` for `STAGES_U32` and a `Uniques<Unique64, MAX>` for `STAGES_U64`.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::dedup::{self, Unique32, Uniques};
+//! use sgpu_compute::kernels::sort::SortParams;
+//!
+//! let params = SortParams::new(8, 1);
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline::<[u32; 8], SortParams, Uniques<Unique32, 8>, 17>(dedup::scratchpad_size(&params), dedup::STAGES_U32);
+//! pipeline.write_uniform(&params);
+//! let uniques = pipeline.run(&[7, 3, 7, 7, 1, 3, 9, 7], dedup::workgroups(&params), |uniques| uniques.to_vec());
+//! assert_eq!(uniques, [Unique32::new(1, 1), Unique32::new(3, 2), Unique32::new(7, 4), Unique32::new(9, 1)]);
+//! ```
+use super::sort::{self, SortParams};
+use crate::StageDesc;
+use std::num::NonZeroUsize;
+
+/// WGSL source containing the radix sort entry points and the `dedup_flag`, `dedup_scan`, `dedup_compact` and `dedup_count` entry points.
+pub const SHADER: &str = sgpu_compute_core::kernels::dedup::SHADER;
+
+/// Stages finding the unique `u32` keys.
+pub const STAGES_U32: [StageDesc; 17] = stages(1);
+
+/// Stages finding the unique `u64` keys.
+pub const STAGES_U64: [StageDesc; 29] = stages(2);
+
+const fn stages<const K: usize>(key_words: usize) -> [StageDesc; K] {
+    const fn stage(entrypoint: &'static str) -> StageDesc {
+        StageDesc::new(SHADER, entrypoint).with_name(entrypoint)
+    }
+    let mut stages = sort::stages::<K>(SHADER, key_words);
+    stages[K - 4] = stage("dedup_flag");
+    stages[K - 3] = stage("dedup_scan");
+    stages[K - 2] = stage("dedup_compact");
+    stages[K - 1] = stage("dedup_count");
+    stages
+}
+
+/// Returns the scratchpad size needed by the stages.
+#[inline]
+pub const fn scratchpad_size(params: &SortParams) -> Option<NonZeroUsize> {
+    let words =
+        params.sort_words() + params.count.div_ceil(sort::BLOCK) as usize + params.count as usize;
+    NonZeroUsize::new(words * std::mem::size_of::<u32>())
+}
+
+/// Returns the workgroups of each stage.
+pub fn workgroups<const K: usize>(params: &SortParams) -> [(u32, u32, u32); K] {
+    let blocks = params.count.div_ceil(sort::BLOCK);
+    let mut workgroups = params.workgroups::<K>();
+    workgroups[K - 4] = (blocks, 1, 1);
+    workgroups[K - 3] = (1, 1, 1);
+    workgroups[K - 2] = (blocks, 1, 1);
+    workgroups[K - 1] = (params.count.div_ceil(64), 1, 1);
+    workgroups
+}
+
+/// A unique `u32` key and its number of occurrences.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct Unique32 {
+    pub key: u32,
+    pub count: u32,
+}
+
+impl Unique32 {
+    #[inline]
+    pub const fn new(key: u32, count: u32) -> Self {
+        Self { key, count }
+    }
+}
+
+/// A unique `u64` key and its number of occurrences.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct Unique64 {
+    pub key: u64,
+    pub count: u32,
+    _pad0: u32,
+}
+
+impl Unique64 {
+    #[inline]
+    pub const fn new(key: u64, count: u32) -> Self {
+        Self {
+            key,
+            count,
+            _pad0: 0,
+        }
+    }
+}
+
+/// Output of the stages holding at most `MAX` unique keys, `E` being `Unique32` or `Unique64`.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct Uniques<E, const MAX: usize> {
+    count: u32,
+    _pad0: u32,
+    entries: [E; MAX],
+}
+
+impl<E: Copy, const MAX: usize> Uniques<E, MAX> {
+    /// Number of unique keys, which can be greater than `MAX`.
+    #[inline]
+    pub const fn count(&self) -> usize {
+        self.count as usize
+    }
+
+    /// Whether some unique keys were dropped because there are more than `MAX` of them.
+    #[inline]
+    pub const fn overflowed(&self) -> bool {
+        self.count() > MAX
+    }
+
+    /// The unique keys that fit in the output, in increasing order.
+    #[inline]
+    pub fn as_slice(&self) -> &[E] {
+        &self.entries[..self.count().min(MAX)]
+    }
+
+    #[inline]
+    pub fn to_vec(&self) -> Vec<E> {
+        self.as_slice().to_vec()
+    }
+}
+
+// SAFETY: the header is 8 bytes and the entries are made of `u32` and `u64` without padding, so the struct has no padding.
+unsafe impl<const MAX: usize> bytemuck::Zeroable for Uniques<Unique32, MAX> {}
+unsafe impl<const MAX: usize> bytemuck::Pod for Uniques<Unique32, MAX> {}
+unsafe impl<const MAX: usize> bytemuck::Zeroable for Uniques<Unique64, MAX> {}
+unsafe impl<const MAX: usize> bytemuck::Pod for Uniques<Unique64, MAX> {}
@@ -0,0 +1,239 @@
+//! Block-based spectral processing of audio signals.
+//!
+//! A signal is cut into `frames` frames of `frame_size` samples, a power of two up to `MAX_FRAME_SIZE`, starting every `hop_size` samples. The stages share their bindings, so custom spectral stages can be inserted between them:
+//!     - `FRAME` multiplies each frame of the input signal by the window and stores it in the scratchpad as complex values
+//!     - `FORWARD_FFT` and `INVERSE_FFT` transform each frame of the scratchpad in place, the inverse transform is normalized
+//!     - `WRITE_SPECTRUM` writes the frames of the scratchpad to the output as interleaved real and imaginary parts
+//!     - `OVERLAP_ADD` sums the real parts of the frames of the scratchpad into the output signal
+//!
+//! With a `Window::Hann` window and a hop size of half the frame size, `FRAME`, `FORWARD_FFT`, `INVERSE_FFT` and `OVERLAP_ADD` reconstruct the input signal, except for the first and the last `frame_size - hop_size` samples which are only covered by one frame. `BlockStream` handles these overlaps for a stream cut in blocks of `frames * hop_size` samples.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::dsp::{self, DspParams, Window};
+//!
+//! const FRAME_SIZE: u32 = 64;
+//!
+//! // Sine with 4 periods per frame.
+//! let signal: [f32; FRAME_SIZE as usize] = std::array::from_fn(|i| (std::f32::consts::TAU * 4.0 * i as f32 / FRAME_SIZE as f32).sin());
+//! let params = DspParams::new(FRAME_SIZE, FRAME_SIZE, 1, Window::Rectangular);
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline::<[f32; 64], DspParams, [[f32; 2]; 64], 3>(params.scratchpad_size(), dsp::ANALYSIS);
+//! pipeline.write_uniform(&params);
+//! let spectrum = pipeline.run(&signal, params.analysis_workgroups(), |spectrum| *spectrum);
+//! let peak = (0..32).max_by(|&a, &b| spectrum[a][1].abs().total_cmp(&spectrum[b][1].abs())).unwrap();
+//! assert_eq!(peak, 4);
+//! ```
+use crate::StageDesc;
+use std::num::NonZeroUsize;
+
+/// WGSL source containing the `frame`, `forward_fft`, `inverse_fft`, `write_spectrum` and `overlap_add` entry points.
+pub const SHADER: &str = sgpu_compute_core::kernels::dsp::SHADER;
+
+/// Largest frame size supported by the FFT stages, which transform a frame in workgroup memory.
+pub const MAX_FRAME_SIZE: u32 = 1024;
+
+/// Stage windowing the frames of the input signal.
+pub const FRAME: StageDesc = StageDesc::new(SHADER, "frame").with_name("dsp_frame");
+
+/// Stage transforming each frame to the frequency domain.
+pub const FORWARD_FFT: StageDesc =
+    StageDesc::new(SHADER, "forward_fft").with_name("dsp_forward_fft");
+
+/// Stage transforming each frame back to the time domain.
+pub const INVERSE_FFT: StageDesc =
+    StageDesc::new(SHADER, "inverse_fft").with_name("dsp_inverse_fft");
+
+/// Stage writing the spectra to the output.
+pub const WRITE_SPECTRUM: StageDesc =
+    StageDesc::new(SHADER, "write_spectrum").with_name("dsp_write_spectrum");
+
+/// Stage writing the overlap-added frames to the output.
+pub const OVERLAP_ADD: StageDesc =
+    StageDesc::new(SHADER, "overlap_add").with_name("dsp_overlap_add");
+
+/// Stages computing the spectrum of each frame, dispatch them with `DspParams::analysis_workgroups`.
+pub const ANALYSIS: [StageDesc; 3] = [FRAME, FORWARD_FFT, WRITE_SPECTRUM];
+
+/// Stages transforming each frame back and forth and overlap-adding them, dispatch them with `DspParams::resynthesis_workgroups`.
+pub const RESYNTHESIS: [StageDesc; 4] = [FRAME, FORWARD_FFT, INVERSE_FFT, OVERLAP_ADD];
+
+/// Window applied to each frame by `FRAME`. The windows are periodic.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u32)]
+pub enum Window {
+    Rectangular = 0,
+    Hann = 1,
+    Hamming = 2,
+    Blackman = 3,
+}
+
+impl Window {
+    /// Coefficient of the sample `i` of a frame of `frame_size` samples, as computed by the shader.
+    pub fn coefficient(self, i: u32, frame_size: u32) -> f32 {
+        let x = std::f32::consts::TAU * i as f32 / frame_size as f32;
+        match self {
+            Window::Rectangular => 1.0,
+            Window::Hann => 0.5 - 0.5 * x.cos(),
+            Window::Hamming => 0.54 - 0.46 * x.cos(),
+            Window::Blackman => 0.42 - 0.5 * x.cos() + 0.08 * (2.0 * x).cos(),
+        }
+    }
+}
+
+/// Uniform of the stages.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct DspParams {
+    pub frame_size: u32,
+    pub hop_size: u32,
+    pub frames: u32,
+    window: u32,
+}
+
+impl DspParams {
+    /// # Panics
+    /// If `frame_size` is not a power of two between 2 and `MAX_FRAME_SIZE`, or if `hop_size` or `frames` is zero.
+    #[inline]
+    pub const fn new(frame_size: u32, hop_size: u32, frames: u32, window: Window) -> Self {
+        assert!(
+            frame_size.is_power_of_two() && frame_size >= 2 && frame_size <= MAX_FRAME_SIZE,
+            "The frame size must be a power of two between 2 and MAX_FRAME_SIZE"
+        );
+        assert!(
+            hop_size > 0 && frames > 0,
+            "The hop size and the number of frames must be positive"
+        );
+        Self {
+            frame_size,
+            hop_size,
+            frames,
+            window: window as u32,
+        }
+    }
+
+    /// Number of samples of the input signal and of the output of `OVERLAP_ADD`.
+    #[inline]
+    pub const fn signal_len(&self) -> usize {
+        (self.frames as usize - 1) * self.hop_size as usize + self.frame_size as usize
+    }
+
+    /// Number of complex values of the spectra written by `WRITE_SPECTRUM`.
+    #[inline]
+    pub const fn spectrum_len(&self) -> usize {
+        self.frames as usize * self.frame_size as usize
+    }
+
+    /// Returns the scratchpad size needed by the complex frames.
+    #[inline]
+    pub const fn scratchpad_size(&self) -> Option<NonZeroUsize> {
+        NonZeroUsize::new(self.spectrum_len() * std::mem::size_of::<[f32; 2]>())
+    }
+
+    /// Workgroups of `FRAME` and `WRITE_SPECTRUM`.
+    #[inline]
+    pub const fn frame_workgroups(&self) -> (u32, u32, u32) {
+        ((self.spectrum_len() as u32).div_ceil(64), 1, 1)
+    }
+
+    /// Workgroups of `FORWARD_FFT` and `INVERSE_FFT`, one per frame.
+    #[inline]
+    pub const fn fft_workgroups(&self) -> (u32, u32, u32) {
+        (self.frames, 1, 1)
+    }
+
+    /// Workgroups of `OVERLAP_ADD`.
+    #[inline]
+    pub const fn overlap_add_workgroups(&self) -> (u32, u32, u32) {
+        ((self.signal_len() as u32).div_ceil(64), 1, 1)
+    }
+
+    /// Returns the workgroups of `ANALYSIS`.
+    #[inline]
+    pub const fn analysis_workgroups(&self) -> [(u32, u32, u32); 3] {
+        [
+            self.frame_workgroups(),
+            self.fft_workgroups(),
+            self.frame_workgroups(),
+        ]
+    }
+
+    /// Returns the workgroups of `RESYNTHESIS`.
+    #[inline]
+    pub const fn resynthesis_workgroups(&self) -> [(u32, u32, u32); 4] {
+        [
+            self.frame_workgroups(),
+            self.fft_workgroups(),
+            self.fft_workgroups(),
+            self.overlap_add_workgroups(),
+        ]
+    }
+}
+
+/// Overlap handling of a stream processed in fixed blocks of `frames * hop_size` samples by a pipeline ending with `OVERLAP_ADD`.
+///
+/// `BlockStream::input` prepends the last `frame_size - hop_size` samples of the previous blocks to each block, so the frames overlap across blocks, and `BlockStream::output` adds the tail of the previous run to the beginning of each run. The output stream is delayed by `frame_size - hop_size` samples.
+#[derive(Debug, Clone)]
+pub struct BlockStream {
+    params: DspParams,
+    history: Vec<f32>,
+    tail: Vec<f32>,
+}
+
+impl BlockStream {
+    /// Creates a stream starting with silence.
+    pub fn new(params: DspParams) -> Self {
+        let overlap = params.signal_len() - Self::block_len_of(&params);
+        Self {
+            params,
+            history: vec![0.0; overlap],
+            tail: vec![0.0; overlap],
+        }
+    }
+
+    const fn block_len_of(params: &DspParams) -> usize {
+        params.frames as usize * params.hop_size as usize
+    }
+
+    /// Number of samples of each block.
+    #[inline]
+    pub const fn block_len(&self) -> usize {
+        Self::block_len_of(&self.params)
+    }
+
+    /// Fills `input`, the input signal of the next run, with the history of the stream followed by `block`.
+    ///
+    /// # Panics
+    /// If `block` doesn't have `BlockStream::block_len` samples or `input` doesn't have `DspParams::signal_len` samples.
+    pub fn input(&mut self, block: &[f32], input: &mut [f32]) {
+        assert_eq!(block.len(), self.block_len(), "Wrong block length");
+        assert_eq!(input.len(), self.params.signal_len(), "Wrong input length");
+        let overlap = self.history.len();
+        input[..overlap].copy_from_slice(&self.history);
+        input[overlap..].copy_from_slice(block);
+        self.history
+            .copy_from_slice(&input[input.len() - overlap..]);
+    }
+
+    /// Fills `block` with the next block of the output stream from `output`, the output signal of the run.
+    ///
+    /// # Panics
+    /// If `output` doesn't have `DspParams::signal_len` samples or `block` doesn't have `BlockStream::block_len` samples.
+    pub fn output(&mut self, output: &[f32], block: &mut [f32]) {
+        assert_eq!(
+            output.len(),
+            self.params.signal_len(),
+            "Wrong output length"
+        );
+        assert_eq!(block.len(), self.block_len(), "Wrong block length");
+        let previous = std::mem::take(&mut self.tail);
+        let mut tail = previous.into_iter().chain(std::iter::repeat(0.0));
+        for (sample, value) in block.iter_mut().zip(output) {
+            *sample = value + tail.next().unwrap_or_default();
+        }
+        self.tail = output[block.len()..]
+            .iter()
+            .map(|value| value + tail.next().unwrap_or_default())
+            .collect();
+    }
+}
@@ -0,0 +1,375 @@
+//! Per-element comparison of results with a stored reference.
+//!
+//! An `ErrorMapAsync` keeps a reference of `f32` values on the GPU and compares results with it: the absolute error `|actual - reference|` and the relative error `|actual - reference| / |reference|` of each element are written to an error map which stays on the GPU, and only an `ErrorSummary` is read back, with the maximum errors, their first indices and the number of elements over the thresholds. Results computed by other pipelines are compared without any readback with `ErrorMapAsync::compare_buffer`, so large results are validated without reading back either them or the reference. The error map itself is only read with `ErrorMapAsync::errors`, e.g. to locate the mismatches once the summary reports some.
+//!
+//! Equal values, including infinities of the same sign and two NaNs, have no error. A NaN compared with a number and a number compared with a zero or infinite reference have infinite errors.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::error_map::ErrorMap;
+//!
+//! let reference: Vec<f32> = (0..10_000).map(|i| i as f32).collect();
+//! let mut actual = reference.clone();
+//! actual[1234] += 0.5;
+//! actual[5000] = f32::NAN;
+//! let gpu = GpuCompute::new();
+//! let mut map = ErrorMap::new(&gpu, &reference);
+//! map.set_thresholds(1e-3, 1e-6);
+//! let summary = map.compare(&actual);
+//! assert_eq!((summary.mismatches, summary.nan_mismatches), (2, 1));
+//! assert_eq!(summary.max_absolute_index, 5000);
+//! assert_eq!(map.errors()[1234].absolute, 0.5);
+//! ```
+use crate::{
+    BufferSizes, Buffers, CompiledStages, GpuComputeAsync, GpuRef, ScratchpadDesc, StageDesc,
+};
+use std::num::NonZeroUsize;
+
+/// WGSL source containing the `error_map` and `argmax` entry points.
+pub const SHADER: &str = sgpu_compute_core::kernels::error_map::SHADER;
+
+/// Number of invocations of a workgroup.
+const WORKGROUP_SIZE: u32 = 256;
+
+/// Maximum number of workgroups of a stage, the invocations loop over the elements.
+const MAX_WORKGROUPS: u32 = 4096;
+
+const STAGES: [StageDesc; 2] = [
+    StageDesc::new(SHADER, "error_map").with_name("error_map"),
+    StageDesc::new(SHADER, "argmax").with_name("error_map_argmax"),
+];
+
+/// Uniform of the stages.
+#[derive(Debug, Copy, Clone, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+struct Params {
+    len: u32,
+    absolute: f32,
+    relative: f32,
+    _pad: u32,
+}
+
+/// Summary as written by the stages, see the shader.
+#[derive(Debug, Copy, Clone, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+struct RawSummary {
+    max_absolute: u32,
+    max_absolute_index: u32,
+    max_relative: u32,
+    max_relative_index: u32,
+    over_absolute: u32,
+    over_relative: u32,
+    mismatches: u32,
+    nan_mismatches: u32,
+}
+
+/// Errors of an element, the layout of the error map.
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct ElementError {
+    pub absolute: f32,
+    pub relative: f32,
+}
+
+/// Summary of a comparison, see `ErrorMapAsync::compare`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ErrorSummary {
+    pub max_absolute: f32,
+    /// First element with the maximum absolute error.
+    pub max_absolute_index: usize,
+    pub max_relative: f32,
+    /// First element with the maximum relative error.
+    pub max_relative_index: usize,
+    /// Number of elements whose absolute error is over the absolute threshold.
+    pub over_absolute: u32,
+    /// Number of elements whose relative error is over the relative threshold.
+    pub over_relative: u32,
+    /// Number of elements over both thresholds, which fail a `float_cmp::Tolerance` with these bounds.
+    pub mismatches: u32,
+    /// Number of elements where only one of the values is NaN.
+    pub nan_mismatches: u32,
+}
+
+impl ErrorSummary {
+    /// Whether all the elements are within one of the thresholds.
+    #[inline]
+    pub fn is_close(&self) -> bool {
+        self.mismatches == 0
+    }
+}
+
+impl From<RawSummary> for ErrorSummary {
+    fn from(raw: RawSummary) -> Self {
+        Self {
+            max_absolute: f32::from_bits(raw.max_absolute),
+            max_absolute_index: !raw.max_absolute_index as usize,
+            max_relative: f32::from_bits(raw.max_relative),
+            max_relative_index: !raw.max_relative_index as usize,
+            over_absolute: raw.over_absolute,
+            over_relative: raw.over_relative,
+            mismatches: raw.mismatches,
+            nan_mismatches: raw.nan_mismatches,
+        }
+    }
+}
+
+/// Reference of `size` values compared with results on the GPU, see the module documentation.
+pub struct ErrorMapAsync<'a> {
+    buffers: Buffers,
+    stages: CompiledStages<2>,
+    params: Params,
+    device: GpuRef<'a>,
+}
+
+impl<'a> ErrorMapAsync<'a> {
+    /// Uploads `reference`, the results are compared with thresholds of 0 until `ErrorMapAsync::set_thresholds` is called.
+    ///
+    /// # Panics
+    /// If `reference` is empty.
+    pub async fn new(gpu: &'a GpuComputeAsync, reference: &[f32]) -> Self {
+        assert!(!reference.is_empty(), "The reference must not be empty");
+        let len = reference.len();
+        let sizes = BufferSizes {
+            uniform: std::mem::size_of::<Params>(),
+            scratchpad: None,
+            scratchpads: vec![
+                ScratchpadDesc {
+                    name: Some("Error map reference"),
+                    size: NonZeroUsize::new(std::mem::size_of_val(reference)).unwrap(),
+                },
+                ScratchpadDesc {
+                    name: Some("Error map"),
+                    size: NonZeroUsize::new(len * std::mem::size_of::<ElementError>()).unwrap(),
+                },
+            ],
+            input: std::mem::size_of_val(reference),
+            output: std::mem::size_of::<RawSummary>(),
+            extra_outputs: Vec::new(),
+            push_constants: 0,
+        };
+        let stages = gpu
+            .compile_stages(&sizes, STAGES, None)
+            .await
+            .unwrap_or_else(|error| panic!("{}", error));
+        let mut map = Self {
+            buffers: gpu.create_buffers(&sizes, &stages.bindgroup_layout, gpu.zero_init),
+            stages,
+            params: Params {
+                len: len as u32,
+                absolute: 0.0,
+                relative: 0.0,
+                _pad: 0,
+            },
+            device: GpuRef::Borrowed(gpu),
+        };
+        map.set_reference(reference);
+        map.set_thresholds(0.0, 0.0);
+        map
+    }
+
+    /// Number of values of the reference.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.params.len as usize
+    }
+
+    /// This method is used to replace the reference, e.g. with the next one of a test suite.
+    ///
+    /// # Panics
+    /// If `reference` doesn't have `ErrorMapAsync::size` values.
+    pub fn set_reference(&mut self, reference: &[f32]) {
+        assert_eq!(
+            reference.len(),
+            self.size(),
+            "Expected a reference of {} values, got {}",
+            self.size(),
+            reference.len()
+        );
+        self.device.queue.write_buffer(
+            &self.buffers.scratchpads[0],
+            0,
+            bytemuck::cast_slice(reference),
+        );
+    }
+
+    /// This method is used to set the thresholds of the counts of the summary, an error is over its threshold if it is strictly greater.
+    pub fn set_thresholds(&mut self, absolute: f32, relative: f32) {
+        self.params.absolute = absolute;
+        self.params.relative = relative;
+        self.device.queue.write_buffer(
+            self.buffers
+                .uniform
+                .as_ref()
+                .expect("The stages have a uniform"),
+            0,
+            bytemuck::bytes_of(&self.params),
+        );
+    }
+
+    /// The error map of the last comparison, `ErrorMapAsync::size` `ElementError`s. It has the `STORAGE` and `COPY_SRC` usages, so it can be used by other passes, e.g. to visualize the errors.
+    #[inline]
+    pub fn errors_buffer(&self) -> &wgpu::Buffer {
+        &self.buffers.scratchpads[1]
+    }
+
+    /// Compares `actual` with the reference and returns the summary, the error map stays on the GPU.
+    ///
+    /// # Panics
+    /// If `actual` doesn't have `ErrorMapAsync::size` values.
+    pub async fn compare(&mut self, actual: &[f32]) -> ErrorSummary {
+        assert_eq!(
+            actual.len(),
+            self.size(),
+            "Expected {} values, got {}",
+            self.size(),
+            actual.len()
+        );
+        let input = self.buffers.input.as_ref().expect("The input isn't empty");
+        self.device
+            .queue
+            .write_buffer(input, 0, bytemuck::cast_slice(actual));
+        self.submit(None).await
+    }
+
+    /// Same as `ErrorMapAsync::compare`, but the values are copied from `buffer` at `offset` on the GPU, e.g. from `PipelineAsync::output_buffer` of the pipeline under test.
+    ///
+    /// # Panics
+    /// If `buffer` doesn't have the `COPY_SRC` usage or doesn't have `ErrorMapAsync::size` values after `offset`.
+    pub async fn compare_buffer(&mut self, buffer: &wgpu::Buffer, offset: u64) -> ErrorSummary {
+        assert!(
+            buffer.usage().contains(wgpu::BufferUsages::COPY_SRC),
+            "The compared buffer must have the COPY_SRC usage"
+        );
+        let size = (self.size() * std::mem::size_of::<f32>()) as u64;
+        assert!(
+            offset + size <= buffer.size(),
+            "The compared buffer doesn't have {} values after the offset {}",
+            self.size(),
+            offset
+        );
+        self.submit(Some((buffer, offset))).await
+    }
+
+    /// Copies the values from `source` if there is one, submits the stages and reads back the summary.
+    async fn submit(&mut self, source: Option<(&wgpu::Buffer, u64)>) -> ErrorSummary {
+        let buffers = &self.buffers;
+        let mut encoder =
+            self.device
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Error map"),
+                });
+        if let Some((buffer, offset)) = source {
+            let input = buffers.input.as_ref().expect("The input isn't empty");
+            encoder.copy_buffer_to_buffer(buffer, offset, input, 0, input.size());
+        }
+        encoder.clear_buffer(&buffers.staging, 0, None);
+        let groups = self.params.len.div_ceil(WORKGROUP_SIZE).min(MAX_WORKGROUPS);
+        self.stages.record_passes(
+            &mut encoder,
+            &buffers.bindgroup,
+            &self.device,
+            &[(groups, 1, 1), (groups, 1, 1)],
+        );
+        encoder.copy_buffer_to_buffer(
+            &buffers.staging,
+            0,
+            &buffers.output,
+            0,
+            buffers.output.size(),
+        );
+        self.device.queue.submit(Some(encoder.finish()));
+        let summary: RawSummary = read_buffer(&self.device, &buffers.output, |bytes| {
+            bytemuck::pod_read_unaligned(bytes)
+        })
+        .await;
+        summary.into()
+    }
+
+    /// Reads back the error map of the last comparison.
+    pub async fn errors(&self) -> Vec<ElementError> {
+        let map = self.errors_buffer();
+        let readback = self.device.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Error map readback"),
+            size: map.size(),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = self
+            .device
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_buffer_to_buffer(map, 0, &readback, 0, map.size());
+        self.device.queue.submit(Some(encoder.finish()));
+        read_buffer(&self.device, &readback, |bytes| {
+            bytes
+                .chunks_exact(std::mem::size_of::<ElementError>())
+                .map(bytemuck::pod_read_unaligned)
+                .collect()
+        })
+        .await
+    }
+}
+
+/// Maps `buffer` once the submitted commands are done and calls `callback` on its content.
+async fn read_buffer<T>(
+    device: &GpuComputeAsync,
+    buffer: &wgpu::Buffer,
+    callback: impl FnOnce(&[u8]) -> T,
+) -> T {
+    let (sender, receiver) = flume::bounded(1);
+    buffer.slice(..).map_async(wgpu::MapMode::Read, move |e| {
+        e.expect("Could not map buffer");
+        sender.send(()).unwrap()
+    });
+    device.wait_submitted();
+    receiver.recv_async().await.expect("Error with channel");
+    let res = callback(buffer.slice(..).get_mapped_range().as_ref());
+    buffer.unmap();
+    res
+}
+
+/// Blocking version of `ErrorMapAsync`, it is enabled by the `blocking` feature.
+#[cfg(feature = "blocking")]
+pub struct ErrorMap<'a>(ErrorMapAsync<'a>);
+
+#[cfg(feature = "blocking")]
+impl<'a> ErrorMap<'a> {
+    /// Blocking version of `ErrorMapAsync::new`.
+    pub fn new(gpu: &'a crate::blocking::GpuCompute, reference: &[f32]) -> Self {
+        Self(pollster::block_on(ErrorMapAsync::new(gpu, reference)))
+    }
+
+    /// Blocking version of `ErrorMapAsync::compare`.
+    pub fn compare(&mut self, actual: &[f32]) -> ErrorSummary {
+        pollster::block_on(self.0.compare(actual))
+    }
+
+    /// Blocking version of `ErrorMapAsync::compare_buffer`.
+    pub fn compare_buffer(&mut self, buffer: &wgpu::Buffer, offset: u64) -> ErrorSummary {
+        pollster::block_on(self.0.compare_buffer(buffer, offset))
+    }
+
+    /// Blocking version of `ErrorMapAsync::errors`.
+    pub fn errors(&self) -> Vec<ElementError> {
+        pollster::block_on(self.0.errors())
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<'a> std::ops::Deref for ErrorMap<'a> {
+    type Target = ErrorMapAsync<'a>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl std::ops::DerefMut for ErrorMap<'_> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
@@ -0,0 +1,159 @@
+//! Gather and scatter of elements of any `bytemuck::Pod` type whose size is a multiple of 4 bytes.
+//!
+//! The input is an `Indexed` holding `I` indices followed by the words of the elements. `GATHER` writes `out[i] = data[indices[i]]` and `SCATTER` writes `out[indices[i]] = data[i]`, when several indices are the same one of the elements is written. `GatherParams::len` is the number of elements of the indexed array, the data for a gather and the output for a scatter, and the `BoundsPolicy` chooses what happens to the indices outside of it.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::gather::{self, BoundsPolicy, GatherParams, Indexed, Values};
+//!
+//! let data = [[1.0f32, 2.0], [3.0, 4.0], [5.0, 6.0]];
+//! let input = Indexed::<4, 6>::new([2, 0, 7, 1], &data);
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline::<Indexed<4, 6>, GatherParams, Values<8>, 2>(None, gather::GATHER);
+//! pipeline.write_uniform(&GatherParams::new::<[f32; 2]>(4, 3, BoundsPolicy::Flag));
+//! let (values, first) = pipeline.run(&input, gather::workgroups(4), |out| (out.to_vec::<[f32; 2]>(), out.first_out_of_range()));
+//! assert_eq!(values, [[5.0, 6.0], [1.0, 2.0], [0.0, 0.0], [3.0, 4.0]]);
+//! assert_eq!(first, Some(2));
+//! ```
+use crate::StageDesc;
+
+/// WGSL source containing the `clear`, `gather` and `scatter` entry points.
+pub const SHADER: &str = sgpu_compute_core::kernels::gather::SHADER;
+
+/// Stages of a gather, in order: clearing the out-of-range flag and gathering.
+pub const GATHER: [StageDesc; 2] = [
+    StageDesc::new(SHADER, "clear").with_name("gather_clear"),
+    StageDesc::new(SHADER, "gather").with_name("gather"),
+];
+
+/// Stages of a scatter, in order: clearing the out-of-range flag and scattering.
+pub const SCATTER: [StageDesc; 2] = [
+    StageDesc::new(SHADER, "clear").with_name("scatter_clear"),
+    StageDesc::new(SHADER, "scatter").with_name("scatter"),
+];
+
+/// Returns the workgroups of `GATHER` or `SCATTER` for `count` indices.
+#[inline]
+pub const fn workgroups(count: u32) -> [(u32, u32, u32); 2] {
+    [(1, 1, 1), (count.div_ceil(64), 1, 1)]
+}
+
+/// What happens to an index outside of the indexed array.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u32)]
+pub enum BoundsPolicy {
+    /// The index is clamped to the last element.
+    Clamp = 0,
+    /// The element is not written, so it keeps its previous value.
+    Skip = 1,
+    /// Same as `BoundsPolicy::Skip`, and the output records the number of indices out of range and the first position holding one.
+    Flag = 2,
+}
+
+/// Uniform of the stages.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct GatherParams {
+    /// Number of indices.
+    pub count: u32,
+    /// Number of elements of the indexed array.
+    pub len: u32,
+    element_words: u32,
+    policy: u32,
+}
+
+impl GatherParams {
+    /// Parameters for `count` indices into an array of `len` elements of type `T`.
+    ///
+    /// # Panics
+    /// If the size of `T` is not a non-zero multiple of 4 bytes or if `len` is zero.
+    #[inline]
+    pub const fn new<T: bytemuck::Pod>(count: u32, len: u32, policy: BoundsPolicy) -> Self {
+        let size = std::mem::size_of::<T>();
+        assert!(
+            size > 0 && size.is_multiple_of(4),
+            "The size of the elements must be a multiple of 4 bytes"
+        );
+        assert!(len > 0, "The indexed array must not be empty");
+        Self {
+            count,
+            len,
+            element_words: (size / 4) as u32,
+            policy: policy as u32,
+        }
+    }
+}
+
+/// Input of the stages: `I` indices followed by `W` words of elements.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(C)]
+pub struct Indexed<const I: usize, const W: usize> {
+    pub indices: [u32; I],
+    pub words: [u32; W],
+}
+
+impl<const I: usize, const W: usize> Indexed<I, W> {
+    /// Builds the input from the indices and the elements.
+    ///
+    /// # Panics
+    /// If the elements are not exactly `W` words.
+    pub fn new<T: bytemuck::Pod>(indices: [u32; I], data: &[T]) -> Self {
+        let mut words = [0; W];
+        bytemuck::cast_slice_mut::<u32, u8>(&mut words).copy_from_slice(bytemuck::cast_slice(data));
+        Self { indices, words }
+    }
+}
+
+// SAFETY: the struct is only made of `u32`, so it has no padding.
+unsafe impl<const I: usize, const W: usize> bytemuck::Zeroable for Indexed<I, W> {}
+unsafe impl<const I: usize, const W: usize> bytemuck::Pod for Indexed<I, W> {}
+
+/// Output of the stages: the out-of-range flag followed by `W` words of elements.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(C)]
+pub struct Values<const W: usize> {
+    out_of_range: u32,
+    first_out_of_range: u32,
+    _pad0: u32,
+    _pad1: u32,
+    pub words: [u32; W],
+}
+
+impl<const W: usize> Values<W> {
+    /// Number of indices out of range, only counted with `BoundsPolicy::Flag`.
+    #[inline]
+    pub const fn out_of_range(&self) -> u32 {
+        self.out_of_range
+    }
+
+    /// First position of the indices holding an index out of range, only recorded with `BoundsPolicy::Flag`.
+    #[inline]
+    pub const fn first_out_of_range(&self) -> Option<u32> {
+        if self.out_of_range > 0 {
+            Some(self.first_out_of_range)
+        } else {
+            None
+        }
+    }
+
+    /// The elements of the output.
+    ///
+    /// # Panics
+    /// If `W` words are not a whole number of `T`.
+    #[inline]
+    pub fn to_vec<T: bytemuck::Pod>(&self) -> Vec<T> {
+        let bytes: &[u8] = bytemuck::cast_slice(&self.words);
+        assert!(
+            bytes.len().is_multiple_of(std::mem::size_of::<T>()),
+            "The output is not a whole number of elements"
+        );
+        bytes
+            .chunks_exact(std::mem::size_of::<T>())
+            .map(bytemuck::pod_read_unaligned)
+            .collect()
+    }
+}
+
+// SAFETY: the struct is only made of `u32`, so it has no padding.
+unsafe impl<const W: usize> bytemuck::Zeroable for Values<W> {}
+unsafe impl<const W: usize> bytemuck::Pod for Values<W> {}
@@ -0,0 +1,147 @@
+//! Gray-Scott reaction-diffusion simulation.
+//!
+//! The `u` and `v` concentrations of a `width * height` grid, wrapping around its edges, are stored in two fields of the scratchpad. A run is made of `K - 1` steps ping-ponging between them, so `K` must be odd, followed by an output stage writing either the `v` concentrations as `f32` or an RGBA8 image packed in `u32` which can be copied into a texture as is. The fields persist between runs, the input holding the initial concentrations is only read by `FIRST_STEP` when `GrayScottParams::reset` is set.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::gray_scott::{self, GrayScottOutput, GrayScottParams};
+//!
+//! const WIDTH: u32 = 32;
+//! const N: usize = (WIDTH * WIDTH) as usize;
+//!
+//! let initial: [[f32; 2]; N] = gray_scott::seed_square(WIDTH, WIDTH, 4).try_into().unwrap();
+//! let params = GrayScottParams::new(WIDTH, WIDTH);
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline::<[[f32; 2]; N], GrayScottParams, [f32; N], 9>(gray_scott::scratchpad_size(WIDTH, WIDTH), gray_scott::stages(GrayScottOutput::Concentration));
+//! pipeline.write_uniform(&params.with_reset(true));
+//! pipeline.run(&initial, gray_scott::workgroups(WIDTH, WIDTH), |_| ());
+//! pipeline.write_uniform(&params);
+//! let v = pipeline.run(&initial, gray_scott::workgroups(WIDTH, WIDTH), |v| *v);
+//! assert!(v.iter().all(|v| (0.0..=1.0).contains(v)));
+//! ```
+use crate::StageDesc;
+use std::num::NonZeroUsize;
+
+/// WGSL source containing the `first_step`, `step_ab`, `step_ba`, `write_concentration` and `write_rgba` entry points.
+pub const SHADER: &str = sgpu_compute_core::kernels::gray_scott::SHADER;
+
+/// Step reading the first field, or the input on reset, and writing the second one.
+pub const FIRST_STEP: StageDesc =
+    StageDesc::new(SHADER, "first_step").with_name("gray_scott_first_step");
+
+/// Step reading the first field and writing the second one.
+pub const STEP_AB: StageDesc = StageDesc::new(SHADER, "step_ab").with_name("gray_scott_step_ab");
+
+/// Step reading the second field and writing the first one.
+pub const STEP_BA: StageDesc = StageDesc::new(SHADER, "step_ba").with_name("gray_scott_step_ba");
+
+/// Stage writing the `v` concentrations as `f32`.
+pub const WRITE_CONCENTRATION: StageDesc =
+    StageDesc::new(SHADER, "write_concentration").with_name("gray_scott_write_concentration");
+
+/// Stage writing the colors as RGBA8 packed in `u32`.
+pub const WRITE_RGBA: StageDesc =
+    StageDesc::new(SHADER, "write_rgba").with_name("gray_scott_write_rgba");
+
+/// What the output stage writes, one value per cell.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GrayScottOutput {
+    /// The `v` concentration, as `f32`.
+    Concentration,
+    /// A color, as RGBA8 packed in `u32`, i.e. `[u8; 4]` per cell.
+    Rgba,
+}
+
+/// Returns `FIRST_STEP` and `K - 2` steps alternating `STEP_BA` and `STEP_AB` followed by the output stage.
+///
+/// # Panics
+/// If `K` is even, since the fields would not end in the first one.
+pub fn stages<const K: usize>(output: GrayScottOutput) -> [StageDesc; K] {
+    assert!(
+        K % 2 == 1,
+        "Gray-Scott pipelines need an odd number of stages"
+    );
+    std::array::from_fn(|i| match (i == K - 1, output) {
+        (true, GrayScottOutput::Concentration) => WRITE_CONCENTRATION,
+        (true, GrayScottOutput::Rgba) => WRITE_RGBA,
+        (false, _) if i == 0 => FIRST_STEP,
+        (false, _) if i % 2 == 0 => STEP_AB,
+        (false, _) => STEP_BA,
+    })
+}
+
+/// Returns the scratchpad size needed by the two fields of a `width * height` grid.
+#[inline]
+pub const fn scratchpad_size(width: u32, height: u32) -> Option<NonZeroUsize> {
+    NonZeroUsize::new(2 * width as usize * height as usize * std::mem::size_of::<[f32; 2]>())
+}
+
+/// Returns the workgroups of each stage for a `width * height` grid.
+#[inline]
+pub const fn workgroups<const K: usize>(width: u32, height: u32) -> [(u32, u32, u32); K] {
+    [(width.div_ceil(8), height.div_ceil(8), 1); K]
+}
+
+/// Returns the initial concentrations of a `width * height` grid: `u = 1` and `v = 0` everywhere, except in a centered square of `size` cells where `u = 0.5` and `v = 0.25`.
+pub fn seed_square(width: u32, height: u32, size: u32) -> Vec<[f32; 2]> {
+    let (x0, y0) = (
+        width.saturating_sub(size) / 2,
+        height.saturating_sub(size) / 2,
+    );
+    (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            if (x0..x0 + size).contains(&x) && (y0..y0 + size).contains(&y) {
+                [0.5, 0.25]
+            } else {
+                [1.0, 0.0]
+            }
+        })
+        .collect()
+}
+
+/// Uniform of the stages.
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct GrayScottParams {
+    pub width: u32,
+    pub height: u32,
+    /// Non-zero to restart the simulation from the input.
+    pub reset: u32,
+    pub feed: f32,
+    pub kill: f32,
+    pub diffusion_u: f32,
+    pub diffusion_v: f32,
+    /// Time step of each step.
+    pub dt: f32,
+}
+
+impl GrayScottParams {
+    /// Parameters of a `width * height` grid giving mitosis-like patterns.
+    #[inline]
+    pub const fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            reset: 0,
+            feed: 0.0367,
+            kill: 0.0649,
+            diffusion_u: 1.0,
+            diffusion_v: 0.5,
+            dt: 1.0,
+        }
+    }
+
+    #[inline]
+    pub const fn with_feed_kill(mut self, feed: f32, kill: f32) -> Self {
+        self.feed = feed;
+        self.kill = kill;
+        self
+    }
+
+    #[inline]
+    pub const fn with_reset(mut self, reset: bool) -> Self {
+        self.reset = reset as u32;
+        self
+    }
+}
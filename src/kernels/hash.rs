@@ -0,0 +1,219 @@
+//! Batch hashing of fixed-size keys.
+//!
+//! The keys are stored one after the other in the input, each one made of `key_size` bytes, a multiple of 4. Each stage hashes every key with a different function: `MURMUR3_32` (MurmurHash3 x86 32-bit) and `XXH32` write one `u32` per key, `XXH64` writes one `u64` per key. The hashes are the same as the reference implementations applied to the bytes of the keys, which `murmur3_32`, `xxh32` and `xxh64` compute on the CPU.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::hash::{self, HashParams};
+//!
+//! let keys: [u32; 4] = [1, 2, 3, 4];
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline::<[u32; 4], HashParams, [u64; 4], 1>(None, [hash::XXH64]);
+//! pipeline.write_uniform(&HashParams::new(4, 4, 42));
+//! let hashes = pipeline.run(&keys, [hash::workgroups(4)], |hashes| *hashes);
+//! assert_eq!(hashes[2], hash::xxh64(&3u32.to_le_bytes(), 42));
+//! ```
+use crate::StageDesc;
+
+/// WGSL source containing the `murmur3_32`, `xxh32` and `xxh64` entry points.
+pub const SHADER: &str = sgpu_compute_core::kernels::hash::SHADER;
+
+/// Stage writing the MurmurHash3 x86 32-bit hash of each key.
+pub const MURMUR3_32: StageDesc = StageDesc::new(SHADER, "murmur3_32").with_name("hash_murmur3_32");
+
+/// Stage writing the xxHash32 hash of each key.
+pub const XXH32: StageDesc = StageDesc::new(SHADER, "xxh32").with_name("hash_xxh32");
+
+/// Stage writing the xxHash64 hash of each key.
+pub const XXH64: StageDesc = StageDesc::new(SHADER, "xxh64").with_name("hash_xxh64");
+
+/// Returns the workgroups needed by the stages for `count` keys.
+#[inline]
+pub const fn workgroups(count: usize) -> (u32, u32, u32) {
+    ((count as u32).div_ceil(64), 1, 1)
+}
+
+/// Uniform of the stages.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct HashParams {
+    pub count: u32,
+    key_words: u32,
+    seed_lo: u32,
+    seed_hi: u32,
+}
+
+impl HashParams {
+    /// Parameters hashing `count` keys of `key_size` bytes with `seed`. The 32-bit hashes only use the low 32 bits of the seed.
+    ///
+    /// # Panics
+    /// If `key_size` is not a multiple of 4.
+    #[inline]
+    pub const fn new(count: u32, key_size: u32, seed: u64) -> Self {
+        assert!(
+            key_size.is_multiple_of(4),
+            "The key size must be a multiple of 4"
+        );
+        Self {
+            count,
+            key_words: key_size / 4,
+            seed_lo: seed as u32,
+            seed_hi: (seed >> 32) as u32,
+        }
+    }
+
+    /// Size of the keys in bytes.
+    #[inline]
+    pub const fn key_size(&self) -> u32 {
+        4 * self.key_words
+    }
+
+    #[inline]
+    pub const fn seed(&self) -> u64 {
+        self.seed_lo as u64 | (self.seed_hi as u64) << 32
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes(bytes[..4].try_into().expect("4 bytes"))
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes[..8].try_into().expect("8 bytes"))
+}
+
+/// MurmurHash3 x86 32-bit hash of `key`.
+pub fn murmur3_32(key: &[u8], seed: u32) -> u32 {
+    const C1: u32 = 0xcc9e2d51;
+    const C2: u32 = 0x1b873593;
+    let scramble = |k: u32| k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+    let mut h = seed;
+    let mut chunks = key.chunks_exact(4);
+    for chunk in &mut chunks {
+        h = (h ^ scramble(read_u32(chunk)))
+            .rotate_left(13)
+            .wrapping_mul(5)
+            .wrapping_add(0xe6546b64);
+    }
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        let k = tail.iter().rev().fold(0, |k, byte| (k << 8) | *byte as u32);
+        h ^= scramble(k);
+    }
+    h ^= key.len() as u32;
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85ebca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2ae35);
+    h ^ (h >> 16)
+}
+
+/// xxHash32 hash of `key`.
+pub fn xxh32(key: &[u8], seed: u32) -> u32 {
+    const P1: u32 = 0x9e3779b1;
+    const P2: u32 = 0x85ebca77;
+    const P3: u32 = 0xc2b2ae3d;
+    const P4: u32 = 0x27d4eb2f;
+    const P5: u32 = 0x165667b1;
+    let round = |acc: u32, lane: u32| {
+        acc.wrapping_add(lane.wrapping_mul(P2))
+            .rotate_left(13)
+            .wrapping_mul(P1)
+    };
+    let mut stripes = key.chunks_exact(16);
+    let mut h = if key.len() >= 16 {
+        let mut v = [
+            seed.wrapping_add(P1).wrapping_add(P2),
+            seed.wrapping_add(P2),
+            seed,
+            seed.wrapping_sub(P1),
+        ];
+        for stripe in &mut stripes {
+            for (lane, v) in v.iter_mut().enumerate() {
+                *v = round(*v, read_u32(&stripe[4 * lane..]));
+            }
+        }
+        v[0].rotate_left(1)
+            .wrapping_add(v[1].rotate_left(7))
+            .wrapping_add(v[2].rotate_left(12))
+            .wrapping_add(v[3].rotate_left(18))
+    } else {
+        seed.wrapping_add(P5)
+    };
+    h = h.wrapping_add(key.len() as u32);
+    let mut words = stripes.remainder().chunks_exact(4);
+    for word in &mut words {
+        h = h
+            .wrapping_add(read_u32(word).wrapping_mul(P3))
+            .rotate_left(17)
+            .wrapping_mul(P4);
+    }
+    for byte in words.remainder() {
+        h = h
+            .wrapping_add((*byte as u32).wrapping_mul(P5))
+            .rotate_left(11)
+            .wrapping_mul(P1);
+    }
+    h ^= h >> 15;
+    h = h.wrapping_mul(P2);
+    h ^= h >> 13;
+    h = h.wrapping_mul(P3);
+    h ^ (h >> 16)
+}
+
+/// xxHash64 hash of `key`.
+pub fn xxh64(key: &[u8], seed: u64) -> u64 {
+    const P1: u64 = 0x9e3779b185ebca87;
+    const P2: u64 = 0xc2b2ae3d27d4eb4f;
+    const P3: u64 = 0x165667b19e3779f9;
+    const P4: u64 = 0x85ebca77c2b2ae63;
+    const P5: u64 = 0x27d4eb2f165667c5;
+    let round = |acc: u64, lane: u64| {
+        acc.wrapping_add(lane.wrapping_mul(P2))
+            .rotate_left(31)
+            .wrapping_mul(P1)
+    };
+    let merge_round = |acc: u64, v: u64| (acc ^ round(0, v)).wrapping_mul(P1).wrapping_add(P4);
+    let mut stripes = key.chunks_exact(32);
+    let mut h = if key.len() >= 32 {
+        let mut v = [
+            seed.wrapping_add(P1).wrapping_add(P2),
+            seed.wrapping_add(P2),
+            seed,
+            seed.wrapping_sub(P1),
+        ];
+        for stripe in &mut stripes {
+            for (lane, v) in v.iter_mut().enumerate() {
+                *v = round(*v, read_u64(&stripe[8 * lane..]));
+            }
+        }
+        let h = v[0]
+            .rotate_left(1)
+            .wrapping_add(v[1].rotate_left(7))
+            .wrapping_add(v[2].rotate_left(12))
+            .wrapping_add(v[3].rotate_left(18));
+        v.into_iter().fold(h, merge_round)
+    } else {
+        seed.wrapping_add(P5)
+    };
+    h = h.wrapping_add(key.len() as u64);
+    let mut lanes = stripes.remainder().chunks_exact(8);
+    for lane in &mut lanes {
+        h ^= round(0, read_u64(lane));
+        h = h.rotate_left(27).wrapping_mul(P1).wrapping_add(P4);
+    }
+    let mut words = lanes.remainder().chunks_exact(4);
+    for word in &mut words {
+        h ^= (read_u32(word) as u64).wrapping_mul(P1);
+        h = h.rotate_left(23).wrapping_mul(P2).wrapping_add(P3);
+    }
+    for byte in words.remainder() {
+        h ^= (*byte as u64).wrapping_mul(P5);
+        h = h.rotate_left(11).wrapping_mul(P1);
+    }
+    h ^= h >> 33;
+    h = h.wrapping_mul(P2);
+    h ^= h >> 29;
+    h = h.wrapping_mul(P3);
+    h ^ (h >> 32)
+}
@@ -0,0 +1,237 @@
+//! Open-addressing hash map living on the GPU.
+//!
+//! `GpuHashMapAsync` maps `u32` or `u64` keys to `u32` values. Its table is the scratchpad of a pipeline, so it stays on the GPU between operations, and the keys are inserted and looked up in batches of `BATCH` keys, larger slices being split in several runs. Slots are claimed with atomics, with linear probing. An insertion racing with another one for the same slot is retried in the next pass, `INSERT_PASSES` passes being made per batch, so an entry can fail to be inserted under heavy contention as well as when the table is full. If a batch contains the same key several times, one of its values is kept.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::hash_map::GpuHashMap;
+//!
+//! let gpu = GpuCompute::new();
+//! let mut map = GpuHashMap::<u64, 64>::new(&gpu, 1024);
+//! let failed = map.insert(&[(1 << 40, 1), (7, 2), (42, 3)]);
+//! assert_eq!(failed, 0);
+//! assert_eq!(map.get(&[7, 8, 1 << 40]), [Some(2), None, Some(1)]);
+//! ```
+use crate::{GpuComputeAsync, PipelineAsync, StageDesc};
+use std::{marker::PhantomData, num::NonZeroUsize};
+
+/// WGSL source containing the `clear`, `insert`, `insert_retry`, `settle`, `finish_insert` and `lookup` entry points.
+pub const SHADER: &str = sgpu_compute_core::kernels::hash_map::SHADER;
+
+/// Number of insertion passes made for each batch.
+pub const INSERT_PASSES: usize = 4;
+
+const STAGE_COUNT: usize = 2 * INSERT_PASSES + 3;
+
+const fn stage(name: &'static str, entrypoint: &'static str) -> StageDesc {
+    StageDesc::new(SHADER, entrypoint).with_name(name)
+}
+
+const CLEAR: StageDesc = stage("hash_map_clear", "clear");
+const INSERT: StageDesc = stage("hash_map_insert", "insert");
+const INSERT_RETRY: StageDesc = stage("hash_map_insert_retry", "insert_retry");
+const SETTLE: StageDesc = stage("hash_map_settle", "settle");
+const FINISH_INSERT: StageDesc = stage("hash_map_finish_insert", "finish_insert");
+const LOOKUP: StageDesc = stage("hash_map_lookup", "lookup");
+
+/// Clearing, the insertion passes each followed by a settling stage, then the lookup. The stages which are not part of an operation are dispatched with no workgroups.
+const STAGES: [StageDesc; STAGE_COUNT] = {
+    let mut stages = [SETTLE; STAGE_COUNT];
+    stages[0] = CLEAR;
+    stages[1] = INSERT;
+    let mut pass = 1;
+    while pass < INSERT_PASSES {
+        stages[2 * pass + 1] = INSERT_RETRY;
+        pass += 1;
+    }
+    stages[STAGE_COUNT - 2] = FINISH_INSERT;
+    stages[STAGE_COUNT - 1] = LOOKUP;
+    stages
+};
+
+/// Key of a `GpuHashMapAsync`, stored in two words on the GPU.
+pub trait HashKey: Copy {
+    fn to_words(self) -> [u32; 2];
+}
+
+impl HashKey for u32 {
+    #[inline]
+    fn to_words(self) -> [u32; 2] {
+        [self, 0]
+    }
+}
+
+impl HashKey for u64 {
+    #[inline]
+    fn to_words(self) -> [u32; 2] {
+        [self as u32, (self >> 32) as u32]
+    }
+}
+
+/// Input of the pipeline.
+#[derive(Debug, Copy, Clone, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+struct Entry {
+    key: [u32; 2],
+    value: u32,
+    _pad0: u32,
+}
+
+/// Uniform of the pipeline.
+#[derive(Debug, Copy, Clone, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+struct Params {
+    capacity: u32,
+    count: u32,
+    _pad0: u32,
+    _pad1: u32,
+}
+
+/// Output of the pipeline for each entry: whether it was inserted or found, and its value for lookups.
+#[derive(Debug, Copy, Clone, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+struct Lookup {
+    found: u32,
+    value: u32,
+}
+
+/// Operation made by a run of the pipeline.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Op {
+    Clear,
+    Insert,
+    Lookup,
+}
+
+/// Hash map on the GPU, see the module documentation.
+pub struct GpuHashMapAsync<'a, K: HashKey, const BATCH: usize> {
+    pipeline: PipelineAsync<'a, [Entry; BATCH], Params, [Lookup; BATCH], STAGE_COUNT>,
+    capacity: u32,
+    _key: PhantomData<K>,
+}
+
+impl<'a, K: HashKey, const BATCH: usize> GpuHashMapAsync<'a, K, BATCH> {
+    /// Creates an empty map with `capacity` slots, rounded up to a power of two. The map can hold at most `capacity` keys, but it is faster with a load factor below one half.
+    pub async fn new(gpu: &'a GpuComputeAsync, capacity: u32) -> Self {
+        let capacity = capacity.max(1).next_power_of_two();
+        let words = 4 * capacity as usize + BATCH;
+        let pipeline = gpu
+            .gen_pipeline(
+                NonZeroUsize::new(words * std::mem::size_of::<u32>()),
+                STAGES,
+            )
+            .await;
+        Self {
+            pipeline,
+            capacity,
+            _key: PhantomData,
+        }
+    }
+
+    /// Number of slots of the table.
+    #[inline]
+    pub const fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    async fn run(&mut self, entries: [Entry; BATCH], count: usize, op: Op) -> Vec<Lookup> {
+        self.pipeline.write_uniform(&Params {
+            capacity: self.capacity,
+            count: count as u32,
+            _pad0: 0,
+            _pad1: 0,
+        });
+        let batch = (count as u32).div_ceil(64);
+        let table = self.capacity.div_ceil(64);
+        let workgroups = std::array::from_fn(|stage| {
+            let x = match op {
+                Op::Clear if stage == 0 => table,
+                // Insertion passes at odd indices, settling stages at even ones.
+                Op::Insert if (1..STAGE_COUNT - 2).contains(&stage) => {
+                    if stage % 2 == 1 {
+                        batch
+                    } else {
+                        table
+                    }
+                }
+                Op::Insert if stage == STAGE_COUNT - 2 => batch,
+                Op::Lookup if stage == STAGE_COUNT - 1 => batch,
+                _ => 0,
+            };
+            (x, 1, 1)
+        });
+        self.pipeline
+            .run(&entries, workgroups, move |out| out[..count].to_vec())
+            .await
+    }
+
+    /// Removes all the keys.
+    pub async fn clear(&mut self) {
+        self.run(bytemuck::Zeroable::zeroed(), 0, Op::Clear).await;
+    }
+
+    /// Inserts the entries, replacing the values of the keys already in the map, and returns the number of entries which could not be inserted.
+    pub async fn insert(&mut self, entries: &[(K, u32)]) -> usize {
+        let mut failed = 0;
+        for chunk in entries.chunks(BATCH) {
+            let mut batch: [Entry; BATCH] = bytemuck::Zeroable::zeroed();
+            for (entry, (key, value)) in batch.iter_mut().zip(chunk) {
+                entry.key = key.to_words();
+                entry.value = *value;
+            }
+            let results = self.run(batch, chunk.len(), Op::Insert).await;
+            failed += results.iter().filter(|result| result.found == 0).count();
+        }
+        failed
+    }
+
+    /// Returns the value of each key, or `None` if it is not in the map.
+    pub async fn get(&mut self, keys: &[K]) -> Vec<Option<u32>> {
+        let mut values = Vec::with_capacity(keys.len());
+        for chunk in keys.chunks(BATCH) {
+            let mut batch: [Entry; BATCH] = bytemuck::Zeroable::zeroed();
+            for (entry, key) in batch.iter_mut().zip(chunk) {
+                entry.key = key.to_words();
+            }
+            let results = self.run(batch, chunk.len(), Op::Lookup).await;
+            values.extend(
+                results
+                    .into_iter()
+                    .map(|result| (result.found != 0).then_some(result.value)),
+            );
+        }
+        values
+    }
+}
+
+/// Blocking version of `GpuHashMapAsync`. It is enabled by the `blocking` feature.
+#[cfg(feature = "blocking")]
+pub struct GpuHashMap<'a, K: HashKey, const BATCH: usize>(GpuHashMapAsync<'a, K, BATCH>);
+
+#[cfg(feature = "blocking")]
+impl<'a, K: HashKey, const BATCH: usize> GpuHashMap<'a, K, BATCH> {
+    /// Blocking version of `GpuHashMapAsync::new`.
+    pub fn new(gpu: &'a crate::blocking::GpuCompute, capacity: u32) -> Self {
+        Self(pollster::block_on(GpuHashMapAsync::new(gpu, capacity)))
+    }
+
+    #[inline]
+    pub const fn capacity(&self) -> u32 {
+        self.0.capacity()
+    }
+
+    /// Blocking version of `GpuHashMapAsync::clear`.
+    pub fn clear(&mut self) {
+        pollster::block_on(self.0.clear())
+    }
+
+    /// Blocking version of `GpuHashMapAsync::insert`.
+    pub fn insert(&mut self, entries: &[(K, u32)]) -> usize {
+        pollster::block_on(self.0.insert(entries))
+    }
+
+    /// Blocking version of `GpuHashMapAsync::get`.
+    pub fn get(&mut self, keys: &[K]) -> Vec<Option<u32>> {
+        pollster::block_on(self.0.get(keys))
+    }
+}
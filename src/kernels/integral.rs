@@ -0,0 +1,45 @@
+//! Summed-area table (integral image) of a row-major `f32` image.
+//!
+//! Each output value is the sum of all the input values above and to the left of it, inclusively. The first stage scans each row and writes the result transposed in the scratchpad, the second one scans each row of the scratchpad (the columns of the image) and transposes it back into the output.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::integral::{self, ImageSize};
+//!
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline::<[f32; 6], ImageSize, [f32; 6], 2>(integral::scratchpad_size(3, 2), integral::STAGES);
+//! pipeline.write_uniform(&ImageSize { width: 3, height: 2 });
+//! let table = pipeline.run(&[1.0; 6], integral::workgroups(3, 2), |table| *table);
+//! assert_eq!(table, [1.0, 2.0, 3.0, 2.0, 4.0, 6.0]);
+//! ```
+use crate::StageDesc;
+use std::num::NonZeroUsize;
+
+/// WGSL source containing the `scan_rows` and `scan_columns` entry points.
+pub const SHADER: &str = sgpu_compute_core::kernels::integral::SHADER;
+
+/// The two stages computing the table, in order.
+pub const STAGES: [StageDesc; 2] = [
+    StageDesc::new(SHADER, "scan_rows").with_name("integral_rows"),
+    StageDesc::new(SHADER, "scan_columns").with_name("integral_columns"),
+];
+
+/// Returns the scratchpad size needed for a `width * height` image.
+#[inline]
+pub const fn scratchpad_size(width: u32, height: u32) -> Option<NonZeroUsize> {
+    NonZeroUsize::new(width as usize * height as usize * std::mem::size_of::<f32>())
+}
+
+/// Returns the workgroups of each stage for a `width * height` image.
+#[inline]
+pub const fn workgroups(width: u32, height: u32) -> [(u32, u32, u32); 2] {
+    [(height.div_ceil(64), 1, 1), (width.div_ceil(64), 1, 1)]
+}
+
+/// Uniform of the stages.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct ImageSize {
+    pub width: u32,
+    pub height: u32,
+}
@@ -0,0 +1,154 @@
+//! Batch intersection of rays against triangles or axis-aligned boxes.
+//!
+//! Every ray is tested against every primitive (no acceleration structure) and the closest hit is written to the output. The shader does not use a uniform nor a scratchpad, so the pipeline is generated with `()` as uniform and `None` as scratchpad.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::intersect::{self, Hit, Ray, Triangle, TriangleQuery};
+//!
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline::<TriangleQuery<2, 1>, (), [Hit; 2], 1>(None, [intersect::RAY_TRIANGLE]);
+//! let query = TriangleQuery {
+//!     rays: [
+//!         Ray::new([0.2, 0.2, -1.0], [0.0, 0.0, 1.0]),
+//!         Ray::new([2.0, 2.0, -1.0], [0.0, 0.0, 1.0]),
+//!     ],
+//!     triangles: [Triangle::new([0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0])],
+//! };
+//! let hits = pipeline.run(&query, [intersect::workgroups(2)], |hits| *hits);
+//! assert!(hits[0].is_hit());
+//! assert!(!hits[1].is_hit());
+//! ```
+use crate::StageDesc;
+
+/// WGSL source containing the `ray_triangle` and `ray_aabb` entry points.
+pub const SHADER: &str = sgpu_compute_core::kernels::intersect::SHADER;
+
+/// Number of rays processed by a single workgroup.
+pub const WORKGROUP_SIZE: u32 = 64;
+
+/// Stage testing each ray of a `TriangleQuery` against all its triangles.
+pub const RAY_TRIANGLE: StageDesc =
+    StageDesc::new(SHADER, "ray_triangle").with_name("ray_triangle");
+
+/// Stage testing each ray of an `AabbQuery` against all its boxes.
+pub const RAY_AABB: StageDesc = StageDesc::new(SHADER, "ray_aabb").with_name("ray_aabb");
+
+/// Returns the workgroups needed to process `n_rays` rays.
+#[inline]
+pub const fn workgroups(n_rays: usize) -> (u32, u32, u32) {
+    ((n_rays as u32).div_ceil(WORKGROUP_SIZE), 1, 1)
+}
+
+/// A ray only reporting hits with a distance in `[t_min, t_max]`. The direction does not need to be normalized, distances are expressed in multiple of its length.
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct Ray {
+    pub origin: [f32; 3],
+    pub t_min: f32,
+    pub direction: [f32; 3],
+    pub t_max: f32,
+}
+
+impl Ray {
+    /// Creates a ray accepting every hit in front of its origin.
+    #[inline]
+    pub const fn new(origin: [f32; 3], direction: [f32; 3]) -> Self {
+        Self {
+            origin,
+            t_min: 0.0,
+            direction,
+            t_max: f32::MAX,
+        }
+    }
+}
+
+/// A triangle padded to the 16 bytes alignment of `vec3<f32>`.
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct Triangle {
+    pub a: [f32; 3],
+    _pad0: f32,
+    pub b: [f32; 3],
+    _pad1: f32,
+    pub c: [f32; 3],
+    _pad2: f32,
+}
+
+impl Triangle {
+    #[inline]
+    pub const fn new(a: [f32; 3], b: [f32; 3], c: [f32; 3]) -> Self {
+        Self {
+            a,
+            _pad0: 0.0,
+            b,
+            _pad1: 0.0,
+            c,
+            _pad2: 0.0,
+        }
+    }
+}
+
+/// An axis-aligned bounding box padded to the 16 bytes alignment of `vec3<f32>`.
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct Aabb {
+    pub min: [f32; 3],
+    _pad0: f32,
+    pub max: [f32; 3],
+    _pad1: f32,
+}
+
+impl Aabb {
+    #[inline]
+    pub const fn new(min: [f32; 3], max: [f32; 3]) -> Self {
+        Self {
+            min,
+            _pad0: 0.0,
+            max,
+            _pad1: 0.0,
+        }
+    }
+}
+
+/// Closest hit found for a ray. `primitive` is the index of the hit primitive or `Hit::MISS` when nothing was hit.
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct Hit {
+    pub distance: f32,
+    pub primitive: u32,
+}
+
+impl Hit {
+    /// Primitive index reported when a ray hits nothing.
+    pub const MISS: u32 = u32::MAX;
+
+    #[inline]
+    pub const fn is_hit(&self) -> bool {
+        self.primitive != Self::MISS
+    }
+}
+
+/// Input of the `RAY_TRIANGLE` stage: `R` rays tested against `T` triangles.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct TriangleQuery<const R: usize, const T: usize> {
+    pub rays: [Ray; R],
+    pub triangles: [Triangle; T],
+}
+
+// SAFETY: both fields are arrays of `Pod` types only made of `f32`, so the struct has no padding.
+unsafe impl<const R: usize, const T: usize> bytemuck::Zeroable for TriangleQuery<R, T> {}
+unsafe impl<const R: usize, const T: usize> bytemuck::Pod for TriangleQuery<R, T> {}
+
+/// Input of the `RAY_AABB` stage: `R` rays tested against `B` boxes.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct AabbQuery<const R: usize, const B: usize> {
+    pub rays: [Ray; R],
+    pub boxes: [Aabb; B],
+}
+
+// SAFETY: both fields are arrays of `Pod` types only made of `f32`, so the struct has no padding.
+unsafe impl<const R: usize, const B: usize> bytemuck::Zeroable for AabbQuery<R, B> {}
+unsafe impl<const R: usize, const B: usize> bytemuck::Pod for AabbQuery<R, B> {}
@@ -0,0 +1,125 @@
+//! Fused map and reduction of an array of `f32`.
+//!
+//! `GpuComputeAsync::map_reduce` applies a WGSL expression of `x` to each element and reduces the mapped values with a `ReduceOp` in a single pass over the input: each workgroup reduces a strided part of the input in its registers, then a single workgroup reduces the partial results, so only the final scalar is read back.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::map_reduce::ReduceOp;
+//!
+//! let input: [f32; 1000] = std::array::from_fn(|i| i as f32 / 1000.0);
+//! let gpu = GpuCompute::new();
+//! let sum_of_squares = gpu.map_reduce("x * x", ReduceOp::Sum, &input);
+//! let expected: f32 = input.iter().map(|x| x * x).sum();
+//! assert!((sum_of_squares - expected).abs() < 1e-3);
+//! assert_eq!(gpu.map_reduce("abs(x - 0.5)", ReduceOp::Max, &input), 0.5);
+//! ```
+use crate::{GpuComputeAsync, StageDesc};
+use std::num::NonZeroUsize;
+
+/// WGSL source containing the `map_reduce` and `finish` entry points, without the declarations generated by `shader`.
+pub const SHADER: &str = sgpu_compute_core::kernels::map_reduce::SHADER;
+
+/// Number of invocations of a workgroup, and maximum number of workgroups of the first stage.
+const WORKGROUP_SIZE: u32 = 256;
+
+/// Key of the shader given to the shader source provider of the pipeline.
+const SHADER_KEY: &str = "map_reduce";
+
+const STAGES: [StageDesc; 2] = [
+    StageDesc::new(SHADER_KEY, "map_reduce").with_name("map_reduce"),
+    StageDesc::new(SHADER_KEY, "finish").with_name("map_reduce_finish"),
+];
+
+/// Associative operation reducing the mapped values.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReduceOp {
+    Sum,
+    Product,
+    Min,
+    Max,
+}
+
+impl ReduceOp {
+    /// WGSL expression combining `a` and `b`.
+    #[inline]
+    pub const fn wgsl(&self) -> &'static str {
+        match self {
+            ReduceOp::Sum => "a + b",
+            ReduceOp::Product => "a * b",
+            ReduceOp::Min => "min(a, b)",
+            ReduceOp::Max => "max(a, b)",
+        }
+    }
+
+    /// Result of the reduction of no value.
+    #[inline]
+    pub const fn identity(&self) -> f32 {
+        match self {
+            ReduceOp::Sum => 0.0,
+            ReduceOp::Product => 1.0,
+            ReduceOp::Min => f32::INFINITY,
+            ReduceOp::Max => f32::NEG_INFINITY,
+        }
+    }
+}
+
+/// Returns the complete shader mapping each element `x` with `map_expr_wgsl` and reducing with `op`.
+pub fn shader(map_expr_wgsl: &str, op: ReduceOp) -> String {
+    format!(
+        "fn map_element(x: f32) -> f32 {{\n    return {};\n}}\n\nfn combine(a: f32, b: f32) -> f32 {{\n    return {};\n}}\n\n// Infinities have no WGSL literal.\nfn identity() -> f32 {{\n    return bitcast<f32>({}u);\n}}\n\n{}",
+        map_expr_wgsl,
+        op.wgsl(),
+        op.identity().to_bits(),
+        SHADER
+    )
+}
+
+/// Returns the workgroups of the stages for `len` elements.
+#[inline]
+pub const fn workgroups(len: u32) -> [(u32, u32, u32); 2] {
+    let first = len.div_ceil(WORKGROUP_SIZE);
+    let first = if first < WORKGROUP_SIZE {
+        first
+    } else {
+        WORKGROUP_SIZE
+    };
+    [(first, 1, 1), (1, 1, 1)]
+}
+
+impl GpuComputeAsync {
+    /// This method is used to map each element `x` of `input` with the WGSL expression `map_expr_wgsl` and to reduce the mapped values with `op`, e.g. `"x * x"` and `ReduceOp::Sum` for the sum of squares, see the `map_reduce` module. The shader is compiled on each call. Returns the identity of `op` for an empty input.
+    pub async fn map_reduce<const N: usize>(
+        &self,
+        map_expr_wgsl: &str,
+        op: ReduceOp,
+        input: &[f32; N],
+    ) -> f32 {
+        if N == 0 {
+            return op.identity();
+        }
+        let source = shader(map_expr_wgsl, op);
+        let mut pipeline = self
+            .gen_pipeline_with_provider::<[f32; N], u32, f32, 2>(
+                move |_: &str| source.clone(),
+                NonZeroUsize::new(WORKGROUP_SIZE as usize * std::mem::size_of::<f32>()),
+                STAGES,
+            )
+            .await;
+        pipeline.write_uniform(&(N as u32));
+        pipeline.run(input, workgroups(N as u32), |out| *out).await
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl crate::blocking::GpuCompute {
+    /// Blocking version of `GpuComputeAsync::map_reduce`.
+    #[inline]
+    pub fn map_reduce<const N: usize>(
+        &self,
+        map_expr_wgsl: &str,
+        op: ReduceOp,
+        input: &[f32; N],
+    ) -> f32 {
+        pollster::block_on((**self).map_reduce(map_expr_wgsl, op, input))
+    }
+}
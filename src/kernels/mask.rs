@@ -0,0 +1,127 @@
+//! Compression of mask outputs before readback.
+//!
+//! Masks are arrays of `u32` where non-zero values are set. They can be compressed either as a bitset (`BITPACK`, 32 times smaller) or as runs of identical values (`RLE_STAGES`), which is a lot smaller for segmentation-like masks made of large regions.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::mask::{self, Run, Runs};
+//!
+//! let input = [0, 0, 1, 1, 1, 0, 0, 0];
+//! let gpu = GpuCompute::new();
+//!
+//! let mut bitpack = gpu.gen_pipeline::<[u32; 8], (), [u32; 1], 1>(None, [mask::BITPACK]);
+//! assert_eq!(bitpack.run(&input, [mask::bitpack_workgroups(8)], |bits| *bits), [0b11100]);
+//!
+//! let mut rle = gpu.gen_pipeline::<[u32; 8], (), Runs<4>, 3>(mask::rle_scratchpad_size(8), mask::RLE_STAGES);
+//! let runs = rle.run(&input, mask::rle_workgroups(8), |runs| runs.to_vec());
+//! assert_eq!(runs, [Run::new(0, 0, 2), Run::new(1, 2, 5), Run::new(0, 5, 8)]);
+//! ```
+use crate::StageDesc;
+use std::num::NonZeroUsize;
+
+/// WGSL source of the `bitpack` entry point.
+pub const BITPACK_SHADER: &str = sgpu_compute_core::kernels::mask::BITPACK_SHADER;
+
+/// WGSL source containing the `count_runs`, `scan_blocks` and `emit` entry points.
+pub const RLE_SHADER: &str = sgpu_compute_core::kernels::mask::RLE_SHADER;
+
+/// Number of mask values processed by a single workgroup of the RLE stages.
+pub const RLE_BLOCK: u32 = 256;
+
+/// Stage packing 32 mask values per `u32`, the value `i` is the bit `i % 32` of the word `i / 32`.
+pub const BITPACK: StageDesc = StageDesc::new(BITPACK_SHADER, "bitpack").with_name("bitpack");
+
+/// The three stages of the run-length encoding, in order. The output is a `Runs`.
+pub const RLE_STAGES: [StageDesc; 3] = [
+    StageDesc::new(RLE_SHADER, "count_runs").with_name("rle_count"),
+    StageDesc::new(RLE_SHADER, "scan_blocks").with_name("rle_scan"),
+    StageDesc::new(RLE_SHADER, "emit").with_name("rle_emit"),
+];
+
+/// Returns the workgroups needed by `BITPACK` for `n` mask values.
+#[inline]
+pub const fn bitpack_workgroups(n: usize) -> (u32, u32, u32) {
+    ((n as u32).div_ceil(32).div_ceil(64), 1, 1)
+}
+
+/// Returns the scratchpad size needed by the RLE stages for `n` mask values.
+#[inline]
+pub const fn rle_scratchpad_size(n: usize) -> Option<NonZeroUsize> {
+    NonZeroUsize::new((n as u32).div_ceil(RLE_BLOCK) as usize * std::mem::size_of::<u32>())
+}
+
+/// Returns the workgroups of each RLE stage for `n` mask values.
+#[inline]
+pub const fn rle_workgroups(n: usize) -> [(u32, u32, u32); 3] {
+    let blocks = (n as u32).div_ceil(RLE_BLOCK);
+    [(blocks, 1, 1), (1, 1, 1), (blocks, 1, 1)]
+}
+
+/// A run of identical mask values covering the indices `start..end`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct Run {
+    value: u32,
+    pub start: u32,
+    pub end: u32,
+}
+
+impl Run {
+    #[inline]
+    pub const fn new(value: u32, start: u32, end: u32) -> Self {
+        Self { value, start, end }
+    }
+
+    /// Whether the values of the run are set.
+    #[inline]
+    pub const fn is_set(&self) -> bool {
+        self.value != 0
+    }
+
+    #[inline]
+    pub const fn len(&self) -> u32 {
+        self.end - self.start
+    }
+
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// Output of the RLE stages holding at most `MAX` runs.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct Runs<const MAX: usize> {
+    count: u32,
+    runs: [Run; MAX],
+}
+
+impl<const MAX: usize> Runs<MAX> {
+    /// Number of runs in the mask, which can be greater than `MAX`.
+    #[inline]
+    pub const fn count(&self) -> usize {
+        self.count as usize
+    }
+
+    /// Whether some runs were dropped because the mask has more than `MAX` runs.
+    #[inline]
+    pub const fn overflowed(&self) -> bool {
+        self.count() > MAX
+    }
+
+    /// The runs that fit in the output.
+    #[inline]
+    pub fn as_slice(&self) -> &[Run] {
+        &self.runs[..self.count().min(MAX)]
+    }
+
+    #[inline]
+    pub fn to_vec(&self) -> Vec<Run> {
+        self.as_slice().to_vec()
+    }
+}
+
+// SAFETY: the struct is only made of `u32`, so it has no padding.
+unsafe impl<const MAX: usize> bytemuck::Zeroable for Runs<MAX> {}
+unsafe impl<const MAX: usize> bytemuck::Pod for Runs<MAX> {}
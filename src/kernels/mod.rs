@@ -0,0 +1,38 @@
+//! Ready-to-use kernels built on top of the pipeline API.
+//!
+//! Each submodule exposes its WGSL source, the `StageDesc`s for its entry points and the `bytemuck::Pod` types expected by the shader, so a kernel can be dropped directly into `gen_pipeline`.
+
+pub mod bfs;
+pub mod bvh;
+pub mod ccl;
+pub mod dedup;
+pub mod dsp;
+pub mod error_map;
+pub mod gather;
+pub mod gray_scott;
+pub mod hash;
+pub mod hash_map;
+pub mod integral;
+pub mod intersect;
+pub mod map_reduce;
+pub mod mask;
+pub mod monte_carlo;
+pub mod noise;
+pub mod optimize;
+pub mod pagerank;
+pub mod particles;
+pub mod population;
+pub mod rolling;
+pub mod search;
+pub mod sort;
+pub mod sparse;
+pub mod stats;
+pub mod tonemap;
+pub mod zip_map;
+
+#[cfg(feature = "blocking")]
+pub use monte_carlo::monte_carlo;
+#[cfg(feature = "blocking")]
+pub use optimize::{cma_es, grid_search};
+#[cfg(feature = "blocking")]
+pub use rolling::rolling;
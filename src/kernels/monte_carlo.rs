@@ -0,0 +1,141 @@
+//! Monte Carlo integration of a WGSL function.
+//!
+//! The integrand is WGSL source declaring `fn integrand(x: vec4<f32>) -> f32`, where `x` is uniformly distributed in the unit hypercube `[0, 1)^4`; unused components can be ignored and the domain can be mapped to any box by the integrand itself. `monte_carlo` draws the samples in batches of `BATCH_SAMPLES` with the `seed` module, so an estimate is reproducible for a given seed, and accumulates the mean and the variance of the integrand on the GPU, only reading back the accumulated moments.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::monte_carlo;
+//!
+//! // Area of the quarter of the unit disk.
+//! let integrand = "
+//!     fn integrand(x: vec4<f32>) -> f32 {
+//!         return select(0.0, 1.0, dot(x.xy, x.xy) < 1.0);
+//!     }
+//! ";
+//! let gpu = GpuCompute::new();
+//! let estimate = monte_carlo(&gpu, integrand, 1 << 20, 42);
+//! assert!((4.0 * estimate.mean - std::f64::consts::PI).abs() < 0.01);
+//! assert!(estimate.standard_error() < 0.001);
+//! ```
+use crate::{seed, seed::SeedManager, GpuComputeAsync, StageDesc};
+use std::num::NonZeroUsize;
+
+/// WGSL source containing the `sample` and `accumulate` entry points, without the `seed` module and the integrand, see `shader`.
+pub const SHADER: &str = sgpu_compute_core::kernels::monte_carlo::SHADER;
+
+/// Number of workgroups of a batch.
+const BATCH_WORKGROUPS: u32 = 256;
+
+/// Number of samples drawn by each run of the pipeline.
+pub const BATCH_SAMPLES: u64 = BATCH_WORKGROUPS as u64 * 64 * 16;
+
+/// Key of the shader given to the shader source provider of the pipeline.
+const SHADER_KEY: &str = "monte_carlo";
+
+const STAGES: [StageDesc; 2] = [
+    StageDesc::new(SHADER_KEY, "sample").with_name("monte_carlo_sample"),
+    StageDesc::new(SHADER_KEY, "accumulate").with_name("monte_carlo_accumulate"),
+];
+
+const WORKGROUPS: [(u32, u32, u32); 2] = [(BATCH_WORKGROUPS, 1, 1), (1, 1, 1)];
+
+/// Returns the complete shader for the given integrand.
+pub fn shader(integrand_wgsl: &str) -> String {
+    format!("{}\n{}\n{}", seed::WGSL, SHADER, integrand_wgsl)
+}
+
+/// Uniform of the stages.
+#[derive(Debug, Copy, Clone, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+struct Params {
+    seed: seed::Seed,
+    samples: u32,
+    reset: u32,
+    _pad0: u32,
+    _pad1: u32,
+}
+
+/// Moments accumulated by the shader.
+#[derive(Debug, Copy, Clone, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+struct Moments {
+    count: f32,
+    mean: f32,
+    m2: f32,
+    _pad0: f32,
+}
+
+/// Result of `monte_carlo`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Estimate {
+    /// Mean of the integrand, which is its integral over the unit hypercube.
+    pub mean: f64,
+    /// Unbiased sample variance of the integrand.
+    pub variance: f64,
+    pub samples: u64,
+}
+
+impl Estimate {
+    /// Standard error of `Estimate::mean`.
+    #[inline]
+    pub fn standard_error(&self) -> f64 {
+        (self.variance / self.samples as f64).sqrt()
+    }
+}
+
+/// Async version of `monte_carlo`.
+pub async fn monte_carlo_async(
+    gpu: &GpuComputeAsync,
+    integrand_wgsl: &str,
+    samples: u64,
+    seed: u64,
+) -> Estimate {
+    assert!(
+        samples > 0,
+        "Monte Carlo integration needs at least one sample"
+    );
+    let source = shader(integrand_wgsl);
+    let scratchpad_size =
+        NonZeroUsize::new((BATCH_WORKGROUPS as usize + 1) * std::mem::size_of::<Moments>());
+    let mut pipeline = gpu
+        .gen_pipeline_with_provider::<(), Params, Moments, 2>(
+            move |_: &str| source.clone(),
+            scratchpad_size,
+            STAGES,
+        )
+        .await;
+    let seeds = SeedManager::new(seed);
+    let mut moments = None;
+    for batch in 0..samples.div_ceil(BATCH_SAMPLES) {
+        let batch_samples = (samples - batch * BATCH_SAMPLES).min(BATCH_SAMPLES);
+        pipeline.write_uniform(&Params {
+            seed: seeds.seed(batch),
+            samples: batch_samples as u32,
+            reset: (batch == 0) as u32,
+            _pad0: 0,
+            _pad1: 0,
+        });
+        moments = Some(pipeline.run(&(), WORKGROUPS, |moments| *moments).await);
+    }
+    let moments = moments.expect("At least one batch");
+    Estimate {
+        mean: moments.mean as f64,
+        variance: if samples > 1 {
+            moments.m2 as f64 / (moments.count as f64 - 1.0)
+        } else {
+            0.0
+        },
+        samples,
+    }
+}
+
+/// Estimates the integral of `integrand_wgsl` over the unit hypercube with `samples` samples drawn from the master seed `seed`, see the module documentation. It is enabled by the `blocking` feature.
+#[cfg(feature = "blocking")]
+pub fn monte_carlo(
+    gpu: &crate::blocking::GpuCompute,
+    integrand_wgsl: &str,
+    samples: u64,
+    seed: u64,
+) -> Estimate {
+    pollster::block_on(monte_carlo_async(gpu, integrand_wgsl, samples, seed))
+}
@@ -0,0 +1,87 @@
+//! Perlin and simplex noise fields written into the output buffer.
+//!
+//! The value of the cell `(x, y, z)` is stored at index `x + width * (y + height * z)`, values are roughly in `[-1, 1]`. The 2D field is the `z = 0` slice of the 3D one. Several octaves of noise can be summed (fractal Brownian motion), each one with twice the frequency and half the amplitude of the previous one.
+//!
+//! The `NOISE_*` stages sample Perlin noise and the `SIMPLEX_*` stages simplex noise, which is cheaper in 3D and has no axis-aligned artifacts. They take the same uniform and workgroups.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::noise::{self, NoiseParams};
+//!
+//! const WIDTH: usize = 32;
+//! const HEIGHT: usize = 16;
+//!
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline::<(), NoiseParams, [f32; WIDTH * HEIGHT], 1>(None, [noise::NOISE_2D]);
+//! pipeline.write_uniform(&NoiseParams::new_2d(WIDTH as u32, HEIGHT as u32, 42, 0.1));
+//! let field = pipeline.run(&(), [noise::workgroups_2d(WIDTH as u32, HEIGHT as u32)], |field| *field);
+//! assert!(field.iter().all(|v| v.abs() <= 1.0));
+//! ```
+use crate::StageDesc;
+
+/// WGSL source containing the `noise_2d`, `noise_3d`, `simplex_2d` and `simplex_3d` entry points.
+pub const SHADER: &str = sgpu_compute_core::kernels::noise::SHADER;
+
+/// Stage filling a `width * height` field. Dispatch it with `workgroups_2d`.
+pub const NOISE_2D: StageDesc = StageDesc::new(SHADER, "noise_2d").with_name("noise_2d");
+
+/// Stage filling a `width * height * depth` field. Dispatch it with `workgroups_3d`.
+pub const NOISE_3D: StageDesc = StageDesc::new(SHADER, "noise_3d").with_name("noise_3d");
+
+/// Same as `NOISE_2D`, with simplex noise.
+pub const SIMPLEX_2D: StageDesc = StageDesc::new(SHADER, "simplex_2d").with_name("simplex_2d");
+
+/// Same as `NOISE_3D`, with simplex noise.
+pub const SIMPLEX_3D: StageDesc = StageDesc::new(SHADER, "simplex_3d").with_name("simplex_3d");
+
+/// Returns the workgroups needed by `NOISE_2D` and `SIMPLEX_2D` to fill a `width * height` field.
+#[inline]
+pub const fn workgroups_2d(width: u32, height: u32) -> (u32, u32, u32) {
+    (width.div_ceil(8), height.div_ceil(8), 1)
+}
+
+/// Returns the workgroups needed by `NOISE_3D` and `SIMPLEX_3D` to fill a `width * height * depth` field.
+#[inline]
+pub const fn workgroups_3d(width: u32, height: u32, depth: u32) -> (u32, u32, u32) {
+    (width.div_ceil(4), height.div_ceil(4), depth.div_ceil(4))
+}
+
+/// Uniform of the noise stages. `frequency` scales the cell coordinates before sampling the noise, e.g. `0.1` gives a feature every 10 cells.
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct NoiseParams {
+    pub width: u32,
+    pub height: u32,
+    pub depth: u32,
+    pub seed: u32,
+    pub frequency: f32,
+    pub octaves: u32,
+}
+
+impl NoiseParams {
+    /// Single octave 2D field.
+    #[inline]
+    pub const fn new_2d(width: u32, height: u32, seed: u32, frequency: f32) -> Self {
+        Self::new_3d(width, height, 1, seed, frequency)
+    }
+
+    /// Single octave 3D field.
+    #[inline]
+    pub const fn new_3d(width: u32, height: u32, depth: u32, seed: u32, frequency: f32) -> Self {
+        Self {
+            width,
+            height,
+            depth,
+            seed,
+            frequency,
+            octaves: 1,
+        }
+    }
+
+    /// Sets the number of octaves summed in the field.
+    #[inline]
+    pub const fn with_octaves(mut self, octaves: u32) -> Self {
+        self.octaves = octaves;
+        self
+    }
+}
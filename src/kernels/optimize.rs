@@ -0,0 +1,459 @@
+//! Derivative-free optimization of a WGSL objective.
+//!
+//! The objective is WGSL source declaring `fn objective(x: ptr<function, array<f32, DIMENSIONS>>) -> f32`, where `DIMENSIONS` is the number of parameters of a candidate. An `ObjectiveAsync` evaluates a whole population of candidates in one run of the pipeline, one invocation per candidate, and the drivers search the minimum of the objective on the CPU:
+//!     - `grid_search` evaluates every point of a regular grid over a box, in batches of `GRID_BATCH` candidates
+//!     - `cma_es` runs the covariance matrix adaptation evolution strategy, which samples a population around a mean and adapts the mean, the step size and the covariance of the samples to the ranking of the candidates
+//!
+//! The samples of `cma_es` are drawn with the `seed` module, so a search is reproducible for a given seed.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::optimize::{cma_es, CmaEs};
+//!
+//! // Rosenbrock function, whose minimum is at (1, 1).
+//! let objective = "
+//!     fn objective(x: ptr<function, array<f32, DIMENSIONS>>) -> f32 {
+//!         return 100.0 * pow((*x)[1] - (*x)[0] * (*x)[0], 2.0) + pow(1.0 - (*x)[0], 2.0);
+//!     }
+//! ";
+//! let gpu = GpuCompute::new();
+//! let minimum = cma_es(&gpu, objective, &CmaEs::new(vec![-1.0, 2.0], 0.5).with_seed(42));
+//! assert!((minimum.x[0] - 1.0).abs() < 1e-2 && (minimum.x[1] - 1.0).abs() < 1e-2);
+//! assert!(minimum.value < 1e-4);
+//! ```
+use crate::{dynamic::DynPipelineAsync, seed::SeedManager, GpuComputeAsync, StageDesc};
+use std::sync::Arc;
+
+/// WGSL source of the `evaluate` entry point, without the number of dimensions and the objective, see `shader`.
+pub const SHADER: &str = sgpu_compute_core::kernels::optimize::SHADER;
+
+/// Number of candidates evaluated by each run of the pipeline of `grid_search`.
+pub const GRID_BATCH: usize = 4096;
+
+/// Key of the shader given to the shader source provider of the pipeline.
+const SHADER_KEY: &str = "optimize";
+
+const STAGES: [StageDesc; 1] =
+    [StageDesc::new(SHADER_KEY, "evaluate").with_name("optimize_evaluate")];
+
+/// Returns the complete shader for the given objective of `dimensions` parameters.
+pub fn shader(objective_wgsl: &str, dimensions: usize) -> String {
+    format!(
+        "const DIMENSIONS: u32 = {}u;\n{}\n{}",
+        dimensions, SHADER, objective_wgsl
+    )
+}
+
+/// Objective compiled to evaluate populations of `population` candidates, see the module documentation.
+pub struct ObjectiveAsync<'a> {
+    pipeline: DynPipelineAsync<'a, f32, (), f32, 1>,
+    dimensions: usize,
+    population: usize,
+}
+
+impl<'a> ObjectiveAsync<'a> {
+    /// Compiles `objective_wgsl` for candidates of `dimensions` parameters, evaluated by populations of `population` candidates.
+    ///
+    /// # Panics
+    /// If `dimensions` or `population` is zero, or if the objective doesn't compile.
+    pub async fn new(
+        gpu: &'a GpuComputeAsync,
+        objective_wgsl: &str,
+        dimensions: usize,
+        population: usize,
+    ) -> Self {
+        assert!(
+            dimensions > 0 && population > 0,
+            "An objective needs at least one parameter and one candidate"
+        );
+        let source = shader(objective_wgsl, dimensions);
+        let pipeline = gpu
+            .gen_pipeline_dyn_from(
+                population * dimensions,
+                population,
+                None,
+                STAGES,
+                Some(Arc::new(move |_: &str| source.clone())),
+            )
+            .await
+            .unwrap_or_else(|error| panic!("{}", error));
+        Self {
+            pipeline,
+            dimensions,
+            population,
+        }
+    }
+
+    /// Number of parameters of a candidate.
+    #[inline]
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    /// Number of candidates evaluated by each run of the pipeline.
+    #[inline]
+    pub fn population(&self) -> usize {
+        self.population
+    }
+
+    /// Evaluates the objective for each candidate of `candidates`, whose parameters are consecutive. The candidates are evaluated by populations of `ObjectiveAsync::population`, the last one being padded.
+    ///
+    /// # Panics
+    /// If the length of `candidates` isn't a multiple of `ObjectiveAsync::dimensions`.
+    pub async fn evaluate(&mut self, candidates: &[f32]) -> Vec<f32> {
+        assert!(
+            candidates.len().is_multiple_of(self.dimensions),
+            "Expected candidates of {} parameters, got {} values",
+            self.dimensions,
+            candidates.len()
+        );
+        let count = candidates.len() / self.dimensions;
+        let workgroups = [(self.population.div_ceil(64) as u32, 1, 1)];
+        let mut values = Vec::with_capacity(count);
+        let mut padded = Vec::new();
+        for batch in candidates.chunks(self.population * self.dimensions) {
+            let input = if batch.len() == self.pipeline.input_len() {
+                batch
+            } else {
+                padded.clear();
+                padded.extend_from_slice(batch);
+                padded.resize(self.pipeline.input_len(), 0.0);
+                &padded
+            };
+            let evaluated = batch.len() / self.dimensions;
+            self.pipeline
+                .run(input, workgroups, |out| {
+                    values.extend_from_slice(&out[..evaluated])
+                })
+                .await;
+        }
+        values
+    }
+}
+
+/// Best candidate found by a driver.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Minimum {
+    /// Parameters of the candidate.
+    pub x: Vec<f64>,
+    /// Value of the objective for the candidate.
+    pub value: f64,
+    /// Number of candidates evaluated by the search.
+    pub evaluations: u64,
+}
+
+/// Async version of `grid_search`.
+pub async fn grid_search_async(
+    gpu: &GpuComputeAsync,
+    objective_wgsl: &str,
+    bounds: &[(f64, f64)],
+    steps: usize,
+) -> Minimum {
+    assert!(steps > 0, "A grid needs at least one step per dimension");
+    let dimensions = bounds.len();
+    let total = steps
+        .checked_pow(dimensions as u32)
+        .expect("The grid has too many points");
+    let mut objective =
+        ObjectiveAsync::new(gpu, objective_wgsl, dimensions, total.min(GRID_BATCH)).await;
+    let point = |mut index: usize| {
+        bounds.iter().map(move |&(low, high)| {
+            let step = index % steps;
+            index /= steps;
+            if steps == 1 {
+                (low + high) / 2.0
+            } else {
+                low + (high - low) * step as f64 / (steps - 1) as f64
+            }
+        })
+    };
+    let mut best = (usize::MAX, f64::INFINITY);
+    let mut candidates = Vec::with_capacity(GRID_BATCH * dimensions);
+    for first in (0..total).step_by(GRID_BATCH) {
+        candidates.clear();
+        for index in first..(first + GRID_BATCH).min(total) {
+            candidates.extend(point(index).map(|x| x as f32));
+        }
+        for (i, value) in objective
+            .evaluate(&candidates)
+            .await
+            .into_iter()
+            .enumerate()
+        {
+            if (value as f64) < best.1 || best.0 == usize::MAX {
+                best = (first + i, value as f64);
+            }
+        }
+    }
+    Minimum {
+        x: point(best.0).collect(),
+        value: best.1,
+        evaluations: total as u64,
+    }
+}
+
+/// Evaluates `objective_wgsl` at the `steps^n` points of a regular grid over the box `bounds`, given as the lower and upper bound of each of the `n` parameters, and returns the best one, see the module documentation. A single step evaluates the center of the box. It is enabled by the `blocking` feature.
+///
+/// # Panics
+/// If `steps` is zero, if the grid has more than `usize::MAX` points or if the objective doesn't compile.
+#[cfg(feature = "blocking")]
+pub fn grid_search(
+    gpu: &crate::blocking::GpuCompute,
+    objective_wgsl: &str,
+    bounds: &[(f64, f64)],
+    steps: usize,
+) -> Minimum {
+    pollster::block_on(grid_search_async(gpu, objective_wgsl, bounds, steps))
+}
+
+/// Options of `cma_es`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CmaEs {
+    /// Initial mean of the samples, which sets the number of parameters.
+    pub mean: Vec<f64>,
+    /// Initial step size, about a third of the distance to the minimum is a good start.
+    pub sigma: f64,
+    /// Number of candidates per generation, `4 + 3 ln(n)` for `n` parameters if `None`.
+    pub population: Option<usize>,
+    pub max_generations: usize,
+    /// The search stops when the step size along every axis is below this tolerance.
+    pub tolerance: f64,
+    /// Master seed of the samples.
+    pub seed: u64,
+}
+
+impl CmaEs {
+    /// Default options starting from `mean` with the step size `sigma`.
+    pub fn new(mean: Vec<f64>, sigma: f64) -> Self {
+        Self {
+            mean,
+            sigma,
+            population: None,
+            max_generations: 1000,
+            tolerance: 1e-6,
+            seed: 0,
+        }
+    }
+
+    /// Same options, with `population` candidates per generation.
+    #[inline]
+    pub fn with_population(self, population: usize) -> Self {
+        Self {
+            population: Some(population),
+            ..self
+        }
+    }
+
+    /// Same options, stopping after `max_generations` generations.
+    #[inline]
+    pub fn with_max_generations(self, max_generations: usize) -> Self {
+        Self {
+            max_generations,
+            ..self
+        }
+    }
+
+    /// Same options, stopping when the step size is below `tolerance`.
+    #[inline]
+    pub fn with_tolerance(self, tolerance: f64) -> Self {
+        Self { tolerance, ..self }
+    }
+
+    /// Same options, with the master seed `seed`.
+    #[inline]
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+}
+
+/// Async version of `cma_es`.
+pub async fn cma_es_async(gpu: &GpuComputeAsync, objective_wgsl: &str, options: &CmaEs) -> Minimum {
+    let n = options.mean.len();
+    assert!(n > 0, "CMA-ES needs at least one parameter");
+    assert!(
+        options.sigma > 0.0,
+        "The initial step size of CMA-ES must be positive"
+    );
+    let lambda = options
+        .population
+        .unwrap_or(4 + (3.0 * (n as f64).ln()) as usize)
+        .max(2);
+    let mu = lambda / 2;
+    let mut objective = ObjectiveAsync::new(gpu, objective_wgsl, n, lambda).await;
+
+    // Strategy parameters of the tutorial of N. Hansen, "The CMA Evolution Strategy: A Tutorial".
+    let nf = n as f64;
+    let mut weights = (0..mu)
+        .map(|i| (mu as f64 + 0.5).ln() - (i as f64 + 1.0).ln())
+        .collect::<Vec<_>>();
+    let sum = weights.iter().sum::<f64>();
+    weights.iter_mut().for_each(|w| *w /= sum);
+    let mueff = 1.0 / weights.iter().map(|w| w * w).sum::<f64>();
+    let cc = (4.0 + mueff / nf) / (nf + 4.0 + 2.0 * mueff / nf);
+    let cs = (mueff + 2.0) / (nf + mueff + 5.0);
+    let c1 = 2.0 / ((nf + 1.3).powi(2) + mueff);
+    let cmu = (1.0 - c1).min(2.0 * (mueff - 2.0 + 1.0 / mueff) / ((nf + 2.0).powi(2) + mueff));
+    let damps = 1.0 + 2.0 * (((mueff - 1.0) / (nf + 1.0)).sqrt() - 1.0).max(0.0) + cs;
+    let chi_n = nf.sqrt() * (1.0 - 1.0 / (4.0 * nf) + 1.0 / (21.0 * nf * nf));
+
+    let mut mean = options.mean.clone();
+    let mut sigma = options.sigma;
+    let mut c = identity(n);
+    let mut pc = vec![0.0; n];
+    let mut ps = vec![0.0; n];
+    let seeds = SeedManager::new(options.seed);
+    let mut best = Minimum {
+        x: mean.clone(),
+        value: f64::INFINITY,
+        evaluations: 0,
+    };
+    let mut candidates = Vec::with_capacity(lambda * n);
+    for generation in 0..options.max_generations {
+        // C = B diag(d^2) B^T
+        let (b, mut d) = eigen(&c);
+        d.iter_mut().for_each(|d| *d = d.max(0.0).sqrt());
+        let seed = seeds.seed(generation as u64);
+        let ys = (0..lambda)
+            .map(|k| {
+                let z = (0..n)
+                    .map(|i| {
+                        // Box-Muller transform of two uniform draws.
+                        let index = (k * n + i) as u32;
+                        let u1 = 1.0 - seed.random_f32(index, 0) as f64;
+                        let u2 = seed.random_f32(index, 1) as f64;
+                        (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+                    })
+                    .collect::<Vec<_>>();
+                (0..n)
+                    .map(|i| (0..n).map(|j| b[i][j] * d[j] * z[j]).sum())
+                    .collect::<Vec<f64>>()
+            })
+            .collect::<Vec<_>>();
+        candidates.clear();
+        candidates.extend(
+            ys.iter()
+                .flat_map(|y| y.iter().zip(&mean).map(|(y, m)| (m + sigma * y) as f32)),
+        );
+        let values = objective.evaluate(&candidates).await;
+        best.evaluations += lambda as u64;
+        let mut ranking = (0..lambda).collect::<Vec<_>>();
+        let fitness = |k: usize| {
+            let value = values[k] as f64;
+            if value.is_nan() {
+                f64::INFINITY
+            } else {
+                value
+            }
+        };
+        ranking.sort_by(|&a, &b| fitness(a).total_cmp(&fitness(b)));
+        if fitness(ranking[0]) < best.value {
+            best.value = fitness(ranking[0]);
+            best.x = candidates[ranking[0] * n..][..n]
+                .iter()
+                .map(|&x| x as f64)
+                .collect();
+        }
+
+        let y_w = (0..n)
+            .map(|i| (0..mu).map(|r| weights[r] * ys[ranking[r]][i]).sum::<f64>())
+            .collect::<Vec<_>>();
+        mean.iter_mut().zip(&y_w).for_each(|(m, y)| *m += sigma * y);
+        // C^(-1/2) y_w = B diag(1/d) B^T y_w
+        let bt_y = (0..n)
+            .map(|j| (0..n).map(|i| b[i][j] * y_w[i]).sum::<f64>() / d[j].max(f64::MIN_POSITIVE))
+            .collect::<Vec<_>>();
+        let c_inv_sqrt_y = (0..n)
+            .map(|i| (0..n).map(|j| b[i][j] * bt_y[j]).sum::<f64>())
+            .collect::<Vec<_>>();
+        let ps_scale = (cs * (2.0 - cs) * mueff).sqrt();
+        ps.iter_mut()
+            .zip(&c_inv_sqrt_y)
+            .for_each(|(p, y)| *p = (1.0 - cs) * *p + ps_scale * y);
+        let ps_norm = ps.iter().map(|p| p * p).sum::<f64>().sqrt();
+        let hsig = ps_norm / (1.0 - (1.0 - cs).powi(2 * (generation as i32 + 1))).sqrt() / chi_n
+            < 1.4 + 2.0 / (nf + 1.0);
+        let pc_scale = if hsig {
+            (cc * (2.0 - cc) * mueff).sqrt()
+        } else {
+            0.0
+        };
+        pc.iter_mut()
+            .zip(&y_w)
+            .for_each(|(p, y)| *p = (1.0 - cc) * *p + pc_scale * y);
+        let correction = if hsig { 0.0 } else { c1 * cc * (2.0 - cc) };
+        for i in 0..n {
+            for j in 0..=i {
+                let rank_mu = (0..mu)
+                    .map(|r| weights[r] * ys[ranking[r]][i] * ys[ranking[r]][j])
+                    .sum::<f64>();
+                let value =
+                    (1.0 - c1 - cmu + correction) * c[i][j] + c1 * pc[i] * pc[j] + cmu * rank_mu;
+                c[i][j] = value;
+                c[j][i] = value;
+            }
+        }
+        sigma *= ((cs / damps) * (ps_norm / chi_n - 1.0)).exp();
+
+        let max_std = (0..n).map(|i| c[i][i].sqrt()).fold(0.0, f64::max);
+        if sigma * max_std < options.tolerance || !sigma.is_finite() {
+            break;
+        }
+    }
+    best
+}
+
+/// Minimizes `objective_wgsl` with CMA-ES, evaluating each generation on the GPU, and returns the best candidate found, see the module documentation. It is enabled by the `blocking` feature.
+///
+/// # Panics
+/// If the mean of `options` is empty, if its step size isn't positive or if the objective doesn't compile.
+#[cfg(feature = "blocking")]
+pub fn cma_es(gpu: &crate::blocking::GpuCompute, objective_wgsl: &str, options: &CmaEs) -> Minimum {
+    pollster::block_on(cma_es_async(gpu, objective_wgsl, options))
+}
+
+fn identity(n: usize) -> Vec<Vec<f64>> {
+    (0..n)
+        .map(|i| (0..n).map(|j| (i == j) as u8 as f64).collect())
+        .collect()
+}
+
+/// Eigenvectors, as the columns of the first matrix, and eigenvalues of the symmetric matrix `a`, with the cyclic Jacobi method.
+fn eigen(a: &[Vec<f64>]) -> (Vec<Vec<f64>>, Vec<f64>) {
+    let n = a.len();
+    let mut a = a.to_vec();
+    let mut v = identity(n);
+    for _ in 0..64 {
+        let off = (0..n)
+            .flat_map(|i| (0..i).map(move |j| (i, j)))
+            .map(|(i, j)| a[i][j] * a[i][j])
+            .sum::<f64>();
+        let diagonal = (0..n).map(|i| a[i][i] * a[i][i]).sum::<f64>();
+        if off <= 1e-30 * diagonal || off == 0.0 {
+            break;
+        }
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[p][q] == 0.0 {
+                    continue;
+                }
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let cos = 1.0 / (t * t + 1.0).sqrt();
+                let sin = t * cos;
+                // A = J^T A J and V = V J, for the rotation J of the plane (p, q).
+                for row in a.iter_mut().chain(v.iter_mut()) {
+                    let (kp, kq) = (row[p], row[q]);
+                    row[p] = cos * kp - sin * kq;
+                    row[q] = sin * kp + cos * kq;
+                }
+                let (row_p, row_q) = (a[p].clone(), a[q].clone());
+                for (k, (pk, qk)) in row_p.into_iter().zip(row_q).enumerate() {
+                    a[p][k] = cos * pk - sin * qk;
+                    a[q][k] = sin * pk + cos * qk;
+                }
+            }
+        }
+    }
+    let eigenvalues = (0..n).map(|i| a[i][i]).collect();
+    (v, eigenvalues)
+}
@@ -0,0 +1,211 @@
+//! PageRank by power iteration over CSR graphs.
+//!
+//! Each iteration multiplies the ranks by the transition matrix with a pull-based sparse matrix-vector product over the incoming edges of each vertex, the rank of the vertices without outgoing edges being spread over all the vertices. A run is made of the `init` stage followed by `ITERATIONS_PER_RUN` iterations of the `DANGLING`, `SPMV` and `FINISH` stages, and the output holds the L1 distance between the last two iterations, so `pagerank` runs the pipeline again until this residual is below the tolerance, only continuing from the ranks kept in the scratchpad.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::pagerank::{self, PageRankGraph};
+//!
+//! // 0 -> 1 -> 2 -> 0 and 3 -> 2, the vertex 3 has no incoming edge.
+//! let graph = PageRankGraph::<4, 4>::from_edges(&[(0, 1), (1, 2), (2, 0), (3, 2)]);
+//! let gpu = GpuCompute::new();
+//! let result = pagerank::pagerank(&gpu, &graph, 0.85, 1e-6, 100);
+//! assert!(result.residual < 1e-6);
+//! assert!((result.ranks.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+//! assert!((result.ranks[3] - 0.15 / 4.0).abs() < 1e-5);
+//! ```
+use crate::{GpuComputeAsync, StageDesc};
+use std::num::NonZeroUsize;
+
+/// WGSL source containing the `init`, `dangling`, `spmv` and `finish` entry points.
+pub const SHADER: &str = sgpu_compute_core::kernels::pagerank::SHADER;
+
+/// Number of iterations done by each run of the pipeline of `pagerank`.
+pub const ITERATIONS_PER_RUN: usize = 8;
+
+/// Stage restarting from uniform ranks on reset.
+pub const INIT: StageDesc = StageDesc::new(SHADER, "init").with_name("pagerank_init");
+
+/// Stage summing the ranks of the vertices without outgoing edges.
+pub const DANGLING: StageDesc = StageDesc::new(SHADER, "dangling").with_name("pagerank_dangling");
+
+/// Stage computing the new ranks.
+pub const SPMV: StageDesc = StageDesc::new(SHADER, "spmv").with_name("pagerank_spmv");
+
+/// Stage computing the residual and making the new ranks the current ones.
+pub const FINISH: StageDesc = StageDesc::new(SHADER, "finish").with_name("pagerank_finish");
+
+/// Returns `INIT` followed by `(K - 1) / 3` iterations of `DANGLING`, `SPMV` and `FINISH`.
+///
+/// # Panics
+/// If `K - 1` is not a multiple of 3.
+pub fn stages<const K: usize>() -> [StageDesc; K] {
+    assert!(
+        K > 0 && (K - 1).is_multiple_of(3),
+        "PageRank needs the init stage followed by whole iterations"
+    );
+    std::array::from_fn(|i| match i {
+        0 => INIT,
+        i => [DANGLING, SPMV, FINISH][(i - 1) % 3].clone(),
+    })
+}
+
+/// Returns the workgroups of each stage for a graph of `vertices` vertices.
+pub fn workgroups<const K: usize>(vertices: u32) -> [(u32, u32, u32); K] {
+    std::array::from_fn(|i| match i {
+        i if i > 0 && (i - 1) % 3 != 1 => (1, 1, 1),
+        _ => (vertices.div_ceil(64), 1, 1),
+    })
+}
+
+/// Returns the scratchpad size needed by the ranks of a graph of `vertices` vertices.
+#[inline]
+pub const fn scratchpad_size(vertices: u32) -> Option<NonZeroUsize> {
+    NonZeroUsize::new((4 + 2 * vertices as usize) * std::mem::size_of::<f32>())
+}
+
+/// Uniform of the stages.
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct PageRankParams {
+    pub vertices: u32,
+    /// Probability of following an edge rather than jumping to a random vertex, usually 0.85.
+    pub damping: f32,
+    /// Non-zero to restart from uniform ranks.
+    pub reset: u32,
+    _pad0: u32,
+}
+
+impl PageRankParams {
+    #[inline]
+    pub const fn new(vertices: u32, damping: f32, reset: bool) -> Self {
+        Self {
+            vertices,
+            damping,
+            reset: reset as u32,
+            _pad0: 0,
+        }
+    }
+}
+
+/// Directed graph of `V` vertices and `E` edges stored by incoming edges: the sources of the edges to the vertex `v` are `sources[offsets[v]..offsets[v + 1]]`, with `offsets[V]` being `end`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(C)]
+pub struct PageRankGraph<const V: usize, const E: usize> {
+    pub offsets: [u32; V],
+    pub end: u32,
+    pub sources: [u32; E],
+    pub out_degrees: [u32; V],
+}
+
+impl<const V: usize, const E: usize> PageRankGraph<V, E> {
+    /// Builds the graph from its `E` directed edges `(from, to)`.
+    ///
+    /// # Panics
+    /// If there are not `E` edges or a vertex is not in `0..V`.
+    pub fn from_edges(edges: &[(u32, u32)]) -> Self {
+        assert_eq!(edges.len(), E, "Wrong number of edges");
+        let mut graph = Self {
+            offsets: [0; V],
+            end: E as u32,
+            sources: [0; E],
+            out_degrees: [0; V],
+        };
+        let mut in_degrees = [0u32; V];
+        for &(from, to) in edges {
+            assert!((from as usize) < V, "Vertex {} out of bounds", from);
+            graph.out_degrees[from as usize] += 1;
+            in_degrees[to as usize] += 1;
+        }
+        let mut offset = 0;
+        for (v, degree) in in_degrees.iter().enumerate() {
+            graph.offsets[v] = offset;
+            offset += degree;
+        }
+        let mut cursors = graph.offsets;
+        for &(from, to) in edges {
+            graph.sources[cursors[to as usize] as usize] = from;
+            cursors[to as usize] += 1;
+        }
+        graph
+    }
+}
+
+// SAFETY: the struct is only made of `u32`, so it has no padding.
+unsafe impl<const V: usize, const E: usize> bytemuck::Zeroable for PageRankGraph<V, E> {}
+unsafe impl<const V: usize, const E: usize> bytemuck::Pod for PageRankGraph<V, E> {}
+
+/// Output of the stages for a graph of `V` vertices.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct Ranks<const V: usize> {
+    /// L1 distance between the ranks of the last two iterations.
+    pub residual: f32,
+    /// Number of iterations since the reset.
+    pub iterations: u32,
+    pub ranks: [f32; V],
+}
+
+// SAFETY: the struct is only made of `u32` and `f32`, so it has no padding.
+unsafe impl<const V: usize> bytemuck::Zeroable for Ranks<V> {}
+unsafe impl<const V: usize> bytemuck::Pod for Ranks<V> {}
+
+/// Result of `pagerank`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageRank {
+    /// Rank of each vertex, they sum to 1.
+    pub ranks: Vec<f32>,
+    /// L1 distance between the ranks of the last two iterations.
+    pub residual: f32,
+    pub iterations: u32,
+}
+
+/// Async version of `pagerank`.
+pub async fn pagerank_async<const V: usize, const E: usize>(
+    gpu: &GpuComputeAsync,
+    graph: &PageRankGraph<V, E>,
+    damping: f32,
+    tolerance: f32,
+    max_iterations: u32,
+) -> PageRank {
+    const K: usize = 1 + 3 * ITERATIONS_PER_RUN;
+    let vertices = V as u32;
+    let mut pipeline = gpu
+        .gen_pipeline::<PageRankGraph<V, E>, PageRankParams, Ranks<V>, K>(
+            scratchpad_size(vertices),
+            stages(),
+        )
+        .await;
+    pipeline.write_uniform(&PageRankParams::new(vertices, damping, true));
+    loop {
+        let result = pipeline
+            .run(graph, workgroups(vertices), |out| PageRank {
+                ranks: out.ranks.to_vec(),
+                residual: out.residual,
+                iterations: out.iterations,
+            })
+            .await;
+        if result.residual < tolerance || result.iterations >= max_iterations {
+            return result;
+        }
+        pipeline.write_uniform(&PageRankParams::new(vertices, damping, false));
+    }
+}
+
+/// Computes the PageRank of each vertex of `graph`, iterating until the L1 distance between two iterations is below `tolerance` or at least `max_iterations` iterations are done. The iterations are done by batches of `ITERATIONS_PER_RUN`. It is enabled by the `blocking` feature.
+#[cfg(feature = "blocking")]
+pub fn pagerank<const V: usize, const E: usize>(
+    gpu: &crate::blocking::GpuCompute,
+    graph: &PageRankGraph<V, E>,
+    damping: f32,
+    tolerance: f32,
+    max_iterations: u32,
+) -> PageRank {
+    pollster::block_on(pagerank_async(
+        gpu,
+        graph,
+        damping,
+        tolerance,
+        max_iterations,
+    ))
+}
@@ -0,0 +1,148 @@
+//! 2D particle system: integration, binning in a uniform grid and collision response.
+//!
+//! The particles live in the output buffer, which persists between runs, so a frame is one run of the pipeline and only reads back the particles. The input holds the initial particles, it is only read when `ParticleParams::reset` is set. A run is made of seven stages:
+//!     - `integrate` applies the gravity, moves the particles and bounces them off the walls of the box `[0, bounds]`
+//!     - `clear_grid`, `count`, `scan` and `scatter` sort the particles by grid cell with a counting sort
+//!     - `collide` computes the velocity of each particle after the collisions with the particles of the neighbor cells, then `apply` writes it
+//!
+//! The cells are as large as the diameter of the particles, so a particle can only touch the particles of its cell and of the 8 cells around it.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::particles::{self, Particle, ParticleParams};
+//!
+//! const N: usize = 2;
+//!
+//! // Two particles moving toward each other exchange their velocities.
+//! let initial = [
+//!     Particle::new([4.0, 5.0], [1.0, 0.0]),
+//!     Particle::new([5.9, 5.0], [-1.0, 0.0]),
+//! ];
+//! let params = ParticleParams::new(N as u32, [10.0, 10.0], 1.0, 0.01);
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline::<[Particle; N], ParticleParams, [Particle; N], 7>(params.scratchpad_size(), particles::STAGES);
+//! pipeline.write_uniform(&params.with_reset(true));
+//! pipeline.run(&initial, params.workgroups(), |_| ());
+//! pipeline.write_uniform(&params);
+//! let frame = pipeline.run(&initial, params.workgroups(), |frame| *frame);
+//! assert_eq!(frame[0].velocity, [-1.0, 0.0]);
+//! assert_eq!(frame[1].velocity, [1.0, 0.0]);
+//! ```
+use crate::StageDesc;
+use std::num::NonZeroUsize;
+
+/// WGSL source containing the `integrate`, `clear_grid`, `count`, `scan`, `scatter`, `collide` and `apply` entry points.
+pub const SHADER: &str = sgpu_compute_core::kernels::particles::SHADER;
+
+/// The seven stages of a frame, in order.
+pub const STAGES: [StageDesc; 7] = [
+    StageDesc::new(SHADER, "integrate").with_name("particles_integrate"),
+    StageDesc::new(SHADER, "clear_grid").with_name("particles_clear_grid"),
+    StageDesc::new(SHADER, "count").with_name("particles_count"),
+    StageDesc::new(SHADER, "scan").with_name("particles_scan"),
+    StageDesc::new(SHADER, "scatter").with_name("particles_scatter"),
+    StageDesc::new(SHADER, "collide").with_name("particles_collide"),
+    StageDesc::new(SHADER, "apply").with_name("particles_apply"),
+];
+
+/// A particle, matching the `Particle` struct of the shader.
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct Particle {
+    pub position: [f32; 2],
+    pub velocity: [f32; 2],
+}
+
+impl Particle {
+    #[inline]
+    pub const fn new(position: [f32; 2], velocity: [f32; 2]) -> Self {
+        Self { position, velocity }
+    }
+}
+
+/// Uniform of the stages.
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct ParticleParams {
+    pub gravity: [f32; 2],
+    /// Size of the box containing the particles.
+    pub bounds: [f32; 2],
+    /// Number of particles.
+    pub count: u32,
+    pub grid_width: u32,
+    pub grid_height: u32,
+    /// Non-zero to restart the simulation from the input.
+    pub reset: u32,
+    pub cell_size: f32,
+    pub radius: f32,
+    /// Time step of a frame.
+    pub dt: f32,
+    /// Fraction of the normal velocity kept by the collisions, `1.0` for elastic collisions.
+    pub restitution: f32,
+}
+
+impl ParticleParams {
+    /// Parameters of `count` particles of `radius` in a box of size `bounds`, without gravity and with elastic collisions.
+    #[inline]
+    pub fn new(count: u32, bounds: [f32; 2], radius: f32, dt: f32) -> Self {
+        let cell_size = 2.0 * radius;
+        Self {
+            gravity: [0.0; 2],
+            bounds,
+            count,
+            grid_width: ((bounds[0] / cell_size).ceil() as u32).max(1),
+            grid_height: ((bounds[1] / cell_size).ceil() as u32).max(1),
+            reset: 0,
+            cell_size,
+            radius,
+            dt,
+            restitution: 1.0,
+        }
+    }
+
+    #[inline]
+    pub const fn with_gravity(mut self, gravity: [f32; 2]) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    #[inline]
+    pub const fn with_restitution(mut self, restitution: f32) -> Self {
+        self.restitution = restitution;
+        self
+    }
+
+    #[inline]
+    pub const fn with_reset(mut self, reset: bool) -> Self {
+        self.reset = reset as u32;
+        self
+    }
+
+    /// Number of cells of the grid.
+    #[inline]
+    pub const fn cells(&self) -> u32 {
+        self.grid_width * self.grid_height
+    }
+
+    /// Returns the scratchpad size needed by the stages: the grid, the sorted indices and the velocities after the collisions.
+    #[inline]
+    pub const fn scratchpad_size(&self) -> Option<NonZeroUsize> {
+        let words = 2 * self.cells() as usize + 1 + 3 * self.count as usize;
+        NonZeroUsize::new(words * std::mem::size_of::<u32>())
+    }
+
+    /// Returns the workgroups of each stage.
+    #[inline]
+    pub const fn workgroups(&self) -> [(u32, u32, u32); 7] {
+        let particles = (self.count.div_ceil(64), 1, 1);
+        [
+            particles,
+            (self.cells().div_ceil(64), 1, 1),
+            particles,
+            (1, 1, 1),
+            particles,
+            particles,
+            particles,
+        ]
+    }
+}
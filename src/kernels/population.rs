@@ -0,0 +1,271 @@
+//! Fitness evaluation of the populations of metaheuristics, e.g. genetic algorithms or parallel simulated annealing.
+//!
+//! The fitness is WGSL source declaring the `Genome` type, matching the `bytemuck::Pod` type `G` of the genomes, and `fn fitness(index: u32) -> f32` scoring `population[index]`. If the uniform `U` isn't zero-sized, e.g. the temperature of an annealing schedule, the fitness also declares it at `@group(0) @binding(0)`. A `PopulationAsync` uploads a population, evaluates the fitness of each genome in one run and reads back the scores, the selection and the variation being left to the CPU.
+//!
+//! The generations are double-buffered: each one has its own buffers, so a generation can be uploaded and evaluated with `PopulationAsync::submit` while the previous one is still in flight, and `PopulationAsync::scores` returns the scores of the oldest generation in flight. A steady-state algorithm can then breed the next generation on the CPU while the GPU evaluates the current one.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::population::Population;
+//!
+//! let fitness = "
+//!     alias Genome = array<f32, 4>;
+//!
+//!     fn fitness(index: u32) -> f32 {
+//!         var sum = 0.0;
+//!         for (var i = 0u; i < 4u; i++) {
+//!             sum += population[index][i] * population[index][i];
+//!         }
+//!         return sum;
+//!     }
+//! ";
+//! let gpu = GpuCompute::new();
+//! let mut population = Population::<[f32; 4], ()>::new(&gpu, fitness, 100);
+//! let first: Vec<[f32; 4]> = (0..100).map(|i| [i as f32; 4]).collect();
+//! let second: Vec<[f32; 4]> = (0..100).map(|i| [i as f32, 0.0, 0.0, 0.0]).collect();
+//! population.submit(&first);
+//! population.submit(&second);
+//! assert_eq!(population.scores()[3], 36.0);
+//! assert_eq!(population.scores()[3], 9.0);
+//! ```
+use crate::{BufferSizes, Buffers, CompiledStages, GpuComputeAsync, GpuRef, StageDesc};
+use std::{marker::PhantomData, sync::Arc};
+
+/// WGSL source of the `evaluate` entry point, without the bindings and the fitness, see `shader`.
+pub const SHADER: &str = sgpu_compute_core::kernels::population::SHADER;
+
+/// Key of the shader given to the shader source provider of the pipeline.
+const SHADER_KEY: &str = "population";
+
+const STAGES: [StageDesc; 1] =
+    [StageDesc::new(SHADER_KEY, "evaluate").with_name("population_evaluate")];
+
+/// Returns the complete shader for the given fitness, whose uniform is bound at 0 if `uniform` is true.
+pub fn shader(fitness_wgsl: &str, uniform: bool) -> String {
+    let first = uniform as u32;
+    format!(
+        "@group(0) @binding({}) var<storage, read> population: array<Genome>;\n@group(0) @binding({}) var<storage, read_write> scores: array<f32>;\n{}\n{}",
+        first,
+        first + 1,
+        SHADER,
+        fitness_wgsl
+    )
+}
+
+/// Buffers of a generation, with the receiver of the mapping of its scores while it is in flight.
+struct Generation {
+    buffers: Buffers,
+    in_flight: Option<flume::Receiver<Result<(), wgpu::BufferAsyncError>>>,
+}
+
+/// Fitness compiled to evaluate populations of `size` genomes of type `G` with the uniform `U`, see the module documentation.
+pub struct PopulationAsync<'a, G: bytemuck::Pod, U: bytemuck::Pod> {
+    generations: [Generation; 2],
+    stages: CompiledStages<1>,
+    size: usize,
+    /// Number of generations submitted, and read back.
+    submitted: u64,
+    read: u64,
+    device: GpuRef<'a>,
+    _phantom: PhantomData<(G, U)>,
+}
+
+impl<'a, G: bytemuck::Pod, U: bytemuck::Pod> PopulationAsync<'a, G, U> {
+    /// Compiles `fitness_wgsl` for populations of `size` genomes.
+    ///
+    /// # Panics
+    /// If `size` is zero, if the size of `G` isn't a multiple of 4 bytes or if the fitness doesn't compile.
+    pub async fn new(gpu: &'a GpuComputeAsync, fitness_wgsl: &str, size: usize) -> Self {
+        assert!(size > 0, "A population needs at least one genome");
+        assert!(
+            std::mem::size_of::<G>().is_multiple_of(4) && std::mem::size_of::<G>() > 0,
+            "The size of a genome must be a non-zero multiple of 4 bytes"
+        );
+        let sizes = BufferSizes {
+            uniform: std::mem::size_of::<U>(),
+            scratchpad: None,
+            scratchpads: Vec::new(),
+            input: size * std::mem::size_of::<G>(),
+            output: size * std::mem::size_of::<f32>(),
+            extra_outputs: Vec::new(),
+            push_constants: 0,
+        };
+        let source = shader(fitness_wgsl, sizes.uniform > 0);
+        let stages = gpu
+            .compile_stages(
+                &sizes,
+                STAGES,
+                Some(Arc::new(move |_: &str| source.clone())),
+            )
+            .await
+            .unwrap_or_else(|error| panic!("{}", error));
+        let generation = || Generation {
+            buffers: gpu.create_buffers(&sizes, &stages.bindgroup_layout, gpu.zero_init),
+            in_flight: None,
+        };
+        Self {
+            generations: [generation(), generation()],
+            stages,
+            size,
+            submitted: 0,
+            read: 0,
+            device: GpuRef::Borrowed(gpu),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Number of genomes of a population.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Number of generations submitted whose scores weren't read yet, at most 2.
+    #[inline]
+    pub fn in_flight(&self) -> usize {
+        (self.submitted - self.read) as usize
+    }
+
+    /// This method is used to write the uniform of the fitness, e.g. the temperature of an annealing schedule. It applies to the generations submitted afterwards.
+    pub fn write_uniform(&mut self, uniform: &U) {
+        for generation in &self.generations {
+            self.device.queue.write_buffer(
+                generation.buffers.uniform.as_ref().expect("No uniforms"),
+                0,
+                bytemuck::bytes_of(uniform),
+            );
+        }
+    }
+
+    /// Uploads `population` and submits the evaluation of its fitness, without waiting for it. Returns the number of the generation, starting at 0.
+    ///
+    /// # Panics
+    /// If `population` doesn't have `PopulationAsync::size` genomes, or if two generations are already in flight.
+    pub fn submit(&mut self, population: &[G]) -> u64 {
+        assert_eq!(
+            population.len(),
+            self.size,
+            "Expected {} genomes, got {}",
+            self.size,
+            population.len()
+        );
+        assert!(
+            self.in_flight() < 2,
+            "Two generations are already in flight, read the scores of the oldest one first"
+        );
+        let generation = &mut self.generations[(self.submitted % 2) as usize];
+        let buffers = &generation.buffers;
+        self.device.queue.write_buffer(
+            buffers.input.as_ref().expect("Genomes are not zero-sized"),
+            0,
+            bytemuck::cast_slice(population),
+        );
+        let mut encoder =
+            self.device
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Population evaluation"),
+                });
+        self.stages.record_passes(
+            &mut encoder,
+            &buffers.bindgroup,
+            &self.device,
+            &[(self.size.div_ceil(64) as u32, 1, 1)],
+        );
+        encoder.copy_buffer_to_buffer(
+            &buffers.staging,
+            0,
+            &buffers.output,
+            0,
+            buffers.output.size(),
+        );
+        self.device.queue.submit(Some(encoder.finish()));
+        let (sender, receiver) = flume::bounded(1);
+        buffers
+            .output
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |e| {
+                // The mapping is cancelled if the population is dropped with generations in flight, the error is only reported when the scores are read.
+                let _ = sender.send(e);
+            });
+        generation.in_flight = Some(receiver);
+        self.submitted += 1;
+        self.submitted - 1
+    }
+
+    /// Waits for the oldest generation in flight and returns its scores, in the order of its genomes.
+    ///
+    /// # Panics
+    /// If no generation is in flight.
+    pub async fn scores(&mut self) -> Vec<f32> {
+        let generation = &mut self.generations[(self.read % 2) as usize];
+        let receiver = generation
+            .in_flight
+            .take()
+            .expect("No generation in flight, submit one first");
+        self.device.wait_submitted();
+        receiver
+            .recv_async()
+            .await
+            .expect("Error with channel")
+            .expect("Could not map buffer");
+        let output = &generation.buffers.output;
+        let scores = bytemuck::cast_slice(output.slice(..).get_mapped_range().as_ref()).to_vec();
+        output.unmap();
+        self.read += 1;
+        scores
+    }
+
+    /// Submits `population` and returns its scores, after the ones of the generations already in flight are read.
+    pub async fn evaluate(&mut self, population: &[G]) -> Vec<f32> {
+        while self.in_flight() > 0 {
+            self.scores().await;
+        }
+        self.submit(population);
+        self.scores().await
+    }
+}
+
+/// Blocking version of `PopulationAsync`, it is enabled by the `blocking` feature.
+#[cfg(feature = "blocking")]
+pub struct Population<'a, G: bytemuck::Pod, U: bytemuck::Pod>(PopulationAsync<'a, G, U>);
+
+#[cfg(feature = "blocking")]
+impl<'a, G: bytemuck::Pod, U: bytemuck::Pod> Population<'a, G, U> {
+    /// Blocking version of `PopulationAsync::new`.
+    pub fn new(gpu: &'a crate::blocking::GpuCompute, fitness_wgsl: &str, size: usize) -> Self {
+        Self(pollster::block_on(PopulationAsync::new(
+            gpu,
+            fitness_wgsl,
+            size,
+        )))
+    }
+
+    /// Blocking version of `PopulationAsync::scores`.
+    pub fn scores(&mut self) -> Vec<f32> {
+        pollster::block_on(self.0.scores())
+    }
+
+    /// Blocking version of `PopulationAsync::evaluate`.
+    pub fn evaluate(&mut self, population: &[G]) -> Vec<f32> {
+        pollster::block_on(self.0.evaluate(population))
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<'a, G: bytemuck::Pod, U: bytemuck::Pod> std::ops::Deref for Population<'a, G, U> {
+    type Target = PopulationAsync<'a, G, U>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<G: bytemuck::Pod, U: bytemuck::Pod> std::ops::DerefMut for Population<'_, G, U> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
@@ -0,0 +1,110 @@
+//! Rolling sum, mean, minimum and maximum over 1D series.
+//!
+//! The series is split in blocks of `window` elements and the `blocks` stage computes the prefix and the suffix of each block. A window starting at `i` covers the end of the block of `i` and the beginning of the next block, so the `windows` stage gets its value by combining one suffix and one prefix, whatever the window. The output holds the value of each window starting at `0..len - window + 1`, the remaining elements are left untouched.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::rolling::{self, RollingOp};
+//!
+//! let series = [1.0, 3.0, 2.0, 5.0, 4.0, 0.0];
+//! let gpu = GpuCompute::new();
+//! assert_eq!(rolling::rolling(&gpu, &series, 3, RollingOp::Max), [3.0, 5.0, 5.0, 5.0]);
+//! assert_eq!(rolling::rolling(&gpu, &series, 2, RollingOp::Mean), [2.0, 2.5, 3.5, 4.5, 2.0]);
+//! ```
+use crate::{GpuComputeAsync, StageDesc};
+use std::num::NonZeroUsize;
+
+/// WGSL source containing the `blocks` and `windows` entry points.
+pub const SHADER: &str = sgpu_compute_core::kernels::rolling::SHADER;
+
+/// Stages computing the rolling values, see the module documentation.
+pub const STAGES: [StageDesc; 2] = [
+    StageDesc::new(SHADER, "blocks").with_name("rolling_blocks"),
+    StageDesc::new(SHADER, "windows").with_name("rolling_windows"),
+];
+
+/// Operation applied to each window.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u32)]
+pub enum RollingOp {
+    Sum = 0,
+    Mean = 1,
+    Min = 2,
+    Max = 3,
+}
+
+/// Uniform of the stages.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct RollingParams {
+    pub len: u32,
+    pub window: u32,
+    op: u32,
+    _pad0: u32,
+}
+
+impl RollingParams {
+    /// # Panics
+    /// If `window` is not in `1..=len`.
+    #[inline]
+    pub const fn new(len: u32, window: u32, op: RollingOp) -> Self {
+        assert!(window > 0 && window <= len, "The window must be in 1..=len");
+        Self {
+            len,
+            window,
+            op: op as u32,
+            _pad0: 0,
+        }
+    }
+
+    /// Number of complete windows, which is the number of values written in the output.
+    #[inline]
+    pub const fn windows(&self) -> u32 {
+        self.len - self.window + 1
+    }
+
+    /// Workgroups of `STAGES`.
+    #[inline]
+    pub const fn workgroups(&self) -> [(u32, u32, u32); 2] {
+        [
+            (self.len.div_ceil(self.window).div_ceil(64), 1, 1),
+            (self.windows().div_ceil(64), 1, 1),
+        ]
+    }
+}
+
+/// Returns the scratchpad size for a series of `len` elements.
+#[inline]
+pub const fn scratchpad_size(len: u32) -> Option<NonZeroUsize> {
+    NonZeroUsize::new(2 * len as usize * std::mem::size_of::<f32>())
+}
+
+/// Async version of `rolling`.
+pub async fn rolling_async<const N: usize>(
+    gpu: &GpuComputeAsync,
+    series: &[f32; N],
+    window: u32,
+    op: RollingOp,
+) -> Vec<f32> {
+    let params = RollingParams::new(N as u32, window, op);
+    let mut pipeline = gpu
+        .gen_pipeline::<[f32; N], RollingParams, [f32; N], 2>(scratchpad_size(N as u32), STAGES)
+        .await;
+    pipeline.write_uniform(&params);
+    pipeline
+        .run(series, params.workgroups(), move |out| {
+            out[..params.windows() as usize].to_vec()
+        })
+        .await
+}
+
+/// Returns the value of `op` over each window of `window` consecutive elements of `series`, so `N - window + 1` values. It is enabled by the `blocking` feature.
+#[cfg(feature = "blocking")]
+pub fn rolling<const N: usize>(
+    gpu: &crate::blocking::GpuCompute,
+    series: &[f32; N],
+    window: u32,
+    op: RollingOp,
+) -> Vec<f32> {
+    pollster::block_on(rolling_async(gpu, series, window, op))
+}
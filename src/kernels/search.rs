@@ -0,0 +1,140 @@
+//! Multi-pattern byte search.
+//!
+//! The haystack is given as bytes, its size must be a multiple of 4 and only its first `SearchParams::len` bytes are searched. Each invocation compares the patterns at one offset of the haystack and appends the matches to the output, counting them even if they don't fit. The matches are appended in no particular order, `Matches::to_sorted_vec` sorts them by offset.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::search::{self, Match, Matches, SearchParams};
+//!
+//! let haystack = *b"GATTACA CATTAG GATTACA!!";
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline::<[u8; 24], SearchParams, Matches<8>, 2>(None, search::STAGES);
+//! pipeline.write_uniform(&SearchParams::new(24, &[b"GATTACA", b"CAT"]));
+//! let matches = pipeline.run(&haystack, search::workgroups(24), |matches| matches.to_sorted_vec());
+//! assert_eq!(matches, [Match::new(0, 0), Match::new(8, 1), Match::new(15, 0)]);
+//! ```
+use crate::StageDesc;
+
+/// WGSL source containing the `clear` and `search` entry points.
+pub const SHADER: &str = sgpu_compute_core::kernels::search::SHADER;
+
+/// Maximum number of patterns of a search.
+pub const MAX_PATTERNS: usize = 16;
+
+/// Maximum length of a pattern in bytes.
+pub const MAX_PATTERN_LEN: usize = 32;
+
+/// The two stages of a search, in order: clearing the count of matches and searching.
+pub const STAGES: [StageDesc; 2] = [
+    StageDesc::new(SHADER, "clear").with_name("search_clear"),
+    StageDesc::new(SHADER, "search").with_name("search"),
+];
+
+/// Returns the workgroups of each stage for a haystack of `len` bytes.
+#[inline]
+pub const fn workgroups(len: usize) -> [(u32, u32, u32); 2] {
+    [(1, 1, 1), ((len as u32).div_ceil(64), 1, 1)]
+}
+
+/// Uniform of the stages, holding the patterns.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct SearchParams {
+    /// Number of bytes searched.
+    pub len: u32,
+    pattern_count: u32,
+    _pad0: u32,
+    _pad1: u32,
+    lengths: [u32; MAX_PATTERNS],
+    bytes: [u8; MAX_PATTERNS * MAX_PATTERN_LEN],
+}
+
+impl SearchParams {
+    /// Parameters searching `patterns` in the first `len` bytes of the haystack. The index of a pattern in `patterns` is reported in its matches, empty patterns never match.
+    ///
+    /// # Panics
+    /// If there are more than `MAX_PATTERNS` patterns or a pattern is longer than `MAX_PATTERN_LEN`.
+    pub fn new(len: u32, patterns: &[&[u8]]) -> Self {
+        assert!(patterns.len() <= MAX_PATTERNS, "Too many patterns");
+        let mut params = Self {
+            len,
+            pattern_count: patterns.len() as u32,
+            _pad0: 0,
+            _pad1: 0,
+            lengths: [0; MAX_PATTERNS],
+            bytes: [0; MAX_PATTERNS * MAX_PATTERN_LEN],
+        };
+        for (p, pattern) in patterns.iter().enumerate() {
+            assert!(
+                pattern.len() <= MAX_PATTERN_LEN,
+                "Pattern {} is too long",
+                p
+            );
+            params.lengths[p] = pattern.len() as u32;
+            params.bytes[p * MAX_PATTERN_LEN..][..pattern.len()].copy_from_slice(pattern);
+        }
+        params
+    }
+
+    /// Number of patterns.
+    #[inline]
+    pub const fn pattern_count(&self) -> usize {
+        self.pattern_count as usize
+    }
+}
+
+/// A match of the pattern `pattern` starting at the byte `offset` of the haystack.
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, bytemuck::Zeroable, bytemuck::Pod,
+)]
+#[repr(C)]
+pub struct Match {
+    pub offset: u32,
+    pub pattern: u32,
+}
+
+impl Match {
+    #[inline]
+    pub const fn new(offset: u32, pattern: u32) -> Self {
+        Self { offset, pattern }
+    }
+}
+
+/// Output of the search holding at most `MAX` matches.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct Matches<const MAX: usize> {
+    count: u32,
+    matches: [Match; MAX],
+}
+
+impl<const MAX: usize> Matches<MAX> {
+    /// Number of matches in the haystack, which can be greater than `MAX`.
+    #[inline]
+    pub const fn count(&self) -> usize {
+        self.count as usize
+    }
+
+    /// Whether some matches were dropped because there are more than `MAX` matches.
+    #[inline]
+    pub const fn overflowed(&self) -> bool {
+        self.count() > MAX
+    }
+
+    /// The matches that fit in the output, in no particular order.
+    #[inline]
+    pub fn as_slice(&self) -> &[Match] {
+        &self.matches[..self.count().min(MAX)]
+    }
+
+    /// The matches that fit in the output, sorted by offset then by pattern.
+    pub fn to_sorted_vec(&self) -> Vec<Match> {
+        let mut matches = self.as_slice().to_vec();
+        matches.sort_unstable();
+        matches
+    }
+}
+
+// SAFETY: the struct is only made of `u32`, so it has no padding.
+unsafe impl<const MAX: usize> bytemuck::Zeroable for Matches<MAX> {}
+unsafe impl<const MAX: usize> bytemuck::Pod for Matches<MAX> {}
@@ -0,0 +1,114 @@
+//! Radix sort of `u32` and `u64` keys.
+//!
+//! The keys are sorted 8 bits at a time, from the least significant ones, so a run is made of a loading stage, 3 stages per pass (`sort_histogram`, `sort_scan` and `sort_scatter`) and a stage writing the sorted keys: `STAGES_U32` makes 4 passes and `STAGES_U64` makes 8 passes. Only the first `SortParams::count` keys of the input are sorted.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::sort::{self, SortParams};
+//!
+//! let params = SortParams::new(5, 2);
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline::<[u64; 5], SortParams, [u64; 5], 26>(params.scratchpad_size(), sort::STAGES_U64);
+//! pipeline.write_uniform(&params);
+//! let sorted = pipeline.run(&[5 << 40, 3, 1 << 33, 0, 3], params.workgroups(), |sorted| *sorted);
+//! assert_eq!(sorted, [0, 3, 3, 1 << 33, 5 << 40]);
+//! ```
+use crate::StageDesc;
+use std::num::NonZeroUsize;
+
+/// WGSL source containing the `sort_load`, `sort_histogram`, `sort_scan`, `sort_scatter` and `write_sorted` entry points.
+pub const SHADER: &str = sgpu_compute_core::kernels::sort::SHADER;
+
+/// Number of keys sorted by a workgroup of the histogram and scatter stages.
+pub const BLOCK: u32 = 256;
+
+/// Stages sorting `u32` keys.
+pub const STAGES_U32: [StageDesc; 14] = stages(SHADER, 1);
+
+/// Stages sorting `u64` keys.
+pub const STAGES_U64: [StageDesc; 26] = stages(SHADER, 2);
+
+/// Returns the loading stage and the passes for keys of `key_words` words of `shader`, the remaining stages being `write_sorted`. Shaders including the radix sort, like the one of `dedup`, replace the remaining stages with their own.
+pub(super) const fn stages<const K: usize>(
+    shader: &'static str,
+    key_words: usize,
+) -> [StageDesc; K] {
+    const fn stage(shader: &'static str, entrypoint: &'static str) -> StageDesc {
+        StageDesc::new(shader, entrypoint).with_name(entrypoint)
+    }
+    // `shader` isn't a constant, so the array is filled by the loop.
+    let mut stages = [const { StageDesc::new("", "main") }; K];
+    let mut i = 0;
+    while i < K {
+        stages[i] = stage(shader, "write_sorted");
+        i += 1;
+    }
+    stages[0] = stage(shader, "sort_load");
+    let mut pass = 0;
+    while pass < 4 * key_words {
+        stages[1 + 3 * pass] = stage(shader, "sort_histogram");
+        stages[2 + 3 * pass] = stage(shader, "sort_scan");
+        stages[3 + 3 * pass] = stage(shader, "sort_scatter");
+        pass += 1;
+    }
+    stages
+}
+
+/// Uniform of the stages.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct SortParams {
+    pub count: u32,
+    key_words: u32,
+    blocks: u32,
+    _pad0: u32,
+}
+
+impl SortParams {
+    /// Parameters sorting `count` keys of `key_words` words, 1 for `u32` keys and 2 for `u64` keys.
+    ///
+    /// # Panics
+    /// If `key_words` is not 1 or 2.
+    #[inline]
+    pub const fn new(count: u32, key_words: u32) -> Self {
+        assert!(
+            key_words == 1 || key_words == 2,
+            "Keys are made of 1 or 2 words"
+        );
+        Self {
+            count,
+            key_words,
+            blocks: count.div_ceil(BLOCK),
+            _pad0: 0,
+        }
+    }
+
+    #[inline]
+    pub const fn key_words(&self) -> u32 {
+        self.key_words
+    }
+
+    /// Number of words of the scratchpad used by the sort.
+    #[inline]
+    pub(super) const fn sort_words(&self) -> usize {
+        4 + 2 * self.count as usize * self.key_words as usize + 256 * self.blocks as usize
+    }
+
+    /// Returns the scratchpad size needed by the stages.
+    #[inline]
+    pub const fn scratchpad_size(&self) -> Option<NonZeroUsize> {
+        NonZeroUsize::new(self.sort_words() * std::mem::size_of::<u32>())
+    }
+
+    /// Workgroups of the loading stage and of the passes, followed by the ones of the writing stage if `K` has room for it.
+    pub fn workgroups<const K: usize>(&self) -> [(u32, u32, u32); K] {
+        let words = (self.count * self.key_words).div_ceil(64);
+        let passes = 4 * self.key_words as usize;
+        std::array::from_fn(|i| match i {
+            0 => (words, 1, 1),
+            i if i <= 3 * passes && i % 3 == 2 => (1, 1, 1),
+            i if i <= 3 * passes => (self.blocks, 1, 1),
+            _ => (words, 1, 1),
+        })
+    }
+}
@@ -0,0 +1,148 @@
+//! Conversions between dense arrays of `f32` and sparse lists of entries.
+//!
+//! A `Sparse` holds `(index, value)` entries. `TO_SPARSE` appends the elements of a dense array which are not the fill value of `SparseParams` with an atomic counter, so the entries are in no particular order and `Sparse::to_sorted_vec` sorts them by index. `TO_DENSE` fills a dense array with the fill value and scatters the entries of a `Sparse` in it; when several entries have the same index, one of them is written.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::sparse::{self, Entry, Sparse, SparseParams};
+//!
+//! let dense = [0.0, 1.5, 0.0, 0.0, -2.0, 0.0];
+//! let params = SparseParams::new(6, 0.0);
+//! let gpu = GpuCompute::new();
+//! let mut to_sparse = gpu.gen_pipeline::<[f32; 6], SparseParams, Sparse<4>, 2>(None, sparse::TO_SPARSE);
+//! to_sparse.write_uniform(&params);
+//! let entries = to_sparse.run(&dense, sparse::to_sparse_workgroups(6), |out| *out);
+//! assert_eq!(entries.to_sorted_vec(), [Entry::new(1, 1.5), Entry::new(4, -2.0)]);
+//!
+//! let mut to_dense = gpu.gen_pipeline::<Sparse<4>, SparseParams, [f32; 6], 2>(None, sparse::TO_DENSE);
+//! to_dense.write_uniform(&params);
+//! assert_eq!(to_dense.run(&entries, sparse::to_dense_workgroups(6, 4), |out| *out), dense);
+//! ```
+use crate::StageDesc;
+
+/// WGSL source containing the `clear` and `compact` entry points.
+pub const TO_SPARSE_SHADER: &str = sgpu_compute_core::kernels::sparse::TO_SPARSE_SHADER;
+
+/// WGSL source containing the `fill` and `scatter` entry points.
+pub const TO_DENSE_SHADER: &str = sgpu_compute_core::kernels::sparse::TO_DENSE_SHADER;
+
+/// Stages converting a dense array to a `Sparse`, in order: clearing the count of entries and appending the entries.
+pub const TO_SPARSE: [StageDesc; 2] = [
+    StageDesc::new(TO_SPARSE_SHADER, "clear").with_name("to_sparse_clear"),
+    StageDesc::new(TO_SPARSE_SHADER, "compact").with_name("to_sparse_compact"),
+];
+
+/// Stages converting a `Sparse` to a dense array, in order: filling the array and scattering the entries.
+pub const TO_DENSE: [StageDesc; 2] = [
+    StageDesc::new(TO_DENSE_SHADER, "fill").with_name("to_dense_fill"),
+    StageDesc::new(TO_DENSE_SHADER, "scatter").with_name("to_dense_scatter"),
+];
+
+/// Returns the workgroups of `TO_SPARSE` for a dense array of `len` elements.
+#[inline]
+pub const fn to_sparse_workgroups(len: u32) -> [(u32, u32, u32); 2] {
+    [(1, 1, 1), (len.div_ceil(64), 1, 1)]
+}
+
+/// Returns the workgroups of `TO_DENSE` for a dense array of `len` elements and at most `entries` entries.
+#[inline]
+pub const fn to_dense_workgroups(len: u32, entries: u32) -> [(u32, u32, u32); 2] {
+    [(len.div_ceil(64), 1, 1), (entries.div_ceil(64), 1, 1)]
+}
+
+/// Uniform of the stages.
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct SparseParams {
+    /// Number of elements of the dense array.
+    pub len: u32,
+    /// Value of the elements without entry.
+    pub fill: f32,
+    _pad0: u32,
+    _pad1: u32,
+}
+
+impl SparseParams {
+    #[inline]
+    pub const fn new(len: u32, fill: f32) -> Self {
+        Self {
+            len,
+            fill,
+            _pad0: 0,
+            _pad1: 0,
+        }
+    }
+}
+
+/// Element of the dense array at `index`.
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct Entry {
+    pub index: u32,
+    pub value: f32,
+}
+
+impl Entry {
+    #[inline]
+    pub const fn new(index: u32, value: f32) -> Self {
+        Self { index, value }
+    }
+}
+
+/// List of at most `MAX` entries.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct Sparse<const MAX: usize> {
+    count: u32,
+    entries: [Entry; MAX],
+}
+
+impl<const MAX: usize> Sparse<MAX> {
+    /// Builds the list from the given entries.
+    ///
+    /// # Panics
+    /// If there are more than `MAX` entries.
+    pub fn from_entries(entries: &[Entry]) -> Self {
+        assert!(entries.len() <= MAX, "More than {} entries", MAX);
+        let mut sparse = Self {
+            count: entries.len() as u32,
+            entries: [Entry::new(0, 0.0); MAX],
+        };
+        sparse.entries[..entries.len()].copy_from_slice(entries);
+        sparse
+    }
+
+    /// Number of entries found by `TO_SPARSE`, which can be greater than `MAX`.
+    #[inline]
+    pub const fn count(&self) -> usize {
+        self.count as usize
+    }
+
+    /// Whether some entries were dropped because there are more than `MAX`.
+    #[inline]
+    pub const fn overflowed(&self) -> bool {
+        self.count() > MAX
+    }
+
+    /// The entries that fit in the list.
+    #[inline]
+    pub fn as_slice(&self) -> &[Entry] {
+        &self.entries[..self.count().min(MAX)]
+    }
+
+    #[inline]
+    pub fn to_vec(&self) -> Vec<Entry> {
+        self.as_slice().to_vec()
+    }
+
+    /// The entries that fit in the list, sorted by index.
+    pub fn to_sorted_vec(&self) -> Vec<Entry> {
+        let mut entries = self.to_vec();
+        entries.sort_unstable_by_key(|entry| entry.index);
+        entries
+    }
+}
+
+// SAFETY: the struct is only made of `u32` and `f32`, so it has no padding.
+unsafe impl<const MAX: usize> bytemuck::Zeroable for Sparse<MAX> {}
+unsafe impl<const MAX: usize> bytemuck::Pod for Sparse<MAX> {}
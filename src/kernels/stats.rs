@@ -0,0 +1,180 @@
+//! Online statistics over a stream of batches.
+//!
+//! `OnlineStats` accumulates the count, the sum, the mean, the variance, the minimum and the maximum of successive batches of `f32` in a persistent GPU buffer. Pushing a batch only submits the stages, nothing is read back until `OnlineStats::finalize` copies the accumulator. Each batch is reduced like in the `map_reduce` module, and the moments are merged with the parallel algorithm of Chan et al., so the variance doesn't suffer from the cancellation of the naive sum of squares.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//!
+//! let gpu = GpuCompute::new();
+//! let mut stats = gpu.online_stats::<1000>();
+//! for batch in 0..10 {
+//!     stats.push(&std::array::from_fn(|i| (batch * 1000 + i) as f32));
+//! }
+//! let statistics = stats.finalize();
+//! assert_eq!(statistics.count, 10_000);
+//! assert_eq!((statistics.min, statistics.max), (0.0, 9999.0));
+//! assert!((statistics.mean - 4999.5).abs() < 1e-2);
+//! assert!((statistics.variance() / 8_333_333.25 - 1.0).abs() < 1e-4);
+//! ```
+use crate::{GpuComputeAsync, PipelineAsync, StageDesc};
+use std::num::NonZeroUsize;
+
+/// WGSL source containing the `partial_moments` and `accumulate` entry points.
+pub const SHADER: &str = sgpu_compute_core::kernels::stats::SHADER;
+
+/// Number of invocations of a workgroup, and maximum number of workgroups of the first stage.
+const WORKGROUP_SIZE: u32 = 256;
+
+/// Stages accumulating a batch, see the module documentation.
+pub const STAGES: [StageDesc; 2] = [
+    StageDesc::new(SHADER, "partial_moments").with_name("stats_partial_moments"),
+    StageDesc::new(SHADER, "accumulate").with_name("stats_accumulate"),
+];
+
+/// Uniform of the stages.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct StatsParams {
+    /// Number of values of the batch.
+    pub len: u32,
+    reset: u32,
+    _pad0: u32,
+    _pad1: u32,
+}
+
+impl StatsParams {
+    /// Parameters of a batch of `len` values, the accumulator is cleared before the batch if `reset` is set.
+    #[inline]
+    pub const fn new(len: u32, reset: bool) -> Self {
+        Self {
+            len,
+            reset: reset as u32,
+            _pad0: 0,
+            _pad1: 0,
+        }
+    }
+
+    /// Workgroups of `STAGES`.
+    #[inline]
+    pub const fn workgroups(&self) -> [(u32, u32, u32); 2] {
+        let first = self.len.div_ceil(WORKGROUP_SIZE);
+        let first = if first < WORKGROUP_SIZE {
+            first
+        } else {
+            WORKGROUP_SIZE
+        };
+        [(first, 1, 1), (1, 1, 1)]
+    }
+}
+
+/// Returns the scratchpad size of `STAGES`, which holds the moments of each workgroup.
+#[inline]
+pub const fn scratchpad_size() -> Option<NonZeroUsize> {
+    NonZeroUsize::new(WORKGROUP_SIZE as usize * std::mem::size_of::<Statistics>())
+}
+
+/// Statistics of the accumulated values, the output of `STAGES`.
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct Statistics {
+    pub count: u32,
+    pub sum: f32,
+    pub mean: f32,
+    /// Sum of the squared differences to the mean.
+    pub m2: f32,
+    /// Infinity if no value was accumulated.
+    pub min: f32,
+    /// Negative infinity if no value was accumulated.
+    pub max: f32,
+}
+
+impl Statistics {
+    /// Population variance, NaN if no value was accumulated.
+    #[inline]
+    pub fn variance(&self) -> f32 {
+        self.m2 / self.count as f32
+    }
+
+    /// Sample variance, with Bessel's correction. NaN if less than 2 values were accumulated.
+    #[inline]
+    pub fn sample_variance(&self) -> f32 {
+        if self.count < 2 {
+            return f32::NAN;
+        }
+        self.m2 / (self.count - 1) as f32
+    }
+
+    /// Population standard deviation.
+    #[inline]
+    pub fn std_dev(&self) -> f32 {
+        self.variance().sqrt()
+    }
+}
+
+/// Accumulator of statistics over batches of `N` values, see the module documentation.
+pub struct OnlineStats<'a, const N: usize> {
+    pipeline: PipelineAsync<'a, [f32; N], StatsParams, Statistics, 2>,
+    reset: bool,
+}
+
+impl<const N: usize> OnlineStats<'_, N> {
+    /// Accumulates the values of `batch`. The stages are only submitted, the GPU runs them in order with the next batches.
+    #[inline]
+    pub fn push(&mut self, batch: &[f32; N]) {
+        self.push_prefix(batch, N as u32);
+    }
+
+    /// Same as `OnlineStats::push`, but only the first `len` values of `batch` are accumulated.
+    ///
+    /// # Panics
+    /// If `len` is larger than `N`.
+    pub fn push_prefix(&mut self, batch: &[f32; N], len: u32) {
+        assert!(len as usize <= N, "The batch only has {} values", N);
+        let params = StatsParams::new(len, self.reset);
+        self.reset = false;
+        self.pipeline.write_uniform(&params);
+        self.pipeline.dispatch(batch, params.workgroups());
+    }
+
+    /// Clears the accumulator, the next batches start a new series.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.reset = true;
+    }
+
+    /// Async version of `OnlineStats::finalize`.
+    pub async fn finalize_async(&mut self) -> Statistics {
+        let params = StatsParams::new(0, self.reset);
+        self.reset = false;
+        self.pipeline.write_uniform(&params);
+        self.pipeline
+            .run(&[0.0; N], params.workgroups(), |out| *out)
+            .await
+    }
+
+    /// Waits for the pushed batches and returns the statistics of all the values accumulated since the creation or the last reset. The accumulator is kept, so more batches can be pushed. It is enabled by the `blocking` feature.
+    #[cfg(feature = "blocking")]
+    #[inline]
+    pub fn finalize(&mut self) -> Statistics {
+        pollster::block_on(self.finalize_async())
+    }
+}
+
+impl GpuComputeAsync {
+    /// This method is used to create an accumulator of statistics over batches of `N` values, see the `stats` module.
+    pub async fn online_stats<const N: usize>(&self) -> OnlineStats<'_, N> {
+        OnlineStats {
+            pipeline: self.gen_pipeline(scratchpad_size(), STAGES).await,
+            reset: true,
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl crate::blocking::GpuCompute {
+    /// Blocking version of `GpuComputeAsync::online_stats`.
+    #[inline]
+    pub fn online_stats<const N: usize>(&self) -> OnlineStats<'_, N> {
+        pollster::block_on((**self).online_stats())
+    }
+}
@@ -0,0 +1,57 @@
+//! Tone mapping of HDR `f32` buffers to equalized `u8` values.
+//!
+//! The pipeline is made of four stages sharing the scratchpad:
+//!     - `clear` resets the histogram, the scratchpad isn't cleared between runs
+//!     - `histogram` applies the exposure, Reinhard tone mapping and counts the values in 256 bins
+//!     - `cdf` computes the cumulative histogram
+//!     - `map` equalizes each value with the cumulative histogram and packs them as `u8`
+//!
+//! The number of values must be a multiple of 4 since each invocation of `map` writes 4 packed `u8`.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::tonemap::{self, ToneMapParams};
+//!
+//! const N: usize = 1024;
+//!
+//! let hdr: [f32; N] = std::array::from_fn(|i| (i as f32 / 100.0).exp());
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline::<[f32; N], ToneMapParams, [u8; N], 4>(tonemap::SCRATCHPAD_SIZE, tonemap::STAGES);
+//! pipeline.write_uniform(&ToneMapParams { exposure: 1.0 });
+//! let ldr = pipeline.run(&hdr, tonemap::workgroups(N), |ldr| *ldr);
+//! assert_eq!(ldr[N - 1], 255);
+//! ```
+use crate::StageDesc;
+use std::num::NonZeroUsize;
+
+/// WGSL source containing the `clear`, `histogram`, `cdf` and `map` entry points.
+pub const SHADER: &str = sgpu_compute_core::kernels::tonemap::SHADER;
+
+/// Scratchpad needed by the stages: the histogram, the cumulative histogram and its minimum.
+pub const SCRATCHPAD_SIZE: Option<NonZeroUsize> = NonZeroUsize::new((2 * 256 + 1) * 4);
+
+/// The four stages of the equalization, in order.
+pub const STAGES: [StageDesc; 4] = [
+    StageDesc::new(SHADER, "clear").with_name("tonemap_clear"),
+    StageDesc::new(SHADER, "histogram").with_name("tonemap_histogram"),
+    StageDesc::new(SHADER, "cdf").with_name("tonemap_cdf"),
+    StageDesc::new(SHADER, "map").with_name("tonemap_map"),
+];
+
+/// Returns the workgroups of each stage for `n` values.
+#[inline]
+pub const fn workgroups(n: usize) -> [(u32, u32, u32); 4] {
+    [
+        (3, 1, 1),
+        ((n as u32).div_ceil(256), 1, 1),
+        (1, 1, 1),
+        ((n as u32 / 4).div_ceil(64), 1, 1),
+    ]
+}
+
+/// Uniform of the stages. Values are multiplied by `exposure` before tone mapping.
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct ToneMapParams {
+    pub exposure: f32,
+}
@@ -0,0 +1,260 @@
+//! Elementwise combination of two arrays of `f32`.
+//!
+//! `GpuComputeAsync::zip_map` combines the elements of `a` and `b` at the same index with a WGSL expression of `a` and `b`, e.g. `"a + b"` or `"max(a, b)"`, the elementwise counterpart of `map_reduce`. A `ZipMapAsync` keeps the compiled expression for arrays of a given size, and `ZipMapAsync::apply_buffers` combines buffers already on the GPU into another one, e.g. the outputs of other pipelines, so composed operations are chained without any readback.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::kernels::zip_map::ZipMap;
+//!
+//! let a: Vec<f32> = (0..1000).map(|i| i as f32).collect();
+//! let b = vec![2.0; 1000];
+//! let gpu = GpuCompute::new();
+//! assert_eq!(gpu.zip_map(&a, &b, "a + b")[10], 12.0);
+//!
+//! let mut product = ZipMap::new(&gpu, "a * b", 1000);
+//! assert_eq!(product.apply(&a, &b)[10], 20.0);
+//! ```
+use crate::{BufferSizes, Buffers, CompiledStages, GpuComputeAsync, GpuRef, StageDesc};
+use std::sync::Arc;
+
+/// WGSL source of the `zip_map` entry point, without the declaration generated by `shader`.
+pub const SHADER: &str = sgpu_compute_core::kernels::zip_map::SHADER;
+
+/// Number of invocations of a workgroup.
+const WORKGROUP_SIZE: u32 = 256;
+
+/// Maximum number of workgroups, the invocations loop over the elements.
+const MAX_WORKGROUPS: u32 = 4096;
+
+/// Key of the shader given to the shader source provider of the pipeline.
+const SHADER_KEY: &str = "zip_map";
+
+const STAGES: [StageDesc; 1] = [StageDesc::new(SHADER_KEY, "zip_map").with_name("zip_map")];
+
+/// Returns the complete shader combining each pair of elements `a` and `b` with `zip_expr_wgsl`.
+pub fn shader(zip_expr_wgsl: &str) -> String {
+    format!(
+        "fn zip_element(a: f32, b: f32) -> f32 {{\n    return {};\n}}\n\n{}",
+        zip_expr_wgsl, SHADER
+    )
+}
+
+/// Expression compiled to combine arrays of `size` elements, see the module documentation.
+pub struct ZipMapAsync<'a> {
+    buffers: Buffers,
+    stages: CompiledStages<1>,
+    size: usize,
+    device: GpuRef<'a>,
+}
+
+impl<'a> ZipMapAsync<'a> {
+    /// Compiles `zip_expr_wgsl` for arrays of `size` elements.
+    ///
+    /// # Panics
+    /// If `size` is zero or if the expression doesn't compile.
+    pub async fn new(gpu: &'a GpuComputeAsync, zip_expr_wgsl: &str, size: usize) -> Self {
+        assert!(size > 0, "The arrays must not be empty");
+        let sizes = BufferSizes {
+            uniform: std::mem::size_of::<u32>(),
+            scratchpad: None,
+            scratchpads: Vec::new(),
+            input: 2 * size * std::mem::size_of::<f32>(),
+            output: size * std::mem::size_of::<f32>(),
+            extra_outputs: Vec::new(),
+            push_constants: 0,
+        };
+        let source = shader(zip_expr_wgsl);
+        let stages = gpu
+            .compile_stages(
+                &sizes,
+                STAGES,
+                Some(Arc::new(move |_: &str| source.clone())),
+            )
+            .await
+            .unwrap_or_else(|error| panic!("{}", error));
+        let buffers = gpu.create_buffers(&sizes, &stages.bindgroup_layout, gpu.zero_init);
+        gpu.queue.write_buffer(
+            buffers.uniform.as_ref().expect("The stage has a uniform"),
+            0,
+            bytemuck::bytes_of(&(size as u32)),
+        );
+        Self {
+            buffers,
+            stages,
+            size,
+            device: GpuRef::Borrowed(gpu),
+        }
+    }
+
+    /// Number of elements of the arrays.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Combines `a` and `b` and reads back the result.
+    ///
+    /// # Panics
+    /// If `a` or `b` doesn't have `ZipMapAsync::size` elements.
+    pub async fn apply(&mut self, a: &[f32], b: &[f32]) -> Vec<f32> {
+        for values in [a, b] {
+            assert_eq!(
+                values.len(),
+                self.size,
+                "Expected {} elements, got {}",
+                self.size,
+                values.len()
+            );
+        }
+        let input = self.buffers.input.as_ref().expect("The input isn't empty");
+        let queue = &self.device.queue;
+        queue.write_buffer(input, 0, bytemuck::cast_slice(a));
+        queue.write_buffer(
+            input,
+            std::mem::size_of_val(a) as u64,
+            bytemuck::cast_slice(b),
+        );
+        let output = &self.buffers.output;
+        self.submit(None, Some(output));
+        let (sender, receiver) = flume::bounded(1);
+        output.slice(..).map_async(wgpu::MapMode::Read, move |e| {
+            e.expect("Could not map buffer");
+            sender.send(()).unwrap()
+        });
+        self.device.wait_submitted();
+        receiver.recv_async().await.expect("Error with channel");
+        let values = bytemuck::cast_slice(output.slice(..).get_mapped_range().as_ref()).to_vec();
+        output.unmap();
+        values
+    }
+
+    /// Combines the first `ZipMapAsync::size` values of `a` and `b` into `out` on the GPU, without waiting nor reading anything back. The same buffer may be given several times, e.g. `out` may be `a` to update it in place.
+    ///
+    /// # Panics
+    /// If `a` or `b` doesn't have the `COPY_SRC` usage, if `out` doesn't have the `COPY_DST` usage, or if one of them has less than `ZipMapAsync::size` values.
+    pub fn apply_buffers(&mut self, a: &wgpu::Buffer, b: &wgpu::Buffer, out: &wgpu::Buffer) {
+        let size = (self.size * std::mem::size_of::<f32>()) as u64;
+        for (buffer, usage) in [
+            (a, wgpu::BufferUsages::COPY_SRC),
+            (b, wgpu::BufferUsages::COPY_SRC),
+            (out, wgpu::BufferUsages::COPY_DST),
+        ] {
+            assert!(
+                buffer.usage().contains(usage),
+                "The buffers must have the {:?} usage",
+                usage
+            );
+            assert!(
+                buffer.size() >= size,
+                "The buffers must have at least {} values",
+                self.size
+            );
+        }
+        self.submit(Some((a, b)), Some(out));
+    }
+
+    /// Submits the copy of `sources` into the input if there are some, the stage, and the copy of the result into `destination` if there is one.
+    fn submit(
+        &self,
+        sources: Option<(&wgpu::Buffer, &wgpu::Buffer)>,
+        destination: Option<&wgpu::Buffer>,
+    ) {
+        let buffers = &self.buffers;
+        let size = (self.size * std::mem::size_of::<f32>()) as u64;
+        let mut encoder =
+            self.device
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Zip map"),
+                });
+        if let Some((a, b)) = sources {
+            let input = buffers.input.as_ref().expect("The input isn't empty");
+            encoder.copy_buffer_to_buffer(a, 0, input, 0, size);
+            encoder.copy_buffer_to_buffer(b, 0, input, size, size);
+        }
+        let groups = (self.size as u32)
+            .div_ceil(WORKGROUP_SIZE)
+            .min(MAX_WORKGROUPS);
+        self.stages.record_passes(
+            &mut encoder,
+            &buffers.bindgroup,
+            &self.device,
+            &[(groups, 1, 1)],
+        );
+        if let Some(destination) = destination {
+            encoder.copy_buffer_to_buffer(&buffers.staging, 0, destination, 0, size);
+        }
+        self.device.queue.submit(Some(encoder.finish()));
+    }
+}
+
+impl GpuComputeAsync {
+    /// This method is used to combine the elements of `a` and `b` at the same index with the WGSL expression `zip_expr_wgsl` of `a` and `b`, e.g. `"a + b"`, see the `zip_map` module. The shader is compiled on each call, use a `ZipMapAsync` to combine several pairs of arrays. Returns an empty vector for empty arrays.
+    ///
+    /// # Panics
+    /// If `a` and `b` don't have the same length.
+    pub async fn zip_map(&self, a: &[f32], b: &[f32], zip_expr_wgsl: &str) -> Vec<f32> {
+        assert_eq!(
+            a.len(),
+            b.len(),
+            "The arrays have {} and {} elements",
+            a.len(),
+            b.len()
+        );
+        if a.is_empty() {
+            return Vec::new();
+        }
+        ZipMapAsync::new(self, zip_expr_wgsl, a.len())
+            .await
+            .apply(a, b)
+            .await
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl crate::blocking::GpuCompute {
+    /// Blocking version of `GpuComputeAsync::zip_map`.
+    #[inline]
+    pub fn zip_map(&self, a: &[f32], b: &[f32], zip_expr_wgsl: &str) -> Vec<f32> {
+        pollster::block_on((**self).zip_map(a, b, zip_expr_wgsl))
+    }
+}
+
+/// Blocking version of `ZipMapAsync`, it is enabled by the `blocking` feature.
+#[cfg(feature = "blocking")]
+pub struct ZipMap<'a>(ZipMapAsync<'a>);
+
+#[cfg(feature = "blocking")]
+impl<'a> ZipMap<'a> {
+    /// Blocking version of `ZipMapAsync::new`.
+    pub fn new(gpu: &'a crate::blocking::GpuCompute, zip_expr_wgsl: &str, size: usize) -> Self {
+        Self(pollster::block_on(ZipMapAsync::new(
+            gpu,
+            zip_expr_wgsl,
+            size,
+        )))
+    }
+
+    /// Blocking version of `ZipMapAsync::apply`.
+    pub fn apply(&mut self, a: &[f32], b: &[f32]) -> Vec<f32> {
+        pollster::block_on(self.0.apply(a, b))
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<'a> std::ops::Deref for ZipMap<'a> {
+    type Target = ZipMapAsync<'a>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl std::ops::DerefMut for ZipMap<'_> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
@@ -7,6 +7,8 @@
 //!     - `in` for the input buffer
 //!     - `out` for the output buffer
 //! Their types are inferred from the `run` method. The `scratchpad` buffer is also available, but it is not required.
+//! It is bound after the uniform, and `GpuComputeAsync::gen_pipeline_with_scratchpads` binds several scratchpads at consecutive bindings in its place.
+//! The `uniform` and `in` bindings are skipped when their type is zero-sized (e.g. `()`), the following bindings are then shifted down.
 //!
 //! ## Example
 //! ```
@@ -31,11 +33,7 @@
 //! let gpu = GpuCompute::new();
 //! let mut pipeline = gpu.gen_pipeline(
 //!        None,
-//!        [StageDesc {
-//!            name: Some("norm"),
-//!            shader: my_shader,
-//!            entrypoint: "main",
-//!        }],
+//!        [StageDesc::new(my_shader, "main").with_name("norm")],
 //!    );
 //!
 //! const COEFFICIENT: u32 = 42;
@@ -46,13 +44,72 @@
 //! let result_cpu = input.map(|v| v * COEFFICIENT);
 //! assert_eq!(result_gpu, result_cpu);
 //! ```
-use std::{borrow::Cow, marker::PhantomData, num::NonZeroUsize};
+use error::SgpuError;
+use options::{GpuComputeBuilder, GpuComputeOptions, PollStrategy, ZeroInit};
+use provider::ShaderSourceProvider;
+use sgpu_compute_core::layout::{buffer_layout, BufferSizes};
+use std::{
+    borrow::Cow,
+    marker::PhantomData,
+    num::NonZeroUsize,
+    ops::Deref,
+    sync::{Arc, OnceLock},
+};
 use wgpu::{util::DownloadBuffer, Device, Queue};
 
 #[cfg(feature = "blocking")]
 pub mod blocking;
 
+pub mod adapter;
+pub mod alias;
+pub mod append;
+pub mod batch;
+pub mod binding;
+pub mod budget;
+pub mod change;
+pub mod checkpoint;
+pub mod describe;
+mod diagnostics;
+pub mod dynamic;
+pub mod error;
+pub mod eval;
+#[cfg(feature = "blocking")]
+pub mod executor;
+#[cfg(feature = "csv")]
+pub mod export;
+#[cfg(feature = "blocking")]
+pub mod failover;
+pub mod float_cmp;
+pub mod gradient;
+pub mod graph;
+
+pub mod kernels;
+pub mod lint;
+pub mod minify;
+pub mod multi;
+pub mod options;
+pub mod pool;
 pub mod prelude;
+pub mod preprocess;
+pub mod profile;
+pub mod provider;
+pub mod recovery;
+pub mod scope;
+pub mod seed;
+pub mod serialize;
+pub mod shard;
+pub mod shared;
+#[cfg(all(feature = "shm", unix))]
+pub mod shm;
+pub mod spec;
+mod specialize;
+pub mod testgen;
+pub mod trace;
+pub mod view;
+#[cfg(feature = "vulkan")]
+pub mod vulkan;
+#[cfg(feature = "blocking")]
+pub mod worker;
 
 /// This struct represents a pipeline. It is used to run async compute shaders. To build it use the `gen_pipeline` method of the `GpuComputeAsync` struct.
 pub struct PipelineAsync<
@@ -62,57 +119,461 @@ pub struct PipelineAsync<
     Output: bytemuck::Pod,
     const N: usize,
 > {
+    buffers: Buffers,
+    stages: Arc<CompiledStages<N>>,
+    sizes: BufferSizes,
+    /// Push constants set in each pass, empty if the stages have none.
+    push_constants: Vec<u8>,
+    validator: Option<InputValidator<Input>>,
+    tracing: Option<trace::Tracing>,
+    zero_init: ZeroInit,
+    change_detection: Option<change::ChangeDetection>,
+    /// Uniforms written with `PipelineAsync::write_uniforms`.
+    uniforms: Option<batch::UniformBatch>,
+    device: GpuRef<'a>,
+    _phantom: PhantomData<(Input, Uniform, Output)>,
+}
+
+/// Pipeline owning a reference to its device, so it can be stored next to the device or moved into a thread or a task, see `GpuComputeAsync::gen_pipeline_owned`.
+pub type OwnedPipelineAsync<Input, Uniform, Output, const N: usize> =
+    PipelineAsync<'static, Input, Uniform, Output, N>;
+
+/// Device of a pipeline, borrowed by the pipelines of `GpuComputeAsync::gen_pipeline` and shared by the ones of `GpuComputeAsync::gen_pipeline_owned`.
+#[derive(Clone)]
+enum GpuRef<'a> {
+    Borrowed(&'a GpuComputeAsync),
+    Shared(Arc<dyn SharedGpu>),
+}
+
+/// Device which can be shared by owned pipelines, the blocking `GpuCompute` derefs to the `GpuComputeAsync` it wraps.
+pub(crate) trait SharedGpu: Send + Sync {
+    fn gpu(&self) -> &GpuComputeAsync;
+}
+
+impl SharedGpu for GpuComputeAsync {
+    #[inline]
+    fn gpu(&self) -> &GpuComputeAsync {
+        self
+    }
+}
+
+impl Deref for GpuRef<'_> {
+    type Target = GpuComputeAsync;
+
+    #[inline]
+    fn deref(&self) -> &GpuComputeAsync {
+        match self {
+            GpuRef::Borrowed(gpu) => gpu,
+            GpuRef::Shared(gpu) => gpu.gpu(),
+        }
+    }
+}
+
+/// Closure checking the input before it is uploaded, see `PipelineAsync::set_input_validator`.
+type InputValidator<Input> = Arc<dyn Fn(&Input) -> Result<(), String> + Send + Sync>;
+
+/// Buffers owned by a single pipeline and the bind group binding them.
+pub(crate) struct Buffers {
     uniform: Option<wgpu::Buffer>,
-    input: wgpu::Buffer,
-    scratchpad: Option<wgpu::Buffer>,
+    input: Option<wgpu::Buffer>,
+    /// Shared with other pipelines when it is aliased, see the `alias` module.
+    scratchpad: Option<Arc<wgpu::Buffer>>,
+    /// Named scratchpads bound after the scratchpad.
+    scratchpads: Vec<wgpu::Buffer>,
     staging: wgpu::Buffer,
     output: wgpu::Buffer,
+    /// Storage and readback buffers of the outputs bound after the output.
+    extra_outputs: Vec<(wgpu::Buffer, wgpu::Buffer)>,
     bindgroup: wgpu::BindGroup,
-    stages: [wgpu::ComputePipeline; N],
-    device: &'a GpuComputeAsync,
-    stages_desc: [StageDesc; N],
-    _phantom: PhantomData<(Input, Uniform, Output)>,
+    /// Uniform of each stage written with `PipelineAsync::write_uniform_for_stage`.
+    stage_uniforms: Vec<Option<StageUniform>>,
+}
+
+/// Uniform of a stage written with `PipelineAsync::write_uniform_for_stage`.
+struct StageUniform {
+    buffer: wgpu::Buffer,
+    /// Binds `buffer` instead of the uniform.
+    bindgroup: wgpu::BindGroup,
+    /// Last value written, kept to write it again when the pipeline is rebuilt.
+    value: Vec<u8>,
+}
+
+/// Buffers bound in the group 0, in binding order.
+fn bound_buffers<'b>(
+    uniform: Option<&'b wgpu::Buffer>,
+    scratchpad: Option<&'b wgpu::Buffer>,
+    scratchpads: &'b [wgpu::Buffer],
+    input: Option<&'b wgpu::Buffer>,
+    staging: &'b wgpu::Buffer,
+    extra_outputs: &'b [(wgpu::Buffer, wgpu::Buffer)],
+) -> impl Iterator<Item = &'b wgpu::Buffer> {
+    uniform
+        .into_iter()
+        .chain(scratchpad)
+        .chain(scratchpads)
+        .chain(input)
+        .chain(std::iter::once(staging))
+        .chain(extra_outputs.iter().map(|(staging, _)| staging))
+}
+
+impl Buffers {
+    /// Clears the scratchpad and the output, and the input if `input` is set.
+    fn clear(&self, encoder: &mut wgpu::CommandEncoder, input: bool) {
+        let input = self.input.as_ref().filter(|_| input);
+        for buffer in input
+            .into_iter()
+            .chain(self.scratchpad.as_deref())
+            .chain(&self.scratchpads)
+            .chain(Some(&self.staging))
+            .chain(self.extra_outputs.iter().map(|(staging, _)| staging))
+        {
+            encoder.clear_buffer(buffer, 0, None);
+        }
+    }
+
+    /// Recreates the bind groups after a buffer was replaced.
+    fn rebind(&mut self, gpu: &GpuComputeAsync, bindgroup_layout: &wgpu::BindGroupLayout) {
+        self.bindgroup = gpu.create_bindgroup(
+            bindgroup_layout,
+            bound_buffers(
+                self.uniform.as_ref(),
+                self.scratchpad.as_deref(),
+                &self.scratchpads,
+                self.input.as_ref(),
+                &self.staging,
+                &self.extra_outputs,
+            ),
+        );
+        for stage in self.stage_uniforms.iter_mut().flatten() {
+            stage.bindgroup = gpu.create_bindgroup(
+                bindgroup_layout,
+                bound_buffers(
+                    Some(&stage.buffer),
+                    self.scratchpad.as_deref(),
+                    &self.scratchpads,
+                    self.input.as_ref(),
+                    &self.staging,
+                    &self.extra_outputs,
+                ),
+            );
+        }
+    }
+
+    /// Bind group of the stage at `index`, binding its own uniform if it has one.
+    fn stage_bindgroup(&self, index: usize) -> &wgpu::BindGroup {
+        match self.stage_uniforms.get(index) {
+            Some(Some(stage)) => &stage.bindgroup,
+            _ => &self.bindgroup,
+        }
+    }
+}
+
+/// Compiled stages, shared between a pipeline and its clones. The layout and the compute pipelines are reference counted, so replacing a stage only compiles this stage.
+pub(crate) struct CompiledStages<const N: usize> {
+    bindgroup_layout: Arc<wgpu::BindGroupLayout>,
+    pipelines: [Arc<wgpu::ComputePipeline>; N],
+    desc: [StageDesc; N],
+    provider: Option<Arc<dyn ShaderSourceProvider>>,
+    /// Whether the stages bind the shared uniform of the device.
+    shared_uniform: bool,
+    /// Size of the push constants of the stages, 0 if they have none.
+    push_constants: u32,
+    /// Stages binding the uniform with a dynamic offset, compiled on the first batch of uniforms, see the `batch` module.
+    batched: OnceLock<batch::BatchedStages>,
 }
 
-pub struct StageDesc {
-    pub name: Option<&'static str>,
-    pub shader: &'static str,
-    pub entrypoint: &'static str,
+impl<const N: usize> CompiledStages<N> {
+    /// Pass of the stage at `index`, binding `bindgroup` and setting `push_constants`.
+    fn pass<'p>(
+        &'p self,
+        index: usize,
+        bindgroup: &'p wgpu::BindGroup,
+        push_constants: &'p [u8],
+    ) -> StagePass<'p> {
+        StagePass {
+            pipeline: &self.pipelines[index],
+            bindgroup,
+            push_constants,
+            shared_uniform: self.shared_uniform,
+            name: self.desc[index].name,
+            index,
+        }
+    }
+
+    /// Records one compute pass per stage with `StagePass::record`, binding `bindgroup`, for the pipelines without push constants, run labels nor timestamps. The encoder is submitted and replaced when it reaches the maximum number of passes of `gpu`.
+    pub(crate) fn record_passes(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        bindgroup: &wgpu::BindGroup,
+        gpu: &GpuComputeAsync,
+        workgroups: &[(u32, u32, u32); N],
+    ) {
+        for (i, workgroup) in workgroups.iter().enumerate() {
+            gpu.split_submission(encoder, i, None);
+            self.pass(i, bindgroup, &[])
+                .record(encoder, gpu, *workgroup, None, None, None);
+        }
+    }
 }
 
+pub use sgpu_compute_core::{ScratchpadDesc, ShaderSource, StageDesc};
+
 /// This is the main struct of the library. It is used to create pipelines and run them. It requires an async runtime to work. If you want a blocking version, you can use the `GpuCompute` struct. If you don't use the blocking version disable default features.
 pub struct GpuComputeAsync {
-    device: Device,
-    queue: Queue,
+    // Declared first, so that the polling thread is stopped before the device is dropped.
+    _poller: Option<options::BackgroundPoller>,
+    device: Arc<Device>,
+    queue: Arc<Queue>,
+    poll_strategy: PollStrategy,
+    zero_init: ZeroInit,
+    max_passes_per_submission: Option<NonZeroUsize>,
+    shared_uniform: OnceLock<shared::SharedUniform>,
+    lint: Option<lint::Callback>,
+    adapter_info: Option<wgpu::AdapterInfo>,
+    /// Options the device was created with, `None` if it was given with `GpuComputeAsync::from_device`.
+    builder: Option<GpuComputeBuilder>,
+    device_lost: Arc<recovery::DeviceLostState>,
 }
 
 impl GpuComputeAsync {
     /// This method is used to create a new instance of the `GpuComputeAsync` struct.
+    ///
+    /// # Panics
+    /// If no GPU is available or if the device can't be created, see `GpuComputeAsync::try_new` to handle these errors.
     pub async fn new() -> Self {
-        let instance = wgpu::Instance::default();
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                compatible_surface: None,
-                force_fallback_adapter: false,
-            })
+        Self::with_options(GpuComputeOptions::default()).await
+    }
+
+    /// Same as `GpuComputeAsync::new`, but with the given options.
+    pub async fn with_options(options: GpuComputeOptions) -> Self {
+        Self::try_with_options(options)
             .await
-            .expect("GPU not available.");
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Same as `GpuComputeAsync::new`, but returns `SgpuError::NoAdapter` if no GPU is available and `SgpuError::DeviceRequestFailed` if the device can't be created instead of panicking, e.g. to fall back to a CPU path.
+    pub async fn try_new() -> Result<Self, SgpuError> {
+        Self::try_with_options(GpuComputeOptions::default()).await
+    }
+
+    /// Same as `GpuComputeAsync::try_new`, but with the given options.
+    pub async fn try_with_options(options: GpuComputeOptions) -> Result<Self, SgpuError> {
+        Self::builder().options(options).build_async().await
+    }
+
+    /// This method is used to configure the adapter and the device, see `GpuComputeBuilder`.
+    #[inline]
+    pub fn builder() -> GpuComputeBuilder {
+        GpuComputeBuilder::default()
+    }
 
+    /// Creates the device and the queue on the given adapter.
+    pub(crate) async fn from_adapter(
+        adapter: &wgpu::Adapter,
+        builder: &GpuComputeBuilder,
+    ) -> Result<Self, SgpuError> {
+        builder.check_adapter(adapter)?;
+        let options = &builder.options;
+        let info = adapter.get_info();
+        diagnostics::log_debug!(
+            "Selected adapter {} ({:?}, {:?}, driver {} {})",
+            info.name,
+            info.backend,
+            info.device_type,
+            info.driver,
+            info.driver_info
+        );
+        if info.device_type == wgpu::DeviceType::Cpu {
+            diagnostics::log_warn!(
+                "Adapter {} is a software rasterizer, the shaders run on the CPU",
+                info.name
+            );
+        }
+        let optional_features = builder.optional_features;
+        let features = adapter.features() & optional_features;
+        if features != optional_features {
+            diagnostics::log_debug!(
+                "Adapter doesn't support {:?}, the features depending on them are disabled",
+                optional_features - features
+            );
+        }
+        let features = features | builder.required_features;
+        let mut limits = builder.required_limits.clone();
+        if features.contains(wgpu::Features::PUSH_CONSTANTS) && limits.max_push_constant_size == 0 {
+            limits.max_push_constant_size = adapter.limits().max_push_constant_size;
+        }
+        diagnostics::log_debug!(
+            "Creating the device with {:?}, the {:?} poll strategy and {:?} zero-initialization",
+            features,
+            options.poll_strategy,
+            options.zero_init
+        );
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
                     label: None,
-                    required_features: wgpu::Features::PIPELINE_STATISTICS_QUERY
-                        | wgpu::Features::TIMESTAMP_QUERY,
-                    required_limits: wgpu::Limits::downlevel_defaults(),
+                    required_features: features,
+                    required_limits: limits,
+                    memory_hints: wgpu::MemoryHints::default(),
                 },
                 None,
             )
             .await
-            .unwrap();
+            .map_err(|error| SgpuError::DeviceRequestFailed(error.to_string()))?;
+        let mut gpu = Self::from_parts(Arc::new(device), Arc::new(queue), options, Some(info));
+        gpu.device_lost.watch(&gpu.device);
+        gpu.builder = Some(builder.clone());
+        Ok(gpu)
+    }
+
+    /// This method is used to create a `GpuComputeAsync` on an existing device and its queue, e.g. the ones of a renderer, instead of creating a second device. Owned handles and `Arc`s are both accepted. The device is polled according to the default options, so runs with `PollStrategy::Blocking` also wait for the work submitted by the renderer. Its device lost callback is left to the application, unless `GpuComputeAsync::set_device_lost_handler` is called, which replaces it.
+    #[inline]
+    pub fn from_device(device: impl Into<Arc<Device>>, queue: impl Into<Arc<Queue>>) -> Self {
+        Self::from_device_with_options(device, queue, GpuComputeOptions::default())
+    }
+
+    /// Same as `GpuComputeAsync::from_device`, but with the given options.
+    pub fn from_device_with_options(
+        device: impl Into<Arc<Device>>,
+        queue: impl Into<Arc<Queue>>,
+        options: GpuComputeOptions,
+    ) -> Self {
+        Self::from_parts(device.into(), queue.into(), &options, None)
+    }
+
+    fn from_parts(
+        device: Arc<Device>,
+        queue: Arc<Queue>,
+        options: &GpuComputeOptions,
+        adapter_info: Option<wgpu::AdapterInfo>,
+    ) -> Self {
+        let poller = match options.poll_strategy {
+            PollStrategy::Background(interval) => Some(options::BackgroundPoller::spawn(
+                Arc::clone(&device),
+                interval,
+            )),
+            PollStrategy::Blocking | PollStrategy::OnDemand => None,
+        };
+        Self {
+            _poller: poller,
+            device,
+            queue,
+            poll_strategy: options.poll_strategy,
+            zero_init: options.zero_init,
+            max_passes_per_submission: options.max_passes_per_submission,
+            shared_uniform: OnceLock::new(),
+            lint: None,
+            adapter_info,
+            builder: None,
+            device_lost: Arc::default(),
+        }
+    }
+
+    /// Info of the adapter the device was created on, `None` if it was created with `GpuComputeAsync::from_device`.
+    #[inline]
+    pub fn adapter_info(&self) -> Option<&wgpu::AdapterInfo> {
+        self.adapter_info.as_ref()
+    }
+
+    /// Limits of the device, at least the ones required with `GpuComputeBuilder::required_limits`.
+    #[inline]
+    pub fn limits(&self) -> wgpu::Limits {
+        self.device.limits()
+    }
+
+    /// Features of the device, the required ones and the optional ones supported by the adapter.
+    #[inline]
+    pub fn features(&self) -> wgpu::Features {
+        self.device.features()
+    }
+
+    /// The underlying wgpu device. It is useful to create resources or to record commands alongside the pipelines, e.g. to render their outputs without a CPU round-trip.
+    #[inline]
+    pub fn device(&self) -> &Arc<Device> {
+        &self.device
+    }
+
+    /// The underlying wgpu queue, on which the runs of the pipelines are submitted.
+    #[inline]
+    pub fn queue(&self) -> &Arc<Queue> {
+        &self.queue
+    }
+
+    /// The polling strategy of the device, see `PollStrategy`.
+    #[inline]
+    pub fn poll_strategy(&self) -> PollStrategy {
+        self.poll_strategy
+    }
+
+    /// Maximum number of compute passes recorded in a command buffer, see `GpuComputeOptions::max_passes_per_submission`.
+    #[inline]
+    pub fn max_passes_per_submission(&self) -> Option<NonZeroUsize> {
+        self.max_passes_per_submission
+    }
+
+    /// Submits the commands recorded in `encoder` and replaces it with a new encoder if `passes` compute passes were recorded in it, according to `GpuComputeOptions::max_passes_per_submission`. It is called before each pass but the first, with the number of passes already recorded by the run.
+    pub(crate) fn split_submission(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        passes: usize,
+        label: Option<&str>,
+    ) {
+        if let Some(max) = self.max_passes_per_submission {
+            if passes > 0 && passes.is_multiple_of(max.get()) {
+                let full = std::mem::replace(
+                    encoder,
+                    self.device
+                        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label }),
+                );
+                self.queue.submit(Some(full.finish()));
+            }
+        }
+    }
+
+    /// This method is used to poll the device without blocking, which completes the runs that are done. It is required with `PollStrategy::OnDemand`. Returns whether all the submitted work is completed.
+    #[inline]
+    pub fn poll(&self) -> bool {
+        self.device.poll(wgpu::Maintain::Poll).is_queue_empty()
+    }
+
+    /// Waits until `receiver` gets a message or until `deadline`, polling the device according to the polling strategy. Returns whether the message arrived in time.
+    pub(crate) fn wait_until(
+        &self,
+        receiver: &flume::Receiver<()>,
+        deadline: std::time::Instant,
+    ) -> bool {
+        loop {
+            if receiver.try_recv().is_ok() {
+                return true;
+            }
+            let now = std::time::Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            if self.poll_strategy != PollStrategy::Blocking {
+                return receiver.recv_timeout(deadline - now).is_ok();
+            }
+            // `Maintain::Wait` can't be interrupted, so the device is polled until the deadline.
+            self.device.poll(wgpu::Maintain::Poll);
+            std::thread::sleep((deadline - now).min(std::time::Duration::from_micros(100)));
+        }
+    }
+
+    /// Waits for the submitted work according to the polling strategy. Only the blocking strategy polls here, the other ones rely on another thread polling the device.
+    #[inline]
+    pub(crate) fn wait_submitted(&self) {
+        if self.poll_strategy == PollStrategy::Blocking {
+            self.device.poll(wgpu::Maintain::Wait);
+        }
+    }
 
-        Self { device, queue }
+    /// Waits for `receiver` in the blocking API. The device is polled on this thread unless a background thread polls it, so the wait also completes with `PollStrategy::OnDemand` when the application polls the device on the same thread.
+    #[cfg(feature = "blocking")]
+    pub(crate) fn wait_polled(&self, receiver: &flume::Receiver<()>) {
+        if !matches!(self.poll_strategy, PollStrategy::Background(_)) {
+            self.device.poll(wgpu::Maintain::Wait);
+        }
+        receiver.recv().expect("Error with channel");
     }
 
     /// The input, the uniform and the output must be `bytemuck::Pod` like shown in this small example. The `N` const parameter is the number of stages in the pipeline.
@@ -130,11 +591,11 @@ impl GpuComputeAsync {
     ///     let gpu = GpuComputeAsync::new().await;
     ///     let pipeline = gpu.gen_pipeline::<[f32; 100], Uniform, [f32; 100], 1>( // This is the manual way to specify generics, but it can be inferred most of the times
     ///         None, // No scratchpad
-    ///         [StageDesc {
-    ///             name: Some("norm"),
-    ///             shader: "@compute @workgroup_size(1) fn main() {}", // See other examples for shader content  
-    ///             entrypoint: "main",
-    ///         }]
+    ///         [StageDesc::new(
+    ///             "@compute @workgroup_size(1) fn main() {}", // See other examples for shader content
+    ///             "main",
+    ///         )
+    ///         .with_name("norm")]
     ///     ).await;
     /// }
     /// ```
@@ -147,209 +608,1004 @@ impl GpuComputeAsync {
         &self,
         scratchpad_size: Option<NonZeroUsize>,
         stages: [StageDesc; N],
-    ) -> PipelineAsync<Input, Uniform, Output, N> {
-        let uniform = if std::mem::size_of::<Uniform>() > 0 {
-            Some(self.device.create_buffer(&wgpu::BufferDescriptor {
-                label: "Uniform buffer".into(),
-                size: std::mem::size_of::<Uniform>() as _,
-                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
-                mapped_at_creation: false,
-            }))
-        } else {
-            None
-        };
-        let scratchpad = scratchpad_size.map(|size| {
-            self.device.create_buffer(&wgpu::BufferDescriptor {
-                label: "Scratchpad buffer".into(),
-                size: size.get() as _,
-                usage: wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::STORAGE,
-                mapped_at_creation: false,
-            })
-        });
-        let input = self.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Input buffer"),
-            size: std::mem::size_of::<Input>() as _,
-            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::STORAGE,
-            mapped_at_creation: false,
-        });
-        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Staging buffer"),
-            size: std::mem::size_of::<Output>() as _,
-            usage: wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::STORAGE,
-            mapped_at_creation: false,
-        });
-        let output = self.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Staging buffer"),
-            size: std::mem::size_of::<Output>() as _,
-            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
-            mapped_at_creation: false,
-        });
-
-        let mut bindgroup_layout_items = uniform
-            .as_ref()
-            .map(|_| wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::COMPUTE,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            })
-            .into_iter()
-            .chain(scratchpad.as_ref().map(|_| wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::COMPUTE,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Storage { read_only: false },
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }))
-            .chain(Some(wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::COMPUTE,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Storage { read_only: true },
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }))
-            .chain(Some(wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::COMPUTE,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Storage { read_only: false },
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }))
-            .collect::<Vec<_>>();
-        bindgroup_layout_items
-            .iter_mut()
-            .enumerate()
-            .for_each(|(i, item)| item.binding = i as _);
-
-        let mut bindgroup_items = uniform
-            .as_ref()
-            .map(|buf| wgpu::BindGroupEntry {
-                binding: 0,
-                resource: wgpu::BindingResource::Buffer(buf.as_entire_buffer_binding()),
-            })
-            .into_iter()
-            .chain(scratchpad.as_ref().map(|buf| wgpu::BindGroupEntry {
-                binding: 0,
-                resource: wgpu::BindingResource::Buffer(buf.as_entire_buffer_binding()),
-            }))
-            .chain(Some(wgpu::BindGroupEntry {
-                binding: 0,
-                resource: wgpu::BindingResource::Buffer(input.as_entire_buffer_binding()),
-            }))
-            .chain(Some(wgpu::BindGroupEntry {
-                binding: 0,
-                resource: wgpu::BindingResource::Buffer(staging.as_entire_buffer_binding()),
-            }))
-            .collect::<Vec<_>>();
-        bindgroup_items
-            .iter_mut()
-            .enumerate()
-            .for_each(|(i, item)| item.binding = i as _);
+    ) -> PipelineAsync<'_, Input, Uniform, Output, N> {
+        self.gen_pipeline_from(
+            BufferSizes::of::<Input, Uniform, Output>(scratchpad_size),
+            stages,
+            None,
+        )
+        .await
+        .unwrap_or_else(|error| panic!("{}", error))
+    }
 
-        let bindgroup_layout =
-            self.device
-                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                    entries: &bindgroup_layout_items,
-                    label: Some("Global bind group layout"),
-                });
-        let bindgroup = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &bindgroup_layout,
-            entries: &bindgroup_items,
-            label: Some("Global bind group"),
-        });
+    /// Same as `GpuComputeAsync::gen_pipeline`, but the pipeline holds an `Arc` of the device instead of borrowing it. It has no lifetime, so it can be stored next to the device in the state of an application or moved into a thread or a task.
+    ///
+    /// # Panics
+    /// Same as `GpuComputeAsync::gen_pipeline`.
+    pub async fn gen_pipeline_owned<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        Output: bytemuck::Pod,
+        const N: usize,
+    >(
+        self: &Arc<Self>,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> OwnedPipelineAsync<Input, Uniform, Output, N> {
+        self.gen_pipeline(scratchpad_size, stages)
+            .await
+            .with_device(GpuRef::Shared(Arc::clone(self) as _))
+    }
 
-        let stages_pipeline: [_; N] = stages
-            .iter()
-            .map(|desc| {
-                let shader = self
-                    .device
-                    .create_shader_module(wgpu::ShaderModuleDescriptor {
-                        label: desc
-                            .name
-                            .map(|n| format!("Shader for stage {}", n))
-                            .as_ref()
-                            .map(AsRef::as_ref),
-                        source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(desc.shader)),
-                    });
-
-                let pipeline_layout =
-                    self.device
-                        .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                            label: desc
-                                .name
-                                .map(|n| format!("Compute pipeline layout for stage {}", n))
-                                .as_ref()
-                                .map(AsRef::as_ref),
-                            bind_group_layouts: &[&bindgroup_layout],
-                            push_constant_ranges: &[],
-                        });
-
-                self.device
-                    .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-                        label: desc
-                            .name
-                            .map(|n| format!("Compute pipeline for stage {}", n))
-                            .as_ref()
-                            .map(AsRef::as_ref),
-                        layout: Some(&pipeline_layout),
-                        module: &shader,
-                        entry_point: desc.entrypoint,
-                    })
-            })
-            .collect::<Vec<_>>()
-            .try_into()
-            .expect("Wrong length?");
+    /// Same as `GpuComputeAsync::gen_pipeline`, but the `shader` of each stage is a key given to `provider` which returns the WGSL source, e.g. by decrypting it. The provider is kept by the pipeline, so `PipelineAsync::rebuild_on` can get the sources again.
+    pub async fn gen_pipeline_with_provider<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        Output: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        provider: impl ShaderSourceProvider + 'static,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> PipelineAsync<'_, Input, Uniform, Output, N> {
+        self.gen_pipeline_from(
+            BufferSizes::of::<Input, Uniform, Output>(scratchpad_size),
+            stages,
+            Some(Arc::new(provider)),
+        )
+        .await
+        .unwrap_or_else(|error| panic!("{}", error))
+    }
 
-        PipelineAsync {
-            uniform,
-            input,
-            scratchpad,
-            staging,
-            output,
-            bindgroup,
-            stages: stages_pipeline,
-            stages_desc: stages,
-            device: &self,
-            _phantom: PhantomData,
-        }
+    /// Same as `GpuComputeAsync::gen_pipeline`, but returns an error instead of panicking: `SgpuError::LayoutMismatch` if the bindings of the shaders don't match the types of the pipeline and `SgpuError::InvalidShader` or `SgpuError::ShaderCompilation` if a shader doesn't compile.
+    pub async fn try_gen_pipeline<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        Output: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> Result<PipelineAsync<'_, Input, Uniform, Output, N>, SgpuError> {
+        self.gen_pipeline_from(
+            BufferSizes::of::<Input, Uniform, Output>(scratchpad_size),
+            stages,
+            None,
+        )
+        .await
     }
-}
 
-impl<'a, Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod, const N: usize>
-    PipelineAsync<'a, Input, Uniform, Output, N>
-{
-    /// This method is used to write the uniform buffer. It is useful to change the uniform between runs.
-    #[inline]
-    pub fn write_uniform(&mut self, uniform: &Uniform) {
-        self.device.queue.write_buffer(
-            self.uniform.as_ref().expect("No uniforms"),
-            0,
-            bytemuck::bytes_of(uniform),
+    /// Same as `GpuComputeAsync::gen_pipeline_with_provider`, but returns an error instead of panicking like `GpuComputeAsync::try_gen_pipeline`.
+    pub async fn try_gen_pipeline_with_provider<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        Output: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        provider: impl ShaderSourceProvider + 'static,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> Result<PipelineAsync<'_, Input, Uniform, Output, N>, SgpuError> {
+        self.gen_pipeline_from(
+            BufferSizes::of::<Input, Uniform, Output>(scratchpad_size),
+            stages,
+            Some(Arc::new(provider)),
         )
+        .await
     }
 
-    /// This method is used to print the content of the scratchpad buffer. It is useful for debugging.
-    #[inline]
-    pub fn dbg_print_scratchpad<T: bytemuck::Pod + bytemuck::AnyBitPattern + std::fmt::Debug>(
+    /// Same as `GpuComputeAsync::gen_pipeline`, but with several scratchpads, e.g. for sorts or FFTs needing intermediate buffers of different sizes. They are bound in order at consecutive bindings, where the scratchpad of `GpuComputeAsync::gen_pipeline` would be, and their content persists between runs.
+    ///
+    /// # Panics
+    /// Same as `GpuComputeAsync::gen_pipeline`.
+    pub async fn gen_pipeline_with_scratchpads<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        Output: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        scratchpads: &[ScratchpadDesc],
+        stages: [StageDesc; N],
+    ) -> PipelineAsync<'_, Input, Uniform, Output, N> {
+        self.try_gen_pipeline_with_scratchpads(scratchpads, stages)
+            .await
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Same as `GpuComputeAsync::gen_pipeline_with_scratchpads`, but returns an error instead of panicking like `GpuComputeAsync::try_gen_pipeline`.
+    pub async fn try_gen_pipeline_with_scratchpads<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        Output: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        scratchpads: &[ScratchpadDesc],
+        stages: [StageDesc; N],
+    ) -> Result<PipelineAsync<'_, Input, Uniform, Output, N>, SgpuError> {
+        let sizes = BufferSizes {
+            scratchpads: scratchpads.to_vec(),
+            ..BufferSizes::of::<Input, Uniform, Output>(None)
+        };
+        self.gen_pipeline_from(sizes, stages, None).await
+    }
+
+    /// Same as `GpuComputeAsync::gen_pipeline`, but the stages have push constants of type `PushConstants`, declared as `var<push_constant>`. They are set in each pass from the value given to `PipelineAsync::set_push_constants`, which is cheaper than writing a uniform buffer for small parameters changing at each run, like a frame index. The device must have the `PUSH_CONSTANTS` feature, which is enabled by default when the adapter supports it.
+    ///
+    /// # Panics
+    /// If the device doesn't support push constants of this size, or in the same cases as `GpuComputeAsync::gen_pipeline`.
+    pub async fn gen_pipeline_with_push_constants<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        Output: bytemuck::Pod,
+        PushConstants: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> PipelineAsync<'_, Input, Uniform, Output, N> {
+        self.try_gen_pipeline_with_push_constants::<Input, Uniform, Output, PushConstants, N>(
+            scratchpad_size,
+            stages,
+        )
+        .await
+        .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Same as `GpuComputeAsync::gen_pipeline_with_push_constants`, but returns an error instead of panicking: `SgpuError::Unsupported` if the device doesn't support push constants of this size, or the errors of `GpuComputeAsync::try_gen_pipeline`.
+    pub async fn try_gen_pipeline_with_push_constants<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        Output: bytemuck::Pod,
+        PushConstants: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> Result<PipelineAsync<'_, Input, Uniform, Output, N>, SgpuError> {
+        let sizes = BufferSizes {
+            push_constants: std::mem::size_of::<PushConstants>(),
+            ..BufferSizes::of::<Input, Uniform, Output>(scratchpad_size)
+        };
+        self.gen_pipeline_from(sizes, stages, None).await
+    }
+
+    async fn gen_pipeline_from<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        Output: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        sizes: BufferSizes,
+        stages: [StageDesc; N],
+        provider: Option<Arc<dyn ShaderSourceProvider>>,
+    ) -> Result<PipelineAsync<'_, Input, Uniform, Output, N>, SgpuError> {
+        let stages = self.compile_stages(&sizes, stages, provider).await?;
+        let buffers = self.create_buffers(&sizes, &stages.bindgroup_layout, self.zero_init);
+
+        Ok(PipelineAsync {
+            buffers,
+            stages: Arc::new(stages),
+            push_constants: vec![0; sizes.push_constants],
+            sizes,
+            validator: None,
+            tracing: None,
+            zero_init: self.zero_init,
+            change_detection: None,
+            uniforms: None,
+            device: GpuRef::Borrowed(self),
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Lints, checks and compiles the stages of a pipeline with buffers of the given sizes.
+    pub(crate) async fn compile_stages<const N: usize>(
+        &self,
+        sizes: &BufferSizes,
+        stages: [StageDesc; N],
+        provider: Option<Arc<dyn ShaderSourceProvider>>,
+    ) -> Result<CompiledStages<N>, SgpuError> {
+        let (bindgroup_layout, pipelines, shared_uniform) = self
+            .compile_stage_list(sizes, &stages, provider.as_deref())
+            .await?;
+        Ok(CompiledStages {
+            bindgroup_layout: Arc::new(bindgroup_layout),
+            pipelines: pipelines.try_into().expect("Wrong length?"),
+            desc: stages,
+            provider,
+            shared_uniform,
+            push_constants: sizes.push_constants as u32,
+            batched: OnceLock::new(),
+        })
+    }
+
+    /// Same as `GpuComputeAsync::compile_stages`, for a number of stages known at runtime. Returns the layout of the group 0, the compute pipelines and whether they bind the shared uniform.
+    pub(crate) async fn compile_stage_list(
+        &self,
+        sizes: &BufferSizes,
+        stages: &[StageDesc],
+        provider: Option<&dyn ShaderSourceProvider>,
+    ) -> Result<(wgpu::BindGroupLayout, Vec<Arc<wgpu::ComputePipeline>>, bool), SgpuError> {
+        if sizes.uniform == 0 {
+            diagnostics::log_debug!("Uniform is zero-sized, its binding is skipped");
+        }
+        if sizes.input == 0 {
+            diagnostics::log_debug!("Input is zero-sized, its binding is skipped");
+        }
+        self.compile_layout_stages(
+            &buffer_layout(sizes),
+            sizes.push_constants,
+            stages,
+            provider,
+        )
+        .await
+    }
+
+    /// Same as `GpuComputeAsync::compile_stage_list`, for the buffers of `layout` bound in order and `push_constants` bytes of push constants.
+    pub(crate) async fn compile_layout_stages(
+        &self,
+        layout: &[sgpu_compute_core::layout::BufferDescription],
+        push_constants: usize,
+        stages: &[StageDesc],
+        provider: Option<&dyn ShaderSourceProvider>,
+    ) -> Result<(wgpu::BindGroupLayout, Vec<Arc<wgpu::ComputePipeline>>, bool), SgpuError> {
+        let sources = stages
+            .iter()
+            .map(|desc| describe::stage_source(desc, provider))
+            .collect::<Result<Vec<_>, _>>()?;
+        for (desc, source) in stages.iter().zip(&sources) {
+            describe::diagnose_stage(desc, source)?;
+        }
+        if let Some(callback) = &self.lint {
+            lint::lint_sources(stages, &sources)
+                .iter()
+                .for_each(callback);
+        }
+        describe::check_bindings(layout, stages, &sources)?;
+        let shared_uniform = describe::uses_shared_uniform(stages, &sources);
+        if shared_uniform && self.shared_uniform.get().is_none() {
+            return Err(SgpuError::Validation(
+                "the shared uniform must be written with `write_shared_uniform` before generating a pipeline using it".into(),
+            ));
+        }
+        if push_constants > 0 {
+            if !self
+                .device
+                .features()
+                .contains(wgpu::Features::PUSH_CONSTANTS)
+            {
+                return Err(SgpuError::Unsupported(
+                    "push constants require the PUSH_CONSTANTS feature of the device".into(),
+                ));
+            }
+            let max = self.device.limits().max_push_constant_size as usize;
+            if push_constants > max {
+                return Err(SgpuError::Unsupported(format!(
+                    "the push constants are {} bytes but the device only allows {} bytes",
+                    push_constants, max
+                )));
+            }
+            if !push_constants.is_multiple_of(4) {
+                return Err(SgpuError::LayoutMismatch(format!(
+                    "the push constants are {} bytes, it must be a multiple of 4 bytes",
+                    push_constants
+                )));
+            }
+        }
+        let bindgroup_layout = self.bindgroup_layout(layout, false);
+        // The compilation errors are captured instead of going to the uncaptured error handler, which panics.
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let pipelines = stages
+            .iter()
+            .zip(&sources)
+            .map(|(desc, source)| {
+                Arc::new(self.compile_stage(
+                    desc,
+                    source,
+                    &bindgroup_layout,
+                    shared_uniform,
+                    push_constants as u32,
+                ))
+            })
+            .collect::<Vec<_>>();
+        if let Some(error) = self.device.pop_error_scope().await {
+            return Err(match error {
+                wgpu::Error::Validation { description, .. } => {
+                    SgpuError::ShaderCompilation(description)
+                }
+                error => error.into(),
+            });
+        }
+        Ok((bindgroup_layout, pipelines, shared_uniform))
+    }
+
+    /// Compiles the compute pipeline of a stage from its source, binding the shared uniform if `shared_uniform` is set and with `push_constants` bytes of push constants.
+    fn compile_stage(
+        &self,
+        desc: &StageDesc,
+        source: &str,
+        bindgroup_layout: &wgpu::BindGroupLayout,
+        shared_uniform: bool,
+        push_constants: u32,
+    ) -> wgpu::ComputePipeline {
+        let shader = self
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: desc
+                    .name
+                    .map(|n| format!("Shader for stage {}", n))
+                    .as_ref()
+                    .map(AsRef::as_ref),
+                source: match desc.source {
+                    ShaderSource::Wgsl => wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
+                    #[cfg(feature = "spirv")]
+                    ShaderSource::SpirV(words) => wgpu::ShaderSource::SpirV(Cow::Borrowed(words)),
+                    #[cfg(not(feature = "spirv"))]
+                    ShaderSource::SpirV(_) => {
+                        unreachable!("SPIR-V stages are rejected by `describe::stage_source`")
+                    }
+                    #[cfg(feature = "glsl")]
+                    ShaderSource::Glsl { .. } => wgpu::ShaderSource::Glsl {
+                        shader: Cow::Borrowed(source),
+                        stage: wgpu::naga::ShaderStage::Compute,
+                        defines: Default::default(),
+                    },
+                    #[cfg(not(feature = "glsl"))]
+                    ShaderSource::Glsl { .. } => {
+                        unreachable!("GLSL stages are rejected by `describe::stage_source`")
+                    }
+                },
+            });
+
+        let pipeline_layout = self
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: desc
+                    .name
+                    .map(|n| format!("Compute pipeline layout for stage {}", n))
+                    .as_ref()
+                    .map(AsRef::as_ref),
+                bind_group_layouts: &std::iter::once(bindgroup_layout)
+                    .chain(shared_uniform.then(|| &self.shared_uniform().layout))
+                    .collect::<Vec<_>>(),
+                push_constant_ranges: &[wgpu::PushConstantRange {
+                    stages: wgpu::ShaderStages::COMPUTE,
+                    range: 0..push_constants,
+                }][..(push_constants > 0) as usize],
+            });
+
+        self.device
+            .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: desc
+                    .name
+                    .map(|n| format!("Compute pipeline for stage {}", n))
+                    .as_ref()
+                    .map(AsRef::as_ref),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(desc.entrypoint),
+                compilation_options: wgpu::PipelineCompilationOptions {
+                    constants: &specialize::constants(desc),
+                    ..Default::default()
+                },
+                cache: None,
+            })
+    }
+
+    /// Layout of the group 0 binding the buffers of `layout` in order, binding the uniform with a dynamic offset if `dynamic_uniform` is set.
+    fn bindgroup_layout(
+        &self,
+        layout: &[sgpu_compute_core::layout::BufferDescription],
+        dynamic_uniform: bool,
+    ) -> wgpu::BindGroupLayout {
+        use sgpu_compute_core::layout::BufferRole;
+        let bindgroup_layout_items = layout
+            .iter()
+            .map(|buffer| wgpu::BindGroupLayoutEntry {
+                binding: buffer.binding,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: match buffer.role {
+                        BufferRole::Uniform => wgpu::BufferBindingType::Uniform,
+                        BufferRole::Input => wgpu::BufferBindingType::Storage { read_only: true },
+                        BufferRole::Scratchpad | BufferRole::Output => {
+                            wgpu::BufferBindingType::Storage { read_only: false }
+                        }
+                    },
+                    has_dynamic_offset: dynamic_uniform && buffer.role == BufferRole::Uniform,
+                    min_binding_size: None,
+                },
+                count: None,
+            })
+            .collect::<Vec<_>>();
+
+        self.device
+            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &bindgroup_layout_items,
+                label: Some("Global bind group layout"),
+            })
+    }
+
+    pub(crate) fn create_buffers(
+        &self,
+        sizes: &BufferSizes,
+        bindgroup_layout: &wgpu::BindGroupLayout,
+        zero_init: ZeroInit,
+    ) -> Buffers {
+        let uniform = if sizes.uniform > 0 {
+            Some(self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: "Uniform buffer".into(),
+                size: sizes.uniform as _,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+                mapped_at_creation: false,
+            }))
+        } else {
+            None
+        };
+        let scratchpad = sizes.scratchpad.map(|size| {
+            Arc::new(self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: "Scratchpad buffer".into(),
+                size: size.get() as _,
+                usage: wgpu::BufferUsages::COPY_SRC
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            }))
+        });
+        let scratchpads = sizes
+            .scratchpads
+            .iter()
+            .map(|scratchpad| {
+                self.device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some(scratchpad.name.unwrap_or("Scratchpad buffer")),
+                    size: scratchpad.size.get() as _,
+                    usage: wgpu::BufferUsages::COPY_SRC
+                        | wgpu::BufferUsages::COPY_DST
+                        | wgpu::BufferUsages::STORAGE,
+                    mapped_at_creation: false,
+                })
+            })
+            .collect::<Vec<_>>();
+        let input = if sizes.input > 0 {
+            Some(self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Input buffer"),
+                size: sizes.input as _,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            }))
+        } else {
+            None
+        };
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Staging buffer"),
+            size: sizes.output as _,
+            usage: wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let output = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Staging buffer"),
+            size: sizes.output as _,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let extra_outputs = sizes
+            .extra_outputs
+            .iter()
+            .map(|&size| {
+                let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Extra staging buffer"),
+                    size: size as _,
+                    usage: wgpu::BufferUsages::COPY_SRC
+                        | wgpu::BufferUsages::COPY_DST
+                        | wgpu::BufferUsages::STORAGE,
+                    mapped_at_creation: false,
+                });
+                let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("Extra readback buffer"),
+                    size: size as _,
+                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                });
+                (staging, readback)
+            })
+            .collect::<Vec<_>>();
+
+        let bindgroup = self.create_bindgroup(
+            bindgroup_layout,
+            bound_buffers(
+                uniform.as_ref(),
+                scratchpad.as_deref(),
+                &scratchpads,
+                input.as_ref(),
+                &staging,
+                &extra_outputs,
+            ),
+        );
+
+        diagnostics::log_debug!(
+            "Allocated buffers: uniform {} bytes, scratchpad {} bytes, input {} bytes, output {} bytes, extra outputs {:?} bytes (twice, for the staging and the readback), named scratchpads {:?} bytes",
+            sizes.uniform,
+            sizes.scratchpad.map_or(0, NonZeroUsize::get),
+            sizes.input,
+            sizes.output,
+            sizes.extra_outputs,
+            sizes
+                .scratchpads
+                .iter()
+                .map(|scratchpad| scratchpad.size.get())
+                .collect::<Vec<_>>()
+        );
+        let buffers = Buffers {
+            uniform,
+            input,
+            scratchpad,
+            scratchpads,
+            staging,
+            output,
+            extra_outputs,
+            bindgroup,
+            stage_uniforms: Vec::new(),
+        };
+        if zero_init != ZeroInit::Never {
+            self.clear_buffers(&buffers);
+        }
+        buffers
+    }
+
+    /// Creates a bind group for the group 0 binding `buffers` in order.
+    fn create_bindgroup<'b>(
+        &self,
+        bindgroup_layout: &wgpu::BindGroupLayout,
+        buffers: impl Iterator<Item = &'b wgpu::Buffer>,
+    ) -> wgpu::BindGroup {
+        let bindgroup_items = buffers
+            .enumerate()
+            .map(|(i, buf)| wgpu::BindGroupEntry {
+                binding: i as _,
+                resource: wgpu::BindingResource::Buffer(buf.as_entire_buffer_binding()),
+            })
+            .collect::<Vec<_>>();
+        self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: bindgroup_layout,
+            entries: &bindgroup_items,
+            label: Some("Global bind group"),
+        })
+    }
+
+    /// Submits the clearing of all the buffers of a pipeline.
+    fn clear_buffers(&self, buffers: &Buffers) {
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Zero-initialization of the buffers"),
+            });
+        buffers.clear(&mut encoder, true);
+        self.queue.submit(Some(encoder.finish()));
+    }
+}
+
+impl<'a, Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod, const N: usize>
+    PipelineAsync<'a, Input, Uniform, Output, N>
+{
+    /// This method is used to duplicate the pipeline for another thread. The compiled stages, the input validator and the trace are shared with the original pipeline, but new buffers are allocated, so both pipelines can run concurrently on different data without recompiling the shaders. The push constants are copied, but the uniform, the uniforms of the stages and the scratchpad are not.
+    pub fn clone_for_thread(&self) -> Self {
+        let mut clone = Self {
+            buffers: self.device.create_buffers(
+                &self.sizes,
+                &self.stages.bindgroup_layout,
+                self.zero_init,
+            ),
+            stages: Arc::clone(&self.stages),
+            sizes: self.sizes.clone(),
+            push_constants: self.push_constants.clone(),
+            validator: self.validator.clone(),
+            tracing: self.tracing.as_ref().map(|tracing| {
+                trace::Tracing::new(Arc::clone(&tracing.trace), &self.device.device, N)
+            }),
+            zero_init: self.zero_init,
+            change_detection: None,
+            uniforms: None,
+            device: self.device.clone(),
+            _phantom: PhantomData,
+        };
+        clone.set_change_detection(self.change_detection.is_some());
+        clone
+    }
+
+    /// Moves the pipeline to another reference of the same device.
+    fn with_device<'b>(self, device: GpuRef<'b>) -> PipelineAsync<'b, Input, Uniform, Output, N> {
+        PipelineAsync {
+            buffers: self.buffers,
+            stages: self.stages,
+            sizes: self.sizes,
+            push_constants: self.push_constants,
+            validator: self.validator,
+            tracing: self.tracing,
+            zero_init: self.zero_init,
+            change_detection: self.change_detection,
+            uniforms: self.uniforms,
+            device,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// This method is used to build a pool of `size` pipelines made of this pipeline and `size - 1` clones from `clone_for_thread`.
+    pub fn into_pool(self, size: usize) -> pool::PipelinePool<Self> {
+        let clones = (1..size)
+            .map(|_| self.clone_for_thread())
+            .collect::<Vec<_>>();
+        pool::PipelinePool::new(std::iter::once(self).chain(clones))
+    }
+
+    /// This method is used to rebuild the pipeline with the same stages, input validator and trace on another `GpuComputeAsync`, e.g. after the device of this pipeline was lost. The push constants, the uniforms of the stages and the batch of uniforms are written again, but the buffers are new, so the uniform of `PipelineAsync::write_uniform` has to be written again.
+    ///
+    /// # Errors
+    /// The errors of `GpuComputeAsync::try_gen_pipeline` on `gpu`, e.g. `SgpuError::Unsupported` if it lacks a feature used by the stages.
+    pub async fn rebuild_on<'b>(
+        &self,
+        gpu: &'b GpuComputeAsync,
+    ) -> Result<PipelineAsync<'b, Input, Uniform, Output, N>, SgpuError> {
+        let mut pipeline = gpu
+            .gen_pipeline_from(
+                self.sizes.clone(),
+                self.stages.desc.clone(),
+                self.stages.provider.clone(),
+            )
+            .await?;
+        pipeline.validator = self.validator.clone();
+        pipeline.set_zero_init(self.zero_init);
+        pipeline.set_change_detection(self.change_detection.is_some());
+        if let Some(tracing) = &self.tracing {
+            pipeline.set_trace(Arc::clone(&tracing.trace));
+        }
+        self.copy_stage_parameters(&mut pipeline);
+        if let Some(uniforms) = self.batch_uniforms() {
+            pipeline.write_uniforms(&uniforms);
+        }
+        Ok(pipeline)
+    }
+
+    /// Copies the push constants and the uniforms of the stages into `other`, a clone or a rebuild of this pipeline.
+    pub(crate) fn copy_stage_parameters(
+        &self,
+        other: &mut PipelineAsync<'_, Input, Uniform, Output, N>,
+    ) {
+        other.push_constants.clone_from(&self.push_constants);
+        for index in 0..N {
+            match self.buffers.stage_uniforms.get(index) {
+                Some(Some(stage)) => other
+                    .write_uniform_for_stage(index, &bytemuck::pod_read_unaligned(&stage.value)),
+                _ => other.clear_uniform_for_stage(index),
+            }
+        }
+    }
+
+    /// This method is used to replace the stage at `index` by `stage`, e.g. to try a variant of a kernel in a running application. Only the new stage is compiled, and the buffers, so the uniform and the scratchpad, are kept. Its shader goes through the shader source provider of the pipeline if it has one. Clones of the pipeline keep the previous stage.
+    ///
+    /// # Panics
+    /// If the stage is rejected, see `PipelineAsync::try_replace_stage`.
+    pub async fn replace_stage(&mut self, index: usize, stage: StageDesc) {
+        self.try_replace_stage(index, stage)
+            .await
+            .unwrap_or_else(|error| panic!("{}", error));
+    }
+
+    /// Same as `PipelineAsync::replace_stage`, but returns an error instead of panicking. The pipeline is left unchanged on error.
+    ///
+    /// # Errors
+    /// If `index` is out of bounds, if the bindings of the new stage don't match the buffers of the pipeline, like `GpuComputeAsync::try_gen_pipeline`, if the new stage uses the shared uniform but the previous stages didn't, or if the device rejects its compilation.
+    pub async fn try_replace_stage(
+        &mut self,
+        index: usize,
+        stage: StageDesc,
+    ) -> Result<(), SgpuError> {
+        if index >= N {
+            return Err(SgpuError::Validation(format!(
+                "stage {} out of bounds ({} stages)",
+                index, N
+            )));
+        }
+        let source = describe::stage_source(&stage, self.stages.provider.as_deref())?;
+        describe::diagnose_stage(&stage, &source)?;
+        describe::check_bindings(
+            &buffer_layout(&self.sizes),
+            std::slice::from_ref(&stage),
+            std::slice::from_ref(&source),
+        )?;
+        let shared_uniform = describe::uses_shared_uniform(
+            std::slice::from_ref(&stage),
+            std::slice::from_ref(&source),
+        );
+        if shared_uniform && !self.stages.shared_uniform {
+            return Err(SgpuError::Validation(
+                "the new stage uses the shared uniform but the pipeline wasn't generated with it"
+                    .into(),
+            ));
+        }
+        // The compilation errors are captured instead of going to the uncaptured error handler, which panics.
+        self.device
+            .device
+            .push_error_scope(wgpu::ErrorFilter::Validation);
+        let pipeline = self.device.compile_stage(
+            &stage,
+            &source,
+            &self.stages.bindgroup_layout,
+            self.stages.shared_uniform,
+            self.stages.push_constants,
+        );
+        if let Some(error) = self.device.device.pop_error_scope().await {
+            return Err(match error {
+                wgpu::Error::Validation { description, .. } => {
+                    SgpuError::ShaderCompilation(description)
+                }
+                error => error.into(),
+            });
+        }
+        let mut pipelines = self.stages.pipelines.clone();
+        pipelines[index] = Arc::new(pipeline);
+        let mut desc = self.stages.desc.clone();
+        desc[index] = stage;
+        self.stages = Arc::new(CompiledStages {
+            bindgroup_layout: Arc::clone(&self.stages.bindgroup_layout),
+            pipelines,
+            desc,
+            provider: self.stages.provider.clone(),
+            shared_uniform: self.stages.shared_uniform,
+            push_constants: self.stages.push_constants,
+            batched: OnceLock::new(),
+        });
+        Ok(())
+    }
+
+    /// This method is used to record the runs of the pipeline in `trace`, see the `trace` module. Clones of the pipeline record in the same trace.
+    pub fn set_trace(&mut self, trace: Arc<trace::Trace>) {
+        self.tracing = Some(trace::Tracing::new(trace, &self.device.device, N));
+    }
+
+    /// This method is used to check the input before each upload in debug builds, e.g. that it contains no NaN or that its keys are sorted. The run panics with the returned message if the validator fails. The validator is skipped in release builds.
+    pub fn set_input_validator(
+        &mut self,
+        validator: impl Fn(&Input) -> Result<(), String> + Send + Sync + 'static,
+    ) {
+        self.validator = Some(Arc::new(validator));
+    }
+
+    /// This method is used to choose when the buffers of the pipeline are cleared, see `ZeroInit`. The default is the `zero_init` option of the device. The buffers are cleared right away unless `zero_init` is `ZeroInit::Never`, since the pipeline is already created.
+    pub fn set_zero_init(&mut self, zero_init: ZeroInit) {
+        if zero_init == ZeroInit::BeforeEachRun {
+            diagnostics::log_debug!(
+                "The scratchpad and the output are cleared before each run, their contents are not kept between runs"
+            );
+        }
+        if zero_init != ZeroInit::Never {
+            self.device.clear_buffers(&self.buffers);
+        }
+        self.zero_init = zero_init;
+    }
+
+    /// This method is used to write the uniform buffer. It is useful to change the uniform between runs.
+    #[inline]
+    pub fn write_uniform(&mut self, uniform: &Uniform) {
+        self.device.queue.write_buffer(
+            self.buffers.uniform.as_ref().expect("No uniforms"),
+            0,
+            bytemuck::bytes_of(uniform),
+        )
+    }
+
+    /// This method is used to give the stage at `index` its own uniform, e.g. the index of a pass or a stride, instead of the one written by `write_uniform`. The buffer of the stage is allocated on the first call, and the stage keeps its uniform until `clear_uniform_for_stage`. The other stages still bind the uniform of `write_uniform`.
+    ///
+    /// # Panics
+    /// If `index` is out of bounds or if the uniform is zero-sized.
+    pub fn write_uniform_for_stage(&mut self, index: usize, uniform: &Uniform) {
+        assert!(index < N, "Stage {} out of bounds ({} stages)", index, N);
+        assert!(self.buffers.uniform.is_some(), "No uniforms");
+        if self.buffers.stage_uniforms.len() < N {
+            self.buffers.stage_uniforms.resize_with(N, || None);
+        }
+        if self.buffers.stage_uniforms[index].is_none() {
+            let buffer = self.device.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Stage uniform buffer"),
+                size: std::mem::size_of::<Uniform>() as _,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+                mapped_at_creation: false,
+            });
+            let bindgroup = self.device.create_bindgroup(
+                &self.stages.bindgroup_layout,
+                bound_buffers(
+                    Some(&buffer),
+                    self.buffers.scratchpad.as_deref(),
+                    &self.buffers.scratchpads,
+                    self.buffers.input.as_ref(),
+                    &self.buffers.staging,
+                    &self.buffers.extra_outputs,
+                ),
+            );
+            self.buffers.stage_uniforms[index] = Some(StageUniform {
+                buffer,
+                bindgroup,
+                value: Vec::new(),
+            });
+        }
+        let stage = self.buffers.stage_uniforms[index]
+            .as_mut()
+            .expect("Allocated above");
+        stage.value = bytemuck::bytes_of(uniform).to_vec();
+        self.device
+            .queue
+            .write_buffer(&stage.buffer, 0, &stage.value);
+    }
+
+    /// This method is used to make the stage at `index` bind the uniform of `write_uniform` again after `write_uniform_for_stage`.
+    pub fn clear_uniform_for_stage(&mut self, index: usize) {
+        if let Some(stage) = self.buffers.stage_uniforms.get_mut(index) {
+            *stage = None;
+        }
+    }
+
+    /// This method is used to bind `buffer` as the input of the pipeline instead of its own input buffer, e.g. memory allocated by a capture card or another process and imported with `GpuComputeAsync::import_vulkan_buffer` or `wgpu::Device::create_buffer_from_hal`. The stages then read it directly without any copy, with `PipelineAsync::run_without_upload`. `run` still writes its input into `buffer`, which must then have the `COPY_DST` usage.
+    ///
+    /// # Panics
+    /// If the input is zero-sized, if `buffer` is smaller than `Input` or if it doesn't have the `STORAGE` usage.
+    pub fn set_input_buffer(&mut self, buffer: wgpu::Buffer) {
+        assert!(self.buffers.input.is_some(), "No input");
+        assert!(
+            buffer.size() >= std::mem::size_of::<Input>() as u64,
+            "The input buffer is {} bytes, expected at least {} bytes",
+            buffer.size(),
+            std::mem::size_of::<Input>()
+        );
+        assert!(
+            buffer.usage().contains(wgpu::BufferUsages::STORAGE),
+            "The input buffer must have the STORAGE usage"
+        );
+        self.buffers.input = Some(buffer);
+        self.buffers
+            .rebind(&self.device, &self.stages.bindgroup_layout);
+    }
+
+    /// This method is used to set the push constants of the next runs, see `GpuComputeAsync::gen_pipeline_with_push_constants`. The value is kept on the CPU and recorded in the passes, so setting it before each run costs nothing on the GPU.
+    ///
+    /// # Panics
+    /// If the pipeline has no push constants or if the size of `T` is not the size of its push constants.
+    pub fn set_push_constants<T: bytemuck::Pod>(&mut self, push_constants: &T) {
+        assert!(!self.push_constants.is_empty(), "No push constants");
+        assert_eq!(
+            std::mem::size_of::<T>(),
+            self.push_constants.len(),
+            "The push constants are {} bytes",
+            self.push_constants.len()
+        );
+        self.push_constants
+            .copy_from_slice(bytemuck::bytes_of(push_constants));
+    }
+
+    /// The uniform buffer bound at `@binding(0)`, `None` if the uniform is zero-sized.
+    #[inline]
+    pub fn uniform_buffer(&self) -> Option<&wgpu::Buffer> {
+        self.buffers.uniform.as_ref()
+    }
+
+    /// The scratchpad buffer, `None` if the pipeline has no scratchpad. Its content persists between runs.
+    #[inline]
+    pub fn scratchpad_buffer(&self) -> Option<&wgpu::Buffer> {
+        self.buffers.scratchpad.as_deref()
+    }
+
+    /// The scratchpads of `GpuComputeAsync::gen_pipeline_with_scratchpads`, in order. Their content persists between runs.
+    #[inline]
+    pub fn scratchpad_buffers(&self) -> &[wgpu::Buffer] {
+        &self.buffers.scratchpads
+    }
+
+    /// The input storage buffer, `None` if the input is zero-sized. It is written at the beginning of each run.
+    #[inline]
+    pub fn input_buffer(&self) -> Option<&wgpu::Buffer> {
+        self.buffers.input.as_ref()
+    }
+
+    /// The output storage buffer written by the stages. It has the `STORAGE`, `COPY_SRC` and `COPY_DST` usages, so it can be bound in other passes or copied into e.g. a vertex buffer without a CPU round-trip. Its content persists between runs.
+    #[inline]
+    pub fn output_buffer(&self) -> &wgpu::Buffer {
+        &self.buffers.staging
+    }
+
+    /// The mappable buffer into which the output is copied at the end of a run to be read by the callback.
+    #[inline]
+    pub fn readback_buffer(&self) -> &wgpu::Buffer {
+        &self.buffers.output
+    }
+
+    /// The bind group of the buffers of the pipeline, which is bound to `@group(0)` in each stage.
+    #[inline]
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.buffers.bindgroup
+    }
+
+    /// The layout of `PipelineAsync::bind_group`, e.g. to create another compute pipeline using the buffers of this one.
+    #[inline]
+    pub fn bind_group_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.stages.bindgroup_layout
+    }
+
+    /// This method is used to read the beginning of the scratchpad as a `T`, e.g. to check the intermediate state of the stages in a test. It waits for the runs submitted before.
+    ///
+    /// # Panics
+    /// If the pipeline has no scratchpad, or if `T` is larger than the scratchpad or its size is not a multiple of 4 bytes.
+    pub async fn read_scratchpad<T: bytemuck::Pod + Send>(&self) -> T {
+        let scratchpad = self.checked_scratchpad::<T>();
+        let (sender, receiver) = flume::bounded(1);
+        DownloadBuffer::read_buffer(
+            &self.device.device,
+            &self.device.queue,
+            &scratchpad.slice(..std::mem::size_of::<T>() as u64),
+            move |res| {
+                let res = res.map(|bytes| bytemuck::pod_read_unaligned::<T>(&bytes));
+                sender.send(res).unwrap()
+            },
+        );
+        self.device.wait_submitted();
+        receiver
+            .recv_async()
+            .await
+            .expect("Error with channel")
+            .expect("Could not read scratchpad content")
+    }
+
+    /// This method is used to write `value` at the beginning of the scratchpad, e.g. to seed the initial state of a simulation. The write happens before the next run.
+    ///
+    /// # Panics
+    /// In the same cases as `PipelineAsync::read_scratchpad`.
+    pub fn write_scratchpad<T: bytemuck::Pod>(&mut self, value: &T) {
+        let scratchpad = self.checked_scratchpad::<T>();
+        self.device
+            .queue
+            .write_buffer(scratchpad, 0, bytemuck::bytes_of(value));
+    }
+
+    /// Returns the scratchpad after checking that a `T` can be copied at its beginning.
+    fn checked_scratchpad<T>(&self) -> &wgpu::Buffer {
+        let scratchpad = self.buffers.scratchpad.as_ref().expect("No scratchpad");
+        let size = std::mem::size_of::<T>() as u64;
+        assert!(
+            size <= scratchpad.size(),
+            "{} is {} bytes but the scratchpad is {} bytes",
+            std::any::type_name::<T>(),
+            size,
+            scratchpad.size()
+        );
+        assert!(
+            size > 0 && size.is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT),
+            "The size of {} must be a non-zero multiple of {} bytes",
+            std::any::type_name::<T>(),
+            wgpu::COPY_BUFFER_ALIGNMENT
+        );
+        scratchpad
+    }
+
+    /// This method is used to print the content of the scratchpad buffer. It is useful for debugging.
+    #[inline]
+    pub fn dbg_print_scratchpad<T: bytemuck::Pod + bytemuck::AnyBitPattern + std::fmt::Debug>(
         &mut self,
     ) {
         DownloadBuffer::read_buffer(
             &self.device.device,
             &self.device.queue,
-            &self.scratchpad.as_ref().expect("No scratchpad").slice(..),
+            &self
+                .buffers
+                .scratchpad
+                .as_ref()
+                .expect("No scratchpad")
+                .slice(..),
             |res| {
                 println!(
                     "Contents: {:?}",
@@ -369,51 +1625,513 @@ impl<'a, Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod, co
         workgroups: [(u32, u32, u32); N],
         callback: impl FnOnce(&Output) -> T + Send,
     ) -> T {
-        self.device
-            .queue
-            .write_buffer(&self.input, 0, bytemuck::bytes_of(input));
+        self.run_with_label(None, Some(input), workgroups, callback)
+            .await
+    }
+
+    /// Same as `PipelineAsync::run`, but the out-of-memory and validation errors of the run are caught with error scopes and returned, instead of panicking in the uncaptured error handler of the device. The callback may still be called on a failed run, its result is then dropped. The error scopes belong to the device, so the errors of work submitted concurrently on it by other threads may be caught too.
+    ///
+    /// # Errors
+    /// `SgpuError::DeviceLost` if the device was lost before the run, `SgpuError::OutOfMemory` if the device could not allocate a resource of the run, `SgpuError::Validation` if it rejected a command, e.g. a dispatch of too many workgroups.
+    pub async fn try_run<T: Send + 'static>(
+        &mut self,
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+        callback: impl FnOnce(&Output) -> T + Send,
+    ) -> Result<T, SgpuError> {
+        if self.device.is_device_lost() {
+            return Err(SgpuError::DeviceLost(
+                "the device was lost before the run".into(),
+            ));
+        }
+        let device = self.device.device.clone();
+        device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let res = self
+            .run_with_label(None, Some(input), workgroups, callback)
+            .await;
+        let validation = device.pop_error_scope().await;
+        let out_of_memory = device.pop_error_scope().await;
+        match validation.or(out_of_memory) {
+            Some(error) => Err(error.into()),
+            None => Ok(res),
+        }
+    }
+
+    /// Same as `PipelineAsync::run`, but gives up waiting after `timeout`, e.g. to recover from a shader looping forever instead of blocking the thread forever. The run is traced and checked like with `PipelineAsync::try_run`, the device is polled without blocking until the stages complete, then the output is read back. The stages of a timed out run keep running on the device, the next runs are queued after them. Some backends block in the submission itself, e.g. software rasterizers, the timeout can't interrupt them.
+    ///
+    /// # Errors
+    /// `SgpuError::TimedOut` if the stages didn't complete within `timeout`, or the errors of `PipelineAsync::try_run`.
+    pub async fn run_with_timeout<T: Send + 'static>(
+        &mut self,
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+        timeout: std::time::Duration,
+        callback: impl FnOnce(&Output) -> T + Send,
+    ) -> Result<T, SgpuError> {
+        if self.device.is_device_lost() {
+            return Err(SgpuError::DeviceLost(
+                "the device was lost before the run".into(),
+            ));
+        }
+        let deadline = std::time::Instant::now() + timeout;
+        let device = self.device.device.clone();
+        device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        self.submit_stages(None, Some(input), workgroups);
+        let submitted = std::time::Instant::now();
+        let (sender, receiver) = flume::bounded(1);
+        self.device.queue.on_submitted_work_done(move || {
+            let _ = sender.send(());
+        });
+        let completed = self.device.wait_until(&receiver, deadline);
+        let validation = device.pop_error_scope().await;
+        let out_of_memory = device.pop_error_scope().await;
+        if let Some(error) = validation.or(out_of_memory) {
+            return Err(error.into());
+        }
+        if !completed {
+            diagnostics::log_warn!("Run timed out after {:?}", timeout);
+            return Err(SgpuError::TimedOut(timeout));
+        }
+        // The output is only mapped once the stages completed, so a timed out run doesn't leave it pending.
+        let receiver = self.map_output();
+        self.device.wait_submitted();
+        receiver.recv_async().await.expect("Error with channel");
+        Ok(self.complete_run(None, submitted, callback).await)
+    }
+
+    /// Same as `PipelineAsync::run`, but the output is copied into `out` as a slice of `T`, e.g. `f32` for an `[f32; 1024]` output. The vector is cleared and its capacity is reused, so runs in a hot loop don't allocate once it is large enough.
+    ///
+    /// # Panics
+    /// If the size of `Output` is not a multiple of the size of `T`.
+    pub async fn run_readback_into_vec<T: bytemuck::Pod + Send>(
+        &mut self,
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+        out: &mut Vec<T>,
+    ) {
+        assert!(
+            std::mem::size_of::<T>() > 0
+                && std::mem::size_of::<Output>().is_multiple_of(std::mem::size_of::<T>()),
+            "The output can't be read as a slice of {}",
+            std::any::type_name::<T>()
+        );
+        self.run(input, workgroups, |output| {
+            let bytes = bytemuck::bytes_of(output);
+            out.clear();
+            match bytemuck::try_cast_slice::<u8, T>(bytes) {
+                Ok(values) => out.extend_from_slice(values),
+                // The mapped range may be less aligned than `T`.
+                Err(_) => out.extend(
+                    bytes
+                        .chunks_exact(std::mem::size_of::<T>())
+                        .map(bytemuck::pod_read_unaligned::<T>),
+                ),
+            }
+        })
+        .await
+    }
+
+    /// Same as `PipelineAsync::run`, but the input buffer is not written: the stages read what it already contains, e.g. the content of an external buffer bound with `PipelineAsync::set_input_buffer`, or data copied into `PipelineAsync::input_buffer` by other GPU commands. The input validator is skipped.
+    pub async fn run_without_upload<T: Send + 'static>(
+        &mut self,
+        workgroups: [(u32, u32, u32); N],
+        callback: impl FnOnce(&Output) -> T + Send,
+    ) -> T {
+        self.run_with_label(None, None, workgroups, callback).await
+    }
+
+    /// Same as `PipelineAsync::run`, but the output is left on the GPU: the stages are submitted without copying the output to the buffer mapped for the readback and without waiting for them. It is useful for iterative workloads only reading the result every few iterations with `PipelineAsync::read_output`, the next runs see the scratchpad and the output left by the previous ones unless the buffers are cleared before each run.
+    pub fn run_no_readback(&mut self, input: &Input, workgroups: [(u32, u32, u32); N]) {
+        self.dispatch(input, workgroups);
+    }
+
+    /// Same as `PipelineAsync::run`, but `label` is added to the labels of the command buffer and of the compute passes, to the debug markers and to the events of the trace, e.g. to find a specific frame in a GPU capture.
+    pub async fn run_labeled<T: Send + 'static>(
+        &mut self,
+        label: &str,
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+        callback: impl FnOnce(&Output) -> T + Send,
+    ) -> T {
+        self.run_with_label(Some(label), Some(input), workgroups, callback)
+            .await
+    }
+
+    /// This method is used to record the compute passes of the stages into `encoder`, created from the device of the pipeline, so that the application submits them with its own commands, e.g. in its frame graph. Nothing is written nor read back: the stages read the uniform and the input already in their buffers, e.g. written with `GpuComputeAsync::queue` into `PipelineAsync::input_buffer`, and leave their result in `PipelineAsync::output_buffer`. The buffers are cleared first with `ZeroInit::BeforeEachRun`, like in a run, but the passes are never split across command buffers since the encoder belongs to the application.
+    pub fn encode_passes(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        workgroups: [(u32, u32, u32); N],
+    ) {
+        self.record_stages_into(encoder, workgroups, None, None, false);
+    }
+
+    async fn run_with_label<T: Send + 'static>(
+        &mut self,
+        label: Option<&str>,
+        input: Option<&Input>,
+        workgroups: [(u32, u32, u32); N],
+        callback: impl FnOnce(&Output) -> T + Send,
+    ) -> T {
+        let (_, receiver) = self.submit(label, input, workgroups);
+        let submitted = std::time::Instant::now();
+        self.device.wait_submitted();
+        receiver.recv_async().await.expect("Error with channel");
+        self.complete_run(label, submitted, callback).await
+    }
+
+    /// Calls `callback` on the mapped output of the run submitted at `submitted` and unmaps it, then records the spans of the run if the pipeline is traced.
+    async fn complete_run<T: Send + 'static>(
+        &self,
+        label: Option<&str>,
+        submitted: std::time::Instant,
+        callback: impl FnOnce(&Output) -> T + Send,
+    ) -> T {
+        let mapped = std::time::Instant::now();
+        let res = callback(bytemuck::from_bytes(
+            self.buffers.output.slice(..).get_mapped_range().as_ref(),
+        ));
+        self.buffers.output.unmap();
+        if let Some(tracing) = &self.tracing {
+            tracing
+                .trace
+                .record_cpu(&labeled("map", label), submitted, mapped);
+            tracing.trace.record_cpu(
+                &labeled("callback", label),
+                mapped,
+                std::time::Instant::now(),
+            );
+            if let Some(timestamps) = &tracing.timestamps {
+                self.record_stages(&tracing.trace, timestamps, submitted, label)
+                    .await;
+            }
+        }
+        res
+    }
+
+    /// Reads the timestamps of the last run and records the span of each stage, starting at `origin`.
+    async fn record_stages(
+        &self,
+        trace: &trace::Trace,
+        timestamps: &trace::Timestamps,
+        origin: std::time::Instant,
+        label: Option<&str>,
+    ) {
+        let (sender, receiver) = flume::bounded(1);
+        timestamps
+            .readback
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |e| {
+                e.expect("Could not map timestamps");
+                sender.send(()).unwrap()
+            });
+        self.device.wait_submitted();
+        receiver.recv_async().await.expect("Error with channel");
+        let period = self.device.queue.get_timestamp_period() as f64;
+        {
+            let range = timestamps.readback.slice(..).get_mapped_range();
+            let ticks: &[u64] = bytemuck::cast_slice(&range);
+            let first = ticks[0];
+            let to_duration =
+                |ticks: u64| std::time::Duration::from_nanos((ticks as f64 * period) as u64);
+            for (desc, span) in self.stages.desc.iter().zip(ticks.chunks_exact(2)) {
+                trace.record_gpu(
+                    labeled(desc.name.unwrap_or(desc.entrypoint), label),
+                    origin,
+                    to_duration(span[0].saturating_sub(first)),
+                    to_duration(span[1].saturating_sub(span[0])),
+                );
+            }
+        }
+        timestamps.readback.unmap();
+    }
+
+    /// This method is used to read back the output left by the last stages submitted, e.g. by `PipelineAsync::run_no_readback`, and call `callback` on it like `PipelineAsync::run` does. It waits for the stages to complete.
+    pub async fn read_output<T>(&mut self, callback: impl FnOnce(&Output) -> T) -> T {
         let mut encoder = self
             .device
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
-        for i in 0..N {
-            let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: self.stages_desc[i]
-                    .name
-                    .map(|n| format!("Compute pass for stage {}", n))
-                    .as_ref()
-                    .map(AsRef::as_ref),
-                timestamp_writes: None,
-            });
-            cpass.set_pipeline(&self.stages[i]);
-            cpass.set_bind_group(0, &self.bindgroup, &[]);
-            cpass.insert_debug_marker(
-                &self.stages_desc[i]
-                    .name
-                    .map_or_else(|| format!("sgpu-{}", i), |n| format!("sgpu-{}", n)),
-            );
-            cpass.dispatch_workgroups(workgroups[i].0, workgroups[i].1, workgroups[i].2);
-        }
         encoder.copy_buffer_to_buffer(
-            &self.staging,
+            &self.buffers.staging,
             0,
-            &self.output,
+            &self.buffers.output,
             0,
             std::mem::size_of::<Output>() as _,
         );
         self.device.queue.submit(Some(encoder.finish()));
         let (sender, receiver) = flume::bounded(1);
-        self.output
+        self.buffers
+            .output
             .slice(..)
             .map_async(wgpu::MapMode::Read, move |e| {
-                sender.send(e.expect("Could not map buffer")).unwrap()
+                e.expect("Could not map buffer");
+                sender.send(()).unwrap()
             });
-        self.device.device.poll(wgpu::Maintain::Wait);
+        self.device.wait_submitted();
         receiver.recv_async().await.expect("Error with channel");
         let res = callback(bytemuck::from_bytes(
-            self.output.slice(..).get_mapped_range().as_ref(),
+            self.buffers.output.slice(..).get_mapped_range().as_ref(),
         ));
-        self.output.unmap();
+        self.buffers.output.unmap();
         res
     }
+
+    /// Writes the input if there is one, submits the stages and the copy to the output buffer, then requests the mapping of the output buffer. The receiver gets a message once the output buffer is mapped.
+    fn submit(
+        &mut self,
+        label: Option<&str>,
+        input: Option<&Input>,
+        workgroups: [(u32, u32, u32); N],
+    ) -> (wgpu::SubmissionIndex, flume::Receiver<()>) {
+        let index = self.submit_stages(label, input, workgroups);
+        (index, self.map_output())
+    }
+
+    /// Same as `PipelineAsync::submit`, without requesting the mapping of the output buffer.
+    fn submit_stages(
+        &self,
+        label: Option<&str>,
+        input: Option<&Input>,
+        workgroups: [(u32, u32, u32); N],
+    ) -> wgpu::SubmissionIndex {
+        let timestamps = self
+            .tracing
+            .as_ref()
+            .and_then(|tracing| tracing.timestamps.as_ref());
+        self.encode_and_submit(label, input, workgroups, true, timestamps)
+    }
+
+    /// Requests the mapping of the output buffer, the receiver gets a message once it is mapped.
+    fn map_output(&self) -> flume::Receiver<()> {
+        let (sender, receiver) = flume::bounded(1);
+        self.buffers
+            .output
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |e| {
+                e.expect("Could not map buffer");
+                sender.send(()).unwrap()
+            });
+        receiver
+    }
+
+    /// Writes the input and submits the stages without copying the output, the next run sees the scratchpad and the output they leave.
+    pub(crate) fn dispatch(&mut self, input: &Input, workgroups: [(u32, u32, u32); N]) {
+        self.encode_and_submit(None, Some(input), workgroups, false, None);
+    }
+
+    /// Writes the input if there is one and submits the stages in `range` without copying the output, then waits for them to complete. The buffers are cleared first with `ZeroInit::BeforeEachRun` if the range starts at the first stage, so a run can be submitted in several parts and read back with `PipelineAsync::read_output`.
+    #[cfg(feature = "blocking")]
+    pub(crate) fn submit_stage_range(
+        &self,
+        input: Option<&Input>,
+        workgroups: [(u32, u32, u32); N],
+        range: std::ops::Range<usize>,
+    ) {
+        self.write_input(input);
+        let mut encoder = self
+            .device
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        if range.start == 0 && self.zero_init == ZeroInit::BeforeEachRun {
+            self.buffers.clear(&mut encoder, false);
+        }
+        for i in range {
+            self.record_stage(&mut encoder, i, workgroups[i], None, None, None);
+        }
+        self.device.queue.submit(Some(encoder.finish()));
+        let (sender, receiver) = flume::bounded(1);
+        self.device.queue.on_submitted_work_done(move || {
+            let _ = sender.send(());
+        });
+        self.device.wait_submitted();
+        receiver.recv().expect("Error with channel");
+    }
+
+    /// Writes the input if there is one and submits the stages, followed by the copy to the output buffer if `readback` is set. The stages write their timestamps in `timestamps` if there are some, which are then resolved into its readback buffer.
+    fn encode_and_submit(
+        &self,
+        label: Option<&str>,
+        input: Option<&Input>,
+        workgroups: [(u32, u32, u32); N],
+        readback: bool,
+        timestamps: Option<&trace::Timestamps>,
+    ) -> wgpu::SubmissionIndex {
+        let start = std::time::Instant::now();
+        self.write_input(input);
+        let uploaded = std::time::Instant::now();
+        let mut encoder = self
+            .device
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label });
+        self.record_stages_into(&mut encoder, workgroups, label, timestamps, true);
+        if readback {
+            encoder.copy_buffer_to_buffer(
+                &self.buffers.staging,
+                0,
+                &self.buffers.output,
+                0,
+                std::mem::size_of::<Output>() as _,
+            );
+        }
+        if let Some(timestamps) = timestamps {
+            encoder.resolve_query_set(
+                &timestamps.query_set,
+                0..2 * N as u32,
+                &timestamps.resolve,
+                0,
+            );
+            encoder.copy_buffer_to_buffer(
+                &timestamps.resolve,
+                0,
+                &timestamps.readback,
+                0,
+                timestamps.readback.size(),
+            );
+        }
+        let index = self.device.queue.submit(Some(encoder.finish()));
+        if let Some(tracing) = &self.tracing {
+            tracing
+                .trace
+                .record_cpu(&labeled("upload", label), start, uploaded);
+            tracing.trace.record_cpu(
+                &labeled("submit", label),
+                uploaded,
+                std::time::Instant::now(),
+            );
+        }
+        index
+    }
+
+    /// Records the clearing of the buffers if they are cleared before each run, and the compute pass of each stage. If `split` is set, the encoder is submitted and replaced when it reaches the maximum number of passes of the device.
+    fn record_stages_into(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        workgroups: [(u32, u32, u32); N],
+        label: Option<&str>,
+        timestamps: Option<&trace::Timestamps>,
+        split: bool,
+    ) {
+        if self.zero_init == ZeroInit::BeforeEachRun {
+            self.buffers.clear(encoder, false);
+        }
+        for (i, workgroup) in workgroups.iter().enumerate() {
+            if split {
+                self.device.split_submission(encoder, i, label);
+            }
+            self.record_stage(encoder, i, *workgroup, label, timestamps, None);
+        }
+    }
+
+    /// Checks the input with the input validator in debug builds and writes it if there is one.
+    fn write_input(&self, input: Option<&Input>) {
+        if cfg!(debug_assertions) {
+            if let (Some(validator), Some(input)) = (&self.validator, input) {
+                if let Err(message) = validator(input) {
+                    panic!("Invalid input: {}", message);
+                }
+            }
+        }
+        if let (Some(buffer), Some(input)) = (&self.buffers.input, input) {
+            self.device
+                .queue
+                .write_buffer(buffer, 0, bytemuck::bytes_of(input));
+        }
+    }
+
+    /// Records the compute pass of the stage at `index`, writing its timestamps in `timestamps` and its pipeline statistics at `index` in `statistics` if there are some.
+    fn record_stage(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        index: usize,
+        workgroup: (u32, u32, u32),
+        label: Option<&str>,
+        timestamps: Option<&trace::Timestamps>,
+        statistics: Option<&wgpu::QuerySet>,
+    ) {
+        self.stages
+            .pass(
+                index,
+                self.buffers.stage_bindgroup(index),
+                &self.push_constants,
+            )
+            .record(
+                encoder,
+                &self.device,
+                workgroup,
+                label,
+                timestamps,
+                statistics,
+            );
+    }
+}
+
+/// Compute pass of a stage, recorded the same way by the pipelines and by the `WgpuExecutor`.
+pub(crate) struct StagePass<'p> {
+    pub(crate) pipeline: &'p wgpu::ComputePipeline,
+    pub(crate) bindgroup: &'p wgpu::BindGroup,
+    pub(crate) push_constants: &'p [u8],
+    pub(crate) shared_uniform: bool,
+    pub(crate) name: Option<&'static str>,
+    pub(crate) index: usize,
+}
+
+impl StagePass<'_> {
+    /// Records the pass on `encoder`, binding the shared uniform of `gpu` if the stage uses it, writing its timestamps in `timestamps` and its pipeline statistics at its index in `statistics` if there are some.
+    pub(crate) fn record(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        gpu: &GpuComputeAsync,
+        workgroup: (u32, u32, u32),
+        label: Option<&str>,
+        timestamps: Option<&trace::Timestamps>,
+        statistics: Option<&wgpu::QuerySet>,
+    ) {
+        let index = self.index;
+        let pass_label = match (self.name, label) {
+            (Some(n), Some(label)) => Some(format!("Compute pass for stage {} ({})", n, label)),
+            (Some(n), None) => Some(format!("Compute pass for stage {}", n)),
+            (None, Some(label)) => Some(format!("Compute pass ({})", label)),
+            (None, None) => None,
+        };
+        let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: pass_label.as_deref(),
+            timestamp_writes: timestamps.map(|timestamps| wgpu::ComputePassTimestampWrites {
+                query_set: &timestamps.query_set,
+                beginning_of_pass_write_index: Some(2 * index as u32),
+                end_of_pass_write_index: Some(2 * index as u32 + 1),
+            }),
+        });
+        cpass.set_pipeline(self.pipeline);
+        cpass.set_bind_group(0, self.bindgroup, &[]);
+        if !self.push_constants.is_empty() {
+            cpass.set_push_constants(0, self.push_constants);
+        }
+        if self.shared_uniform {
+            cpass.set_bind_group(shared::GROUP, &gpu.shared_uniform().bindgroup, &[]);
+        }
+        cpass.insert_debug_marker(&labeled(
+            &self
+                .name
+                .map_or_else(|| format!("sgpu-{}", index), |n| format!("sgpu-{}", n)),
+            label,
+        ));
+        if let Some(statistics) = statistics {
+            cpass.begin_pipeline_statistics_query(statistics, index as u32);
+        }
+        cpass.dispatch_workgroups(workgroup.0, workgroup.1, workgroup.2);
+        if statistics.is_some() {
+            cpass.end_pipeline_statistics_query();
+        }
+    }
+}
+
+/// Appends the label of a run to `name`.
+fn labeled(name: &str, label: Option<&str>) -> String {
+    match label {
+        Some(label) => format!("{} ({})", name, label),
+        None => name.to_string(),
+    }
 }
@@ -0,0 +1,291 @@
+//! Lints of the WGSL shaders.
+//!
+//! A callback set with `GpuComputeAsync::set_lint_callback` receives the warnings of the shaders of each pipeline when it is generated. The lints catch mistakes which compile but rarely do what was meant:
+//!     - `Lint::Validation`: the shader doesn't parse or doesn't validate, the message is the error of naga with its location
+//!     - `Lint::UnusedBinding`: a binding is declared but used by none of the entry points of the pipeline
+//!     - `Lint::MissingBarrier`: an entry point reads and writes a `var<workgroup>` without any `workgroupBarrier`
+//!     - `Lint::NonUniformBarrier`: an entry point reaches a barrier in non-uniform control flow, e.g. in a branch depending on the invocation id
+//!
+//! `lint` can also be called directly, e.g. in a test of the shaders.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::lint::{self, Lint};
+//!
+//! let shader = "
+//!     @group(0) @binding(0) var<storage, read> in: array<u32>;
+//!     @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+//!     @group(0) @binding(2) var<storage, read_write> unused: array<u32>;
+//!     var<workgroup> tile: array<u32, 4>;
+//!
+//!     @compute @workgroup_size(4)
+//!     fn main(@builtin(local_invocation_index) i: u32) {
+//!         tile[i] = in[i];
+//!         out[i] = tile[3u - i];
+//!     }
+//! ";
+//! let stages = [StageDesc::new(shader, "main").with_name("reverse")];
+//! let lints: Vec<Lint> = lint::lint(&stages).into_iter().map(|warning| warning.lint).collect();
+//! assert_eq!(lints, [Lint::UnusedBinding, Lint::MissingBarrier]);
+//! ```
+use crate::{GpuComputeAsync, ShaderSource, StageDesc};
+use std::{borrow::Cow, fmt};
+use wgpu::naga;
+
+/// Kind of a lint warning, see the module documentation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Lint {
+    Validation,
+    UnusedBinding,
+    MissingBarrier,
+    NonUniformBarrier,
+}
+
+/// A warning about the shader of a stage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    /// Name of the stage, or its entry point if it has no name.
+    pub stage: &'static str,
+    pub lint: Lint,
+    pub message: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} in stage `{}`: {}",
+            self.lint, self.stage, self.message
+        )
+    }
+}
+
+/// Callback receiving the lint warnings of the pipelines.
+pub(crate) type Callback = Box<dyn Fn(&Warning) + Send + Sync>;
+
+impl GpuComputeAsync {
+    /// This method is used to lint the shaders of the pipelines generated after it, `callback` receives each warning, see the `lint` module.
+    pub fn set_lint_callback(&mut self, callback: impl Fn(&Warning) + Send + Sync + 'static) {
+        self.lint = Some(Box::new(callback));
+    }
+}
+
+/// Lints the stages of a pipeline whose `shader` is the WGSL source.
+pub fn lint(stages: &[StageDesc]) -> Vec<Warning> {
+    let sources = stages
+        .iter()
+        .map(|desc| Cow::Borrowed(desc.shader))
+        .collect::<Vec<_>>();
+    lint_sources(stages, &sources)
+}
+
+/// Lints the stages of a pipeline with the given sources.
+pub(crate) fn lint_sources(stages: &[StageDesc], sources: &[Cow<'_, str>]) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    for (index, (desc, source)) in stages.iter().zip(sources).enumerate() {
+        let stage = desc.name.unwrap_or(desc.entrypoint);
+        let mut warn = |lint, message| {
+            warnings.push(Warning {
+                stage,
+                lint,
+                message,
+            })
+        };
+        if desc.source != ShaderSource::Wgsl {
+            // The lints target the WGSL written by hand, not binaries.
+            continue;
+        }
+        let module = match naga::front::wgsl::parse_str(source) {
+            Ok(module) => module,
+            Err(error) => {
+                warn(Lint::Validation, error.emit_to_string(source));
+                continue;
+            }
+        };
+        let info = match naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::all(),
+        )
+        .validate(&module)
+        {
+            Ok(info) => info,
+            Err(error) => {
+                warn(Lint::Validation, error.emit_to_string(source));
+                continue;
+            }
+        };
+        let Some(entry_index) = module
+            .entry_points
+            .iter()
+            .position(|entry_point| entry_point.name == desc.entrypoint)
+        else {
+            warn(
+                Lint::Validation,
+                format!("no entry point named `{}`", desc.entrypoint),
+            );
+            continue;
+        };
+
+        // A shader shared by several stages is only checked once for unused bindings, with the entry points of all these stages.
+        let first = sources.iter().position(|other| other == source) == Some(index);
+        if first {
+            let entry_points = stages
+                .iter()
+                .zip(sources)
+                .filter(|(_, other)| *other == source)
+                .filter_map(|(desc, _)| {
+                    module
+                        .entry_points
+                        .iter()
+                        .position(|entry_point| entry_point.name == desc.entrypoint)
+                })
+                .collect::<Vec<_>>();
+            for (handle, global) in module.global_variables.iter() {
+                let Some(binding) = &global.binding else {
+                    continue;
+                };
+                let used = entry_points
+                    .iter()
+                    .any(|index| !info.get_entry_point(*index)[handle].is_empty());
+                if !used {
+                    warn(
+                        Lint::UnusedBinding,
+                        format!(
+                            "@group({}) @binding({}) `{}` is not used",
+                            binding.group,
+                            binding.binding,
+                            global.name.as_deref().unwrap_or("_")
+                        ),
+                    );
+                }
+            }
+        }
+
+        let entry_info = info.get_entry_point(entry_index);
+        let shared = module
+            .global_variables
+            .iter()
+            .filter(|(handle, global)| {
+                global.space == naga::AddressSpace::WorkGroup
+                    && entry_info[*handle].contains(naga::valid::GlobalUse::READ)
+                    && entry_info[*handle].contains(naga::valid::GlobalUse::WRITE)
+            })
+            .filter_map(|(_, global)| global.name.clone())
+            .collect::<Vec<_>>();
+        let body = &module.entry_points[entry_index].function.body;
+        if !shared.is_empty() && !has_barrier(&module, body, naga::Barrier::WORK_GROUP) {
+            warn(
+                Lint::MissingBarrier,
+                format!(
+                    "`{}` is written and read without any `workgroupBarrier`",
+                    shared.join("`, `")
+                ),
+            );
+        }
+        if non_uniform_barrier(&module, entry_info, body, false) {
+            warn(
+                Lint::NonUniformBarrier,
+                "a barrier is in a branch or a loop depending on the invocation, the invocations of a workgroup must all reach it".into(),
+            );
+        }
+    }
+    warnings
+}
+
+/// Whether a block has a barrier in control flow depending on a non-uniform value, according to the uniformity analysis of naga. `non_uniform` is set inside such control flow.
+fn non_uniform_barrier(
+    module: &naga::Module,
+    info: &naga::valid::FunctionInfo,
+    block: &naga::Block,
+    non_uniform: bool,
+) -> bool {
+    let is_non_uniform = |expression: naga::Handle<naga::Expression>| {
+        info[expression].uniformity.non_uniform_result.is_some()
+    };
+    if non_uniform && has_barrier(module, block, naga::Barrier::all()) {
+        return true;
+    }
+    block.iter().any(|statement| match statement {
+        naga::Statement::Block(block) => non_uniform_barrier(module, info, block, non_uniform),
+        naga::Statement::If {
+            condition,
+            accept,
+            reject,
+        } => {
+            let non_uniform = non_uniform || is_non_uniform(*condition);
+            non_uniform_barrier(module, info, accept, non_uniform)
+                || non_uniform_barrier(module, info, reject, non_uniform)
+        }
+        naga::Statement::Switch { selector, cases } => {
+            let non_uniform = non_uniform || is_non_uniform(*selector);
+            cases
+                .iter()
+                .any(|case| non_uniform_barrier(module, info, &case.body, non_uniform))
+        }
+        naga::Statement::Loop {
+            body,
+            continuing,
+            break_if,
+        } => {
+            // The iterations are non-uniform if the loop is left depending on a non-uniform value.
+            let non_uniform = non_uniform
+                || break_if.is_some_and(is_non_uniform)
+                || non_uniform_break(info, body);
+            non_uniform_barrier(module, info, body, non_uniform)
+                || non_uniform_barrier(module, info, continuing, non_uniform)
+        }
+        _ => false,
+    })
+}
+
+/// Whether a loop body breaks or returns in a branch depending on a non-uniform value, which is how naga lowers the condition of `for` and `while` loops.
+fn non_uniform_break(info: &naga::valid::FunctionInfo, block: &naga::Block) -> bool {
+    block.iter().any(|statement| match statement {
+        naga::Statement::If {
+            condition,
+            accept,
+            reject,
+        } => {
+            (info[*condition].uniformity.non_uniform_result.is_some()
+                && (exits(accept) || exits(reject)))
+                || non_uniform_break(info, accept)
+                || non_uniform_break(info, reject)
+        }
+        naga::Statement::Block(block) => non_uniform_break(info, block),
+        _ => false,
+    })
+}
+
+/// Whether a block leaves the loop containing it, without looking into nested loops.
+fn exits(block: &naga::Block) -> bool {
+    block.iter().any(|statement| match statement {
+        naga::Statement::Break | naga::Statement::Return { .. } => true,
+        naga::Statement::Block(block) => exits(block),
+        naga::Statement::If { accept, reject, .. } => exits(accept) || exits(reject),
+        _ => false,
+    })
+}
+
+/// Whether a block or a function it calls contains a barrier synchronizing `scope`.
+fn has_barrier(module: &naga::Module, block: &naga::Block, scope: naga::Barrier) -> bool {
+    block.iter().any(|statement| match statement {
+        naga::Statement::Barrier(barrier) => barrier.intersects(scope),
+        // `workgroupUniformLoad` synchronizes the workgroup like a barrier.
+        naga::Statement::WorkGroupUniformLoad { .. } => scope.contains(naga::Barrier::WORK_GROUP),
+        naga::Statement::Block(block) => has_barrier(module, block, scope),
+        naga::Statement::If { accept, reject, .. } => {
+            has_barrier(module, accept, scope) || has_barrier(module, reject, scope)
+        }
+        naga::Statement::Switch { cases, .. } => cases
+            .iter()
+            .any(|case| has_barrier(module, &case.body, scope)),
+        naga::Statement::Loop {
+            body, continuing, ..
+        } => has_barrier(module, body, scope) || has_barrier(module, continuing, scope),
+        // WGSL forbids recursion, so the calls can't loop.
+        naga::Statement::Call { function, .. } => {
+            has_barrier(module, &module.functions[*function].body, scope)
+        }
+        _ => false,
+    })
+}
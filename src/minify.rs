@@ -0,0 +1,565 @@
+//! Minification of WGSL shaders.
+//!
+//! `minify` strips the comments and the whitespace of a shader and can mangle the names of its functions, structs, variables and parameters, e.g. before embedding proprietary kernels in a distributed binary. Entry points, overrides and struct members keep their names since they are used by the host, and the address spaces, access modes and texel formats in the template arguments are never renamed, even if a declaration has the same name. The returned `Minified` maps the mangled names back to the original ones, which is used to demangle the errors of a minified shader. `MinifyOptions::DEBUG` keeps the shader untouched, so the same code path can be used in debug builds.
+//!
+//! Minifying at build time in a `build.rs` avoids shipping the original shader at all, the minified source can then be included with `include_str!`.
+//!
+//! ```rust
+//! use sgpu_compute::minify::{minify, MinifyOptions};
+//!
+//! let shader = "
+//!     // Scales the input by two.
+//!     @group(0) @binding(0) var<storage, read> in: array<u32>;
+//!     @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+//!
+//!     fn scale(value: u32) -> u32 {
+//!         return value * 2u;
+//!     }
+//!
+//!     @compute @workgroup_size(64)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[id.x] = scale(in[id.x]);
+//!     }
+//! ";
+//! let minified = minify(shader, MinifyOptions::RELEASE);
+//! assert_eq!(
+//!     minified.source,
+//!     "@group(0)@binding(0)var<storage,read>_a:array<u32>;@group(0)@binding(1)var<storage,read_write>_b:array<u32>;fn _c(_d:u32)->u32{return _d*2u;}@compute@workgroup_size(64)fn main(@builtin(global_invocation_id)_e:vec3<u32>){_b[_e.x]=_c(_a[_e.x]);}"
+//! );
+//! assert_eq!(minified.original_name("_c"), Some("scale"));
+//! assert_eq!(minified.demangle("unknown identifier `_d`"), "unknown identifier `value`");
+//! ```
+use std::collections::{HashMap, HashSet};
+
+/// Options of `minify`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MinifyOptions {
+    pub strip_comments: bool,
+    pub strip_whitespace: bool,
+    pub mangle_names: bool,
+}
+
+impl MinifyOptions {
+    /// Keeps the shader untouched.
+    pub const DEBUG: Self = Self {
+        strip_comments: false,
+        strip_whitespace: false,
+        mangle_names: false,
+    };
+
+    /// Strips everything that can be stripped and mangles the names.
+    pub const RELEASE: Self = Self {
+        strip_comments: true,
+        strip_whitespace: true,
+        mangle_names: true,
+    };
+}
+
+/// A minified shader and the original names of its mangled identifiers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Minified {
+    pub source: String,
+    names: HashMap<String, String>,
+}
+
+impl Minified {
+    /// Returns the original name of a mangled identifier.
+    #[inline]
+    pub fn original_name(&self, mangled: &str) -> Option<&str> {
+        self.names.get(mangled).map(String::as_str)
+    }
+
+    /// Mangled identifiers and their original names.
+    #[inline]
+    pub fn names(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.names
+            .iter()
+            .map(|(mangled, original)| (mangled.as_str(), original.as_str()))
+    }
+
+    /// Replaces the mangled identifiers of `message`, e.g. a shader compilation error, by their original names.
+    pub fn demangle(&self, message: &str) -> String {
+        let mut demangled = String::with_capacity(message.len());
+        let mut chars = message.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            if is_ident_start(c) {
+                let mut end = start + c.len_utf8();
+                while let Some(&(i, c)) = chars.peek() {
+                    if !is_ident_continue(c) {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                let word = &message[start..end];
+                demangled.push_str(self.original_name(word).unwrap_or(word));
+            } else {
+                demangled.push(c);
+            }
+        }
+        demangled
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Kind {
+    Ident,
+    Number,
+    Punct,
+    Comment,
+    Whitespace,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Token<'s> {
+    pub(crate) kind: Kind,
+    pub(crate) text: &'s str,
+}
+
+#[inline]
+fn is_ident_start(c: char) -> bool {
+    c == '_' || c.is_alphabetic()
+}
+
+#[inline]
+fn is_ident_continue(c: char) -> bool {
+    c == '_' || c.is_alphanumeric()
+}
+
+pub(crate) fn tokenize(source: &str) -> Vec<Token<'_>> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < source.len() {
+        let c = source[i..].chars().next().expect("i is in bounds");
+        let start = i;
+        let kind = if c.is_whitespace() {
+            while let Some(c) = source[i..].chars().next().filter(|c| c.is_whitespace()) {
+                i += c.len_utf8();
+            }
+            Kind::Whitespace
+        } else if source[i..].starts_with("//") {
+            i = source[i..].find('\n').map_or(source.len(), |end| i + end);
+            Kind::Comment
+        } else if source[i..].starts_with("/*") {
+            // Block comments can be nested.
+            let mut depth = 0;
+            while i < source.len() {
+                if source[i..].starts_with("/*") {
+                    depth += 1;
+                    i += 2;
+                } else if source[i..].starts_with("*/") {
+                    depth -= 1;
+                    i += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    i += source[i..].chars().next().map_or(1, char::len_utf8);
+                }
+            }
+            Kind::Comment
+        } else if c.is_ascii_digit()
+            || (c == '.' && bytes.get(i + 1).is_some_and(u8::is_ascii_digit))
+        {
+            let hex = source[i..].starts_with("0x") || source[i..].starts_with("0X");
+            let exponent = if hex { [b'p', b'P'] } else { [b'e', b'E'] };
+            while let Some(&b) = bytes.get(i) {
+                let sign = (b == b'+' || b == b'-') && exponent.contains(&bytes[i - 1]);
+                if !(b.is_ascii_alphanumeric() || b == b'.' || b == b'_' || sign) {
+                    break;
+                }
+                i += 1;
+            }
+            Kind::Number
+        } else if is_ident_start(c) {
+            while let Some(c) = source[i..].chars().next().filter(|&c| is_ident_continue(c)) {
+                i += c.len_utf8();
+            }
+            Kind::Ident
+        } else {
+            i += c.len_utf8();
+            Kind::Punct
+        };
+        tokens.push(Token {
+            kind,
+            text: &source[start..i],
+        });
+    }
+    tokens
+}
+
+/// Predeclared types and built-in functions of WGSL. A declaration shadowing one of them is not mangled, since the built-in can't be told apart from the declaration without resolving the scopes.
+const PREDECLARED: &[&str] = &[
+    "bool",
+    "i32",
+    "u32",
+    "f32",
+    "f16",
+    "vec2",
+    "vec3",
+    "vec4",
+    "mat2x2",
+    "mat2x3",
+    "mat2x4",
+    "mat3x2",
+    "mat3x3",
+    "mat3x4",
+    "mat4x2",
+    "mat4x3",
+    "mat4x4",
+    "array",
+    "atomic",
+    "ptr",
+    "sampler",
+    "sampler_comparison",
+    "texture_1d",
+    "texture_2d",
+    "texture_2d_array",
+    "texture_3d",
+    "texture_cube",
+    "texture_cube_array",
+    "texture_multisampled_2d",
+    "texture_storage_1d",
+    "texture_storage_2d",
+    "texture_storage_2d_array",
+    "texture_storage_3d",
+    "texture_depth_2d",
+    "texture_depth_2d_array",
+    "texture_depth_cube",
+    "texture_depth_cube_array",
+    "texture_depth_multisampled_2d",
+    "bitcast",
+    "all",
+    "any",
+    "select",
+    "arrayLength",
+    "abs",
+    "acos",
+    "acosh",
+    "asin",
+    "asinh",
+    "atan",
+    "atanh",
+    "atan2",
+    "ceil",
+    "clamp",
+    "cos",
+    "cosh",
+    "countLeadingZeros",
+    "countOneBits",
+    "countTrailingZeros",
+    "cross",
+    "degrees",
+    "determinant",
+    "distance",
+    "dot",
+    "exp",
+    "exp2",
+    "extractBits",
+    "faceForward",
+    "firstLeadingBit",
+    "firstTrailingBit",
+    "floor",
+    "fma",
+    "fract",
+    "frexp",
+    "insertBits",
+    "inverseSqrt",
+    "ldexp",
+    "length",
+    "log",
+    "log2",
+    "max",
+    "min",
+    "mix",
+    "modf",
+    "normalize",
+    "pow",
+    "quantizeToF16",
+    "radians",
+    "reflect",
+    "refract",
+    "reverseBits",
+    "round",
+    "saturate",
+    "sign",
+    "sin",
+    "sinh",
+    "smoothstep",
+    "sqrt",
+    "step",
+    "tan",
+    "tanh",
+    "transpose",
+    "trunc",
+    "dpdx",
+    "dpdxCoarse",
+    "dpdxFine",
+    "dpdy",
+    "dpdyCoarse",
+    "dpdyFine",
+    "fwidth",
+    "fwidthCoarse",
+    "fwidthFine",
+    "textureDimensions",
+    "textureGather",
+    "textureGatherCompare",
+    "textureLoad",
+    "textureNumLayers",
+    "textureNumLevels",
+    "textureNumSamples",
+    "textureSample",
+    "textureSampleBias",
+    "textureSampleCompare",
+    "textureSampleCompareLevel",
+    "textureSampleGrad",
+    "textureSampleLevel",
+    "textureSampleBaseClampToEdge",
+    "textureStore",
+    "atomicLoad",
+    "atomicStore",
+    "atomicAdd",
+    "atomicSub",
+    "atomicMax",
+    "atomicMin",
+    "atomicAnd",
+    "atomicOr",
+    "atomicXor",
+    "atomicExchange",
+    "atomicCompareExchangeWeak",
+    "pack4x8snorm",
+    "pack4x8unorm",
+    "pack2x16snorm",
+    "pack2x16unorm",
+    "pack2x16float",
+    "unpack4x8snorm",
+    "unpack4x8unorm",
+    "unpack2x16snorm",
+    "unpack2x16unorm",
+    "unpack2x16float",
+    "storageBarrier",
+    "workgroupBarrier",
+    "workgroupUniformLoad",
+];
+
+/// Names declared by the shader that can be mangled, in order of declaration.
+fn mangleable_names<'s>(tokens: &[&Token<'s>]) -> Vec<&'s str> {
+    let mut names = Vec::new();
+    let mut entry_point = false;
+    let mut in_params = false;
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        let next = tokens.get(i + 1).map(|token| token.text);
+        match token.text {
+            "compute" | "vertex" | "fragment" if i > 0 && tokens[i - 1].text == "@" => {
+                entry_point = true
+            }
+            "fn" => {
+                if let Some(name) = next.filter(|_| !entry_point) {
+                    names.push(name);
+                }
+                entry_point = false;
+                in_params = true;
+                depth = 0;
+            }
+            "struct" | "alias" | "let" | "const" => names.extend(next),
+            "var" => {
+                // Skips the address space and the access mode, e.g. `var<storage, read>`.
+                let mut j = i + 1;
+                if tokens.get(j).is_some_and(|token| token.text == "<") {
+                    while tokens.get(j).is_some_and(|token| token.text != ">") {
+                        j += 1;
+                    }
+                    j += 1;
+                }
+                names.extend(tokens.get(j).map(|token| token.text));
+            }
+            "(" if in_params => depth += 1,
+            ")" if in_params => {
+                depth -= 1;
+                in_params = depth > 0;
+            }
+            _ if in_params && depth == 1 && token.kind == Kind::Ident && next == Some(":") => {
+                names.push(token.text)
+            }
+            _ => {}
+        }
+    }
+    let mut seen = HashSet::new();
+    names.retain(|name| !PREDECLARED.contains(name) && seen.insert(*name));
+    names
+}
+
+/// Identifiers that must keep their name wherever they appear: struct members, member accesses (which include swizzles) and the arguments of the attributes which are not expressions, e.g. `@builtin(position)`.
+fn fixed_positions(tokens: &[&Token<'_>]) -> Vec<bool> {
+    let mut fixed = vec![false; tokens.len()];
+    let mut struct_depth = None;
+    let mut depth = 0;
+    let mut attribute_depth = None;
+    for (i, token) in tokens.iter().enumerate() {
+        let previous = i.checked_sub(1).map(|i| tokens[i].text);
+        let next = tokens.get(i + 1).map(|token| token.text);
+        match token.text {
+            "{" => {
+                depth += 1;
+                if struct_depth.is_none() && i >= 2 && tokens[i - 2].text == "struct" {
+                    struct_depth = Some(depth);
+                }
+            }
+            "}" => {
+                if struct_depth == Some(depth) {
+                    struct_depth = None;
+                }
+                depth -= 1;
+            }
+            "(" if attribute_depth.is_none()
+                && i >= 2
+                && tokens[i - 2].text == "@"
+                && matches!(tokens[i - 1].text, "builtin" | "interpolate" | "diagnostic") =>
+            {
+                attribute_depth = Some(depth)
+            }
+            ")" if attribute_depth == Some(depth) => attribute_depth = None,
+            _ if token.kind == Kind::Ident => {
+                fixed[i] |= previous == Some(".")
+                    || attribute_depth.is_some()
+                    || (struct_depth == Some(depth) && next == Some(":"));
+                if next == Some("<") {
+                    fix_enumerants(tokens, i, &mut fixed);
+                }
+            }
+            _ => {}
+        }
+    }
+    fixed
+}
+
+/// Fixes the address spaces, access modes and texel formats in the template arguments of the token at `i`, e.g. `var<storage, read>` or `ptr<function, T>`. They are context-dependent names, so a declaration named `read` is renamed everywhere but there.
+fn fix_enumerants(tokens: &[&Token<'_>], i: usize, fixed: &mut [bool]) {
+    // Indices of the template arguments which are enumerants, the others being types.
+    let enumerants: &[usize] = match tokens[i].text {
+        "var" => &[0, 1],
+        "ptr" => &[0, 2],
+        name if name.starts_with("texture_storage_") => &[0, 1],
+        _ => return,
+    };
+    let mut depth = 0;
+    let mut argument = 0;
+    for (j, token) in tokens.iter().enumerate().skip(i + 1) {
+        match token.text {
+            "<" => depth += 1,
+            ">" => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            "," if depth == 1 => argument += 1,
+            _ if depth == 1 && token.kind == Kind::Ident && enumerants.contains(&argument) => {
+                fixed[j] = true
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Generates the names `_a`, `_b`, ..., `_Z`, `_a0`, `_b0`... which can't collide with a keyword since none starts with `_`.
+fn mangled_name(mut index: usize) -> String {
+    const FIRST: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    const REST: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    let mut name = String::from("_");
+    name.push(FIRST[index % FIRST.len()] as char);
+    index /= FIRST.len();
+    while index > 0 {
+        index -= 1;
+        name.push(REST[index % REST.len()] as char);
+        index /= REST.len();
+    }
+    name
+}
+
+/// Whether two tokens would be lexed differently if they were written without whitespace between them.
+fn needs_space(previous: &Token<'_>, next: &Token<'_>) -> bool {
+    const OPERATORS: &str = "+-*/%&|^!<>=";
+    let word = |token: &Token<'_>| matches!(token.kind, Kind::Ident | Kind::Number);
+    (word(previous) && word(next))
+        || (previous.kind == Kind::Punct
+            && next.kind == Kind::Punct
+            && OPERATORS.contains(previous.text)
+            && OPERATORS.contains(next.text))
+}
+
+/// Minifies the WGSL `source` according to `options`.
+pub fn minify(source: &str, options: MinifyOptions) -> Minified {
+    let tokens = tokenize(source);
+    let code = tokens
+        .iter()
+        .filter(|token| !matches!(token.kind, Kind::Comment | Kind::Whitespace))
+        .collect::<Vec<_>>();
+
+    let mut renames = HashMap::new();
+    if options.mangle_names {
+        let used = code
+            .iter()
+            .filter(|token| token.kind == Kind::Ident)
+            .map(|token| token.text)
+            .collect::<HashSet<_>>();
+        let mut index = 0;
+        for name in mangleable_names(&code) {
+            let mangled = loop {
+                let mangled = mangled_name(index);
+                index += 1;
+                if !used.contains(mangled.as_str()) {
+                    break mangled;
+                }
+            };
+            renames.insert(name, mangled);
+        }
+    }
+    let fixed = fixed_positions(&code);
+    let mut fixed = fixed.into_iter();
+
+    let mut minified = String::with_capacity(source.len());
+    let mut previous: Option<&Token<'_>> = None;
+    let mut pending_space = false;
+    for token in &tokens {
+        match token.kind {
+            Kind::Whitespace if options.strip_whitespace => pending_space = true,
+            Kind::Comment if options.strip_comments => pending_space = true,
+            Kind::Whitespace | Kind::Comment => {
+                minified.push_str(token.text);
+                if options.strip_whitespace && token.text.starts_with("//") {
+                    minified.push('\n');
+                }
+                previous = None;
+                pending_space = false;
+            }
+            _ => {
+                let is_fixed = fixed.next().expect("One entry per code token");
+                if pending_space && previous.is_some_and(|previous| needs_space(previous, token)) {
+                    minified.push(' ');
+                }
+                match renames.get(token.text) {
+                    Some(mangled) if token.kind == Kind::Ident && !is_fixed => {
+                        minified.push_str(mangled)
+                    }
+                    _ => minified.push_str(token.text),
+                }
+                previous = Some(token);
+                pending_space = false;
+            }
+        }
+    }
+    if options.strip_whitespace {
+        minified.truncate(minified.trim_end().len());
+    }
+
+    Minified {
+        source: minified,
+        names: renames
+            .into_iter()
+            .map(|(original, mangled)| (mangled, original.to_string()))
+            .collect(),
+    }
+}
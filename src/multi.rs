@@ -0,0 +1,206 @@
+//! Pipelines with several outputs.
+//!
+//! When a stage writes values of different shapes, e.g. a result per element and a small summary, they don't have to be packed into a single `Pod` type. `GpuComputeAsync::gen_pipeline_multi` takes a tuple of `Pod` types as its outputs: the first one is bound like the output of `GpuComputeAsync::gen_pipeline` and the next ones at the following bindings, all declared as `var<storage, read_write>`. `MultiPipelineAsync::run` reads all of them back and gives the callback a tuple of references.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//!
+//! let shader = "
+//!     struct Summary { total: atomic<u32>, max: atomic<u32> }
+//!
+//!     @group(0) @binding(0) var<storage, read> in: array<u32>;
+//!     @group(0) @binding(1) var<storage, read_write> doubled: array<u32>;
+//!     @group(0) @binding(2) var<storage, read_write> summary: Summary;
+//!
+//!     @compute @workgroup_size(64)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         doubled[id.x] = 2u * in[id.x];
+//!         atomicAdd(&summary.total, in[id.x]);
+//!         atomicMax(&summary.max, in[id.x]);
+//!     }
+//! ";
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline_multi::<[u32; 64], (), ([u32; 64], [u32; 2]), 1>(None, [StageDesc::new(shader, "main")]);
+//! let input: [u32; 64] = std::array::from_fn(|i| i as u32);
+//! let (last, summary) = pipeline.run(&input, [(1, 1, 1)], |(doubled, summary)| (doubled[63], *summary));
+//! assert_eq!(last, 126);
+//! assert_eq!(summary, [2016, 63]);
+//! ```
+use crate::{
+    error::SgpuError, options::ZeroInit, BufferSizes, Buffers, CompiledStages, GpuComputeAsync,
+    GpuRef, StageDesc,
+};
+use std::{marker::PhantomData, num::NonZeroUsize};
+
+/// Tuple of `Pod` types read back by a `MultiPipelineAsync`, one buffer per type.
+pub trait Outputs {
+    /// References to the outputs given to the callback of `MultiPipelineAsync::run`.
+    type Refs<'r>;
+
+    /// Sizes in bytes of the outputs, in binding order.
+    fn sizes() -> Vec<usize>;
+
+    /// Casts the bytes of each output, in binding order.
+    fn from_bytes<'r>(bytes: &[&'r [u8]]) -> Self::Refs<'r>;
+}
+
+macro_rules! impl_outputs {
+    ($($name:ident $index:tt),+) => {
+        impl<$($name: bytemuck::Pod),+> Outputs for ($($name,)+) {
+            type Refs<'r> = ($(&'r $name,)+);
+
+            fn sizes() -> Vec<usize> {
+                vec![$(std::mem::size_of::<$name>()),+]
+            }
+
+            fn from_bytes<'r>(bytes: &[&'r [u8]]) -> Self::Refs<'r> {
+                ($(bytemuck::from_bytes::<$name>(bytes[$index]),)+)
+            }
+        }
+    };
+}
+
+impl_outputs!(A 0, B 1);
+impl_outputs!(A 0, B 1, C 2);
+impl_outputs!(A 0, B 1, C 2, D 3);
+
+/// Pipeline with the outputs `O`, see the module documentation.
+pub struct MultiPipelineAsync<
+    'a,
+    Input: bytemuck::Pod,
+    Uniform: bytemuck::Pod,
+    O: Outputs,
+    const N: usize,
+> {
+    buffers: Buffers,
+    stages: CompiledStages<N>,
+    zero_init: ZeroInit,
+    device: GpuRef<'a>,
+    _phantom: PhantomData<(Input, Uniform, O)>,
+}
+
+impl GpuComputeAsync {
+    /// Same as `GpuComputeAsync::gen_pipeline`, but the output is a tuple of `Pod` types bound at consecutive bindings, see the `multi` module.
+    ///
+    /// # Panics
+    /// In the same cases as `GpuComputeAsync::gen_pipeline`.
+    pub async fn gen_pipeline_multi<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        O: Outputs,
+        const N: usize,
+    >(
+        &self,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> MultiPipelineAsync<'_, Input, Uniform, O, N> {
+        self.try_gen_pipeline_multi(scratchpad_size, stages)
+            .await
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Same as `GpuComputeAsync::gen_pipeline_multi`, but the errors of the shaders are returned instead of panicking.
+    pub async fn try_gen_pipeline_multi<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        O: Outputs,
+        const N: usize,
+    >(
+        &self,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> Result<MultiPipelineAsync<'_, Input, Uniform, O, N>, SgpuError> {
+        let outputs = O::sizes();
+        let sizes = BufferSizes {
+            output: outputs[0],
+            extra_outputs: outputs[1..].to_vec(),
+            ..BufferSizes::of::<Input, Uniform, ()>(scratchpad_size)
+        };
+        let stages = self.compile_stages(&sizes, stages, None).await?;
+        let buffers = self.create_buffers(&sizes, &stages.bindgroup_layout, self.zero_init);
+        Ok(MultiPipelineAsync {
+            buffers,
+            stages,
+            zero_init: self.zero_init,
+            device: GpuRef::Borrowed(self),
+            _phantom: PhantomData,
+        })
+    }
+}
+
+impl<Input: bytemuck::Pod, Uniform: bytemuck::Pod, O: Outputs, const N: usize>
+    MultiPipelineAsync<'_, Input, Uniform, O, N>
+{
+    /// This method is used to write the uniform buffer. It is useful to change the uniform between runs.
+    #[inline]
+    pub fn write_uniform(&mut self, uniform: &Uniform) {
+        self.device.queue.write_buffer(
+            self.buffers.uniform.as_ref().expect("No uniforms"),
+            0,
+            bytemuck::bytes_of(uniform),
+        )
+    }
+
+    /// Same as `PipelineAsync::run`, but the callback gets a reference to each output.
+    pub async fn run<T: Send + 'static>(
+        &mut self,
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+        callback: impl for<'r> FnOnce(O::Refs<'r>) -> T + Send,
+    ) -> T {
+        if let Some(buffer) = &self.buffers.input {
+            self.device
+                .queue
+                .write_buffer(buffer, 0, bytemuck::bytes_of(input));
+        }
+        let mut encoder = self
+            .device
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        if self.zero_init == ZeroInit::BeforeEachRun {
+            self.buffers.clear(&mut encoder, false);
+        }
+        self.stages.record_passes(
+            &mut encoder,
+            &self.buffers.bindgroup,
+            &self.device,
+            &workgroups,
+        );
+        let outputs = std::iter::once((&self.buffers.staging, &self.buffers.output))
+            .chain(
+                self.buffers
+                    .extra_outputs
+                    .iter()
+                    .map(|(staging, readback)| (staging, readback)),
+            )
+            .collect::<Vec<_>>();
+        for (staging, readback) in &outputs {
+            encoder.copy_buffer_to_buffer(staging, 0, readback, 0, readback.size());
+        }
+        self.device.queue.submit(Some(encoder.finish()));
+        let (sender, receiver) = flume::bounded(outputs.len());
+        for (_, readback) in &outputs {
+            let sender = sender.clone();
+            readback.slice(..).map_async(wgpu::MapMode::Read, move |e| {
+                e.expect("Could not map buffer");
+                sender.send(()).unwrap()
+            });
+        }
+        self.device.wait_submitted();
+        for _ in &outputs {
+            receiver.recv_async().await.expect("Error with channel");
+        }
+        let res = {
+            let views = outputs
+                .iter()
+                .map(|(_, readback)| readback.slice(..).get_mapped_range())
+                .collect::<Vec<_>>();
+            let bytes = views.iter().map(|view| view.as_ref()).collect::<Vec<_>>();
+            callback(O::from_bytes(&bytes))
+        };
+        for (_, readback) in &outputs {
+            readback.unmap();
+        }
+        res
+    }
+}
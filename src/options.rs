@@ -0,0 +1,235 @@
+//! Options of a `GpuComputeAsync`.
+//!
+//! The options are given to `GpuComputeAsync::with_options`, `GpuComputeAsync::new` using the default ones. The adapter and the device are configured with a `GpuComputeBuilder`, e.g. to request higher limits or the features used by the shaders.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use std::time::Duration;
+//!
+//! let options = GpuComputeOptions {
+//!     poll_strategy: PollStrategy::Background(Duration::from_millis(1)),
+//!     ..Default::default()
+//! };
+//! let gpu = GpuCompute::with_options(options);
+//! assert_eq!(gpu.poll_strategy(), PollStrategy::Background(Duration::from_millis(1)));
+//! ```
+use crate::error::SgpuError;
+use std::{num::NonZeroUsize, sync::Arc, thread::JoinHandle, time::Duration};
+
+/// How the device is polled to complete the runs. The right choice depends on the application: a CLI tool can block, a service running many runs concurrently is better served by a background thread and a GUI application usually polls from its event loop.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum PollStrategy {
+    /// Each run blocks the calling thread until the device completes it.
+    #[default]
+    Blocking,
+    /// A background thread polls the device at the given interval, so runs only await their result. The thread stops when the device is dropped.
+    Background(Duration),
+    /// The device is only polled by `GpuComputeAsync::poll`, which the application calls e.g. once per frame. A run never completes if nobody polls the device, so the blocking API must not be called from the polling thread.
+    OnDemand,
+}
+
+/// When the buffers of a pipeline are cleared to zero. Clearing them makes the reads of memory that no stage wrote deterministic, so a shader reading outside of what it initialized fails the same way on every run and on every device.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum ZeroInit {
+    /// The buffers are never cleared explicitly, the scratchpad and the output keep their contents between runs.
+    #[default]
+    Never,
+    /// The input, the scratchpad and the output are cleared once, when the pipeline is created.
+    AtCreation,
+    /// Same as `ZeroInit::AtCreation`, and the scratchpad and the output are also cleared before each run, so no state is kept between runs.
+    BeforeEachRun,
+}
+
+/// Options of a `GpuComputeAsync`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GpuComputeOptions {
+    pub poll_strategy: PollStrategy,
+    /// Zero-initialization of the buffers of the pipelines created by the device, it can be changed per pipeline with `PipelineAsync::set_zero_init`.
+    pub zero_init: ZeroInit,
+    /// Maximum number of compute passes recorded in a command buffer, `None` by default, which never splits a run. Otherwise, the stages of a run with more passes are split across several command buffers submitted one after the other, which keeps each submission under the limits of the drivers and lets the device interleave other work between them, e.g. the rendering of a UI.
+    pub max_passes_per_submission: Option<NonZeroUsize>,
+}
+
+/// Builder of a `GpuComputeAsync`, created by `GpuComputeAsync::builder`. By default, it requests a high-performance adapter of any backend, the downlevel limits and no feature, and enables the timestamp and pipeline statistics queries and the push constants when the adapter supports them.
+///
+/// ```rust
+/// use sgpu_compute::prelude::*;
+///
+/// let unsupported = GpuCompute::builder()
+///     .required_limits(wgpu::Limits { max_compute_workgroups_per_dimension: u32::MAX, ..Default::default() })
+///     .build();
+/// assert!(matches!(unsupported, Err(SgpuError::Unsupported(_))));
+///
+/// let gpu = GpuCompute::builder()
+///     .power_preference(wgpu::PowerPreference::LowPower)
+///     .required_limits(wgpu::Limits {
+///         max_storage_buffer_binding_size: 64 << 20,
+///         ..wgpu::Limits::downlevel_defaults()
+///     })
+///     .optional_features(wgpu::Features::SHADER_F16)
+///     .build()
+///     .unwrap();
+/// assert!(gpu.limits().max_storage_buffer_binding_size >= 64 << 20);
+/// ```
+#[derive(Debug, Clone)]
+pub struct GpuComputeBuilder {
+    pub(crate) options: GpuComputeOptions,
+    pub(crate) power_preference: wgpu::PowerPreference,
+    pub(crate) backends: wgpu::Backends,
+    pub(crate) required_limits: wgpu::Limits,
+    pub(crate) required_features: wgpu::Features,
+    pub(crate) optional_features: wgpu::Features,
+}
+
+impl Default for GpuComputeBuilder {
+    fn default() -> Self {
+        Self {
+            options: GpuComputeOptions::default(),
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            backends: wgpu::Backends::all(),
+            required_limits: wgpu::Limits::downlevel_defaults(),
+            required_features: wgpu::Features::empty(),
+            optional_features: wgpu::Features::PIPELINE_STATISTICS_QUERY
+                | wgpu::Features::TIMESTAMP_QUERY
+                | wgpu::Features::PUSH_CONSTANTS,
+        }
+    }
+}
+
+impl GpuComputeBuilder {
+    /// Options of the device, replacing the poll strategy, the zero-initialization and the maximum number of passes per submission set before.
+    pub fn options(mut self, options: GpuComputeOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn poll_strategy(mut self, poll_strategy: PollStrategy) -> Self {
+        self.options.poll_strategy = poll_strategy;
+        self
+    }
+
+    pub fn zero_init(mut self, zero_init: ZeroInit) -> Self {
+        self.options.zero_init = zero_init;
+        self
+    }
+
+    pub fn max_passes_per_submission(mut self, max_passes: Option<NonZeroUsize>) -> Self {
+        self.options.max_passes_per_submission = max_passes;
+        self
+    }
+
+    /// Power preference of the requested adapter, `HighPerformance` by default.
+    pub fn power_preference(mut self, power_preference: wgpu::PowerPreference) -> Self {
+        self.power_preference = power_preference;
+        self
+    }
+
+    /// Backends among which the adapter is requested, all of them by default.
+    pub fn backends(mut self, backends: wgpu::Backends) -> Self {
+        self.backends = backends;
+        self
+    }
+
+    /// Limits of the device, the build fails with `SgpuError::Unsupported` if the adapter doesn't reach them.
+    pub fn required_limits(mut self, limits: wgpu::Limits) -> Self {
+        self.required_limits = limits;
+        self
+    }
+
+    /// Features of the device, the build fails with `SgpuError::Unsupported` if the adapter doesn't support them.
+    pub fn required_features(mut self, features: wgpu::Features) -> Self {
+        self.required_features = features;
+        self
+    }
+
+    /// Features enabled if the adapter supports them, added to the timestamp and pipeline statistics queries.
+    pub fn optional_features(mut self, features: wgpu::Features) -> Self {
+        self.optional_features |= features;
+        self
+    }
+
+    /// Requests the adapter and creates the device. Returns `SgpuError::NoAdapter` if no adapter of the backends is available and `SgpuError::Unsupported` if it doesn't satisfy the required limits and features.
+    pub async fn build_async(self) -> Result<crate::GpuComputeAsync, SgpuError> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends: self.backends,
+            ..Default::default()
+        });
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: self.power_preference,
+                compatible_surface: None,
+                force_fallback_adapter: false,
+            })
+            .await
+            .ok_or(SgpuError::NoAdapter)?;
+        crate::GpuComputeAsync::from_adapter(&adapter, &self).await
+    }
+
+    /// Blocking version of `GpuComputeBuilder::build_async`. It is enabled by the `blocking` feature.
+    #[cfg(feature = "blocking")]
+    pub fn build(self) -> Result<crate::blocking::GpuCompute, SgpuError> {
+        pollster::block_on(self.build_async()).map(crate::blocking::GpuCompute)
+    }
+
+    /// Checks that the adapter satisfies the required limits and features.
+    pub(crate) fn check_adapter(&self, adapter: &wgpu::Adapter) -> Result<(), SgpuError> {
+        let mut unsupported = Vec::new();
+        let missing = self.required_features - adapter.features();
+        if !missing.is_empty() {
+            unsupported.push(format!("features {:?}", missing));
+        }
+        self.required_limits.check_limits_with_fail_fn(
+            &adapter.limits(),
+            false,
+            |name, required, allowed| {
+                unsupported.push(format!(
+                    "limit `{}` of {} (the adapter allows {})",
+                    name, required, allowed
+                ))
+            },
+        );
+        if unsupported.is_empty() {
+            Ok(())
+        } else {
+            Err(SgpuError::Unsupported(format!(
+                "adapter {} doesn't support the required {}",
+                adapter.get_info().name,
+                unsupported.join(", ")
+            )))
+        }
+    }
+}
+
+/// Thread polling a device at a fixed interval, it is stopped and joined when dropped.
+pub(crate) struct BackgroundPoller {
+    stop: Option<flume::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl BackgroundPoller {
+    pub(crate) fn spawn(device: Arc<wgpu::Device>, interval: Duration) -> Self {
+        let (stop, stopped) = flume::bounded::<()>(0);
+        let thread = std::thread::Builder::new()
+            .name("sgpu-poll".into())
+            .spawn(move || {
+                while let Err(flume::RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                    device.poll(wgpu::Maintain::Poll);
+                }
+            })
+            .expect("Could not spawn the polling thread");
+        Self {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for BackgroundPoller {
+    fn drop(&mut self) {
+        // Disconnecting the channel stops the thread, which releases its reference to the device.
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
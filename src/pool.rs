@@ -0,0 +1,129 @@
+//! Pool of identical pipelines shared between concurrent tasks.
+//!
+//! The pool owns a fixed number of pipelines, usually created with `into_pool` which uses `clone_for_thread` to avoid recompiling the shaders. Acquiring a pipeline waits until one is available and the pipeline returns to the pool when the guard is dropped, which bounds the memory used by concurrent runs.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//!
+//! let shader = "
+//!     @group(0) @binding(0) var<storage, read> in: array<u32>;
+//!     @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+//!
+//!     @compute @workgroup_size(4)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[id.x] = in[id.x] + 1u;
+//!     }
+//! ";
+//! let gpu = GpuCompute::new();
+//! let pipeline = gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(None, [StageDesc::new(shader, "main")]);
+//! let pool = pipeline.into_pool(2);
+//! std::thread::scope(|s| {
+//!     for i in 0..8 {
+//!         let pool = &pool;
+//!         s.spawn(move || {
+//!             let mut pipeline = pool.acquire_blocking();
+//!             assert_eq!(pipeline.run(&[i; 4], [(1, 1, 1)], |out| *out), [i + 1; 4]);
+//!         });
+//!     }
+//! });
+//! ```
+use std::ops::{Deref, DerefMut};
+
+/// A pool of pipelines of type `P`, either `PipelineAsync` or the blocking `Pipeline`.
+pub struct PipelinePool<P> {
+    sender: flume::Sender<P>,
+    receiver: flume::Receiver<P>,
+    size: usize,
+}
+
+impl<P> PipelinePool<P> {
+    /// Creates a pool owning all the given pipelines.
+    pub fn new(pipelines: impl IntoIterator<Item = P>) -> Self {
+        let (sender, receiver) = flume::unbounded();
+        let mut size = 0;
+        for pipeline in pipelines {
+            sender.send(pipeline).expect("Receiver is alive");
+            size += 1;
+        }
+        Self {
+            sender,
+            receiver,
+            size,
+        }
+    }
+
+    /// Number of pipelines owned by the pool.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Number of pipelines currently not acquired.
+    #[inline]
+    pub fn available(&self) -> usize {
+        self.receiver.len()
+    }
+
+    /// Waits until a pipeline is available and acquires it.
+    pub async fn acquire(&self) -> PooledPipeline<'_, P> {
+        let pipeline = self.receiver.recv_async().await.expect("Sender is alive");
+        self.guard(pipeline)
+    }
+
+    /// Blocking version of `PipelinePool::acquire`.
+    pub fn acquire_blocking(&self) -> PooledPipeline<'_, P> {
+        let pipeline = self.receiver.recv().expect("Sender is alive");
+        self.guard(pipeline)
+    }
+
+    /// Acquires a pipeline if one is available right now.
+    pub fn try_acquire(&self) -> Option<PooledPipeline<'_, P>> {
+        self.receiver
+            .try_recv()
+            .ok()
+            .map(|pipeline| self.guard(pipeline))
+    }
+
+    #[inline]
+    fn guard(&self, pipeline: P) -> PooledPipeline<'_, P> {
+        PooledPipeline {
+            pipeline: Some(pipeline),
+            pool: self,
+        }
+    }
+}
+
+/// A pipeline acquired from a `PipelinePool`, it returns to the pool when dropped.
+pub struct PooledPipeline<'p, P> {
+    pipeline: Option<P>,
+    pool: &'p PipelinePool<P>,
+}
+
+impl<P> Deref for PooledPipeline<'_, P> {
+    type Target = P;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.pipeline
+            .as_ref()
+            .expect("Pipeline is only taken on drop")
+    }
+}
+
+impl<P> DerefMut for PooledPipeline<'_, P> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.pipeline
+            .as_mut()
+            .expect("Pipeline is only taken on drop")
+    }
+}
+
+impl<P> Drop for PooledPipeline<'_, P> {
+    fn drop(&mut self) {
+        if let Some(pipeline) = self.pipeline.take() {
+            // The pool holds the receiver, so it can't be dropped while a pipeline is acquired.
+            let _ = self.pool.sender.send(pipeline);
+        }
+    }
+}
@@ -5,6 +5,12 @@ pub use crate::blocking::GpuCompute;
 
 pub use crate::GpuComputeAsync;
 
-pub use crate::StageDesc;
+pub use crate::options::{GpuComputeBuilder, GpuComputeOptions, PollStrategy, ZeroInit};
+
+pub use crate::binding::BindingSet;
+
+pub use crate::error::SgpuError;
+
+pub use crate::{ScratchpadDesc, ShaderSource, StageDesc};
 /// This re-exports is needed for giving the scratchpad size.
 pub use std::num::NonZeroUsize;
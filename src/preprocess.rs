@@ -0,0 +1,310 @@
+//! Compile-time constants and shared modules baked into WGSL sources.
+//!
+//! A `Preprocessor` substitutes the placeholders of a shader before it is compiled:
+//!     - `{{NAME}}` is replaced by the value of `NAME`, anywhere in the source
+//!     - a `#define NAME value` line defines `NAME` for the following lines, and each identifier `NAME` of these lines is replaced by the value, like the C preprocessor
+//!     - a `#include "name"` line is replaced by the module `name` registered with `Preprocessor::module`, itself processed, so stages can share helper functions without pasting them in every shader
+//!
+//! The values given to `Preprocessor::define` take precedence over the `#define` lines, which then act as default values, and the `#define` lines of a module apply to the lines after its `#include`. A module is only included once per shader, the next `#include` lines of the same module are removed, so modules can include the modules they depend on. A placeholder without a value, an unknown module, a cycle of includes and any other `#` directive are errors, reported with their line and their module.
+//!
+//! A `Preprocessor` is a `ShaderSourceProvider` whose keys are the sources themselves, so the `shader` of the stages given to `GpuComputeAsync::gen_pipeline_with_provider` is the template, and `GpuComputeAsync::try_gen_pipeline_with_provider` returns the errors as `SgpuError::ShaderCompilation`.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::preprocess::Preprocessor;
+//!
+//! let shader = "
+//!     #define TILE 4u
+//!     @group(0) @binding(0) var<storage, read> in: array<u32, {{N}}>;
+//!     @group(0) @binding(1) var<storage, read_write> out: array<u32, {{N}}>;
+//!
+//!     @compute @workgroup_size(TILE)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[id.x] = in[id.x] * TILE;
+//!     }
+//! ";
+//! let preprocessor = Preprocessor::new().define("N", 8).define("TILE", "2u");
+//! assert!(preprocessor.process(shader).unwrap().contains("array<u32, 8>"));
+//!
+//! let common = "
+//!     fn square(x: u32) -> u32 {
+//!         return x * x;
+//!     }
+//! ";
+//! let library = Preprocessor::new().module("common", common);
+//! let composed = library.process("#include \"common\"\nconst NINE = square(3u);").unwrap();
+//! assert!(composed.contains("fn square") && composed.ends_with("const NINE = square(3u);"));
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline_with_provider::<[u32; 8], (), [u32; 8], 1>(
+//!     preprocessor,
+//!     None,
+//!     [StageDesc::new(shader, "main")],
+//! );
+//! assert_eq!(pipeline.run(&[1; 8], [(4, 1, 1)], |out| *out), [2; 8]);
+//! ```
+use crate::{
+    error::SgpuError,
+    minify::{tokenize, Kind},
+    provider::ShaderSourceProvider,
+};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+/// Substitutes the placeholders and the includes of shaders, see the module documentation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Preprocessor {
+    defines: HashMap<String, String>,
+    modules: HashMap<String, String>,
+}
+
+/// State of `Preprocessor::process` shared by a shader and the modules it includes.
+#[derive(Default)]
+struct Context {
+    /// Values of the `#define` lines processed so far.
+    local: HashMap<String, String>,
+    included: HashSet<String>,
+    /// Modules being included, from the outermost one.
+    stack: Vec<String>,
+}
+
+/// Error of `Preprocessor::process`, the lines start at 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreprocessError {
+    /// The placeholder `{{name}}` has no value.
+    Unresolved { name: String, line: usize },
+    /// The line starts with `#` but isn't a `#define`.
+    UnknownDirective { directive: String, line: usize },
+    /// A `#define` line without a name.
+    InvalidDefine { line: usize },
+    /// A `#include` line whose name isn't quoted.
+    InvalidInclude { line: usize },
+    /// A `#include` line of a module which isn't registered.
+    UnknownModule { name: String, line: usize },
+    /// The module includes itself, directly or through other modules.
+    IncludeCycle { name: String, line: usize },
+    /// The error is in the included module `module`, at a line of this module.
+    InModule {
+        module: String,
+        error: Box<PreprocessError>,
+    },
+}
+
+impl Preprocessor {
+    /// Preprocessor without any value.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Same preprocessor, with `value` for the placeholders `name`.
+    pub fn define(mut self, name: impl Into<String>, value: impl fmt::Display) -> Self {
+        self.defines.insert(name.into(), value.to_string());
+        self
+    }
+
+    /// Same preprocessor, with the module `name` for the `#include "name"` lines.
+    pub fn module(mut self, name: impl Into<String>, source: impl Into<String>) -> Self {
+        self.modules.insert(name.into(), source.into());
+        self
+    }
+
+    /// Value of `name`, if it is defined.
+    #[inline]
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.defines.get(name).map(String::as_str)
+    }
+
+    /// Returns `source` with its placeholders substituted, its includes replaced by their modules and its `#define` lines removed, see the module documentation. The removed lines are left empty, so without includes the lines of the errors of the compiler match the lines of the template.
+    ///
+    /// # Errors
+    /// If a placeholder has no value, if a module can't be included or if a line is an unknown directive.
+    pub fn process(&self, source: &str) -> Result<String, PreprocessError> {
+        let mut processed = String::with_capacity(source.len());
+        self.process_into(source, &mut Context::default(), &mut processed)?;
+        Ok(processed)
+    }
+
+    fn process_into(
+        &self,
+        source: &str,
+        context: &mut Context,
+        processed: &mut String,
+    ) -> Result<(), PreprocessError> {
+        for (index, text) in source.split_inclusive('\n').enumerate() {
+            let line = index + 1;
+            let trimmed = text.trim();
+            if let Some(directive) = trimmed.strip_prefix('#') {
+                let directive = directive.trim_start();
+                let (name, rest) = directive
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((directive, ""));
+                let rest = rest.trim();
+                match name {
+                    "define" => {
+                        let (name, value) =
+                            rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                        if name.is_empty() {
+                            return Err(PreprocessError::InvalidDefine { line });
+                        }
+                        let value = self.placeholders(value.trim(), &context.local, line)?;
+                        context.local.insert(name.to_string(), value);
+                    }
+                    "include" => self.include(rest, line, context, processed)?,
+                    _ => {
+                        return Err(PreprocessError::UnknownDirective {
+                            directive: name.to_string(),
+                            line,
+                        })
+                    }
+                }
+                processed.push_str(&text[text.trim_end().len()..]);
+                continue;
+            }
+            let text = self.placeholders(text, &context.local, line)?;
+            if context.local.is_empty() {
+                processed.push_str(&text);
+                continue;
+            }
+            for token in tokenize(&text) {
+                match context.local.get(token.text) {
+                    Some(value) if token.kind == Kind::Ident => {
+                        processed.push_str(self.value(token.text).unwrap_or(value))
+                    }
+                    _ => processed.push_str(token.text),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Processes the module named by the quoted `name` of a `#include` line, unless it was already included.
+    fn include(
+        &self,
+        name: &str,
+        line: usize,
+        context: &mut Context,
+        processed: &mut String,
+    ) -> Result<(), PreprocessError> {
+        let name = name
+            .strip_prefix('"')
+            .and_then(|name| name.strip_suffix('"'))
+            .ok_or(PreprocessError::InvalidInclude { line })?;
+        if context.stack.iter().any(|module| module == name) {
+            return Err(PreprocessError::IncludeCycle {
+                name: name.to_string(),
+                line,
+            });
+        }
+        if !context.included.insert(name.to_string()) {
+            return Ok(());
+        }
+        let module = self
+            .modules
+            .get(name)
+            .ok_or_else(|| PreprocessError::UnknownModule {
+                name: name.to_string(),
+                line,
+            })?;
+        context.stack.push(name.to_string());
+        self.process_into(module, context, processed)
+            .map_err(|error| PreprocessError::InModule {
+                module: name.to_string(),
+                error: Box::new(error),
+            })?;
+        context.stack.pop();
+        if !processed.ends_with('\n') {
+            processed.push('\n');
+        }
+        Ok(())
+    }
+
+    /// Substitutes the `{{NAME}}` placeholders of `text`.
+    fn placeholders(
+        &self,
+        text: &str,
+        local: &HashMap<String, String>,
+        line: usize,
+    ) -> Result<String, PreprocessError> {
+        let mut substituted = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find("{{") {
+            let Some(end) = rest[start..].find("}}") else {
+                break;
+            };
+            let name = rest[start + 2..start + end].trim();
+            let is_name = !name.is_empty()
+                && name.chars().all(|c| c == '_' || c.is_alphanumeric())
+                && !name.starts_with(|c: char| c.is_ascii_digit());
+            if !is_name {
+                // Not a placeholder, e.g. two nested blocks.
+                substituted.push_str(&rest[..start + 2]);
+                rest = &rest[start + 2..];
+                continue;
+            }
+            let value = self
+                .value(name)
+                .or_else(|| local.get(name).map(String::as_str))
+                .ok_or_else(|| PreprocessError::Unresolved {
+                    name: name.to_string(),
+                    line,
+                })?;
+            substituted.push_str(&rest[..start]);
+            substituted.push_str(value);
+            rest = &rest[start + end + 2..];
+        }
+        substituted.push_str(rest);
+        Ok(substituted)
+    }
+}
+
+impl ShaderSourceProvider for Preprocessor {
+    /// Returns the key, which is the template, processed.
+    ///
+    /// # Panics
+    /// If the template can't be processed, see `Preprocessor::process`.
+    fn source(&self, key: &str) -> Cow<'_, str> {
+        Cow::Owned(
+            self.process(key)
+                .unwrap_or_else(|error| panic!("{}", error)),
+        )
+    }
+
+    fn try_source(&self, key: &str) -> Result<Cow<'_, str>, SgpuError> {
+        self.process(key)
+            .map(Cow::Owned)
+            .map_err(|error| SgpuError::ShaderCompilation(error.to_string()))
+    }
+}
+
+impl fmt::Display for PreprocessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PreprocessError::Unresolved { name, line } => {
+                write!(f, "line {}: the placeholder `{}` has no value", line, name)
+            }
+            PreprocessError::UnknownDirective { directive, line } => {
+                write!(f, "line {}: unknown directive `#{}`", line, directive)
+            }
+            PreprocessError::InvalidDefine { line } => {
+                write!(f, "line {}: `#define` without a name", line)
+            }
+            PreprocessError::InvalidInclude { line } => {
+                write!(f, "line {}: the name of an `#include` must be quoted", line)
+            }
+            PreprocessError::UnknownModule { name, line } => {
+                write!(f, "line {}: unknown module `{}`", line, name)
+            }
+            PreprocessError::IncludeCycle { name, line } => {
+                write!(f, "line {}: the module `{}` includes itself", line, name)
+            }
+            PreprocessError::InModule { module, error } => {
+                write!(f, "in module `{}`, {}", module, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PreprocessError {}
@@ -0,0 +1,316 @@
+//! GPU time and invocations of each stage of a run.
+//!
+//! `PipelineAsync::run_profiled` runs the pipeline like `PipelineAsync::run` and also returns the `StageTimings` of the run, e.g. to tune the workgroup sizes of the stages without an external profiler. When the device supports `TIMESTAMP_QUERY`, the passes of the stages write timestamps at their boundaries, which are resolved and converted to durations with the timestamp period of the queue. Otherwise each stage is submitted on its own once the previous one is completed, and timed on the CPU from its submission to its completion, which includes the latency of the queue. `StageTimings::timestamps` tells which measurement was used.
+//!
+//! `PipelineAsync::run_with_statistics` returns the `PassStatistics` of each stage instead: the invocations dispatched, from the workgroups and the workgroup size declared by the entry point, and the compute shader invocations counted by the device when it supports `PIPELINE_STATISTICS_QUERY`. `PassStatistics::covers` checks that the dispatch dimensions cover all the elements.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//!
+//! let shader = "
+//!     @group(0) @binding(0) var<storage, read> in: array<f32>;
+//!     @group(0) @binding(1) var<storage, read_write> out: array<f32>;
+//!
+//!     @compute @workgroup_size(64)
+//!     fn double(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[id.x] = 2.0 * in[id.x];
+//!     }
+//!
+//!     @compute @workgroup_size(64)
+//!     fn square(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[id.x] *= out[id.x];
+//!     }
+//! ";
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline::<[f32; 256], (), [f32; 256], 2>(
+//!     None,
+//!     [
+//!         StageDesc::new(shader, "double").with_name("double"),
+//!         StageDesc::new(shader, "square").with_name("square"),
+//!     ],
+//! );
+//! let (last, timings) = pipeline.run_profiled(&[3.0; 256], [(4, 1, 1); 2], |out| out[255]);
+//! assert_eq!(last, 36.0);
+//! assert_eq!(timings.stages[1].name, Some("square"));
+//! println!("{}", timings);
+//!
+//! let (_, statistics) = pipeline.run_with_statistics(&[3.0; 256], [(4, 1, 1); 2], |_| ());
+//! assert_eq!(statistics[0].dispatched, Some(256));
+//! assert!(statistics[0].covers([256, 1, 1]) && !statistics[0].covers([257, 1, 1]));
+//! ```
+use crate::{trace::Timestamps, GpuComputeAsync, PipelineAsync, ZeroInit};
+use std::{fmt, time::Duration, time::Instant};
+
+/// GPU time of a stage, see `StageTimings`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageTiming {
+    pub name: Option<&'static str>,
+    pub entrypoint: &'static str,
+    pub duration: Duration,
+}
+
+/// GPU time of each stage of a run, returned by `PipelineAsync::run_profiled`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageTimings {
+    /// The stages in order.
+    pub stages: Vec<StageTiming>,
+    /// Whether the durations come from timestamp queries, or from the CPU if the device doesn't support them.
+    pub timestamps: bool,
+}
+
+impl StageTimings {
+    /// Sum of the durations of the stages.
+    pub fn total(&self) -> Duration {
+        self.stages.iter().map(|stage| stage.duration).sum()
+    }
+
+    /// The stage taking the most time, `None` if the pipeline has no stage.
+    pub fn slowest(&self) -> Option<&StageTiming> {
+        self.stages.iter().max_by_key(|stage| stage.duration)
+    }
+}
+
+impl fmt::Display for StageTimings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, stage) in self.stages.iter().enumerate() {
+            writeln!(
+                f,
+                "{}: {:.3} ms",
+                stage.name.unwrap_or(stage.entrypoint),
+                stage.duration.as_secs_f64() * 1e3
+            )?;
+            if i + 1 == self.stages.len() {
+                write!(
+                    f,
+                    "total: {:.3} ms{}",
+                    self.total().as_secs_f64() * 1e3,
+                    if self.timestamps { "" } else { " (CPU)" }
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Invocations of a stage, returned by `PipelineAsync::run_with_statistics`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PassStatistics {
+    pub name: Option<&'static str>,
+    pub entrypoint: &'static str,
+    pub workgroups: (u32, u32, u32),
+    /// Workgroup size declared by the entry point, `None` if its shader can't be reflected.
+    pub workgroup_size: Option<[u32; 3]>,
+    /// Invocations dispatched, the number of workgroups times the workgroup size.
+    pub dispatched: Option<u64>,
+    /// Compute shader invocations counted by the device, `None` if it doesn't support `PIPELINE_STATISTICS_QUERY`.
+    pub invocations: Option<u64>,
+}
+
+impl PassStatistics {
+    /// Whether the invocations dispatched along each dimension cover a grid of `elements`, e.g. `[width, height, 1]` for an image. `false` if the workgroup size is unknown.
+    pub fn covers(&self, elements: [u32; 3]) -> bool {
+        let workgroups = [self.workgroups.0, self.workgroups.1, self.workgroups.2];
+        self.workgroup_size.is_some_and(|size| {
+            (0..3).all(|i| workgroups[i] as u64 * size[i] as u64 >= elements[i] as u64)
+        })
+    }
+}
+
+impl<Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod, const N: usize>
+    PipelineAsync<'_, Input, Uniform, Output, N>
+{
+    /// Same as `PipelineAsync::run`, but the GPU time of each stage is measured and returned with the result of the callback, see the `profile` module.
+    pub async fn run_profiled<T>(
+        &mut self,
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+        callback: impl FnOnce(&Output) -> T,
+    ) -> (T, StageTimings) {
+        let supported = self
+            .device
+            .device
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY);
+        let durations = if supported && N > 0 {
+            let timestamps = Timestamps::new(&self.device.device, N);
+            self.encode_and_submit(
+                Some("profiled"),
+                Some(input),
+                workgroups,
+                false,
+                Some(&timestamps),
+            );
+            let ticks: Vec<u64> = read(&self.device, &timestamps.readback, |bytes| {
+                bytes
+                    .chunks_exact(std::mem::size_of::<u64>())
+                    .map(bytemuck::pod_read_unaligned)
+                    .collect()
+            })
+            .await;
+            let period = self.device.queue.get_timestamp_period() as f64;
+            ticks
+                .chunks_exact(2)
+                .map(|span| {
+                    Duration::from_nanos((span[1].saturating_sub(span[0]) as f64 * period) as u64)
+                })
+                .collect()
+        } else {
+            self.write_input(Some(input));
+            let mut durations = Vec::with_capacity(N);
+            for (i, workgroup) in workgroups.iter().enumerate() {
+                let mut encoder =
+                    self.device
+                        .device
+                        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                            label: Some("profiled"),
+                        });
+                if i == 0 && self.zero_init == ZeroInit::BeforeEachRun {
+                    self.buffers.clear(&mut encoder, false);
+                }
+                self.record_stage(&mut encoder, i, *workgroup, Some("profiled"), None, None);
+                let (sender, receiver) = flume::bounded(1);
+                let submitted = Instant::now();
+                self.device.queue.submit(Some(encoder.finish()));
+                self.device
+                    .queue
+                    .on_submitted_work_done(move || sender.send(Instant::now()).unwrap());
+                self.device.wait_submitted();
+                let done = receiver.recv_async().await.expect("Error with channel");
+                durations.push(done.saturating_duration_since(submitted));
+            }
+            durations
+        };
+        let timings = StageTimings {
+            stages: self
+                .stages
+                .desc
+                .iter()
+                .zip(durations)
+                .map(|(desc, duration)| StageTiming {
+                    name: desc.name,
+                    entrypoint: desc.entrypoint,
+                    duration,
+                })
+                .collect(),
+            timestamps: supported && N > 0,
+        };
+        (self.read_output(callback).await, timings)
+    }
+
+    /// Same as `PipelineAsync::run`, but the invocations of each stage are returned with the result of the callback, see the `profile` module.
+    pub async fn run_with_statistics<T>(
+        &mut self,
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+        callback: impl FnOnce(&Output) -> T,
+    ) -> (T, Vec<PassStatistics>) {
+        let device = &self.device.device;
+        let supported = device
+            .features()
+            .contains(wgpu::Features::PIPELINE_STATISTICS_QUERY);
+        let queries = (supported && N > 0).then(|| {
+            let size = (N * std::mem::size_of::<u64>()) as u64;
+            let buffer = |label, usage| {
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some(label),
+                    size,
+                    usage,
+                    mapped_at_creation: false,
+                })
+            };
+            (
+                device.create_query_set(&wgpu::QuerySetDescriptor {
+                    label: Some("Stage statistics"),
+                    ty: wgpu::QueryType::PipelineStatistics(
+                        wgpu::PipelineStatisticsTypes::COMPUTE_SHADER_INVOCATIONS,
+                    ),
+                    count: N as u32,
+                }),
+                buffer(
+                    "Statistics resolve buffer",
+                    wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                ),
+                buffer(
+                    "Statistics readback buffer",
+                    wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                ),
+            )
+        });
+        self.write_input(Some(input));
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("statistics"),
+        });
+        if self.zero_init == ZeroInit::BeforeEachRun {
+            self.buffers.clear(&mut encoder, false);
+        }
+        let query_set = queries.as_ref().map(|(query_set, _, _)| query_set);
+        for (i, workgroup) in workgroups.iter().enumerate() {
+            self.record_stage(
+                &mut encoder,
+                i,
+                *workgroup,
+                Some("statistics"),
+                None,
+                query_set,
+            );
+        }
+        if let Some((query_set, resolve, readback)) = &queries {
+            encoder.resolve_query_set(query_set, 0..N as u32, resolve, 0);
+            encoder.copy_buffer_to_buffer(resolve, 0, readback, 0, readback.size());
+        }
+        self.device.queue.submit(Some(encoder.finish()));
+        let invocations: Vec<Option<u64>> = match &queries {
+            Some((_, _, readback)) => {
+                read(&self.device, readback, |bytes| {
+                    bytes
+                        .chunks_exact(std::mem::size_of::<u64>())
+                        .map(|count| Some(bytemuck::pod_read_unaligned(count)))
+                        .collect()
+                })
+                .await
+            }
+            None => vec![None; N],
+        };
+        let description = self.describe();
+        let statistics = description
+            .stages
+            .into_iter()
+            .zip(workgroups)
+            .zip(invocations)
+            .map(|((stage, workgroups), invocations)| PassStatistics {
+                name: stage.name,
+                entrypoint: stage.entrypoint,
+                workgroups,
+                workgroup_size: stage.workgroup_size,
+                dispatched: stage.workgroup_size.map(|size| {
+                    let (x, y, z) = workgroups;
+                    [x, y, z]
+                        .iter()
+                        .zip(size)
+                        .map(|(&count, size)| count as u64 * size as u64)
+                        .product()
+                }),
+                invocations,
+            })
+            .collect();
+        (self.read_output(callback).await, statistics)
+    }
+}
+
+/// Maps `buffer` once the submitted commands are done and calls `callback` on its content.
+async fn read<T>(
+    device: &GpuComputeAsync,
+    buffer: &wgpu::Buffer,
+    callback: impl FnOnce(&[u8]) -> T,
+) -> T {
+    let (sender, receiver) = flume::bounded(1);
+    buffer.slice(..).map_async(wgpu::MapMode::Read, move |e| {
+        e.expect("Could not map buffer");
+        sender.send(()).unwrap()
+    });
+    device.wait_submitted();
+    receiver.recv_async().await.expect("Error with channel");
+    let res = callback(buffer.slice(..).get_mapped_range().as_ref());
+    buffer.unmap();
+    res
+}
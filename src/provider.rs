@@ -0,0 +1,64 @@
+//! Shader sources provided at runtime.
+//!
+//! By default the `shader` of a `StageDesc` is the WGSL source itself, which then lives as plaintext in the binary. With `GpuComputeAsync::gen_pipeline_with_provider`, it is instead a key given to a `ShaderSourceProvider` returning the source, e.g. by decrypting it from an embedded store or by generating it. The source returned by the provider is only kept while the shader is compiled.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//!
+//! // A trivial obfuscation, a real provider would decrypt the shader.
+//! fn obfuscate(source: &str) -> Vec<u8> {
+//!     source.bytes().map(|b| b ^ 0x5a).collect()
+//! }
+//!
+//! let store = obfuscate("
+//!     @group(0) @binding(0) var<storage, read> in: array<u32>;
+//!     @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+//!
+//!     @compute @workgroup_size(4)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[id.x] = in[id.x] + 1u;
+//!     }
+//! ");
+//! let provider = move |key: &str| {
+//!     assert_eq!(key, "increment");
+//!     String::from_utf8(store.iter().map(|b| b ^ 0x5a).collect()).unwrap()
+//! };
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline_with_provider::<[u32; 4], (), [u32; 4], 1>(
+//!     provider,
+//!     None,
+//!     [StageDesc::new("increment", "main")],
+//! );
+//! assert_eq!(pipeline.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out), [2, 3, 4, 5]);
+//! ```
+use crate::error::SgpuError;
+use std::{borrow::Cow, collections::HashMap};
+
+/// Source of the shaders of a pipeline, see the module documentation.
+pub trait ShaderSourceProvider: Send + Sync {
+    /// Returns the WGSL source of the shader identified by `key`, which is the `shader` of a `StageDesc`.
+    fn source(&self, key: &str) -> Cow<'_, str>;
+
+    /// Same as `ShaderSourceProvider::source`, but returns an error instead of panicking, which is then returned by `GpuComputeAsync::try_gen_pipeline_with_provider`. By default it calls `ShaderSourceProvider::source`.
+    #[inline]
+    fn try_source(&self, key: &str) -> Result<Cow<'_, str>, SgpuError> {
+        Ok(self.source(key))
+    }
+}
+
+impl<F: Fn(&str) -> String + Send + Sync> ShaderSourceProvider for F {
+    #[inline]
+    fn source(&self, key: &str) -> Cow<'_, str> {
+        Cow::Owned(self(key))
+    }
+}
+
+impl ShaderSourceProvider for HashMap<String, String> {
+    #[inline]
+    fn source(&self, key: &str) -> Cow<'_, str> {
+        Cow::Borrowed(
+            self.get(key)
+                .unwrap_or_else(|| panic!("No shader for the key {}", key)),
+        )
+    }
+}
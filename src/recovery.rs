@@ -0,0 +1,142 @@
+//! Detection of the loss of the device and recovery on a new one.
+//!
+//! A device can be lost at any time, e.g. after a driver reset or when a laptop switches GPUs, and every later command on it fails. `GpuComputeAsync::set_device_lost_handler` is notified of the loss and `GpuComputeAsync::is_device_lost` tells whether it happened, `PipelineAsync::try_run` then returns `SgpuError::DeviceLost` instead of waiting for a run which never completes. `GpuComputeAsync::recreate` creates a new device with the options of the lost one, and `PipelineAsync::rebuild_on` rebuilds each pipeline from its stages on it.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//!
+//! let shader = "
+//!     @group(0) @binding(0) var<storage, read> in: array<u32>;
+//!     @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+//!
+//!     @compute @workgroup_size(4)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[id.x] = in[id.x] + 1u;
+//!     }
+//! ";
+//! let gpu = GpuCompute::new();
+//! gpu.set_device_lost_handler(|message| eprintln!("Device lost: {}", message));
+//! let mut pipeline = gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(None, [StageDesc::new(shader, "main")]);
+//! if gpu.is_device_lost() {
+//!     let gpu = gpu.recreate().expect("No device to recover on");
+//!     let mut pipeline = pipeline.rebuild_on(&gpu).expect("Stages rejected by the new device");
+//!     assert_eq!(pipeline.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out), [2, 3, 4, 5]);
+//! } else {
+//!     assert_eq!(pipeline.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out), [2, 3, 4, 5]);
+//! }
+//! ```
+use crate::{error::SgpuError, GpuComputeAsync};
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc, Mutex, Weak,
+};
+
+type Handler = Box<dyn Fn(&str) + Send>;
+
+/// States of the `GpuComputeAsync`s watching each device, with the key of the device given to its callback. A device has a single device lost callback, which notifies all the states watching it. An entry is removed when its state is dropped, releasing the device.
+static WATCHERS: Mutex<Vec<(u64, wgpu::Device, Weak<DeviceLostState>)>> = Mutex::new(Vec::new());
+
+/// Key of the next device watched.
+static NEXT_KEY: AtomicU64 = AtomicU64::new(0);
+
+/// Whether the device was lost, and the handler to notify when it is.
+#[derive(Default)]
+pub(crate) struct DeviceLostState {
+    lost: AtomicBool,
+    watched: AtomicBool,
+    handler: Mutex<Option<Handler>>,
+}
+
+impl DeviceLostState {
+    /// Registers this state as a watcher of `device`, once, and installs the device lost callback of `device` notifying all its watchers, replacing the previous callback.
+    pub(crate) fn watch(self: &Arc<Self>, device: &wgpu::Device) {
+        if self.watched.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let key = {
+            let mut watchers = WATCHERS.lock().expect("Watchers panicked");
+            let key = watchers
+                .iter()
+                .find(|(_, watched, _)| watched == device)
+                .map_or_else(
+                    || NEXT_KEY.fetch_add(1, Ordering::Relaxed),
+                    |(key, _, _)| *key,
+                );
+            watchers.push((key, device.clone(), Arc::downgrade(self)));
+            key
+        };
+        device.set_device_lost_callback(move |reason, message| {
+            // The callback is also called when the device is dropped or when the callback is replaced.
+            if matches!(
+                reason,
+                wgpu::DeviceLostReason::Unknown | wgpu::DeviceLostReason::Destroyed
+            ) {
+                crate::diagnostics::log_warn!("Device lost ({:?}): {}", reason, message);
+                let states = WATCHERS
+                    .lock()
+                    .expect("Watchers panicked")
+                    .iter()
+                    .filter(|(watched, _, _)| *watched == key)
+                    .filter_map(|(_, _, state)| state.upgrade())
+                    .collect::<Vec<_>>();
+                for state in states {
+                    state.notify(&message);
+                }
+            }
+        });
+    }
+
+    fn notify(&self, message: &str) {
+        self.lost.store(true, Ordering::Release);
+        if let Some(handler) = &*self.handler.lock().expect("Handler panicked") {
+            handler(message);
+        }
+    }
+}
+
+impl Drop for DeviceLostState {
+    fn drop(&mut self) {
+        if *self.watched.get_mut() {
+            if let Ok(mut watchers) = WATCHERS.lock() {
+                watchers.retain(|(_, _, state)| state.strong_count() > 0);
+            }
+        }
+    }
+}
+
+impl GpuComputeAsync {
+    /// This method is used to be notified with the message of the driver when the device is lost, replacing the previous handler. The handler is called on the thread polling the device, it shouldn't block. Every `GpuComputeAsync` sharing the device is notified, but a device given with `GpuComputeAsync::from_device` is only watched once this method is called, and its previous device lost callback is then replaced.
+    pub fn set_device_lost_handler(&self, handler: impl Fn(&str) + Send + 'static) {
+        *self.device_lost.handler.lock().expect("Handler panicked") = Some(Box::new(handler));
+        self.device_lost.watch(&self.device);
+    }
+
+    /// Whether the device was lost, in which case the pipelines have to be rebuilt on a new device, see `GpuComputeAsync::recreate`. It is always `false` for a device given with `GpuComputeAsync::from_device` without a device lost handler.
+    #[inline]
+    pub fn is_device_lost(&self) -> bool {
+        self.device_lost.lost.load(Ordering::Acquire)
+    }
+
+    /// This method is used to create a new device with the adapter options and the requirements of this one, e.g. after it was lost. The pipelines are rebuilt on it with `PipelineAsync::rebuild_on`. The lint callback, the device lost handler and the shared uniform are not carried over.
+    ///
+    /// # Errors
+    /// `SgpuError::Unsupported` if the device was created with `GpuComputeAsync::from_device`, since it is owned by the application, or the errors of `GpuComputeBuilder::build_async`.
+    pub async fn recreate(&self) -> Result<GpuComputeAsync, SgpuError> {
+        let builder = self.builder.clone().ok_or_else(|| {
+            SgpuError::Unsupported(
+                "a device given with `from_device` can't be recreated, create a new one and use `from_device` again".into(),
+            )
+        })?;
+        crate::diagnostics::log_debug!("Recreating the device");
+        builder.build_async().await
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl crate::blocking::GpuCompute {
+    /// Blocking version of `GpuComputeAsync::recreate`.
+    #[inline]
+    pub fn recreate(&self) -> Result<crate::blocking::GpuCompute, SgpuError> {
+        pollster::block_on(self.0.recreate()).map(crate::blocking::GpuCompute)
+    }
+}
@@ -0,0 +1,150 @@
+//! Scoped runs, submitted to the GPU together and all completed before the scope returns.
+//!
+//! `GpuComputeAsync::scope` gives a `Scope` on which runs are spawned with `Scope::spawn_run`. A spawned run only submits the work to the GPU, so several pipelines can run concurrently and be joined later. The pipelines are borrowed for the whole scope and the scope waits for all the submitted work before returning, so no GPU work can outlive the buffers it uses. A run that is dropped without being joined is cancelled: its callback is never called.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//!
+//! let shader = "
+//!     @group(0) @binding(0) var<uniform> coefficient: u32;
+//!     @group(0) @binding(1) var<storage, read> in: array<u32>;
+//!     @group(0) @binding(2) var<storage, read_write> out: array<u32>;
+//!
+//!     @compute @workgroup_size(4)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[id.x] = coefficient * in[id.x];
+//!     }
+//! ";
+//! let stage = StageDesc::new(shader, "main");
+//! let gpu = GpuCompute::new();
+//! let mut double = gpu.gen_pipeline::<[u32; 4], u32, [u32; 4], 1>(None, [stage.clone()]);
+//! let mut triple = double.clone_for_thread();
+//! double.write_uniform(&2);
+//! triple.write_uniform(&3);
+//!
+//! let (doubled, tripled) = gpu.scope(|s| {
+//!     let doubled = s.spawn_run(&mut double, &[1, 2, 3, 4], [(1, 1, 1)], |out| *out);
+//!     let tripled = s.spawn_run(&mut triple, &[1, 2, 3, 4], [(1, 1, 1)], |out| *out);
+//!     (doubled.join_blocking(), tripled.join_blocking())
+//! });
+//! assert_eq!(doubled, [2, 4, 6, 8]);
+//! assert_eq!(tripled, [3, 6, 9, 12]);
+//! ```
+use crate::{options::PollStrategy, GpuComputeAsync, PipelineAsync};
+use std::marker::PhantomData;
+
+/// A scope in which runs can be spawned, see `GpuComputeAsync::scope`.
+pub struct Scope<'scope, 'env: 'scope> {
+    gpu: &'env GpuComputeAsync,
+    // Invariant over `'scope` like `std::thread::Scope`, so that the scope can't be shrunk.
+    _scope: PhantomData<&'scope mut &'scope ()>,
+}
+
+impl GpuComputeAsync {
+    /// This method is used to run several pipelines concurrently. All the runs spawned in the scope are completed, or cancelled if they were not joined, before this method returns.
+    pub fn scope<'env, R>(
+        &'env self,
+        f: impl for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> R,
+    ) -> R {
+        let scope = Scope {
+            gpu: self,
+            _scope: PhantomData,
+        };
+        let res = f(&scope);
+        self.device.poll(wgpu::Maintain::Wait);
+        res
+    }
+}
+
+impl<'scope, 'env> Scope<'scope, 'env> {
+    /// Submits a run of `pipeline` without waiting for it. The pipeline stays borrowed until the end of the scope and the callback is called when the returned `ScopedRun` is joined. The pipeline must have been generated by the device of the scope.
+    pub fn spawn_run<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        Output: bytemuck::Pod,
+        const N: usize,
+        T,
+    >(
+        &'scope self,
+        pipeline: &'scope mut PipelineAsync<'_, Input, Uniform, Output, N>,
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+        callback: impl FnOnce(&Output) -> T + Send + 'scope,
+    ) -> ScopedRun<'scope, T> {
+        assert!(
+            std::ptr::eq(&*pipeline.device, self.gpu),
+            "The pipeline was generated by another device"
+        );
+        let (index, mapped) = pipeline.submit(None, Some(input), workgroups);
+        ScopedRun {
+            gpu: self.gpu,
+            output: &pipeline.buffers.output,
+            index: Some(index),
+            mapped,
+            callback: Some(Box::new(move |bytes| callback(bytemuck::from_bytes(bytes)))),
+        }
+    }
+}
+
+/// Callback of a run, converting the mapped output bytes.
+type Callback<'scope, T> = Box<dyn FnOnce(&[u8]) -> T + Send + 'scope>;
+
+/// A run spawned in a `Scope`. Joining it waits for the run and returns the result of its callback.
+pub struct ScopedRun<'scope, T> {
+    gpu: &'scope GpuComputeAsync,
+    output: &'scope wgpu::Buffer,
+    index: Option<wgpu::SubmissionIndex>,
+    mapped: flume::Receiver<()>,
+    callback: Option<Callback<'scope, T>>,
+}
+
+impl<T> ScopedRun<'_, T> {
+    /// Whether the run is completed, in which case joining it doesn't wait.
+    pub fn is_finished(&self) -> bool {
+        self.gpu.device.poll(wgpu::Maintain::Poll);
+        !self.mapped.is_empty()
+    }
+
+    /// Waits for the run and returns the result of its callback. The device is only polled here with `PollStrategy::Blocking`.
+    pub async fn join(mut self) -> T {
+        if self.gpu.poll_strategy() == PollStrategy::Blocking {
+            self.wait();
+        }
+        self.mapped.recv_async().await.expect("Error with channel");
+        self.finish()
+    }
+
+    /// Blocking version of `ScopedRun::join`.
+    pub fn join_blocking(mut self) -> T {
+        self.wait();
+        self.mapped.recv().expect("Error with channel");
+        self.finish()
+    }
+
+    fn wait(&mut self) {
+        if let Some(index) = self.index.take() {
+            self.gpu
+                .device
+                .poll(wgpu::Maintain::WaitForSubmissionIndex(index));
+        }
+    }
+
+    fn finish(&mut self) -> T {
+        let callback = self.callback.take().expect("Run is only finished once");
+        let res = callback(self.output.slice(..).get_mapped_range().as_ref());
+        self.output.unmap();
+        res
+    }
+}
+
+impl<T> Drop for ScopedRun<'_, T> {
+    fn drop(&mut self) {
+        if self.callback.take().is_some() {
+            // The run was not joined, the output buffer is unmapped without calling the callback so the pipeline can be run again.
+            self.wait();
+            if self.mapped.recv().is_ok() {
+                self.output.unmap();
+            }
+        }
+    }
+}
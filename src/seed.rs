@@ -0,0 +1,121 @@
+//! Reproducible random numbers for stochastic kernels.
+//!
+//! A `SeedManager` derives the `Seed` of each dispatch from a master seed and the step of the dispatch, e.g. the iteration of a simulation. The shader gets the seed through its uniform and draws its random numbers with the functions of `WGSL`, which only depend on the seed, the global index of the element and the number of the draw. Dispatching the same step in several chunks, each one with the offset of its first element, gives the same random numbers as a single dispatch. `Seed::random_u32` and `Seed::random_f32` compute the same numbers on the CPU.
+//!
+//! `Seed` is 16 bytes, when it is a member of a larger uniform struct it must be at an offset multiple of 16 bytes.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::seed::{self, SeedManager};
+//!
+//! let shader = format!("{}{}", seed::WGSL, "
+//!     @group(0) @binding(0) var<uniform> seed: Seed;
+//!     @group(0) @binding(1) var<storage, read_write> out: array<f32>;
+//!
+//!     @compute @workgroup_size(64)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[id.x] = seed_random_f32(seed, id.x, 0u);
+//!     }
+//! ");
+//! let seeds = SeedManager::new(42);
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline::<(), seed::Seed, [f32; 64], 1>(None, [StageDesc::new(shader.leak(), "main")]);
+//! pipeline.write_uniform(&seeds.seed(0));
+//! let first = pipeline.run(&(), [(1, 1, 1)], |out| *out);
+//! pipeline.write_uniform(&seeds.seed(0));
+//! assert_eq!(pipeline.run(&(), [(1, 1, 1)], |out| *out), first);
+//! assert_eq!(first[3], seeds.seed(0).random_f32(3, 0));
+//! ```
+
+/// WGSL source declaring the `Seed` struct and the `seed_random_u32` and `seed_random_f32` functions. It has no binding, so it can be prepended to any shader.
+pub const WGSL: &str = include_str!("seed.wgsl");
+
+/// SplitMix64 finalizer, used to derive independent keys.
+#[inline]
+const fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/// Same as `seed_hash` in `WGSL`.
+#[inline]
+const fn hash(v: u32) -> u32 {
+    let state = v.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+    (word >> 22) ^ word
+}
+
+/// Derives the seeds of the dispatches from a master seed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SeedManager {
+    master: u64,
+}
+
+impl SeedManager {
+    #[inline]
+    pub const fn new(master: u64) -> Self {
+        Self { master }
+    }
+
+    #[inline]
+    pub const fn master(&self) -> u64 {
+        self.master
+    }
+
+    /// Returns an independent manager for the given stream, e.g. one per kernel, so that kernels sharing a master seed don't draw the same numbers.
+    #[inline]
+    pub const fn derive(&self, stream: u64) -> Self {
+        Self::new(splitmix64(self.master ^ splitmix64(stream)))
+    }
+
+    /// Returns the seed of the dispatch of `step`, covering the elements from index 0.
+    #[inline]
+    pub const fn seed(&self, step: u64) -> Seed {
+        self.seed_chunk(step, 0)
+    }
+
+    /// Returns the seed of the chunk of the dispatch of `step` whose first element has the global index `offset`.
+    #[inline]
+    pub const fn seed_chunk(&self, step: u64, offset: u32) -> Seed {
+        let key = splitmix64(self.master ^ splitmix64(step.wrapping_add(1)));
+        Seed {
+            key: (key >> 32) as u32,
+            offset,
+            _pad0: 0,
+            _pad1: 0,
+        }
+    }
+}
+
+/// Seed of a dispatch, see `SeedManager`. It matches the `Seed` struct of `WGSL`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+pub struct Seed {
+    pub key: u32,
+    pub offset: u32,
+    _pad0: u32,
+    _pad1: u32,
+}
+
+impl Seed {
+    /// Same seed for a chunk starting at the global index `offset`.
+    #[inline]
+    pub const fn with_offset(self, offset: u32) -> Self {
+        Self { offset, ..self }
+    }
+
+    /// Same as `seed_random_u32` in `WGSL`, where `index` is relative to the offset of the seed.
+    #[inline]
+    pub const fn random_u32(&self, index: u32, draw: u32) -> u32 {
+        let element = hash(self.key ^ hash(self.offset.wrapping_add(index)));
+        hash(element.wrapping_add(draw.wrapping_mul(0x9e3779b9)))
+    }
+
+    /// Same as `seed_random_f32` in `WGSL`.
+    #[inline]
+    pub fn random_f32(&self, index: u32, draw: u32) -> f32 {
+        (self.random_u32(index, draw) >> 8) as f32 * (1.0 / 16777216.0)
+    }
+}
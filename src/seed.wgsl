@@ -0,0 +1,26 @@
+// Counter-based random numbers, see the `seed` module of sgpu-compute.
+
+struct Seed {
+    key: u32,
+    offset: u32,
+    _pad0: u32,
+    _pad1: u32,
+}
+
+// PCG hash, "Hash Functions for GPU Rendering" (Jarzynski and Olano, 2020).
+fn seed_hash(v: u32) -> u32 {
+    let state = v * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+// Returns the `draw`-th random number of the element `index` of the dispatch.
+fn seed_random_u32(seed: Seed, index: u32, draw: u32) -> u32 {
+    let element = seed_hash(seed.key ^ seed_hash(seed.offset + index));
+    return seed_hash(element + draw * 0x9e3779b9u);
+}
+
+// Same as `seed_random_u32`, but uniformly distributed in [0, 1).
+fn seed_random_f32(seed: Seed, index: u32, draw: u32) -> f32 {
+    return f32(seed_random_u32(seed, index, draw) >> 8u) * (1.0 / 16777216.0);
+}
@@ -0,0 +1,180 @@
+//! Portable serialization of result buffers.
+//!
+//! Buffers are written with a small header followed by the values, everything being little-endian whatever the machine:
+//!     - the magic `SGPU` and the format version (1 byte)
+//!     - the `DType` of the values (1 byte) and the number of dimensions (1 byte)
+//!     - each dimension as a `u64`
+//!     - the values in row-major order
+//!
+//! ```rust
+//! use sgpu_compute::serialize;
+//!
+//! let values: [f32; 6] = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0];
+//! let mut file = Vec::new();
+//! serialize::write(&mut file, &[2, 3], &values).unwrap();
+//! let (shape, read) = serialize::read::<f32>(file.as_slice()).unwrap();
+//! assert_eq!(shape, [2, 3]);
+//! assert_eq!(read, values);
+//! ```
+use crate::error::invalid_data;
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+const MAGIC: &[u8; 4] = b"SGPU";
+const VERSION: u8 = 1;
+
+/// Type of the values stored in a file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u8)]
+pub enum DType {
+    U8 = 0,
+    I8 = 1,
+    U16 = 2,
+    I16 = 3,
+    U32 = 4,
+    I32 = 5,
+    U64 = 6,
+    I64 = 7,
+    F32 = 8,
+    F64 = 9,
+}
+
+impl DType {
+    /// Size of a single value in bytes.
+    #[inline]
+    pub const fn size(self) -> usize {
+        match self {
+            DType::U8 | DType::I8 => 1,
+            DType::U16 | DType::I16 => 2,
+            DType::U32 | DType::I32 | DType::F32 => 4,
+            DType::U64 | DType::I64 | DType::F64 => 8,
+        }
+    }
+
+    fn from_u8(value: u8) -> Option<Self> {
+        const ALL: [DType; 10] = [
+            DType::U8,
+            DType::I8,
+            DType::U16,
+            DType::I16,
+            DType::U32,
+            DType::I32,
+            DType::U64,
+            DType::I64,
+            DType::F32,
+            DType::F64,
+        ];
+        ALL.get(value as usize).copied()
+    }
+}
+
+/// Scalar types that can be serialized.
+pub trait Element: bytemuck::Pod {
+    const DTYPE: DType;
+}
+
+macro_rules! impl_element {
+    ($($ty:ty => $dtype:ident),*) => {
+        $(impl Element for $ty {
+            const DTYPE: DType = DType::$dtype;
+        })*
+    };
+}
+
+impl_element!(u8 => U8, i8 => I8, u16 => U16, i16 => I16, u32 => U32, i32 => I32, u64 => U64, i64 => I64, f32 => F32, f64 => F64);
+
+/// Swaps the bytes of each value between native and little-endian order, which is a no-op on little-endian machines.
+fn swap_to_le<T: Element>(bytes: &mut [u8]) {
+    if cfg!(target_endian = "big") {
+        bytes
+            .chunks_exact_mut(std::mem::size_of::<T>())
+            .for_each(<[u8]>::reverse);
+    }
+}
+
+/// Writes `data` with the given `shape`, whose product must be the length of `data`.
+pub fn write<T: Element>(mut writer: impl Write, shape: &[usize], data: &[T]) -> io::Result<()> {
+    if shape.iter().product::<usize>() != data.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("shape {:?} does not match {} values", shape, data.len()),
+        ));
+    }
+    let ndim = u8::try_from(shape.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too many dimensions"))?;
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[VERSION, T::DTYPE as u8, ndim])?;
+    for &dim in shape {
+        writer.write_all(&(dim as u64).to_le_bytes())?;
+    }
+    let mut bytes = bytemuck::cast_slice::<T, u8>(data).to_vec();
+    swap_to_le::<T>(&mut bytes);
+    writer.write_all(&bytes)
+}
+
+/// Reads values written by `write`, returning their shape and the values. Fails if the values are not of type `T`.
+pub fn read<T: Element>(mut reader: impl Read) -> io::Result<(Vec<usize>, Vec<T>)> {
+    let mut header = [0u8; 7];
+    reader.read_exact(&mut header)?;
+    if &header[..4] != MAGIC {
+        return Err(invalid_data("not an sgpu-compute file".into()));
+    }
+    if header[4] != VERSION {
+        return Err(invalid_data(format!("unsupported version {}", header[4])));
+    }
+    let dtype = DType::from_u8(header[5])
+        .ok_or_else(|| invalid_data(format!("unknown dtype {}", header[5])))?;
+    if dtype != T::DTYPE {
+        return Err(invalid_data(format!(
+            "file contains {:?} values, not {:?}",
+            dtype,
+            T::DTYPE
+        )));
+    }
+    let shape = (0..header[6])
+        .map(|_| {
+            let mut dim = [0u8; 8];
+            reader.read_exact(&mut dim)?;
+            usize::try_from(u64::from_le_bytes(dim))
+                .map_err(|_| invalid_data("dimension does not fit in usize".into()))
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    let len = shape
+        .iter()
+        .try_fold(1usize, |acc, &dim| acc.checked_mul(dim))
+        .ok_or_else(|| invalid_data("shape is too large".into()))?;
+    let size = len
+        .checked_mul(std::mem::size_of::<T>())
+        .ok_or_else(|| invalid_data("shape is too large".into()))?;
+    // Reading at most `size` bytes doesn't allocate the size announced by a corrupted header up front.
+    let mut bytes = Vec::new();
+    reader.take(size as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != size {
+        return Err(invalid_data(format!(
+            "expected {} bytes of values, found {}",
+            size,
+            bytes.len()
+        )));
+    }
+    swap_to_le::<T>(&mut bytes);
+    let data = bytes
+        .chunks_exact(std::mem::size_of::<T>())
+        .map(bytemuck::pod_read_unaligned)
+        .collect();
+    Ok((shape, data))
+}
+
+/// Writes `data` with the given `shape` to the file at `path`, see `write`.
+pub fn save<T: Element>(path: impl AsRef<Path>, shape: &[usize], data: &[T]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write(&mut writer, shape, data)?;
+    writer.flush()
+}
+
+/// Reads the file at `path` written by `save`, see `read`.
+pub fn load<T: Element>(path: impl AsRef<Path>) -> io::Result<(Vec<usize>, Vec<T>)> {
+    read(BufReader::new(File::open(path)?))
+}
@@ -0,0 +1,172 @@
+//! Partition of a workload across several pipelines, e.g. on several devices or queues.
+//!
+//! `shard_by` assigns each item to a shard from the hash returned by the given closure, so the same key always goes to the same shard and, with jump consistent hashing, only a fraction of the keys move when the number of shards changes. Each shard is processed independently, and `Shards::merge` puts the outputs back in the order of the inputs, so the result doesn't depend on the number of shards nor on the order in which they complete. `Shards::run_on` runs each shard on its own pipeline in its own thread.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::shard::shard_by;
+//!
+//! let shader = "
+//!     @group(0) @binding(0) var<storage, read> in: array<u32>;
+//!     @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+//!
+//!     @compute @workgroup_size(64)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[id.x] = in[id.x] * 2u;
+//!     }
+//! ";
+//! let gpu = GpuCompute::new();
+//! let pipeline = gpu.gen_pipeline::<[u32; 64], (), [u32; 64], 1>(None, [StageDesc::new(shader, "main")]);
+//! let mut pipelines = vec![pipeline.clone_for_thread(), pipeline];
+//!
+//! let items = (0..100u32).collect::<Vec<_>>();
+//! let shards = shard_by(items, 2, |item| *item as u64);
+//! let doubled = shards.run_on(&mut pipelines, |pipeline, shard| {
+//!     shard
+//!         .chunks(64)
+//!         .flat_map(|chunk| {
+//!             let mut input = [0; 64];
+//!             input[..chunk.len()].copy_from_slice(chunk);
+//!             pipeline.run(&input, [(1, 1, 1)], |out| out[..chunk.len()].to_vec())
+//!         })
+//!         .collect()
+//! });
+//! assert_eq!(doubled, (0..100).map(|v| v * 2).collect::<Vec<_>>());
+//! ```
+
+/// Items partitioned by `shard_by`, with the position of each item in the input.
+#[derive(Debug, Clone)]
+pub struct Shards<T> {
+    shards: Vec<Vec<T>>,
+    /// Shard of each input item, in the order of the inputs.
+    origins: Vec<usize>,
+}
+
+/// Partitions `items` into `count` shards from the hash returned by `hash`. Items with the same hash always go to the same shard, and the items of a shard keep their relative order.
+///
+/// # Panics
+/// If `count` is zero.
+pub fn shard_by<T>(
+    items: impl IntoIterator<Item = T>,
+    count: usize,
+    hash: impl Fn(&T) -> u64,
+) -> Shards<T> {
+    assert!(count > 0, "At least one shard is required");
+    let mut shards = (0..count).map(|_| Vec::new()).collect::<Vec<_>>();
+    let origins = items
+        .into_iter()
+        .map(|item| {
+            let shard = shard_of(hash(&item), count);
+            shards[shard].push(item);
+            shard
+        })
+        .collect();
+    Shards { shards, origins }
+}
+
+/// Shard of the key `hash` among `count` shards, with the jump consistent hash of Lamping and Veach. When the number of shards grows from `count` to `count + 1`, only `1 / (count + 1)` of the keys move, all to the new shard.
+pub fn shard_of(hash: u64, count: usize) -> usize {
+    let mut key = hash;
+    let mut shard = -1i64;
+    let mut next = 0i64;
+    while next < count as i64 {
+        shard = next;
+        key = key.wrapping_mul(2862933555777941757).wrapping_add(1);
+        next = ((shard + 1) as f64 * ((1u64 << 31) as f64 / ((key >> 33) + 1) as f64)) as i64;
+    }
+    shard as usize
+}
+
+impl<T> Shards<T> {
+    /// Number of shards, including the empty ones.
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Number of items in all the shards.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.origins.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.origins.is_empty()
+    }
+
+    /// Items of the shard at `index`, in the order of the inputs.
+    #[inline]
+    pub fn shard(&self, index: usize) -> &[T] {
+        &self.shards[index]
+    }
+
+    /// Iterator over the items of each shard.
+    pub fn iter(&self) -> impl Iterator<Item = &[T]> {
+        self.shards.iter().map(Vec::as_slice)
+    }
+
+    /// Merges the outputs of the shards, one output per item in the order of its shard, back in the order of the inputs.
+    ///
+    /// # Panics
+    /// If there isn't one output per shard, or if an output doesn't have one value per item of its shard.
+    pub fn merge<R>(&self, outputs: Vec<Vec<R>>) -> Vec<R> {
+        assert_eq!(outputs.len(), self.count(), "Expected one output per shard");
+        for (i, (output, shard)) in outputs.iter().zip(&self.shards).enumerate() {
+            assert_eq!(
+                output.len(),
+                shard.len(),
+                "Expected one value per item in the output of shard {}",
+                i
+            );
+        }
+        let mut outputs = outputs.into_iter().map(Vec::into_iter).collect::<Vec<_>>();
+        self.origins
+            .iter()
+            .map(|&shard| outputs[shard].next().expect("Lengths are checked"))
+            .collect()
+    }
+
+    /// Runs `run` on each shard in turn with its index and merges the outputs, see `Shards::merge`.
+    pub fn map<R>(&self, mut run: impl FnMut(usize, &[T]) -> Vec<R>) -> Vec<R> {
+        let outputs = self
+            .shards
+            .iter()
+            .enumerate()
+            .map(|(i, shard)| run(i, shard))
+            .collect();
+        self.merge(outputs)
+    }
+
+    /// Runs `run` on each shard with the pipeline of the same index, each on its own thread, and merges the outputs, see `Shards::merge`. The pipelines can be on different devices, e.g. owned pipelines from `GpuComputeAsync::gen_pipeline_owned` on each available adapter.
+    ///
+    /// # Panics
+    /// If there isn't one pipeline per shard, or if `run` panics.
+    pub fn run_on<P: Send, R: Send>(
+        &self,
+        pipelines: &mut [P],
+        run: impl Fn(&mut P, &[T]) -> Vec<R> + Sync,
+    ) -> Vec<R>
+    where
+        T: Sync,
+    {
+        assert_eq!(
+            pipelines.len(),
+            self.count(),
+            "Expected one pipeline per shard"
+        );
+        let run = &run;
+        let outputs = std::thread::scope(|s| {
+            let handles = pipelines
+                .iter_mut()
+                .zip(&self.shards)
+                .map(|(pipeline, shard)| s.spawn(move || run(pipeline, shard)))
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("Shard panicked"))
+                .collect()
+        });
+        self.merge(outputs)
+    }
+}
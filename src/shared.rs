@@ -0,0 +1,116 @@
+//! Uniform shared by all the pipelines of a device.
+//!
+//! Values used by many pipelines, like the simulation time or a global configuration, can be written once per frame with `GpuComputeAsync::write_shared_uniform` instead of being written in the uniform of every pipeline. The shared uniform is bound at `@group(1) @binding(0)`, so it doesn't shift the bindings of the group 0, and only the pipelines whose shaders use it bind it. It must be written before generating these pipelines, since its size is part of their layout, and it keeps the type of its first write.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//!
+//! #[derive(Debug, Copy, Clone, bytemuck::Zeroable, bytemuck::Pod)]
+//! #[repr(C)]
+//! struct Globals {
+//!     time: f32,
+//!     scale: f32,
+//! }
+//!
+//! let shader = "
+//!     struct Globals {
+//!         time: f32,
+//!         scale: f32,
+//!     }
+//!
+//!     @group(0) @binding(0) var<storage, read> in: array<f32>;
+//!     @group(0) @binding(1) var<storage, read_write> out: array<f32>;
+//!     @group(1) @binding(0) var<uniform> globals: Globals;
+//!
+//!     @compute @workgroup_size(4)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[id.x] = globals.scale * in[id.x] + globals.time;
+//!     }
+//! ";
+//! let gpu = GpuCompute::new();
+//! gpu.write_shared_uniform(&Globals { time: 0.0, scale: 2.0 });
+//! let stage = StageDesc::new(shader, "main");
+//! let mut first = gpu.gen_pipeline::<[f32; 4], (), [f32; 4], 1>(None, [stage.clone()]);
+//! let mut second = gpu.gen_pipeline::<[f32; 4], (), [f32; 4], 1>(None, [stage]);
+//! assert_eq!(first.run(&[1.0; 4], [(1, 1, 1)], |out| *out), [2.0; 4]);
+//!
+//! gpu.write_shared_uniform(&Globals { time: 1.0, scale: 2.0 });
+//! assert_eq!(first.run(&[1.0; 4], [(1, 1, 1)], |out| *out), [3.0; 4]);
+//! assert_eq!(second.run(&[1.0; 4], [(1, 1, 1)], |out| *out), [3.0; 4]);
+//! ```
+use crate::GpuComputeAsync;
+
+/// Bind group of the shared uniform.
+pub const GROUP: u32 = 1;
+
+/// Buffer of the shared uniform and its bind group.
+pub(crate) struct SharedUniform {
+    pub(crate) buffer: wgpu::Buffer,
+    pub(crate) layout: wgpu::BindGroupLayout,
+    pub(crate) bindgroup: wgpu::BindGroup,
+}
+
+impl SharedUniform {
+    fn new(device: &wgpu::Device, size: usize) -> Self {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Shared uniform buffer"),
+            size: size as _,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+            mapped_at_creation: false,
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Shared uniform bind group layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bindgroup = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Shared uniform bind group"),
+            layout: &layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(buffer.as_entire_buffer_binding()),
+            }],
+        });
+        Self {
+            buffer,
+            layout,
+            bindgroup,
+        }
+    }
+}
+
+impl GpuComputeAsync {
+    /// This method is used to write the uniform shared by the pipelines of the device, see the `shared` module. The write is visible to the next run of every pipeline using it.
+    ///
+    /// # Panics
+    /// If `T` is zero-sized or doesn't have the size of the first value written.
+    pub fn write_shared_uniform<T: bytemuck::Pod>(&self, value: &T) {
+        let size = std::mem::size_of::<T>();
+        assert!(size > 0, "The shared uniform can't be zero-sized");
+        let shared = self
+            .shared_uniform
+            .get_or_init(|| SharedUniform::new(&self.device, size));
+        assert_eq!(
+            shared.buffer.size(),
+            size as u64,
+            "The shared uniform was created with another size"
+        );
+        self.queue
+            .write_buffer(&shared.buffer, 0, bytemuck::bytes_of(value));
+    }
+
+    /// Returns the shared uniform, which must have been written.
+    pub(crate) fn shared_uniform(&self) -> &SharedUniform {
+        self.shared_uniform
+            .get()
+            .expect("The shared uniform must be written with `write_shared_uniform` before generating a pipeline using it")
+    }
+}
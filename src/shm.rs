@@ -0,0 +1,283 @@
+//! Results shared with other processes through a ring buffer in shared memory, behind the `shm` feature on Unix.
+//!
+//! A GPU worker process creates a `ShmRing` in a file, usually in `/dev/shm`, and publishes the output of each run with `ShmRing::publish` or `ShmRing::publish_pod`. Consumers map the same file, in Rust with `ShmRing::open` or in any language able to map a file, and read the records by sequence number without sockets nor file IO. The ring keeps the last `slot_count` records, older ones are overwritten.
+//!
+//! The file is made of a header of 64 bytes followed by `slot_count` slots, all integers are little-endian:
+//!     - header: the magic `SGRB`, the version `1` as `u32`, `slot_size` and `slot_count` as `u64`, then the number of published records as `u64` at offset 24
+//!     - slot: a stamp as `u64`, the length of the record as `u64`, then `slot_size` bytes padded to 8 bytes
+//!
+//! The record of sequence number `seq` is in the slot `seq % slot_count`. Its stamp is `seq + 1` once it is complete and `0` while it is written, so a reader reads the stamp, copies the record and reads the stamp again: the copy is valid if both are `seq + 1`.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::shm::ShmRing;
+//!
+//! let shader = "
+//!     @group(0) @binding(0) var<storage, read> in: array<u32>;
+//!     @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+//!
+//!     @compute @workgroup_size(64)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[id.x] = in[id.x] * in[id.x];
+//!     }
+//! ";
+//! let path = std::env::temp_dir().join("sgpu-shm-doc");
+//! let mut ring = ShmRing::create(&path, 64 * 4, 8).unwrap();
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline::<[u32; 64], (), [u32; 64], 1>(None, [StageDesc::new(shader, "main")]);
+//! let input: [u32; 64] = std::array::from_fn(|i| i as u32);
+//! let seq = pipeline.run(&input, [(1, 1, 1)], |out| ring.publish_pod(out));
+//!
+//! // In the consumer process.
+//! let consumer = ShmRing::open(&path).unwrap();
+//! let squares = consumer.read_pod::<[u32; 64]>(seq).unwrap();
+//! assert_eq!(squares[9], 81);
+//! # std::fs::remove_file(path).unwrap();
+//! ```
+use crate::error::invalid_data;
+use std::{
+    fs::OpenOptions,
+    io,
+    os::fd::AsRawFd,
+    path::Path,
+    sync::atomic::{fence, AtomicU64, Ordering},
+};
+
+const MAGIC: &[u8; 4] = b"SGRB";
+const VERSION: u32 = 1;
+const HEADER_SIZE: usize = 64;
+const PUBLISHED_OFFSET: usize = 24;
+const SLOT_HEADER_SIZE: usize = 16;
+
+/// Ring buffer of records in a shared-memory file, see the module documentation.
+pub struct ShmRing {
+    ptr: *mut u8,
+    len: usize,
+    slot_size: usize,
+    slot_count: usize,
+    /// Size of a slot with its header, see `slot_stride`.
+    stride: usize,
+}
+
+// SAFETY: The shared fields are only accessed through atomics, and only `publish`, which takes `&mut self`, writes the records.
+unsafe impl Send for ShmRing {}
+unsafe impl Sync for ShmRing {}
+
+impl ShmRing {
+    /// Creates the file at `path`, or truncates it, with `slot_count` slots of `slot_size` bytes and maps it.
+    ///
+    /// # Errors
+    /// If the file can't be created or mapped, or if the size of the ring overflows `usize`.
+    ///
+    /// # Panics
+    /// If `slot_size` or `slot_count` is zero.
+    pub fn create(path: impl AsRef<Path>, slot_size: usize, slot_count: usize) -> io::Result<Self> {
+        assert!(
+            slot_size > 0 && slot_count > 0,
+            "The slots of a ring can't be empty"
+        );
+        let len = ring_len(slot_size, slot_count)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "the ring is too large"))?;
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len(len as u64)?;
+        let ring = Self {
+            ptr: map(&file, len)?,
+            len,
+            slot_size,
+            slot_count,
+            stride: slot_stride(slot_size).expect("The size of the ring was checked"),
+        };
+        // SAFETY: The header is in the mapping, which is zeroed by `set_len`.
+        unsafe {
+            let header = std::slice::from_raw_parts_mut(ring.ptr, HEADER_SIZE);
+            header[0..4].copy_from_slice(MAGIC);
+            header[4..8].copy_from_slice(&VERSION.to_le_bytes());
+            header[8..16].copy_from_slice(&(slot_size as u64).to_le_bytes());
+            header[16..24].copy_from_slice(&(slot_count as u64).to_le_bytes());
+        }
+        Ok(ring)
+    }
+
+    /// Maps the ring created by `ShmRing::create` at `path`, e.g. in a consumer process.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let len = file.metadata()?.len() as usize;
+        if len < HEADER_SIZE {
+            return Err(invalid_data("the file is too small to be a ring".into()));
+        }
+        let ptr = map(&file, len)?;
+        // SAFETY: The header is in the mapping.
+        let header = unsafe { std::slice::from_raw_parts(ptr, HEADER_SIZE) };
+        // The header is written by another process, the sizes are checked against the file below without overflowing.
+        let read_u64 = |offset: usize| {
+            let value = u64::from_le_bytes(header[offset..offset + 8].try_into().unwrap());
+            usize::try_from(value).unwrap_or(usize::MAX)
+        };
+        let (slot_size, slot_count) = (read_u64(8), read_u64(16));
+        let ring = Self {
+            ptr,
+            len,
+            slot_size,
+            slot_count,
+            stride: slot_stride(slot_size).unwrap_or(0),
+        };
+        if &header[0..4] != MAGIC {
+            return Err(invalid_data("the file is not a ring".into()));
+        }
+        let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        if version != VERSION {
+            return Err(invalid_data(format!(
+                "unsupported ring version {}",
+                version
+            )));
+        }
+        if slot_size == 0 || slot_count == 0 || ring_len(slot_size, slot_count) != Some(len) {
+            return Err(invalid_data(
+                "the size of the file doesn't match its slots".into(),
+            ));
+        }
+        Ok(ring)
+    }
+
+    /// Maximum size in bytes of a record.
+    #[inline]
+    pub fn slot_size(&self) -> usize {
+        self.slot_size
+    }
+
+    /// Number of records kept by the ring.
+    #[inline]
+    pub fn slot_count(&self) -> usize {
+        self.slot_count
+    }
+
+    /// Number of records published so far, which is also the sequence number of the next one.
+    #[inline]
+    pub fn published(&self) -> u64 {
+        self.atomic(PUBLISHED_OFFSET).load(Ordering::Acquire)
+    }
+
+    /// Publishes `record` and returns its sequence number. There must be a single publisher per ring, across all the processes.
+    ///
+    /// # Panics
+    /// If `record` is larger than `ShmRing::slot_size`.
+    pub fn publish(&mut self, record: &[u8]) -> u64 {
+        assert!(
+            record.len() <= self.slot_size,
+            "The record is {} bytes but the slots are {} bytes",
+            record.len(),
+            self.slot_size
+        );
+        let seq = self.published();
+        let slot = self.slot_offset(seq);
+        let stamp = self.atomic(slot);
+        stamp.store(0, Ordering::Relaxed);
+        fence(Ordering::Release);
+        self.atomic(slot + 8)
+            .store(record.len() as u64, Ordering::Relaxed);
+        // SAFETY: The data of the slot is in the mapping and is at least `slot_size` bytes.
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                record.as_ptr(),
+                self.ptr.add(slot + SLOT_HEADER_SIZE),
+                record.len(),
+            );
+        }
+        stamp.store(seq + 1, Ordering::Release);
+        self.atomic(PUBLISHED_OFFSET)
+            .store(seq + 1, Ordering::Release);
+        seq
+    }
+
+    /// Same as `ShmRing::publish`, but the record is the bytes of `value`, e.g. the output given to the callback of `PipelineAsync::run`.
+    #[inline]
+    pub fn publish_pod<T: bytemuck::Pod>(&mut self, value: &T) -> u64 {
+        self.publish(bytemuck::bytes_of(value))
+    }
+
+    /// Returns a copy of the record `seq`, `None` if it isn't published yet or was already overwritten.
+    pub fn read(&self, seq: u64) -> Option<Vec<u8>> {
+        let slot = self.slot_offset(seq);
+        let stamp = self.atomic(slot);
+        if stamp.load(Ordering::Acquire) != seq + 1 {
+            return None;
+        }
+        let len = (self.atomic(slot + 8).load(Ordering::Relaxed) as usize).min(self.slot_size);
+        let mut record = vec![0; len];
+        // SAFETY: The data of the slot is in the mapping and is at least `slot_size` bytes. A concurrent write is detected by the stamp below.
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                self.ptr.add(slot + SLOT_HEADER_SIZE),
+                record.as_mut_ptr(),
+                len,
+            );
+        }
+        fence(Ordering::Acquire);
+        (stamp.load(Ordering::Relaxed) == seq + 1).then_some(record)
+    }
+
+    /// Same as `ShmRing::read`, but the record is read as a `T`. Returns `None` if its size isn't the size of `T`.
+    pub fn read_pod<T: bytemuck::Pod>(&self, seq: u64) -> Option<T> {
+        self.read(seq)
+            .filter(|record| record.len() == std::mem::size_of::<T>())
+            .map(|record| bytemuck::pod_read_unaligned(&record))
+    }
+
+    /// Offset of the slot of the record `seq`. It can't overflow since the slots fit in the mapping, which was checked by `ShmRing::create` and `ShmRing::open`.
+    fn slot_offset(&self, seq: u64) -> usize {
+        HEADER_SIZE + (seq % self.slot_count as u64) as usize * self.stride
+    }
+
+    fn atomic(&self, offset: usize) -> &AtomicU64 {
+        debug_assert!(offset.is_multiple_of(8) && offset + 8 <= self.len);
+        // SAFETY: The offsets are multiples of 8 in a page-aligned mapping which lives as long as `self`.
+        unsafe { &*(self.ptr.add(offset) as *const AtomicU64) }
+    }
+}
+
+impl Drop for ShmRing {
+    fn drop(&mut self) {
+        // SAFETY: The mapping was created by `map` with this length and isn't used anymore.
+        unsafe {
+            libc::munmap(self.ptr as *mut libc::c_void, self.len);
+        }
+    }
+}
+
+/// Size of a slot with its stamp and its length, aligned to 8 bytes, `None` if it overflows `usize`.
+fn slot_stride(slot_size: usize) -> Option<usize> {
+    slot_size
+        .checked_next_multiple_of(8)?
+        .checked_add(SLOT_HEADER_SIZE)
+}
+
+/// Size of the file of a ring, `None` if it overflows `usize`.
+fn ring_len(slot_size: usize, slot_count: usize) -> Option<usize> {
+    slot_stride(slot_size)?
+        .checked_mul(slot_count)?
+        .checked_add(HEADER_SIZE)
+}
+
+/// Maps `len` bytes of `file` as shared memory.
+fn map(file: &std::fs::File, len: usize) -> io::Result<*mut u8> {
+    // SAFETY: A new mapping of the file is created, its validity is checked below.
+    let ptr = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED,
+            file.as_raw_fd(),
+            0,
+        )
+    };
+    if ptr == libc::MAP_FAILED {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(ptr as *mut u8)
+}
@@ -0,0 +1,428 @@
+//! Versioned pipeline bundles.
+//!
+//! A `PipelineSpec` records what is needed to generate a pipeline again: the sizes of its buffers and, for each stage, its entry point and the WGSL source of its shader with a hash of it. `PipelineAsync::spec` exports it and `PipelineSpec::write` stores it with a small little-endian header, like the `serialize` module:
+//!     - the magic `SGPS` and the schema version (1 byte)
+//!     - the sizes of the uniform, the scratchpad (0 if there is none), the input and the output as `u64`
+//!     - the number of stages as a `u32`, then for each stage its key, its name, its entry point, its source as strings prefixed by their length and the FNV-1a hash of the source as a `u64`
+//!
+//! `PipelineSpec::read` rejects files of another schema version and shaders whose hash doesn't match, e.g. edited by hand. `PipelineSpec::check` validates the buffer sizes against the limits of the device and `PipelineSpec::check_types` against the types of the pipeline, so that a bundle shipped to end users fails with a `SpecError` explaining what to fix instead of a validation error of the backend.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::spec::PipelineSpec;
+//!
+//! let shader = "
+//!     @group(0) @binding(0) var<storage, read> in: array<u32>;
+//!     @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+//!
+//!     @compute @workgroup_size(4)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[id.x] = in[id.x] + 1u;
+//!     }
+//! ";
+//! let gpu = GpuCompute::new();
+//! let pipeline = gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(None, [StageDesc::new(shader, "main").with_name("increment")]);
+//! let mut file = Vec::new();
+//! pipeline.spec().write(&mut file).unwrap();
+//!
+//! let spec = PipelineSpec::read(file.as_slice()).unwrap();
+//! spec.check(&gpu).unwrap();
+//! spec.check_types::<[u32; 4], (), [u32; 4]>().unwrap();
+//! let mut pipeline = gpu.gen_pipeline_with_provider::<[u32; 4], (), [u32; 4], 1>(
+//!     spec.shaders(),
+//!     None,
+//!     [StageDesc::new("stage0", "main").with_name("increment")],
+//! );
+//! assert_eq!(pipeline.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out), [2, 3, 4, 5]);
+//! ```
+use crate::{describe::BufferRole, GpuComputeAsync, PipelineAsync, ShaderSource};
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+const MAGIC: &[u8; 4] = b"SGPS";
+/// Schema version written by `PipelineSpec::write`, the only one `PipelineSpec::read` accepts.
+pub const VERSION: u8 = 1;
+
+/// Error of loading or validating a `PipelineSpec`.
+#[derive(Debug)]
+pub enum SpecError {
+    Io(io::Error),
+    /// The file doesn't start with the magic of a spec.
+    NotASpec,
+    /// The file was written with another schema version.
+    UnsupportedVersion {
+        found: u8,
+    },
+    /// The file is truncated or one of its strings is not UTF-8.
+    Corrupted(String),
+    /// The source of a stage doesn't have the hash recorded when the spec was exported.
+    ShaderHashMismatch {
+        stage: String,
+        expected: u64,
+        found: u64,
+    },
+    /// A buffer is larger than a limit of the device.
+    BufferTooLarge {
+        role: BufferRole,
+        size: u64,
+        limit: &'static str,
+        max: u64,
+    },
+    /// The pipeline binds more storage buffers than the device allows per stage.
+    TooManyStorageBuffers {
+        count: u32,
+        max: u32,
+    },
+    /// A buffer doesn't have the size of the type given to the pipeline.
+    TypeMismatch {
+        role: BufferRole,
+        expected: u64,
+        found: u64,
+    },
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpecError::Io(error) => write!(f, "could not read the spec: {}", error),
+            SpecError::NotASpec => write!(f, "not an sgpu-compute pipeline spec"),
+            SpecError::UnsupportedVersion { found } if *found > VERSION => write!(
+                f,
+                "spec version {} is newer than the supported version {}, update sgpu-compute",
+                found, VERSION
+            ),
+            SpecError::UnsupportedVersion { found } => write!(
+                f,
+                "spec version {} is older than the supported version {}, export the spec again",
+                found, VERSION
+            ),
+            SpecError::Corrupted(reason) => write!(f, "corrupted spec: {}", reason),
+            SpecError::ShaderHashMismatch {
+                stage,
+                expected,
+                found,
+            } => write!(
+                f,
+                "the shader of the stage `{}` has the hash {:016x} instead of {:016x}, it was modified after the spec was exported, export the spec again",
+                stage, found, expected
+            ),
+            SpecError::BufferTooLarge {
+                role,
+                size,
+                limit,
+                max,
+            } => write!(
+                f,
+                "the {:?} buffer needs {} bytes but `{}` of the device is {} bytes, use a smaller buffer or a device with higher limits",
+                role, size, limit, max
+            ),
+            SpecError::TooManyStorageBuffers { count, max } => write!(
+                f,
+                "the pipeline binds {} storage buffers but the device only allows {} per stage, merge the scratchpad into the output or use a device with higher limits",
+                count, max
+            ),
+            SpecError::TypeMismatch {
+                role,
+                expected,
+                found,
+            } => write!(
+                f,
+                "the {:?} buffer of the spec is {} bytes but the type given to the pipeline is {} bytes, use the types the spec was exported with",
+                role, expected, found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SpecError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SpecError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SpecError {
+    fn from(error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::UnexpectedEof => SpecError::Corrupted("unexpected end of file".into()),
+            _ => SpecError::Io(error),
+        }
+    }
+}
+
+/// FNV-1a hash of a shader source, which is stable across platforms and versions of Rust.
+pub fn shader_hash(source: &str) -> u64 {
+    source.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// A stage of a `PipelineSpec`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageSpec {
+    /// Key of the shader in `PipelineSpec::shaders`, `stage{index}`.
+    pub key: String,
+    pub name: Option<String>,
+    pub entrypoint: String,
+    pub source: String,
+    pub hash: u64,
+}
+
+/// Description of a pipeline which can be stored and validated, see the module documentation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipelineSpec {
+    /// Sizes of the buffers in bytes.
+    pub uniform_size: u64,
+    pub scratchpad_size: Option<u64>,
+    pub input_size: u64,
+    pub output_size: u64,
+    pub stages: Vec<StageSpec>,
+}
+
+impl<Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod, const N: usize>
+    PipelineAsync<'_, Input, Uniform, Output, N>
+{
+    /// This method is used to export the pipeline as a `PipelineSpec`. The shaders go through the shader source provider of the pipeline if it has one.
+    ///
+    /// # Panics
+    /// If a stage is a SPIR-V binary, a spec only records WGSL sources.
+    pub fn spec(&self) -> PipelineSpec {
+        let stages = self
+            .stages
+            .desc
+            .iter()
+            .enumerate()
+            .map(|(index, desc)| {
+                assert!(
+                    desc.source == ShaderSource::Wgsl,
+                    "A spec can't record the SPIR-V binary of stage {}",
+                    index
+                );
+                let source = crate::describe::stage_source(desc, self.stages.provider.as_deref())
+                    .expect("The sources were read when the pipeline was generated")
+                    .into_owned();
+                StageSpec {
+                    key: format!("stage{}", index),
+                    name: desc.name.map(str::to_string),
+                    entrypoint: desc.entrypoint.to_string(),
+                    hash: shader_hash(&source),
+                    source,
+                }
+            })
+            .collect();
+        PipelineSpec {
+            uniform_size: std::mem::size_of::<Uniform>() as u64,
+            scratchpad_size: self.sizes.scratchpad.map(|size| size.get() as u64),
+            input_size: std::mem::size_of::<Input>() as u64,
+            output_size: std::mem::size_of::<Output>() as u64,
+            stages,
+        }
+    }
+}
+
+fn write_str(writer: &mut impl Write, value: &str) -> io::Result<()> {
+    let len = u32::try_from(value.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "string is too long"))?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(value.as_bytes())
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_str(reader: &mut impl Read) -> Result<String, SpecError> {
+    let len = read_u32(reader)?;
+    let mut bytes = Vec::new();
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len as usize {
+        return Err(SpecError::Corrupted("unexpected end of file".into()));
+    }
+    String::from_utf8(bytes).map_err(|_| SpecError::Corrupted("string is not UTF-8".into()))
+}
+
+impl PipelineSpec {
+    /// Writes the spec, see the module documentation for the format.
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        for size in [
+            self.uniform_size,
+            self.scratchpad_size.unwrap_or(0),
+            self.input_size,
+            self.output_size,
+        ] {
+            writer.write_all(&size.to_le_bytes())?;
+        }
+        let stages = u32::try_from(self.stages.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too many stages"))?;
+        writer.write_all(&stages.to_le_bytes())?;
+        for stage in &self.stages {
+            write_str(&mut writer, &stage.key)?;
+            // The name is written as an empty string when there is none.
+            write_str(&mut writer, stage.name.as_deref().unwrap_or(""))?;
+            write_str(&mut writer, &stage.entrypoint)?;
+            write_str(&mut writer, &stage.source)?;
+            writer.write_all(&stage.hash.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Reads a spec written by `PipelineSpec::write`. Fails if it was written with another schema version or if the source of a stage doesn't match its hash.
+    pub fn read(mut reader: impl Read) -> Result<Self, SpecError> {
+        let mut header = [0u8; 5];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(SpecError::NotASpec);
+        }
+        if header[4] != VERSION {
+            return Err(SpecError::UnsupportedVersion { found: header[4] });
+        }
+        let uniform_size = read_u64(&mut reader)?;
+        let scratchpad_size = Some(read_u64(&mut reader)?).filter(|size| *size > 0);
+        let input_size = read_u64(&mut reader)?;
+        let output_size = read_u64(&mut reader)?;
+        let count = read_u32(&mut reader)?;
+        let mut stages = Vec::new();
+        for _ in 0..count {
+            let key = read_str(&mut reader)?;
+            let name = Some(read_str(&mut reader)?).filter(|name| !name.is_empty());
+            let entrypoint = read_str(&mut reader)?;
+            let source = read_str(&mut reader)?;
+            let hash = read_u64(&mut reader)?;
+            let found = shader_hash(&source);
+            if found != hash {
+                return Err(SpecError::ShaderHashMismatch {
+                    stage: name.unwrap_or(key),
+                    expected: hash,
+                    found,
+                });
+            }
+            stages.push(StageSpec {
+                key,
+                name,
+                entrypoint,
+                source,
+                hash,
+            });
+        }
+        Ok(Self {
+            uniform_size,
+            scratchpad_size,
+            input_size,
+            output_size,
+            stages,
+        })
+    }
+
+    /// Writes the spec to the file at `path`, see `PipelineSpec::write`.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Reads the file at `path` written by `PipelineSpec::save`, see `PipelineSpec::read`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SpecError> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Checks that the buffers of the spec fit in the limits of the device.
+    pub fn check(&self, gpu: &GpuComputeAsync) -> Result<(), SpecError> {
+        let limits = gpu.device.limits();
+        let buffers = [
+            (BufferRole::Uniform, self.uniform_size),
+            (BufferRole::Scratchpad, self.scratchpad_size.unwrap_or(0)),
+            (BufferRole::Input, self.input_size),
+            (BufferRole::Output, self.output_size),
+        ];
+        for (role, size) in buffers {
+            let (limit, max) = match role {
+                BufferRole::Uniform => (
+                    "max_uniform_buffer_binding_size",
+                    limits.max_uniform_buffer_binding_size as u64,
+                ),
+                _ => (
+                    "max_storage_buffer_binding_size",
+                    limits.max_storage_buffer_binding_size as u64,
+                ),
+            };
+            let (limit, max) = if limits.max_buffer_size < max {
+                ("max_buffer_size", limits.max_buffer_size)
+            } else {
+                (limit, max)
+            };
+            if size > max {
+                return Err(SpecError::BufferTooLarge {
+                    role,
+                    size,
+                    limit,
+                    max,
+                });
+            }
+        }
+        let count = buffers[1..].iter().filter(|(_, size)| *size > 0).count() as u32;
+        if count > limits.max_storage_buffers_per_shader_stage {
+            return Err(SpecError::TooManyStorageBuffers {
+                count,
+                max: limits.max_storage_buffers_per_shader_stage,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that the buffers of the spec have the sizes of the types given to the pipeline.
+    pub fn check_types<Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod>(
+        &self,
+    ) -> Result<(), SpecError> {
+        for (role, expected, found) in [
+            (
+                BufferRole::Uniform,
+                self.uniform_size,
+                std::mem::size_of::<Uniform>(),
+            ),
+            (
+                BufferRole::Input,
+                self.input_size,
+                std::mem::size_of::<Input>(),
+            ),
+            (
+                BufferRole::Output,
+                self.output_size,
+                std::mem::size_of::<Output>(),
+            ),
+        ] {
+            if expected != found as u64 {
+                return Err(SpecError::TypeMismatch {
+                    role,
+                    expected,
+                    found: found as u64,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the sources of the stages by key, to be used as the shader source provider of `GpuComputeAsync::gen_pipeline_with_provider`.
+    pub fn shaders(&self) -> HashMap<String, String> {
+        self.stages
+            .iter()
+            .map(|stage| (stage.key.clone(), stage.source.clone()))
+            .collect()
+    }
+}
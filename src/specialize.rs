@@ -0,0 +1,96 @@
+//! Specialization of the `override` declarations of a shader with the constants of its stage.
+//!
+//! The constants are given to wgpu in the `compilation_options` of the compute pipeline, see `constants`. wgpu ignores the constants which don't match an override, so `check_constants` rejects them first, along with the values which don't fit the type of their override. Like in WebGPU, an override with an `@id` is set by its id and the others by their name.
+use crate::{error::SgpuError, StageDesc};
+use std::collections::HashMap;
+use wgpu::naga;
+
+/// Constants of a stage, in the form of the `compilation_options` of wgpu. The last value set for a key wins.
+pub(crate) fn constants(desc: &StageDesc) -> HashMap<String, f64> {
+    desc.constants
+        .iter()
+        .map(|&(key, value)| (key.to_owned(), value))
+        .collect()
+}
+
+/// Key setting an override, its id if it has one and its name otherwise.
+fn key(o: &naga::Override) -> Option<String> {
+    o.id.map(|id| id.to_string()).or_else(|| o.name.clone())
+}
+
+/// Checks the constants of a stage against the overrides of its shader.
+///
+/// # Errors
+/// If a constant doesn't match an override, if its value doesn't fit the type of the override, or if an override has no value.
+pub(crate) fn check_constants(desc: &StageDesc, module: &naga::Module) -> Result<(), SgpuError> {
+    let stage = desc.name.unwrap_or(desc.entrypoint);
+    for &(key, value) in desc.constants {
+        let Some((_, o)) = module
+            .overrides
+            .iter()
+            .find(|(_, o)| self::key(o).as_deref() == Some(key))
+        else {
+            return Err(SgpuError::Validation(format!(
+                "stage {} sets the constant `{}` but its shader has no override with this id, or with this name and no id",
+                stage, key
+            )));
+        };
+        check_value(stage, key, &module.types[o.ty].inner, value)?;
+    }
+    for (_, o) in module.overrides.iter() {
+        let key = key(o).unwrap_or_default();
+        if o.init.is_none() && !desc.constants.iter().any(|(k, _)| *k == key) {
+            return Err(SgpuError::Validation(format!(
+                "the override `{}` of stage {} has no default value and isn't set by the constants of the stage",
+                o.name.as_deref().unwrap_or(&key),
+                stage
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that `value` can be converted to the type of an override without losing its meaning, which wgpu doesn't do: it truncates the fractional part of integers.
+fn check_value(stage: &str, key: &str, ty: &naga::TypeInner, value: f64) -> Result<(), SgpuError> {
+    let invalid = |expected: &str| {
+        SgpuError::Validation(format!(
+            "the constant `{}` of stage {} is {}, which isn't {}",
+            key, stage, value, expected
+        ))
+    };
+    match ty {
+        naga::TypeInner::Scalar(naga::Scalar::BOOL) => Ok(()),
+        naga::TypeInner::Scalar(naga::Scalar::I32)
+            if value.fract() != 0.0 || value < i32::MIN as f64 || value > i32::MAX as f64 =>
+        {
+            Err(invalid("an i32"))
+        }
+        naga::TypeInner::Scalar(naga::Scalar::U32)
+            if value.fract() != 0.0 || value < 0.0 || value > u32::MAX as f64 =>
+        {
+            Err(invalid("a u32"))
+        }
+        naga::TypeInner::Scalar(naga::Scalar::F32)
+            if !value.is_finite() || !(value as f32).is_finite() =>
+        {
+            Err(invalid("a finite f32"))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Returns `module` with its overrides replaced by the constants of the stage, or `None` if it doesn't validate or if the constants don't match, e.g. to reflect the workgroup size set by a constant.
+pub(crate) fn specialize(desc: &StageDesc, module: naga::Module) -> Option<naga::Module> {
+    if module.overrides.is_empty() {
+        return Some(module);
+    }
+    let info = naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::all(),
+    )
+    .validate(&module)
+    .ok()?;
+    naga::back::pipeline_constants::process_overrides(&module, &info, &constants(desc))
+        .ok()
+        .map(|(module, _)| module.into_owned())
+}
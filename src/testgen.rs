@@ -0,0 +1,206 @@
+//! Input generation for tests, benchmarks and examples.
+//!
+//! The generators are deterministic: the random ones take a seed and give the same values on every platform, so a failing test can be reproduced from its seed. `TestRng` is a small SplitMix64 generator, so the crate doesn't depend on `rand`.
+//!
+//! ```rust
+//! use sgpu_compute::testgen::{self, Pattern};
+//!
+//! let values: [f32; 64] = testgen::random_f32(7, -1.0..1.0);
+//! assert!(values.iter().all(|v| (-1.0..1.0).contains(v)));
+//! assert_eq!(values, testgen::random_f32(7, -1.0..1.0));
+//!
+//! let ramp: [u32; 4] = testgen::ramp_u32(10, 5);
+//! assert_eq!(ramp, [10, 15, 20, 25]);
+//!
+//! let image: [f32; 16] = testgen::image(4, 4, Pattern::Checkerboard { cell: 2 });
+//! assert_eq!(&image[..4], &[0.0, 0.0, 1.0, 1.0]);
+//!
+//! let matrix = testgen::sparse_matrix(3, 100, 50, 0.1);
+//! assert_eq!(matrix.offsets.len(), 101);
+//! assert_eq!(matrix.to_dense().len(), 100 * 50);
+//! ```
+use std::ops::Range;
+
+/// Deterministic random generator based on SplitMix64.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestRng {
+    state: u64,
+}
+
+impl TestRng {
+    #[inline]
+    pub const fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    #[inline]
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut x = self.state;
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+        x ^ (x >> 31)
+    }
+
+    #[inline]
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Uniform in `[0, 1)`.
+    #[inline]
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 * (1.0 / 16777216.0)
+    }
+
+    /// Uniform in `range`, which must not be empty.
+    #[inline]
+    pub fn range_u32(&mut self, range: Range<u32>) -> u32 {
+        assert!(!range.is_empty(), "Empty range");
+        let len = (range.end - range.start) as u64;
+        range.start + ((self.next_u32() as u64 * len) >> 32) as u32
+    }
+
+    /// Uniform in `range`.
+    #[inline]
+    pub fn range_f32(&mut self, range: Range<f32>) -> f32 {
+        let value = range.start + self.next_f32() * (range.end - range.start);
+        // Rounding can reach the end of the range.
+        if value < range.end {
+            value
+        } else {
+            range.start
+        }
+    }
+
+    /// `true` with the probability `p`.
+    #[inline]
+    pub fn bool(&mut self, p: f32) -> bool {
+        self.next_f32() < p
+    }
+}
+
+/// Returns `N` random `u32` drawn from `seed`.
+pub fn random_u32<const N: usize>(seed: u64) -> [u32; N] {
+    let mut rng = TestRng::new(seed);
+    std::array::from_fn(|_| rng.next_u32())
+}
+
+/// Returns `N` random `u32` in `range` drawn from `seed`.
+pub fn random_u32_in<const N: usize>(seed: u64, range: Range<u32>) -> [u32; N] {
+    let mut rng = TestRng::new(seed);
+    std::array::from_fn(|_| rng.range_u32(range.clone()))
+}
+
+/// Returns `N` random `f32` in `range` drawn from `seed`.
+pub fn random_f32<const N: usize>(seed: u64, range: Range<f32>) -> [f32; N] {
+    let mut rng = TestRng::new(seed);
+    std::array::from_fn(|_| rng.range_f32(range.clone()))
+}
+
+/// Returns `[start, start + step, start + 2 * step, ...]`.
+pub fn ramp<const N: usize>(start: f32, step: f32) -> [f32; N] {
+    std::array::from_fn(|i| start + i as f32 * step)
+}
+
+/// Same as `ramp`, but with wrapping `u32`.
+pub fn ramp_u32<const N: usize>(start: u32, step: u32) -> [u32; N] {
+    std::array::from_fn(|i| start.wrapping_add((i as u32).wrapping_mul(step)))
+}
+
+/// Pattern of the images of `image`, with values in `[0, 1]`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Pattern {
+    /// Squares of `cell` pixels alternating 0 and 1, starting with 0 at the top left.
+    Checkerboard { cell: u32 },
+    /// Horizontal gradient from 0 on the left column to 1 on the right column.
+    Gradient,
+    /// Disk of 1 on a background of 0, centered in the image.
+    Disk { radius: f32 },
+    /// Random values drawn from `seed`.
+    Noise { seed: u64 },
+}
+
+/// Returns a row-major `width * height` image with the given pattern.
+///
+/// # Panics
+/// If `N` is not `width * height`.
+pub fn image<const N: usize>(width: u32, height: u32, pattern: Pattern) -> [f32; N] {
+    assert_eq!(
+        N,
+        width as usize * height as usize,
+        "Image of {}x{} pixels in an array of {}",
+        width,
+        height,
+        N
+    );
+    let mut rng = match pattern {
+        Pattern::Noise { seed } => TestRng::new(seed),
+        _ => TestRng::new(0),
+    };
+    std::array::from_fn(|i| {
+        let (x, y) = (i as u32 % width, i as u32 / width);
+        match pattern {
+            Pattern::Checkerboard { cell } => ((x / cell + y / cell) % 2) as f32,
+            Pattern::Gradient => x as f32 / (width - 1).max(1) as f32,
+            Pattern::Disk { radius } => {
+                let dx = x as f32 + 0.5 - width as f32 / 2.0;
+                let dy = y as f32 + 0.5 - height as f32 / 2.0;
+                (dx * dx + dy * dy <= radius * radius) as u32 as f32
+            }
+            Pattern::Noise { .. } => rng.next_f32(),
+        }
+    })
+}
+
+/// Sparse matrix in compressed sparse row format: the non-zero values of the row `r` are `values[offsets[r]..offsets[r + 1]]`, in the columns `columns[offsets[r]..offsets[r + 1]]`, sorted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseMatrix {
+    pub rows: u32,
+    pub cols: u32,
+    pub offsets: Vec<u32>,
+    pub columns: Vec<u32>,
+    pub values: Vec<f32>,
+}
+
+impl SparseMatrix {
+    /// Number of non-zero values.
+    #[inline]
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns the matrix as a row-major dense matrix.
+    pub fn to_dense(&self) -> Vec<f32> {
+        let mut dense = vec![0.0; self.rows as usize * self.cols as usize];
+        for row in 0..self.rows as usize {
+            for i in self.offsets[row] as usize..self.offsets[row + 1] as usize {
+                dense[row * self.cols as usize + self.columns[i] as usize] = self.values[i];
+            }
+        }
+        dense
+    }
+}
+
+/// Returns a `rows * cols` sparse matrix where each value is non-zero with the probability `density`, the non-zero values being uniform in `(0, 1]`.
+pub fn sparse_matrix(seed: u64, rows: u32, cols: u32, density: f32) -> SparseMatrix {
+    let mut rng = TestRng::new(seed);
+    let mut matrix = SparseMatrix {
+        rows,
+        cols,
+        offsets: Vec::with_capacity(rows as usize + 1),
+        columns: Vec::new(),
+        values: Vec::new(),
+    };
+    matrix.offsets.push(0);
+    for _ in 0..rows {
+        for col in 0..cols {
+            if rng.bool(density) {
+                matrix.columns.push(col);
+                matrix.values.push(1.0 - rng.next_f32());
+            }
+        }
+        matrix.offsets.push(matrix.columns.len() as u32);
+    }
+    matrix
+}
@@ -0,0 +1,229 @@
+//! Timeline of the runs in the Chrome trace format.
+//!
+//! A `Trace` attached to pipelines with `PipelineAsync::set_trace` records the CPU side of each run (upload of the input, submission, wait for the mapping of the output, callback) and, when the device supports `TIMESTAMP_QUERY`, the GPU span of each stage. `Trace::write_chrome_trace` writes them as a JSON file which can be opened in `chrome://tracing` or in Perfetto. The GPU clock is not the CPU clock, so the GPU spans of a run are placed right after its submission.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::trace::Trace;
+//! use std::sync::Arc;
+//!
+//! let shader = "
+//!     @group(0) @binding(0) var<storage, read> in: array<u32>;
+//!     @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+//!
+//!     @compute @workgroup_size(4)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[id.x] = in[id.x] + 1u;
+//!     }
+//! ";
+//! let trace = Arc::new(Trace::new());
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(None, [StageDesc::new(shader, "main").with_name("increment")]);
+//! pipeline.set_trace(Arc::clone(&trace));
+//! pipeline.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out);
+//!
+//! let mut json = Vec::new();
+//! trace.write_chrome_trace_to(&mut json).unwrap();
+//! assert!(String::from_utf8(json).unwrap().contains("\"name\":\"submit\""));
+//! ```
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+    thread::ThreadId,
+    time::{Duration, Instant},
+};
+
+/// Thread id of the GPU spans in the trace, the CPU threads are numbered from 1.
+const GPU_TID: usize = 0;
+
+#[derive(Debug, Clone)]
+struct Event {
+    name: String,
+    category: &'static str,
+    start: Duration,
+    duration: Duration,
+    tid: usize,
+}
+
+/// Recorder of the events of the runs, see the module documentation.
+#[derive(Debug)]
+pub struct Trace {
+    start: Instant,
+    events: Mutex<Vec<Event>>,
+    threads: Mutex<Vec<ThreadId>>,
+}
+
+impl Trace {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            events: Mutex::new(Vec::new()),
+            threads: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Number of recorded events.
+    pub fn len(&self) -> usize {
+        self.events.lock().expect("Trace poisoned").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Records a span of the current thread.
+    pub(crate) fn record_cpu(&self, name: &str, start: Instant, end: Instant) {
+        let id = std::thread::current().id();
+        let tid = {
+            let mut threads = self.threads.lock().expect("Trace poisoned");
+            match threads.iter().position(|thread| *thread == id) {
+                Some(i) => i + 1,
+                None => {
+                    threads.push(id);
+                    threads.len()
+                }
+            }
+        };
+        self.push(Event {
+            name: name.to_string(),
+            category: "cpu",
+            start: start.saturating_duration_since(self.start),
+            duration: end.saturating_duration_since(start),
+            tid,
+        });
+    }
+
+    /// Records a span of the GPU starting `offset` after `origin`.
+    pub(crate) fn record_gpu(
+        &self,
+        name: String,
+        origin: Instant,
+        offset: Duration,
+        duration: Duration,
+    ) {
+        self.push(Event {
+            name,
+            category: "gpu",
+            start: origin.saturating_duration_since(self.start) + offset,
+            duration,
+            tid: GPU_TID,
+        });
+    }
+
+    fn push(&self, event: Event) {
+        self.events.lock().expect("Trace poisoned").push(event);
+    }
+
+    /// Writes the recorded events as a Chrome trace JSON.
+    pub fn write_chrome_trace_to(&self, mut writer: impl Write) -> io::Result<()> {
+        let events = self.events.lock().expect("Trace poisoned").clone();
+        let threads = self.threads.lock().expect("Trace poisoned").len();
+        write!(writer, "{{\"traceEvents\":[")?;
+        write!(
+            writer,
+            "{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":1,\"tid\":{},\"args\":{{\"name\":\"GPU\"}}}}",
+            GPU_TID
+        )?;
+        for tid in 1..=threads {
+            write!(
+                writer,
+                ",{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":1,\"tid\":{},\"args\":{{\"name\":\"CPU {}\"}}}}",
+                tid, tid
+            )?;
+        }
+        for event in events {
+            write!(
+                writer,
+                ",{{\"name\":\"{}\",\"cat\":\"{}\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\"pid\":1,\"tid\":{}}}",
+                escape(&event.name),
+                event.category,
+                event.start.as_secs_f64() * 1e6,
+                event.duration.as_secs_f64() * 1e6,
+                event.tid
+            )?;
+        }
+        write!(writer, "]}}")?;
+        writer.flush()
+    }
+
+    /// Writes the recorded events as a Chrome trace JSON to the file at `path`.
+    pub fn write_chrome_trace(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_chrome_trace_to(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl Default for Trace {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn escape(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Trace of a pipeline and the queries used to time its stages.
+pub(crate) struct Tracing {
+    pub(crate) trace: Arc<Trace>,
+    pub(crate) timestamps: Option<Timestamps>,
+}
+
+/// Query set with the beginning and the end timestamps of each stage, and the buffers used to read it.
+pub(crate) struct Timestamps {
+    pub(crate) query_set: wgpu::QuerySet,
+    pub(crate) resolve: wgpu::Buffer,
+    pub(crate) readback: wgpu::Buffer,
+}
+
+impl Tracing {
+    /// Creates the timestamp queries of `stages` stages if the device supports them.
+    pub(crate) fn new(trace: Arc<Trace>, device: &wgpu::Device, stages: usize) -> Self {
+        let supported = device.features().contains(wgpu::Features::TIMESTAMP_QUERY);
+        if !supported {
+            crate::diagnostics::log_warn!(
+                "Timestamp queries are not supported by the device, the trace only records the CPU spans"
+            );
+        }
+        let timestamps = (supported && stages > 0).then(|| Timestamps::new(device, stages));
+        Self { trace, timestamps }
+    }
+}
+
+impl Timestamps {
+    /// Creates the queries of `stages` stages, the device must support `TIMESTAMP_QUERY`.
+    pub(crate) fn new(device: &wgpu::Device, stages: usize) -> Self {
+        let count = 2 * stages as u32;
+        let size = count as u64 * std::mem::size_of::<u64>() as u64;
+        Self {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("Stage timestamps"),
+                ty: wgpu::QueryType::Timestamp,
+                count,
+            }),
+            resolve: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Timestamp resolve buffer"),
+                size,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            readback: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Timestamp readback buffer"),
+                size,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            }),
+        }
+    }
+}
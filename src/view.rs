@@ -0,0 +1,161 @@
+//! Typed views over the fields of struct outputs.
+//!
+//! When the output is a struct of arrays whose lengths are only known at runtime, e.g. a WGSL `struct { density: array<f32, 1024>, velocity: array<vec4<f32>, 1024> }` read back as raw words, a `Layout` describes its fields once and `Layout::view` returns each field as a slice in the readback callback, without computing offsets by hand. The fields are placed in declaration order, each one aligned to the alignment of its element type, or to the alignment given to `Layout::field_aligned` for the WGSL types which are more aligned than their Rust counterparts, like `vec4`. The elements are padded to the alignment too, so an array of `vec3<f32>`, whose stride is 16 bytes, is viewed as `[f32; 4]`.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::view::Layout;
+//!
+//! let shader = "
+//!     struct Out {
+//!         count: u32,
+//!         velocity: array<vec4<f32>, 4>,
+//!         density: array<f32, 4>,
+//!     }
+//!
+//!     @group(0) @binding(0) var<storage, read_write> out: Out;
+//!
+//!     @compute @workgroup_size(4)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out.count = 4u;
+//!         out.velocity[id.x] = vec4(f32(id.x));
+//!         out.density[id.x] = 0.5 * f32(id.x);
+//!     }
+//! ";
+//! let layout = Layout::new()
+//!     .field::<u32>("count", 1)
+//!     .field_aligned::<[f32; 4]>("velocity", 4, 16)
+//!     .field::<f32>("density", 4);
+//! assert_eq!(layout.size(), 96);
+//!
+//! let gpu = GpuCompute::new();
+//! let mut pipeline = gpu.gen_pipeline::<(), (), [u32; 24], 1>(None, [StageDesc::new(shader, "main")]);
+//! pipeline.run(&(), [(1, 1, 1)], |out| {
+//!     assert_eq!(layout.view::<u32>(out, "count"), [4]);
+//!     assert_eq!(layout.view::<[f32; 4]>(out, "velocity")[2], [2.0; 4]);
+//!     assert_eq!(layout.view::<f32>(out, "density"), [0.0, 0.5, 1.0, 1.5]);
+//! });
+//! ```
+use std::any::{type_name, TypeId};
+
+/// A field of a `Layout`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub name: &'static str,
+    /// Offset of the field in bytes.
+    pub offset: usize,
+    /// Number of elements of the field.
+    pub len: usize,
+    type_id: TypeId,
+    type_name: &'static str,
+    element_size: usize,
+}
+
+impl Field {
+    /// Size of the field in bytes.
+    #[inline]
+    pub const fn size(&self) -> usize {
+        self.len * self.element_size
+    }
+}
+
+/// Layout of a struct of arrays, see the module documentation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Layout {
+    fields: Vec<Field>,
+    size: usize,
+    align: usize,
+}
+
+impl Layout {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a field of `len` elements of type `T`, aligned to the alignment of `T`.
+    #[inline]
+    pub fn field<T: bytemuck::Pod>(self, name: &'static str, len: usize) -> Self {
+        self.field_aligned::<T>(name, len, std::mem::align_of::<T>())
+    }
+
+    /// Same as `Layout::field`, but the field is aligned to `align` bytes, e.g. 16 for an array of `vec4<f32>` viewed as `[f32; 4]`.
+    ///
+    /// # Panics
+    /// If `align` is not a power of two or if a field has the same name.
+    pub fn field_aligned<T: bytemuck::Pod>(
+        mut self,
+        name: &'static str,
+        len: usize,
+        align: usize,
+    ) -> Self {
+        assert!(align.is_power_of_two(), "Alignment must be a power of two");
+        assert!(self.get(name).is_none(), "Field `{}` declared twice", name);
+        let offset = self.size.next_multiple_of(align);
+        let element_size = std::mem::size_of::<T>().next_multiple_of(align);
+        self.fields.push(Field {
+            name,
+            offset,
+            len,
+            type_id: TypeId::of::<T>(),
+            type_name: type_name::<T>(),
+            element_size,
+        });
+        self.size = offset + len * element_size;
+        self.align = self.align.max(align);
+        self
+    }
+
+    /// Size of the struct in bytes, including the padding at its end.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size.next_multiple_of(self.align.max(1))
+    }
+
+    #[inline]
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    /// Returns the field `name` of `output` as a slice.
+    ///
+    /// # Panics
+    /// If there is no such field, if it was declared with another type than `T`, if `output` is smaller than the layout or if the field is not aligned in memory for `T`.
+    pub fn view<'a, T: bytemuck::Pod>(
+        &self,
+        output: &'a impl bytemuck::Pod,
+        name: &str,
+    ) -> &'a [T] {
+        let field = self
+            .get(name)
+            .unwrap_or_else(|| panic!("No field `{}` in the layout", name));
+        assert!(
+            field.type_id == TypeId::of::<T>(),
+            "Field `{}` is declared as {}, not {}",
+            name,
+            field.type_name,
+            type_name::<T>()
+        );
+        assert!(
+            field.element_size == std::mem::size_of::<T>(),
+            "Field `{}` has a stride of {} bytes, it can't be viewed as a slice of {}",
+            name,
+            field.element_size,
+            type_name::<T>()
+        );
+        let bytes = bytemuck::bytes_of(output);
+        assert!(
+            bytes.len() >= self.size,
+            "Output of {} bytes is smaller than the layout of {} bytes",
+            bytes.len(),
+            self.size
+        );
+        bytemuck::try_cast_slice(&bytes[field.offset..field.offset + field.size()])
+            .unwrap_or_else(|e| panic!("Field `{}` can't be viewed: {:?}", name, e))
+    }
+}
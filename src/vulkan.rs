@@ -0,0 +1,79 @@
+//! Interop with other Vulkan libraries, behind the `vulkan` feature.
+//!
+//! When the device uses the Vulkan backend, `GpuComputeAsync::vulkan_handles` returns its raw handles, e.g. to create a video decoder or an allocator on the same device, and `GpuComputeAsync::import_vulkan_buffer` wraps a buffer created by such a library in a `wgpu::Buffer`, which can then be bound as the input of a pipeline with `PipelineAsync::set_input_buffer`. Importing memory from another process, e.g. a dma-buf, requires the external memory extensions, which wgpu doesn't enable: the device has to be created with them through wgpu-hal and given to `GpuComputeAsync::from_device`. The buffers of the pipelines are not exported, so the data goes from the external buffers to the pipelines and not the other way around.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//!
+//! let gpu = GpuCompute::new();
+//! match gpu.vulkan_handles() {
+//!     Some(handles) => println!("Vulkan queue family {}", handles.queue_family_index),
+//!     None => println!("Not a Vulkan device"),
+//! }
+//! ```
+use crate::GpuComputeAsync;
+pub use ash;
+use ash::vk;
+use wgpu::hal::api::Vulkan;
+
+/// Raw Vulkan handles of a device. They stay valid as long as the `GpuComputeAsync` they come from, and must not be destroyed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VulkanHandles {
+    pub instance: vk::Instance,
+    pub physical_device: vk::PhysicalDevice,
+    pub device: vk::Device,
+    /// Queue used by the pipelines, submissions to it must be synchronized with the ones of wgpu.
+    pub queue: vk::Queue,
+    pub queue_family_index: u32,
+}
+
+impl GpuComputeAsync {
+    /// Raw Vulkan handles of the device, `None` if it doesn't use the Vulkan backend.
+    pub fn vulkan_handles(&self) -> Option<VulkanHandles> {
+        // SAFETY: The handles are only copied, the callback doesn't destroy anything.
+        unsafe {
+            self.device.as_hal::<Vulkan, _, _>(|device| {
+                device.map(|device| VulkanHandles {
+                    instance: device.shared_instance().raw_instance().handle(),
+                    physical_device: device.raw_physical_device(),
+                    device: device.raw_device().handle(),
+                    queue: device.raw_queue(),
+                    queue_family_index: device.queue_family_index(),
+                })
+            })
+        }
+    }
+
+    /// Same as `GpuComputeAsync::vulkan_handles`, but `callback` gets the wgpu-hal device, with the function pointers of `ash` to call Vulkan. Returns `None` if the device doesn't use the Vulkan backend.
+    ///
+    /// # Safety
+    /// The handles given to `callback` must not be destroyed, and wgpu must not be used from `callback`.
+    pub unsafe fn with_vulkan_device<R>(
+        &self,
+        callback: impl FnOnce(&wgpu::hal::vulkan::Device) -> R,
+    ) -> Option<R> {
+        self.device
+            .as_hal::<Vulkan, _, _>(|device| device.map(callback))
+    }
+
+    /// This method is used to wrap `buffer`, created by another Vulkan library on the device of `GpuComputeAsync::vulkan_handles`, in a `wgpu::Buffer` of `size` bytes. It can't be mapped, so `usage` must not contain `MAP_READ` nor `MAP_WRITE`.
+    ///
+    /// # Safety
+    /// `buffer` must have been created on this device with usages matching `usage`, its memory must be bound and stay alive, and it must be freed by its owner only after the returned buffer is dropped. The writes of the other library must be finished before the buffer is used by wgpu.
+    pub unsafe fn import_vulkan_buffer(
+        &self,
+        buffer: vk::Buffer,
+        size: u64,
+        usage: wgpu::BufferUsages,
+    ) -> wgpu::Buffer {
+        self.device.create_buffer_from_hal::<Vulkan>(
+            wgpu::hal::vulkan::Device::buffer_from_raw(buffer),
+            &wgpu::BufferDescriptor {
+                label: Some("Imported Vulkan buffer"),
+                size,
+                usage,
+                mapped_at_creation: false,
+            },
+        )
+    }
+}
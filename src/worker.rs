@@ -0,0 +1,520 @@
+//! GPU work on a dedicated thread. It is enabled by the `blocking` feature.
+//!
+//! `GpuWorker` owns a `GpuCompute` on its own thread and receives commands through a channel. Its pipelines are handles: writing a uniform or running the pipeline only sends a command, and runs return a `PendingRun` which can be polled with `PendingRun::try_take` from a GUI event loop or waited with `PendingRun::wait` like the blocking API. The commands of a pipeline are executed in order, so a pipeline can be used right after `GpuWorker::gen_pipeline`.
+//!
+//! A pipeline whose generation fails on the worker thread, e.g. because of a typo in its shader, doesn't stop the worker: its runs return the error of the generation, with `PendingRun::try_wait`, and the other pipelines keep running. `GpuWorker::try_gen_pipeline` waits for the generation and returns its error directly.
+//!
+//! Pipelines sharing a worker can be given a `Priority` with `WorkerPipeline::set_priority`. The priorities only order the commands of the pipelines of a same `GpuWorker`: `GpuCompute` and `GpuComputeAsync` don't schedule the submissions of their pipelines, which are executed in the order they are submitted. Once the current command is done, the worker executes the pending command with the highest priority first, the oldest one between equal priorities, so an interactive pipeline jumps ahead of the runs queued by a background one. The commands of a pipeline are still executed in order (first in, first out), so queuing a command raises the priority of the commands queued before it by the same pipeline. The runs executed at `Priority::Low` are also split into several submissions of `GpuComputeOptions::max_passes_per_submission` passes, see `GpuWorker::with_options`, one stage per submission if it is unset, and the worker waits for each submission to complete before the next one: the commands of higher priority queued in the meantime by other pipelines are executed in between, so they wait for at most one submission instead of the whole run. A single dispatch is never split.
+//!
+//! ```rust
+//! use sgpu_compute::prelude::*;
+//! use sgpu_compute::worker::GpuWorker;
+//!
+//! let shader = "
+//!     @group(0) @binding(0) var<uniform> offset: u32;
+//!     @group(0) @binding(1) var<storage, read> in: array<u32>;
+//!     @group(0) @binding(2) var<storage, read_write> out: array<u32>;
+//!
+//!     @compute @workgroup_size(4)
+//!     fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+//!         out[id.x] = in[id.x] + offset;
+//!     }
+//! ";
+//! let worker = GpuWorker::new();
+//! let pipeline = worker.gen_pipeline::<[u32; 4], u32, [u32; 4], 1>(None, [StageDesc::new(shader, "main")]);
+//! pipeline.write_uniform(&10);
+//! let mut pending = pipeline.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out);
+//! while !pending.is_ready() {
+//!     // Process the events of the GUI.
+//! }
+//! assert_eq!(pending.wait(), [11, 12, 13, 14]);
+//! ```
+use crate::{
+    blocking::GpuCompute,
+    error::SgpuError,
+    options::{GpuComputeOptions, PollStrategy},
+    StageDesc,
+};
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    num::NonZeroUsize,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread::JoinHandle,
+};
+
+/// Pipelines owned by the worker thread by id, or the error of their generation.
+type Pipelines<'g> = HashMap<usize, Result<Box<dyn ErasedPipeline + 'g>, SgpuError>>;
+
+/// A command executed by the worker thread, with the priority it is executed at.
+type Command =
+    Box<dyn for<'g> FnOnce(&'g GpuCompute, &mut Pipelines<'g>, &mut Scheduler, Priority) + Send>;
+
+/// Priority of the commands of a pipeline, see `WorkerPipeline::set_priority`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Background work, executed when no other pipeline has pending commands.
+    Low,
+    #[default]
+    Normal,
+    /// Interactive work, executed before the pending commands of the other pipelines.
+    High,
+}
+
+/// A command of a pipeline waiting to be executed by the worker thread.
+struct Queued {
+    pipeline: usize,
+    priority: Priority,
+    command: Command,
+}
+
+/// Removes the next command to execute from `pending`, which is in arrival order, among the `eligible` ones. The highest priority wins and the oldest command wins between equal priorities. Since the priority of a command is raised to the one of the later commands of its pipeline, see `Scheduler::receive`, the commands of a pipeline are never reordered.
+fn next_command(pending: &mut Vec<Queued>, eligible: impl Fn(&Queued) -> bool) -> Option<Queued> {
+    let next = pending
+        .iter()
+        .enumerate()
+        .filter(|(_, queued)| eligible(queued))
+        .max_by_key(|(i, queued)| (queued.priority, std::cmp::Reverse(*i)))?
+        .0;
+    Some(pending.remove(next))
+}
+
+/// Commands received by the worker thread and not executed yet.
+struct Scheduler {
+    receiver: flume::Receiver<Queued>,
+    pending: Vec<Queued>,
+}
+
+impl Scheduler {
+    /// Waits for the next command to execute, `None` once the worker is dropped and every command is executed.
+    fn next(&mut self) -> Option<Queued> {
+        if self.pending.is_empty() {
+            let queued = self.receiver.recv().ok()?;
+            self.push(queued);
+        }
+        self.receive();
+        next_command(&mut self.pending, |_| true)
+    }
+
+    /// Adds the commands received since the last call to `pending`.
+    fn receive(&mut self) {
+        while let Ok(queued) = self.receiver.try_recv() {
+            self.push(queued);
+        }
+    }
+
+    /// Adds a command to `pending`, raising the priority of the pending commands of its pipeline to its own: they have to be executed before it, so a high-priority run doesn't wait behind the other pipelines because of the low-priority runs queued before it.
+    fn push(&mut self, queued: Queued) {
+        for pending in &mut self.pending {
+            if pending.pipeline == queued.pipeline {
+                pending.priority = pending.priority.max(queued.priority);
+            }
+        }
+        self.pending.push(queued);
+    }
+
+    /// Executes the pending commands of the pipelines other than `running` with a priority higher than `priority`, between two submissions of a split run.
+    fn preempt<'g>(
+        &mut self,
+        gpu: &'g GpuCompute,
+        pipelines: &mut Pipelines<'g>,
+        running: usize,
+        priority: Priority,
+    ) {
+        loop {
+            self.receive();
+            let Some(queued) = next_command(&mut self.pending, |queued| {
+                queued.pipeline != running && queued.priority > priority
+            }) else {
+                break;
+            };
+            (queued.command)(gpu, pipelines, self, queued.priority);
+        }
+    }
+}
+
+/// Pipeline with its types erased, the values being passed as bytes, so that pipelines of different types can be owned by the worker thread.
+trait ErasedPipeline {
+    fn write_uniform(&mut self, uniform: &[u8]);
+
+    fn run(
+        &mut self,
+        input: &[u8],
+        workgroups: &[(u32, u32, u32)],
+        callback: &mut (dyn FnMut(&[u8]) + Send),
+    );
+
+    /// Same as `ErasedPipeline::run`, but the stages are submitted `chunk` at a time, calling `between` after each submission but the last.
+    fn run_split(
+        &mut self,
+        input: &[u8],
+        workgroups: &[(u32, u32, u32)],
+        chunk: usize,
+        between: &mut dyn FnMut(),
+        callback: &mut (dyn FnMut(&[u8]) + Send),
+    );
+}
+
+impl<Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod, const N: usize>
+    ErasedPipeline for crate::blocking::Pipeline<'_, Input, Uniform, Output, N>
+{
+    fn write_uniform(&mut self, uniform: &[u8]) {
+        self.0.write_uniform(&bytemuck::pod_read_unaligned(uniform));
+    }
+
+    fn run(
+        &mut self,
+        input: &[u8],
+        workgroups: &[(u32, u32, u32)],
+        callback: &mut (dyn FnMut(&[u8]) + Send),
+    ) {
+        let workgroups = workgroups.try_into().expect("One workgroup per stage");
+        let input = bytemuck::pod_read_unaligned(input);
+        crate::blocking::Pipeline::run(self, &input, workgroups, |out| {
+            callback(bytemuck::bytes_of(out))
+        });
+    }
+
+    fn run_split(
+        &mut self,
+        input: &[u8],
+        workgroups: &[(u32, u32, u32)],
+        chunk: usize,
+        between: &mut dyn FnMut(),
+        callback: &mut (dyn FnMut(&[u8]) + Send),
+    ) {
+        let workgroups = workgroups.try_into().expect("One workgroup per stage");
+        let input: Input = bytemuck::pod_read_unaligned(input);
+        for start in (0..N).step_by(chunk) {
+            if start > 0 {
+                between();
+            }
+            let input = (start == 0).then_some(&input);
+            self.0
+                .submit_stage_range(input, workgroups, start..(start + chunk).min(N));
+        }
+        pollster::block_on(self.0.read_output(|out| callback(bytemuck::bytes_of(out))));
+    }
+}
+
+/// A `GpuCompute` running on a dedicated thread.
+pub struct GpuWorker {
+    sender: Option<flume::Sender<Queued>>,
+    thread: Option<JoinHandle<()>>,
+    next_id: AtomicUsize,
+}
+
+impl GpuWorker {
+    /// Spawns the worker thread, named `sgpu-worker`, and creates the device on it.
+    pub fn new() -> Self {
+        Self::with_thread(std::thread::Builder::new().name("sgpu-worker".into()))
+    }
+
+    /// Same as `GpuWorker::new`, but the worker thread is spawned with the given builder, e.g. to set its name or its stack size.
+    pub fn with_thread(builder: std::thread::Builder) -> Self {
+        Self::with_thread_and_options(builder, GpuComputeOptions::default())
+    }
+
+    /// Same as `GpuWorker::new`, but the device is created with the given options, e.g. to choose how many passes of a low-priority run are submitted at once. `PollStrategy::OnDemand` is replaced by `PollStrategy::Blocking`, since the application can't poll the device of the worker.
+    pub fn with_options(options: GpuComputeOptions) -> Self {
+        Self::with_thread_and_options(
+            std::thread::Builder::new().name("sgpu-worker".into()),
+            options,
+        )
+    }
+
+    /// Same as `GpuWorker::with_thread`, but the device is created with the given options, see `GpuWorker::with_options`.
+    pub fn with_thread_and_options(
+        builder: std::thread::Builder,
+        mut options: GpuComputeOptions,
+    ) -> Self {
+        if options.poll_strategy == PollStrategy::OnDemand {
+            crate::diagnostics::log_warn!(
+                "The device of a worker can't be polled on demand, it is polled by the worker thread instead"
+            );
+            options.poll_strategy = PollStrategy::Blocking;
+        }
+        let (sender, receiver) = flume::unbounded::<Queued>();
+        let thread = builder
+            .spawn(move || {
+                let gpu = GpuCompute::with_options(options);
+                let mut pipelines = Pipelines::new();
+                let mut scheduler = Scheduler {
+                    receiver,
+                    pending: Vec::new(),
+                };
+                while let Some(queued) = scheduler.next() {
+                    (queued.command)(&gpu, &mut pipelines, &mut scheduler, queued.priority);
+                }
+            })
+            .expect("Could not spawn the worker thread");
+        Self {
+            sender: Some(sender),
+            thread: Some(thread),
+            next_id: AtomicUsize::new(0),
+        }
+    }
+
+    fn send(&self, pipeline: usize, priority: Priority, command: Command) {
+        self.sender
+            .as_ref()
+            .expect("Sender is only taken on drop")
+            .send(Queued {
+                pipeline,
+                priority,
+                command,
+            })
+            .expect("Worker thread panicked");
+    }
+
+    /// Same as `GpuCompute::gen_pipeline`, but the pipeline is generated on the worker thread. This method doesn't wait for the shaders to compile: if the generation fails, the runs of the pipeline return its error, see `PendingRun::try_wait`.
+    pub fn gen_pipeline<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        Output: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> WorkerPipeline<'_, Input, Uniform, Output, N> {
+        self.send_gen_pipeline(scratchpad_size, stages, None)
+    }
+
+    /// Same as `GpuWorker::gen_pipeline`, but waits for the generation on the worker thread and returns its error, like `GpuCompute::try_gen_pipeline`.
+    pub fn try_gen_pipeline<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        Output: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+    ) -> Result<WorkerPipeline<'_, Input, Uniform, Output, N>, SgpuError> {
+        let (sender, receiver) = flume::bounded(1);
+        let pipeline = self.send_gen_pipeline(scratchpad_size, stages, Some(sender));
+        receiver.recv().expect("Worker thread panicked")?;
+        Ok(pipeline)
+    }
+
+    /// Queues the generation of a pipeline, sending its result to `generated` if it is set.
+    fn send_gen_pipeline<
+        Input: bytemuck::Pod,
+        Uniform: bytemuck::Pod,
+        Output: bytemuck::Pod,
+        const N: usize,
+    >(
+        &self,
+        scratchpad_size: Option<NonZeroUsize>,
+        stages: [StageDesc; N],
+        generated: Option<flume::Sender<Result<(), SgpuError>>>,
+    ) -> WorkerPipeline<'_, Input, Uniform, Output, N> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.send(
+            id,
+            Priority::default(),
+            Box::new(move |gpu, pipelines, _, _| {
+                let pipeline = gpu
+                    .try_gen_pipeline::<Input, Uniform, Output, N>(scratchpad_size, stages)
+                    .map(|pipeline| Box::new(pipeline) as Box<dyn ErasedPipeline>);
+                if let Err(error) = &pipeline {
+                    crate::diagnostics::log_warn!("Could not generate pipeline {}: {}", id, error);
+                }
+                if let Some(generated) = generated {
+                    let _ = generated.send(pipeline.as_ref().map(|_| ()).map_err(Clone::clone));
+                }
+                pipelines.insert(id, pipeline);
+            }),
+        );
+        WorkerPipeline {
+            worker: self,
+            id,
+            priority: Priority::default(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl Default for GpuWorker {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for GpuWorker {
+    fn drop(&mut self) {
+        // Closing the channel stops the worker thread once all the pending commands are executed.
+        drop(self.sender.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Handle to a pipeline owned by a `GpuWorker`. The pipeline is dropped on the worker thread when the handle is dropped.
+pub struct WorkerPipeline<
+    'w,
+    Input: bytemuck::Pod,
+    Uniform: bytemuck::Pod,
+    Output: bytemuck::Pod,
+    const N: usize,
+> {
+    worker: &'w GpuWorker,
+    id: usize,
+    priority: Priority,
+    _phantom: PhantomData<(Input, Uniform, Output)>,
+}
+
+impl<Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod, const N: usize>
+    WorkerPipeline<'_, Input, Uniform, Output, N>
+{
+    /// Priority of the commands sent after this call, `Priority::Normal` by default. The commands of a pipeline are executed in order, so the commands already queued are raised to the priority of the next ones: a high-priority run doesn't wait behind the other pipelines because of the low-priority runs queued before it by the same pipeline.
+    pub fn set_priority(&mut self, priority: Priority) {
+        self.priority = priority;
+    }
+
+    /// Priority of the commands of this pipeline, see `WorkerPipeline::set_priority`.
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    /// Same as `PipelineAsync::write_uniform`, the uniform is written on the worker thread before the next run. It is ignored if the generation of the pipeline failed.
+    pub fn write_uniform(&self, uniform: &Uniform) {
+        let id = self.id;
+        let uniform = bytemuck::bytes_of(uniform).to_vec();
+        self.worker.send(
+            id,
+            self.priority,
+            Box::new(move |_, pipelines, _, _| {
+                if let Ok(pipeline) = pipelines.get_mut(&id).expect("Pipeline is alive") {
+                    pipeline.write_uniform(&uniform);
+                }
+            }),
+        );
+    }
+
+    /// Same as `Pipeline::run`, but the run is only queued on the worker thread. The input is copied, so it doesn't have to outlive the run. The runs still at `Priority::Low` when they are executed are split, see the module documentation.
+    pub fn run<T: Send + 'static>(
+        &self,
+        input: &Input,
+        workgroups: [(u32, u32, u32); N],
+        callback: impl FnOnce(&Output) -> T + Send + 'static,
+    ) -> PendingRun<T> {
+        let id = self.id;
+        let input = bytemuck::bytes_of(input).to_vec();
+        let (sender, receiver) = flume::bounded(1);
+        self.worker.send(
+            id,
+            self.priority,
+            Box::new(move |gpu, pipelines, scheduler, priority| {
+                let pipeline = match pipelines.get_mut(&id).expect("Pipeline is alive") {
+                    Ok(pipeline) => pipeline,
+                    Err(error) => {
+                        let _ = sender.send(Err(error.clone()));
+                        return;
+                    }
+                };
+                let mut callback = Some(callback);
+                let mut callback = |out: &[u8]| {
+                    let callback = callback.take().expect("Callback is only called once");
+                    // The run was dropped if the receiver is gone.
+                    let _ = sender.send(Ok(callback(bytemuck::from_bytes(out))));
+                };
+                if priority > Priority::Low {
+                    pipeline.run(&input, &workgroups, &mut callback);
+                    return;
+                }
+                // The pipeline is taken out of the map while the commands of the other pipelines are executed between its submissions.
+                let Some(Ok(mut pipeline)) = pipelines.remove(&id) else {
+                    unreachable!("The pipeline was generated");
+                };
+                let chunk = gpu.max_passes_per_submission().map_or(1, NonZeroUsize::get);
+                pipeline.run_split(
+                    &input,
+                    &workgroups,
+                    chunk,
+                    &mut || scheduler.preempt(gpu, pipelines, id, priority),
+                    &mut callback,
+                );
+                pipelines.insert(id, Ok(pipeline));
+            }),
+        );
+        PendingRun {
+            receiver,
+            result: None,
+        }
+    }
+}
+
+impl<Input: bytemuck::Pod, Uniform: bytemuck::Pod, Output: bytemuck::Pod, const N: usize> Drop
+    for WorkerPipeline<'_, Input, Uniform, Output, N>
+{
+    fn drop(&mut self) {
+        let id = self.id;
+        self.worker.send(
+            id,
+            self.priority,
+            Box::new(move |_, pipelines, _, _| {
+                pipelines.remove(&id);
+            }),
+        );
+    }
+}
+
+/// Result of a run queued on a `GpuWorker`.
+pub struct PendingRun<T> {
+    receiver: flume::Receiver<Result<T, SgpuError>>,
+    result: Option<Result<T, SgpuError>>,
+}
+
+impl<T> PendingRun<T> {
+    /// Whether the run is completed, in which case `PendingRun::wait` doesn't block.
+    pub fn is_ready(&mut self) -> bool {
+        if self.result.is_none() {
+            self.result = self.receiver.try_recv().ok();
+        }
+        self.result.is_some()
+    }
+
+    /// Returns the result if the run is completed, without blocking.
+    ///
+    /// # Panics
+    /// If the generation of the pipeline failed, see `PendingRun::try_wait`.
+    pub fn try_take(&mut self) -> Option<T> {
+        self.is_ready();
+        self.result
+            .take()
+            .map(|result| result.unwrap_or_else(|error| panic!("{}", error)))
+    }
+
+    /// Blocks until the run is completed and returns its result.
+    ///
+    /// # Panics
+    /// If the generation of the pipeline failed, see `PendingRun::try_wait`.
+    pub fn wait(self) -> T {
+        self.try_wait().unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Same as `PendingRun::wait`, but returns the error of the generation of the pipeline instead of panicking.
+    pub fn try_wait(mut self) -> Result<T, SgpuError> {
+        match self.result.take() {
+            Some(result) => result,
+            None => self.receiver.recv().expect("Worker thread panicked"),
+        }
+    }
+
+    /// Async version of `PendingRun::wait`.
+    pub async fn wait_async(mut self) -> T {
+        match self.result.take() {
+            Some(result) => result,
+            None => self
+                .receiver
+                .recv_async()
+                .await
+                .expect("Worker thread panicked"),
+        }
+        .unwrap_or_else(|error| panic!("{}", error))
+    }
+}
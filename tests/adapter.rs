@@ -0,0 +1,24 @@
+use sgpu_compute::prelude::*;
+
+#[test]
+fn select_adapter_by_index_and_predicate() {
+    let adapters = GpuCompute::enumerate_adapters();
+    assert!(!adapters.is_empty());
+    {
+        let gpu = GpuCompute::with_adapter(0, GpuComputeOptions::default()).unwrap();
+        assert_eq!(gpu.adapter_info(), Some(&adapters[0]));
+    }
+    {
+        let last = adapters.last().unwrap().clone();
+        let gpu = GpuCompute::with_adapter(
+            |info: &wgpu::AdapterInfo| info.backend == last.backend && info.name == last.name,
+            GpuComputeOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(gpu.adapter_info().unwrap().name, last.name);
+    }
+    assert!(matches!(
+        GpuCompute::with_adapter(adapters.len(), GpuComputeOptions::default()),
+        Err(SgpuError::NoAdapter)
+    ));
+}
@@ -0,0 +1,70 @@
+use sgpu_compute::prelude::*;
+
+const SHADER: &str = "
+    @group(0) @binding(0) var<storage, read_write> tmp: array<u32>;
+    @group(0) @binding(1) var<storage, read> in: array<u32, 4>;
+    @group(0) @binding(2) var<storage, read_write> out: array<u32, 4>;
+
+    @compute @workgroup_size(4)
+    fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+        tmp[id.x] = in[id.x] + 1u;
+        out[id.x] = tmp[id.x] * 2u;
+    }
+";
+
+type Pipeline<'a> = sgpu_compute::blocking::Pipeline<'a, [u32; 4], (), [u32; 4], 1>;
+
+fn pipeline(gpu: &GpuCompute, scratchpad: Option<usize>) -> Pipeline<'_> {
+    gpu.gen_pipeline(
+        scratchpad.and_then(NonZeroUsize::new),
+        [StageDesc::new(
+            if scratchpad.is_some() { SHADER } else { " @group(0) @binding(0) var<storage, read> in: array<u32, 4>; @group(0) @binding(1) var<storage, read_write> out: array<u32, 4>; @compute @workgroup_size(4) fn main(@builtin(global_invocation_id) id: vec3<u32>) { out[id.x] = in[id.x]; } " },
+            "main",
+        )],
+    )
+}
+
+#[test]
+fn scratchpads_never_live_together_share_a_buffer() {
+    let gpu = GpuCompute::new();
+    let mut a = pipeline(&gpu, Some(1024));
+    let mut b = pipeline(&gpu, Some(512));
+    let mut c = pipeline(&gpu, Some(256));
+    let mut d = pipeline(&gpu, None);
+    let report = gpu.alias_scratchpads(&mut [
+        (&mut *a, 0..=1),
+        (&mut *b, 1..=2),
+        (&mut *c, 2..=3),
+        (&mut *d, 0..=3),
+    ]);
+    // `c` overlaps `b` but not `a`.
+    assert_eq!(report.allocations().len(), 2);
+    assert_eq!(report.allocations()[0].size, 1024);
+    assert_eq!(report.allocations()[0].scratchpads, [0, 2]);
+    assert_eq!(report.allocations()[1].scratchpads, [1]);
+    assert_eq!(report.skipped(), [3]);
+    assert_eq!(report.size_before(), 1792);
+    assert_eq!(report.size_after(), 1536);
+    assert_eq!(report.saved(), 256);
+    assert_eq!(
+        report.to_string(),
+        "allocation 0 (1024 bytes): scratchpads [0, 2]\nallocation 1 (512 bytes): scratchpads [1]\nwithout scratchpad: [3]\n1536 bytes instead of 1792 bytes, 256 bytes saved"
+    );
+
+    let a_buffer = a.scratchpad_buffer().unwrap();
+    assert_eq!(c.scratchpad_buffer().unwrap(), a_buffer);
+    assert_ne!(b.scratchpad_buffer().unwrap(), a_buffer);
+    assert_eq!(
+        c.scratchpad_buffer().unwrap().size(),
+        1024,
+        "The allocation has the size of its largest scratchpad"
+    );
+
+    for pipeline in [&mut a, &mut b, &mut c] {
+        assert_eq!(
+            pipeline.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out),
+            [4, 6, 8, 10]
+        );
+    }
+    assert_eq!(c.read_scratchpad::<[u32; 4]>(), [2, 3, 4, 5]);
+}
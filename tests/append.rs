@@ -0,0 +1,71 @@
+use sgpu_compute::prelude::*;
+
+const SHADER: &str = "
+    @group(0) @binding(0) var<uniform> divisor: u32;
+    @group(0) @binding(1) var<storage, read> in: array<u32>;
+    @group(0) @binding(2) var<storage, read_write> out: array<u32>;
+    @group(0) @binding(3) var<storage, read_write> len: atomic<u32>;
+
+    @compute @workgroup_size(64)
+    fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+        if id.x < arrayLength(&in) && in[id.x] % divisor == 0u {
+            let i = atomicAdd(&len, 1u);
+            if i < arrayLength(&out) {
+                out[i] = in[id.x];
+            }
+        }
+    }
+";
+
+fn stage() -> [StageDesc; 1] {
+    [StageDesc::new(SHADER, "main").with_name("filter")]
+}
+
+fn multiples(divisor: u32, len: u32) -> Vec<u32> {
+    (0..len).filter(|x| x % divisor == 0).collect()
+}
+
+#[test]
+fn output_grows_to_the_appended_length() {
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline_append::<u32, u32, u32, 1>(4096, 8, None, stage());
+    let input = (0..4096).collect::<Vec<u32>>();
+    for divisor in [1000, 7, 2, 5000] {
+        pipeline.write_uniform(&divisor);
+        let mut out = pipeline.run(&input, [(64, 1, 1)]);
+        out.sort();
+        assert_eq!(out, multiples(divisor, 4096));
+        assert_eq!(pipeline.overflow(), 0);
+    }
+    // The output only grows, to the largest length appended.
+    assert_eq!(pipeline.capacity(), 2048);
+}
+
+#[test]
+fn values_past_the_capacity_are_dropped() {
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline_append::<u32, u32, u32, 1>(1024, 16, None, stage());
+    pipeline.set_growable(false);
+    pipeline.write_uniform(&4);
+    let input = (0..1024).collect::<Vec<u32>>();
+    let out = pipeline.run(&input, [(16, 1, 1)]);
+    assert_eq!(out.len(), 16);
+    assert!(out.iter().all(|x| x % 4 == 0));
+    assert_eq!(pipeline.overflow(), 256 - 16);
+    assert_eq!(pipeline.capacity(), 16);
+
+    pipeline.write_uniform(&100);
+    let mut out = pipeline.run(&input, [(16, 1, 1)]);
+    out.sort();
+    assert_eq!(out, multiples(100, 1024));
+    assert_eq!(pipeline.overflow(), 0);
+}
+
+#[test]
+fn empty_capacity_is_rejected() {
+    let gpu = GpuCompute::new();
+    assert!(matches!(
+        gpu.try_gen_pipeline_append::<u32, u32, u32, 1>(1024, 0, None, stage()),
+        Err(SgpuError::LayoutMismatch(_))
+    ));
+}
@@ -0,0 +1,129 @@
+use sgpu_compute::prelude::*;
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Affine {
+    scale: f32,
+    offset: f32,
+}
+
+const SHADER: &str = "
+    struct Affine {
+        scale: f32,
+        offset: f32,
+    }
+
+    @group(0) @binding(0) var<uniform> affine: Affine;
+    @group(0) @binding(1) var<storage, read> input: array<f32>;
+    @group(0) @binding(2) var<storage, read_write> out: array<f32>;
+
+    @compute @workgroup_size(8)
+    fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+        out[id.x] = out[id.x] * affine.scale + input[id.x] + affine.offset;
+    }
+";
+
+fn pipeline(
+    gpu: &GpuCompute,
+) -> sgpu_compute::blocking::Pipeline<'_, [f32; 8], Affine, [f32; 8], 1> {
+    gpu.gen_pipeline(None, [StageDesc::new(SHADER, "main").with_name("affine")])
+}
+
+#[test]
+fn each_run_binds_its_uniform() {
+    let gpu = GpuCompute::new();
+    let mut pipeline = pipeline(&gpu);
+    let input = [1.0; 8];
+    let uniforms = [
+        Affine {
+            scale: 0.0,
+            offset: 1.0,
+        },
+        Affine {
+            scale: 2.0,
+            offset: 0.0,
+        },
+        Affine {
+            scale: 3.0,
+            offset: -1.0,
+        },
+    ];
+    pipeline.write_uniforms(&uniforms);
+    assert_eq!(pipeline.batch_size(), 3);
+    // ((0 * 0 + 1 + 1) * 2 + 1 + 0) * 3 + 1 - 1
+    assert_eq!(
+        pipeline.run_batch(&input, [(1, 1, 1)], |out| *out),
+        [15.0; 8]
+    );
+
+    // A smaller batch reuses the buffer, a larger one grows it.
+    pipeline.set_zero_init(ZeroInit::BeforeEachRun);
+    pipeline.write_uniforms(&uniforms[1..2]);
+    assert_eq!(pipeline.batch_size(), 1);
+    assert_eq!(
+        pipeline.run_batch(&input, [(1, 1, 1)], |out| *out),
+        [1.0; 8]
+    );
+    let many = (0..300)
+        .map(|i| Affine {
+            scale: 1.0,
+            offset: i as f32,
+        })
+        .collect::<Vec<_>>();
+    pipeline.write_uniforms(&many);
+    assert_eq!(
+        pipeline.run_batch(&input, [(1, 1, 1)], |out| *out),
+        [300.0; 8]
+    );
+}
+
+#[test]
+fn batches_leave_the_uniform_and_the_clones_alone() {
+    let gpu = GpuCompute::new();
+    let mut pipeline = pipeline(&gpu);
+    pipeline.write_uniform(&Affine {
+        scale: 0.0,
+        offset: 5.0,
+    });
+    pipeline.write_uniforms(&[Affine {
+        scale: 0.0,
+        offset: 1.0,
+    }]);
+    pipeline.dispatch_batch(&[0.0; 8], [(1, 1, 1)]);
+    assert_eq!(pipeline.run(&[0.0; 8], [(1, 1, 1)], |out| *out), [5.0; 8]);
+
+    let mut clone = pipeline.clone_for_thread();
+    assert_eq!(clone.batch_size(), 0);
+    clone.write_uniforms(
+        &[Affine {
+            scale: 1.0,
+            offset: 2.0,
+        }; 2],
+    );
+    assert_eq!(
+        clone.run_batch(&[0.0; 8], [(1, 1, 1)], |out| *out),
+        [4.0; 8]
+    );
+}
+
+#[test]
+fn sweep_returns_the_output_of_each_configuration() {
+    let gpu = GpuCompute::new();
+    let mut pipeline = pipeline(&gpu);
+    let input = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0];
+    // The output of the previous run is scaled, which would leak between configurations without clearing.
+    let uniforms = (0..200)
+        .map(|i| Affine {
+            scale: 10.0,
+            offset: i as f32,
+        })
+        .collect::<Vec<_>>();
+    let outputs = pipeline.sweep(&uniforms, &input, [(1, 1, 1)]);
+    assert_eq!(outputs.len(), 200);
+    for (i, output) in outputs.iter().enumerate() {
+        assert_eq!(*output, input.map(|x| x + i as f32));
+    }
+
+    let outputs = pipeline.sweep(&uniforms[5..7], &input, [(1, 1, 1)]);
+    assert_eq!(outputs, [input.map(|x| x + 5.0), input.map(|x| x + 6.0)]);
+}
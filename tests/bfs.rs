@@ -0,0 +1,61 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sgpu_compute::kernels::bfs::{self, BfsParams, Csr, Distances};
+use sgpu_compute::prelude::*;
+use std::collections::VecDeque;
+
+const V: usize = 3000;
+const E: usize = 6000;
+
+fn bfs_cpu(graph: &Csr<V, E>, source: usize) -> Vec<u32> {
+    let mut distances = vec![bfs::UNREACHED; V];
+    distances[source] = 0;
+    let mut queue = VecDeque::from([source]);
+    while let Some(v) = queue.pop_front() {
+        for &n in graph.neighbors(v) {
+            if distances[n as usize] == bfs::UNREACHED {
+                distances[n as usize] = distances[v] + 1;
+                queue.push_back(n as usize);
+            }
+        }
+    }
+    distances
+}
+
+#[test]
+fn bfs_compare() {
+    let mut rng = StdRng::seed_from_u64(51);
+    let edges: Vec<(u32, u32)> = (0..E)
+        .map(|_| (rng.gen_range(0..V as u32), rng.gen_range(0..V as u32)))
+        .collect();
+    let graph = Box::new(Csr::<V, E>::from_edges(&edges));
+
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<Csr<V, E>, BfsParams, Distances<V>, 9>(
+        bfs::scratchpad_size(V as u32),
+        bfs::stages(),
+    );
+    for source in [0, 17] {
+        pipeline.write_uniform(&BfsParams::new(V as u32, E as u32, source, true));
+        let mut runs = 0;
+        let (levels, distances) = loop {
+            runs += 1;
+            let (done, level, distances) = pipeline.run(&graph, bfs::workgroups(V as u32), |out| {
+                (out.is_done(), out.level(), out.distances.to_vec())
+            });
+            if done {
+                break (level, distances);
+            }
+            pipeline.write_uniform(&BfsParams::new(V as u32, E as u32, source, false));
+        };
+        let expected = bfs_cpu(&graph, source as usize);
+        assert_eq!(distances, expected);
+        let eccentricity = *expected
+            .iter()
+            .filter(|d| **d != bfs::UNREACHED)
+            .max()
+            .unwrap();
+        assert_eq!(levels, eccentricity + 1);
+        // Four levels are expanded per run.
+        assert_eq!(runs, (levels as usize).div_ceil(4));
+    }
+}
@@ -0,0 +1,117 @@
+use sgpu_compute::binding::BindingRole;
+use sgpu_compute::blocking::BoundPipeline;
+use sgpu_compute::prelude::*;
+
+#[derive(BindingSet)]
+struct Accumulate {
+    #[binding(input)]
+    values: [u32; 16],
+    #[binding(storage)]
+    total: [u32; 16],
+    #[binding(uniform)]
+    step: u32,
+    #[binding(output)]
+    sums: [u32; 16],
+    #[binding(output)]
+    runs: u32,
+}
+
+const SHADER: &str = "
+    @group(0) @binding(0) var<storage, read> values: array<u32, 16>;
+    @group(0) @binding(1) var<storage, read_write> total: array<u32, 16>;
+    @group(0) @binding(2) var<uniform> step: u32;
+    @group(0) @binding(3) var<storage, read_write> sums: array<u32, 16>;
+    @group(0) @binding(4) var<storage, read_write> runs: u32;
+
+    @compute @workgroup_size(16)
+    fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+        total[id.x] += values[id.x] * step;
+        sums[id.x] = total[id.x];
+        if id.x == 0u {
+            runs += 1u;
+        }
+    }
+";
+
+fn stage(shader: &'static str) -> [StageDesc; 1] {
+    [StageDesc::new(shader, "main").with_name("accumulate")]
+}
+
+#[test]
+fn fields_are_bound_in_order() {
+    let roles = Accumulate::bindings()
+        .iter()
+        .map(|binding| (binding.name, binding.role, binding.size))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        roles,
+        [
+            ("values", BindingRole::Input, 64),
+            ("total", BindingRole::Storage, 64),
+            ("step", BindingRole::Uniform, 4),
+            ("sums", BindingRole::Output, 64),
+            ("runs", BindingRole::Output, 4),
+        ]
+    );
+}
+
+#[test]
+fn storage_is_kept_between_runs() {
+    let gpu = GpuCompute::new();
+    let mut pipeline: BoundPipeline<'_, Accumulate, 1> = gpu.gen_bound_pipeline(stage(SHADER));
+    let mut set = Accumulate {
+        values: std::array::from_fn(|i| i as u32),
+        total: [0; 16],
+        step: 1,
+        sums: [0; 16],
+        runs: 0,
+    };
+    pipeline.run(&mut set, [(1, 1, 1)]);
+    assert_eq!(set.sums, set.values);
+    assert_eq!(set.runs, 1);
+
+    set.step = 2;
+    pipeline.run(&mut set, [(1, 1, 1)]);
+    assert_eq!(set.sums, set.values.map(|x| 3 * x));
+    assert_eq!(set.runs, 2);
+
+    // `write` resets the storage and the outputs.
+    set.total = [100; 16];
+    set.runs = 0;
+    pipeline.write(&set);
+    pipeline.run(&mut set, [(1, 1, 1)]);
+    assert_eq!(set.sums, set.values.map(|x| 100 + 2 * x));
+    assert_eq!(set.runs, 1);
+}
+
+#[test]
+fn bindings_are_checked_against_the_set() {
+    let gpu = GpuCompute::new();
+    // The uniform of the set is declared as a storage buffer by the shader.
+    let shader = "
+        @group(0) @binding(0) var<storage, read> values: array<u32, 16>;
+        @group(0) @binding(1) var<storage, read_write> total: array<u32, 16>;
+        @group(0) @binding(2) var<storage, read> step: u32;
+        @group(0) @binding(3) var<storage, read_write> sums: array<u32, 16>;
+
+        @compute @workgroup_size(16)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            sums[id.x] = total[id.x] + values[id.x] * step;
+        }
+    ";
+    assert!(matches!(
+        gpu.try_gen_bound_pipeline::<Accumulate, 1>(stage(shader)),
+        Err(SgpuError::LayoutMismatch(_))
+    ));
+
+    #[derive(BindingSet)]
+    struct Bytes {
+        #[binding(output)]
+        out: [u8; 3],
+    }
+    let error = gpu
+        .try_gen_bound_pipeline::<Bytes, 1>(stage(SHADER))
+        .err()
+        .expect("The output isn't a multiple of 4 bytes");
+    assert!(error.to_string().contains("`out` is 3 bytes"));
+}
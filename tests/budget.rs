@@ -0,0 +1,73 @@
+use sgpu_compute::prelude::*;
+use sgpu_compute::PipelineAsync;
+use std::time::Duration;
+
+const SHADER: &str = "
+    @group(0) @binding(0) var<uniform> chunk: u32;
+    @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+    @compute @workgroup_size(64)
+    fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+        out[64u * chunk + id.x] += chunk + 1u;
+    }
+";
+
+const CHUNKS: usize = 64;
+
+fn pipeline(
+    gpu: &GpuCompute,
+) -> sgpu_compute::blocking::Pipeline<'_, (), u32, [u32; 64 * CHUNKS], 1> {
+    gpu.gen_pipeline(None, [StageDesc::new(SHADER, "main").with_name("chunk")])
+}
+
+fn dispatch(
+    chunk: usize,
+    pipeline: &mut PipelineAsync<'_, (), u32, [u32; 64 * CHUNKS], 1>,
+) -> ((), [(u32, u32, u32); 1]) {
+    pipeline.write_uniform(&(chunk as u32));
+    ((), [(1, 1, 1)])
+}
+
+#[test]
+fn every_chunk_runs_once() {
+    let gpu = GpuCompute::new();
+    let mut job = pipeline(&gpu).into_budgeted(Duration::from_millis(1), CHUNKS);
+    let mut next = 0;
+    let mut frames = 0;
+    while !job.is_done() {
+        let chunks = job.frame(dispatch);
+        assert_eq!(chunks.start, next);
+        assert!(!chunks.is_empty());
+        next = chunks.end;
+        frames += 1;
+        gpu.poll();
+    }
+    assert_eq!(job.dispatched(), CHUNKS);
+    assert!(job.frame(dispatch).is_empty());
+    assert!(frames > 1, "The first frame only calibrates the cost");
+    let output = job.read_output(|out| out.to_vec());
+    for (i, value) in output.iter().enumerate() {
+        assert_eq!(*value, (i / 64) as u32 + 1);
+    }
+}
+
+#[test]
+fn chunks_per_frame_follow_the_budget() {
+    let gpu = GpuCompute::new();
+    // A budget no chunk fits in dispatches one chunk per frame.
+    let mut job = pipeline(&gpu).into_budgeted(Duration::from_nanos(1), CHUNKS);
+    for _ in 0..4 {
+        assert_eq!(job.frame(dispatch).len(), 1);
+        gpu.device().poll(wgpu::Maintain::Wait);
+    }
+    assert!(job.chunk_cost().is_some());
+
+    // A large budget doubles the chunks of each frame once the cost is measured.
+    let mut job = pipeline(&gpu).into_budgeted(Duration::from_secs(10), CHUNKS);
+    let mut sizes = Vec::new();
+    while !job.is_done() {
+        sizes.push(job.frame(dispatch).len());
+        gpu.device().poll(wgpu::Maintain::Wait);
+    }
+    assert_eq!(sizes, [1, 2, 4, 8, 16, 32, 1]);
+}
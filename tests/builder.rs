@@ -0,0 +1,58 @@
+use sgpu_compute::prelude::*;
+
+#[test]
+fn builder_reports_unsupported_requirements() {
+    let error = GpuCompute::builder()
+        .required_features(wgpu::Features::all_native_mask())
+        .build()
+        .err()
+        .unwrap();
+    match error {
+        SgpuError::Unsupported(description) => assert!(description.contains("features")),
+        error => panic!("unexpected {:?}", error),
+    }
+    let error = GpuCompute::builder()
+        .required_limits(wgpu::Limits {
+            max_bind_groups: u32::MAX,
+            ..wgpu::Limits::downlevel_defaults()
+        })
+        .build()
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("max_bind_groups"));
+    assert!(error.is_fatal());
+    assert!(matches!(
+        GpuCompute::builder()
+            .backends(wgpu::Backends::empty())
+            .build(),
+        Err(SgpuError::NoAdapter)
+    ));
+}
+
+#[test]
+fn builder_applies_limits_and_options() {
+    let gpu = GpuCompute::builder()
+        .power_preference(wgpu::PowerPreference::LowPower)
+        .required_limits(wgpu::Limits {
+            max_compute_invocations_per_workgroup: 512,
+            max_compute_workgroup_size_x: 512,
+            ..wgpu::Limits::downlevel_defaults()
+        })
+        .zero_init(ZeroInit::AtCreation)
+        .build()
+        .unwrap();
+    assert_eq!(gpu.limits().max_compute_invocations_per_workgroup, 512);
+    let shader = "
+        @group(0) @binding(0) var<storage, read> in: array<u32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+        @compute @workgroup_size(512)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] + 1u;
+        }
+    ";
+    let mut pipeline =
+        gpu.gen_pipeline::<[u32; 512], (), [u32; 512], 1>(None, [StageDesc::new(shader, "main")]);
+    let out = pipeline.run(&[1; 512], [(1, 1, 1)], |out| *out);
+    assert!(out.iter().all(|x| *x == 2));
+}
@@ -0,0 +1,49 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sgpu_compute::kernels::bvh::{self, Bounds, Child, Node};
+use sgpu_compute::prelude::*;
+
+fn expand_bits(v: u32) -> u32 {
+    (0..10).fold(0, |acc, bit| acc | (((v >> bit) & 1) << (3 * bit)))
+}
+
+fn morton_cpu(p: [f32; 4]) -> u32 {
+    let cell = |x: f32| ((x.clamp(0.0, 1.0) * 1024.0) as u32).min(1023);
+    (expand_bits(cell(p[0])) << 2) | (expand_bits(cell(p[1])) << 1) | expand_bits(cell(p[2]))
+}
+
+fn collect_leaves(nodes: &[Node], child: Child, leaves: &mut Vec<u32>, visited: &mut usize) {
+    match child {
+        Child::Leaf(i) => leaves.push(i),
+        Child::Internal(i) => {
+            *visited += 1;
+            let node = nodes[i as usize];
+            collect_leaves(nodes, node.left(), leaves, visited);
+            collect_leaves(nodes, node.right(), leaves, visited);
+        }
+    }
+}
+
+#[test]
+fn lbvh_covers_all_leaves() {
+    const N: usize = 500;
+    let mut rng = StdRng::seed_from_u64(42);
+    // Values are snapped to a coarse grid so that some codes are duplicated.
+    let points: [[f32; 4]; N] =
+        std::array::from_fn(|_| [0.0; 4].map(|_| rng.gen_range(0..64) as f32 / 64.0));
+
+    let gpu = GpuCompute::new();
+    let mut morton = gpu.gen_pipeline::<[[f32; 4]; N], Bounds, [u32; N], 1>(None, [bvh::MORTON]);
+    morton.write_uniform(&Bounds::new([0.0; 3], [1.0; 3]));
+    let mut codes = morton.run(&points, [bvh::workgroups(N)], |codes| *codes);
+    assert_eq!(codes, points.map(morton_cpu));
+
+    codes.sort_unstable();
+    let mut lbvh = gpu.gen_pipeline::<[u32; N], (), [Node; N - 1], 1>(None, [bvh::LBVH]);
+    let nodes = lbvh.run(&codes, [bvh::workgroups(N - 1)], |nodes| *nodes);
+
+    let mut leaves = Vec::new();
+    let mut visited = 0;
+    collect_leaves(&nodes, Child::Internal(0), &mut leaves, &mut visited);
+    assert_eq!(visited, N - 1);
+    assert_eq!(leaves, (0..N as u32).collect::<Vec<_>>());
+}
@@ -0,0 +1,61 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sgpu_compute::kernels::ccl::{self, CclParams, Labels};
+use sgpu_compute::prelude::*;
+
+/// Flood fill labeling each component with its smallest index.
+fn ccl_cpu(mask: &[u32], width: usize, height: usize) -> Vec<u32> {
+    let mut labels = vec![ccl::BACKGROUND; mask.len()];
+    for start in 0..mask.len() {
+        if mask[start] == 0 || labels[start] != ccl::BACKGROUND {
+            continue;
+        }
+        let mut stack = vec![start];
+        labels[start] = start as u32;
+        while let Some(i) = stack.pop() {
+            let (x, y) = (i % width, i / width);
+            let neighbours = [
+                (x > 0).then(|| i - 1),
+                (x + 1 < width).then(|| i + 1),
+                (y > 0).then(|| i - width),
+                (y + 1 < height).then(|| i + width),
+            ];
+            for j in neighbours.into_iter().flatten() {
+                if mask[j] != 0 && labels[j] == ccl::BACKGROUND {
+                    labels[j] = start as u32;
+                    stack.push(j);
+                }
+            }
+        }
+    }
+    labels
+}
+
+#[test]
+fn ccl_compare() {
+    const WIDTH: u32 = 45;
+    const HEIGHT: u32 = 30;
+    const LEN: usize = (WIDTH * HEIGHT) as usize;
+    let mut rng = StdRng::seed_from_u64(8);
+    let mask: [u32; LEN] = std::array::from_fn(|_| rng.gen_bool(0.55) as u32);
+
+    let gpu = GpuCompute::new();
+    let mut pipeline =
+        gpu.gen_pipeline::<[u32; LEN], CclParams, Labels<LEN>, 8>(None, ccl::stages());
+    pipeline.write_uniform(&CclParams::new(WIDTH, HEIGHT, true));
+    let mut runs = 0;
+    let labels = loop {
+        runs += 1;
+        let (changed, labels) = pipeline.run(&mask, ccl::workgroups(WIDTH, HEIGHT), |out| {
+            (out.changed(), out.labels)
+        });
+        if !changed {
+            break labels;
+        }
+        pipeline.write_uniform(&CclParams::new(WIDTH, HEIGHT, false));
+        assert!(runs < LEN, "labeling did not converge");
+    };
+    assert_eq!(
+        labels.to_vec(),
+        ccl_cpu(&mask, WIDTH as usize, HEIGHT as usize)
+    );
+}
@@ -0,0 +1,134 @@
+use sgpu_compute::checkpoint::{Checkpoint, CheckpointError};
+use std::io::{Read, Write};
+
+fn path(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("sgpu-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn resumes_after_failed_chunk() {
+    let path = path("checkpoint-resume");
+    let mut calls = Vec::new();
+    let mut checkpoint = Checkpoint::open(&path, 5).unwrap();
+    let error = checkpoint
+        .run(|chunk| {
+            calls.push(chunk);
+            if chunk == 3 {
+                Err("device lost")
+            } else {
+                Ok(vec![chunk as u32; 2])
+            }
+        })
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        CheckpointError::Chunk {
+            index: 3,
+            error: "device lost"
+        }
+    ));
+    drop(checkpoint);
+
+    // A new process reopens the checkpoint and only runs the remaining chunks.
+    let mut checkpoint = Checkpoint::open(&path, 5).unwrap();
+    assert_eq!(checkpoint.completed(), 3);
+    assert_eq!(checkpoint.pending().collect::<Vec<_>>(), [3, 4]);
+    let outputs = checkpoint
+        .run(|chunk| {
+            calls.push(chunk);
+            Ok::<_, ()>(vec![chunk as u32; 2])
+        })
+        .unwrap();
+    assert_eq!(calls, [0, 1, 2, 3, 3, 4]);
+    assert_eq!(outputs, [0, 0, 1, 1, 2, 2, 3, 3, 4, 4]);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn drops_cut_records() {
+    let path = path("checkpoint-cut");
+    let mut checkpoint = Checkpoint::open(&path, 3).unwrap();
+    checkpoint.record(0, &[1, 2, 3, 4]).unwrap();
+    checkpoint.record(2, &[5, 6, 7, 8]).unwrap();
+    drop(checkpoint);
+    // A crash in the middle of a record leaves its beginning in the file.
+    let len = std::fs::metadata(&path).unwrap().len();
+    let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+    file.set_len(len - 3).unwrap();
+    drop(file);
+
+    let mut checkpoint = Checkpoint::open(&path, 3).unwrap();
+    assert_eq!(checkpoint.output(0).unwrap(), Some(vec![1, 2, 3, 4]));
+    assert!(!checkpoint.is_completed(2));
+    checkpoint.record(1, &[9; 4]).unwrap();
+    drop(checkpoint);
+    let checkpoint = Checkpoint::open(&path, 3).unwrap();
+    assert_eq!(checkpoint.output(1).unwrap(), Some(vec![9; 4]));
+    assert_eq!(checkpoint.completed(), 2);
+
+    assert_eq!(
+        Checkpoint::open(&path, 4).err().unwrap().kind(),
+        std::io::ErrorKind::InvalidData
+    );
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn compacts_superseded_records() {
+    let path = path("checkpoint-compact");
+    let mut checkpoint = Checkpoint::open(&path, 2).unwrap();
+    checkpoint.record(0, &[1; 64]).unwrap();
+    checkpoint.record(1, &[2; 64]).unwrap();
+    let len = std::fs::metadata(&path).unwrap().len();
+    // The first records are superseded, but they don't take more space than the others yet.
+    checkpoint.record(0, &[3; 64]).unwrap();
+    assert!(std::fs::metadata(&path).unwrap().len() > len);
+    checkpoint.record(1, &[4; 64]).unwrap();
+    checkpoint.record(0, &[5; 64]).unwrap();
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), len);
+    assert_eq!(checkpoint.output(0).unwrap(), Some(vec![5; 64]));
+    checkpoint.record(1, &[6; 8]).unwrap();
+    drop(checkpoint);
+
+    let checkpoint = Checkpoint::open(&path, 2).unwrap();
+    assert_eq!(checkpoint.output(0).unwrap(), Some(vec![5; 64]));
+    // The readers read at the position of their record, so they can be interleaved.
+    let mut first = checkpoint.reader(0).unwrap().unwrap();
+    let mut second = checkpoint.reader(1).unwrap().unwrap();
+    let (mut a, mut b) = ([0; 4], [0; 4]);
+    first.read_exact(&mut a).unwrap();
+    second.read_exact(&mut b).unwrap();
+    assert_eq!(checkpoint.output(0).unwrap(), Some(vec![5; 64]));
+    let mut rest = Vec::new();
+    first.read_to_end(&mut rest).unwrap();
+    second.read_exact(&mut b).unwrap();
+    assert_eq!((a, rest.len(), b), ([5; 4], 60, [6; 4]));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn rejects_outputs_of_another_type() {
+    let path = path("checkpoint-type");
+    let mut checkpoint = Checkpoint::open(&path, 1).unwrap();
+    checkpoint.record(0, &[1, 2, 3, 4, 5, 6]).unwrap();
+    let error = checkpoint
+        .run(|_| Ok::<Vec<u32>, ()>(Vec::new()))
+        .unwrap_err();
+    assert!(
+        matches!(&error, CheckpointError::Io(error) if error.kind() == std::io::ErrorKind::InvalidData)
+    );
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn rejects_other_files() {
+    let path = path("checkpoint-invalid");
+    std::fs::File::create(&path)
+        .unwrap()
+        .write_all(b"not a checkpoint")
+        .unwrap();
+    assert!(Checkpoint::open(&path, 1).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
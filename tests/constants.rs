@@ -0,0 +1,107 @@
+use sgpu_compute::prelude::*;
+
+const SHADER: &str = "
+    @group(0) @binding(0) var<storage, read> in: array<u32>;
+    @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+    override WORKGROUP_SIZE: u32 = 64;
+    @id(0) override SCALE: u32;
+    override SCALED: bool = true;
+    // override COMMENTED: u32;
+
+    @compute @workgroup_size(WORKGROUP_SIZE)
+    fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+        out[id.x] = select(in[id.x], in[id.x] * SCALE, SCALED) + WORKGROUP_SIZE;
+    }
+";
+
+#[test]
+fn overrides_are_specialized_per_pipeline() {
+    let gpu = GpuCompute::new();
+    let input: [u32; 64] = std::array::from_fn(|i| i as u32);
+    let mut wide = gpu.gen_pipeline::<[u32; 64], (), [u32; 64], 1>(
+        None,
+        [StageDesc::new(SHADER, "main")
+            .with_name("wide")
+            .with_constants(&[("0", 3.0)])],
+    );
+    let mut narrow = gpu.gen_pipeline::<[u32; 64], (), [u32; 64], 1>(
+        None,
+        [StageDesc::new(SHADER, "main")
+            .with_name("narrow")
+            .with_constants(&[("WORKGROUP_SIZE", 16.0), ("0", 2.0), ("SCALED", 1.0)])],
+    );
+    assert_eq!(wide.describe().stages[0].workgroup_size, Some([64, 1, 1]));
+    assert_eq!(narrow.describe().stages[0].workgroup_size, Some([16, 1, 1]));
+    let wide = wide.run(&input, [(1, 1, 1)], |out| *out);
+    let narrow = narrow.run(&input, [(4, 1, 1)], |out| *out);
+    assert_eq!(wide, input.map(|i| 3 * i + 64));
+    assert_eq!(narrow, input.map(|i| 2 * i + 16));
+}
+
+#[test]
+fn i32_bounds_are_accepted() {
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<(), (), [i32; 2], 1>(
+        None,
+        [StageDesc::new(
+            "
+                @group(0) @binding(0) var<storage, read_write> out: array<i32>;
+
+                override MIN: i32;
+                override MAX: i32;
+
+                @compute @workgroup_size(1)
+                fn main() {
+                    out[0] = MIN;
+                    out[1] = MAX;
+                }
+            ",
+            "main",
+        )
+        .with_constants(&[("MIN", i32::MIN as f64), ("MAX", i32::MAX as f64)])],
+    );
+    assert_eq!(
+        pipeline.run(&(), [(1, 1, 1)], |out| *out),
+        [i32::MIN, i32::MAX]
+    );
+}
+
+#[test]
+fn invalid_constants_are_rejected() {
+    let gpu = GpuCompute::new();
+    let stage = |constants: &'static [(&'static str, f64)]| {
+        StageDesc::new(SHADER, "main")
+            .with_name("scale")
+            .with_constants(constants)
+    };
+    for (constants, message) in [
+        (
+            &[][..],
+            "the override `SCALE` of stage scale has no default value",
+        ),
+        (
+            &[("0", 1.0), ("COMMENTED", 1.0)],
+            "no override with this id",
+        ),
+        (
+            &[("SCALE", 1.0)],
+            "has no override with this id, or with this name and no id",
+        ),
+        (
+            &[("0", 1.5)],
+            "the constant `0` of stage scale is 1.5, which isn't a u32",
+        ),
+        (&[("0", -1.0)], "which isn't a u32"),
+    ] {
+        let error = gpu
+            .try_gen_pipeline::<[u32; 64], (), [u32; 64], 1>(None, [stage(constants)])
+            .err()
+            .expect("the constants are invalid");
+        assert!(
+            matches!(&error, SgpuError::Validation(description) if description.contains(message)),
+            "{}",
+            error
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sgpu_compute::kernels::dedup::{self, Unique32, Unique64, Uniques};
+use sgpu_compute::kernels::sort::SortParams;
+use sgpu_compute::prelude::*;
+use std::collections::BTreeMap;
+
+const N: usize = 20_000;
+
+#[test]
+fn dedup_u32_compare() {
+    let mut rng = StdRng::seed_from_u64(41);
+    let keys: [u32; N] = std::array::from_fn(|_| rng.gen_range(0..5000) * 7919);
+    let mut expected = BTreeMap::new();
+    for key in keys {
+        *expected.entry(key).or_insert(0) += 1;
+    }
+    let expected: Vec<Unique32> = expected
+        .into_iter()
+        .map(|(key, count)| Unique32::new(key, count))
+        .collect();
+
+    let params = SortParams::new(N as u32, 1);
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[u32; N], SortParams, Uniques<Unique32, 5000>, 17>(
+        dedup::scratchpad_size(&params),
+        dedup::STAGES_U32,
+    );
+    pipeline.write_uniform(&params);
+    let (overflowed, uniques) = pipeline.run(&keys, dedup::workgroups(&params), |uniques| {
+        (uniques.overflowed(), uniques.to_vec())
+    });
+    assert!(!overflowed);
+    assert_eq!(uniques, expected);
+}
+
+#[test]
+fn dedup_u64_overflow() {
+    let mut rng = StdRng::seed_from_u64(42);
+    let keys: [u64; N] = std::array::from_fn(|_| rng.gen_range(0..3000) << 35);
+    let mut expected = BTreeMap::new();
+    for key in keys {
+        *expected.entry(key).or_insert(0) += 1;
+    }
+
+    let params = SortParams::new(N as u32, 2);
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[u64; N], SortParams, Uniques<Unique64, 1000>, 29>(
+        dedup::scratchpad_size(&params),
+        dedup::STAGES_U64,
+    );
+    pipeline.write_uniform(&params);
+    let (count, overflowed, uniques) = pipeline.run(&keys, dedup::workgroups(&params), |uniques| {
+        (uniques.count(), uniques.overflowed(), uniques.to_vec())
+    });
+    assert_eq!(count, expected.len());
+    assert!(overflowed);
+    // The smallest keys are kept.
+    let expected: Vec<Unique64> = expected
+        .into_iter()
+        .take(1000)
+        .map(|(key, count)| Unique64::new(key, count))
+        .collect();
+    assert_eq!(uniques, expected);
+}
@@ -0,0 +1,107 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sgpu_compute::kernels::dsp::{self, BlockStream, DspParams, Window};
+use sgpu_compute::prelude::*;
+
+const FRAME_SIZE: u32 = 256;
+const HOP_SIZE: u32 = 128;
+const FRAMES: u32 = 8;
+const SIGNAL_LEN: usize = ((FRAMES - 1) * HOP_SIZE + FRAME_SIZE) as usize;
+const SPECTRUM_LEN: usize = (FRAMES * FRAME_SIZE) as usize;
+
+fn dft(frame: &[f32]) -> Vec<[f64; 2]> {
+    let n = frame.len();
+    (0..n)
+        .map(|k| {
+            frame.iter().enumerate().fold([0.0; 2], |[re, im], (t, x)| {
+                let angle = -std::f64::consts::TAU * (k * t) as f64 / n as f64;
+                [re + *x as f64 * angle.cos(), im + *x as f64 * angle.sin()]
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn analysis_matches_dft() {
+    let mut rng = StdRng::seed_from_u64(11);
+    let signal: [f32; SIGNAL_LEN] = std::array::from_fn(|_| rng.gen_range(-1.0..1.0));
+    for window in [
+        Window::Rectangular,
+        Window::Hann,
+        Window::Hamming,
+        Window::Blackman,
+    ] {
+        let params = DspParams::new(FRAME_SIZE, HOP_SIZE, FRAMES, window);
+        let gpu = GpuCompute::new();
+        let mut pipeline = gpu
+            .gen_pipeline::<[f32; SIGNAL_LEN], DspParams, [[f32; 2]; SPECTRUM_LEN], 3>(
+                params.scratchpad_size(),
+                dsp::ANALYSIS,
+            );
+        pipeline.write_uniform(&params);
+        let spectra = pipeline.run(&signal, params.analysis_workgroups(), |spectra| *spectra);
+        for f in 0..FRAMES as usize {
+            let start = f * HOP_SIZE as usize;
+            let frame: Vec<f32> = (0..FRAME_SIZE)
+                .map(|i| signal[start + i as usize] * window.coefficient(i, FRAME_SIZE))
+                .collect();
+            let expected = dft(&frame);
+            let spectrum = &spectra[f * FRAME_SIZE as usize..][..FRAME_SIZE as usize];
+            for (k, (gpu, cpu)) in spectrum.iter().zip(&expected).enumerate() {
+                assert!(
+                    (gpu[0] as f64 - cpu[0]).abs() < 1e-3 && (gpu[1] as f64 - cpu[1]).abs() < 1e-3,
+                    "{:?} frame {} bin {}: {:?} != {:?}",
+                    window,
+                    f,
+                    k,
+                    gpu,
+                    cpu
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn block_stream_reconstructs_signal() {
+    const BLOCK_LEN: usize = (FRAMES * HOP_SIZE) as usize;
+    let params = DspParams::new(FRAME_SIZE, HOP_SIZE, FRAMES, Window::Hann);
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[f32; SIGNAL_LEN], DspParams, [f32; SIGNAL_LEN], 4>(
+        params.scratchpad_size(),
+        dsp::RESYNTHESIS,
+    );
+    pipeline.write_uniform(&params);
+
+    let mut rng = StdRng::seed_from_u64(12);
+    let stream: Vec<f32> = (0..4 * BLOCK_LEN)
+        .map(|_| rng.gen_range(-1.0..1.0))
+        .collect();
+    let mut blocks = BlockStream::new(params);
+    assert_eq!(blocks.block_len(), BLOCK_LEN);
+    let mut output = Vec::new();
+    let mut input = [0.0; SIGNAL_LEN];
+    let mut block = [0.0; BLOCK_LEN];
+    for chunk in stream.chunks(BLOCK_LEN) {
+        blocks.input(chunk, &mut input);
+        let signal = pipeline.run(&input, params.resynthesis_workgroups(), |signal| *signal);
+        blocks.output(&signal, &mut block);
+        output.extend_from_slice(&block);
+    }
+    // The output is delayed by the overlap, and its first samples are only covered by one frame.
+    let delay = (FRAME_SIZE - HOP_SIZE) as usize;
+    for t in delay..stream.len() {
+        assert!(
+            (output[t] - stream[t - delay]).abs() < 1e-4,
+            "sample {}: {} != {}",
+            t,
+            output[t],
+            stream[t - delay]
+        );
+    }
+}
+
+#[test]
+#[should_panic(expected = "power of two")]
+fn dsp_rejects_frame_size() {
+    DspParams::new(100, 50, 1, Window::Hann);
+}
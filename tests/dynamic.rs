@@ -0,0 +1,128 @@
+use sgpu_compute::prelude::*;
+
+const SHADER: &str = "
+    @group(0) @binding(0) var<storage, read> in: array<u32>;
+    @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+    @compute @workgroup_size(64)
+    fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+        let len = arrayLength(&out);
+        if id.x < len {
+            out[id.x] = in[id.x] + in[len + id.x];
+        }
+    }
+";
+
+fn stage() -> [StageDesc; 1] {
+    [StageDesc::new(SHADER, "main").with_name("add_halves")]
+}
+
+#[test]
+fn runtime_lengths() {
+    let gpu = GpuCompute::new();
+    for len in [4, 100, 5000] {
+        let mut pipeline = gpu.gen_pipeline_dyn::<u32, (), u32, 1>(2 * len, len, None, stage());
+        assert_eq!(
+            (pipeline.input_len(), pipeline.output_len()),
+            (2 * len, len)
+        );
+        let input = (0..2 * len as u32).collect::<Vec<_>>();
+        let workgroups = [((len as u32).div_ceil(64), 1, 1)];
+        let expected = (0..len as u32)
+            .map(|i| 2 * i + len as u32)
+            .collect::<Vec<_>>();
+        assert_eq!(pipeline.run_to_vec(&input, workgroups), expected);
+
+        let mut out = Vec::new();
+        pipeline.run_readback_into_vec(&input, workgroups, &mut out);
+        assert_eq!(out, expected);
+        assert_eq!(pipeline.run(&input, workgroups, |out| out.len()), len);
+    }
+}
+
+#[test]
+fn lengths_are_checked() {
+    let gpu = GpuCompute::new();
+    let error = gpu
+        .try_gen_pipeline_dyn::<u8, (), u32, 1>(6, 3, None, stage())
+        .err()
+        .unwrap();
+    assert_eq!(
+        error,
+        SgpuError::LayoutMismatch("the input is 6 bytes, it must be a multiple of 4 bytes".into())
+    );
+    assert!(matches!(
+        gpu.try_gen_pipeline_dyn::<u32, (), u32, 1>(6, 0, None, stage()),
+        Err(SgpuError::LayoutMismatch(_))
+    ));
+
+    let mut pipeline = gpu.gen_pipeline_dyn::<u32, (), u32, 1>(8, 4, None, stage());
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        pipeline.run_to_vec(&[0; 7], [(1, 1, 1)])
+    }));
+    let message = res.unwrap_err();
+    assert!(message
+        .downcast_ref::<String>()
+        .unwrap()
+        .contains("Expected 8 input values, got 7"));
+}
+
+#[test]
+fn resize_keeps_the_stages_and_the_uniform() {
+    // Each invocation counts its runs in the scratchpad, if it has a slot there.
+    let shader = "
+        @group(0) @binding(0) var<uniform> offset: u32;
+        @group(0) @binding(1) var<storage, read_write> runs: array<u32>;
+        @group(0) @binding(2) var<storage, read> in: array<u32>;
+        @group(0) @binding(3) var<storage, read_write> out: array<u32>;
+
+        @compute @workgroup_size(64)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            if id.x < arrayLength(&out) {
+                var n = 0u;
+                if id.x < arrayLength(&runs) {
+                    runs[id.x] += 1u;
+                    n = runs[id.x];
+                }
+                out[id.x] = in[id.x] + offset + 1000u * n;
+            }
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline_dyn::<u32, u32, u32, 1>(
+        4,
+        4,
+        NonZeroUsize::new(16),
+        [StageDesc::new(shader, "main")],
+    );
+    pipeline.write_uniform(&10);
+    assert_eq!(
+        pipeline.run_to_vec(&[1, 2, 3, 4], [(1, 1, 1)]),
+        [1011, 1012, 1013, 1014]
+    );
+
+    pipeline.resize_input(100);
+    pipeline.resize_output(100);
+    assert_eq!((pipeline.input_len(), pipeline.output_len()), (100, 100));
+    let input = (0..100).collect::<Vec<_>>();
+    let output = pipeline.run_to_vec(&input, [(2, 1, 1)]);
+    // The uniform and the scratchpad kept their content.
+    assert_eq!(output[..5], [2010, 2011, 2012, 2013, 14]);
+    assert_eq!(output[99], 109);
+
+    pipeline.resize_scratchpad(NonZeroUsize::new(64).unwrap());
+    assert_eq!(pipeline.scratchpad_size(), NonZeroUsize::new(64));
+    let output = pipeline.run_to_vec(&input, [(2, 1, 1)]);
+    assert_eq!(output[..2], [1010, 1011]);
+    assert_eq!(output[15..17], [1025, 26]);
+
+    assert!(matches!(
+        pipeline.try_resize(0, 100, NonZeroUsize::new(64)),
+        Err(SgpuError::LayoutMismatch(_))
+    ));
+    assert!(matches!(
+        pipeline.try_resize(100, 100, None),
+        Err(SgpuError::LayoutMismatch(_))
+    ));
+    assert_eq!(pipeline.input_len(), 100);
+}
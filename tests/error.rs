@@ -0,0 +1,230 @@
+use sgpu_compute::error::{ShaderDiagnostic, SourceSpan};
+use sgpu_compute::prelude::*;
+
+#[test]
+fn retryable_classification() {
+    assert!(SgpuError::OutOfMemory.is_retryable());
+    assert!(SgpuError::DeviceLost("driver reset".into()).is_retryable());
+    assert!(SgpuError::Validation("invalid shader".into()).is_fatal());
+    assert!(SgpuError::LayoutMismatch("missing binding 2".into()).is_fatal());
+    assert!(SgpuError::NoAdapter.is_fatal());
+    assert!(SgpuError::DeviceRequestFailed("limits".into()).is_retryable());
+    assert!(SgpuError::ShaderCompilation("parse error".into()).is_fatal());
+    assert!(SgpuError::InvalidShader(Box::new(ShaderDiagnostic {
+        stage: "main".into(),
+        message: "parse error".into(),
+        spans: Vec::new(),
+    }))
+    .is_fatal());
+    assert!(SgpuError::TimedOut(std::time::Duration::from_secs(1)).is_fatal());
+    assert!(SgpuError::InvalidGraph("cycle".into()).is_fatal());
+}
+
+#[test]
+fn from_wgpu_error() {
+    let source = || Box::new(std::fmt::Error) as Box<dyn std::error::Error + Send + Sync>;
+    let oom = SgpuError::from(wgpu::Error::OutOfMemory { source: source() });
+    assert_eq!(oom, SgpuError::OutOfMemory);
+    let validation = SgpuError::from(wgpu::Error::Validation {
+        source: source(),
+        description: "invalid shader".into(),
+    });
+    assert_eq!(validation, SgpuError::Validation("invalid shader".into()));
+    assert!(!validation.is_retryable());
+}
+
+#[test]
+fn fallible_pipeline_generation() {
+    let gpu = GpuCompute::try_new().unwrap();
+    let invalid = gpu.try_gen_pipeline::<[u32; 4], (), [u32; 4], 1>(
+        None,
+        [StageDesc::new(
+            "@compute @workgroup_size(1) fn main() { let x: u32 = 1.0; }",
+            "main",
+        )
+        .with_name("invalid")],
+    );
+    assert!(matches!(invalid, Err(SgpuError::InvalidShader(_))));
+
+    let mismatch = gpu.try_gen_pipeline::<[u32; 4], (), [u32; 4], 1>(
+        None,
+        [StageDesc::new(
+            " @group(0) @binding(0) var<storage, read_write> in: array<u32>; @group(0) @binding(1) var<storage, read_write> out: array<u32>; @compute @workgroup_size(4) fn main(@builtin(global_invocation_id) id: vec3<u32>) { out[id.x] = in[id.x]; } ",
+            "main",
+        )],
+    );
+    assert!(matches!(mismatch, Err(SgpuError::LayoutMismatch(_))));
+
+    let mut pipeline = gpu
+        .try_gen_pipeline::<[u32; 4], (), [u32; 4], 1>(
+            None,
+            [StageDesc::new(
+                " @group(0) @binding(0) var<storage, read> in: array<u32>; @group(0) @binding(1) var<storage, read_write> out: array<u32>; @compute @workgroup_size(4) fn main(@builtin(global_invocation_id) id: vec3<u32>) { out[id.x] = 2u * in[id.x]; } ",
+                "main",
+            )],
+        )
+        .unwrap();
+    assert_eq!(
+        pipeline.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out),
+        [2, 4, 6, 8]
+    );
+}
+
+fn diagnostic(shader: &'static str) -> ShaderDiagnostic {
+    let gpu = GpuCompute::try_new().unwrap();
+    let error = gpu
+        .try_gen_pipeline::<[u32; 4], (), [u32; 4], 1>(
+            None,
+            [StageDesc::new(shader, "main").with_name("norm")],
+        )
+        .err()
+        .unwrap();
+    match error {
+        SgpuError::InvalidShader(diagnostic) => *diagnostic,
+        error => panic!("Expected a shader diagnostic, got {}", error),
+    }
+}
+
+#[test]
+fn parse_errors_are_located() {
+    let diagnostic = diagnostic(
+        "@group(0) @binding(0) var<storage, read_write> out: array<u32>;
+
+@compute @workgroup_size(4)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    out[id.x] = id.x +;
+}",
+    );
+    assert_eq!(diagnostic.stage, "norm");
+    assert_eq!(diagnostic.spans.len(), 1);
+    let span = &diagnostic.spans[0];
+    assert_eq!((span.line, span.column), (5, 23));
+    assert_eq!(span.excerpt, "    out[id.x] = id.x +;");
+    let message = SgpuError::InvalidShader(Box::new(diagnostic.clone())).to_string();
+    assert!(message.contains("in stage `norm`"), "{}", message);
+    assert!(
+        message.contains("   | {}^".replace("{}", &" ".repeat(22)).as_str()),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn validation_errors_are_located() {
+    let diagnostic = diagnostic(
+        "@group(0) @binding(0) var<storage, read_write> out: array<u32>;
+
+@compute @workgroup_size(4)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let half: f32 = 0.5;
+    out[id.x] = id.x * half;
+}",
+    );
+    assert!(!diagnostic.message.is_empty());
+    assert!(
+        diagnostic
+            .spans
+            .iter()
+            .any(|span: &SourceSpan| span.line == 6 && span.excerpt.contains("id.x * half")),
+        "{:?}",
+        diagnostic
+    );
+}
+
+#[test]
+fn unknown_entry_point_lists_the_available_ones() {
+    let gpu = GpuCompute::try_new().unwrap();
+    let stage = |entrypoint| {
+        StageDesc::new(
+        " @group(0) @binding(0) var<storage, read_write> out: array<u32>; @compute @workgroup_size(4) fn main(@builtin(global_invocation_id) id: vec3<u32>) { out[id.x] = id.x; } @compute @workgroup_size(4) fn clear(@builtin(global_invocation_id) id: vec3<u32>) { out[id.x] = 0u; } ",
+        entrypoint,
+    )
+    };
+    let error = gpu
+        .try_gen_pipeline::<(), (), [u32; 4], 1>(None, [stage("mian").with_name("norm")])
+        .err()
+        .unwrap();
+    assert_eq!(
+        error,
+        SgpuError::Validation(
+            "entry point `mian` not found in stage `norm`; available: [main, clear]".into()
+        )
+    );
+    let error = gpu
+        .try_gen_pipeline::<(), (), [u32; 4], 1>(None, [stage("mian")])
+        .err()
+        .unwrap();
+    assert_eq!(
+        error.to_string(),
+        "validation error: entry point `mian` not found; available: [main, clear]"
+    );
+    assert!(gpu
+        .try_gen_pipeline::<(), (), [u32; 4], 1>(None, [stage("clear")])
+        .is_ok());
+}
+
+#[test]
+fn run_errors_are_returned() {
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(
+        None,
+        [StageDesc::new(
+            " @group(0) @binding(0) var<storage, read> input: array<u32, 4>; @group(0) @binding(1) var<storage, read_write> output: array<u32, 4>; @compute @workgroup_size(1) fn main(@builtin(global_invocation_id) id: vec3<u32>) { if id.x < 4u { output[id.x] = input[id.x]; } } ",
+            "main",
+        )
+        .with_name("copy")],
+    );
+    let error = pipeline
+        .try_run(&[1, 2, 3, 4], [(u32::MAX, 1, 1)], |out| *out)
+        .expect_err("Too many workgroups");
+    assert!(matches!(error, SgpuError::Validation(_)));
+    assert!(!error.is_retryable());
+
+    // The pipeline is still usable after the error.
+    assert_eq!(
+        pipeline.try_run(&[1, 2, 3, 4], [(4, 1, 1)], |out| *out),
+        Ok([1, 2, 3, 4])
+    );
+}
+
+#[test]
+fn runs_time_out() {
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(
+        None,
+        [StageDesc::new(
+            " @group(0) @binding(0) var<storage, read> input: array<u32, 4>; @group(0) @binding(1) var<storage, read_write> output: array<u32, 4>; @compute @workgroup_size(4) fn main(@builtin(global_invocation_id) id: vec3<u32>) { output[id.x] = input[id.x] + 1u; } ",
+            "main",
+        )
+        .with_name("increment")],
+    );
+    // The deadline passes before the device is polled for the first time.
+    let timeout = std::time::Duration::ZERO;
+    let error = pipeline
+        .run_with_timeout(&[1, 2, 3, 4], [(1, 1, 1)], timeout, |out| *out)
+        .expect_err("The device wasn't polled");
+    assert_eq!(error, SgpuError::TimedOut(timeout));
+
+    // The pipeline is still usable after a timeout.
+    assert_eq!(
+        pipeline.run_with_timeout(
+            &[5, 6, 7, 8],
+            [(1, 1, 1)],
+            std::time::Duration::from_secs(60),
+            |out| *out
+        ),
+        Ok([6, 7, 8, 9])
+    );
+    assert_eq!(pipeline.run(&[0; 4], [(1, 1, 1)], |out| *out), [1; 4]);
+
+    // The errors of the run are returned like with `try_run`.
+    let error = pipeline
+        .run_with_timeout(
+            &[1, 2, 3, 4],
+            [(u32::MAX, 1, 1)],
+            std::time::Duration::from_secs(60),
+            |out| *out,
+        )
+        .expect_err("Too many workgroups");
+    assert!(matches!(error, SgpuError::Validation(_)));
+}
@@ -0,0 +1,110 @@
+use sgpu_compute::kernels::error_map::ErrorMap;
+use sgpu_compute::prelude::*;
+
+fn reference(len: usize) -> Vec<f32> {
+    (0..len).map(|i| (i as f32 * 0.37).sin() * 100.0).collect()
+}
+
+#[test]
+fn summary_matches_the_cpu() {
+    let gpu = GpuCompute::new();
+    // More elements than invocations, so the invocations loop.
+    let reference = reference(1_500_000);
+    let actual: Vec<f32> = reference
+        .iter()
+        .enumerate()
+        .map(|(i, &r)| if i % 1000 == 7 { r * 1.001 } else { r })
+        .collect();
+    let mut map = ErrorMap::new(&gpu, &reference);
+    map.set_thresholds(1e-3, 1e-4);
+    let summary = map.compare(&actual);
+
+    let errors: Vec<(f32, f32)> = reference
+        .iter()
+        .zip(&actual)
+        .map(|(&r, &a)| {
+            let absolute = (a - r).abs();
+            (absolute, if a == r { 0.0 } else { absolute / r.abs() })
+        })
+        .collect();
+    let first_max = |error: fn(&(f32, f32)) -> f32| {
+        let max = errors.iter().map(error).fold(0.0, f32::max);
+        (max, errors.iter().position(|e| error(e) == max).unwrap())
+    };
+    assert_eq!(
+        (summary.max_absolute, summary.max_absolute_index),
+        first_max(|e| e.0)
+    );
+    assert_eq!(
+        (summary.max_relative, summary.max_relative_index),
+        first_max(|e| e.1)
+    );
+    let count = |over: fn(&(f32, f32)) -> bool| errors.iter().filter(|e| over(e)).count() as u32;
+    assert_eq!(summary.over_absolute, count(|e| e.0 > 1e-3));
+    assert_eq!(summary.over_relative, count(|e| e.1 > 1e-4));
+    assert_eq!(summary.mismatches, count(|e| e.0 > 1e-3 && e.1 > 1e-4));
+    assert_eq!(summary.nan_mismatches, 0);
+    assert!(!summary.is_close());
+
+    let map_errors = map.errors();
+    assert_eq!(map_errors.len(), reference.len());
+    assert_eq!(map_errors[7].absolute, errors[7].0);
+    assert_eq!(map_errors[8].absolute, 0.0);
+
+    // The reference is compared with itself without any error.
+    let summary = map.compare(&reference);
+    assert!(summary.is_close());
+    assert_eq!((summary.max_absolute, summary.max_absolute_index), (0.0, 0));
+}
+
+#[test]
+fn special_values() {
+    let gpu = GpuCompute::new();
+    let reference = [
+        0.0,
+        1.0,
+        f32::NAN,
+        f32::INFINITY,
+        f32::INFINITY,
+        2.0,
+        f32::NAN,
+        -0.0,
+    ];
+    let actual = [1e-9, 1.0, f32::NAN, f32::INFINITY, 1.0, f32::NAN, 3.0, 0.0];
+    let mut map = ErrorMap::new(&gpu, &reference);
+    map.set_thresholds(1e-6, 1e-6);
+    let summary = map.compare(&actual);
+    assert_eq!(summary.nan_mismatches, 2);
+    // The infinite reference and the two NaN mismatches, the zero reference is within the absolute threshold.
+    assert_eq!(summary.mismatches, 3);
+    assert_eq!(summary.max_absolute, f32::INFINITY);
+    assert_eq!(summary.max_absolute_index, 4);
+    let errors = map.errors();
+    assert_eq!(errors[0].relative, f32::INFINITY);
+    for i in [1, 2, 3, 7] {
+        assert_eq!((errors[i].absolute, errors[i].relative), (0.0, 0.0));
+    }
+}
+
+#[test]
+fn results_are_compared_on_the_gpu() {
+    let shader = "
+        @group(0) @binding(0) var<storage, read> in: array<f32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<f32>;
+
+        @compute @workgroup_size(64)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = sqrt(in[id.x]);
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let mut pipeline =
+        gpu.gen_pipeline::<[f32; 256], (), [f32; 256], 1>(None, [StageDesc::new(shader, "main")]);
+    let input: [f32; 256] = std::array::from_fn(|i| i as f32);
+    pipeline.run(&input, [(4, 1, 1)], |_| ());
+    let mut map = ErrorMap::new(&gpu, &input.map(f32::sqrt));
+    map.set_thresholds(0.0, 1e-6);
+    let summary = map.compare_buffer(pipeline.output_buffer(), 0);
+    assert!(summary.is_close(), "{:?}", summary);
+    assert_eq!(summary.over_relative, 0);
+}
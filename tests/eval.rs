@@ -0,0 +1,43 @@
+use sgpu_compute::prelude::*;
+
+#[test]
+fn eval_hash_against_reference() {
+    let source = "
+        const PRIME: u32 = 0x9e3779b1u;
+
+        fn mix(x: u32) -> u32 {
+            var h = x * PRIME;
+            h ^= h >> 15u;
+            return h;
+        }
+    ";
+    let reference = |x: u32| {
+        let h = x.wrapping_mul(0x9e37_79b1);
+        h ^ (h >> 15)
+    };
+    // More arguments than a single run evaluates.
+    let args: Vec<u32> = (0..40_000).collect();
+    let gpu = GpuCompute::new();
+    let results: Vec<u32> = gpu.eval_function(source, "mix", &args);
+    assert_eq!(results.len(), args.len());
+    assert!(args.iter().zip(&results).all(|(x, h)| reference(*x) == *h));
+}
+
+#[test]
+fn eval_vector_arguments() {
+    let source = "
+        fn scale(v: vec4<f32>, s: vec2<i32>) -> vec2<f32> {
+            return v.xy * f32(s.x) + v.zw * f32(s.y);
+        }
+    ";
+    let args = [
+        ([1.0f32, 2.0, 3.0, 4.0], [2i32, -1]),
+        ([0.5, 0.0, 1.0, 1.0], [4, 0]),
+    ];
+    let gpu = GpuCompute::new();
+    let results: Vec<[f32; 2]> = gpu.eval_function(source, "scale", &args);
+    assert_eq!(results, [[-1.0, 0.0], [2.0, 0.0]]);
+    let empty: Vec<f32> =
+        gpu.eval_function("fn id(x: f32) -> f32 { return x; }", "id", &[] as &[f32]);
+    assert!(empty.is_empty());
+}
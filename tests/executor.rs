@@ -0,0 +1,149 @@
+use sgpu_compute::executor::{CpuExecutor, Executor, WgpuExecutor};
+use sgpu_compute::prelude::*;
+
+const SHADER: &str = "
+    @group(0) @binding(0) var<uniform> scale: u32;
+    @group(0) @binding(1) var<storage, read_write> partial: array<u32>;
+    @group(0) @binding(2) var<storage, read> in: array<u32>;
+    @group(0) @binding(3) var<storage, read_write> out: array<u32>;
+
+    @compute @workgroup_size(16)
+    fn scale_values(@builtin(global_invocation_id) id: vec3<u32>) {
+        partial[id.x] = in[id.x] * scale;
+    }
+
+    @compute @workgroup_size(16)
+    fn prefix_sum(@builtin(global_invocation_id) id: vec3<u32>) {
+        var sum = 0u;
+        for (var i = 0u; i <= id.x; i++) {
+            sum += partial[i];
+        }
+        out[id.x] = sum;
+    }
+";
+
+fn cpu() -> CpuExecutor {
+    CpuExecutor::new()
+        .with_kernel("scale_values", |buffers, _| {
+            let scale: u32 = bytemuck::pod_read_unaligned(buffers.uniform);
+            let input: &[u32] = bytemuck::cast_slice(buffers.input);
+            let partial: &mut [u32] = bytemuck::cast_slice_mut(buffers.scratchpad);
+            for (p, v) in partial.iter_mut().zip(input) {
+                *p = v * scale;
+            }
+        })
+        .with_kernel("prefix_sum", |buffers, _| {
+            let partial: &[u32] = bytemuck::cast_slice(buffers.scratchpad);
+            let output: &mut [u32] = bytemuck::cast_slice_mut(buffers.output);
+            let mut sum = 0;
+            for (out, p) in output.iter_mut().zip(partial) {
+                sum += p;
+                *out = sum;
+            }
+        })
+}
+
+fn scaled_prefix_sums(executor: &mut dyn Executor, scale: u32) -> [u32; 32] {
+    let pipeline = executor
+        .gen_pipeline::<[u32; 32], u32, [u32; 32]>(
+            NonZeroUsize::new(128),
+            &[
+                StageDesc::new(SHADER, "scale_values").with_name("scale"),
+                StageDesc::new(SHADER, "prefix_sum").with_name("prefix_sum"),
+            ],
+        )
+        .unwrap();
+    executor.write_uniform(pipeline, bytemuck::bytes_of(&scale));
+    let input: [u32; 32] = std::array::from_fn(|i| i as u32);
+    let output = executor.run(pipeline, &input, &[(2, 1, 1), (2, 1, 1)]);
+    executor.destroy_pipeline(pipeline);
+    output
+}
+
+#[test]
+fn cpu_and_gpu_agree() {
+    let mut executors: Vec<Box<dyn Executor>> = vec![
+        Box::new(cpu()),
+        Box::new(WgpuExecutor::from(GpuCompute::new())),
+    ];
+    let outputs = executors
+        .iter_mut()
+        .map(|executor| scaled_prefix_sums(executor.as_mut(), 3))
+        .collect::<Vec<_>>();
+    assert_eq!(outputs[0], outputs[1]);
+    assert_eq!(outputs[0][31], 3 * 31 * 32 / 2);
+}
+
+#[test]
+fn cpu_kernel_must_be_registered() {
+    let mut executor = CpuExecutor::new();
+    let error = (&mut executor as &mut dyn Executor)
+        .gen_pipeline::<[u32; 4], (), [u32; 4]>(None, &[StageDesc::new(SHADER, "prefix_sum")])
+        .unwrap_err();
+    assert!(matches!(error, SgpuError::Unsupported(_)));
+    assert!(error.to_string().contains("`prefix_sum`"));
+}
+
+#[test]
+fn wgpu_executor_follows_the_options_of_the_device() {
+    let shader = "
+        @group(0) @binding(0) var<storage, read> in: array<u32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+        @compute @workgroup_size(4)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] += in[id.x];
+        }
+    ";
+    // The readback polls the device itself, nobody else polls it on demand.
+    let gpu = GpuCompute::with_options(GpuComputeOptions {
+        poll_strategy: PollStrategy::OnDemand,
+        zero_init: ZeroInit::BeforeEachRun,
+        ..Default::default()
+    });
+    let mut executor = WgpuExecutor::from(gpu);
+    let executor: &mut dyn Executor = &mut executor;
+    let pipeline = executor
+        .gen_pipeline::<[u32; 4], (), [u32; 4]>(None, &[StageDesc::new(shader, "main")])
+        .unwrap();
+    // The output is cleared before each run, so it doesn't accumulate.
+    for _ in 0..2 {
+        let output: [u32; 4] = executor.run(pipeline, &[1u32, 2, 3, 4], &[(1, 1, 1)]);
+        assert_eq!(output, [1, 2, 3, 4]);
+    }
+}
+
+#[test]
+fn wgpu_executor_sets_push_constants() {
+    let shader = "
+        var<push_constant> offset: u32;
+        @group(0) @binding(0) var<storage, read> in: array<u32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+        @compute @workgroup_size(4)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] + offset;
+        }
+    ";
+    let mut executor = WgpuExecutor::from(GpuCompute::new());
+    let supported = executor
+        .gpu()
+        .features()
+        .contains(wgpu::Features::PUSH_CONSTANTS);
+    let executor: &mut dyn Executor = &mut executor;
+    let sizes = sgpu_compute_core::layout::BufferSizes {
+        push_constants: 4,
+        ..sgpu_compute_core::layout::BufferSizes::of::<[u32; 4], (), [u32; 4]>(None)
+    };
+    let pipeline = executor.create_pipeline(&sizes, &[StageDesc::new(shader, "main")]);
+    if !supported {
+        assert!(matches!(pipeline, Err(SgpuError::Unsupported(_))));
+        return;
+    }
+    let pipeline = pipeline.unwrap();
+    for offset in [10u32, 20] {
+        executor.write_push_constants(pipeline, bytemuck::bytes_of(&offset));
+        let output: [u32; 4] = executor.run(pipeline, &[1u32, 2, 3, 4], &[(1, 1, 1)]);
+        assert_eq!(output, [1, 2, 3, 4].map(|v| v + offset));
+    }
+}
@@ -0,0 +1,52 @@
+#![cfg(feature = "csv")]
+use sgpu_compute::export::{Column, ToCsv};
+use sgpu_compute::prelude::*;
+
+#[derive(Copy, Clone, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+struct Output {
+    square: [u32; 4],
+    parity: [u32; 2],
+}
+
+impl ToCsv for Output {
+    fn columns(&self) -> Vec<Column<'_>> {
+        vec![
+            Column::new("square", &self.square),
+            Column::new("parity", &self.parity),
+        ]
+    }
+}
+
+#[test]
+fn export_gpu_output() {
+    let shader = "
+        @group(0) @binding(0) var<storage, read> in: array<u32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+        @compute
+        @workgroup_size(4, 1, 1)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] * in[id.x];
+            if id.x < 2u {
+                out[4u + id.x] = in[id.x] % 2u;
+            }
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[u32; 4], (), Output, 1>(
+        None,
+        [StageDesc::new(shader, "main").with_name("export")],
+    );
+    let output = pipeline.run(&[1, 2, 3, 4], [(1, 1, 1)], |output| *output);
+
+    let path = std::env::temp_dir().join("sgpu_compute_export_test.csv");
+    output.to_csv(&path).unwrap();
+    let csv = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(csv, "square,parity\n1,1\n4,0\n9,\n16,\n");
+
+    let mut csv = Vec::new();
+    output.square.write_csv(&mut csv).unwrap();
+    assert_eq!(csv, b"value\n1\n4\n9\n16\n");
+}
@@ -0,0 +1,36 @@
+use sgpu_compute::float_cmp::{assert_all_close, ulps, Tolerance};
+
+#[test]
+fn ulps_across_zero_and_signs() {
+    assert_eq!(ulps(0.0f32, -0.0), 0);
+    assert_eq!(ulps(f32::from_bits(1), -f32::from_bits(1)), 2);
+    assert_eq!(ulps(1.0f64, 1.0 + f64::EPSILON), 1);
+    assert_eq!(ulps(f32::NAN, 1.0), u64::MAX);
+    assert_eq!(ulps(f32::MAX, f32::INFINITY), 1);
+}
+
+#[test]
+fn tolerance_near_zero() {
+    // A relative tolerance alone rejects values which should be zero.
+    assert!(!Tolerance::rel(1e-3).is_close(1e-9f32, 0.0));
+    assert!(Tolerance::rel(1e-3).with_abs(1e-6).is_close(1e-9f32, 0.0));
+    // An absolute tolerance alone accepts very different small values.
+    assert!(Tolerance::abs(1e-5).is_close(1e-7f32, 5e-6));
+    assert!(!Tolerance::ulps(16).is_close(1e-7f32, 5e-6));
+    assert!(Tolerance::rel(1e-6).is_close(1e9f64, 1e9 + 100.0));
+}
+
+#[test]
+fn special_values() {
+    let tolerance = Tolerance::abs(f64::MAX);
+    assert!(!tolerance.is_close(f32::NAN, f32::NAN));
+    assert!(tolerance.is_close(f32::INFINITY, f32::INFINITY));
+    assert!(!tolerance.is_close(f32::INFINITY, f32::MAX));
+    assert!(Tolerance::EXACT.is_close(-0.0f32, 0.0));
+}
+
+#[test]
+#[should_panic(expected = "Values at index 1 differ: 2.0 != 2.1")]
+fn assert_reports_first_mismatch() {
+    assert_all_close(&[1.0f32, 2.0, 4.0], &[1.0, 2.1, 3.0], Tolerance::ulps(8));
+}
@@ -0,0 +1,79 @@
+use sgpu_compute::kernels::gather::{self, BoundsPolicy, GatherParams, Indexed, Values};
+use sgpu_compute::prelude::*;
+use sgpu_compute::testgen::{self, TestRng};
+
+const COUNT: usize = 5000;
+const LEN: usize = 3000;
+
+#[test]
+fn gather_compare() {
+    let data: Vec<[u32; 2]> = (0..LEN as u32).map(|i| [i, !i]).collect();
+    let indices: [u32; COUNT] = testgen::random_u32_in(46, 0..LEN as u32 + 100);
+    let input = Box::new(Indexed::<COUNT, { 2 * LEN }>::new(indices, &data));
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu
+        .gen_pipeline::<Indexed<COUNT, { 2 * LEN }>, GatherParams, Values<{ 2 * COUNT }>, 2>(
+            None,
+            gather::GATHER,
+        );
+
+    for policy in [BoundsPolicy::Clamp, BoundsPolicy::Skip, BoundsPolicy::Flag] {
+        pipeline.write_uniform(&GatherParams::new::<[u32; 2]>(
+            COUNT as u32,
+            LEN as u32,
+            policy,
+        ));
+        let (values, out_of_range, first) =
+            pipeline.run(&input, gather::workgroups(COUNT as u32), |out| {
+                (
+                    out.to_vec::<[u32; 2]>(),
+                    out.out_of_range(),
+                    out.first_out_of_range(),
+                )
+            });
+        let mut expected_out_of_range = 0;
+        for (i, (&index, value)) in indices.iter().zip(&values).enumerate() {
+            if (index as usize) < LEN {
+                assert_eq!(*value, data[index as usize]);
+                continue;
+            }
+            // Skipped elements keep the value written by the clamp policy in the first run.
+            assert_eq!(*value, data[LEN - 1]);
+            if expected_out_of_range == 0 && policy == BoundsPolicy::Flag {
+                assert_eq!(first, Some(i as u32));
+            }
+            expected_out_of_range += 1;
+        }
+        match policy {
+            BoundsPolicy::Flag => assert_eq!(out_of_range, expected_out_of_range),
+            _ => assert_eq!((out_of_range, first), (0, None)),
+        }
+    }
+}
+
+#[test]
+fn scatter_permutation() {
+    const N: usize = 1000;
+    let mut rng = TestRng::new(47);
+    let mut permutation: Vec<u32> = (0..N as u32).collect();
+    for i in (1..N).rev() {
+        permutation.swap(i, rng.range_u32(0..i as u32 + 1) as usize);
+    }
+    let data: [f32; N] = testgen::random_f32(48, 0.0..1.0);
+    let input = Indexed::<N, N>::new(permutation.clone().try_into().unwrap(), &data);
+    let gpu = GpuCompute::new();
+    let mut pipeline =
+        gpu.gen_pipeline::<Indexed<N, N>, GatherParams, Values<N>, 2>(None, gather::SCATTER);
+    pipeline.write_uniform(&GatherParams::new::<f32>(
+        N as u32,
+        N as u32,
+        BoundsPolicy::Flag,
+    ));
+    let values = pipeline.run(&input, gather::workgroups(N as u32), |out| {
+        assert_eq!(out.first_out_of_range(), None);
+        out.to_vec::<f32>()
+    });
+    for (i, &index) in permutation.iter().enumerate() {
+        assert_eq!(values[index as usize], data[i]);
+    }
+}
@@ -0,0 +1,67 @@
+use sgpu_compute::prelude::*;
+
+const SHADER: &str = "
+    #version 450
+    layout(local_size_x = 16) in;
+
+    layout(set = 0, binding = 0) uniform Parameters { uint offset; };
+    layout(set = 0, binding = 1) readonly buffer Inputs { uint values[]; } inputs;
+    layout(set = 0, binding = 2) buffer Outputs { uint values[]; } outputs;
+
+    void main() {
+        uint i = gl_GlobalInvocationID.x;
+        outputs.values[i] = inputs.values[i] + offset;
+    }
+";
+
+fn stage(entrypoint: &'static str) -> StageDesc {
+    StageDesc::new("offset.comp", entrypoint)
+        .with_name("offset")
+        .with_source(ShaderSource::Glsl { src: SHADER })
+}
+
+#[cfg(feature = "glsl")]
+#[test]
+fn glsl_stage_runs_and_is_reflected() {
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[u32; 16], u32, [u32; 16], 1>(None, [stage("main")]);
+    pipeline.write_uniform(&10);
+    let input: [u32; 16] = std::array::from_fn(|i| i as u32);
+    assert_eq!(
+        pipeline.run(&input, [(1, 1, 1)], |out| *out),
+        input.map(|i| i + 10)
+    );
+    assert_eq!(
+        pipeline.describe().stages[0].workgroup_size,
+        Some([16, 1, 1])
+    );
+}
+
+#[cfg(feature = "glsl")]
+#[test]
+fn glsl_stage_errors() {
+    let gpu = GpuCompute::new();
+    let error = |desc| {
+        gpu.try_gen_pipeline::<[u32; 16], u32, [u32; 16], 1>(None, [desc])
+            .err()
+            .unwrap()
+    };
+    assert!(matches!(error(stage("offset")), SgpuError::Validation(_)));
+    // The bindings of the shader are checked like the ones of a WGSL shader.
+    let error = gpu
+        .try_gen_pipeline::<[u32; 16], (), [u32; 16], 1>(None, [stage("main")])
+        .err()
+        .unwrap();
+    assert!(matches!(error, SgpuError::LayoutMismatch(_)), "{}", error);
+}
+
+#[cfg(not(feature = "glsl"))]
+#[test]
+fn glsl_requires_the_feature() {
+    let gpu = GpuCompute::new();
+    let error = gpu
+        .try_gen_pipeline::<[u32; 16], u32, [u32; 16], 1>(None, [stage("main")])
+        .err()
+        .unwrap();
+    assert!(matches!(error, SgpuError::Unsupported(_)), "{}", error);
+}
@@ -0,0 +1,149 @@
+use sgpu_compute::gradient::{FiniteDifferences, Scheme};
+use sgpu_compute::prelude::*;
+
+const SHADER: &str = "
+    struct Parameters { a: f32, b: f32, count: u32, c: f32 }
+
+    @group(0) @binding(0) var<uniform> p: Parameters;
+    @group(0) @binding(1) var<storage, read> in: array<f32>;
+    @group(0) @binding(2) var<storage, read_write> out: array<f32>;
+
+    @compute @workgroup_size(8)
+    fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+        let x = in[id.x];
+        out[id.x] = select(0.0, p.a * x * x + sin(p.b * x) + exp(p.c), id.x < p.count);
+    }
+";
+
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct Parameters {
+    a: f32,
+    b: f32,
+    count: u32,
+    c: f32,
+}
+
+const INPUT: [f32; 8] = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8];
+
+/// Analytic gradient of the sum of the outputs with respect to `a`, `b` and `c`.
+fn expected(p: &Parameters) -> [f64; 3] {
+    let xs = &INPUT[..p.count as usize];
+    [
+        xs.iter().map(|&x| (x * x) as f64).sum(),
+        xs.iter()
+            .map(|&x| x as f64 * (p.b as f64 * x as f64).cos())
+            .sum(),
+        xs.len() as f64 * (p.c as f64).exp(),
+    ]
+}
+
+fn differences(
+    gpu: &GpuCompute,
+    scheme: Scheme,
+) -> FiniteDifferences<'_, [f32; 8], Parameters, [f32; 8], 1> {
+    let pipeline = gpu
+        .gen_pipeline::<[f32; 8], Parameters, [f32; 8], 1>(None, [StageDesc::new(SHADER, "main")]);
+    // The count isn't a parameter.
+    FiniteDifferences::new(pipeline, scheme).with_parameters([0, 1, 3])
+}
+
+fn sum(out: &[f32; 8]) -> f64 {
+    out.iter().map(|&v| v as f64).sum()
+}
+
+#[test]
+fn gradients_match_the_analytic_ones() {
+    let gpu = GpuCompute::new();
+    let parameters = Parameters {
+        a: 1.5,
+        b: 2.0,
+        count: 6,
+        c: -0.5,
+    };
+    for (scheme, tolerance, evaluations) in [(Scheme::Forward, 2e-2, 4), (Scheme::Central, 5e-3, 7)]
+    {
+        let mut differences = differences(&gpu, scheme);
+        let gradient = differences.gradient(&parameters, &INPUT, [(1, 1, 1)], sum);
+        assert_eq!(gradient.evaluations, evaluations);
+        assert_eq!(gradient.gradient.len(), 3);
+        for (estimate, exact) in gradient.gradient.iter().zip(expected(&parameters)) {
+            assert!(
+                (estimate - exact).abs() < tolerance * exact.abs().max(1.0),
+                "{:?}: {} instead of {}",
+                scheme,
+                estimate,
+                exact
+            );
+        }
+        // The pipeline is left with the unperturbed uniform.
+        let mut pipeline = differences.into_pipeline();
+        assert_eq!(pipeline.run(&INPUT, [(1, 1, 1)], sum), gradient.value);
+    }
+}
+
+#[test]
+fn gradients_can_be_evaluated_repeatedly() {
+    let gpu = GpuCompute::new();
+    let mut differences = differences(&gpu, Scheme::Central).with_step(1e-2);
+    let mut parameters = Parameters {
+        a: 1.0,
+        b: 1.0,
+        count: 8,
+        c: 0.0,
+    };
+    // Gradient descent on the sum, whose gradient with respect to `c` is 8 exp(c).
+    let first = differences
+        .gradient(&parameters, &INPUT, [(1, 1, 1)], sum)
+        .value;
+    for _ in 0..5 {
+        let gradient = differences.gradient(&parameters, &INPUT, [(1, 1, 1)], sum);
+        parameters.c -= 0.05 * gradient.gradient[2] as f32;
+    }
+    let last = differences
+        .gradient(&parameters, &INPUT, [(1, 1, 1)], sum)
+        .value;
+    assert!(last < first);
+}
+
+#[test]
+#[should_panic(expected = "out of the uniform")]
+fn parameters_must_be_in_the_uniform() {
+    let gpu = GpuCompute::new();
+    let _ = differences(&gpu, Scheme::Forward).with_parameters([4]);
+}
+
+#[test]
+fn gradients_keep_the_push_constants() {
+    let shader = "
+        var<push_constant> scale: f32;
+        @group(0) @binding(0) var<uniform> a: f32;
+        @group(0) @binding(1) var<storage, read> in: array<f32>;
+        @group(0) @binding(2) var<storage, read_write> out: array<f32>;
+
+        @compute @workgroup_size(4)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = scale * a * in[id.x];
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let pipeline = gpu.try_gen_pipeline_with_push_constants::<[f32; 4], f32, [f32; 4], f32, 1>(
+        None,
+        [StageDesc::new(shader, "main")],
+    );
+    if !gpu.features().contains(wgpu::Features::PUSH_CONSTANTS) {
+        assert!(matches!(pipeline, Err(SgpuError::Unsupported(_))));
+        return;
+    }
+    let mut pipeline = pipeline.unwrap();
+    pipeline.set_push_constants(&3.0f32);
+    let mut differences = FiniteDifferences::new(pipeline, Scheme::Forward);
+    let gradient = differences.gradient(&2.0, &[1.0; 4], [(1, 1, 1)], |out| out[0] as f64);
+    assert!((gradient.value - 6.0).abs() < 1e-4);
+    assert!((gradient.gradient[0] - 3.0).abs() < 1e-2);
+
+    // The clones follow the push constants changed between gradients.
+    differences.pipeline().set_push_constants(&5.0f32);
+    let gradient = differences.gradient(&2.0, &[1.0; 4], [(1, 1, 1)], |out| out[0] as f64);
+    assert!((gradient.gradient[0] - 5.0).abs() < 1e-2);
+}
@@ -0,0 +1,234 @@
+use sgpu_compute::graph::{Access, ComputeGraph};
+use sgpu_compute::prelude::*;
+
+const SCALE: &str = "
+    @group(0) @binding(0) var<uniform> factor: u32;
+    @group(0) @binding(1) var<storage, read> in: array<u32, 16>;
+    @group(0) @binding(2) var<storage, read_write> out: array<u32, 16>;
+
+    @compute @workgroup_size(16)
+    fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+        out[id.x] = in[id.x] * factor;
+    }
+";
+
+const ADD: &str = "
+    @group(0) @binding(0) var<storage, read> a: array<u32, 16>;
+    @group(0) @binding(1) var<storage, read> b: array<u32, 16>;
+    @group(0) @binding(2) var<storage, read_write> out: array<u32, 16>;
+
+    @compute @workgroup_size(16)
+    fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+        out[id.x] = a[id.x] + b[id.x];
+    }
+";
+
+const ACCUMULATE: &str = "
+    @group(0) @binding(0) var<storage, read> in: array<u32, 16>;
+    @group(0) @binding(1) var<storage, read_write> total: array<u32, 16>;
+
+    @compute @workgroup_size(16)
+    fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+        total[id.x] += in[id.x];
+    }
+";
+
+fn stage(shader: &'static str) -> StageDesc {
+    StageDesc::new(shader, "main")
+}
+
+#[test]
+fn kernels_run_in_dependency_order() {
+    let mut graph = ComputeGraph::new();
+    let x = graph.buffer("x", 64);
+    let factor = graph.buffer("factor", 4);
+    let scaled = graph.buffer("scaled", 64);
+    let sum = graph.buffer("sum", 64);
+    let sum_input = graph.buffer("sum_input", 64);
+    let total = graph.buffer("total", 64);
+    // Declared from the last kernel to the first one.
+    graph.kernel(
+        "accumulate",
+        stage(ACCUMULATE),
+        &[Access::Read(sum_input), Access::Write(total)],
+        (1, 1, 1),
+    );
+    graph.connect(sum, sum_input);
+    graph.kernel(
+        "add",
+        stage(ADD),
+        &[Access::Read(scaled), Access::Read(x), Access::Write(sum)],
+        (1, 1, 1),
+    );
+    graph.kernel(
+        "scale",
+        stage(SCALE),
+        &[
+            Access::Uniform(factor),
+            Access::Read(x),
+            Access::Write(scaled),
+        ],
+        (1, 1, 1),
+    );
+    graph.read_back(sum);
+    graph.read_back(total);
+
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_graph_pipeline(graph);
+    assert_eq!(pipeline.kernel_order(), ["scale", "add", "accumulate"]);
+    assert_eq!(pipeline.buffer_id("total"), Some(total));
+    assert_eq!(pipeline.buffer_id("missing"), None);
+    assert_eq!(pipeline.read::<u32>(total), [0; 16]);
+
+    let input = (0..16).collect::<Vec<u32>>();
+    pipeline.write(x, &input);
+    pipeline.write(factor, &[2u32]);
+    pipeline.run();
+    assert_eq!(
+        pipeline.read::<u32>(sum),
+        input.iter().map(|x| 3 * x).collect::<Vec<_>>()
+    );
+    assert_eq!(pipeline.read::<u32>(total), pipeline.read::<u32>(sum));
+
+    // The buffers keep their content between runs.
+    pipeline.write(factor, &[4u32]);
+    pipeline.run();
+    assert_eq!(
+        pipeline.read::<u32>(total),
+        input.iter().map(|x| 8 * x).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn kernels_read_as_uniform_a_buffer_written_by_another_kernel() {
+    let mut graph = ComputeGraph::new();
+    let factor = graph.buffer("factor", 4);
+    let x = graph.buffer("x", 64);
+    let scaled = graph.buffer("scaled", 64);
+    graph.kernel(
+        "scale",
+        stage(SCALE),
+        &[
+            Access::Uniform(factor),
+            Access::Read(x),
+            Access::Write(scaled),
+        ],
+        (1, 1, 1),
+    );
+    graph.kernel(
+        "factor",
+        stage(
+            "
+            @group(0) @binding(0) var<storage, read_write> factor: array<u32, 1>;
+
+            @compute @workgroup_size(1)
+            fn main() {
+                factor[0] = 5u;
+            }
+            ",
+        ),
+        &[Access::Write(factor)],
+        (1, 1, 1),
+    );
+    graph.read_back(scaled);
+
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_graph_pipeline(graph);
+    assert_eq!(pipeline.kernel_order(), ["factor", "scale"]);
+    let input = (0..16).collect::<Vec<u32>>();
+    pipeline.write(x, &input);
+    pipeline.run();
+    assert_eq!(
+        pipeline.read::<u32>(scaled),
+        input.iter().map(|x| 5 * x).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn malformed_graphs_are_rejected() {
+    let gpu = GpuCompute::new();
+    let error = |graph: ComputeGraph| match gpu.try_gen_graph_pipeline(graph) {
+        Err(SgpuError::InvalidGraph(message)) => message,
+        Err(error) => panic!("Unexpected error: {}", error),
+        Ok(_) => panic!("The graph is malformed"),
+    };
+
+    let mut cycle = ComputeGraph::new();
+    let (a, b, out) = (
+        cycle.buffer("a", 64),
+        cycle.buffer("b", 64),
+        cycle.buffer("out", 64),
+    );
+    cycle.kernel(
+        "first",
+        stage(ADD),
+        &[Access::Read(a), Access::Read(b), Access::Write(out)],
+        (1, 1, 1),
+    );
+    cycle.connect(out, a);
+    assert!(error(cycle).contains("cycle through kernel `first`, copy of `out` into `a`"));
+
+    let mut twice = ComputeGraph::new();
+    let (a, b) = (twice.buffer("a", 64), twice.buffer("b", 64));
+    twice.kernel(
+        "first",
+        stage(ACCUMULATE),
+        &[Access::Read(a), Access::Write(b)],
+        (1, 1, 1),
+    );
+    twice.kernel(
+        "second",
+        stage(ACCUMULATE),
+        &[Access::Read(a), Access::Write(b)],
+        (1, 1, 1),
+    );
+    assert_eq!(
+        error(twice),
+        "the buffer `b` is written by the kernel `first` and by the kernel `second`"
+    );
+
+    let mut sizes = ComputeGraph::new();
+    let (a, b) = (sizes.buffer("a", 64), sizes.buffer("b", 32));
+    sizes.connect(a, b);
+    assert_eq!(
+        error(sizes),
+        "the buffer `a` (64 bytes) can't be copied into `b` (32 bytes)"
+    );
+
+    let mut odd = ComputeGraph::new();
+    odd.buffer("a", 6);
+    assert!(error(odd).contains("`a` is 6 bytes"));
+
+    let mut aliased = ComputeGraph::new();
+    let a = aliased.buffer("a", 64);
+    aliased.kernel(
+        "in_place",
+        stage(ACCUMULATE),
+        &[Access::Read(a), Access::Write(a)],
+        (1, 1, 1),
+    );
+    assert_eq!(
+        error(aliased),
+        "the kernel `in_place` binds the buffer `a` twice"
+    );
+}
+
+#[test]
+fn bindings_are_checked_per_kernel() {
+    let gpu = GpuCompute::new();
+    let mut graph = ComputeGraph::new();
+    let (a, b) = (graph.buffer("a", 64), graph.buffer("b", 64));
+    // The shader writes its second binding, which is bound as read-only.
+    graph.kernel(
+        "accumulate",
+        stage(ACCUMULATE),
+        &[Access::Read(a), Access::Read(b)],
+        (1, 1, 1),
+    );
+    match gpu.try_gen_graph_pipeline(graph) {
+        Err(SgpuError::LayoutMismatch(message)) => {
+            assert!(message.starts_with("kernel `accumulate`"), "{}", message)
+        }
+        _ => panic!("The bindings don't match"),
+    }
+}
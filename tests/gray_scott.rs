@@ -0,0 +1,95 @@
+use sgpu_compute::float_cmp::Tolerance;
+use sgpu_compute::kernels::gray_scott::{self, GrayScottOutput, GrayScottParams};
+use sgpu_compute::prelude::*;
+
+const WIDTH: u32 = 24;
+const HEIGHT: u32 = 20;
+const N: usize = (WIDTH * HEIGHT) as usize;
+
+fn step_cpu(field: &[[f32; 2]], params: &GrayScottParams) -> Vec<[f32; 2]> {
+    let (w, h) = (params.width as i32, params.height as i32);
+    let at = |x: i32, y: i32| field[((x + w) % w + w * ((y + h) % h)) as usize];
+    let mut next = vec![[0.0; 2]; field.len()];
+    for y in 0..h {
+        for x in 0..w {
+            let c = at(x, y);
+            let mut laplacian = [-c[0], -c[1]];
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    if dx == 0 && dy == 0 {
+                        continue;
+                    }
+                    let weight = if dx != 0 && dy != 0 { 0.05 } else { 0.2 };
+                    let n = at(x + dx, y + dy);
+                    laplacian[0] += weight * n[0];
+                    laplacian[1] += weight * n[1];
+                }
+            }
+            let reaction = c[0] * c[1] * c[1];
+            let du = params.diffusion_u * laplacian[0] - reaction + params.feed * (1.0 - c[0]);
+            let dv =
+                params.diffusion_v * laplacian[1] + reaction - (params.kill + params.feed) * c[1];
+            next[(x + w * y) as usize] = [c[0] + params.dt * du, c[1] + params.dt * dv];
+        }
+    }
+    next
+}
+
+#[test]
+fn gray_scott_matches_cpu() {
+    let initial: [[f32; 2]; N] = gray_scott::seed_square(WIDTH, HEIGHT, 6)
+        .try_into()
+        .unwrap();
+    let params = GrayScottParams::new(WIDTH, HEIGHT);
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[[f32; 2]; N], GrayScottParams, [f32; N], 5>(
+        gray_scott::scratchpad_size(WIDTH, HEIGHT),
+        gray_scott::stages(GrayScottOutput::Concentration),
+    );
+    pipeline.write_uniform(&params.with_reset(true));
+    let first = pipeline.run(&initial, gray_scott::workgroups(WIDTH, HEIGHT), |v| *v);
+    pipeline.write_uniform(&params);
+    let second = pipeline.run(&initial, gray_scott::workgroups(WIDTH, HEIGHT), |v| *v);
+
+    let mut field = initial.to_vec();
+    for (run, gpu_v) in [first, second].iter().enumerate() {
+        for _ in 0..4 {
+            field = step_cpu(&field, &params);
+        }
+        let cpu = field.iter().map(|cell| cell[1]).collect::<Vec<_>>();
+        if let Some((i, gpu, cpu)) = Tolerance::abs(1e-5)
+            .with_ulps(4)
+            .first_mismatch(gpu_v, &cpu)
+        {
+            panic!("run {} cell {}: {} != {}", run, i, gpu, cpu);
+        }
+    }
+}
+
+#[test]
+fn gray_scott_rgba() {
+    let initial: [[f32; 2]; N] = gray_scott::seed_square(WIDTH, HEIGHT, 6)
+        .try_into()
+        .unwrap();
+    let params = GrayScottParams::new(WIDTH, HEIGHT).with_reset(true);
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[[f32; 2]; N], GrayScottParams, [[u8; 4]; N], 3>(
+        gray_scott::scratchpad_size(WIDTH, HEIGHT),
+        gray_scott::stages(GrayScottOutput::Rgba),
+    );
+    pipeline.write_uniform(&params);
+    let image = pipeline.run(&initial, gray_scott::workgroups(WIDTH, HEIGHT), |image| {
+        *image
+    });
+    // Far from the square the concentrations are still u = 1 and v = 0 after two steps.
+    assert_eq!(image[0], [255, 0, 0, 255]);
+    let center = (WIDTH / 2 + WIDTH * (HEIGHT / 2)) as usize;
+    assert_ne!(image[center], image[0]);
+    assert!(image.iter().all(|color| color[3] == 255));
+}
+
+#[test]
+#[should_panic(expected = "odd number of stages")]
+fn gray_scott_even_stages() {
+    gray_scott::stages::<4>(GrayScottOutput::Concentration);
+}
@@ -0,0 +1,60 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sgpu_compute::kernels::hash::{self, HashParams};
+use sgpu_compute::prelude::*;
+
+#[test]
+fn reference_vectors() {
+    assert_eq!(hash::murmur3_32(b"", 0), 0);
+    assert_eq!(hash::murmur3_32(b"hello", 0), 0x248bfa47);
+    assert_eq!(hash::xxh32(b"", 0), 0x02cc5d05);
+    assert_eq!(hash::xxh32(b"abc", 0), 0x32d153ff);
+    assert_eq!(hash::xxh64(b"", 0), 0xef46db3751d8e999);
+    assert_eq!(hash::xxh64(b"abc", 0), 0x44bc2cf5ad770999);
+}
+
+fn hash_compare<const WORDS: usize, const COUNT: usize>(seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let keys: Vec<[u32; WORDS]> = (0..COUNT)
+        .map(|_| std::array::from_fn(|_| rng.gen()))
+        .collect();
+    let keys: [[u32; WORDS]; COUNT] = keys.try_into().unwrap();
+    let params = HashParams::new(COUNT as u32, 4 * WORDS as u32, seed);
+    let bytes = |key: &[u32; WORDS]| bytemuck::bytes_of(key).to_vec();
+
+    let gpu = GpuCompute::new();
+    let mut murmur = gpu.gen_pipeline::<[[u32; WORDS]; COUNT], HashParams, [u32; COUNT], 1>(
+        None,
+        [hash::MURMUR3_32],
+    );
+    murmur.write_uniform(&params);
+    let hashes = murmur.run(&keys, [hash::workgroups(COUNT)], |hashes| *hashes);
+    for (key, hash) in keys.iter().zip(hashes) {
+        assert_eq!(hash, hash::murmur3_32(&bytes(key), seed as u32));
+    }
+
+    let mut xxh32 =
+        gpu.gen_pipeline::<[[u32; WORDS]; COUNT], HashParams, [u32; COUNT], 1>(None, [hash::XXH32]);
+    xxh32.write_uniform(&params);
+    let hashes = xxh32.run(&keys, [hash::workgroups(COUNT)], |hashes| *hashes);
+    for (key, hash) in keys.iter().zip(hashes) {
+        assert_eq!(hash, hash::xxh32(&bytes(key), seed as u32));
+    }
+
+    let mut xxh64 =
+        gpu.gen_pipeline::<[[u32; WORDS]; COUNT], HashParams, [u64; COUNT], 1>(None, [hash::XXH64]);
+    xxh64.write_uniform(&params);
+    let hashes = xxh64.run(&keys, [hash::workgroups(COUNT)], |hashes| *hashes);
+    for (key, hash) in keys.iter().zip(hashes) {
+        assert_eq!(hash, hash::xxh64(&bytes(key), seed));
+    }
+}
+
+#[test]
+fn hash_compare_sizes() {
+    // Sizes covering the stripes and the tails of each algorithm.
+    hash_compare::<1, 1000>(1);
+    hash_compare::<3, 1000>(0xdead_beef_0000_0001);
+    hash_compare::<4, 1000>(3);
+    hash_compare::<9, 1000>(u64::MAX);
+    hash_compare::<19, 500>(5);
+}
@@ -0,0 +1,54 @@
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use sgpu_compute::kernels::hash_map::GpuHashMap;
+use sgpu_compute::prelude::*;
+use std::collections::HashMap;
+
+#[test]
+fn hash_map_compare() {
+    let mut rng = StdRng::seed_from_u64(21);
+    // Many duplicated keys, inserted in several batches.
+    let entries: Vec<(u64, u32)> = (0..5000)
+        .map(|_| (rng.gen_range(0..3000) << 33, rng.gen()))
+        .collect();
+    let gpu = GpuCompute::new();
+    let mut map = GpuHashMap::<u64, 1024>::new(&gpu, 6000);
+    assert_eq!(map.capacity(), 8192);
+    let mut expected = HashMap::new();
+    for chunk in entries.chunks(1024) {
+        assert_eq!(map.insert(chunk), 0);
+        // Within a batch any of the values of a duplicated key can be kept.
+        let mut batch: HashMap<u64, Vec<u32>> = HashMap::new();
+        for (key, value) in chunk {
+            batch.entry(*key).or_default().push(*value);
+        }
+        let keys: Vec<u64> = batch.keys().copied().collect();
+        for (key, value) in keys.iter().zip(map.get(&keys)) {
+            let value = value.unwrap();
+            assert!(batch[key].contains(&value));
+            expected.insert(*key, value);
+        }
+    }
+
+    let mut queries: Vec<u64> = (0..4000).map(|key| key << 33).collect();
+    queries.shuffle(&mut rng);
+    let values = map.get(&queries);
+    for (key, value) in queries.iter().zip(values) {
+        assert_eq!(value, expected.get(key).copied(), "key {}", key);
+    }
+
+    map.clear();
+    assert!(map.get(&queries).iter().all(Option::is_none));
+}
+
+#[test]
+fn hash_map_full() {
+    let gpu = GpuCompute::new();
+    let mut map = GpuHashMap::<u32, 64>::new(&gpu, 16);
+    let entries: Vec<(u32, u32)> = (0..20).map(|key| (key, key * 10)).collect();
+    assert_eq!(map.insert(&entries), 4);
+    let values = map.get(&(0..20).collect::<Vec<_>>());
+    assert_eq!(values.iter().flatten().count(), 16);
+    for (key, value) in values.into_iter().enumerate() {
+        assert!(value.is_none() || value == Some(key as u32 * 10));
+    }
+}
@@ -0,0 +1,44 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sgpu_compute::kernels::integral::{self, ImageSize};
+use sgpu_compute::prelude::*;
+
+#[test]
+fn integral_compare() {
+    const WIDTH: u32 = 83;
+    const HEIGHT: u32 = 70;
+    const LEN: usize = (WIDTH * HEIGHT) as usize;
+    let mut rng = StdRng::seed_from_u64(5);
+    // Small integers keep the sums exact in f32.
+    let image: [f32; LEN] = std::array::from_fn(|_| rng.gen_range(0..16) as f32);
+
+    let mut expected = [0.0; LEN];
+    for y in 0..HEIGHT as usize {
+        for x in 0..WIDTH as usize {
+            let i = x + WIDTH as usize * y;
+            let left = if x > 0 { expected[i - 1] } else { 0.0 };
+            let up = if y > 0 {
+                expected[i - WIDTH as usize]
+            } else {
+                0.0
+            };
+            let diag = if x > 0 && y > 0 {
+                expected[i - WIDTH as usize - 1]
+            } else {
+                0.0
+            };
+            expected[i] = image[i] + left + up - diag;
+        }
+    }
+
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[f32; LEN], ImageSize, [f32; LEN], 2>(
+        integral::scratchpad_size(WIDTH, HEIGHT),
+        integral::STAGES,
+    );
+    pipeline.write_uniform(&ImageSize {
+        width: WIDTH,
+        height: HEIGHT,
+    });
+    let table = pipeline.run(&image, integral::workgroups(WIDTH, HEIGHT), |table| *table);
+    assert_eq!(table, expected);
+}
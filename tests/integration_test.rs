@@ -1,6 +1,7 @@
 use std::array;
 
 use crate::normal_distribution::numerical_integration_cpu;
+use sgpu_compute::float_cmp::{assert_all_close, Tolerance};
 use sgpu_compute::prelude::*;
 
 pub mod normal_distribution;
@@ -13,19 +14,1326 @@ fn normal_distribution_compare() {
     let gpu_compute = GpuCompute::new();
     let mut pipeline = gpu_compute.gen_pipeline::<[f32; N as usize], u32, [f32; N as usize], 1>(
         None,
-        [StageDesc {
-            name: Some("norm"),
-            shader: include_str!("../examples/normal_distribution.wgsl"),
-            entrypoint: "main",
-        }],
+        [
+            StageDesc::new(include_str!("../examples/normal_distribution.wgsl"), "main")
+                .with_name("norm"),
+        ],
     );
     pipeline.write_uniform(&32768);
     let input: [f32; N as usize] = array::from_fn(|i| i as f32 / 300.0);
     let cpu = numerical_integration_cpu(&input);
     pipeline.run(&input, [(N_WORKGROUP, 1, 1)], |outputs| {
-        assert_eq!(cpu.len(), outputs.len());
-        for (a, b) in outputs.iter().zip(cpu) {
-            assert!((a - b).abs() < 0.00001, "{} != {}", a, b);
-        }
+        assert_all_close(outputs, &cpu, Tolerance::ulps(4).with_abs(1e-5));
     })
 }
+
+#[test]
+fn clone_for_thread_concurrent() {
+    const N: usize = 256;
+    let shader = "
+        @group(0) @binding(0) var<uniform> coefficient: u32;
+        @group(0) @binding(1) var<storage, read> in: array<u32>;
+        @group(0) @binding(2) var<storage, read_write> out: array<u32>;
+
+        @compute
+        @workgroup_size(64, 1, 1)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = coefficient * in[id.x];
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let pipeline = gpu.gen_pipeline::<[u32; N], u32, [u32; N], 1>(
+        None,
+        [StageDesc::new(shader, "main").with_name("scale")],
+    );
+    std::thread::scope(|s| {
+        for thread in 0..4u32 {
+            let mut pipeline = pipeline.clone_for_thread();
+            s.spawn(move || {
+                pipeline.write_uniform(&(thread + 1));
+                for run in 0..8u32 {
+                    let input = array::from_fn(|i| i as u32 + run);
+                    let output = pipeline.run(&input, [(N as u32 / 64, 1, 1)], |out| *out);
+                    assert_eq!(output, input.map(|v| v * (thread + 1)));
+                }
+            });
+        }
+    });
+}
+
+#[test]
+fn pipeline_pool_bounded() {
+    let shader = "
+        @group(0) @binding(0) var<storage, read> in: array<u32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+        @compute
+        @workgroup_size(4, 1, 1)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] * 2u;
+        }
+    ";
+    let gpu = pollster::block_on(GpuComputeAsync::new());
+    let pipeline = pollster::block_on(gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(
+        None,
+        [StageDesc::new(shader, "main").with_name("double")],
+    ));
+    let pool = pipeline.into_pool(2);
+    assert_eq!(pool.size(), 2);
+
+    let first = pool.try_acquire().unwrap();
+    let mut second = pollster::block_on(pool.acquire());
+    assert!(pool.try_acquire().is_none());
+    assert_eq!(
+        pollster::block_on(second.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out)),
+        [2, 4, 6, 8]
+    );
+    drop(first);
+    assert_eq!(pool.available(), 1);
+    drop(second);
+    assert_eq!(pool.available(), 2);
+}
+
+#[test]
+fn failover_keeps_uniform() {
+    let shader = "
+        @group(0) @binding(0) var<uniform> offset: u32;
+        @group(0) @binding(1) var<storage, read> in: array<u32>;
+        @group(0) @binding(2) var<storage, read_write> out: array<u32>;
+
+        @compute
+        @workgroup_size(4, 1, 1)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] + offset;
+        }
+    ";
+    let gpu = sgpu_compute::failover::FailoverGpu::try_new().unwrap();
+    let mut pipeline = gpu.gen_pipeline::<[u32; 4], u32, [u32; 4], 1>(
+        None,
+        [StageDesc::new(shader, "main").with_name("offset")],
+    );
+    pipeline.write_uniform(&10);
+    assert_eq!(
+        pipeline.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out),
+        [11, 12, 13, 14]
+    );
+    assert!(!pipeline.is_on_standby());
+
+    gpu.force_failover();
+    assert!(gpu.is_primary_lost());
+    assert_eq!(
+        pipeline.try_run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out),
+        Ok([11, 12, 13, 14])
+    );
+    assert!(pipeline.is_on_standby());
+}
+
+#[test]
+fn scope_cancels_unjoined_runs() {
+    let shader = "
+        @group(0) @binding(0) var<storage, read> in: array<u32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+        @compute
+        @workgroup_size(4, 1, 1)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] + 1u;
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let mut first = gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(
+        None,
+        [StageDesc::new(shader, "main").with_name("increment")],
+    );
+    let mut second = first.clone_for_thread();
+    let joined = gpu.scope(|s| {
+        let joined = s.spawn_run(&mut first, &[1, 2, 3, 4], [(1, 1, 1)], |out| *out);
+        let cancelled = s.spawn_run(&mut second, &[5, 6, 7, 8], [(1, 1, 1)], |_| {
+            panic!("The callback of a cancelled run must not be called")
+        });
+        drop(cancelled);
+        pollster::block_on(joined.join())
+    });
+    assert_eq!(joined, [2, 3, 4, 5]);
+    assert_eq!(
+        second.run(&[0, 0, 0, 0], [(1, 1, 1)], |out| *out),
+        [1, 1, 1, 1]
+    );
+}
+
+#[test]
+fn worker_runs_in_order() {
+    let shader = "
+        @group(0) @binding(0) var<uniform> offset: u32;
+        @group(0) @binding(1) var<storage, read> in: array<u32>;
+        @group(0) @binding(2) var<storage, read_write> out: array<u32>;
+
+        @compute
+        @workgroup_size(4, 1, 1)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] + offset;
+        }
+    ";
+    let worker = sgpu_compute::worker::GpuWorker::new();
+    let pipeline = worker.gen_pipeline::<[u32; 4], u32, [u32; 4], 1>(
+        None,
+        [StageDesc::new(shader, "main").with_name("offset")],
+    );
+    let pending = (0..4u32)
+        .map(|offset| {
+            pipeline.write_uniform(&offset);
+            pipeline.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out)
+        })
+        .collect::<Vec<_>>();
+    for (offset, pending) in (0..4u32).zip(pending) {
+        assert_eq!(pending.wait(), [1, 2, 3, 4].map(|v| v + offset));
+    }
+    let queued = pipeline.run(&[0; 4], [(1, 1, 1)], |out| *out);
+    drop(pipeline);
+    assert_eq!(queued.wait(), [3; 4]);
+}
+
+#[test]
+fn worker_survives_a_failed_generation() {
+    let shader = "
+        @group(0) @binding(0) var<storage, read> in: array<u32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+        @compute @workgroup_size(4)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] * 2u;
+        }
+    ";
+    let typo = StageDesc::new(
+        "@compute @workgroup_size(1) fn main() { let x: u32 = 1.0; }",
+        "main",
+    )
+    .with_name("typo");
+    let worker = sgpu_compute::worker::GpuWorker::new();
+    let error = worker
+        .try_gen_pipeline::<(), (), [u32; 4], 1>(None, [typo.clone()])
+        .err()
+        .expect("The shader is invalid");
+    assert!(matches!(error, SgpuError::InvalidShader(_)), "{}", error);
+
+    let broken = worker.gen_pipeline::<(), (), [u32; 4], 1>(None, [typo]);
+    let pipeline = worker.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(
+        None,
+        [StageDesc::new(shader, "main").with_name("double")],
+    );
+    let failed = broken.run(&(), [(1, 1, 1)], |out| *out);
+    assert!(matches!(
+        failed.try_wait(),
+        Err(SgpuError::InvalidShader(_))
+    ));
+    assert_eq!(
+        pipeline.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out).wait(),
+        [2, 4, 6, 8]
+    );
+}
+
+#[test]
+fn worker_runs_high_priority_first() {
+    use sgpu_compute::worker::{GpuWorker, Priority};
+    use std::sync::{Arc, Mutex};
+
+    let shader = "
+        @group(0) @binding(0) var<storage, read> in: array<u32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+        @compute @workgroup_size(4)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] + 1u;
+        }
+    ";
+    let stages = [StageDesc::new(shader, "main")];
+    let worker = GpuWorker::new();
+    let mut background = worker.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(None, stages.clone());
+    background.set_priority(Priority::Low);
+    let mut interactive = worker.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(None, stages);
+    interactive.set_priority(Priority::High);
+    assert_eq!(interactive.priority(), Priority::High);
+
+    // The callback of the first run blocks the worker until everything else is queued.
+    let (release, blocked) = flume::bounded::<()>(0);
+    let order = Arc::new(Mutex::new(Vec::new()));
+    let first = background.run(&[0; 4], [(1, 1, 1)], move |out| {
+        blocked.recv().unwrap();
+        out[0]
+    });
+    let record = |name: &'static str| {
+        let order = Arc::clone(&order);
+        move |out: &[u32; 4]| {
+            order.lock().unwrap().push(name);
+            out[0]
+        }
+    };
+    let low = (0..2)
+        .map(|_| background.run(&[1; 4], [(1, 1, 1)], record("low")))
+        .collect::<Vec<_>>();
+    let high = interactive.run(&[2; 4], [(1, 1, 1)], record("high"));
+    release.send(()).unwrap();
+
+    assert_eq!(first.wait(), 1);
+    assert_eq!(high.wait(), 3);
+    assert!(low.into_iter().all(|pending| pending.wait() == 2));
+    assert_eq!(*order.lock().unwrap(), ["high", "low", "low"]);
+}
+
+#[test]
+fn worker_raises_the_priority_of_earlier_commands() {
+    use sgpu_compute::worker::{GpuWorker, Priority};
+    use std::sync::{Arc, Mutex};
+
+    let shader = "
+        @group(0) @binding(0) var<storage, read> in: array<u32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+        @compute @workgroup_size(4)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] + 1u;
+        }
+    ";
+    let stages = [StageDesc::new(shader, "main")];
+    let worker = GpuWorker::new();
+    let mut pipeline = worker.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(None, stages.clone());
+    let other = worker.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(None, stages);
+
+    // The callback of the first run blocks the worker until everything else is queued.
+    let (release, blocked) = flume::bounded::<()>(0);
+    let order = Arc::new(Mutex::new(Vec::new()));
+    let first = other.run(&[0; 4], [(1, 1, 1)], move |out| {
+        blocked.recv().unwrap();
+        out[0]
+    });
+    let record = |name: &'static str| {
+        let order = Arc::clone(&order);
+        move |out: &[u32; 4]| {
+            order.lock().unwrap().push(name);
+            out[0]
+        }
+    };
+    let normal = other.run(&[1; 4], [(1, 1, 1)], record("normal"));
+    pipeline.set_priority(Priority::Low);
+    let low = pipeline.run(&[2; 4], [(1, 1, 1)], record("low"));
+    pipeline.set_priority(Priority::High);
+    let high = pipeline.run(&[3; 4], [(1, 1, 1)], record("high"));
+    release.send(()).unwrap();
+
+    assert_eq!(first.wait(), 1);
+    assert_eq!(high.wait(), 4);
+    assert_eq!(low.wait(), 3);
+    assert_eq!(normal.wait(), 2);
+    // The low-priority run is executed before the high-priority one of its pipeline, and so before the other pipeline.
+    assert_eq!(*order.lock().unwrap(), ["low", "high", "normal"]);
+}
+
+#[test]
+fn worker_polls_its_own_device() {
+    use sgpu_compute::worker::GpuWorker;
+
+    let shader = "
+        @group(0) @binding(0) var<storage, read> in: array<u32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+        @compute @workgroup_size(4)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] + 1u;
+        }
+    ";
+    // Nobody can poll the device of the worker on demand, the runs would never complete.
+    let worker = GpuWorker::with_options(GpuComputeOptions {
+        poll_strategy: PollStrategy::OnDemand,
+        max_passes_per_submission: NonZeroUsize::new(1),
+        ..Default::default()
+    });
+    let stage = StageDesc::new(shader, "main");
+    let mut pipeline =
+        worker.gen_pipeline::<[u32; 4], (), [u32; 4], 2>(None, [stage.clone(), stage]);
+    pipeline.set_priority(sgpu_compute::worker::Priority::Low);
+    assert_eq!(
+        pipeline
+            .run(&[1, 2, 3, 4], [(1, 1, 1); 2], |out| *out)
+            .wait(),
+        [2, 3, 4, 5]
+    );
+}
+
+#[test]
+fn worker_preempts_split_low_priority_runs() {
+    use sgpu_compute::worker::{GpuWorker, Priority};
+    use std::sync::{Arc, Mutex};
+
+    // Each stage is dispatched over many workgroups which do nothing but the first one, so the low-priority run is still running when the high-priority one is queued.
+    let slow = "
+        @group(0) @binding(0) var<storage, read> in: array<u32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+        @compute @workgroup_size(64)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            if id.x < 4u && id.y == 0u {
+                out[id.x] += in[id.x];
+            }
+        }
+    ";
+    let fast = "
+        @group(0) @binding(0) var<storage, read> in: array<u32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+        @compute @workgroup_size(4)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] + 1u;
+        }
+    ";
+    let stage = |shader| StageDesc::new(shader, "main");
+    // One pass per submission, so the high-priority run can go between any two stages of the low-priority one.
+    let worker = GpuWorker::with_options(GpuComputeOptions {
+        max_passes_per_submission: NonZeroUsize::new(1),
+        ..Default::default()
+    });
+    let mut background = worker
+        .gen_pipeline::<[u32; 4], (), [u32; 4], 8>(None, std::array::from_fn(|_| stage(slow)));
+    background.set_priority(Priority::Low);
+    let mut interactive = worker.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(None, [stage(fast)]);
+    interactive.set_priority(Priority::High);
+    // Both pipelines are compiled once these runs are done.
+    assert_eq!(
+        interactive.run(&[0; 4], [(1, 1, 1)], |out| out[0]).wait(),
+        1
+    );
+    assert_eq!(
+        background.run(&[0; 4], [(1, 1, 1); 8], |out| out[0]).wait(),
+        0
+    );
+
+    let order = Arc::new(Mutex::new(Vec::new()));
+    let record = |name: &'static str| {
+        let order = Arc::clone(&order);
+        move |out: &[u32; 4]| {
+            order.lock().unwrap().push(name);
+            *out
+        }
+    };
+    let low = background.run(&[1, 2, 3, 4], [(1024, 128, 1); 8], record("low"));
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    let high = interactive.run(&[2; 4], [(1, 1, 1)], record("high"));
+    assert_eq!(high.wait(), [3; 4]);
+    // The stages of the split run still run in order on the same buffers.
+    assert_eq!(low.wait(), [8, 16, 24, 32]);
+    assert_eq!(*order.lock().unwrap(), ["high", "low"]);
+}
+
+#[test]
+#[cfg_attr(debug_assertions, should_panic(expected = "Invalid input: NaN at 2"))]
+fn input_validator_rejects_nan() {
+    let shader = "
+        @group(0) @binding(0) var<storage, read> in: array<f32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<f32>;
+
+        @compute
+        @workgroup_size(4, 1, 1)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x];
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[f32; 4], (), [f32; 4], 1>(
+        None,
+        [StageDesc::new(shader, "main").with_name("copy")],
+    );
+    pipeline.set_input_validator(|input| match input.iter().position(|v| v.is_nan()) {
+        Some(i) => Err(format!("NaN at {}", i)),
+        None => Ok(()),
+    });
+    let valid = [1.0, 2.0, 3.0, 4.0];
+    assert_eq!(pipeline.run(&valid, [(1, 1, 1)], |out| *out), valid);
+    pipeline.run(&[0.0, 1.0, f32::NAN, 3.0], [(1, 1, 1)], |_| ());
+}
+
+#[test]
+fn provider_used_by_rebuild() {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+    let calls = Arc::new(AtomicUsize::new(0));
+    let provider = {
+        let calls = Arc::clone(&calls);
+        move |key: &str| {
+            calls.fetch_add(1, Ordering::Relaxed);
+            format!(
+                "
+                @group(0) @binding(0) var<storage, read> in: array<u32>;
+                @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+                @compute
+                @workgroup_size(4, 1, 1)
+                fn main(@builtin(global_invocation_id) id: vec3<u32>) {{
+                    out[id.x] = in[id.x] + {}u;
+                }}
+                ",
+                key.len()
+            )
+        }
+    };
+    let gpu = GpuCompute::new();
+    let pipeline = gpu.gen_pipeline_with_provider::<[u32; 4], (), [u32; 4], 1>(
+        provider,
+        None,
+        [StageDesc::new("abc", "main").with_name("offset")],
+    );
+    assert_eq!(calls.load(Ordering::Relaxed), 1);
+    let mut rebuilt = pipeline.rebuild_on(&gpu).unwrap();
+    assert_eq!(calls.load(Ordering::Relaxed), 2);
+    assert_eq!(
+        rebuilt.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out),
+        [4, 5, 6, 7]
+    );
+}
+
+#[test]
+fn describe_reports_bindings() {
+    use sgpu_compute::describe::{BindingKind, BufferRole};
+    let shader = "
+        @group(0) @binding(0) var<uniform> offset: u32;
+        @group(0) @binding(1) var<storage, read_write> scratchpad: array<u32>;
+        @group(0) @binding(2) var<storage, read> in: array<f32>;
+        @group(0) @binding(3) var<storage, read_write> out: array<f32>;
+
+        @compute
+        @workgroup_size(8, 2, 1)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            scratchpad[id.x % 4u] = offset;
+            out[id.x] = in[id.x];
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let pipeline = gpu.gen_pipeline::<[f32; 1000], u32, [f32; 1000], 1>(
+        NonZeroUsize::new(16),
+        [StageDesc::new(shader, "main").with_name("copy")],
+    );
+    let description = pipeline.describe();
+    let roles = description
+        .buffers
+        .iter()
+        .map(|buffer| (buffer.role, buffer.size))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        roles,
+        [
+            (BufferRole::Uniform, 4),
+            (BufferRole::Scratchpad, 16),
+            (BufferRole::Input, 4000),
+            (BufferRole::Output, 4000)
+        ]
+    );
+    let stage = &description.stages[0];
+    assert_eq!(stage.workgroup_size, Some([8, 2, 1]));
+    let kinds = stage
+        .bindings
+        .iter()
+        .map(|binding| (binding.binding, binding.kind))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        [
+            (0, BindingKind::Uniform),
+            (1, BindingKind::Storage { read_only: false }),
+            (2, BindingKind::Storage { read_only: true }),
+            (3, BindingKind::Storage { read_only: false })
+        ]
+    );
+    assert!(description.to_string().contains("stage 0 `copy`"));
+}
+
+#[test]
+fn trace_records_runs() {
+    use sgpu_compute::trace::Trace;
+    use std::sync::Arc;
+    let shader = "
+        @group(0) @binding(0) var<storage, read> in: array<u32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+        @compute
+        @workgroup_size(4, 1, 1)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] * 2u;
+        }
+    ";
+    let trace = Arc::new(Trace::new());
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(
+        None,
+        [StageDesc::new(shader, "main").with_name("double")],
+    );
+    pipeline.set_trace(Arc::clone(&trace));
+    let mut clone = pipeline.clone_for_thread();
+    for _ in 0..3 {
+        pipeline.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out);
+    }
+    std::thread::scope(|s| {
+        s.spawn(|| clone.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out));
+    });
+    // upload, submit, map and callback, plus one span per stage with timestamp queries.
+    assert!(trace.len() == 16 || trace.len() == 20);
+    let len = trace.len();
+    pipeline
+        .run_with_timeout(
+            &[1, 2, 3, 4],
+            [(1, 1, 1)],
+            std::time::Duration::from_secs(60),
+            |out| *out,
+        )
+        .unwrap();
+    assert_eq!(trace.len(), len + len / 4);
+
+    let mut json = Vec::new();
+    trace.write_chrome_trace_to(&mut json).unwrap();
+    let json = String::from_utf8(json).unwrap();
+    assert!(json.starts_with("{\"traceEvents\":[") && json.ends_with("]}"));
+    assert_eq!(json.matches("\"name\":\"map\"").count(), 5);
+    assert!(json.contains("\"args\":{\"name\":\"CPU 2\"}"));
+}
+
+#[test]
+fn poll_strategies() {
+    use std::time::Duration;
+    let shader = "
+        @group(0) @binding(0) var<storage, read> in: array<u32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+        @compute
+        @workgroup_size(4, 1, 1)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] + 1u;
+        }
+    ";
+    let stage = StageDesc::new(shader, "main").with_name("increment");
+
+    {
+        let background = GpuCompute::with_options(GpuComputeOptions {
+            poll_strategy: PollStrategy::Background(Duration::from_millis(1)),
+            ..Default::default()
+        });
+        let mut pipeline =
+            background.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(None, [stage.clone()]);
+        assert_eq!(
+            pipeline.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out),
+            [2, 3, 4, 5]
+        );
+    }
+
+    let on_demand = GpuCompute::with_options(GpuComputeOptions {
+        poll_strategy: PollStrategy::OnDemand,
+        ..Default::default()
+    });
+    let mut pipeline = on_demand.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(None, [stage]);
+    let output = std::thread::scope(|s| {
+        let run = s.spawn(|| pipeline.run(&[5, 6, 7, 8], [(1, 1, 1)], |out| *out));
+        while !run.is_finished() {
+            on_demand.poll();
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        run.join().unwrap()
+    });
+    assert_eq!(output, [6, 7, 8, 9]);
+}
+
+#[test]
+fn run_labeled_in_trace() {
+    use sgpu_compute::trace::Trace;
+    use std::sync::Arc;
+    let shader = "
+        @group(0) @binding(0) var<storage, read> in: array<u32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+        @compute
+        @workgroup_size(4, 1, 1)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] * 3u;
+        }
+    ";
+    let trace = Arc::new(Trace::new());
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(
+        None,
+        [StageDesc::new(shader, "main").with_name("triple")],
+    );
+    pipeline.set_trace(Arc::clone(&trace));
+    let output = pipeline.run_labeled("frame 123", &[1, 2, 3, 4], [(1, 1, 1)], |out| *out);
+    assert_eq!(output, [3, 6, 9, 12]);
+    pipeline.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out);
+
+    let mut json = Vec::new();
+    trace.write_chrome_trace_to(&mut json).unwrap();
+    let json = String::from_utf8(json).unwrap();
+    assert!(json.contains("\"name\":\"submit (frame 123)\""));
+    assert!(json.contains("\"name\":\"callback (frame 123)\""));
+    assert!(json.contains("\"name\":\"submit\""));
+}
+
+#[test]
+fn zero_init_clears_state() {
+    let shader = "
+        @group(0) @binding(0) var<storage, read_write> scratchpad: array<u32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+        @compute
+        @workgroup_size(4, 1, 1)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            scratchpad[id.x] += 1u;
+            out[id.x] += scratchpad[id.x];
+        }
+    ";
+    let stages = [StageDesc::new(shader, "main").with_name("accumulate")];
+    let gpu = GpuCompute::with_options(GpuComputeOptions {
+        zero_init: ZeroInit::BeforeEachRun,
+        ..Default::default()
+    });
+    let mut pipeline =
+        gpu.gen_pipeline::<(), (), [u32; 4], 1>(NonZeroUsize::new(16), stages.clone());
+    assert_eq!(pipeline.run(&(), [(1, 1, 1)], |out| *out), [1; 4]);
+    assert_eq!(pipeline.run(&(), [(1, 1, 1)], |out| *out), [1; 4]);
+
+    pipeline.set_zero_init(ZeroInit::Never);
+    assert_eq!(pipeline.run(&(), [(1, 1, 1)], |out| *out), [3; 4]);
+    pipeline.set_zero_init(ZeroInit::AtCreation);
+    assert_eq!(pipeline.run(&(), [(1, 1, 1)], |out| *out), [1; 4]);
+    assert_eq!(pipeline.run(&(), [(1, 1, 1)], |out| *out), [3; 4]);
+}
+
+#[test]
+fn stages_split_across_submissions() {
+    let shader = "
+        @group(0) @binding(0) var<storage, read> in: array<u32, 4>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32, 4>;
+
+        @compute @workgroup_size(4)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = out[id.x] * 2u + in[id.x];
+        }
+    ";
+    let stages: [StageDesc; 10] = std::array::from_fn(|_| StageDesc::new(shader, "main"));
+    // Runs are only split when it is asked for.
+    assert_eq!(GpuComputeOptions::default().max_passes_per_submission, None);
+    for max_passes in [None, NonZeroUsize::new(1), NonZeroUsize::new(3)] {
+        let gpu = GpuCompute::with_options(GpuComputeOptions {
+            zero_init: ZeroInit::BeforeEachRun,
+            max_passes_per_submission: max_passes,
+            ..Default::default()
+        });
+        assert_eq!(gpu.max_passes_per_submission(), max_passes);
+        let mut pipeline = gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 10>(None, stages.clone());
+        // The stages run in order, each one doubling the output of the previous one.
+        assert_eq!(
+            pipeline.run(&[1, 0, 2, 1], [(1, 1, 1); 10], |out| *out),
+            [1023, 0, 2046, 1023]
+        );
+    }
+}
+
+#[test]
+fn access_mismatch_fails_early() {
+    let shader = "
+        @group(0) @binding(0) var<storage, read_write> in: array<u32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+        @compute
+        @workgroup_size(4, 1, 1)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            in[id.x] += 1u;
+            out[id.x] = in[id.x];
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let error = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(
+            None,
+            [StageDesc::new(shader, "main").with_name("increment")],
+        );
+    }))
+    .unwrap_err();
+    let message = error.downcast_ref::<String>().unwrap();
+    assert!(message.starts_with("layout mismatch"));
+    assert!(message.contains("`in` as var<storage, read_write>"));
+    assert!(message.contains("Input buffer, which must be declared as var<storage, read>"));
+}
+
+#[test]
+fn size_mismatch_fails_early() {
+    let shader = "
+        struct Parameters { scale: vec4<f32> }
+
+        @group(0) @binding(0) var<uniform> parameters: Parameters;
+        @group(0) @binding(1) var<storage, read> in: array<f32, 8>;
+        @group(0) @binding(2) var<storage, read_write> out: array<f32>;
+
+        @compute
+        @workgroup_size(4, 1, 1)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] * parameters.scale.x;
+        }
+    ";
+    let stage = StageDesc::new(shader, "main").with_name("scale");
+    let gpu = GpuCompute::new();
+    let error = gpu
+        .try_gen_pipeline::<[f32; 4], f32, [f32; 4], 1>(None, [stage.clone()])
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(error.contains(
+        "`parameters` with 16 bytes but the Uniform buffer has 4 bytes, the size of `Uniform`"
+    ));
+    assert!(
+        error.contains("`in` with 32 bytes but the Input buffer has 16 bytes, the size of `Input`")
+    );
+    // A runtime-sized output fits any output of at least one element, and a uniform may be padded.
+    let pipeline = gpu
+        .try_gen_pipeline::<[f32; 8], [f32; 8], [f32; 4], 1>(None, [stage])
+        .unwrap();
+    let bindings = &pipeline.describe().stages[0].bindings;
+    assert_eq!(
+        (bindings[1].min_size, bindings[1].runtime_sized),
+        (32, false)
+    );
+    assert_eq!((bindings[2].min_size, bindings[2].runtime_sized), (4, true));
+}
+
+#[test]
+fn replace_stage_keeps_buffers() {
+    let accumulate = "
+        @group(0) @binding(0) var<storage, read_write> scratchpad: array<u32>;
+        @group(0) @binding(1) var<storage, read> in: array<u32>;
+        @group(0) @binding(2) var<storage, read_write> out: array<u32>;
+
+        @compute
+        @workgroup_size(4, 1, 1)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            scratchpad[id.x] += in[id.x];
+        }
+    ";
+    let write = |factor| {
+        format!(
+            "
+            @group(0) @binding(0) var<storage, read_write> scratchpad: array<u32>;
+            @group(0) @binding(2) var<storage, read_write> out: array<u32>;
+
+            @compute
+            @workgroup_size(4, 1, 1)
+            fn main(@builtin(global_invocation_id) id: vec3<u32>) {{
+                out[id.x] = {}u * scratchpad[id.x];
+            }}
+            ",
+            factor
+        )
+    };
+    let shaders: &'static [String] = Vec::leak(vec![write(1), write(10)]);
+    let stage = |name, shader| StageDesc::new(shader, "main").with_name(name);
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 2>(
+        NonZeroUsize::new(16),
+        [stage("accumulate", accumulate), stage("write", &shaders[0])],
+    );
+    let workgroups = [(1, 1, 1); 2];
+    assert_eq!(
+        pipeline.run(&[1, 2, 3, 4], workgroups, |out| *out),
+        [1, 2, 3, 4]
+    );
+    let mut clone = pipeline.clone_for_thread();
+
+    pipeline.replace_stage(1, stage("write_scaled", &shaders[1]));
+    assert_eq!(pipeline.describe().stages[1].name, Some("write_scaled"));
+    assert_eq!(
+        pipeline.run(&[1, 2, 3, 4], workgroups, |out| *out),
+        [20, 40, 60, 80]
+    );
+    assert_eq!(
+        clone.run(&[1, 1, 1, 1], workgroups, |out| *out),
+        [1, 1, 1, 1]
+    );
+
+    // A rejected stage leaves the pipeline unchanged.
+    assert!(matches!(
+        pipeline.try_replace_stage(2, stage("write", &shaders[0])),
+        Err(SgpuError::Validation(_))
+    ));
+    assert!(matches!(
+        pipeline.try_replace_stage(1, stage("typo", "fn main( {}")),
+        Err(SgpuError::InvalidShader(_))
+    ));
+    // Valid for naga, but the device wasn't created with the SHADER_F64 feature.
+    let f64_shader = "
+        @group(0) @binding(0) var<storage, read_write> scratchpad: array<u32>;
+        @group(0) @binding(2) var<storage, read_write> out: array<u32>;
+
+        @compute
+        @workgroup_size(4, 1, 1)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            let value: f64 = f64(scratchpad[id.x]);
+            out[id.x] = u32(value);
+        }
+    ";
+    assert!(matches!(
+        pipeline.try_replace_stage(1, stage("f64", f64_shader)),
+        Err(SgpuError::ShaderCompilation(_))
+    ));
+    assert_eq!(pipeline.describe().stages[1].name, Some("write_scaled"));
+    assert_eq!(
+        pipeline.run(&[1, 2, 3, 4], workgroups, |out| *out),
+        [30, 60, 90, 120]
+    );
+}
+
+#[test]
+fn shared_uniform_updates_all_pipelines() {
+    let add = "
+        @group(0) @binding(0) var<storage, read> in: array<u32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+        @group(1) @binding(0) var<uniform> frame: u32;
+
+        @compute @workgroup_size(4)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] + frame;
+        }
+    ";
+    let mul = "
+        @group(0) @binding(0) var<uniform> factor: u32;
+        @group(0) @binding(1) var<storage, read> in: array<u32>;
+        @group(0) @binding(2) var<storage, read_write> out: array<u32>;
+        @group(1) @binding(0) var<uniform> frame: u32;
+
+        @compute @workgroup_size(4)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] * factor * frame;
+        }
+    ";
+    let gpu = GpuCompute::new();
+    gpu.write_shared_uniform(&1u32);
+    let mut add =
+        gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(None, [StageDesc::new(add, "main")]);
+    let mut mul =
+        gpu.gen_pipeline::<[u32; 4], u32, [u32; 4], 1>(None, [StageDesc::new(mul, "main")]);
+    mul.write_uniform(&2);
+    let input = [1, 2, 3, 4];
+    assert_eq!(add.run(&input, [(1, 1, 1)], |out| *out), [2, 3, 4, 5]);
+    assert_eq!(mul.run(&input, [(1, 1, 1)], |out| *out), [2, 4, 6, 8]);
+
+    gpu.write_shared_uniform(&3u32);
+    assert_eq!(add.run(&input, [(1, 1, 1)], |out| *out), [4, 5, 6, 7]);
+    assert_eq!(mul.run(&input, [(1, 1, 1)], |out| *out), [6, 12, 18, 24]);
+}
+
+#[test]
+fn from_existing_device() {
+    let instance = wgpu::Instance::default();
+    let adapter =
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+            .unwrap();
+    let (device, queue) = pollster::block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            label: None,
+            required_features: wgpu::Features::empty(),
+            required_limits: wgpu::Limits::downlevel_defaults(),
+            memory_hints: wgpu::MemoryHints::default(),
+        },
+        None,
+    ))
+    .unwrap();
+    let device = std::sync::Arc::new(device);
+    let gpu = GpuCompute::from_device(std::sync::Arc::clone(&device), queue);
+    assert!(gpu.adapter_info().is_none());
+    let shader = "
+        @group(0) @binding(0) var<storage, read> in: array<u32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+        @compute @workgroup_size(4)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] * in[id.x];
+        }
+    ";
+    let mut pipeline =
+        gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(None, [StageDesc::new(shader, "main")]);
+    assert_eq!(
+        pipeline.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out),
+        [1, 4, 9, 16]
+    );
+    // The renderer keeps using the device.
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: 16,
+        usage: wgpu::BufferUsages::VERTEX,
+        mapped_at_creation: false,
+    });
+    assert_eq!(buffer.size(), 16);
+}
+
+#[test]
+fn change_detection_skips_unchanged_outputs() {
+    let shader = "
+        @group(0) @binding(0) var<uniform> threshold: f32;
+        @group(0) @binding(1) var<storage, read> in: array<f32>;
+        @group(0) @binding(2) var<storage, read_write> out: array<u32>;
+
+        @compute @workgroup_size(64)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = u32(in[id.x] > threshold);
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let mut pipeline =
+        gpu.gen_pipeline::<[f32; 256], f32, [u32; 256], 1>(None, [StageDesc::new(shader, "main")]);
+    let input: [f32; 256] = std::array::from_fn(|i| i as f32);
+    let workgroups = [(4, 1, 1)];
+    pipeline.write_uniform(&100.5);
+    // Without change detection, every run reads back.
+    assert!(pipeline
+        .run_if_changed(&input, workgroups, |_| ())
+        .is_some());
+    assert!(pipeline
+        .run_if_changed(&input, workgroups, |_| ())
+        .is_some());
+
+    pipeline.set_change_detection(true);
+    let count = |out: &[u32; 256]| out.iter().sum::<u32>();
+    assert_eq!(
+        pipeline.run_if_changed(&input, workgroups, count),
+        Some(155)
+    );
+    assert_eq!(pipeline.run_if_changed(&input, workgroups, count), None);
+    pipeline.write_uniform(&100.7);
+    assert_eq!(pipeline.run_if_changed(&input, workgroups, count), None);
+    pipeline.write_uniform(&199.5);
+    assert_eq!(pipeline.run_if_changed(&input, workgroups, count), Some(56));
+
+    let mut clone = pipeline.clone_for_thread();
+    clone.write_uniform(&199.5);
+    assert_eq!(clone.run_if_changed(&input, workgroups, count), Some(56));
+    assert_eq!(clone.run_if_changed(&input, workgroups, count), None);
+}
+
+#[test]
+fn raw_handles_copy_output_without_readback() {
+    let shader = "
+        @group(0) @binding(0) var<storage, read> in: array<u32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+        @compute @workgroup_size(4)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] * 2u;
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let mut pipeline =
+        gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(None, [StageDesc::new(shader, "main")]);
+    assert!(pipeline.uniform_buffer().is_none());
+    assert!(pipeline.scratchpad_buffer().is_none());
+    assert_eq!(
+        pipeline.input_buffer().map(|buffer| buffer.size()),
+        Some(16)
+    );
+    pipeline.run(&[1, 2, 3, 4], [(1, 1, 1)], |_| ());
+
+    // Copy the output into a buffer owned by the caller with its own encoder.
+    let device = gpu.device();
+    let target = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: 16,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    encoder.copy_buffer_to_buffer(pipeline.output_buffer(), 0, &target, 0, 16);
+    gpu.queue().submit(Some(encoder.finish()));
+    target
+        .slice(..)
+        .map_async(wgpu::MapMode::Read, |res| res.unwrap());
+    device.poll(wgpu::Maintain::Wait);
+    let copied: [u32; 4] = bytemuck::pod_read_unaligned(&target.slice(..).get_mapped_range());
+    assert_eq!(copied, [2, 4, 6, 8]);
+}
+
+#[test]
+fn passes_encoded_into_an_external_encoder() {
+    let shader = "
+        @group(0) @binding(0) var<storage, read> in: array<u32, 4>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32, 4>;
+
+        @compute @workgroup_size(4)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] += in[id.x];
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let pipeline =
+        gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(None, [StageDesc::new(shader, "main")]);
+    let device = gpu.device();
+    let target = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: 16,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    gpu.queue().write_buffer(
+        pipeline.input_buffer().unwrap(),
+        0,
+        bytemuck::bytes_of(&[1u32, 2, 3, 4]),
+    );
+
+    // The passes are recorded twice in the same submission as a copy of the application.
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    pipeline.encode_passes(&mut encoder, [(1, 1, 1)]);
+    pipeline.encode_passes(&mut encoder, [(1, 1, 1)]);
+    encoder.copy_buffer_to_buffer(pipeline.output_buffer(), 0, &target, 0, 16);
+    gpu.queue().submit(Some(encoder.finish()));
+    target
+        .slice(..)
+        .map_async(wgpu::MapMode::Read, |res| res.unwrap());
+    device.poll(wgpu::Maintain::Wait);
+    let copied: [u32; 4] = bytemuck::pod_read_unaligned(&target.slice(..).get_mapped_range());
+    assert_eq!(copied, [2, 4, 6, 8]);
+}
+
+#[test]
+fn results_read_on_demand() {
+    let shader = "
+        @group(0) @binding(0) var<storage, read> in: array<u32, 4>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32, 4>;
+
+        @compute @workgroup_size(4)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] += in[id.x];
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let mut pipeline =
+        gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(None, [StageDesc::new(shader, "main")]);
+    for _ in 0..10 {
+        pipeline.run_no_readback(&[1, 2, 3, 4], [(1, 1, 1)]);
+    }
+    assert_eq!(pipeline.read_output(|out| *out), [10, 20, 30, 40]);
+    // Reading again doesn't run the stages.
+    assert_eq!(pipeline.read_output(|out| *out), [10, 20, 30, 40]);
+    assert_eq!(
+        pipeline.run(&[1, 1, 1, 1], [(1, 1, 1)], |out| *out),
+        [11, 21, 31, 41]
+    );
+}
+
+#[test]
+fn owned_pipeline_moves_into_thread() {
+    use sgpu_compute::blocking::OwnedPipeline;
+    use std::sync::Arc;
+
+    struct App {
+        gpu: Arc<GpuCompute>,
+        pipeline: OwnedPipeline<[u32; 4], u32, [u32; 4], 1>,
+    }
+
+    let shader = "
+        @group(0) @binding(0) var<uniform> offset: u32;
+        @group(0) @binding(1) var<storage, read> in: array<u32>;
+        @group(0) @binding(2) var<storage, read_write> out: array<u32>;
+
+        @compute @workgroup_size(4)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] + offset;
+        }
+    ";
+    let gpu = Arc::new(GpuCompute::new());
+    let pipeline = gpu.gen_pipeline_owned(None, [StageDesc::new(shader, "main")]);
+    let mut app = App { gpu, pipeline };
+    app.pipeline.write_uniform(&1);
+    assert_eq!(
+        app.pipeline.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out),
+        [2, 3, 4, 5]
+    );
+
+    let App { gpu, mut pipeline } = app;
+    let handle = std::thread::spawn(move || {
+        pipeline.write_uniform(&10);
+        pipeline.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out)
+    });
+    assert_eq!(handle.join().unwrap(), [11, 12, 13, 14]);
+    // The pipeline kept the device alive, this is the last reference.
+    assert_eq!(Arc::strong_count(&gpu), 1);
+}
+
+#[test]
+fn readback_into_vec_reuses_capacity() {
+    let shader = "
+        @group(0) @binding(0) var<uniform> scale: f32;
+        @group(0) @binding(1) var<storage, read> in: array<f32>;
+        @group(0) @binding(2) var<storage, read_write> out: array<f32>;
+
+        @compute @workgroup_size(64)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] * scale;
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let mut pipeline =
+        gpu.gen_pipeline::<[f32; 256], f32, [f32; 256], 1>(None, [StageDesc::new(shader, "main")]);
+    let input: [f32; 256] = std::array::from_fn(|i| i as f32);
+    let mut out = Vec::<f32>::new();
+    pipeline.write_uniform(&2.0);
+    pipeline.run_readback_into_vec(&input, [(4, 1, 1)], &mut out);
+    assert_eq!(out, input.map(|v| v * 2.0));
+    let (ptr, capacity) = (out.as_ptr(), out.capacity());
+
+    pipeline.write_uniform(&3.0);
+    pipeline.run_readback_into_vec(&input, [(4, 1, 1)], &mut out);
+    assert_eq!(out, input.map(|v| v * 3.0));
+    assert_eq!((out.as_ptr(), out.capacity()), (ptr, capacity));
+
+    // The output can also be read as wider elements.
+    let mut pairs = Vec::<[f32; 2]>::with_capacity(1024);
+    pipeline.run_readback_into_vec(&input, [(4, 1, 1)], &mut pairs);
+    assert_eq!(pairs.len(), 128);
+    assert_eq!(pairs[1], [6.0, 9.0]);
+}
+
+#[test]
+fn uniform_per_stage() {
+    let shader = "
+        @group(0) @binding(0) var<uniform> offset: u32;
+        @group(0) @binding(1) var<storage, read> in: array<u32>;
+        @group(0) @binding(2) var<storage, read_write> out: array<u32>;
+
+        @compute @workgroup_size(64)
+        fn first(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] + offset;
+        }
+
+        @compute @workgroup_size(64)
+        fn second(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = out[id.x] * offset;
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[u32; 64], u32, [u32; 64], 2>(
+        None,
+        [
+            StageDesc::new(shader, "first").with_name("first"),
+            StageDesc::new(shader, "second").with_name("second"),
+        ],
+    );
+    let input: [u32; 64] = std::array::from_fn(|i| i as u32);
+    pipeline.write_uniform(&2);
+    let out = pipeline.run(&input, [(1, 1, 1); 2], |out| *out);
+    assert_eq!(out, input.map(|v| (v + 2) * 2));
+
+    pipeline.write_uniform_for_stage(1, &10);
+    let out = pipeline.run(&input, [(1, 1, 1); 2], |out| *out);
+    assert_eq!(out, input.map(|v| (v + 2) * 10));
+
+    pipeline.write_uniform(&1);
+    let out = pipeline.run(&input, [(1, 1, 1); 2], |out| *out);
+    assert_eq!(out, input.map(|v| (v + 1) * 10));
+
+    pipeline.clear_uniform_for_stage(1);
+    let out = pipeline.run(&input, [(1, 1, 1); 2], |out| *out);
+    assert_eq!(out, input.map(|v| v + 1));
+}
+
+#[test]
+fn external_input_buffer() {
+    let shader = "
+        @group(0) @binding(0) var<storage, read> in: array<u32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+        @compute @workgroup_size(64)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] + 1u;
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let mut pipeline =
+        gpu.gen_pipeline::<[u32; 64], (), [u32; 64], 1>(None, [StageDesc::new(shader, "main")]);
+    let data: [u32; 64] = std::array::from_fn(|i| 10 * i as u32);
+    let external = gpu.device().create_buffer(&wgpu::BufferDescriptor {
+        label: Some("External"),
+        size: 256,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    gpu.queue()
+        .write_buffer(&external, 0, bytemuck::bytes_of(&data));
+    pipeline.set_input_buffer(external);
+    let out = pipeline.run_without_upload([(1, 1, 1)], |out| *out);
+    assert_eq!(out, data.map(|v| v + 1));
+
+    // `run` writes into the external buffer, which is kept by the next runs.
+    let input: [u32; 64] = std::array::from_fn(|i| i as u32);
+    pipeline.run(&input, [(1, 1, 1)], |_| ());
+    let out = pipeline.run_without_upload([(1, 1, 1)], |out| *out);
+    assert_eq!(out, input.map(|v| v + 1));
+    assert_eq!(pipeline.input_buffer().unwrap().size(), 256);
+}
+
+#[test]
+fn read_and_write_scratchpad() {
+    let shader = "
+        @group(0) @binding(0) var<uniform> dt: f32;
+        @group(0) @binding(1) var<storage, read_write> particles: array<vec2<f32>>;
+        @group(0) @binding(2) var<storage, read_write> out: array<f32>;
+
+        @compute @workgroup_size(32)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            let particle = particles[id.x];
+            particles[id.x] = vec2(particle.x + particle.y * dt, particle.y);
+            out[id.x] = particles[id.x].x;
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<(), f32, [f32; 32], 1>(
+        NonZeroUsize::new(32 * 8),
+        [StageDesc::new(shader, "main")],
+    );
+    let particles: [[f32; 2]; 32] = std::array::from_fn(|i| [i as f32, 1.0]);
+    pipeline.write_scratchpad(&particles);
+    pipeline.write_uniform(&0.5);
+    pipeline.run(&(), [(1, 1, 1)], |_| ());
+    pipeline.run(&(), [(1, 1, 1)], |_| ());
+    let state = pipeline.read_scratchpad::<[[f32; 2]; 32]>();
+    assert_eq!(state, particles.map(|[x, v]| [x + 1.0, v]));
+
+    // Only the beginning of the scratchpad can be read.
+    let first: [f32; 2] = pipeline.read_scratchpad();
+    assert_eq!(first, [1.0, 1.0]);
+}
+
+#[test]
+fn push_constants() {
+    let shader = "
+        struct Frame { index: u32, scale: f32 }
+        var<push_constant> frame: Frame;
+        @group(0) @binding(0) var<storage, read> in: array<f32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<f32>;
+
+        @compute @workgroup_size(64)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] * frame.scale + f32(frame.index);
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let stages = [StageDesc::new(shader, "main")];
+    let pipeline = gpu
+        .try_gen_pipeline_with_push_constants::<[f32; 64], (), [f32; 64], [u32; 2], 1>(
+            None, stages,
+        );
+    if !gpu.features().contains(wgpu::Features::PUSH_CONSTANTS) {
+        assert!(matches!(pipeline, Err(SgpuError::Unsupported(_))));
+        return;
+    }
+    let mut pipeline = pipeline.unwrap();
+    let input: [f32; 64] = std::array::from_fn(|i| i as f32);
+    for index in 0..3u32 {
+        pipeline.set_push_constants(&[index, 2.0f32.to_bits()]);
+        let out = pipeline.run(&input, [(1, 1, 1)], |out| *out);
+        assert_eq!(out, input.map(|v| v * 2.0 + index as f32));
+    }
+    // Clones keep the push constants.
+    let mut clone = pipeline.clone_for_thread();
+    let out = clone.run(&input, [(1, 1, 1)], |out| *out);
+    assert_eq!(out, input.map(|v| v * 2.0 + 2.0));
+}
@@ -0,0 +1,118 @@
+use sgpu_compute::kernels::intersect::{self, Aabb, AabbQuery, Hit, Ray, Triangle, TriangleQuery};
+use sgpu_compute::prelude::*;
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn triangle_cpu(ray: &Ray, tri: &Triangle) -> Option<f32> {
+    let e1 = sub(tri.b, tri.a);
+    let e2 = sub(tri.c, tri.a);
+    let p = cross(ray.direction, e2);
+    let det = dot(e1, p);
+    if det.abs() < 1e-8 {
+        return None;
+    }
+    let s = sub(ray.origin, tri.a);
+    let u = dot(s, p) / det;
+    let q = cross(s, e1);
+    let v = dot(ray.direction, q) / det;
+    let t = dot(e2, q) / det;
+    (u >= 0.0 && v >= 0.0 && u + v <= 1.0 && t >= ray.t_min && t <= ray.t_max).then_some(t)
+}
+
+fn rays<const R: usize>() -> [Ray; R] {
+    std::array::from_fn(|i| {
+        let x = (i % 8) as f32 / 2.0 - 2.0;
+        let y = (i / 8) as f32 / 2.0 - 2.0;
+        Ray::new([x, y, -5.0], [0.01 * x, -0.01 * y, 1.0])
+    })
+}
+
+#[test]
+fn ray_triangle_compare() {
+    const R: usize = 64;
+    let triangles = [
+        Triangle::new([-2.0, -2.0, 0.0], [2.0, -2.0, 0.0], [-2.0, 2.0, 0.0]),
+        Triangle::new([-2.0, -2.0, -1.0], [0.0, -2.0, -1.0], [-2.0, 0.0, -1.0]),
+        Triangle::new([0.0, 0.0, 3.0], [2.0, 0.0, 3.0], [0.0, 2.0, 3.0]),
+    ];
+    let query = TriangleQuery {
+        rays: rays::<R>(),
+        triangles,
+    };
+    let gpu = GpuCompute::new();
+    let mut pipeline =
+        gpu.gen_pipeline::<TriangleQuery<R, 3>, (), [Hit; R], 1>(None, [intersect::RAY_TRIANGLE]);
+    let hits = pipeline.run(&query, [intersect::workgroups(R)], |hits| *hits);
+    for (ray, hit) in query.rays.iter().zip(hits) {
+        let expected = triangles
+            .iter()
+            .enumerate()
+            .filter_map(|(i, tri)| triangle_cpu(ray, tri).map(|t| (i, t)))
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        match expected {
+            Some((i, t)) => {
+                assert_eq!(hit.primitive, i as u32, "{:?}", ray);
+                assert!((hit.distance - t).abs() < 1e-4, "{} != {}", hit.distance, t);
+            }
+            None => assert!(!hit.is_hit(), "{:?} {:?}", ray, hit),
+        }
+    }
+    assert!(hits.iter().any(Hit::is_hit));
+    assert!(hits.iter().any(|hit| !hit.is_hit()));
+}
+
+#[test]
+fn ray_aabb_closest() {
+    let query = AabbQuery {
+        rays: [
+            Ray::new([0.5, 0.5, -5.0], [0.0, 0.0, 1.0]),
+            Ray::new([0.5, 0.5, 0.5], [0.0, 0.0, 1.0]),
+            Ray::new([5.0, 5.0, -5.0], [0.0, 0.0, 1.0]),
+            Ray::new([-5.0, 0.5, 2.5], [1.0, 0.0, 0.0]),
+        ],
+        boxes: [
+            Aabb::new([0.0, 0.0, 2.0], [1.0, 1.0, 3.0]),
+            Aabb::new([0.0, 0.0, 0.0], [1.0, 1.0, 1.0]),
+        ],
+    };
+    let gpu = GpuCompute::new();
+    let mut pipeline =
+        gpu.gen_pipeline::<AabbQuery<4, 2>, (), [Hit; 4], 1>(None, [intersect::RAY_AABB]);
+    let hits = pipeline.run(&query, [intersect::workgroups(4)], |hits| *hits);
+    assert_eq!(
+        hits[0],
+        Hit {
+            distance: 5.0,
+            primitive: 1
+        }
+    );
+    assert_eq!(
+        hits[1],
+        Hit {
+            distance: 0.0,
+            primitive: 1
+        }
+    );
+    assert!(!hits[2].is_hit());
+    assert_eq!(
+        hits[3],
+        Hit {
+            distance: 5.0,
+            primitive: 0
+        }
+    );
+}
@@ -0,0 +1,92 @@
+use sgpu_compute::lint::{self, Lint};
+use sgpu_compute::prelude::*;
+use std::sync::{Arc, Mutex};
+
+const TWO_STAGES: &str = "
+    @group(0) @binding(0) var<storage, read> in: array<u32>;
+    @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+    var<workgroup> tile: array<u32, 4>;
+
+    @compute @workgroup_size(4)
+    fn copy(@builtin(local_invocation_index) i: u32) {
+        out[i] = in[i];
+    }
+
+    @compute @workgroup_size(4)
+    fn reverse(@builtin(local_invocation_index) i: u32) {
+        tile[i] = out[i];
+        workgroupBarrier();
+        out[i] = tile[3u - i];
+    }
+";
+
+#[test]
+fn lint_clean_shader() {
+    let stages = [
+        StageDesc::new(TWO_STAGES, "copy"),
+        StageDesc::new(TWO_STAGES, "reverse"),
+    ];
+    assert_eq!(lint::lint(&stages), []);
+}
+
+#[test]
+fn lint_non_uniform_barrier_and_validation() {
+    let non_uniform = "
+        @group(0) @binding(0) var<storage, read_write> out: array<u32>;
+
+        @compute @workgroup_size(4)
+        fn main(@builtin(local_invocation_index) i: u32) {
+            if i == 0u {
+                workgroupBarrier();
+            }
+            out[i] = i;
+        }
+    ";
+    let warnings = lint::lint(&[
+        StageDesc::new(non_uniform, "main").with_name("barrier"),
+        StageDesc::new(
+            "@compute @workgroup_size(1) fn main() { let x: u32 = 1.0; }",
+            "main",
+        )
+        .with_name("typo"),
+        StageDesc::new("@compute @workgroup_size(1) fn main() {}", "missing"),
+    ]);
+    let lints: Vec<_> = warnings
+        .iter()
+        .map(|warning| (warning.stage, warning.lint))
+        .collect();
+    assert_eq!(
+        lints,
+        [
+            ("barrier", Lint::NonUniformBarrier),
+            ("typo", Lint::Validation),
+            ("missing", Lint::Validation)
+        ]
+    );
+}
+
+#[test]
+fn lint_callback_at_pipeline_creation() {
+    let shader = "
+        @group(0) @binding(0) var<storage, read> in: array<u32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+        @compute @workgroup_size(4)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = id.x;
+        }
+    ";
+    let warnings = Arc::new(Mutex::new(Vec::new()));
+    let mut gpu = GpuCompute::new();
+    let collected = Arc::clone(&warnings);
+    gpu.set_lint_callback(move |warning| collected.lock().unwrap().push(warning.to_string()));
+    let mut pipeline = gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(
+        None,
+        [StageDesc::new(shader, "main").with_name("ids")],
+    );
+    assert_eq!(pipeline.run(&[0; 4], [(1, 1, 1)], |out| *out), [0, 1, 2, 3]);
+    assert_eq!(
+        *warnings.lock().unwrap(),
+        ["UnusedBinding in stage `ids`: @group(0) @binding(0) `in` is not used"]
+    );
+}
@@ -0,0 +1,54 @@
+#![cfg(feature = "log")]
+
+use sgpu_compute::prelude::*;
+use std::sync::Mutex;
+
+static MESSAGES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct Collector;
+
+impl log::Log for Collector {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.target() == "sgpu_compute"
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            MESSAGES.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+#[test]
+fn log_diagnostics() {
+    log::set_logger(&Collector).unwrap();
+    log::set_max_level(log::LevelFilter::Debug);
+
+    let shader = "
+        @group(0) @binding(0) var<storage, read_write> scratchpad: array<u32>;
+        @group(0) @binding(1) var<storage, read> in: array<u32>;
+        @group(0) @binding(2) var<storage, read_write> out: array<u32>;
+
+        @compute
+        @workgroup_size(4, 1, 1)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x];
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let _pipeline = gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(
+        NonZeroUsize::new(64),
+        [StageDesc::new(shader, "main")],
+    );
+
+    let messages = MESSAGES.lock().unwrap();
+    assert!(messages.iter().any(|m| m.starts_with("Selected adapter")));
+    assert!(messages
+        .iter()
+        .any(|m| m == "Uniform is zero-sized, its binding is skipped"));
+    assert!(messages.iter().any(|m| m.starts_with(
+        "Allocated buffers: uniform 0 bytes, scratchpad 64 bytes, input 16 bytes, output 16 bytes"
+    )));
+}
@@ -0,0 +1,40 @@
+use sgpu_compute::kernels::map_reduce::ReduceOp;
+use sgpu_compute::prelude::*;
+use sgpu_compute::testgen;
+
+#[test]
+fn map_reduce_ops() {
+    const N: usize = 100_000;
+    let input: Box<[f32; N]> = Box::new(testgen::random_f32(17, -1.0..1.0));
+    let gpu = GpuCompute::new();
+
+    let sum = gpu.map_reduce("x * x", ReduceOp::Sum, &input);
+    let expected: f64 = input.iter().map(|x| (x * x) as f64).sum();
+    assert!((sum as f64 - expected).abs() / expected < 1e-4);
+
+    let min = gpu.map_reduce("x", ReduceOp::Min, &input);
+    assert_eq!(min, input.iter().copied().fold(f32::INFINITY, f32::min));
+    let max = gpu.map_reduce("2.0 * x", ReduceOp::Max, &input);
+    assert_eq!(
+        max,
+        input
+            .iter()
+            .map(|x| 2.0 * x)
+            .fold(f32::NEG_INFINITY, f32::max)
+    );
+
+    let head: [f32; 1000] = input[..1000].try_into().unwrap();
+    let product = gpu.map_reduce("1.0 + x * 1e-4", ReduceOp::Product, &head);
+    let expected: f64 = head.iter().map(|x| 1.0 + *x as f64 * 1e-4).product();
+    assert!((product as f64 - expected).abs() < 1e-4);
+}
+
+#[test]
+fn map_reduce_small_and_empty() {
+    let gpu = GpuCompute::new();
+    assert_eq!(
+        gpu.map_reduce("x + 1.0", ReduceOp::Sum, &[1.0, 2.0, 3.0]),
+        9.0
+    );
+    assert_eq!(gpu.map_reduce("x", ReduceOp::Min, &[]), f32::INFINITY);
+}
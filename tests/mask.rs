@@ -0,0 +1,69 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sgpu_compute::kernels::mask::{self, Run, Runs};
+use sgpu_compute::prelude::*;
+
+const N: usize = 5000;
+
+/// Mask made of runs of random lengths.
+fn gen_mask(seed: u64) -> [u32; N] {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut value = rng.gen_bool(0.5) as u32;
+    let mut remaining = 0;
+    std::array::from_fn(|_| {
+        if remaining == 0 {
+            remaining = rng.gen_range(1..40);
+            value ^= 1;
+        }
+        remaining -= 1;
+        value * rng.gen_range(1..4)
+    })
+}
+
+fn rle_cpu(mask: &[u32]) -> Vec<Run> {
+    let mut runs: Vec<Run> = Vec::new();
+    for (i, &v) in mask.iter().enumerate() {
+        match runs.last_mut() {
+            Some(run) if run.is_set() == (v != 0) => run.end += 1,
+            _ => runs.push(Run::new((v != 0) as u32, i as u32, i as u32 + 1)),
+        }
+    }
+    runs
+}
+
+#[test]
+fn bitpack_compare() {
+    const WORDS: usize = N.div_ceil(32);
+    let input = gen_mask(1);
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[u32; N], (), [u32; WORDS], 1>(None, [mask::BITPACK]);
+    let bits = pipeline.run(&input, [mask::bitpack_workgroups(N)], |bits| *bits);
+    for (i, v) in input.iter().enumerate() {
+        assert_eq!(bits[i / 32] >> (i % 32) & 1 == 1, *v != 0, "at {}", i);
+    }
+}
+
+#[test]
+fn rle_compare() {
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu
+        .gen_pipeline::<[u32; N], (), Runs<N>, 3>(mask::rle_scratchpad_size(N), mask::RLE_STAGES);
+    for seed in 0..3 {
+        let input = gen_mask(seed);
+        let runs = pipeline.run(&input, mask::rle_workgroups(N), |runs| runs.to_vec());
+        assert_eq!(runs, rle_cpu(&input));
+    }
+}
+
+#[test]
+fn rle_overflow() {
+    let input: [u32; N] = std::array::from_fn(|i| (i % 2) as u32);
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu
+        .gen_pipeline::<[u32; N], (), Runs<16>, 3>(mask::rle_scratchpad_size(N), mask::RLE_STAGES);
+    let (overflowed, count, runs) = pipeline.run(&input, mask::rle_workgroups(N), |runs| {
+        (runs.overflowed(), runs.count(), runs.to_vec())
+    });
+    assert!(overflowed);
+    assert_eq!(count, N);
+    assert_eq!(runs, rle_cpu(&input)[..16]);
+}
@@ -0,0 +1,104 @@
+use sgpu_compute::kernels::{bvh, ccl, integral, intersect, mask, noise, tonemap};
+use sgpu_compute::minify::{minify, MinifyOptions};
+use sgpu_compute::prelude::*;
+
+const SHADERS: [&str; 9] = [
+    bvh::MORTON_SHADER,
+    bvh::LBVH_SHADER,
+    ccl::SHADER,
+    integral::SHADER,
+    intersect::SHADER,
+    mask::BITPACK_SHADER,
+    mask::RLE_SHADER,
+    noise::SHADER,
+    tonemap::SHADER,
+];
+
+fn entry_points(shader: &str) -> Vec<String> {
+    let module = wgpu::naga::front::wgsl::parse_str(shader).expect("Shader is valid");
+    module.entry_points.into_iter().map(|ep| ep.name).collect()
+}
+
+#[test]
+fn minified_kernels_stay_valid() {
+    for shader in SHADERS {
+        let minified = minify(shader, MinifyOptions::RELEASE);
+        assert!(minified.source.len() < shader.len());
+        assert!(!minified.source.contains("//"));
+        assert_eq!(entry_points(&minified.source), entry_points(shader));
+    }
+}
+
+#[test]
+fn debug_keeps_source() {
+    for shader in SHADERS {
+        assert_eq!(minify(shader, MinifyOptions::DEBUG).source, shader);
+    }
+    let comments_only = MinifyOptions {
+        strip_comments: false,
+        ..MinifyOptions::RELEASE
+    };
+    let minified = minify(
+        include_str!("../examples/normal_distribution.wgsl"),
+        comments_only,
+    );
+    entry_points(&minified.source);
+}
+
+#[test]
+fn minified_shader_runs() {
+    const N: usize = 1000;
+    let minified = minify(
+        include_str!("../examples/normal_distribution.wgsl"),
+        MinifyOptions::RELEASE,
+    );
+    let shader = Box::leak(minified.source.into_boxed_str());
+    let gpu = GpuCompute::new();
+    let mut original = gpu.gen_pipeline::<[f32; N], u32, [f32; N], 1>(
+        None,
+        [
+            StageDesc::new(include_str!("../examples/normal_distribution.wgsl"), "main")
+                .with_name("original"),
+        ],
+    );
+    let mut minified = gpu.gen_pipeline::<[f32; N], u32, [f32; N], 1>(
+        None,
+        [StageDesc::new(shader, "main").with_name("minified")],
+    );
+    original.write_uniform(&1024);
+    minified.write_uniform(&1024);
+    let input = std::array::from_fn(|i| i as f32 / 300.0);
+    let expected = original.run(&input, [(100, 1, 1)], |out| *out);
+    assert_eq!(minified.run(&input, [(100, 1, 1)], |out| *out), expected);
+}
+
+#[test]
+fn enumerants_keep_their_name() {
+    // `read`, `storage`, `function` and `r32uint` are only enumerants in template arguments, so they can be declared.
+    let shader = "
+        struct Item { value: u32 }
+        @group(0) @binding(0) var<storage, read> items: array<Item>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+        @group(0) @binding(2) var image: texture_storage_2d<r32uint, write>;
+
+        fn read(storage: ptr<function, Item>) -> u32 {
+            return (*storage).value;
+        }
+
+        @compute @workgroup_size(1)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            var function = items[id.x];
+            let r32uint = read(&function);
+            out[id.x] = r32uint;
+            textureStore(image, vec2<i32>(0, 0), vec4<u32>(r32uint));
+        }
+    ";
+    let minified = minify(shader, MinifyOptions::RELEASE);
+    assert!(minified.source.contains("var<storage,read>"));
+    assert!(minified.source.contains("ptr<function,_"));
+    assert!(minified
+        .source
+        .contains("texture_storage_2d<r32uint,write>"));
+    assert!(!minified.source.contains("fn read"));
+    assert_eq!(entry_points(&minified.source), entry_points(shader));
+}
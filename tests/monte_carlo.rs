@@ -0,0 +1,35 @@
+use sgpu_compute::kernels::monte_carlo::{self, monte_carlo};
+use sgpu_compute::prelude::*;
+
+#[test]
+fn monte_carlo_moments() {
+    let gpu = GpuCompute::new();
+    // Not a multiple of the batch size, so the last batch is partial.
+    let samples = 3 * monte_carlo::BATCH_SAMPLES + 1234;
+    let integrand = "
+        fn integrand(x: vec4<f32>) -> f32 {
+            return x.x * x.x;
+        }
+    ";
+    let estimate = monte_carlo(&gpu, integrand, samples, 7);
+    assert_eq!(estimate.samples, samples);
+    // E[x^2] = 1/3 and Var[x^2] = 1/5 - 1/9 for x uniform in [0, 1).
+    assert!((estimate.mean - 1.0 / 3.0).abs() < 5.0 * estimate.standard_error());
+    assert!((estimate.variance - 4.0 / 45.0).abs() < 1e-3);
+    assert_eq!(monte_carlo(&gpu, integrand, samples, 7), estimate);
+    assert_ne!(monte_carlo(&gpu, integrand, samples, 8), estimate);
+}
+
+#[test]
+fn monte_carlo_uses_all_dimensions() {
+    let gpu = GpuCompute::new();
+    let integrand = "
+        fn integrand(x: vec4<f32>) -> f32 {
+            return x.x + x.y + x.z + x.w;
+        }
+    ";
+    let estimate = monte_carlo(&gpu, integrand, 100_000, 1);
+    assert!((estimate.mean - 2.0).abs() < 0.01);
+    // Sum of 4 independent uniform variables.
+    assert!((estimate.variance - 4.0 / 12.0).abs() < 0.01);
+}
@@ -0,0 +1,59 @@
+use sgpu_compute::prelude::*;
+
+#[test]
+fn three_outputs_with_uniform() {
+    let shader = "
+        @group(0) @binding(0) var<uniform> threshold: f32;
+        @group(0) @binding(1) var<storage, read> in: array<f32>;
+        @group(0) @binding(2) var<storage, read_write> clamped: array<f32>;
+        @group(0) @binding(3) var<storage, read_write> above: array<u32>;
+        @group(0) @binding(4) var<storage, read_write> count: atomic<u32>;
+
+        @compute @workgroup_size(32)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            let value = in[id.x];
+            clamped[id.x] = min(value, threshold);
+            above[id.x] = u32(value > threshold);
+            if value > threshold {
+                atomicAdd(&count, 1u);
+            }
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline_multi::<[f32; 32], f32, ([f32; 32], [u32; 32], u32), 1>(
+        None,
+        [StageDesc::new(shader, "main").with_name("threshold")],
+    );
+    let input: [f32; 32] = std::array::from_fn(|i| i as f32);
+    pipeline.write_uniform(&19.5);
+    let (clamped, above, count) = pipeline.run(&input, [(1, 1, 1)], |(clamped, above, count)| {
+        (*clamped, *above, *count)
+    });
+    assert_eq!(clamped[31], 19.5);
+    assert_eq!(clamped[3], 3.0);
+    assert_eq!(above.iter().sum::<u32>(), 12);
+    assert_eq!(count, 12);
+}
+
+#[test]
+fn extra_outputs_are_checked() {
+    let shader = "
+        @group(0) @binding(0) var<storage, read_write> first: array<u32>;
+        @group(0) @binding(1) var<uniform> second: vec4<u32>;
+
+        @compute @workgroup_size(4)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            first[id.x] = second.x;
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let error = gpu
+        .try_gen_pipeline_multi::<(), (), ([u32; 4], [u32; 4]), 1>(
+            None,
+            [StageDesc::new(shader, "main").with_name("wrong")],
+        )
+        .err()
+        .unwrap();
+    assert!(matches!(error, SgpuError::LayoutMismatch(_)));
+    assert!(error.to_string().contains("`second`"), "{}", error);
+}
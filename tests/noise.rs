@@ -0,0 +1,172 @@
+use sgpu_compute::kernels::noise::{self, NoiseParams};
+use sgpu_compute::prelude::*;
+
+fn pcg(v: u32) -> u32 {
+    let state = v.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+    (word >> 22) ^ word
+}
+
+fn grad(h: u32, d: [f32; 3]) -> f32 {
+    let k = h & 15;
+    let u = if k < 8 { d[0] } else { d[1] };
+    let v = if k < 4 {
+        d[1]
+    } else if k == 12 || k == 14 {
+        d[0]
+    } else {
+        d[2]
+    };
+    (if k & 1 == 0 { u } else { -u }) + (if k & 2 == 0 { v } else { -v })
+}
+
+fn perlin_cpu(p: [f32; 3], seed: u32) -> f32 {
+    let cell = p.map(f32::floor);
+    let f = [p[0] - cell[0], p[1] - cell[1], p[2] - cell[2]];
+    let u = f.map(|t| t * t * t * (t * (t * 6.0 - 15.0) + 10.0));
+    let corner = |dx: u32, dy: u32, dz: u32| {
+        let c = [
+            (cell[0] as i32 as u32).wrapping_add(dx),
+            (cell[1] as i32 as u32).wrapping_add(dy),
+            (cell[2] as i32 as u32).wrapping_add(dz),
+        ];
+        let h = pcg(c[0] ^ pcg(c[1] ^ pcg(c[2] ^ pcg(seed))));
+        grad(h, [f[0] - dx as f32, f[1] - dy as f32, f[2] - dz as f32])
+    };
+    let mix = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    let x00 = mix(corner(0, 0, 0), corner(1, 0, 0), u[0]);
+    let x10 = mix(corner(0, 1, 0), corner(1, 1, 0), u[0]);
+    let x01 = mix(corner(0, 0, 1), corner(1, 0, 1), u[0]);
+    let x11 = mix(corner(0, 1, 1), corner(1, 1, 1), u[0]);
+    mix(mix(x00, x10, u[1]), mix(x01, x11, u[1]), u[2])
+}
+
+fn simplex_cpu(p: [f32; 3], seed: u32) -> f32 {
+    let s = (p[0] + p[1] + p[2]) / 3.0;
+    let cell = p.map(|x| (x + s).floor());
+    let t = (cell[0] + cell[1] + cell[2]) / 6.0;
+    let d0 = [p[0] - cell[0] + t, p[1] - cell[1] + t, p[2] - cell[2] + t];
+    let g = [0, 1, 2].map(|i| (d0[i] >= d0[(i + 1) % 3]) as u32);
+    let l = g.map(|g| 1 - g);
+    let i1 = [0, 1, 2].map(|i| g[i].min(l[(i + 2) % 3]));
+    let i2 = [0, 1, 2].map(|i| g[i].max(l[(i + 2) % 3]));
+    let corner = |offset: [u32; 3], k: f32| {
+        let c = [0, 1, 2].map(|i| (cell[i] as i32 as u32).wrapping_add(offset[i]));
+        let d = [0, 1, 2].map(|i| d0[i] - offset[i] as f32 + k / 6.0);
+        let t = (0.5 - (d[0] * d[0] + d[1] * d[1] + d[2] * d[2])).max(0.0);
+        let h = pcg(c[0] ^ pcg(c[1] ^ pcg(c[2] ^ pcg(seed))));
+        t * t * t * t * grad(h, d)
+    };
+    (76.0 * (corner([0; 3], 0.0) + corner(i1, 1.0) + corner(i2, 2.0) + corner([1; 3], 3.0)))
+        .clamp(-1.0, 1.0)
+}
+
+fn fbm_cpu(p: [f32; 3], params: &NoiseParams) -> f32 {
+    fbm_of(p, params, perlin_cpu)
+}
+
+fn fbm_of(p: [f32; 3], params: &NoiseParams, noise: fn([f32; 3], u32) -> f32) -> f32 {
+    let (mut total, mut amplitude, mut norm, mut frequency) = (0.0, 1.0, 0.0, params.frequency);
+    for octave in 0..params.octaves.max(1) {
+        total += amplitude * noise(p.map(|x| x * frequency), params.seed + octave);
+        norm += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    total / norm
+}
+
+#[test]
+fn noise_2d_compare() {
+    const WIDTH: u32 = 37;
+    const HEIGHT: u32 = 21;
+    const LEN: usize = (WIDTH * HEIGHT) as usize;
+    let params = NoiseParams::new_2d(WIDTH, HEIGHT, 7, 0.13).with_octaves(3);
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<(), NoiseParams, [f32; LEN], 1>(None, [noise::NOISE_2D]);
+    pipeline.write_uniform(&params);
+    let field = pipeline.run(&(), [noise::workgroups_2d(WIDTH, HEIGHT)], |field| *field);
+    for (i, v) in field.iter().enumerate() {
+        let p = [(i as u32 % WIDTH) as f32, (i as u32 / WIDTH) as f32, 0.0];
+        let expected = fbm_cpu(p, &params);
+        assert!(
+            (v - expected).abs() < 1e-5,
+            "{} != {} at {:?}",
+            v,
+            expected,
+            p
+        );
+    }
+    assert!(field.iter().any(|v| v.abs() > 0.1));
+}
+
+#[test]
+fn noise_3d_compare() {
+    const SIZE: u32 = 10;
+    const LEN: usize = (SIZE * SIZE * SIZE) as usize;
+    let params = NoiseParams::new_3d(SIZE, SIZE, SIZE, 1234, 0.35);
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<(), NoiseParams, [f32; LEN], 1>(None, [noise::NOISE_3D]);
+    pipeline.write_uniform(&params);
+    let field = pipeline.run(&(), [noise::workgroups_3d(SIZE, SIZE, SIZE)], |field| {
+        *field
+    });
+    for (i, v) in field.iter().enumerate() {
+        let i = i as u32;
+        let p = [
+            (i % SIZE) as f32,
+            (i / SIZE % SIZE) as f32,
+            (i / SIZE / SIZE) as f32,
+        ];
+        let expected = fbm_cpu(p, &params);
+        assert!(
+            (v - expected).abs() < 1e-5,
+            "{} != {} at {:?}",
+            v,
+            expected,
+            p
+        );
+    }
+}
+
+#[test]
+fn simplex_compare() {
+    const SIZE: u32 = 10;
+    const LEN: usize = (SIZE * SIZE * SIZE) as usize;
+    let params = NoiseParams::new_3d(SIZE, SIZE, SIZE, 99, 0.27).with_octaves(2);
+    let gpu = GpuCompute::new();
+    let mut pipeline =
+        gpu.gen_pipeline::<(), NoiseParams, [f32; LEN], 1>(None, [noise::SIMPLEX_3D]);
+    pipeline.write_uniform(&params);
+    let field = pipeline.run(&(), [noise::workgroups_3d(SIZE, SIZE, SIZE)], |field| {
+        *field
+    });
+    for (i, v) in field.iter().enumerate() {
+        let i = i as u32;
+        let p = [
+            (i % SIZE) as f32,
+            (i / SIZE % SIZE) as f32,
+            (i / SIZE / SIZE) as f32,
+        ];
+        let expected = fbm_of(p, &params, simplex_cpu);
+        assert!(
+            (v - expected).abs() < 1e-4,
+            "{} != {} at {:?}",
+            v,
+            expected,
+            p
+        );
+    }
+    assert!(field.iter().all(|v| v.abs() <= 1.0));
+    assert!(field.iter().any(|v| v.abs() > 0.1));
+
+    // The 2D field is the first slice of the 3D one.
+    let params = NoiseParams::new_2d(SIZE, SIZE, 99, 0.27).with_octaves(2);
+    let mut pipeline = gpu.gen_pipeline::<(), NoiseParams, [f32; (SIZE * SIZE) as usize], 1>(
+        None,
+        [noise::SIMPLEX_2D],
+    );
+    pipeline.write_uniform(&params);
+    let slice = pipeline.run(&(), [noise::workgroups_2d(SIZE, SIZE)], |field| *field);
+    assert_eq!(slice, field[..slice.len()]);
+}
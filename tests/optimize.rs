@@ -0,0 +1,48 @@
+use sgpu_compute::kernels::optimize::{cma_es, grid_search, CmaEs, ObjectiveAsync};
+use sgpu_compute::prelude::*;
+
+const SPHERE: &str = "
+    fn objective(x: ptr<function, array<f32, DIMENSIONS>>) -> f32 {
+        var sum = 0.0;
+        for (var i = 0u; i < DIMENSIONS; i++) {
+            let d = (*x)[i] - f32(i) * 0.5;
+            sum += d * d;
+        }
+        return sum;
+    }
+";
+
+#[test]
+fn objective_evaluates_partial_populations() {
+    let gpu = GpuCompute::new();
+    let mut objective = pollster::block_on(ObjectiveAsync::new(&gpu, SPHERE, 2, 3));
+    let candidates = [0.0, 0.5, 1.0, 0.5, 0.0, 0.0, 0.0, 1.5, 2.0, 0.5];
+    let values = pollster::block_on(objective.evaluate(&candidates));
+    assert_eq!(values, [0.0, 1.0, 0.25, 1.0, 4.0]);
+}
+
+#[test]
+fn grid_search_finds_the_closest_point() {
+    let gpu = GpuCompute::new();
+    let minimum = grid_search(&gpu, SPHERE, &[(-1.0, 1.0), (-1.0, 1.0), (-1.0, 1.0)], 41);
+    assert_eq!(minimum.evaluations, 41 * 41 * 41);
+    assert_eq!(minimum.x, [0.0, 0.5, 1.0]);
+    assert_eq!(minimum.value, 0.0);
+}
+
+#[test]
+fn cma_es_is_reproducible() {
+    let gpu = GpuCompute::new();
+    let options = CmaEs::new(vec![2.0; 6], 1.0).with_seed(7);
+    let minimum = cma_es(&gpu, SPHERE, &options);
+    for (i, x) in minimum.x.iter().enumerate() {
+        assert!((x - i as f64 * 0.5).abs() < 1e-3, "{:?}", minimum);
+    }
+    assert_eq!(cma_es(&gpu, SPHERE, &options), minimum);
+    let short = cma_es(
+        &gpu,
+        SPHERE,
+        &options.clone().with_max_generations(3).with_population(10),
+    );
+    assert_eq!(short.evaluations, 30);
+}
@@ -0,0 +1,63 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sgpu_compute::kernels::pagerank::{self, PageRankGraph};
+use sgpu_compute::prelude::*;
+
+const V: usize = 2000;
+const E: usize = 8000;
+
+fn pagerank_cpu(edges: &[(u32, u32)], damping: f64, iterations: usize) -> Vec<f64> {
+    let mut out_degrees = vec![0; V];
+    for &(from, _) in edges {
+        out_degrees[from as usize] += 1;
+    }
+    let mut ranks = vec![1.0 / V as f64; V];
+    for _ in 0..iterations {
+        let dangling: f64 = (0..V)
+            .filter(|v| out_degrees[*v] == 0)
+            .map(|v| ranks[v])
+            .sum();
+        let mut next = vec![(1.0 - damping) / V as f64 + damping * dangling / V as f64; V];
+        for &(from, to) in edges {
+            next[to as usize] += damping * ranks[from as usize] / out_degrees[from as usize] as f64;
+        }
+        ranks = next;
+    }
+    ranks
+}
+
+#[test]
+fn pagerank_compare() {
+    let mut rng = StdRng::seed_from_u64(33);
+    // The first vertices have no outgoing edges.
+    let edges: Vec<(u32, u32)> = (0..E)
+        .map(|_| (rng.gen_range(10..V as u32), rng.gen_range(0..V as u32)))
+        .collect();
+    let graph = Box::new(PageRankGraph::<V, E>::from_edges(&edges));
+
+    let gpu = GpuCompute::new();
+    let result = pagerank::pagerank(&gpu, &graph, 0.85, 1e-5, 200);
+    assert!(result.residual < 1e-5);
+    assert_eq!(result.iterations as usize % pagerank::ITERATIONS_PER_RUN, 0);
+    assert!((result.ranks.iter().sum::<f32>() - 1.0).abs() < 1e-3);
+
+    let expected = pagerank_cpu(&edges, 0.85, 100);
+    for (rank, expected) in result.ranks.iter().zip(expected) {
+        assert!(
+            (*rank as f64 - expected).abs() < 1e-5,
+            "{} != {}",
+            rank,
+            expected
+        );
+    }
+}
+
+#[test]
+fn pagerank_max_iterations() {
+    let graph = PageRankGraph::<3, 3>::from_edges(&[(0, 1), (1, 2), (2, 0)]);
+    let gpu = GpuCompute::new();
+    let result = pagerank::pagerank(&gpu, &graph, 0.85, 0.0, 10);
+    assert_eq!(result.iterations, 16);
+    for rank in result.ranks {
+        assert!((rank - 1.0 / 3.0).abs() < 1e-6);
+    }
+}
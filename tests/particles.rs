@@ -0,0 +1,80 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sgpu_compute::kernels::particles::{self, Particle, ParticleParams};
+use sgpu_compute::prelude::*;
+
+const N: usize = 500;
+
+fn momentum(particles: &[Particle]) -> [f32; 2] {
+    particles
+        .iter()
+        .fold([0.0; 2], |[x, y], p| [x + p.velocity[0], y + p.velocity[1]])
+}
+
+fn energy(particles: &[Particle]) -> f32 {
+    particles
+        .iter()
+        .map(|p| p.velocity[0] * p.velocity[0] + p.velocity[1] * p.velocity[1])
+        .sum()
+}
+
+#[test]
+fn particles_stay_in_box() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let initial: [Particle; N] = std::array::from_fn(|_| {
+        Particle::new(
+            [rng.gen_range(1.0..99.0), rng.gen_range(1.0..99.0)],
+            [rng.gen_range(-5.0..5.0), rng.gen_range(-5.0..5.0)],
+        )
+    });
+    let params = ParticleParams::new(N as u32, [100.0, 100.0], 0.5, 0.05)
+        .with_gravity([0.0, -9.81])
+        .with_restitution(0.8);
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[Particle; N], ParticleParams, [Particle; N], 7>(
+        params.scratchpad_size(),
+        particles::STAGES,
+    );
+    pipeline.write_uniform(&params.with_reset(true));
+    pipeline.run(&initial, params.workgroups(), |_| ());
+    pipeline.write_uniform(&params);
+    let mut frame = initial;
+    for _ in 0..100 {
+        frame = pipeline.run(&initial, params.workgroups(), |frame| *frame);
+    }
+    for particle in frame {
+        assert!((0.5..=99.5).contains(&particle.position[0]));
+        assert!((0.5..=99.5).contains(&particle.position[1]));
+    }
+    // The particles fall, so most of them end in the lower half of the box.
+    assert!(frame.iter().filter(|p| p.position[1] < 50.0).count() > N * 3 / 4);
+}
+
+#[test]
+fn collisions_conserve_momentum() {
+    // Dense particles far from the walls, with a time step too short to reach them.
+    let mut rng = StdRng::seed_from_u64(4);
+    let initial: [Particle; N] = std::array::from_fn(|i| {
+        let (x, y) = ((i % 25) as f32, (i / 25) as f32);
+        Particle::new(
+            [40.0 + x * 0.9, 40.0 + y * 0.9],
+            [rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0)],
+        )
+    });
+    let params = ParticleParams::new(N as u32, [100.0, 100.0], 0.5, 0.001);
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[Particle; N], ParticleParams, [Particle; N], 7>(
+        params.scratchpad_size(),
+        particles::STAGES,
+    );
+    pipeline.write_uniform(&params.with_reset(true));
+    let frame = pipeline.run(&initial, params.workgroups(), |frame| *frame);
+    // Some particles collided.
+    assert!(frame
+        .iter()
+        .zip(&initial)
+        .any(|(a, b)| a.velocity != b.velocity));
+    let [px, py] = momentum(&frame);
+    let [qx, qy] = momentum(&initial);
+    assert!((px - qx).abs() < 1e-3 && (py - qy).abs() < 1e-3);
+    assert!(energy(&frame) <= energy(&initial) * 1.001);
+}
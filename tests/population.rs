@@ -0,0 +1,67 @@
+use sgpu_compute::kernels::population::Population;
+use sgpu_compute::prelude::*;
+
+#[derive(Debug, Copy, Clone, bytemuck::Zeroable, bytemuck::Pod)]
+#[repr(C)]
+struct Genome {
+    bits: u32,
+    penalty: f32,
+}
+
+const ONE_MAX: &str = "
+    @group(0) @binding(0) var<uniform> weight: f32;
+
+    struct Genome {
+        bits: u32,
+        penalty: f32,
+    }
+
+    fn fitness(index: u32) -> f32 {
+        let genome = population[index];
+        return f32(countOneBits(genome.bits)) - weight * genome.penalty;
+    }
+";
+
+#[test]
+fn generations_are_double_buffered() {
+    let gpu = GpuCompute::new();
+    let mut population = Population::<Genome, f32>::new(&gpu, ONE_MAX, 300);
+    let generation = |g: u32| {
+        (0..300)
+            .map(|i| Genome {
+                bits: i ^ g,
+                penalty: g as f32,
+            })
+            .collect::<Vec<_>>()
+    };
+    let expected = |g: u32, weight: f32| {
+        (0..300)
+            .map(|i: u32| (i ^ g).count_ones() as f32 - weight * g as f32)
+            .collect::<Vec<_>>()
+    };
+    population.write_uniform(&0.5);
+    assert_eq!(population.submit(&generation(0)), 0);
+    assert_eq!(population.submit(&generation(1)), 1);
+    assert_eq!(population.in_flight(), 2);
+    assert_eq!(population.scores(), expected(0, 0.5));
+    population.write_uniform(&2.0);
+    assert_eq!(population.submit(&generation(2)), 2);
+    assert_eq!(population.scores(), expected(1, 0.5));
+    assert_eq!(population.scores(), expected(2, 2.0));
+    assert_eq!(population.in_flight(), 0);
+    assert_eq!(population.evaluate(&generation(7)), expected(7, 2.0));
+}
+
+#[test]
+#[should_panic(expected = "Two generations are already in flight")]
+fn at_most_two_generations_in_flight() {
+    let gpu = GpuCompute::new();
+    let mut population = Population::<Genome, f32>::new(&gpu, ONE_MAX, 4);
+    let genomes = [Genome {
+        bits: 1,
+        penalty: 0.0,
+    }; 4];
+    population.submit(&genomes);
+    population.submit(&genomes);
+    population.submit(&genomes);
+}
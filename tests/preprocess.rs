@@ -0,0 +1,135 @@
+use sgpu_compute::prelude::*;
+use sgpu_compute::preprocess::{PreprocessError, Preprocessor};
+
+#[test]
+fn defines_and_placeholders() {
+    let source = "#define LEN {{N}}\n  # define SCALE 2.0\nconst LENGTH = LEN; // LEN\nlet x = SCALE * LENGTH_2 + {{ N }};\nif a {{b();}}\n";
+    let processed = Preprocessor::new().define("N", 16).process(source).unwrap();
+    assert_eq!(
+        processed,
+        "\n\nconst LENGTH = 16; // LEN\nlet x = 2.0 * LENGTH_2 + 16;\nif a {{b();}}\n"
+    );
+    // The values of the host take precedence over the `#define` lines.
+    let processed = Preprocessor::new()
+        .define("N", 16)
+        .define("SCALE", "0.5")
+        .process(source)
+        .unwrap();
+    assert!(processed.contains("let x = 0.5 * LENGTH_2 + 16;"));
+}
+
+#[test]
+fn errors_have_their_line() {
+    let preprocessor = Preprocessor::new();
+    assert_eq!(
+        preprocessor.process("const A = 1;\nconst B = {{B}};"),
+        Err(PreprocessError::Unresolved {
+            name: "B".into(),
+            line: 2
+        })
+    );
+    assert_eq!(
+        preprocessor.process("#ifdef DEBUG"),
+        Err(PreprocessError::UnknownDirective {
+            directive: "ifdef".into(),
+            line: 1
+        })
+    );
+    assert_eq!(
+        preprocessor.process("\n\n#define"),
+        Err(PreprocessError::InvalidDefine { line: 3 })
+    );
+}
+
+#[test]
+fn modules_are_included_once() {
+    let preprocessor = Preprocessor::new()
+        .module("consts", "#define SCALE 3u\nconst LEN = {{N}};")
+        .module(
+            "scale",
+            "#include \"consts\"\nfn scale(x: u32) -> u32 { return x * SCALE; }",
+        )
+        .module(
+            "sum",
+            "#include \"consts\"\nfn sum() -> u32 { return LEN; }",
+        )
+        .define("N", 4);
+    let processed = preprocessor
+        .process("#include \"scale\"\n#include \"sum\"\nconst X = scale(SCALE);")
+        .unwrap();
+    assert_eq!(processed.matches("const LEN = 4;").count(), 1);
+    assert!(processed.contains("fn scale(x: u32) -> u32 { return x * 3u; }"));
+    assert!(processed.contains("fn sum() -> u32 { return LEN; }"));
+    assert!(processed.ends_with("const X = scale(3u);"));
+}
+
+#[test]
+fn include_errors() {
+    let preprocessor = Preprocessor::new()
+        .module("a", "#include \"b\"")
+        .module("b", "\n#include \"a\"")
+        .module("broken", "const A = 1;\nconst B = {{B}};");
+    assert_eq!(
+        preprocessor.process("#include \"missing\""),
+        Err(PreprocessError::UnknownModule {
+            name: "missing".into(),
+            line: 1
+        })
+    );
+    assert_eq!(
+        preprocessor.process("\n#include missing"),
+        Err(PreprocessError::InvalidInclude { line: 2 })
+    );
+    assert_eq!(
+        preprocessor
+            .process("#include \"a\"")
+            .unwrap_err()
+            .to_string(),
+        "in module `a`, in module `b`, line 2: the module `a` includes itself"
+    );
+    assert_eq!(
+        preprocessor
+            .process("#include \"broken\"")
+            .unwrap_err()
+            .to_string(),
+        "in module `broken`, line 2: the placeholder `B` has no value"
+    );
+}
+
+#[test]
+fn unresolved_placeholders_fail_the_pipeline() {
+    let shader = "
+        @group(0) @binding(0) var<storage, read_write> out: array<u32, {{N}}>;
+
+        @compute @workgroup_size({{WORKGROUP_SIZE}})
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = id.x;
+        }
+    ";
+    let stages = [StageDesc::new(shader, "main").with_name("iota")];
+    let gpu = GpuCompute::new();
+    let error = gpu
+        .try_gen_pipeline_with_provider::<(), (), [u32; 8], 1>(
+            Preprocessor::new().define("N", 8),
+            None,
+            stages.clone(),
+        )
+        .err()
+        .unwrap();
+    assert!(
+        matches!(&error, SgpuError::ShaderCompilation(message) if message == "line 4: the placeholder `WORKGROUP_SIZE` has no value"),
+        "{}",
+        error
+    );
+    let mut pipeline = gpu.gen_pipeline_with_provider::<(), (), [u32; 8], 1>(
+        Preprocessor::new()
+            .define("N", 8)
+            .define("WORKGROUP_SIZE", 8),
+        None,
+        stages,
+    );
+    assert_eq!(
+        pipeline.run(&(), [(1, 1, 1)], |out| *out),
+        [0, 1, 2, 3, 4, 5, 6, 7]
+    );
+}
@@ -0,0 +1,86 @@
+use sgpu_compute::prelude::*;
+use std::time::Duration;
+
+const SHADER: &str = "
+    @group(0) @binding(0) var<uniform> iterations: u32;
+    @group(0) @binding(1) var<storage, read> in: array<f32>;
+    @group(0) @binding(2) var<storage, read_write> out: array<f32>;
+
+    @compute @workgroup_size(64)
+    fn copy(@builtin(global_invocation_id) id: vec3<u32>) {
+        out[id.x] = in[id.x];
+    }
+
+    @compute @workgroup_size(64)
+    fn spin(@builtin(global_invocation_id) id: vec3<u32>) {
+        var x = out[id.x];
+        for (var i = 0u; i < iterations; i++) {
+            x = fract(x * 1.0001 + 0.5);
+        }
+        out[id.x] = x;
+    }
+";
+
+#[test]
+fn each_stage_is_timed() {
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[f32; 4096], u32, [f32; 4096], 2>(
+        None,
+        [
+            StageDesc::new(SHADER, "copy").with_name("copy"),
+            StageDesc::new(SHADER, "spin"),
+        ],
+    );
+    pipeline.write_uniform(&0);
+    let (first, timings) = pipeline.run_profiled(&[0.25; 4096], [(64, 1, 1); 2], |out| out[0]);
+    assert_eq!(first, 0.25);
+    assert_eq!(timings.stages.len(), 2);
+    assert_eq!(timings.stages[0].name, Some("copy"));
+    assert_eq!(timings.stages[1].entrypoint, "spin");
+    assert_eq!(
+        timings.total(),
+        timings.stages[0].duration + timings.stages[1].duration
+    );
+
+    // A stage doing much more work stands out.
+    pipeline.write_uniform(&20_000);
+    let (_, timings) = pipeline.run_profiled(&[0.25; 4096], [(64, 1, 1); 2], |_| ());
+    assert_eq!(timings.slowest().unwrap().entrypoint, "spin");
+    assert!(timings.stages[1].duration > Duration::ZERO);
+    let report = timings.to_string();
+    assert!(report.starts_with("copy: "), "{}", report);
+    assert!(report.contains("\nspin: "), "{}", report);
+    assert!(report.contains("total: "), "{}", report);
+}
+
+#[test]
+fn statistics_report_the_dispatched_invocations() {
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[f32; 4096], u32, [f32; 4096], 2>(
+        None,
+        [
+            StageDesc::new(SHADER, "copy").with_name("copy"),
+            StageDesc::new(SHADER, "spin").with_name("spin"),
+        ],
+    );
+    pipeline.write_uniform(&1);
+    // The second stage misses the last workgroup.
+    let (_, statistics) =
+        pipeline.run_with_statistics(&[0.0; 4096], [(64, 1, 1), (63, 1, 1)], |_| ());
+    assert_eq!(statistics.len(), 2);
+    assert_eq!(statistics[0].workgroup_size, Some([64, 1, 1]));
+    assert_eq!(statistics[0].dispatched, Some(4096));
+    assert_eq!(statistics[1].dispatched, Some(4032));
+    assert!(statistics[0].covers([4096, 1, 1]));
+    assert!(!statistics[1].covers([4096, 1, 1]));
+    let supported = gpu
+        .device()
+        .features()
+        .contains(wgpu::Features::PIPELINE_STATISTICS_QUERY);
+    for stage in &statistics {
+        assert_eq!(stage.invocations.is_some(), supported);
+        if let Some(invocations) = stage.invocations {
+            assert_eq!(Some(invocations), stage.dispatched);
+        }
+    }
+}
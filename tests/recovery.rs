@@ -0,0 +1,188 @@
+use sgpu_compute::prelude::*;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+const SHADER: &str = "
+    @group(0) @binding(0) var<storage, read> in: array<u32>;
+    @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+    @compute @workgroup_size(4)
+    fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+        out[id.x] = in[id.x] * 2u;
+    }
+";
+
+#[test]
+fn lost_device_is_detected_and_recreated() {
+    let gpu = GpuCompute::new();
+    let notified = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&notified);
+    gpu.set_device_lost_handler(move |_| {
+        counter.fetch_add(1, Ordering::Relaxed);
+    });
+    let mut pipeline = gpu.gen_pipeline::<[u32; 4], (), [u32; 4], 1>(
+        None,
+        [StageDesc::new(SHADER, "main").with_name("double")],
+    );
+    assert_eq!(
+        pipeline.try_run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out),
+        Ok([2, 4, 6, 8])
+    );
+    assert!(!gpu.is_device_lost());
+
+    // The loss is reported when the device is polled.
+    gpu.device().destroy();
+    gpu.device().poll(wgpu::Maintain::Wait);
+    assert!(gpu.is_device_lost());
+    assert_eq!(notified.load(Ordering::Relaxed), 1);
+    assert!(matches!(
+        pipeline.try_run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out),
+        Err(SgpuError::DeviceLost(_))
+    ));
+
+    let recreated = gpu.recreate().expect("The device was created by the crate");
+    assert!(!recreated.is_device_lost());
+    let mut rebuilt = pipeline.rebuild_on(&recreated).unwrap();
+    assert_eq!(
+        rebuilt.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out),
+        [2, 4, 6, 8]
+    );
+    drop(rebuilt);
+    drop(pipeline);
+    drop(recreated);
+    // The GL backend terminates the EGL display shared by all its devices when one of them is dropped, see `FailoverGpu`.
+    if gpu.adapter_info().map(|info| info.backend) == Some(wgpu::Backend::Gl) {
+        std::mem::forget(gpu);
+    }
+}
+
+#[test]
+fn external_devices_are_not_recreated() {
+    let gpu = GpuCompute::new();
+    let external = GpuComputeAsync::from_device(gpu.device().clone(), gpu.queue().clone());
+    assert!(matches!(
+        pollster::block_on(external.recreate()),
+        Err(SgpuError::Unsupported(_))
+    ));
+}
+
+#[test]
+fn external_devices_keep_their_device_lost_callback() {
+    let gpu = GpuCompute::new();
+    let notified = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&notified);
+    gpu.device().set_device_lost_callback(move |reason, _| {
+        if matches!(reason, wgpu::DeviceLostReason::Destroyed) {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+    let external = GpuCompute::from_device(gpu.device().clone(), gpu.queue().clone());
+    gpu.device().destroy();
+    gpu.device().poll(wgpu::Maintain::Wait);
+    assert_eq!(notified.load(Ordering::Relaxed), 1);
+    assert!(!external.is_device_lost());
+    drop(external);
+    if gpu.adapter_info().map(|info| info.backend) == Some(wgpu::Backend::Gl) {
+        std::mem::forget(gpu);
+    }
+}
+
+#[test]
+fn instances_sharing_a_device_are_all_notified() {
+    let gpu = GpuCompute::new();
+    let notified = Arc::new(AtomicUsize::new(0));
+    let externals = [0, 1].map(|_| {
+        let external = GpuCompute::from_device(gpu.device().clone(), gpu.queue().clone());
+        let counter = Arc::clone(&notified);
+        external.set_device_lost_handler(move |_| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+        external
+    });
+    gpu.device().destroy();
+    gpu.device().poll(wgpu::Maintain::Wait);
+    assert_eq!(notified.load(Ordering::Relaxed), 2);
+    assert!(gpu.is_device_lost());
+    assert!(externals.iter().all(|external| external.is_device_lost()));
+    drop(externals);
+    if gpu.adapter_info().map(|info| info.backend) == Some(wgpu::Backend::Gl) {
+        std::mem::forget(gpu);
+    }
+}
+
+#[test]
+fn rebuilt_pipelines_keep_their_uniforms() {
+    let shader = "
+        @group(0) @binding(0) var<uniform> scale: u32;
+        @group(0) @binding(1) var<storage, read> in: array<u32>;
+        @group(0) @binding(2) var<storage, read_write> out: array<u32>;
+
+        @compute @workgroup_size(4)
+        fn scale_values(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] * scale;
+        }
+
+        @compute @workgroup_size(4)
+        fn add(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] += scale;
+        }
+    ";
+    let stage = |entrypoint| StageDesc::new(shader, entrypoint).with_name(entrypoint);
+    let gpu = GpuCompute::new();
+    let mut pipeline =
+        gpu.gen_pipeline::<[u32; 4], u32, [u32; 4], 2>(None, [stage("scale_values"), stage("add")]);
+    pipeline.write_uniform(&2);
+    pipeline.write_uniform_for_stage(1, &5);
+    let expected = pipeline.run(&[1, 2, 3, 4], [(1, 1, 1); 2], |out| *out);
+    assert_eq!(expected, [7, 9, 11, 13]);
+    let mut rebuilt = pipeline.rebuild_on(&gpu).unwrap();
+    rebuilt.write_uniform(&2);
+    assert_eq!(
+        rebuilt.run(&[1, 2, 3, 4], [(1, 1, 1); 2], |out| *out),
+        expected
+    );
+
+    let mut pipeline =
+        gpu.gen_pipeline::<[u32; 4], u32, [u32; 4], 1>(None, [stage("scale_values")]);
+    pipeline.write_uniforms(&[2, 3]);
+    let mut rebuilt = pipeline.rebuild_on(&gpu).unwrap();
+    assert_eq!(rebuilt.batch_size(), 2);
+    assert_eq!(
+        rebuilt.run_batch(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out),
+        [3, 6, 9, 12]
+    );
+}
+
+#[test]
+fn rebuilt_pipelines_keep_their_push_constants() {
+    let shader = "
+        var<push_constant> offset: u32;
+        @group(0) @binding(0) var<storage, read> in: array<u32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+        @compute @workgroup_size(4)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = in[id.x] + offset;
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let pipeline = gpu.try_gen_pipeline_with_push_constants::<[u32; 4], (), [u32; 4], u32, 1>(
+        None,
+        [StageDesc::new(shader, "main")],
+    );
+    if !gpu.features().contains(wgpu::Features::PUSH_CONSTANTS) {
+        assert!(matches!(pipeline, Err(SgpuError::Unsupported(_))));
+        return;
+    }
+    let mut pipeline = pipeline.unwrap();
+    pipeline.set_push_constants(&10u32);
+    let expected = pipeline.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out);
+    assert_eq!(expected, [11, 12, 13, 14]);
+    let mut rebuilt = pipeline.rebuild_on(&gpu).unwrap();
+    assert_eq!(
+        rebuilt.run(&[1, 2, 3, 4], [(1, 1, 1)], |out| *out),
+        expected
+    );
+}
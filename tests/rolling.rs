@@ -0,0 +1,54 @@
+use sgpu_compute::kernels::rolling::{self, RollingOp};
+use sgpu_compute::prelude::*;
+use sgpu_compute::testgen;
+
+fn rolling_cpu(series: &[f32], window: usize, op: RollingOp) -> Vec<f32> {
+    series
+        .windows(window)
+        .map(|w| match op {
+            RollingOp::Sum => w.iter().sum(),
+            RollingOp::Mean => w.iter().sum::<f32>() / window as f32,
+            RollingOp::Min => w.iter().copied().fold(f32::INFINITY, f32::min),
+            RollingOp::Max => w.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+        })
+        .collect()
+}
+
+#[test]
+fn rolling_compare() {
+    const N: usize = 10_000;
+    let series: Box<[f32; N]> = Box::new(testgen::random_f32(44, -10.0..10.0));
+    let gpu = GpuCompute::new();
+    for window in [1, 7, 64, 1000, N as u32] {
+        for op in [
+            RollingOp::Sum,
+            RollingOp::Mean,
+            RollingOp::Min,
+            RollingOp::Max,
+        ] {
+            let gpu_values = sgpu_compute::kernels::rolling(&gpu, &series, window, op);
+            let cpu_values = rolling_cpu(&series[..], window as usize, op);
+            assert_eq!(gpu_values.len(), cpu_values.len());
+            for (i, (a, b)) in gpu_values.iter().zip(&cpu_values).enumerate() {
+                match op {
+                    RollingOp::Min | RollingOp::Max => assert_eq!(a, b),
+                    _ => assert!(
+                        (a - b).abs() <= 1e-3 * b.abs().max(window as f32),
+                        "{:?} window {} at {}: {} != {}",
+                        op,
+                        window,
+                        i,
+                        a,
+                        b
+                    ),
+                }
+            }
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "The window must be in 1..=len")]
+fn rolling_window_too_large() {
+    rolling::RollingParams::new(4, 5, RollingOp::Sum);
+}
@@ -0,0 +1,103 @@
+use sgpu_compute::prelude::*;
+
+const HISTOGRAM: ScratchpadDesc = ScratchpadDesc {
+    name: Some("histogram"),
+    size: NonZeroUsize::new(16 * 4).unwrap(),
+};
+const PREFIX: ScratchpadDesc = ScratchpadDesc {
+    name: Some("prefix"),
+    size: NonZeroUsize::new(16 * 4).unwrap(),
+};
+
+#[test]
+fn counting_sort_with_two_scratchpads() {
+    let shader = "
+        @group(0) @binding(0) var<storage, read_write> histogram: array<atomic<u32>, 16>;
+        @group(0) @binding(1) var<storage, read_write> prefix: array<atomic<u32>, 16>;
+        @group(0) @binding(2) var<storage, read> in: array<u32, 64>;
+        @group(0) @binding(3) var<storage, read_write> out: array<u32, 64>;
+
+        @compute @workgroup_size(64)
+        fn count(@builtin(global_invocation_id) id: vec3<u32>) {
+            atomicAdd(&histogram[in[id.x]], 1u);
+        }
+
+        @compute @workgroup_size(1)
+        fn scan() {
+            var sum = 0u;
+            for (var i = 0u; i < 16u; i++) {
+                atomicStore(&prefix[i], sum);
+                sum += atomicLoad(&histogram[i]);
+            }
+        }
+
+        @compute @workgroup_size(16)
+        fn scatter(@builtin(global_invocation_id) id: vec3<u32>) {
+            let start = atomicLoad(&prefix[id.x]);
+            let count = atomicLoad(&histogram[id.x]);
+            for (var i = 0u; i < count; i++) {
+                out[start + i] = id.x;
+            }
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline_with_scratchpads::<[u32; 64], (), [u32; 64], 3>(
+        &[HISTOGRAM, PREFIX],
+        [
+            StageDesc::new(shader, "count").with_name("count"),
+            StageDesc::new(shader, "scan").with_name("scan"),
+            StageDesc::new(shader, "scatter").with_name("scatter"),
+        ],
+    );
+    pipeline.set_zero_init(ZeroInit::BeforeEachRun);
+    assert_eq!(pipeline.scratchpad_buffers().len(), 2);
+    let input: [u32; 64] = std::array::from_fn(|i| (i as u32 * 7) % 16);
+    let mut expected = input;
+    expected.sort();
+    let out = pipeline.run(&input, [(1, 1, 1); 3], |out| *out);
+    assert_eq!(out, expected);
+    let out = pipeline
+        .clone_for_thread()
+        .run(&input, [(1, 1, 1); 3], |out| *out);
+    assert_eq!(out, expected);
+
+    let roles = pipeline
+        .describe()
+        .buffers
+        .iter()
+        .map(|buffer| buffer.role)
+        .collect::<Vec<_>>();
+    use sgpu_compute::describe::BufferRole;
+    assert_eq!(
+        roles,
+        [
+            BufferRole::Scratchpad,
+            BufferRole::Scratchpad,
+            BufferRole::Input,
+            BufferRole::Output
+        ]
+    );
+}
+
+#[test]
+fn scratchpads_are_checked() {
+    let shader = "
+        @group(0) @binding(0) var<storage, read_write> histogram: array<u32>;
+        @group(0) @binding(1) var<storage, read> in: array<u32>;
+        @group(0) @binding(2) var<storage, read_write> out: array<u32>;
+
+        @compute @workgroup_size(1)
+        fn main() {
+            out[0] = histogram[0] + in[0];
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let error = gpu
+        .try_gen_pipeline_with_scratchpads::<[u32; 4], (), [u32; 4], 1>(
+            &[HISTOGRAM, PREFIX],
+            [StageDesc::new(shader, "main")],
+        )
+        .err()
+        .unwrap();
+    assert!(matches!(error, SgpuError::LayoutMismatch(_)), "{}", error);
+}
@@ -0,0 +1,64 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sgpu_compute::kernels::search::{self, Match, Matches, SearchParams};
+use sgpu_compute::prelude::*;
+
+const LEN: usize = 1 << 16;
+
+fn search_cpu(haystack: &[u8], patterns: &[&[u8]]) -> Vec<Match> {
+    let mut matches = Vec::new();
+    for offset in 0..haystack.len() {
+        for (p, pattern) in patterns.iter().enumerate() {
+            if !pattern.is_empty() && haystack[offset..].starts_with(pattern) {
+                matches.push(Match::new(offset as u32, p as u32));
+            }
+        }
+    }
+    matches
+}
+
+#[test]
+fn search_compare() {
+    let mut rng = StdRng::seed_from_u64(9);
+    // Small alphabet so that the patterns match often.
+    let haystack: [u8; LEN] = std::array::from_fn(|_| b"ACGT"[rng.gen_range(0..4)]);
+    let patterns: [&[u8]; 4] = [b"ACGTAC", b"TTTTTTT", b"GATTACA", b"C"];
+    let expected = search_cpu(&haystack[..LEN - 3], &patterns);
+
+    let gpu = GpuCompute::new();
+    let mut pipeline =
+        gpu.gen_pipeline::<[u8; LEN], SearchParams, Matches<20000>, 2>(None, search::STAGES);
+    // The last bytes are not searched.
+    pipeline.write_uniform(&SearchParams::new(LEN as u32 - 3, &patterns));
+    for _ in 0..2 {
+        let (count, matches) = pipeline.run(&haystack, search::workgroups(LEN), |matches| {
+            (matches.count(), matches.to_sorted_vec())
+        });
+        assert_eq!(count, expected.len());
+        assert_eq!(matches, expected);
+    }
+}
+
+#[test]
+fn search_overflow() {
+    let haystack = [b'a'; 64];
+    let gpu = GpuCompute::new();
+    let mut pipeline =
+        gpu.gen_pipeline::<[u8; 64], SearchParams, Matches<4>, 2>(None, search::STAGES);
+    pipeline.write_uniform(&SearchParams::new(64, &[b"aa", b""]));
+    let (overflowed, count, kept) = pipeline.run(&haystack, search::workgroups(64), |matches| {
+        (
+            matches.overflowed(),
+            matches.count(),
+            matches.as_slice().len(),
+        )
+    });
+    assert!(overflowed);
+    assert_eq!(count, 63);
+    assert_eq!(kept, 4);
+}
+
+#[test]
+#[should_panic(expected = "too long")]
+fn search_rejects_long_pattern() {
+    SearchParams::new(0, &[&[0; search::MAX_PATTERN_LEN + 1]]);
+}
@@ -0,0 +1,66 @@
+use sgpu_compute::prelude::*;
+use sgpu_compute::seed::{self, Seed, SeedManager};
+
+const N: usize = 512;
+
+fn pipeline(gpu: &GpuCompute) -> sgpu_compute::blocking::Pipeline<'_, (), Seed, [u32; N], 1> {
+    let shader = format!(
+        "{}{}",
+        seed::WGSL,
+        "
+        @group(0) @binding(0) var<uniform> seed: Seed;
+        @group(0) @binding(1) var<storage, read_write> out: array<u32>;
+
+        @compute @workgroup_size(64)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = seed_random_u32(seed, id.x, (seed.offset + id.x) % 3u);
+        }
+        "
+    );
+    gpu.gen_pipeline(
+        None,
+        [StageDesc::new(shader.leak(), "main").with_name("random")],
+    )
+}
+
+#[test]
+fn gpu_matches_cpu() {
+    let gpu = GpuCompute::new();
+    let mut pipeline = pipeline(&gpu);
+    let seed = SeedManager::new(7).seed(3);
+    pipeline.write_uniform(&seed);
+    let gpu_values = pipeline.run(&(), [(N as u32 / 64, 1, 1)], |out| *out);
+    let cpu_values: [u32; N] = std::array::from_fn(|i| seed.random_u32(i as u32, i as u32 % 3));
+    assert_eq!(gpu_values, cpu_values);
+}
+
+#[test]
+fn chunks_match_single_dispatch() {
+    let gpu = GpuCompute::new();
+    let mut pipeline = pipeline(&gpu);
+    let seeds = SeedManager::new(7);
+    pipeline.write_uniform(&seeds.seed(5));
+    let whole = pipeline.run(&(), [(N as u32 / 64, 1, 1)], |out| *out);
+
+    // Two chunks of N / 2 elements, only the first half of the output is used by each one.
+    let mut chunked = Vec::new();
+    for offset in [0, N as u32 / 2] {
+        pipeline.write_uniform(&seeds.seed_chunk(5, offset));
+        pipeline.run(&(), [(N as u32 / 128, 1, 1)], |out| {
+            chunked.extend_from_slice(&out[..N / 2])
+        });
+    }
+    assert_eq!(whole.to_vec(), chunked);
+}
+
+#[test]
+fn steps_and_streams_differ() {
+    let seeds = SeedManager::new(1);
+    assert_ne!(seeds.seed(0), seeds.seed(1));
+    assert_ne!(seeds.derive(0).seed(0), seeds.derive(1).seed(0));
+    assert_eq!(seeds.seed(4), SeedManager::new(1).seed(4));
+    let values = (0..1000).map(|i| seeds.seed(0).random_f32(i, 0));
+    assert!(values.clone().all(|v| (0.0..1.0).contains(&v)));
+    let mean = values.sum::<f32>() / 1000.0;
+    assert!((mean - 0.5).abs() < 0.05);
+}
@@ -0,0 +1,60 @@
+use sgpu_compute::prelude::*;
+use sgpu_compute::serialize;
+
+#[test]
+fn serialize_layout() {
+    let mut file = Vec::new();
+    serialize::write(&mut file, &[2], &[1u16, 0x0203]).unwrap();
+    #[rustfmt::skip]
+    let expected = [
+        b'S', b'G', b'P', b'U', 1, 2, 1,
+        2, 0, 0, 0, 0, 0, 0, 0,
+        1, 0, 3, 2,
+    ];
+    assert_eq!(file, expected);
+}
+
+#[test]
+fn serialize_errors() {
+    let mut file = Vec::new();
+    assert!(serialize::write(&mut file, &[3], &[1u32, 2]).is_err());
+    serialize::write(&mut file, &[2], &[1u32, 2]).unwrap();
+    assert!(serialize::read::<f32>(file.as_slice()).is_err());
+    assert!(serialize::read::<u32>(&file[..file.len() - 1]).is_err());
+    assert!(serialize::read::<u32>(&b"nope"[..]).is_err());
+
+    // A corrupted header announcing a huge shape fails instead of allocating it.
+    let mut corrupted = file[..7].to_vec();
+    corrupted.extend_from_slice(&(1u64 << 60).to_le_bytes());
+    corrupted.extend_from_slice(&[0; 8]);
+    let error = serialize::read::<u32>(corrupted.as_slice()).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn serialize_gpu_output() {
+    let shader = "
+        @group(0) @binding(0) var<storage, read> in: array<f32>;
+        @group(0) @binding(1) var<storage, read_write> out: array<f32>;
+
+        @compute
+        @workgroup_size(16, 1, 1)
+        fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+            out[id.x] = sqrt(in[id.x]);
+        }
+    ";
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[f32; 64], (), [f32; 64], 1>(
+        None,
+        [StageDesc::new(shader, "main").with_name("sqrt")],
+    );
+    let input = std::array::from_fn(|i| i as f32);
+    let output = pipeline.run(&input, [(4, 1, 1)], |output| *output);
+
+    let path = std::env::temp_dir().join("sgpu_compute_serialize_test.bin");
+    serialize::save(&path, &[8, 8], &output).unwrap();
+    let (shape, read) = serialize::load::<f32>(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(shape, [8, 8]);
+    assert_eq!(read, output);
+}
@@ -0,0 +1,41 @@
+use sgpu_compute::shard::{shard_by, shard_of};
+
+#[test]
+fn shards_are_consistent() {
+    let shards = shard_by(0..1000u64, 4, |item| item.wrapping_mul(0x9e3779b97f4a7c15));
+    assert_eq!(shards.count(), 4);
+    assert_eq!(shards.len(), 1000);
+    assert!(shards.iter().all(|shard| shard.len() > 150));
+    assert!(shards
+        .iter()
+        .all(|shard| shard.windows(2).all(|w| w[0] < w[1])));
+
+    // Growing from 4 to 5 shards only moves keys to the new shard.
+    let moved = (0..10000u64)
+        .map(|key| key.wrapping_mul(0x9e3779b97f4a7c15))
+        .filter(|&hash| shard_of(hash, 4) != shard_of(hash, 5))
+        .inspect(|&hash| assert_eq!(shard_of(hash, 5), 4))
+        .count();
+    assert!((1500..2500).contains(&moved), "{} keys moved", moved);
+}
+
+#[test]
+fn merge_restores_input_order() {
+    let items = ["a", "bb", "ccc", "dddd", "e", "ff"];
+    let shards = shard_by(items, 3, |item| item.len() as u64);
+    for i in 0..shards.count() {
+        assert!(shards
+            .shard(i)
+            .iter()
+            .all(|item| shard_of(item.len() as u64, 3) == i));
+    }
+    let lengths = shards.map(|_, shard| shard.iter().map(|item| item.len()).collect());
+    assert_eq!(lengths, [1, 2, 3, 4, 1, 2]);
+}
+
+#[test]
+#[should_panic(expected = "Expected one value per item in the output of shard")]
+fn merge_checks_lengths() {
+    let shards = shard_by(0..10u64, 2, |item| *item);
+    shards.map(|_, _| vec![0]);
+}
@@ -0,0 +1,55 @@
+#![cfg(all(feature = "shm", unix))]
+use sgpu_compute::shm::ShmRing;
+
+#[test]
+fn ring_keeps_last_records() {
+    let path = std::env::temp_dir().join(format!("sgpu-shm-test-{}", std::process::id()));
+    let mut ring = ShmRing::create(&path, 16, 4).unwrap();
+    let consumer = ShmRing::open(&path).unwrap();
+    assert_eq!((consumer.slot_size(), consumer.slot_count()), (16, 4));
+    assert_eq!(consumer.read(0), None);
+
+    for i in 0..6u32 {
+        assert_eq!(ring.publish_pod(&[i; 4]), i as u64);
+    }
+    assert_eq!(consumer.published(), 6);
+    // The first two records were overwritten.
+    assert_eq!(consumer.read(1), None);
+    assert_eq!(consumer.read_pod::<[u32; 4]>(2), Some([2; 4]));
+    assert_eq!(consumer.read_pod::<[u32; 4]>(5), Some([5; 4]));
+    assert_eq!(consumer.read_pod::<[u32; 2]>(5), None);
+    assert_eq!(consumer.read(6), None);
+
+    ring.publish(b"short");
+    assert_eq!(consumer.read(6).as_deref(), Some(&b"short"[..]));
+    std::fs::remove_file(&path).unwrap();
+    assert!(ShmRing::open(&path).is_err());
+}
+
+#[test]
+fn open_rejects_other_files() {
+    let path = std::env::temp_dir().join(format!("sgpu-shm-invalid-{}", std::process::id()));
+    std::fs::write(&path, [0u8; 128]).unwrap();
+    let error = ShmRing::open(&path).err().unwrap();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn open_rejects_overflowing_headers() {
+    let path = std::env::temp_dir().join(format!("sgpu-shm-overflow-{}", std::process::id()));
+    drop(ShmRing::create(&path, 8, 4).unwrap());
+    // A slot count whose slots would wrap around to the size of the file: 24 * 2^61 is a multiple of 2^64.
+    let mut bytes = std::fs::read(&path).unwrap();
+    let wrapping = 4 + (1u64 << 61);
+    bytes[16..24].copy_from_slice(&wrapping.to_le_bytes());
+    std::fs::write(&path, &bytes).unwrap();
+    let error = ShmRing::open(&path).err().unwrap();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    // A slot size which overflows the stride.
+    bytes[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+    std::fs::write(&path, &bytes).unwrap();
+    let error = ShmRing::open(&path).err().unwrap();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,43 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sgpu_compute::kernels::sort::{self, SortParams};
+use sgpu_compute::prelude::*;
+
+const N: usize = 20_000;
+
+#[test]
+fn sort_u32_compare() {
+    let mut rng = StdRng::seed_from_u64(31);
+    let keys: [u32; N] = std::array::from_fn(|_| rng.gen());
+    let params = SortParams::new(N as u32, 1);
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[u32; N], SortParams, [u32; N], 14>(
+        params.scratchpad_size(),
+        sort::STAGES_U32,
+    );
+    pipeline.write_uniform(&params);
+    let sorted = pipeline.run(&keys, params.workgroups(), |sorted| sorted.to_vec());
+    let mut expected = keys.to_vec();
+    expected.sort_unstable();
+    assert_eq!(sorted, expected);
+}
+
+#[test]
+fn sort_u64_compare() {
+    let mut rng = StdRng::seed_from_u64(32);
+    let keys: [u64; N] = std::array::from_fn(|_| rng.gen::<u64>() >> rng.gen_range(0..64));
+    // Only the first keys are sorted.
+    let count = N - 123;
+    let params = SortParams::new(count as u32, 2);
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[u64; N], SortParams, [u64; N], 26>(
+        params.scratchpad_size(),
+        sort::STAGES_U64,
+    );
+    pipeline.write_uniform(&params);
+    let sorted = pipeline.run(&keys, params.workgroups(), |sorted| {
+        sorted[..count].to_vec()
+    });
+    let mut expected = keys[..count].to_vec();
+    expected.sort_unstable();
+    assert_eq!(sorted, expected);
+}
@@ -0,0 +1,65 @@
+use sgpu_compute::kernels::sparse::{self, Entry, Sparse, SparseParams};
+use sgpu_compute::prelude::*;
+use sgpu_compute::testgen::TestRng;
+
+const N: usize = 20_000;
+const MAX: usize = 2_500;
+
+#[test]
+fn sparse_round_trip() {
+    let mut rng = TestRng::new(45);
+    let dense: Box<[f32; N]> = Box::new(std::array::from_fn(|_| {
+        if rng.bool(0.1) {
+            rng.range_f32(1.0..2.0)
+        } else {
+            -1.0
+        }
+    }));
+    let params = SparseParams::new(N as u32, -1.0);
+    let gpu = GpuCompute::new();
+
+    let mut to_sparse =
+        gpu.gen_pipeline::<[f32; N], SparseParams, Sparse<MAX>, 2>(None, sparse::TO_SPARSE);
+    to_sparse.write_uniform(&params);
+    let entries =
+        Box::new(to_sparse.run(&dense, sparse::to_sparse_workgroups(N as u32), |out| *out));
+    assert!(!entries.overflowed());
+    let expected: Vec<Entry> = dense
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| **v != -1.0)
+        .map(|(i, v)| Entry::new(i as u32, *v))
+        .collect();
+    assert_eq!(entries.to_sorted_vec(), expected);
+
+    let mut to_dense =
+        gpu.gen_pipeline::<Sparse<MAX>, SparseParams, [f32; N], 2>(None, sparse::TO_DENSE);
+    to_dense.write_uniform(&params);
+    let round_trip = to_dense.run(
+        &entries,
+        sparse::to_dense_workgroups(N as u32, MAX as u32),
+        |out| out.to_vec(),
+    );
+    assert_eq!(round_trip, dense.to_vec());
+}
+
+#[test]
+fn sparse_overflow_and_out_of_bounds() {
+    let gpu = GpuCompute::new();
+    let mut to_sparse =
+        gpu.gen_pipeline::<[f32; 8], SparseParams, Sparse<2>, 2>(None, sparse::TO_SPARSE);
+    to_sparse.write_uniform(&SparseParams::new(8, 0.0));
+    let entries = to_sparse.run(&[1.0; 8], sparse::to_sparse_workgroups(8), |out| *out);
+    assert_eq!(entries.count(), 8);
+    assert!(entries.overflowed());
+    assert_eq!(entries.as_slice().len(), 2);
+
+    let mut to_dense =
+        gpu.gen_pipeline::<Sparse<3>, SparseParams, [f32; 4], 2>(None, sparse::TO_DENSE);
+    to_dense.write_uniform(&SparseParams::new(4, 0.5));
+    let entries = Sparse::<3>::from_entries(&[Entry::new(3, 7.0), Entry::new(10, 1.0)]);
+    assert_eq!(
+        to_dense.run(&entries, sparse::to_dense_workgroups(4, 3), |out| *out),
+        [0.5, 0.5, 0.5, 7.0]
+    );
+}
@@ -0,0 +1,81 @@
+use sgpu_compute::describe::BufferRole;
+use sgpu_compute::prelude::*;
+use sgpu_compute::spec::{shader_hash, PipelineSpec, SpecError, StageSpec, VERSION};
+
+fn spec() -> PipelineSpec {
+    let source = "@compute @workgroup_size(1) fn main() {}".to_string();
+    PipelineSpec {
+        uniform_size: 4,
+        scratchpad_size: None,
+        input_size: 16,
+        output_size: 16,
+        stages: vec![StageSpec {
+            key: "stage0".into(),
+            name: Some("noop".into()),
+            entrypoint: "main".into(),
+            hash: shader_hash(&source),
+            source,
+        }],
+    }
+}
+
+#[test]
+fn spec_roundtrip() {
+    let spec = spec();
+    let mut file = Vec::new();
+    spec.write(&mut file).unwrap();
+    assert_eq!(&file[..5], &[b'S', b'G', b'P', b'S', VERSION]);
+    assert_eq!(PipelineSpec::read(file.as_slice()).unwrap(), spec);
+    assert!(matches!(
+        PipelineSpec::read(&file[..file.len() - 1]),
+        Err(SpecError::Corrupted(_))
+    ));
+    assert!(matches!(
+        PipelineSpec::read(&b"SGPU\x01"[..]),
+        Err(SpecError::NotASpec)
+    ));
+}
+
+#[test]
+fn spec_rejects_other_versions_and_edited_shaders() {
+    let mut file = Vec::new();
+    spec().write(&mut file).unwrap();
+    let mut newer = file.clone();
+    newer[4] = VERSION + 1;
+    let error = PipelineSpec::read(newer.as_slice()).unwrap_err();
+    assert!(error.to_string().contains("update sgpu-compute"));
+
+    let mut edited = spec();
+    edited.stages[0].source.push(' ');
+    let mut file = Vec::new();
+    edited.write(&mut file).unwrap();
+    match PipelineSpec::read(file.as_slice()) {
+        Err(SpecError::ShaderHashMismatch { stage, .. }) => assert_eq!(stage, "noop"),
+        res => panic!("unexpected {:?}", res),
+    }
+}
+
+#[test]
+fn spec_checks_device_limits_and_types() {
+    let gpu = GpuCompute::new();
+    let mut spec = spec();
+    spec.check(&gpu).unwrap();
+    spec.check_types::<[u32; 4], u32, [u32; 4]>().unwrap();
+    assert!(matches!(
+        spec.check_types::<[u32; 4], (), [u32; 4]>(),
+        Err(SpecError::TypeMismatch {
+            role: BufferRole::Uniform,
+            expected: 4,
+            found: 0
+        })
+    ));
+    spec.output_size = u64::MAX;
+    let error = spec.check(&gpu).unwrap_err();
+    assert!(matches!(
+        error,
+        SpecError::BufferTooLarge {
+            role: BufferRole::Output,
+            ..
+        }
+    ));
+}
@@ -0,0 +1,70 @@
+use sgpu_compute::prelude::*;
+
+const SHADER: &str = "
+    @group(0) @binding(0) var<uniform> offset: u32;
+    @group(0) @binding(1) var<storage, read> in: array<u32>;
+    @group(0) @binding(2) var<storage, read_write> out: array<u32>;
+
+    @compute @workgroup_size(16)
+    fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+        out[id.x] = in[id.x] + offset;
+    }
+";
+
+/// SPIR-V binary of `SHADER`, like the output of an offline compiler.
+#[cfg(feature = "spirv")]
+fn spirv(source: &str) -> &'static [u32] {
+    use wgpu::naga;
+    let module = naga::front::wgsl::parse_str(source).unwrap();
+    let info = naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::empty(),
+    )
+    .validate(&module)
+    .unwrap();
+    naga::back::spv::write_vec(&module, &info, &Default::default(), None)
+        .unwrap()
+        .leak()
+}
+
+#[cfg(feature = "spirv")]
+#[test]
+fn spirv_stage_runs_and_is_reflected() {
+    let gpu = GpuCompute::new();
+    let stage = StageDesc::new("offset.spv", "main")
+        .with_name("offset")
+        .with_source(ShaderSource::SpirV(spirv(SHADER)));
+    let mut pipeline = gpu.gen_pipeline::<[u32; 16], u32, [u32; 16], 1>(None, [stage.clone()]);
+    pipeline.write_uniform(&10);
+    let input: [u32; 16] = std::array::from_fn(|i| i as u32);
+    assert_eq!(
+        pipeline.run(&input, [(1, 1, 1)], |out| *out),
+        input.map(|i| i + 10)
+    );
+    assert_eq!(
+        pipeline.describe().stages[0].workgroup_size,
+        Some([16, 1, 1])
+    );
+    // The bindings of the binary are checked like the ones of a WGSL shader.
+    let error = gpu
+        .try_gen_pipeline::<[u32; 16], (), [u32; 16], 1>(None, [stage])
+        .err()
+        .unwrap();
+    assert!(matches!(error, SgpuError::LayoutMismatch(_)), "{}", error);
+}
+
+#[cfg(not(feature = "spirv"))]
+#[test]
+fn spirv_requires_the_feature() {
+    let gpu = GpuCompute::new();
+    let error = gpu
+        .try_gen_pipeline::<[u32; 16], u32, [u32; 16], 1>(
+            None,
+            [StageDesc::new(SHADER, "main")
+                .with_name("offset")
+                .with_source(ShaderSource::SpirV(&[0x0723_0203]))],
+        )
+        .err()
+        .unwrap();
+    assert!(matches!(error, SgpuError::Unsupported(_)), "{}", error);
+}
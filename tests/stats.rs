@@ -0,0 +1,45 @@
+use sgpu_compute::prelude::*;
+
+#[test]
+fn online_stats_matches_reference() {
+    let batches: Vec<[f32; 700]> = (0..5)
+        .map(|seed| sgpu_compute::testgen::random_f32(seed, -10.0..10.0))
+        .collect();
+    let gpu = GpuCompute::new();
+    let mut stats = gpu.online_stats::<700>();
+    for batch in &batches {
+        stats.push(batch);
+    }
+    stats.push_prefix(&[100.0; 700], 1);
+    let values: Vec<f64> = batches
+        .iter()
+        .flatten()
+        .map(|x| *x as f64)
+        .chain([100.0])
+        .collect();
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let variance = values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+
+    let statistics = stats.finalize();
+    assert_eq!(statistics.count, values.len() as u32);
+    assert_eq!(statistics.max, 100.0);
+    assert!(statistics.min >= -10.0);
+    assert!((statistics.mean as f64 - mean).abs() < 1e-3);
+    assert!((statistics.variance() as f64 / variance - 1.0).abs() < 1e-3);
+
+    // Finalizing keeps the accumulator, resetting clears it.
+    stats.push_prefix(&[1.0; 700], 2);
+    assert_eq!(stats.finalize().count, values.len() as u32 + 2);
+    stats.reset();
+    let empty = stats.finalize();
+    assert_eq!(empty.count, 0);
+    assert_eq!(empty.min, f32::INFINITY);
+    stats.push_prefix(&[3.0; 700], 4);
+    let statistics = stats.finalize();
+    assert_eq!(
+        (statistics.count, statistics.sum, statistics.mean),
+        (4, 12.0, 3.0)
+    );
+    assert_eq!(statistics.variance(), 0.0);
+}
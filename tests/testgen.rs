@@ -0,0 +1,50 @@
+use sgpu_compute::testgen::{self, Pattern, TestRng};
+
+#[test]
+fn random_is_reproducible() {
+    let a: [u32; 256] = testgen::random_u32(1);
+    assert_eq!(a, testgen::random_u32::<256>(1));
+    assert_ne!(a, testgen::random_u32::<256>(2));
+
+    let values: [u32; 1000] = testgen::random_u32_in(3, 5..9);
+    assert!(values.iter().all(|v| (5..9).contains(v)));
+    for v in 5..9 {
+        assert!(values.contains(&v));
+    }
+
+    let mut rng = TestRng::new(4);
+    let mean = (0..10000).map(|_| rng.next_f32()).sum::<f32>() / 10000.0;
+    assert!((mean - 0.5).abs() < 0.02);
+}
+
+#[test]
+fn images() {
+    let gradient: [f32; 12] = testgen::image(4, 3, Pattern::Gradient);
+    assert_eq!(&gradient[4..8], &[0.0, 1.0 / 3.0, 2.0 / 3.0, 1.0]);
+
+    let disk: [f32; 100] = testgen::image(10, 10, Pattern::Disk { radius: 3.0 });
+    assert_eq!(disk[0], 0.0);
+    assert_eq!(disk[5 * 10 + 5], 1.0);
+    for y in 0..10 {
+        for x in 0..10 {
+            assert_eq!(disk[y * 10 + x], disk[(9 - y) * 10 + (9 - x)]);
+        }
+    }
+
+    let noise: [f32; 64] = testgen::image(8, 8, Pattern::Noise { seed: 5 });
+    assert!(noise.iter().all(|v| (0.0..1.0).contains(v)));
+}
+
+#[test]
+fn sparse_matrix_density() {
+    let matrix = testgen::sparse_matrix(6, 200, 300, 0.05);
+    let density = matrix.nnz() as f32 / (200.0 * 300.0);
+    assert!((density - 0.05).abs() < 0.005);
+    for row in matrix.offsets.windows(2) {
+        let columns = &matrix.columns[row[0] as usize..row[1] as usize];
+        assert!(columns.windows(2).all(|c| c[0] < c[1]));
+    }
+    let dense = matrix.to_dense();
+    assert_eq!(dense.iter().filter(|v| **v != 0.0).count(), matrix.nnz());
+    assert_eq!(matrix, testgen::sparse_matrix(6, 200, 300, 0.05));
+}
@@ -0,0 +1,39 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sgpu_compute::kernels::tonemap::{self, ToneMapParams};
+use sgpu_compute::prelude::*;
+
+fn bin(v: f32, exposure: f32) -> usize {
+    let exposed = v.max(0.0) * exposure;
+    ((exposed / (1.0 + exposed) * 256.0) as usize).min(255)
+}
+
+fn equalize_cpu(input: &[f32], exposure: f32) -> Vec<u8> {
+    let mut cdf = [0u32; 256];
+    input.iter().for_each(|&v| cdf[bin(v, exposure)] += 1);
+    for i in 1..256 {
+        cdf[i] += cdf[i - 1];
+    }
+    let cdf_min = *cdf.iter().find(|&&c| c != 0).unwrap();
+    let range = (input.len() as u32 - cdf_min).max(1) as f32;
+    input
+        .iter()
+        .map(|&v| ((cdf[bin(v, exposure)] - cdf_min) as f32 / range * 255.0).round() as u8)
+        .collect()
+}
+
+#[test]
+fn tonemap_compare() {
+    const N: usize = 4096;
+    let mut rng = StdRng::seed_from_u64(3);
+    let input: [f32; N] = std::array::from_fn(|_| rng.gen_range(0.0f32..8.0).powi(3));
+    let gpu = GpuCompute::new();
+    let mut pipeline = gpu.gen_pipeline::<[f32; N], ToneMapParams, [u8; N], 4>(
+        tonemap::SCRATCHPAD_SIZE,
+        tonemap::STAGES,
+    );
+    for exposure in [0.5, 2.0] {
+        pipeline.write_uniform(&ToneMapParams { exposure });
+        let output = pipeline.run(&input, tonemap::workgroups(N), |output| *output);
+        assert_eq!(output.to_vec(), equalize_cpu(&input, exposure));
+    }
+}
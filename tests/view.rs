@@ -0,0 +1,56 @@
+use sgpu_compute::view::Layout;
+
+#[test]
+fn layout_offsets() {
+    let layout = Layout::new()
+        .field::<u32>("count", 1)
+        .field_aligned::<[f32; 3]>("position", 2, 16)
+        .field::<u16>("flags", 3)
+        .field::<f32>("mass", 2);
+    let offsets: Vec<_> = layout
+        .fields()
+        .iter()
+        .map(|field| (field.name, field.offset, field.size()))
+        .collect();
+    assert_eq!(
+        offsets,
+        [
+            ("count", 0, 4),
+            ("position", 16, 32),
+            ("flags", 48, 6),
+            ("mass", 56, 8)
+        ]
+    );
+    assert_eq!(layout.size(), 64);
+}
+
+#[test]
+fn view_fields() {
+    let layout = Layout::new()
+        .field::<u32>("ids", 3)
+        .field::<f32>("values", 3);
+    let output: [u32; 6] = [1, 2, 3, 1.5f32.to_bits(), 2.5f32.to_bits(), 0];
+    assert_eq!(layout.view::<u32>(&output, "ids"), [1, 2, 3]);
+    assert_eq!(layout.view::<f32>(&output, "values"), [1.5, 2.5, 0.0]);
+}
+
+#[test]
+#[should_panic(expected = "Field `ids` is declared as u32, not f32")]
+fn view_wrong_type() {
+    let layout = Layout::new().field::<u32>("ids", 2);
+    layout.view::<f32>(&[0u32; 2], "ids");
+}
+
+#[test]
+#[should_panic(expected = "smaller than the layout")]
+fn view_output_too_small() {
+    let layout = Layout::new().field::<u32>("ids", 4);
+    layout.view::<u32>(&[0u32; 2], "ids");
+}
+
+#[test]
+#[should_panic(expected = "stride of 16 bytes")]
+fn view_padded_stride() {
+    let layout = Layout::new().field_aligned::<[f32; 3]>("position", 2, 16);
+    layout.view::<[f32; 3]>(&[0u32; 8], "position");
+}
@@ -0,0 +1,14 @@
+#![cfg(feature = "vulkan")]
+use sgpu_compute::prelude::*;
+
+#[test]
+fn vulkan_handles_match_backend() {
+    let gpu = GpuCompute::new();
+    let backend = gpu.adapter_info().map(|info| info.backend);
+    let handles = gpu.vulkan_handles();
+    assert_eq!(handles.is_some(), backend == Some(wgpu::Backend::Vulkan));
+    if let Some(handles) = handles {
+        let family = unsafe { gpu.with_vulkan_device(|device| device.queue_family_index()) };
+        assert_eq!(family, Some(handles.queue_family_index));
+    }
+}
@@ -0,0 +1,63 @@
+use sgpu_compute::kernels::zip_map::ZipMap;
+use sgpu_compute::prelude::*;
+use wgpu::util::DeviceExt;
+
+#[test]
+fn matches_the_cpu() {
+    let gpu = GpuCompute::new();
+    let a: Vec<f32> = (0..100_000).map(|i| (i as f32 * 0.01).sin()).collect();
+    let b: Vec<f32> = (0..100_000).map(|i| (i % 7) as f32).collect();
+    let out = gpu.zip_map(&a, &b, "max(a, b) - a * b");
+    assert_eq!(out.len(), a.len());
+    for ((a, b), out) in a.iter().zip(&b).zip(&out) {
+        assert!((out - (a.max(*b) - a * b)).abs() < 1e-5);
+    }
+    assert!(gpu.zip_map(&[], &[], "a + b").is_empty());
+}
+
+#[test]
+fn buffers_are_chained_without_readback() {
+    let gpu = GpuCompute::new();
+    let buffer = |values: &[f32]| {
+        gpu.device()
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::cast_slice(values),
+                usage: wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+            })
+    };
+    let a = buffer(&[1.0, 2.0, 3.0, 4.0]);
+    let b = buffer(&[10.0, 20.0, 30.0, 40.0]);
+    let out = buffer(&[0.0; 4]);
+    let mut add = ZipMap::new(&gpu, "a + b", 4);
+    let mut mul = ZipMap::new(&gpu, "a * b", 4);
+    add.apply_buffers(&a, &b, &out);
+    // In place, with the same buffer twice.
+    mul.apply_buffers(&out, &out, &out);
+    add.apply_buffers(&out, &a, &a);
+
+    let readback = gpu.device().create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size: 16,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    let mut encoder = gpu
+        .device()
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    encoder.copy_buffer_to_buffer(&a, 0, &readback, 0, 16);
+    gpu.queue().submit(Some(encoder.finish()));
+    readback
+        .slice(..)
+        .map_async(wgpu::MapMode::Read, |e| e.unwrap());
+    gpu.device().poll(wgpu::Maintain::Wait);
+    let values: Vec<f32> = bytemuck::cast_slice(&readback.slice(..).get_mapped_range()).to_vec();
+    assert_eq!(values, [122.0, 486.0, 1092.0, 1940.0]);
+}
+
+#[test]
+#[should_panic(expected = "Expected 4 elements, got 3")]
+fn wrong_length_panics() {
+    let gpu = GpuCompute::new();
+    ZipMap::new(&gpu, "a - b", 4).apply(&[1.0; 3], &[1.0; 3]);
+}